@@ -1,10 +1,12 @@
 //! Code-aware chunking strategy.
 //!
 //! Chunks source code at natural boundaries (functions, classes, methods)
-//! using regex-based pattern matching for multiple languages.
+//! using regex-based pattern matching for multiple languages. With the
+//! `treesitter` feature, Rust, Python, JavaScript, TypeScript, and Go are
+//! parsed instead, so chunks follow top-level definitions exactly.
 
 use crate::chunking::traits::{ChunkMetadata, Chunker};
-use crate::chunking::{DEFAULT_CHUNK_SIZE, DEFAULT_OVERLAP};
+use crate::chunking::{DEFAULT_CHUNK_SIZE, DEFAULT_OVERLAP, assign_titles};
 use crate::core::Chunk;
 use crate::error::Result;
 use regex::Regex;
@@ -28,6 +30,17 @@ use std::sync::OnceLock;
 /// - Ruby (.rb)
 /// - PHP (.php)
 ///
+/// # Tree-sitter
+///
+/// With the `treesitter` feature, Rust, Python, JavaScript/TypeScript, and
+/// Go sources are split at top-level definitions found by their tree-sitter
+/// grammar, with the imports and comments before a definition kept in its
+/// chunk. Each chunk records the language ([`Chunk::language`]) and the
+/// enclosing function, class, or type ([`Chunk::symbol`]); a definition
+/// larger than the chunk size is split at line breaks into several chunks
+/// that all carry its symbol. Sources without a grammar or without any
+/// top-level definition use the pattern-based boundaries.
+///
 /// # Examples
 ///
 /// ```
@@ -90,17 +103,20 @@ impl CodeChunker {
         }
     }
 
-    /// Detects language from file extension or content type.
-    fn detect_language(metadata: Option<&ChunkMetadata>) -> Language {
-        let ext = metadata
+    /// Returns the file extension from the content type or source path.
+    fn extension(metadata: Option<&ChunkMetadata>) -> &str {
+        metadata
             .and_then(|m| {
                 m.content_type
                     .as_deref()
                     .or_else(|| m.source.as_deref().and_then(|s| s.rsplit('.').next()))
             })
-            .unwrap_or("");
+            .unwrap_or("")
+    }
 
-        Language::from_extension(ext)
+    /// Detects language from file extension or content type.
+    fn detect_language(metadata: Option<&ChunkMetadata>) -> Language {
+        Language::from_extension(Self::extension(metadata))
     }
 
     /// Finds code structure boundaries in the text.
@@ -270,6 +286,18 @@ impl Chunker for CodeChunker {
         });
         let overlap = metadata.map_or(self.overlap, |m| m.overlap);
 
+        #[cfg(feature = "treesitter")]
+        if let Some(mut chunks) = super::treesitter::chunk_symbols(
+            buffer_id,
+            text,
+            Self::extension(metadata),
+            chunk_size,
+            overlap,
+        ) {
+            assign_titles(&mut chunks);
+            return Ok(chunks);
+        }
+
         // Detect language
         let lang = Self::detect_language(metadata);
 
@@ -277,7 +305,10 @@ impl Chunker for CodeChunker {
         let boundaries = self.find_boundaries(text, lang);
 
         // Chunk at boundaries
-        Ok(self.chunk_at_boundaries(buffer_id, text, &boundaries, chunk_size, overlap))
+        let mut chunks =
+            self.chunk_at_boundaries(buffer_id, text, &boundaries, chunk_size, overlap);
+        assign_titles(&mut chunks);
+        Ok(chunks)
     }
 
     fn name(&self) -> &'static str {
@@ -693,4 +724,20 @@ export async function fetchData() {
         let chunker = CodeChunker::new();
         assert!(!chunker.description().is_empty());
     }
+
+    #[test]
+    fn test_code_chunker_titles_from_symbols() {
+        let chunker = CodeChunker::with_size_and_overlap(60, 0);
+        let code = "fn alpha() {\n    let x = 1;\n    let y = 2;\n}\n\nfn beta() {\n    let z = 3;\n    let w = 4;\n}\n";
+        let meta = ChunkMetadata::with_size_and_overlap(60, 0).content_type("rs");
+        let chunks = chunker.chunk(1, code, Some(&meta)).unwrap();
+
+        assert!(chunks.len() >= 2);
+        assert_eq!(chunks[0].title(), Some("fn alpha()".to_string()));
+        assert!(
+            chunks
+                .iter()
+                .any(|c| c.title() == Some("fn beta()".to_string()))
+        );
+    }
 }
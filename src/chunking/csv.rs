@@ -0,0 +1,329 @@
+//! CSV/TSV row-group chunking strategy.
+//!
+//! Splits delimited tables into groups of whole rows and repeats the header
+//! row at the top of every chunk, so each chunk reads as a complete table.
+
+use crate::chunking::traits::{ChunkMetadata, Chunker};
+use crate::chunking::{DEFAULT_CHUNK_SIZE, MAX_CHUNK_SIZE};
+use crate::core::Chunk;
+use crate::error::{ChunkingError, Result};
+use std::ops::Range;
+
+/// Default maximum number of data rows per chunk.
+pub const DEFAULT_MAX_ROWS: usize = 100;
+
+/// Chunker for CSV and TSV tables.
+///
+/// Each chunk holds up to `max_rows` whole data rows, as many as fit in the
+/// chunk size together with the header row. A row larger than the chunk
+/// size becomes a chunk of its own; rows are never split. Quoted fields may
+/// contain delimiters and line breaks.
+///
+/// The delimiter is a tab for `tsv` content and a comma for `csv` content;
+/// otherwise whichever of the two occurs more often in the header row.
+///
+/// Every chunk starts with the header row. The first chunk's byte range
+/// covers the header; later chunks' ranges cover only their rows, so their
+/// content is the header followed by the text of that range. The 1-based
+/// data row range is stored in custom metadata, available via
+/// [`Chunk::row_range`]. A table with only a header becomes a single chunk.
+///
+/// Overlap is not applied: chunks always hold whole rows.
+///
+/// # Examples
+///
+/// ```
+/// use rlm_rs::chunking::{Chunker, CsvChunker};
+///
+/// let chunker = CsvChunker::new().with_max_rows(2);
+/// let text = "id,name\n1,a\n2,b\n3,c\n";
+/// let chunks = chunker.chunk(1, text, None).unwrap();
+/// assert_eq!(chunks[1].content, "id,name\n3,c\n");
+/// assert_eq!(chunks[1].row_range(), Some((3, 3)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct CsvChunker {
+    /// Target chunk size in characters.
+    chunk_size: usize,
+    /// Maximum data rows per chunk.
+    max_rows: usize,
+}
+
+impl Default for CsvChunker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CsvChunker {
+    /// Creates a new CSV chunker with default settings.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            max_rows: DEFAULT_MAX_ROWS,
+        }
+    }
+
+    /// Creates a CSV chunker with a custom chunk size.
+    #[must_use]
+    pub const fn with_size(chunk_size: usize) -> Self {
+        Self {
+            chunk_size,
+            max_rows: DEFAULT_MAX_ROWS,
+        }
+    }
+
+    /// Sets the maximum number of data rows per chunk (0 is treated as 1).
+    #[must_use]
+    pub const fn with_max_rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = max_rows;
+        self
+    }
+}
+
+impl Chunker for CsvChunker {
+    fn chunk(
+        &self,
+        buffer_id: i64,
+        text: &str,
+        metadata: Option<&ChunkMetadata>,
+    ) -> Result<Vec<Chunk>> {
+        let chunk_size = metadata.map_or(self.chunk_size, |meta| meta.chunk_size);
+
+        if chunk_size == 0 {
+            return Err(ChunkingError::InvalidConfig {
+                reason: "chunk_size must be > 0".to_string(),
+            }
+            .into());
+        }
+        if chunk_size > MAX_CHUNK_SIZE {
+            return Err(ChunkingError::ChunkTooLarge {
+                size: chunk_size,
+                max: MAX_CHUNK_SIZE,
+            }
+            .into());
+        }
+
+        let content_type = metadata.and_then(|meta| meta.content_type.as_deref());
+        let delimiter = detect_delimiter(text, content_type);
+        let mut records = records(text, delimiter)
+            .into_iter()
+            .filter(|range| !text[range.clone()].trim().is_empty());
+        let Some(header) = records.next() else {
+            return Ok(vec![]);
+        };
+        let rows: Vec<Range<usize>> = records.collect();
+
+        if rows.is_empty() {
+            let mut chunk = Chunk::with_strategy(
+                buffer_id,
+                text[header.clone()].to_string(),
+                header,
+                0,
+                self.name(),
+            );
+            chunk.set_token_count(chunk.estimate_tokens());
+            return Ok(vec![chunk]);
+        }
+
+        // Group whole rows, leaving room for the repeated header
+        let header_text = &text[header.clone()];
+        let budget = chunk_size.saturating_sub(header_text.len());
+        let max_rows = self.max_rows.max(1);
+        let mut groups: Vec<Range<usize>> = Vec::new();
+        let mut start = 0;
+        for (i, row) in rows.iter().enumerate().skip(1) {
+            if i - start >= max_rows || row.end - rows[start].start > budget {
+                groups.push(start..i);
+                start = i;
+            }
+        }
+        groups.push(start..rows.len());
+
+        let max_chunks = metadata.map_or(0, |meta| meta.max_chunks);
+        let mut chunks: Vec<Chunk> = groups
+            .into_iter()
+            .enumerate()
+            .map(|(index, group)| {
+                let span = rows[group.start].start..rows[group.end - 1].end;
+                let (content, byte_range) = if index == 0 {
+                    (
+                        text[header.start..span.end].to_string(),
+                        header.start..span.end,
+                    )
+                } else {
+                    (format!("{header_text}{}", &text[span.clone()]), span)
+                };
+                let mut chunk =
+                    Chunk::with_strategy(buffer_id, content, byte_range, index, self.name());
+                chunk.set_token_count(chunk.estimate_tokens());
+                chunk.set_row_range(group.start + 1, group.end);
+                chunk.set_title(&format!("Rows {}-{}", group.start + 1, group.end));
+                chunk
+            })
+            .collect();
+        if max_chunks > 0 {
+            chunks.truncate(max_chunks);
+        }
+
+        Ok(chunks)
+    }
+
+    fn name(&self) -> &'static str {
+        "csv"
+    }
+
+    fn description(&self) -> &'static str {
+        "CSV/TSV chunking into groups of whole rows, repeating the header row"
+    }
+}
+
+/// Picks the delimiter for `content_type`, or from the first line.
+fn detect_delimiter(text: &str, content_type: Option<&str>) -> u8 {
+    match content_type {
+        Some(t) if t.eq_ignore_ascii_case("tsv") || t.eq_ignore_ascii_case("tab") => b'\t',
+        Some(t) if t.eq_ignore_ascii_case("csv") => b',',
+        _ => {
+            let first_line = text.lines().next().unwrap_or_default();
+            let mut in_quotes = false;
+            let (mut tabs, mut commas) = (0usize, 0usize);
+            for c in first_line.chars() {
+                match c {
+                    '"' => in_quotes = !in_quotes,
+                    '\t' if !in_quotes => tabs += 1,
+                    ',' if !in_quotes => commas += 1,
+                    _ => {}
+                }
+            }
+            if tabs > commas { b'\t' } else { b',' }
+        }
+    }
+}
+
+/// Splits text into records, each including its line ending.
+///
+/// A quote opens a quoted field only at the start of a field; inside one,
+/// `""` is an escaped quote, and line breaks belong to the field.
+fn records(text: &str, delimiter: u8) -> Vec<Range<usize>> {
+    let bytes = text.as_bytes();
+    let mut records = Vec::new();
+    let mut start = 0;
+    let mut in_quotes = false;
+    let mut field_start = true;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let b = bytes[i];
+        if in_quotes {
+            if b == b'"' {
+                if bytes.get(i + 1) == Some(&b'"') {
+                    i += 1;
+                } else {
+                    in_quotes = false;
+                }
+            }
+            field_start = false;
+        } else if b == b'"' && field_start {
+            in_quotes = true;
+            field_start = false;
+        } else if b == b'\n' {
+            records.push(start..i + 1);
+            start = i + 1;
+            field_start = true;
+        } else {
+            field_start = b == delimiter;
+        }
+        i += 1;
+    }
+    if start < bytes.len() {
+        records.push(start..bytes.len());
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_chunker_repeats_header() {
+        let chunker = CsvChunker::new().with_max_rows(2);
+        let text = "id,name\n1,a\n2,b\n3,c\n";
+        let chunks = chunker.chunk(1, text, None).unwrap();
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].content, "id,name\n1,a\n2,b\n");
+        assert_eq!(chunks[0].byte_range, 0..16);
+        assert_eq!(chunks[0].row_range(), Some((1, 2)));
+        assert_eq!(chunks[0].title(), Some("Rows 1-2".to_string()));
+        assert_eq!(chunks[1].content, "id,name\n3,c\n");
+        assert_eq!(&text[chunks[1].byte_range.clone()], "3,c\n");
+        assert_eq!(chunks[1].row_range(), Some((3, 3)));
+        assert_eq!(chunks[1].metadata.strategy.as_deref(), Some("csv"));
+    }
+
+    #[test]
+    fn test_csv_chunker_size_budget() {
+        // Header (8 bytes) plus two 10-byte rows fit in 28 bytes, not three
+        let text = "id,name\n1,aaaaaaa\n2,bbbbbbb\n3,ccccccc\n";
+        let chunks = CsvChunker::with_size(28).chunk(1, text, None).unwrap();
+        let rows: Vec<_> = chunks.iter().map(Chunk::row_range).collect();
+        assert_eq!(rows, vec![Some((1, 2)), Some((3, 3))]);
+
+        // A row larger than the budget still becomes a whole chunk
+        let chunks = CsvChunker::with_size(10).chunk(1, text, None).unwrap();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[2].content, "id,name\n3,ccccccc\n");
+    }
+
+    #[test]
+    fn test_csv_chunker_quoted_newlines() {
+        let text = "id,note\n1,\"first\nline, still 1\"\n2,\"say \"\"hi\"\"\"\n3,plain\n";
+        let chunks = CsvChunker::new()
+            .with_max_rows(1)
+            .chunk(1, text, None)
+            .unwrap();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].content, "id,note\n1,\"first\nline, still 1\"\n");
+        assert_eq!(chunks[1].content, "id,note\n2,\"say \"\"hi\"\"\"\n");
+        assert_eq!(chunks[2].row_range(), Some((3, 3)));
+    }
+
+    #[test]
+    fn test_csv_chunker_header_only_and_empty() {
+        let chunks = CsvChunker::new().chunk(1, "id,name\n", None).unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].content, "id,name\n");
+        assert_eq!(chunks[0].row_range(), None);
+
+        assert!(CsvChunker::new().chunk(1, "", None).unwrap().is_empty());
+        assert!(CsvChunker::new().chunk(1, "\n\n", None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_detect_delimiter() {
+        assert_eq!(detect_delimiter("a\tb\tc\n", None), b'\t');
+        assert_eq!(detect_delimiter("a,b\tc,d\n", None), b',');
+        assert_eq!(detect_delimiter("\"a\tb\",c\n", None), b',');
+        assert_eq!(detect_delimiter("a,b\n", Some("TSV")), b'\t');
+
+        let text = "id\tnote\n1\t\"x\ty\"\n2\tz\n";
+        let chunks = CsvChunker::new()
+            .with_max_rows(1)
+            .chunk(1, text, None)
+            .unwrap();
+        assert_eq!(chunks[0].content, "id\tnote\n1\t\"x\ty\"\n");
+        assert_eq!(chunks[1].content, "id\tnote\n2\tz\n");
+    }
+
+    #[test]
+    fn test_csv_chunker_invalid_size() {
+        assert!(CsvChunker::with_size(0).chunk(1, "a\n", None).is_err());
+        assert!(
+            CsvChunker::with_size(MAX_CHUNK_SIZE + 1)
+                .chunk(1, "a\n", None)
+                .is_err()
+        );
+    }
+}
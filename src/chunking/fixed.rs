@@ -3,10 +3,14 @@
 //! Provides simple character-based chunking with configurable size and overlap.
 //! Respects UTF-8 character boundaries to avoid splitting multi-byte characters.
 
-use crate::chunking::traits::{ChunkMetadata, Chunker};
-use crate::chunking::{DEFAULT_CHUNK_SIZE, DEFAULT_OVERLAP, MAX_CHUNK_SIZE};
+use crate::chunking::tokenizer::{TokenBudget, chunk_by_tokens};
+use crate::chunking::traits::{ChunkMetadata, Chunker, OverlapPlacement};
+use crate::chunking::{
+    DEFAULT_CHUNK_SIZE, DEFAULT_OVERLAP, MAX_CHUNK_SIZE, assign_titles, place_overlap,
+};
 use crate::core::Chunk;
 use crate::error::{ChunkingError, Result};
+use crate::io::find_char_boundary;
 
 /// Fixed-size chunker that splits text at character boundaries.
 ///
@@ -93,7 +97,9 @@ impl FixedChunker {
     }
 
     /// Finds a valid chunk boundary respecting UTF-8 and optionally lines.
-    fn find_boundary(&self, text: &str, target_pos: usize) -> usize {
+    ///
+    /// Newlines are searched for up to `window` bytes before the target.
+    fn find_boundary(&self, text: &str, target_pos: usize, window: usize) -> usize {
         let mut pos = target_pos.min(text.len());
 
         // First, find valid UTF-8 boundary
@@ -103,7 +109,7 @@ impl FixedChunker {
 
         // If line-aware, try to find a newline before this position
         if self.line_aware && pos > 0 {
-            let search_start = pos.saturating_sub(self.chunk_size / 10); // Look back up to 10%
+            let search_start = find_char_boundary(text, pos.saturating_sub(window));
             if let Some(newline_offset) = text[search_start..pos].rfind('\n') {
                 let newline_pos = search_start + newline_offset + 1; // Position after newline
                 if newline_pos > search_start {
@@ -114,6 +120,46 @@ impl FixedChunker {
 
         pos
     }
+
+    /// Chunks `text` within a token budget, preferring line boundaries in
+    /// the last fifth of each step.
+    fn chunk_by_token_budget(
+        &self,
+        buffer_id: i64,
+        text: &str,
+        meta: &ChunkMetadata,
+        budget: &TokenBudget,
+    ) -> Result<Vec<Chunk>> {
+        chunk_by_tokens(
+            buffer_id,
+            text,
+            self.name(),
+            budget,
+            meta.overlap_placement,
+            meta.max_chunks,
+            |floor, target| self.find_boundary(text, target, (target - floor) / 5),
+        )
+    }
+
+    /// Chunks without overlap, then extends the boundaries by `overlap`
+    /// bytes as `meta.overlap_placement` asks.
+    fn chunk_with_placement(
+        &self,
+        buffer_id: i64,
+        text: &str,
+        meta: &ChunkMetadata,
+        chunk_size: usize,
+        overlap: usize,
+    ) -> Result<Vec<Chunk>> {
+        let base_meta = ChunkMetadata {
+            chunk_size: chunk_size - overlap,
+            overlap: 0,
+            overlap_placement: OverlapPlacement::Leading,
+            ..meta.clone()
+        };
+        let base = self.chunk(buffer_id, text, Some(&base_meta))?;
+        Ok(place_overlap(text, base, overlap, meta.overlap_placement))
+    }
 }
 
 impl Chunker for FixedChunker {
@@ -123,6 +169,13 @@ impl Chunker for FixedChunker {
         text: &str,
         metadata: Option<&ChunkMetadata>,
     ) -> Result<Vec<Chunk>> {
+        // Token budgets take precedence over character sizes
+        if let Some(meta) = metadata
+            && let Some(budget) = &meta.token_budget
+        {
+            return self.chunk_by_token_budget(buffer_id, text, meta, budget);
+        }
+
         // Get effective chunk size and overlap
         let (chunk_size, overlap) = metadata.map_or((self.chunk_size, self.overlap), |meta| {
             (meta.chunk_size, meta.overlap)
@@ -157,13 +210,22 @@ impl Chunker for FixedChunker {
 
         // Handle text smaller than chunk size
         if text.len() <= chunk_size {
-            return Ok(vec![Chunk::with_strategy(
+            let mut chunks = vec![Chunk::with_strategy(
                 buffer_id,
                 text.to_string(),
                 0..text.len(),
                 0,
                 self.name(),
-            )]);
+            )];
+            assign_titles(&mut chunks);
+            return Ok(chunks);
+        }
+
+        if let Some(meta) = metadata
+            && overlap > 0
+            && meta.overlap_placement != OverlapPlacement::Leading
+        {
+            return self.chunk_with_placement(buffer_id, text, meta, chunk_size, overlap);
         }
 
         let mut chunks = Vec::new();
@@ -175,7 +237,8 @@ impl Chunker for FixedChunker {
             let end = if target_end >= text.len() {
                 text.len()
             } else {
-                self.find_boundary(text, target_end)
+                // Look back up to 10% of the chunk size for a newline
+                self.find_boundary(text, target_end, self.chunk_size / 10)
             };
 
             // Ensure we make progress
@@ -222,6 +285,7 @@ impl Chunker for FixedChunker {
             index += 1;
         }
 
+        assign_titles(&mut chunks);
         Ok(chunks)
     }
 
@@ -237,6 +301,7 @@ impl Chunker for FixedChunker {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::chunking::TokenBudget;
 
     #[test]
     fn test_fixed_chunker_default() {
@@ -485,4 +550,78 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_fixed_chunker_leading_overlap_default() {
+        let chunker = FixedChunker::new().line_aware(false);
+        let text = "0123456789ABCDEFGHIJ";
+        let default_meta = ChunkMetadata::with_size_and_overlap(10, 3);
+        let leading_meta = ChunkMetadata::with_size_and_overlap(10, 3)
+            .overlap_placement(OverlapPlacement::Leading);
+
+        let a = chunker.chunk(1, text, Some(&default_meta)).unwrap();
+        let b = chunker.chunk(1, text, Some(&leading_meta)).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a[1].byte_range.start, 7);
+    }
+
+    #[test]
+    fn test_fixed_chunker_trailing_overlap() {
+        let chunker = FixedChunker::new().line_aware(false);
+        let text = "0123456789ABCDEFGHIJ";
+        let meta = ChunkMetadata::with_size_and_overlap(10, 3)
+            .overlap_placement(OverlapPlacement::Trailing);
+        let chunks = chunker.chunk(1, text, Some(&meta)).unwrap();
+
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].byte_range, 0..10);
+        assert_eq!(chunks[1].byte_range, 7..17);
+        assert_eq!(chunks[2].byte_range, 14..20);
+        assert!(chunks[0].metadata.has_overlap);
+        assert!(!chunks[2].metadata.has_overlap);
+        for chunk in &chunks {
+            assert_eq!(&text[chunk.byte_range.clone()], chunk.content);
+            assert!(chunk.size() <= 10);
+        }
+    }
+
+    #[test]
+    fn test_fixed_chunker_symmetric_overlap() {
+        let chunker = FixedChunker::new().line_aware(false);
+        let text = "0123456789ABCDEFGHIJ";
+        let meta = ChunkMetadata::with_size_and_overlap(10, 4)
+            .overlap_placement(OverlapPlacement::Symmetric);
+        let chunks = chunker.chunk(1, text, Some(&meta)).unwrap();
+
+        // Base ranges are 0..6, 6..12, 12..18, 18..20; 2 bytes on each side
+        assert_eq!(chunks[0].byte_range, 0..8);
+        assert_eq!(chunks[1].byte_range, 4..14);
+        assert_eq!(chunks.last().unwrap().byte_range.end, text.len());
+        assert!(chunks.iter().all(|c| c.metadata.has_overlap));
+    }
+
+    #[test]
+    fn test_fixed_chunker_token_budget() {
+        let chunker = FixedChunker::new();
+        let text = "let value = compute(alpha, beta);\n".repeat(50);
+        let meta = ChunkMetadata::new().token_budget(TokenBudget::new(40, 8));
+        let chunks = chunker.chunk(1, &text, Some(&meta)).unwrap();
+
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks[0].byte_range.start, 0);
+        assert_eq!(chunks.last().unwrap().byte_range.end, text.len());
+        assert!(chunks[1].metadata.has_overlap);
+        for chunk in &chunks {
+            assert_eq!(&text[chunk.byte_range.clone()], chunk.content);
+            assert!(chunk.metadata.token_count.unwrap() <= 40);
+            assert!(chunk.estimate_tokens() <= 40);
+        }
+    }
+
+    #[test]
+    fn test_fixed_chunker_token_budget_invalid() {
+        let chunker = FixedChunker::new();
+        let meta = ChunkMetadata::new().token_budget(TokenBudget::new(10, 10));
+        assert!(chunker.chunk(1, "some text", Some(&meta)).is_err());
+    }
 }
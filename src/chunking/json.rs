@@ -0,0 +1,392 @@
+//! JSON/JSONL structure-aware chunking strategy.
+//!
+//! Splits a top-level JSON array between its elements and JSON Lines (or
+//! other whitespace-separated values) between values, so every chunk is
+//! valid JSON on its own.
+
+use crate::chunking::semantic::SemanticChunker;
+use crate::chunking::traits::{ChunkMetadata, Chunker};
+use crate::chunking::{DEFAULT_CHUNK_SIZE, MAX_CHUNK_SIZE};
+use crate::core::Chunk;
+use crate::error::{ChunkingError, Result};
+use std::ops::Range;
+
+/// Chunker for JSON arrays and JSON Lines.
+///
+/// For a document that is a single top-level array, consecutive elements
+/// are grouped up to the chunk size and each chunk is a JSON array of its
+/// elements: the text between the first and last element, wrapped in
+/// brackets. Otherwise the document is read as a sequence of values (JSON
+/// Lines, one value per line), and each chunk is a group of whole values,
+/// taken from the text as is.
+///
+/// The 0-based range of values a chunk holds is stored in custom metadata,
+/// available via [`Chunk::element_range`]. A value larger than the chunk
+/// size becomes a chunk of its own. Values larger than [`MAX_CHUNK_SIZE`],
+/// and documents that aren't well-formed JSON, fall back to the semantic
+/// splitter; those chunks are flagged with [`Chunk::is_json_fragment`], as
+/// they are not valid JSON on their own.
+///
+/// Overlap is not applied: chunks always hold whole values.
+///
+/// # Examples
+///
+/// ```
+/// use rlm_rs::chunking::{Chunker, JsonChunker};
+///
+/// let chunker = JsonChunker::with_size(24);
+/// let text = r#"[{"id": 1}, {"id": 2}, {"id": 3}]"#;
+/// let chunks = chunker.chunk(1, text, None).unwrap();
+/// assert_eq!(chunks[0].content, r#"[{"id": 1}, {"id": 2}]"#);
+/// assert_eq!(chunks[1].element_range(), Some((2, 2)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct JsonChunker {
+    /// Target chunk size in characters.
+    chunk_size: usize,
+}
+
+impl Default for JsonChunker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JsonChunker {
+    /// Creates a new JSON chunker with default settings.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+        }
+    }
+
+    /// Creates a JSON chunker with a custom chunk size.
+    #[must_use]
+    pub const fn with_size(chunk_size: usize) -> Self {
+        Self { chunk_size }
+    }
+
+    /// Splits a span with the semantic chunker into fragment pieces.
+    fn split_semantic(
+        text: &str,
+        span: Range<usize>,
+        chunk_size: usize,
+        elements: Option<(usize, usize)>,
+        pieces: &mut Vec<Piece>,
+    ) -> Result<()> {
+        let meta = ChunkMetadata::with_size(chunk_size);
+        let sub_chunks = SemanticChunker::new().chunk(0, &text[span.clone()], Some(&meta))?;
+        pieces.extend(sub_chunks.into_iter().map(|c| Piece {
+            span: span.start + c.byte_range.start..span.start + c.byte_range.end,
+            elements,
+            fragment: true,
+        }));
+        Ok(())
+    }
+
+    /// Turns pieces into chunks, wrapping whole array elements in brackets.
+    fn build_chunks(
+        &self,
+        buffer_id: i64,
+        text: &str,
+        pieces: Vec<Piece>,
+        in_array: bool,
+        max_chunks: usize,
+    ) -> Vec<Chunk> {
+        let mut chunks: Vec<Chunk> = pieces
+            .into_iter()
+            .enumerate()
+            .map(|(index, piece)| {
+                let slice = &text[piece.span.clone()];
+                let content = if in_array && !piece.fragment && piece.elements.is_some() {
+                    format!("[{slice}]")
+                } else {
+                    slice.to_string()
+                };
+                let mut chunk =
+                    Chunk::with_strategy(buffer_id, content, piece.span, index, self.name());
+                chunk.set_token_count(chunk.estimate_tokens());
+                if let Some((first, last)) = piece.elements {
+                    chunk.set_element_range(first, last);
+                    chunk.set_title(&format!("Elements {first}-{last}"));
+                }
+                if piece.fragment {
+                    chunk.set_json_fragment(true);
+                }
+                chunk
+            })
+            .collect();
+        if max_chunks > 0 {
+            chunks.truncate(max_chunks);
+        }
+        chunks
+    }
+}
+
+impl Chunker for JsonChunker {
+    fn chunk(
+        &self,
+        buffer_id: i64,
+        text: &str,
+        metadata: Option<&ChunkMetadata>,
+    ) -> Result<Vec<Chunk>> {
+        let chunk_size = metadata.map_or(self.chunk_size, |meta| meta.chunk_size);
+
+        if chunk_size == 0 {
+            return Err(ChunkingError::InvalidConfig {
+                reason: "chunk_size must be > 0".to_string(),
+            }
+            .into());
+        }
+        if chunk_size > MAX_CHUNK_SIZE {
+            return Err(ChunkingError::ChunkTooLarge {
+                size: chunk_size,
+                max: MAX_CHUNK_SIZE,
+            }
+            .into());
+        }
+
+        if text.trim().is_empty() {
+            return Ok(vec![]);
+        }
+
+        let max_chunks = metadata.map_or(0, |meta| meta.max_chunks);
+        let mut pieces = Vec::new();
+        let Some((values, in_array)) = top_level_values(text) else {
+            Self::split_semantic(text, 0..text.len(), chunk_size, None, &mut pieces)?;
+            return Ok(self.build_chunks(buffer_id, text, pieces, false, max_chunks));
+        };
+        if in_array && values.is_empty() {
+            // An empty array is a chunk of its own
+            let start = text.len() - text.trim_start().len();
+            pieces.push(Piece {
+                span: start..text.trim_end().len(),
+                elements: None,
+                fragment: false,
+            });
+        }
+
+        // Group whole values, leaving room for the brackets of array chunks
+        let budget = if in_array {
+            chunk_size.saturating_sub(2)
+        } else {
+            chunk_size
+        };
+        let mut start = 0;
+        while start < values.len() {
+            let mut end = start + 1;
+            while end < values.len() && values[end].end - values[start].start <= budget {
+                end += 1;
+            }
+            let span = values[start].start..values[end - 1].end;
+            if span.len() > MAX_CHUNK_SIZE {
+                Self::split_semantic(text, span, chunk_size, Some((start, start)), &mut pieces)?;
+            } else {
+                pieces.push(Piece {
+                    span,
+                    elements: Some((start, end - 1)),
+                    fragment: false,
+                });
+            }
+            start = end;
+        }
+
+        Ok(self.build_chunks(buffer_id, text, pieces, in_array, max_chunks))
+    }
+
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn description(&self) -> &'static str {
+        "JSON chunking between array elements or JSON Lines values, keeping each chunk valid JSON"
+    }
+}
+
+/// A chunk-to-be: a byte span, the values it holds, and whether it is only
+/// part of a value.
+#[derive(Debug)]
+struct Piece {
+    span: Range<usize>,
+    elements: Option<(usize, usize)>,
+    fragment: bool,
+}
+
+/// Finds the top-level values of a document: the elements of a document
+/// that is one array (with `true`), or else its whitespace-separated values.
+///
+/// Returns `None` if the document is not well-formed.
+fn top_level_values(text: &str) -> Option<(Vec<Range<usize>>, bool)> {
+    let start = text.len() - text.trim_start().len();
+    if text[start..].starts_with('[')
+        && let Some((elements, end)) = value_spans(text, start + 1, true)
+        && text[end..].trim().is_empty()
+    {
+        return Some((elements, true));
+    }
+    value_spans(text, start, false).map(|(values, _)| (values, false))
+}
+
+/// Scans values at nesting depth 0 from `start`, separated by commas up to
+/// the closing `]` when `in_array`, or by whitespace up to the end of the
+/// text otherwise.
+///
+/// Returns the value spans and the offset just past the scanned region, or
+/// `None` if brackets or strings are unbalanced.
+fn value_spans(text: &str, start: usize, in_array: bool) -> Option<(Vec<Range<usize>>, usize)> {
+    let bytes = text.as_bytes();
+    let mut spans = Vec::new();
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut value_start: Option<usize> = None;
+    let mut value_end = start;
+
+    for (i, &b) in bytes.iter().enumerate().skip(start) {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if b == b'\\' {
+                escaped = true;
+            } else if b == b'"' {
+                in_string = false;
+                value_end = i + 1;
+            }
+            continue;
+        }
+        match b {
+            b'"' => {
+                value_start.get_or_insert(i);
+                in_string = true;
+            }
+            b'{' | b'[' => {
+                value_start.get_or_insert(i);
+                depth += 1;
+            }
+            b'}' | b']' if depth > 0 => {
+                depth -= 1;
+                value_end = i + 1;
+            }
+            b']' if in_array => {
+                spans.extend(value_start.map(|s| s..value_end));
+                return Some((spans, i + 1));
+            }
+            b'}' | b']' => return None,
+            b',' if depth == 0 => {
+                if !in_array {
+                    return None;
+                }
+                spans.push(value_start.take()?..value_end);
+            }
+            _ if b.is_ascii_whitespace() => {
+                if depth == 0
+                    && !in_array
+                    && let Some(s) = value_start.take()
+                {
+                    spans.push(s..value_end);
+                }
+            }
+            _ => {
+                value_start.get_or_insert(i);
+                value_end = i + 1;
+            }
+        }
+    }
+
+    if in_array || in_string || depth > 0 {
+        return None;
+    }
+    spans.extend(value_start.map(|s| s..value_end));
+    Some((spans, bytes.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_array_groups_elements() {
+        let text = "[\n  {\"id\": 1},\n  {\"id\": 2},\n  {\"id\": 3}\n]\n";
+        let chunks = JsonChunker::with_size(26).chunk(1, text, None).unwrap();
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].content, "[{\"id\": 1},\n  {\"id\": 2}]");
+        assert_eq!(chunks[0].element_range(), Some((0, 1)));
+        assert_eq!(chunks[1].content, "[{\"id\": 3}]");
+        assert_eq!(&text[chunks[1].byte_range.clone()], "{\"id\": 3}");
+        assert_eq!(chunks[1].title(), Some("Elements 2-2".to_string()));
+        assert_eq!(chunks[1].metadata.strategy.as_deref(), Some("json"));
+        for chunk in &chunks {
+            assert!(serde_json::from_str::<serde_json::Value>(&chunk.content).is_ok());
+            assert!(!chunk.is_json_fragment());
+        }
+    }
+
+    #[test]
+    fn test_json_strings_with_brackets() {
+        let text = r#"["a, ]", {"b": "}\"["}, [1, [2]]]"#;
+        let chunks = JsonChunker::with_size(1).chunk(1, text, None).unwrap();
+        let contents: Vec<_> = chunks.iter().map(|c| c.content.as_str()).collect();
+        assert_eq!(
+            contents,
+            vec![r#"["a, ]"]"#, r#"[{"b": "}\"["}]"#, "[[1, [2]]]"]
+        );
+    }
+
+    #[test]
+    fn test_jsonl_groups_lines() {
+        let text = "{\"n\": 1}\n{\"n\": 2}\n\n{\"n\": 3}\n";
+        let chunks = JsonChunker::with_size(17).chunk(1, text, None).unwrap();
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].content, "{\"n\": 1}\n{\"n\": 2}");
+        assert_eq!(chunks[0].element_range(), Some((0, 1)));
+        assert_eq!(chunks[1].content, "{\"n\": 3}");
+        assert_eq!(chunks[1].element_range(), Some((2, 2)));
+
+        // Lines that are arrays are values too, not one array document
+        let chunks = JsonChunker::with_size(5)
+            .chunk(1, "[1]\n[2]\n", None)
+            .unwrap();
+        assert_eq!(chunks[1].content, "[2]");
+    }
+
+    #[test]
+    fn test_json_oversized_value_falls_back() {
+        let big = format!("{{\"text\": \"{}\"}}", "word ".repeat(MAX_CHUNK_SIZE / 5));
+        let text = format!("[{{\"id\": 1}}, {big}]");
+        let chunks = JsonChunker::with_size(10_000)
+            .chunk(1, &text, None)
+            .unwrap();
+
+        assert_eq!(chunks[0].content, "[{\"id\": 1}]");
+        assert!(!chunks[0].is_json_fragment());
+        assert!(chunks.len() > 2);
+        for chunk in &chunks[1..] {
+            assert!(chunk.is_json_fragment());
+            assert_eq!(chunk.element_range(), Some((1, 1)));
+            assert_eq!(chunk.content, text[chunk.byte_range.clone()]);
+        }
+    }
+
+    #[test]
+    fn test_json_malformed_and_empty() {
+        let chunks = JsonChunker::new()
+            .chunk(1, "[{\"id\": 1}, {\"id\": ", None)
+            .unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert!(chunks[0].is_json_fragment());
+        assert_eq!(chunks[0].element_range(), None);
+
+        assert!(JsonChunker::new().chunk(1, " \n", None).unwrap().is_empty());
+        let chunks = JsonChunker::new().chunk(1, " [ ]\n", None).unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].content, "[ ]");
+    }
+
+    #[test]
+    fn test_json_invalid_size() {
+        assert!(JsonChunker::with_size(0).chunk(1, "[]", None).is_err());
+    }
+}
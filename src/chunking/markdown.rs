@@ -0,0 +1,631 @@
+//! Markdown-aware chunking strategy.
+//!
+//! Splits Markdown documents at heading boundaries, keeping fenced code
+//! blocks and tables intact and recording each chunk's heading path.
+
+use crate::chunking::semantic::SemanticChunker;
+use crate::chunking::traits::{ChunkMetadata, Chunker};
+use crate::chunking::{DEFAULT_CHUNK_SIZE, MAX_CHUNK_SIZE, assign_titles};
+use crate::core::Chunk;
+use crate::error::{ChunkingError, Result};
+
+/// Markdown chunker that respects heading hierarchy.
+///
+/// Chunks start at ATX (`## Title`) or Setext (`Title\n-----`) headings
+/// where possible. Consecutive small sections are packed together up to
+/// the chunk size. Fenced code blocks (```` ``` ```` or `~~~`) and tables
+/// are never split; a section larger than the chunk size is split between
+/// paragraphs and blocks instead. Sections larger than [`MAX_CHUNK_SIZE`]
+/// fall back to the semantic splitter.
+///
+/// Each chunk records its heading path (e.g. `["Installation", "From Source"]`)
+/// in custom metadata, available via [`Chunk::heading_path`]. When sections
+/// are packed together, the path is the headings they all share.
+///
+/// Overlap is not applied: chunks always align with document structure.
+///
+/// # Examples
+///
+/// ```
+/// use rlm_rs::chunking::{Chunker, MarkdownChunker};
+///
+/// let chunker = MarkdownChunker::with_size(40);
+/// let text = "# Guide\n\nIntro text.\n\n## Install\n\nRun the installer now.\n";
+/// let chunks = chunker.chunk(1, text, None).unwrap();
+/// assert_eq!(chunks[1].heading_path(), vec!["Guide", "Install"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct MarkdownChunker {
+    /// Target chunk size in characters.
+    chunk_size: usize,
+}
+
+impl Default for MarkdownChunker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MarkdownChunker {
+    /// Creates a new Markdown chunker with default settings.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+        }
+    }
+
+    /// Creates a Markdown chunker with a custom chunk size.
+    #[must_use]
+    pub const fn with_size(chunk_size: usize) -> Self {
+        Self { chunk_size }
+    }
+
+    /// Splits an oversized span with the semantic chunker.
+    fn split_semantic(
+        text: &str,
+        start: usize,
+        end: usize,
+        chunk_size: usize,
+        heading_path: &[String],
+        pieces: &mut Vec<Piece>,
+    ) -> Result<()> {
+        let meta = ChunkMetadata::with_size(chunk_size);
+        let sub_chunks = SemanticChunker::new().chunk(0, &text[start..end], Some(&meta))?;
+        pieces.extend(sub_chunks.into_iter().map(|c| Piece {
+            start: start + c.byte_range.start,
+            end: start + c.byte_range.end,
+            heading_path: heading_path.to_vec(),
+        }));
+        Ok(())
+    }
+
+    /// Splits one oversized section between its blocks.
+    fn split_section(
+        text: &str,
+        lines: &[Line],
+        section: &Section,
+        chunk_size: usize,
+        pieces: &mut Vec<Piece>,
+    ) -> Result<()> {
+        let path = &section.heading_path;
+        if section.end - section.start > MAX_CHUNK_SIZE {
+            return Self::split_semantic(
+                text,
+                section.start,
+                section.end,
+                chunk_size,
+                path,
+                pieces,
+            );
+        }
+
+        let mut current: Option<(usize, usize)> = None;
+        for block in blocks(&lines[section.lines.clone()]) {
+            let len = block.end - block.start;
+            if len > chunk_size && (!block.atomic || len > MAX_CHUNK_SIZE) {
+                // Keep pending blocks (e.g. the heading) with the text that follows
+                let start = current.take().map_or(block.start, |(start, _)| start);
+                Self::split_semantic(text, start, block.end, chunk_size, path, pieces)?;
+                continue;
+            }
+            current = match current {
+                Some((start, _)) if block.end - start > chunk_size => {
+                    pieces.push(Piece::new(start, block.start, path));
+                    Some((block.start, block.end))
+                }
+                Some((start, _)) => Some((start, block.end)),
+                None => Some((block.start, block.end)),
+            };
+        }
+        if let Some((start, end)) = current {
+            pieces.push(Piece::new(start, end, path));
+        }
+        Ok(())
+    }
+}
+
+impl Chunker for MarkdownChunker {
+    fn chunk(
+        &self,
+        buffer_id: i64,
+        text: &str,
+        metadata: Option<&ChunkMetadata>,
+    ) -> Result<Vec<Chunk>> {
+        let chunk_size = metadata.map_or(self.chunk_size, |meta| meta.chunk_size);
+
+        if chunk_size == 0 {
+            return Err(ChunkingError::InvalidConfig {
+                reason: "chunk_size must be > 0".to_string(),
+            }
+            .into());
+        }
+        if chunk_size > MAX_CHUNK_SIZE {
+            return Err(ChunkingError::ChunkTooLarge {
+                size: chunk_size,
+                max: MAX_CHUNK_SIZE,
+            }
+            .into());
+        }
+
+        if text.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let lines = analyze(text);
+        let mut pieces: Vec<Piece> = Vec::new();
+        let mut current: Option<Piece> = None;
+
+        for section in sections(&lines) {
+            let len = section.end - section.start;
+            if len > chunk_size {
+                if let Some(piece) = current.take() {
+                    pieces.push(piece);
+                }
+                Self::split_section(text, &lines, &section, chunk_size, &mut pieces)?;
+                continue;
+            }
+
+            if let Some(piece) = current.as_mut()
+                && section.end - piece.start <= chunk_size
+            {
+                // Packed sections keep only the headings they share
+                piece.end = section.end;
+                let shared = piece
+                    .heading_path
+                    .iter()
+                    .zip(&section.heading_path)
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                piece.heading_path.truncate(shared);
+            } else {
+                if let Some(piece) = current.take() {
+                    pieces.push(piece);
+                }
+                current = Some(Piece::new(
+                    section.start,
+                    section.end,
+                    &section.heading_path,
+                ));
+            }
+        }
+        if let Some(piece) = current {
+            pieces.push(piece);
+        }
+
+        let max_chunks = metadata.map_or(0, |meta| meta.max_chunks);
+        let mut chunks: Vec<Chunk> = pieces
+            .into_iter()
+            .filter(|piece| !text[piece.start..piece.end].trim().is_empty())
+            .enumerate()
+            .map(|(index, piece)| {
+                let mut chunk = Chunk::with_strategy(
+                    buffer_id,
+                    text[piece.start..piece.end].to_string(),
+                    piece.start..piece.end,
+                    index,
+                    self.name(),
+                );
+                chunk.set_token_count(chunk.estimate_tokens());
+                chunk.set_heading_path(&piece.heading_path);
+                chunk
+            })
+            .collect();
+        if max_chunks > 0 {
+            chunks.truncate(max_chunks);
+        }
+
+        assign_titles(&mut chunks);
+        Ok(chunks)
+    }
+
+    fn name(&self) -> &'static str {
+        "markdown"
+    }
+
+    fn description(&self) -> &'static str {
+        "Markdown chunking at heading boundaries, keeping code fences and tables intact"
+    }
+}
+
+/// A chunk-to-be: a byte span and its heading path.
+#[derive(Debug)]
+struct Piece {
+    start: usize,
+    end: usize,
+    heading_path: Vec<String>,
+}
+
+impl Piece {
+    fn new(start: usize, end: usize, heading_path: &[String]) -> Self {
+        Self {
+            start,
+            end,
+            heading_path: heading_path.to_vec(),
+        }
+    }
+}
+
+/// Structural role of a Markdown line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LineKind {
+    /// Ordinary text (paragraphs, lists, headings, blank lines).
+    Text,
+    /// Inside a fenced code block, including the fence lines.
+    Fence,
+    /// Part of a table, including header and delimiter rows.
+    Table,
+}
+
+/// A line of Markdown with its byte span and structural role.
+#[derive(Debug)]
+struct Line {
+    start: usize,
+    end: usize,
+    kind: LineKind,
+    /// Whether the line starts a new fence or table.
+    opens_block: bool,
+    blank: bool,
+    /// Heading level and text, if the line is a heading.
+    heading: Option<(usize, String)>,
+}
+
+/// A heading-delimited section: lines from one heading to the next.
+#[derive(Debug)]
+struct Section {
+    start: usize,
+    end: usize,
+    lines: std::ops::Range<usize>,
+    heading_path: Vec<String>,
+}
+
+/// A run of lines that can be moved between chunks as a unit.
+#[derive(Debug)]
+struct Block {
+    start: usize,
+    end: usize,
+    /// Fences and tables must not be split.
+    atomic: bool,
+}
+
+/// Classifies every line of `text`.
+fn analyze(text: &str) -> Vec<Line> {
+    let raw: Vec<(usize, &str)> = text
+        .split_inclusive('\n')
+        .scan(0, |offset, line| {
+            let start = *offset;
+            *offset += line.len();
+            Some((start, line))
+        })
+        .collect();
+
+    let mut lines: Vec<Line> = Vec::with_capacity(raw.len());
+    let mut fence: Option<(char, usize)> = None;
+    let mut in_table = false;
+
+    for (i, &(start, raw_line)) in raw.iter().enumerate() {
+        let content = raw_line.trim_end_matches(['\n', '\r']);
+        let mut line = Line {
+            start,
+            end: start + raw_line.len(),
+            kind: LineKind::Text,
+            opens_block: false,
+            blank: content.trim().is_empty(),
+            heading: None,
+        };
+
+        if let Some((ch, len)) = fence {
+            line.kind = LineKind::Fence;
+            if closes_fence(content, ch, len) {
+                fence = None;
+            }
+        } else if let Some(open) = fence_open(content) {
+            fence = Some(open);
+            in_table = false;
+            line.kind = LineKind::Fence;
+            line.opens_block = true;
+        } else if in_table && !line.blank && content.contains('|') {
+            line.kind = LineKind::Table;
+        } else if content.contains('|')
+            && raw
+                .get(i + 1)
+                .is_some_and(|&(_, next)| is_table_delimiter(content, next))
+        {
+            in_table = true;
+            line.kind = LineKind::Table;
+            line.opens_block = true;
+        } else {
+            in_table = false;
+            if let Some(heading) = atx_heading(content) {
+                line.heading = Some(heading);
+            } else if let Some(level) = setext_level(content)
+                && let Some(prev) = lines.last_mut()
+                && prev.kind == LineKind::Text
+                && !prev.blank
+                && prev.heading.is_none()
+            {
+                let title = text[prev.start..prev.end].trim().to_string();
+                prev.heading = Some((level, title));
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Groups lines into sections starting at each heading.
+fn sections(lines: &[Line]) -> Vec<Section> {
+    let mut sections = Vec::new();
+    let mut stack: Vec<(usize, String)> = Vec::new();
+    let mut first = 0;
+
+    for (i, line) in lines.iter().enumerate() {
+        let Some((level, title)) = &line.heading else {
+            continue;
+        };
+        if i > first {
+            sections.push(Section {
+                start: lines[first].start,
+                end: lines[i - 1].end,
+                lines: first..i,
+                heading_path: stack.iter().map(|(_, t)| t.clone()).collect(),
+            });
+        }
+        while stack.last().is_some_and(|(l, _)| l >= level) {
+            stack.pop();
+        }
+        stack.push((*level, title.clone()));
+        first = i;
+    }
+    if first < lines.len() {
+        sections.push(Section {
+            start: lines[first].start,
+            end: lines[lines.len() - 1].end,
+            lines: first..lines.len(),
+            heading_path: stack.into_iter().map(|(_, t)| t).collect(),
+        });
+    }
+    sections
+}
+
+/// Splits a section's lines into paragraphs, fences, and tables.
+fn blocks(lines: &[Line]) -> Vec<Block> {
+    let mut blocks: Vec<Block> = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let starts_new = i == 0
+            || line.opens_block
+            || line.kind != lines[i - 1].kind
+            || (line.kind == LineKind::Text && lines[i - 1].blank && !line.blank);
+        match blocks.last_mut() {
+            Some(block) if !starts_new => block.end = line.end,
+            _ => blocks.push(Block {
+                start: line.start,
+                end: line.end,
+                atomic: line.kind != LineKind::Text,
+            }),
+        }
+    }
+    blocks
+}
+
+/// Returns the leading-space count, or `None` if indented as a code block.
+fn indent_of(line: &str) -> Option<usize> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    (indent <= 3).then_some(indent)
+}
+
+/// Parses an ATX heading (`## Title ##`) into its level and text.
+fn atx_heading(line: &str) -> Option<(usize, String)> {
+    let rest = &line[indent_of(line)?..];
+    let level = rest.len() - rest.trim_start_matches('#').len();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let after = &rest[level..];
+    if !after.is_empty() && !after.starts_with([' ', '\t']) {
+        return None;
+    }
+    let title = after.trim();
+    let without_closing = title.trim_end_matches('#');
+    let title = if without_closing.is_empty() || without_closing.ends_with([' ', '\t']) {
+        without_closing.trim_end()
+    } else {
+        title
+    };
+    Some((level, title.to_string()))
+}
+
+/// Returns the heading level if `line` is a Setext underline (`===` or `---`).
+fn setext_level(line: &str) -> Option<usize> {
+    let underline = line[indent_of(line)?..].trim_end();
+    if !underline.is_empty() && underline.chars().all(|c| c == '=') {
+        Some(1)
+    } else if underline.len() >= 2 && underline.chars().all(|c| c == '-') {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+/// Parses a code fence opening into its character and length.
+fn fence_open(line: &str) -> Option<(char, usize)> {
+    let rest = &line[indent_of(line)?..];
+    let ch = rest.chars().next().filter(|c| matches!(c, '`' | '~'))?;
+    let len = rest.len() - rest.trim_start_matches(ch).len();
+    // Backtick fences cannot have backticks in their info string
+    if len < 3 || (ch == '`' && rest[len..].contains('`')) {
+        return None;
+    }
+    Some((ch, len))
+}
+
+/// Checks whether `line` closes a fence opened with `len` copies of `ch`.
+fn closes_fence(line: &str, ch: char, len: usize) -> bool {
+    let Some(indent) = indent_of(line) else {
+        return false;
+    };
+    let rest = &line[indent..];
+    let run = rest.len() - rest.trim_start_matches(ch).len();
+    run >= len && rest[run..].trim().is_empty()
+}
+
+/// Splits a table row into cells, ignoring outer pipes.
+fn table_cells(row: &str) -> Vec<&str> {
+    let row = row.trim();
+    let row = row.strip_prefix('|').unwrap_or(row);
+    let row = row.strip_suffix('|').unwrap_or(row);
+    row.split('|').map(str::trim).collect()
+}
+
+/// Checks whether `delimiter` is a table delimiter row (`|---|:--:|`) for `header`.
+fn is_table_delimiter(header: &str, delimiter: &str) -> bool {
+    let delimiter = delimiter.trim_end_matches(['\n', '\r']);
+    if !delimiter.contains('-') {
+        return false;
+    }
+    let cells = table_cells(delimiter);
+    let valid = cells.iter().all(|cell| {
+        let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+        !dashes.is_empty() && dashes.chars().all(|c| c == '-')
+    });
+    valid && cells.len() == table_cells(header).len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk_md(text: &str, size: usize) -> Vec<Chunk> {
+        MarkdownChunker::with_size(size)
+            .chunk(1, text, None)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_markdown_chunker_empty_text() {
+        assert!(chunk_md("", 100).is_empty());
+    }
+
+    #[test]
+    fn test_markdown_chunker_splits_at_headings() {
+        let text = "# Guide\n\nIntro paragraph here.\n\n## Install\n\nRun the installer.\n\n## Usage\n\nCall the tool.\n";
+        let chunks = chunk_md(text, 40);
+
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks[1].content.starts_with("## Install"));
+        assert!(chunks[2].content.starts_with("## Usage"));
+        assert_eq!(chunks[0].heading_path(), vec!["Guide"]);
+        assert_eq!(chunks[2].heading_path(), vec!["Guide", "Usage"]);
+        assert_eq!(chunks[2].title(), Some("Usage".to_string()));
+        for chunk in &chunks {
+            assert_eq!(&text[chunk.byte_range.clone()], chunk.content);
+            assert_eq!(chunk.metadata.strategy.as_deref(), Some("markdown"));
+        }
+    }
+
+    #[test]
+    fn test_markdown_chunker_packs_small_sections() {
+        let text = "# A\none\n## B\ntwo\n## C\nthree\n";
+        let chunks = chunk_md(text, 1000);
+
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].content, text);
+        // Shared prefix of ["A"], ["A", "B"], ["A", "C"]
+        assert_eq!(chunks[0].heading_path(), vec!["A"]);
+    }
+
+    #[test]
+    fn test_markdown_chunker_setext_headings() {
+        let text =
+            "Installation\n============\n\nSteps.\n\nFrom Source\n-----------\n\nBuild it.\n";
+        let chunks = chunk_md(text, 40);
+
+        let last = chunks.last().unwrap();
+        assert!(last.content.starts_with("From Source"));
+        assert_eq!(last.heading_path(), vec!["Installation", "From Source"]);
+    }
+
+    #[test]
+    fn test_markdown_chunker_keeps_code_fence_intact() {
+        let fence = "```rust\n# not a heading\nfn main() {}\n\nfn other() {}\n```\n";
+        let text = format!("# Code\n\nSome intro text.\n\n{fence}\nAfter the fence.\n");
+        let chunks = chunk_md(&text, 30);
+
+        assert!(chunks.iter().any(|c| c.content.contains(fence)));
+        // The `#` inside the fence is not a heading
+        assert!(chunks.iter().all(|c| c.heading_path() == vec!["Code"]));
+    }
+
+    #[test]
+    fn test_markdown_chunker_tilde_fence() {
+        let text = "# T\n\n~~~\n## inside\n```\nstill inside\n~~~\n\n## Real\n\nText.\n";
+        let chunks = chunk_md(text, 20);
+
+        assert!(chunks.iter().any(|c| {
+            c.content
+                .contains("~~~\n## inside\n```\nstill inside\n~~~\n")
+        }));
+        assert_eq!(chunks.last().unwrap().heading_path(), vec!["T", "Real"]);
+    }
+
+    #[test]
+    fn test_markdown_chunker_keeps_table_intact() {
+        let table = "| Name | Value |\n|------|:-----:|\n| a    | 1     |\n| b    | 2     |\n";
+        let text = format!("# Data\n\nParagraph before the table.\n\n{table}\nParagraph after.\n");
+        let chunks = chunk_md(&text, 40);
+
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().any(|c| c.content.contains(table)));
+    }
+
+    #[test]
+    fn test_markdown_chunker_oversized_section_falls_back() {
+        let paragraph = "Sentence number one. ".repeat(200);
+        let text = format!("# Big\n\n{paragraph}\n");
+        let chunks = chunk_md(&text, 500);
+
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(chunk.size() <= 600);
+            assert_eq!(&text[chunk.byte_range.clone()], chunk.content);
+            assert_eq!(chunk.heading_path(), vec!["Big"]);
+        }
+        for (i, chunk) in chunks.iter().enumerate() {
+            assert_eq!(chunk.index, i);
+        }
+    }
+
+    #[test]
+    fn test_markdown_chunker_preamble_has_empty_path() {
+        let text = "Preamble text before headings.\n\n# First\n\nBody.\n";
+        let chunks = chunk_md(text, 35);
+
+        assert!(chunks[0].heading_path().is_empty());
+        assert_eq!(chunks.last().unwrap().heading_path(), vec!["First"]);
+    }
+
+    #[test]
+    fn test_markdown_chunker_invalid_config() {
+        let chunker = MarkdownChunker::with_size(0);
+        assert!(chunker.chunk(1, "# x", None).is_err());
+        let chunker = MarkdownChunker::with_size(MAX_CHUNK_SIZE + 1);
+        assert!(chunker.chunk(1, "# x", None).is_err());
+    }
+
+    #[test]
+    fn test_atx_heading() {
+        assert_eq!(atx_heading("## Usage ##"), Some((2, "Usage".to_string())));
+        assert_eq!(atx_heading("# C#"), Some((1, "C#".to_string())));
+        assert_eq!(atx_heading("#hashtag"), None);
+        assert_eq!(atx_heading("####### seven"), None);
+        assert_eq!(atx_heading("    # indented code"), None);
+    }
+
+    #[test]
+    fn test_is_table_delimiter() {
+        assert!(is_table_delimiter("| a | b |", "|---|:--:|\n"));
+        assert!(is_table_delimiter("a | b", "--- | ---"));
+        assert!(!is_table_delimiter("a | b", "---"));
+        assert!(!is_table_delimiter("| a |", "| text |"));
+    }
+}
@@ -5,20 +5,37 @@
 //!
 //! - **Fixed**: Simple character-based chunking with configurable size and overlap
 //! - **Semantic**: Unicode-aware chunking respecting sentence/paragraph boundaries
-//! - **Code**: Language-aware chunking at function/class boundaries
+//! - **Code**: Language-aware chunking at function/class boundaries, using
+//!   tree-sitter grammars with the `treesitter` feature
+//! - **Markdown**: Heading-aware chunking that keeps code fences and tables intact
+//! - **CSV**: Row-group chunking of CSV/TSV tables that repeats the header row
+//! - **JSON**: Element-group chunking of JSON arrays and JSON Lines into valid JSON
 //! - **Parallel**: Orchestrator for parallel chunk processing
 
 pub mod code;
+pub mod csv;
 pub mod fixed;
+pub mod json;
+pub mod markdown;
 pub mod parallel;
 pub mod semantic;
+pub mod tokenizer;
 pub mod traits;
+#[cfg(feature = "treesitter")]
+mod treesitter;
 
 pub use code::CodeChunker;
+pub use csv::CsvChunker;
 pub use fixed::FixedChunker;
+pub use json::JsonChunker;
+pub use markdown::MarkdownChunker;
 pub use parallel::ParallelChunker;
 pub use semantic::SemanticChunker;
-pub use traits::{ChunkMetadata as ChunkerMetadata, Chunker};
+pub use tokenizer::{SimpleTokenizer, TokenBudget, Tokenizer};
+pub use traits::{ChunkMetadata as ChunkerMetadata, Chunker, OverlapPlacement};
+
+use crate::core::{Chunk, derive_title};
+use crate::io::unicode::find_grapheme_boundary;
 
 /// Default chunk size in characters (~750 tokens at 4 chars/token).
 /// Sized for granular semantic search with embeddings.
@@ -30,6 +47,72 @@ pub const DEFAULT_OVERLAP: usize = 500;
 /// Maximum allowed chunk size (50k chars, ~12.5k tokens).
 pub const MAX_CHUNK_SIZE: usize = 50_000;
 
+/// Assigns a derived title to every chunk that does not already have one.
+///
+/// Chunkers with structural knowledge (headings, symbols) should set titles
+/// themselves first; this fills in the rest from each chunk's first line.
+pub(crate) fn assign_titles(chunks: &mut [Chunk]) {
+    for chunk in chunks {
+        if chunk.title().is_none()
+            && let Some(title) = derive_title(&chunk.content)
+        {
+            chunk.set_title(&title);
+        }
+    }
+}
+
+/// Extends non-overlapping base chunks with overlap at the given placement.
+///
+/// `base` must be contiguous, non-overlapping chunks of `text` in order.
+/// Leading overlap moves each chunk's start back into the previous chunk;
+/// trailing overlap moves each chunk's end forward into the next one.
+/// Offsets are snapped to grapheme cluster boundaries.
+pub(crate) fn place_overlap(
+    text: &str,
+    base: Vec<Chunk>,
+    overlap: usize,
+    placement: OverlapPlacement,
+) -> Vec<Chunk> {
+    let (lead, trail) = match placement {
+        OverlapPlacement::Leading => (overlap, 0),
+        OverlapPlacement::Trailing => (0, overlap),
+        OverlapPlacement::Symmetric => (overlap / 2, overlap - overlap / 2),
+    };
+    let last = base.len().saturating_sub(1);
+
+    base.into_iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            let mut start = chunk.byte_range.start;
+            let mut end = chunk.byte_range.end;
+            if i > 0 && lead > 0 {
+                start = find_grapheme_boundary(text, start.saturating_sub(lead));
+            }
+            if i < last && trail > 0 {
+                end = find_grapheme_boundary(text, end + trail).max(end);
+            }
+            if start == chunk.byte_range.start && end == chunk.byte_range.end {
+                return chunk;
+            }
+
+            let strategy = chunk.metadata.strategy.as_deref().unwrap_or_default();
+            let mut extended = Chunk::with_strategy(
+                chunk.buffer_id,
+                text[start..end].to_string(),
+                start..end,
+                chunk.index,
+                strategy,
+            );
+            if chunk.metadata.token_count.is_some() {
+                extended.set_token_count(extended.estimate_tokens());
+            }
+            extended.metadata.custom = chunk.metadata.custom;
+            extended.set_has_overlap(true);
+            extended
+        })
+        .collect()
+}
+
 /// Creates the default chunker (semantic).
 #[must_use]
 pub const fn default_chunker() -> SemanticChunker {
@@ -40,7 +123,8 @@ pub const fn default_chunker() -> SemanticChunker {
 ///
 /// # Arguments
 ///
-/// * `name` - Chunker strategy name: "fixed", "semantic", "code", or "parallel".
+/// * `name` - Chunker strategy name: "fixed", "semantic", "code", "markdown", "csv",
+///   "json", or "parallel".
 ///
 /// # Returns
 ///
@@ -54,6 +138,9 @@ pub fn create_chunker(name: &str) -> crate::error::Result<Box<dyn Chunker>> {
         "fixed" => Ok(Box::new(FixedChunker::new())),
         "semantic" => Ok(Box::new(SemanticChunker::new())),
         "code" | "ast" => Ok(Box::new(CodeChunker::new())),
+        "markdown" | "md" => Ok(Box::new(MarkdownChunker::new())),
+        "csv" | "tsv" => Ok(Box::new(CsvChunker::new())),
+        "json" | "jsonl" | "ndjson" => Ok(Box::new(JsonChunker::new())),
         "parallel" => Ok(Box::new(ParallelChunker::new(SemanticChunker::new()))),
         _ => Err(crate::error::ChunkingError::UnknownStrategy {
             name: name.to_string(),
@@ -65,7 +152,9 @@ pub fn create_chunker(name: &str) -> crate::error::Result<Box<dyn Chunker>> {
 /// Lists available chunking strategy names.
 #[must_use]
 pub fn available_strategies() -> Vec<&'static str> {
-    vec!["fixed", "semantic", "code", "parallel"]
+    vec![
+        "fixed", "semantic", "code", "markdown", "csv", "json", "parallel",
+    ]
 }
 
 #[cfg(test)]
@@ -112,19 +201,99 @@ mod tests {
     #[test]
     fn test_available_strategies() {
         let strategies = available_strategies();
-        assert_eq!(strategies.len(), 4);
+        assert_eq!(strategies.len(), 7);
         assert!(strategies.contains(&"fixed"));
         assert!(strategies.contains(&"semantic"));
         assert!(strategies.contains(&"code"));
+        assert!(strategies.contains(&"markdown"));
+        assert!(strategies.contains(&"csv"));
+        assert!(strategies.contains(&"json"));
         assert!(strategies.contains(&"parallel"));
     }
 
+    #[test]
+    fn test_place_overlap_ranges() {
+        let text = "0123456789ABCDEFGHIJ";
+        let base = vec![
+            Chunk::with_strategy(1, text[0..10].to_string(), 0..10, 0, "fixed"),
+            Chunk::with_strategy(1, text[10..20].to_string(), 10..20, 1, "fixed"),
+        ];
+
+        let leading = place_overlap(text, base.clone(), 4, OverlapPlacement::Leading);
+        assert_eq!(leading[0].byte_range, 0..10);
+        assert_eq!(leading[1].byte_range, 6..20);
+        assert!(!leading[0].metadata.has_overlap);
+        assert!(leading[1].metadata.has_overlap);
+
+        let trailing = place_overlap(text, base.clone(), 4, OverlapPlacement::Trailing);
+        assert_eq!(trailing[0].byte_range, 0..14);
+        assert_eq!(trailing[0].content, "0123456789ABCD");
+        assert_eq!(trailing[1].byte_range, 10..20);
+        assert!(trailing[0].metadata.has_overlap);
+        assert!(!trailing[1].metadata.has_overlap);
+
+        let symmetric = place_overlap(text, base, 4, OverlapPlacement::Symmetric);
+        assert_eq!(symmetric[0].byte_range, 0..12);
+        assert_eq!(symmetric[1].byte_range, 8..20);
+    }
+
+    #[test]
+    fn test_place_overlap_char_boundaries() {
+        let text = "ab世界cd";
+        let base = vec![
+            Chunk::with_strategy(1, text[0..5].to_string(), 0..5, 0, "fixed"),
+            Chunk::with_strategy(1, text[5..10].to_string(), 5..10, 1, "fixed"),
+        ];
+        let trailing = place_overlap(text, base, 2, OverlapPlacement::Trailing);
+        assert!(text.is_char_boundary(trailing[0].byte_range.end));
+        assert_eq!(&text[trailing[0].byte_range.clone()], trailing[0].content);
+    }
+
+    #[test]
+    fn test_assign_titles() {
+        let mut chunks = vec![
+            Chunk::new(1, "## Usage\nRun it".to_string(), 0..15, 0),
+            Chunk::new(1, "   ".to_string(), 15..18, 1),
+        ];
+        chunks[0].set_title("Preset");
+        assign_titles(&mut chunks);
+        assert_eq!(chunks[0].title(), Some("Preset".to_string()));
+        assert_eq!(chunks[1].title(), None);
+
+        let mut chunks = vec![Chunk::new(1, "## Usage\nRun it".to_string(), 0..15, 0)];
+        assign_titles(&mut chunks);
+        assert_eq!(chunks[0].title(), Some("Usage".to_string()));
+    }
+
     #[test]
     fn test_create_chunker_code() {
         let chunker = create_chunker("code").unwrap();
         assert_eq!(chunker.name(), "code");
     }
 
+    #[test]
+    fn test_create_chunker_markdown() {
+        let chunker = create_chunker("markdown").unwrap();
+        assert_eq!(chunker.name(), "markdown");
+        let chunker = create_chunker("md").unwrap();
+        assert_eq!(chunker.name(), "markdown");
+    }
+
+    #[test]
+    fn test_create_chunker_csv() {
+        let chunker = create_chunker("csv").unwrap();
+        assert_eq!(chunker.name(), "csv");
+        let chunker = create_chunker("tsv").unwrap();
+        assert_eq!(chunker.name(), "csv");
+    }
+
+    #[test]
+    fn test_create_chunker_json() {
+        for name in ["json", "jsonl", "ndjson"] {
+            assert_eq!(create_chunker(name).unwrap().name(), "json");
+        }
+    }
+
     #[test]
     fn test_create_chunker_ast_alias() {
         let chunker = create_chunker("ast").unwrap();
@@ -1,13 +1,19 @@
 //! Semantic chunking strategy.
 //!
 //! Provides Unicode-aware chunking that respects sentence and paragraph
-//! boundaries using the `unicode-segmentation` crate.
-
-use crate::chunking::traits::{ChunkMetadata, Chunker};
-use crate::chunking::{DEFAULT_CHUNK_SIZE, DEFAULT_OVERLAP, MAX_CHUNK_SIZE};
+//! boundaries using the `unicode-segmentation` crate. Chunk boundaries are
+//! snapped to grapheme clusters so emoji sequences and combining marks are
+//! never split between chunks.
+
+use crate::chunking::tokenizer::chunk_by_tokens;
+use crate::chunking::traits::{ChunkMetadata, Chunker, OverlapPlacement};
+use crate::chunking::{
+    DEFAULT_CHUNK_SIZE, DEFAULT_OVERLAP, MAX_CHUNK_SIZE, assign_titles, place_overlap,
+};
 use crate::core::Chunk;
 use crate::error::{ChunkingError, Result};
 use crate::io::find_char_boundary;
+use crate::io::unicode::{find_grapheme_boundary, find_grapheme_boundary_forward};
 use unicode_segmentation::UnicodeSegmentation;
 
 /// Semantic chunker that respects sentence and paragraph boundaries.
@@ -81,15 +87,15 @@ impl SemanticChunker {
 
     /// Finds the best boundary near the target position.
     ///
-    /// Prefers paragraph breaks > sentence breaks > word breaks > character breaks.
-    fn find_best_boundary(&self, text: &str, target_pos: usize) -> usize {
+    /// Prefers paragraph breaks > sentence breaks > word breaks > character breaks,
+    /// searching up to `window` bytes before the target.
+    fn find_best_boundary(text: &str, target_pos: usize, window: usize) -> usize {
         if target_pos >= text.len() {
             return text.len();
         }
 
-        // Search window: look back up to 20% of chunk size for a good boundary
         // Ensure both boundaries are valid UTF-8 character boundaries
-        let search_start = find_char_boundary(text, target_pos.saturating_sub(self.chunk_size / 5));
+        let search_start = find_char_boundary(text, target_pos.saturating_sub(window));
         let search_end = find_char_boundary(text, target_pos.min(text.len()));
 
         if search_start >= search_end {
@@ -162,6 +168,21 @@ impl Chunker for SemanticChunker {
         text: &str,
         metadata: Option<&ChunkMetadata>,
     ) -> Result<Vec<Chunk>> {
+        // Token budgets take precedence over character sizes
+        if let Some(meta) = metadata
+            && let Some(budget) = &meta.token_budget
+        {
+            return chunk_by_tokens(
+                buffer_id,
+                text,
+                self.name(),
+                budget,
+                meta.overlap_placement,
+                meta.max_chunks,
+                |floor, target| Self::find_best_boundary(text, target, target - floor),
+            );
+        }
+
         // Get effective chunk size and overlap
         let (chunk_size, overlap) = metadata.map_or((self.chunk_size, self.overlap), |meta| {
             (meta.chunk_size, meta.overlap)
@@ -196,13 +217,30 @@ impl Chunker for SemanticChunker {
 
         // Handle text smaller than chunk size
         if text.len() <= chunk_size {
-            return Ok(vec![Chunk::with_strategy(
+            let mut chunks = vec![Chunk::with_strategy(
                 buffer_id,
                 text.to_string(),
                 0..text.len(),
                 0,
                 self.name(),
-            )]);
+            )];
+            assign_titles(&mut chunks);
+            return Ok(chunks);
+        }
+
+        // Non-leading overlap: chunk without overlap, then extend boundaries
+        if let Some(meta) = metadata
+            && overlap > 0
+            && meta.overlap_placement != OverlapPlacement::Leading
+        {
+            let base_meta = ChunkMetadata {
+                chunk_size: chunk_size - overlap,
+                overlap: 0,
+                overlap_placement: OverlapPlacement::Leading,
+                ..meta.clone()
+            };
+            let base = self.chunk(buffer_id, text, Some(&base_meta))?;
+            return Ok(place_overlap(text, base, overlap, meta.overlap_placement));
         }
 
         let mut chunks = Vec::new();
@@ -214,12 +252,21 @@ impl Chunker for SemanticChunker {
             let end = if target_end >= text.len() {
                 text.len()
             } else {
-                self.find_best_boundary(text, target_end)
+                // Look back up to 20% of chunk size for a good boundary
+                find_grapheme_boundary(
+                    text,
+                    Self::find_best_boundary(text, target_end, self.chunk_size / 5),
+                )
             };
 
-            // Ensure we make progress
+            // Ensure we make progress, even past a grapheme larger than chunk_size
             let end = if end <= start {
-                find_char_boundary(text, (start + chunk_size).min(text.len()))
+                let forced = find_grapheme_boundary(text, target_end);
+                if forced > start {
+                    forced
+                } else {
+                    find_grapheme_boundary_forward(text, start + 1)
+                }
             } else {
                 end
             };
@@ -254,7 +301,10 @@ impl Chunker for SemanticChunker {
             let next_start = if overlap > 0 {
                 // For overlap, we need to find a good boundary before the overlap point
                 let overlap_start = end.saturating_sub(overlap);
-                self.find_best_boundary(text, overlap_start)
+                find_grapheme_boundary(
+                    text,
+                    Self::find_best_boundary(text, overlap_start, self.chunk_size / 5),
+                )
             } else {
                 end
             };
@@ -293,6 +343,7 @@ impl Chunker for SemanticChunker {
             chunks.push(merged);
         }
 
+        assign_titles(&mut chunks);
         Ok(chunks)
     }
 
@@ -312,6 +363,7 @@ impl Chunker for SemanticChunker {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::chunking::{SimpleTokenizer, TokenBudget, Tokenizer};
 
     #[test]
     fn test_semantic_chunker_default() {
@@ -651,4 +703,143 @@ mod tests {
             assert!(text.is_char_boundary(chunk.byte_range.end));
         }
     }
+
+    #[test]
+    fn test_semantic_chunker_trailing_overlap() {
+        let chunker = SemanticChunker::new().min_chunk_size(1);
+        let text = "Alpha beta gamma. Delta epsilon zeta. Eta theta iota. Kappa lambda mu.";
+        let meta = ChunkMetadata::with_size_and_overlap(30, 8)
+            .overlap_placement(OverlapPlacement::Trailing);
+        let chunks = chunker.chunk(1, text, Some(&meta)).unwrap();
+
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks[0].byte_range.start, 0);
+        for pair in chunks.windows(2) {
+            // Each chunk runs past the start of the next one
+            assert!(pair[0].byte_range.end > pair[1].byte_range.start);
+            assert!(pair[0].metadata.has_overlap);
+        }
+        assert!(!chunks.last().unwrap().metadata.has_overlap);
+        for chunk in &chunks {
+            assert_eq!(&text[chunk.byte_range.clone()], chunk.content);
+            assert!(chunk.metadata.token_count.is_some());
+        }
+    }
+
+    #[test]
+    fn test_semantic_chunker_symmetric_overlap() {
+        let chunker = SemanticChunker::new().min_chunk_size(1);
+        let text = "Hello \u{4E16}\u{754C}! ".repeat(20);
+        let meta = ChunkMetadata::with_size_and_overlap(40, 10)
+            .overlap_placement(OverlapPlacement::Symmetric);
+        let chunks = chunker.chunk(1, &text, Some(&meta)).unwrap();
+
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks[0].byte_range.start, 0);
+        assert_eq!(chunks.last().unwrap().byte_range.end, text.len());
+        for chunk in &chunks {
+            assert!(text.is_char_boundary(chunk.byte_range.start));
+            assert!(text.is_char_boundary(chunk.byte_range.end));
+            assert!(chunk.metadata.has_overlap);
+        }
+    }
+
+    /// Asserts every chunk starts and ends on a grapheme cluster boundary.
+    fn assert_grapheme_aligned(text: &str, chunks: &[Chunk]) {
+        let boundaries: std::collections::HashSet<usize> = text
+            .grapheme_indices(true)
+            .map(|(i, _)| i)
+            .chain(std::iter::once(text.len()))
+            .collect();
+        for chunk in chunks {
+            assert!(boundaries.contains(&chunk.byte_range.start), "{chunk:?}");
+            assert!(boundaries.contains(&chunk.byte_range.end), "{chunk:?}");
+            assert_eq!(&text[chunk.byte_range.clone()], chunk.content);
+        }
+    }
+
+    #[test]
+    fn test_semantic_chunker_zwj_emoji_across_boundary() {
+        // 25-byte family emoji; chunk targets land inside the sequence
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}\u{200D}\u{1F466}";
+        let text = format!("ab{}", family.repeat(6));
+        let chunker = SemanticChunker::with_size(30).min_chunk_size(1);
+        let chunks = chunker.chunk(1, &text, None).unwrap();
+
+        assert!(chunks.len() > 1);
+        assert_grapheme_aligned(&text, &chunks);
+        assert_eq!(chunks.last().unwrap().byte_range.end, text.len());
+    }
+
+    #[test]
+    fn test_semantic_chunker_grapheme_larger_than_chunk() {
+        // Each grapheme (25 bytes) exceeds the chunk size; chunks must still progress
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}\u{200D}\u{1F466}";
+        let text = family.repeat(3);
+        let chunker = SemanticChunker::with_size(10).min_chunk_size(1);
+        let chunks = chunker.chunk(1, &text, None).unwrap();
+
+        assert_eq!(chunks.len(), 3);
+        assert_grapheme_aligned(&text, &chunks);
+    }
+
+    #[test]
+    fn test_semantic_chunker_combining_marks_and_overlap() {
+        let text = "cafe\u{301} \u{1F44D}\u{1F3FD} na\u{308}ive ".repeat(20);
+        let chunker = SemanticChunker::new().min_chunk_size(1);
+        for placement in [
+            OverlapPlacement::Leading,
+            OverlapPlacement::Trailing,
+            OverlapPlacement::Symmetric,
+        ] {
+            let meta = ChunkMetadata::with_size_and_overlap(37, 9).overlap_placement(placement);
+            let chunks = chunker.chunk(1, &text, Some(&meta)).unwrap();
+            assert!(chunks.len() > 1);
+            assert_grapheme_aligned(&text, &chunks);
+        }
+    }
+
+    #[test]
+    fn test_semantic_chunker_assigns_titles() {
+        let chunker = SemanticChunker::with_size(40).min_chunk_size(1);
+        let text = "Getting started\nInstall the tool first.\n\nConfiguration\nEdit the file.";
+        let chunks = chunker.chunk(1, text, None).unwrap();
+
+        assert_eq!(chunks[0].title(), Some("Getting started".to_string()));
+        assert!(chunks.iter().all(|c| c.title().is_some()));
+    }
+
+    #[test]
+    fn test_semantic_chunker_token_budget() {
+        let chunker = SemanticChunker::new();
+        let text = "The quick brown fox jumps over the lazy dog. ".repeat(40);
+        let meta = ChunkMetadata::new().token_budget(TokenBudget::new(50, 10));
+        let chunks = chunker.chunk(1, &text, Some(&meta)).unwrap();
+
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks.last().unwrap().byte_range.end, text.len());
+        for chunk in &chunks {
+            assert!(chunk.metadata.token_count.unwrap() <= 50);
+            assert!(chunk.estimate_tokens() <= 50);
+        }
+        // Boundaries still prefer sentence ends
+        assert!(chunks[0].content.trim_end().ends_with('.'));
+    }
+
+    #[test]
+    fn test_semantic_chunker_token_budget_graphemes() {
+        let chunker = SemanticChunker::new();
+        let text = "👨‍👩‍👧‍👦 e\u{301}tude 日本語. ".repeat(30);
+        let meta = ChunkMetadata::new().token_budget(TokenBudget::new(20, 4));
+        let chunks = chunker.chunk(1, &text, Some(&meta)).unwrap();
+
+        assert!(chunks.len() > 1);
+        assert_grapheme_aligned(&text, &chunks);
+        assert_eq!(chunks.last().unwrap().byte_range.end, text.len());
+        for chunk in &chunks {
+            let tokens = SimpleTokenizer.count_tokens(&chunk.content);
+            assert!(tokens <= 20, "{tokens} tokens in {:?}", chunk.content);
+            assert_eq!(chunk.metadata.token_count, Some(tokens));
+        }
+    }
 }
@@ -0,0 +1,417 @@
+//! Token counting for token-budgeted chunking.
+//!
+//! Character counts are a poor proxy for LLM context limits, especially
+//! for code and CJK text. A [`Tokenizer`] lets chunkers size chunks by
+//! token count instead; [`SimpleTokenizer`] is a dependency-free default
+//! that approximates BPE tokenizers closely enough for budgeting.
+
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
+use crate::chunking::OverlapPlacement;
+use crate::core::Chunk;
+use crate::error::{ChunkingError, Result};
+use crate::io::unicode::{find_grapheme_boundary, find_grapheme_boundary_forward};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Splits text into tokens for budgeting purposes.
+///
+/// Implementations must be prefix-monotone: a prefix of a text never has
+/// more tokens than the text itself. Chunkers rely on this to shrink a
+/// chunk to a preferred boundary without exceeding the budget.
+pub trait Tokenizer: Send + Sync {
+    /// Returns the byte offset at which each token ends, in order.
+    fn token_ends(&self, text: &str) -> Vec<usize>;
+
+    /// Returns the number of tokens in `text`.
+    fn count_tokens(&self, text: &str) -> usize {
+        self.token_ends(text).len()
+    }
+
+    /// Returns the name of the tokenizer.
+    fn name(&self) -> &'static str;
+}
+
+/// Maximum ASCII word piece length counted as a single token.
+const MAX_PIECE_LEN: usize = 4;
+
+/// Whitespace/BPE-style heuristic tokenizer.
+///
+/// Whitespace separates tokens but is never counted. ASCII words are split
+/// into pieces of up to four characters, each ASCII punctuation character
+/// is its own token, and every non-ASCII grapheme cluster counts as exactly
+/// one token, so emoji sequences and combining marks do not inflate counts.
+///
+/// # Examples
+///
+/// ```
+/// use rlm_rs::chunking::{SimpleTokenizer, Tokenizer};
+///
+/// let tokenizer = SimpleTokenizer;
+/// assert_eq!(tokenizer.count_tokens("hello, world"), 5);
+/// assert_eq!(tokenizer.count_tokens("日本語"), 3);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SimpleTokenizer;
+
+impl Tokenizer for SimpleTokenizer {
+    fn token_ends(&self, text: &str) -> Vec<usize> {
+        let mut ends = Vec::new();
+        let mut piece_len = 0;
+
+        for (offset, grapheme) in text.grapheme_indices(true) {
+            let end = offset + grapheme.len();
+            let is_word = grapheme.len() == 1
+                && grapheme
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'_');
+
+            if is_word {
+                if piece_len > 0 && piece_len < MAX_PIECE_LEN {
+                    // Extend the current word piece
+                    if let Some(last) = ends.last_mut() {
+                        *last = end;
+                    }
+                    piece_len += 1;
+                } else {
+                    ends.push(end);
+                    piece_len = 1;
+                }
+                continue;
+            }
+
+            piece_len = 0;
+            if !grapheme.chars().all(char::is_whitespace) {
+                ends.push(end);
+            }
+        }
+
+        ends
+    }
+
+    fn name(&self) -> &'static str {
+        "simple"
+    }
+}
+
+/// Chunk size and overlap measured in tokens.
+///
+/// When set on chunker metadata, token-aware chunkers ignore the character
+/// `chunk_size`/`overlap` and keep each chunk within `chunk_tokens` as
+/// counted by the budget's tokenizer.
+#[derive(Clone)]
+pub struct TokenBudget {
+    /// Maximum tokens per chunk (before trailing overlap is added).
+    pub chunk_tokens: usize,
+    /// Tokens shared between consecutive chunks.
+    pub overlap_tokens: usize,
+    /// Tokenizer used to count tokens.
+    pub tokenizer: Arc<dyn Tokenizer>,
+}
+
+impl fmt::Debug for TokenBudget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenBudget")
+            .field("chunk_tokens", &self.chunk_tokens)
+            .field("overlap_tokens", &self.overlap_tokens)
+            .field("tokenizer", &self.tokenizer.name())
+            .finish()
+    }
+}
+
+impl TokenBudget {
+    /// Creates a budget using [`SimpleTokenizer`].
+    #[must_use]
+    pub fn new(chunk_tokens: usize, overlap_tokens: usize) -> Self {
+        Self {
+            chunk_tokens,
+            overlap_tokens,
+            tokenizer: Arc::new(SimpleTokenizer),
+        }
+    }
+
+    /// Replaces the tokenizer used to count tokens.
+    #[must_use]
+    pub fn with_tokenizer(mut self, tokenizer: Arc<dyn Tokenizer>) -> Self {
+        self.tokenizer = tokenizer;
+        self
+    }
+
+    /// Validates the budget.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `chunk_tokens` is zero or the overlap is not
+    /// smaller than the chunk budget.
+    pub fn validate(&self) -> Result<()> {
+        if self.chunk_tokens == 0 {
+            return Err(ChunkingError::InvalidConfig {
+                reason: "chunk_tokens must be > 0".to_string(),
+            }
+            .into());
+        }
+        if self.overlap_tokens >= self.chunk_tokens {
+            return Err(ChunkingError::OverlapTooLarge {
+                overlap: self.overlap_tokens,
+                size: self.chunk_tokens,
+            }
+            .into());
+        }
+        Ok(())
+    }
+}
+
+/// Token end offsets for a whole text, used to map token counts to bytes.
+struct TokenIndex<'a> {
+    text: &'a str,
+    tokenizer: &'a dyn Tokenizer,
+    ends: Vec<usize>,
+}
+
+impl<'a> TokenIndex<'a> {
+    fn new(text: &'a str, tokenizer: &'a dyn Tokenizer) -> Self {
+        Self {
+            text,
+            tokenizer,
+            ends: tokenizer.token_ends(text),
+        }
+    }
+
+    /// Returns the furthest offset reachable from `start` within `n` tokens.
+    fn advance(&self, start: usize, n: usize) -> usize {
+        if n == 0 {
+            return start;
+        }
+        let first = self.ends.partition_point(|&e| e <= start);
+        let end = self
+            .ends
+            .get(first + n - 1)
+            .copied()
+            .unwrap_or(self.text.len());
+
+        // Starting mid-token can split one token into two; recount the span
+        let span = self.tokenizer.token_ends(&self.text[start..end]);
+        if span.len() > n {
+            start + span[n - 1]
+        } else {
+            end
+        }
+    }
+
+    /// Returns the offset `n` tokens before `end`.
+    fn retreat(&self, end: usize, n: usize) -> usize {
+        let count = self.ends.partition_point(|&e| e <= end);
+        if count <= n {
+            0
+        } else {
+            self.ends[count - n - 1]
+        }
+    }
+}
+
+/// Chunks `text` so every chunk stays within `budget`.
+///
+/// `snap(floor, target)` returns the chunker's preferred boundary in
+/// `floor..=target`, where `floor` trails `target` by half the step's (or
+/// the overlap's) tokens; boundaries are then aligned to grapheme clusters.
+/// Non-leading overlap is added by extending token-budgeted base chunks.
+pub(crate) fn chunk_by_tokens(
+    buffer_id: i64,
+    text: &str,
+    strategy: &str,
+    budget: &TokenBudget,
+    placement: OverlapPlacement,
+    max_chunks: usize,
+    snap: impl Fn(usize, usize) -> usize,
+) -> Result<Vec<Chunk>> {
+    budget.validate()?;
+    if text.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let tokenizer = budget.tokenizer.as_ref();
+    let index = TokenIndex::new(text, tokenizer);
+    let overlap = budget.overlap_tokens;
+    let (step, lead, trail) = match placement {
+        OverlapPlacement::Leading => (budget.chunk_tokens, overlap, 0),
+        OverlapPlacement::Trailing => (budget.chunk_tokens - overlap, 0, overlap),
+        OverlapPlacement::Symmetric => (
+            budget.chunk_tokens - overlap,
+            overlap / 2,
+            overlap - overlap / 2,
+        ),
+    };
+
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    let mut start = 0;
+
+    while start < text.len() {
+        let target_end = index.advance(start, step);
+        let end = if target_end >= text.len() {
+            text.len()
+        } else {
+            let floor = index.retreat(target_end, step / 2).max(start);
+            find_grapheme_boundary(text, snap(floor, target_end))
+        };
+
+        // Ensure we make progress, even past a token larger than the budget
+        let end = if end <= start {
+            let forced = find_grapheme_boundary(text, target_end);
+            if forced > start {
+                forced
+            } else {
+                find_grapheme_boundary_forward(text, start + 1)
+            }
+        } else {
+            end
+        };
+
+        ranges.push(start..end);
+
+        if max_chunks > 0 && ranges.len() >= max_chunks {
+            break;
+        }
+        if end >= text.len() {
+            break;
+        }
+
+        // Leading overlap is built into the walk; the others are added below
+        let next_start = if placement == OverlapPlacement::Leading && lead > 0 {
+            let target = index.retreat(end, lead);
+            let floor = index.retreat(target, lead / 2).max(start).min(target);
+            find_grapheme_boundary(text, snap(floor, target))
+        } else {
+            end
+        };
+        start = if next_start <= start { end } else { next_start };
+    }
+
+    let last = ranges.len().saturating_sub(1);
+    let mut chunks: Vec<Chunk> = ranges
+        .into_iter()
+        .enumerate()
+        .map(|(i, mut range)| {
+            if placement != OverlapPlacement::Leading {
+                if i > 0 && lead > 0 {
+                    range.start = find_grapheme_boundary(text, index.retreat(range.start, lead));
+                }
+                if i < last && trail > 0 {
+                    range.end = find_grapheme_boundary(text, index.advance(range.end, trail));
+                }
+            }
+
+            let content = text[range.clone()].to_string();
+            let token_count = tokenizer.count_tokens(&content);
+            let mut chunk = Chunk::with_strategy(buffer_id, content, range, i, strategy);
+            chunk.set_has_overlap((i > 0 && lead > 0) || (i < last && trail > 0));
+            chunk.set_token_count(token_count);
+            chunk
+        })
+        .collect();
+
+    super::assign_titles(&mut chunks);
+    Ok(chunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simple_tokenizer_words_and_punctuation() {
+        let tokenizer = SimpleTokenizer;
+        assert_eq!(tokenizer.count_tokens(""), 0);
+        assert_eq!(tokenizer.count_tokens("   \n\t"), 0);
+        assert_eq!(tokenizer.count_tokens("a b c"), 3);
+        assert_eq!(tokenizer.count_tokens("fn main() {}"), 6);
+        // 20-char word splits into five 4-char pieces
+        assert_eq!(tokenizer.count_tokens("internationalization"), 5);
+    }
+
+    #[test]
+    fn test_simple_tokenizer_graphemes_count_once() {
+        let tokenizer = SimpleTokenizer;
+        // Family emoji: 7 code points, 25 bytes, one grapheme
+        assert_eq!(tokenizer.count_tokens("👨‍👩‍👧‍👦"), 1);
+        // Base letter plus combining acute accent
+        assert_eq!(tokenizer.count_tokens("e\u{301}"), 1);
+        assert_eq!(tokenizer.count_tokens("日本語のテキスト"), 8);
+    }
+
+    #[test]
+    fn test_simple_tokenizer_ends_are_ordered() {
+        let text = "Hello, 世界! 👍🏽 done.";
+        let ends = SimpleTokenizer.token_ends(text);
+        assert!(ends.windows(2).all(|w| w[0] < w[1]));
+        assert!(ends.iter().all(|&e| text.is_char_boundary(e)));
+        assert_eq!(ends.last().copied(), Some(text.len()));
+    }
+
+    #[test]
+    fn test_token_budget_validate() {
+        assert!(TokenBudget::new(100, 10).validate().is_ok());
+        assert!(TokenBudget::new(0, 0).validate().is_err());
+        assert!(TokenBudget::new(10, 10).validate().is_err());
+    }
+
+    #[test]
+    fn test_token_index_advance_and_retreat() {
+        let text = "one two six ten for";
+        let index = TokenIndex::new(text, &SimpleTokenizer);
+        assert_eq!(index.advance(0, 2), 7);
+        assert_eq!(index.advance(7, 2), 15);
+        assert_eq!(index.advance(15, 10), text.len());
+        assert_eq!(index.retreat(text.len(), 1), 15);
+        assert_eq!(index.retreat(7, 5), 0);
+    }
+
+    #[test]
+    fn test_chunk_by_tokens_respects_budget() {
+        let text = "The quick brown fox jumps over the lazy dog. ".repeat(40);
+        let budget = TokenBudget::new(25, 5);
+        for placement in [
+            OverlapPlacement::Leading,
+            OverlapPlacement::Trailing,
+            OverlapPlacement::Symmetric,
+        ] {
+            let chunks =
+                chunk_by_tokens(1, &text, "fixed", &budget, placement, 0, |_, t| t).unwrap();
+            assert!(chunks.len() > 1);
+            assert_eq!(chunks.first().unwrap().byte_range.start, 0);
+            assert_eq!(chunks.last().unwrap().byte_range.end, text.len());
+            for chunk in &chunks {
+                let tokens = SimpleTokenizer.count_tokens(&chunk.content);
+                assert!(tokens <= 25, "{placement:?}: {tokens} tokens");
+                assert_eq!(chunk.metadata.token_count, Some(tokens));
+            }
+        }
+    }
+
+    #[test]
+    fn test_chunk_by_tokens_custom_tokenizer() {
+        struct ByteTokenizer;
+        impl Tokenizer for ByteTokenizer {
+            fn token_ends(&self, text: &str) -> Vec<usize> {
+                text.char_indices().map(|(i, c)| i + c.len_utf8()).collect()
+            }
+            fn name(&self) -> &'static str {
+                "chars"
+            }
+        }
+
+        let budget = TokenBudget::new(10, 0).with_tokenizer(Arc::new(ByteTokenizer));
+        let chunks = chunk_by_tokens(
+            1,
+            "abcdefghijklmnopqrstuvwxy",
+            "fixed",
+            &budget,
+            OverlapPlacement::Leading,
+            0,
+            |_, t| t,
+        )
+        .unwrap();
+        let sizes: Vec<usize> = chunks.iter().map(Chunk::size).collect();
+        assert_eq!(sizes, vec![10, 10, 5]);
+    }
+}
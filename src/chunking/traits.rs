@@ -3,6 +3,7 @@
 //! Defines the interface for all chunking strategies, enabling
 //! pluggable text segmentation approaches.
 
+use crate::chunking::tokenizer::TokenBudget;
 use crate::core::Chunk;
 use crate::error::Result;
 
@@ -120,6 +121,44 @@ pub struct ChunkMetadata {
 
     /// Maximum chunks to produce (0 = unlimited).
     pub max_chunks: usize,
+
+    /// Where overlap text is placed relative to chunk boundaries.
+    pub overlap_placement: OverlapPlacement,
+
+    /// Token budget; when set, token-aware chunkers size chunks in tokens
+    /// instead of `chunk_size`/`overlap` characters.
+    pub token_budget: Option<TokenBudget>,
+}
+
+/// Placement of overlap text between consecutive chunks.
+///
+/// With `Leading` (the default), each chunk starts with the tail of the
+/// previous chunk. With `Trailing`, each chunk ends with the head of the
+/// next chunk. `Symmetric` splits the overlap evenly between both sides.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverlapPlacement {
+    /// Repeat the previous chunk's tail at the start of the next chunk.
+    #[default]
+    Leading,
+    /// Append the next chunk's head to the end of the previous chunk.
+    Trailing,
+    /// Split the overlap evenly between leading and trailing.
+    Symmetric,
+}
+
+impl OverlapPlacement {
+    /// Parses a placement name ("leading", "trailing", "symmetric").
+    ///
+    /// Returns `None` for unrecognized names.
+    #[must_use]
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "leading" => Some(Self::Leading),
+            "trailing" => Some(Self::Trailing),
+            "symmetric" => Some(Self::Symmetric),
+            _ => None,
+        }
+    }
 }
 
 impl ChunkMetadata {
@@ -189,6 +228,20 @@ impl ChunkMetadata {
         self.max_chunks = max;
         self
     }
+
+    /// Sets where overlap text is placed.
+    #[must_use]
+    pub const fn overlap_placement(mut self, placement: OverlapPlacement) -> Self {
+        self.overlap_placement = placement;
+        self
+    }
+
+    /// Sizes chunks in tokens using the given budget.
+    #[must_use]
+    pub fn token_budget(mut self, budget: TokenBudget) -> Self {
+        self.token_budget = Some(budget);
+        self
+    }
 }
 
 #[cfg(test)]
@@ -202,6 +255,25 @@ mod tests {
         assert_eq!(meta.overlap, super::super::DEFAULT_OVERLAP);
         assert!(meta.preserve_lines);
         assert!(!meta.preserve_sentences);
+        assert_eq!(meta.overlap_placement, OverlapPlacement::Leading);
+        assert!(meta.token_budget.is_none());
+    }
+
+    #[test]
+    fn test_overlap_placement_parse() {
+        assert_eq!(
+            OverlapPlacement::parse("leading"),
+            Some(OverlapPlacement::Leading)
+        );
+        assert_eq!(
+            OverlapPlacement::parse("TRAILING"),
+            Some(OverlapPlacement::Trailing)
+        );
+        assert_eq!(
+            OverlapPlacement::parse("symmetric"),
+            Some(OverlapPlacement::Symmetric)
+        );
+        assert_eq!(OverlapPlacement::parse("middle"), None);
     }
 
     #[test]
@@ -0,0 +1,345 @@
+//! Tree-sitter symbol boundaries for the code chunker.
+//!
+//! Parses Rust, Python, JavaScript, TypeScript, and Go sources and splits
+//! them at top-level definitions, so each chunk belongs to one function,
+//! class, or type. Only compiled with the `treesitter` feature.
+
+use crate::core::{Chunk, derive_title};
+use crate::io::unicode::{find_char_boundary, find_char_boundary_forward};
+use std::ops::Range;
+use tree_sitter::{Language, Node, Parser};
+
+/// Node kinds that define a top-level symbol named by their `name` field.
+const NAMED_DEFINITIONS: &[&str] = &[
+    // Rust
+    "function_item",
+    "struct_item",
+    "enum_item",
+    "union_item",
+    "trait_item",
+    "mod_item",
+    "type_item",
+    "macro_definition",
+    // Python
+    "function_definition",
+    "class_definition",
+    // JavaScript, TypeScript, and Go
+    "function_declaration",
+    "generator_function_declaration",
+    "class_declaration",
+    "abstract_class_declaration",
+    "interface_declaration",
+    "type_alias_declaration",
+    "enum_declaration",
+    "method_declaration",
+];
+
+/// A top-level span of source: a definition together with the imports and
+/// comments before it, or code outside any definition.
+struct Section {
+    range: Range<usize>,
+    /// Symbol name and the byte offset where its definition starts.
+    symbol: Option<(String, usize)>,
+}
+
+/// Returns the grammar and language name for a file extension.
+fn grammar(ext: &str) -> Option<(Language, &'static str)> {
+    let grammar: (Language, &'static str) = match ext.to_lowercase().as_str() {
+        "rs" => (tree_sitter_rust::LANGUAGE.into(), "rust"),
+        "py" | "pyw" | "pyi" => (tree_sitter_python::LANGUAGE.into(), "python"),
+        "js" | "mjs" | "cjs" | "jsx" => (tree_sitter_javascript::LANGUAGE.into(), "javascript"),
+        "ts" | "mts" | "cts" => (
+            tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            "typescript",
+        ),
+        "tsx" => (tree_sitter_typescript::LANGUAGE_TSX.into(), "typescript"),
+        "go" => (tree_sitter_go::LANGUAGE.into(), "go"),
+        _ => return None,
+    };
+    Some(grammar)
+}
+
+/// Chunks `text` at top-level definitions found by the grammar for `ext`.
+///
+/// Each chunk records the language and, unless it holds only code outside
+/// any definition, the enclosing symbol's name. A definition larger than
+/// `chunk_size` is split at line breaks into several chunks, each still
+/// tagged with the symbol.
+///
+/// Returns `None` when no grammar matches `ext` or the source has no
+/// top-level definitions, so the caller can fall back to pattern matching.
+pub fn chunk_symbols(
+    buffer_id: i64,
+    text: &str,
+    ext: &str,
+    chunk_size: usize,
+    overlap: usize,
+) -> Option<Vec<Chunk>> {
+    let (language, name) = grammar(ext)?;
+    let mut parser = Parser::new();
+    parser.set_language(&language).ok()?;
+    let tree = parser.parse(text, None)?;
+
+    let sections = sections(tree.root_node(), text, chunk_size);
+    if sections.iter().all(|section| section.symbol.is_none()) {
+        return None;
+    }
+
+    let mut chunks = Vec::new();
+    for section in sections {
+        let title = section
+            .symbol
+            .as_ref()
+            .and_then(|(_, start)| derive_title(&text[*start..section.range.end]));
+        for range in split_range(text, &section.range, chunk_size, overlap) {
+            if text[range.clone()].trim().is_empty() {
+                continue;
+            }
+            let index = chunks.len();
+            let mut chunk = Chunk::new(buffer_id, text[range.clone()].to_string(), range, index);
+            chunk.set_language(name);
+            if let Some((symbol, _)) = &section.symbol {
+                chunk.set_symbol(symbol);
+            }
+            if let Some(title) = &title {
+                chunk.set_title(title);
+            }
+            chunks.push(chunk);
+        }
+    }
+    Some(chunks)
+}
+
+/// Splits the source into sections at top-level definitions.
+///
+/// Code before a definition (imports, comments, constants) joins it unless
+/// that code alone exceeds `chunk_size`, in which case it becomes a section
+/// of its own. Code after the last definition joins it the same way.
+fn sections(root: Node<'_>, text: &str, chunk_size: usize) -> Vec<Section> {
+    let mut sections: Vec<Section> = Vec::new();
+    let mut start = 0;
+    let mut cursor = root.walk();
+
+    for node in root.named_children(&mut cursor) {
+        let Some(symbol) = symbol_name(node, text) else {
+            continue;
+        };
+        let line_start = text[..node.start_byte()]
+            .rfind('\n')
+            .map_or(0, |pos| pos + 1)
+            .max(start);
+        if line_start - start > chunk_size {
+            sections.push(Section {
+                range: start..line_start,
+                symbol: None,
+            });
+            start = line_start;
+        }
+        sections.push(Section {
+            range: start..node.end_byte(),
+            symbol: Some((symbol, node.start_byte())),
+        });
+        start = node.end_byte();
+    }
+
+    if start < text.len() {
+        match sections.last_mut() {
+            Some(last) if text.len() - start <= chunk_size => last.range.end = text.len(),
+            _ => sections.push(Section {
+                range: start..text.len(),
+                symbol: None,
+            }),
+        }
+    }
+    sections
+}
+
+/// Returns the name of the symbol a top-level node defines, if any.
+fn symbol_name(node: Node<'_>, text: &str) -> Option<String> {
+    let node_text = |n: Node<'_>| n.utf8_text(text.as_bytes()).ok().map(String::from);
+
+    match node.kind() {
+        // Wrappers around a definition: Python decorators, JS/TS `export`
+        "decorated_definition" => symbol_name(node.child_by_field_name("definition")?, text),
+        "export_statement" => symbol_name(node.child_by_field_name("declaration")?, text),
+        "impl_item" => node_text(node.child_by_field_name("type")?),
+        "type_declaration" => {
+            let mut cursor = node.walk();
+            let spec = node
+                .named_children(&mut cursor)
+                .find(|child| matches!(child.kind(), "type_spec" | "type_alias"))?;
+            node_text(spec.child_by_field_name("name")?)
+        }
+        // `const handler = () => ...` and `const handler = function () ...`
+        "lexical_declaration" | "variable_declaration" => {
+            let mut cursor = node.walk();
+            let declarator = node
+                .named_children(&mut cursor)
+                .find(|child| child.kind() == "variable_declarator")?;
+            let value = declarator.child_by_field_name("value")?;
+            if !matches!(
+                value.kind(),
+                "arrow_function" | "function_expression" | "function"
+            ) {
+                return None;
+            }
+            node_text(declarator.child_by_field_name("name")?)
+        }
+        kind if NAMED_DEFINITIONS.contains(&kind) => node_text(node.child_by_field_name("name")?),
+        _ => None,
+    }
+}
+
+/// Splits `range` into pieces of at most `chunk_size` bytes, ending at line
+/// breaks where possible.
+///
+/// Each piece after the first starts up to `overlap` bytes before the end
+/// of the previous one, moved forward to the next line start if there is
+/// one in that window.
+fn split_range(
+    text: &str,
+    range: &Range<usize>,
+    chunk_size: usize,
+    overlap: usize,
+) -> Vec<Range<usize>> {
+    let mut pieces = Vec::new();
+    let mut start = range.start;
+
+    while range.end - start > chunk_size {
+        let limit = find_char_boundary(text, start + chunk_size);
+        let end = match text[start..limit].rfind('\n') {
+            Some(newline) if newline > 0 => start + newline + 1,
+            _ if limit > start => limit,
+            _ => find_char_boundary_forward(text, start + 1),
+        };
+        pieces.push(start..end);
+
+        let target = find_char_boundary(text, end.saturating_sub(overlap));
+        start = if target <= start {
+            end
+        } else {
+            text[target..end]
+                .find('\n')
+                .map_or(target, |newline| target + newline + 1)
+        };
+    }
+    pieces.push(start..range.end);
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt::Write;
+
+    fn symbols(chunks: &[Chunk]) -> Vec<Option<String>> {
+        chunks.iter().map(Chunk::symbol).collect()
+    }
+
+    #[test]
+    fn test_chunk_symbols_rust() {
+        let code = "use std::fmt;\n\nfn alpha() {\n    let x = 1;\n}\n\nstruct Beta;\n\nimpl Beta {\n    fn new() -> Self {\n        Self\n    }\n}\n";
+        let chunks = chunk_symbols(1, code, "rs", 1000, 0).unwrap();
+
+        assert_eq!(
+            symbols(&chunks),
+            vec![
+                Some("alpha".to_string()),
+                Some("Beta".to_string()),
+                Some("Beta".to_string())
+            ]
+        );
+        // Imports join the definition that follows them
+        assert!(chunks[0].content.starts_with("use std::fmt;"));
+        assert_eq!(chunks[0].title(), Some("fn alpha()".to_string()));
+        assert_eq!(chunks[0].language(), Some("rust".to_string()));
+        assert_eq!(chunks.last().unwrap().byte_range.end, code.len());
+    }
+
+    #[test]
+    fn test_chunk_symbols_python_decorated() {
+        let code = "import os\n\n@cache\ndef load(path):\n    return open(path)\n\nclass Store:\n    def get(self):\n        pass\n";
+        let chunks = chunk_symbols(1, code, "py", 1000, 0).unwrap();
+
+        assert_eq!(
+            symbols(&chunks),
+            vec![Some("load".to_string()), Some("Store".to_string())]
+        );
+        assert_eq!(chunks[1].language(), Some("python".to_string()));
+    }
+
+    #[test]
+    fn test_chunk_symbols_javascript_and_typescript() {
+        let code = "export function greet(name) {\n  return name;\n}\n\nconst double = (x) => x * 2;\n\nclass Person {}\n";
+        let chunks = chunk_symbols(1, code, "js", 1000, 0).unwrap();
+        assert_eq!(
+            symbols(&chunks),
+            vec![
+                Some("greet".to_string()),
+                Some("double".to_string()),
+                Some("Person".to_string())
+            ]
+        );
+
+        let code = "interface Shape {\n  area(): number;\n}\n\ntype Id = string;\n";
+        let chunks = chunk_symbols(1, code, "ts", 1000, 0).unwrap();
+        assert_eq!(
+            symbols(&chunks),
+            vec![Some("Shape".to_string()), Some("Id".to_string())]
+        );
+        assert_eq!(chunks[0].language(), Some("typescript".to_string()));
+    }
+
+    #[test]
+    fn test_chunk_symbols_go() {
+        let code = "package main\n\ntype Server struct {\n\taddr string\n}\n\nfunc (s *Server) Run() error {\n\treturn nil\n}\n\nfunc main() {}\n";
+        let chunks = chunk_symbols(1, code, "go", 1000, 0).unwrap();
+        assert_eq!(
+            symbols(&chunks),
+            vec![
+                Some("Server".to_string()),
+                Some("Run".to_string()),
+                Some("main".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_chunk_symbols_splits_long_function() {
+        let body = (0..20).fold(String::new(), |mut body, i| {
+            let _ = writeln!(body, "    let v{i} = {i};");
+            body
+        });
+        let code = format!("fn short() {{}}\n\nfn long() {{\n{body}}}\n");
+        let chunks = chunk_symbols(1, &code, "rs", 120, 20).unwrap();
+
+        let long: Vec<_> = chunks
+            .iter()
+            .filter(|c| c.symbol().as_deref() == Some("long"))
+            .collect();
+        assert!(long.len() > 1);
+        for chunk in &long {
+            assert!(chunk.content.len() <= 120);
+            assert_eq!(chunk.title(), Some("fn long()".to_string()));
+        }
+        // Pieces overlap but leave no gaps
+        for pair in long.windows(2) {
+            assert!(pair[1].byte_range.start < pair[0].byte_range.end);
+        }
+        assert_eq!(long.last().unwrap().byte_range.end, code.len());
+    }
+
+    #[test]
+    fn test_chunk_symbols_falls_back() {
+        assert!(chunk_symbols(1, "fn main() {}\n", "java", 1000, 0).is_none());
+        assert!(chunk_symbols(1, "print('hi')\n", "py", 1000, 0).is_none());
+    }
+
+    #[test]
+    fn test_split_range_line_breaks() {
+        let text = "aaaa\nbbbb\ncccc\n";
+        assert_eq!(split_range(text, &(0..15), 11, 0), vec![0..10, 10..15]);
+        assert_eq!(split_range(text, &(0..15), 11, 6), vec![0..10, 5..15]);
+        assert_eq!(split_range(text, &(0..15), 100, 0), vec![0..15]);
+    }
+}
@@ -11,21 +11,121 @@
 #![allow(clippy::redundant_closure_for_method_calls)]
 #![allow(clippy::if_not_else)]
 
-use crate::chunking::{ChunkerMetadata, create_chunker};
+use crate::chunking::{
+    Chunker, ChunkerMetadata, DEFAULT_CHUNK_SIZE, DEFAULT_OVERLAP, TokenBudget, create_chunker,
+};
 use crate::cli::output::{
-    GrepMatch, OutputFormat, format_buffer, format_buffer_list, format_chunk_indices,
-    format_grep_matches, format_peek, format_status, format_write_chunks_result,
+    BUFFER_LIST_FIELDS, GrepMatch, OutputFormat, format_buffer, format_buffer_list,
+    format_buffer_list_null, format_buffer_versions, format_chunk_indices, format_context_buffers,
+    format_grep_matches, format_grep_null, format_ndjson, format_ndjson_records, format_peek,
+    format_status, format_storage_usage, format_trash_list, format_write_chunks_result,
+    null_records,
 };
 use crate::cli::parser::{ChunkCommands, Cli, Commands};
-use crate::core::{Buffer, Context, ContextValue};
-use crate::embedding::create_embedder;
-use crate::error::{CommandError, Result, StorageError};
-use crate::io::{read_file, write_file};
-use crate::search::{SearchConfig, SearchResult, embed_buffer_chunks, hybrid_search};
-use crate::storage::{SqliteStorage, Storage};
+use crate::core::{Buffer, Chunk, Context, ContextValue, content_hash, find_chunk_refs};
+use crate::corpus::{CorpusConfig, populate_corpus};
+use crate::embedding::{
+    DEFAULT_DIMENSIONS, DEFAULT_EMBED_BATCH_SIZE, Embedder, FallbackEmbedder, SimilarityMetric,
+    cosine_similarity, create_embedder_with_dimensions, euclidean_distance,
+};
+use crate::error::{CommandError, IoError, Result, StorageError};
+use crate::io::{
+    FileReader, LineEnding, buffer_name_from_url, create_extractor, decode_text, fetch_url,
+    glob_match, is_url, list_files, make_preview, read_file, relative_path_string, write_file,
+};
+use crate::search::{
+    DEFAULT_TOP_K, IvfRecall, SearchConfig, SearchDeadline, SearchResult, build_ivf_index,
+    embed_buffer_chunks_with_progress, embed_missing_chunks, estimate_ivf_recall, hybrid_search,
+};
+use crate::storage::{
+    CURRENT_SCHEMA_VERSION, ChunkDirection, EmbeddingCoverage, SqliteOptions, SqliteStorage,
+    Storage,
+};
+use clap::CommandFactory;
 use regex::RegexBuilder;
+use std::cell::{Cell, RefCell};
 use std::fmt::Write as FmtWrite;
 use std::io::{self, Read, Write as IoWrite};
+use std::sync::Arc;
+use std::time::Duration;
+
+thread_local! {
+    /// Connection options for the command being executed.
+    ///
+    /// Set by [`execute`] from global CLI flags so that every command opens
+    /// the database the same way without threading options through each
+    /// `cmd_*` signature.
+    static STORAGE_OPTIONS: RefCell<SqliteOptions> = RefCell::new(SqliteOptions::default());
+
+    /// Embedding batch size for the command being executed, set by
+    /// [`execute`] from `--embed-batch-size`.
+    static EMBED_BATCH_SIZE: Cell<usize> = const { Cell::new(DEFAULT_EMBED_BATCH_SIZE) };
+
+    /// Whether `--null` was given, set by [`execute`].
+    static NULL_SEPARATED: Cell<bool> = const { Cell::new(false) };
+
+    /// Whether to draw embedding progress on stderr, set by [`execute`]
+    /// from `--verbose` in text mode.
+    static SHOW_PROGRESS: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Opens (or creates) the database with the current connection options.
+fn open_database(db_path: &std::path::Path) -> Result<SqliteStorage> {
+    let options = STORAGE_OPTIONS.with(|current| current.borrow().clone());
+    SqliteStorage::open_with_options(db_path, &options)
+}
+
+/// Returns whether record listings should be NUL-separated.
+///
+/// Only applies to text output; JSON is already unambiguous.
+fn null_separated(format: OutputFormat) -> bool {
+    format == OutputFormat::Text && NULL_SEPARATED.with(Cell::get)
+}
+
+/// Returns the embedding batch size for the current command.
+fn embed_batch_size() -> usize {
+    EMBED_BATCH_SIZE.with(Cell::get)
+}
+
+/// Returns the embedding progress callback for the current command, if
+/// progress should be shown.
+fn embed_progress() -> Option<&'static dyn Fn(usize, usize)> {
+    SHOW_PROGRESS
+        .with(Cell::get)
+        .then_some(&draw_progress as &dyn Fn(usize, usize))
+}
+
+/// Width of the embedding progress bar, in characters.
+const PROGRESS_BAR_WIDTH: usize = 30;
+
+/// Draws an embedding progress bar on stderr, redrawing the line in place
+/// and ending it once `done` reaches `total`.
+fn draw_progress(done: usize, total: usize) {
+    let filled = (done.min(total) * PROGRESS_BAR_WIDTH)
+        .checked_div(total)
+        .unwrap_or(PROGRESS_BAR_WIDTH);
+    let mut stderr = io::stderr().lock();
+    let _ = write!(
+        stderr,
+        "\rEmbedding [{}{}] {done}/{total}",
+        "#".repeat(filled),
+        "-".repeat(PROGRESS_BAR_WIDTH - filled)
+    );
+    if done >= total {
+        let _ = writeln!(stderr);
+    }
+    let _ = stderr.flush();
+}
+
+/// Returns the `--embedding-dim` override for the current command.
+fn embedding_dimensions() -> Option<usize> {
+    STORAGE_OPTIONS.with(|current| current.borrow().embedding_dimensions)
+}
+
+/// Creates the embedder for the current command, honoring `--embedding-dim`.
+fn create_embedder() -> Result<Box<dyn Embedder>> {
+    create_embedder_with_dimensions(embedding_dimensions())
+}
 
 /// Executes the CLI command.
 ///
@@ -44,17 +144,39 @@ use std::io::{self, Read, Write as IoWrite};
 pub fn execute(cli: &Cli) -> Result<String> {
     let format = OutputFormat::parse(&cli.format);
     let db_path = cli.get_db_path();
+    let options = cli.storage_options()?;
+    STORAGE_OPTIONS.with(|current| *current.borrow_mut() = options);
+    EMBED_BATCH_SIZE.with(|current| current.set(cli.embed_batch_size));
+    NULL_SEPARATED.with(|current| current.set(cli.null));
+    SHOW_PROGRESS.with(|current| current.set(cli.verbose && format == OutputFormat::Text));
 
     match &cli.command {
         Commands::Init { force } => cmd_init(&db_path, *force, format),
-        Commands::Status => cmd_status(&db_path, format),
+        Commands::Status { detailed } => cmd_status(&db_path, *detailed, format),
+        Commands::Health { probe_embedder } => Ok(cmd_health(&db_path, *probe_embedder, format)),
+        Commands::Config { print, action } => cmd_config(cli, *print || action.is_some(), format),
+        Commands::Completions { shell } => cmd_completions(*shell),
         Commands::Reset { yes } => cmd_reset(&db_path, *yes, format),
+        Commands::Analyze => cmd_analyze(&db_path, format),
+        Commands::Compact => cmd_compact(&db_path, format),
+        Commands::Usage => cmd_usage(&db_path, format),
+        Commands::FixDimensions {
+            target_dim,
+            reembed,
+        } => cmd_fix_dimensions(&db_path, *target_dim, *reembed, format),
+        Commands::ReembedAll { model_check } => cmd_reembed_all(&db_path, *model_check, format),
+        Commands::DiffEmbeddings { a, b, top } => {
+            cmd_diff_embeddings(&db_path, *a, *b, *top, format)
+        }
         Commands::Load {
             file,
             name,
             chunker,
             chunk_size,
             overlap,
+            chunk_unit,
+            encoding,
+            normalize_whitespace,
         } => cmd_load(
             &db_path,
             file,
@@ -62,13 +184,84 @@ pub fn execute(cli: &Cli) -> Result<String> {
             chunker,
             *chunk_size,
             *overlap,
+            chunk_unit,
+            encoding.as_deref(),
+            *normalize_whitespace,
+            format,
+        ),
+        Commands::ImportDir {
+            dir,
+            glob,
+            chunker,
+            chunk_size,
+            overlap,
+            recursive,
+        } => cmd_import_dir(
+            &db_path,
+            dir,
+            glob.as_deref(),
+            (chunker, *chunk_size, *overlap),
+            *recursive,
             format,
         ),
-        Commands::ListBuffers => cmd_list_buffers(&db_path, format),
+        Commands::ExplainChunking {
+            file,
+            chunker,
+            chunk_size,
+            overlap,
+        } => cmd_explain_chunking(file, chunker, *chunk_size, *overlap, format),
+        Commands::ListBuffers {
+            tag,
+            sample,
+            seed,
+            fields,
+        } => cmd_list_buffers(&db_path, tag, sample.map(|n| (n, *seed)), fields, format),
         Commands::ShowBuffer { buffer, chunks } => {
             cmd_show_buffer(&db_path, buffer, *chunks, format)
         }
-        Commands::DeleteBuffer { buffer, yes } => cmd_delete_buffer(&db_path, buffer, *yes, format),
+        Commands::DeleteBuffer { buffer, yes, soft } => {
+            cmd_delete_buffer(&db_path, buffer, *yes, *soft, format)
+        }
+        Commands::ListTrash => cmd_list_trash(&db_path, format),
+        Commands::Restore { buffer } => cmd_restore(&db_path, buffer, format),
+        Commands::Purge { yes } => cmd_purge(&db_path, *yes, format),
+        Commands::Merge {
+            sources,
+            name,
+            chunker,
+            chunk_size,
+            overlap,
+            separator,
+            delete_sources,
+        } => cmd_merge(
+            &db_path,
+            sources,
+            name,
+            (chunker, *chunk_size, *overlap),
+            separator,
+            *delete_sources,
+            format,
+        ),
+        Commands::Diff { a, b } => cmd_diff(&db_path, a, b, format),
+        Commands::Rename { buffer, new_name } => cmd_rename(&db_path, buffer, new_name, format),
+        Commands::Tag {
+            buffer,
+            add,
+            remove,
+        } => cmd_tag(&db_path, buffer.as_deref(), add, remove, format),
+        Commands::BufferMeta {
+            buffer,
+            key,
+            value,
+            delete,
+        } => cmd_buffer_meta(
+            &db_path,
+            buffer,
+            key.as_deref(),
+            value.as_deref(),
+            *delete,
+            format,
+        ),
         Commands::Peek { buffer, start, end } => cmd_peek(&db_path, buffer, *start, *end, format),
         Commands::Grep {
             buffer,
@@ -112,51 +305,124 @@ pub fn execute(cli: &Cli) -> Result<String> {
             buffer,
             content,
             embed,
+            no_embed_on_update,
             strategy,
             chunk_size,
             overlap,
+            history,
+            keep_versions,
         } => cmd_update_buffer(
             &db_path,
             buffer,
             content.as_deref(),
-            *embed,
-            strategy,
-            *chunk_size,
-            *overlap,
+            *embed && !*no_embed_on_update,
+            (strategy, *chunk_size, *overlap),
+            history.then_some(*keep_versions),
             format,
         ),
+        Commands::History { buffer } => cmd_history(&db_path, buffer, format),
+        Commands::RevertBuffer {
+            buffer,
+            version,
+            embed,
+            keep_versions,
+        } => cmd_revert_buffer(&db_path, buffer, *version, *embed, *keep_versions, format),
         Commands::ExportBuffers { output, pretty } => {
             cmd_export_buffers(&db_path, output.as_deref(), *pretty, format)
         }
+        Commands::ImportBuffers {
+            input,
+            merge,
+            chunk,
+            embed,
+            strategy,
+            chunk_size,
+            overlap,
+        } => {
+            let chunking = (strategy.as_str(), *chunk_size, *overlap);
+            cmd_import_buffers(&db_path, input, *merge, *chunk, chunking, *embed, format)
+        }
         Commands::Variable {
             name,
             value,
+            value_type,
             delete,
-        } => cmd_variable(&db_path, name, value.as_deref(), *delete, format),
+        } => {
+            let value = value
+                .as_deref()
+                .map(|v| ContextValueType::parse(value_type)?.value(v))
+                .transpose()?;
+            cmd_variable(&db_path, name, value, *delete, format)
+        }
         Commands::Global {
             name,
             value,
+            value_type,
             delete,
-        } => cmd_global(&db_path, name, value.as_deref(), *delete, format),
+        } => {
+            let value = value
+                .as_deref()
+                .map(|v| ContextValueType::parse(value_type)?.value(v))
+                .transpose()?;
+            cmd_global(&db_path, name, value, *delete, format)
+        }
+        Commands::ContextBuffers => cmd_context_buffers(&db_path, format),
+        Commands::ContextPrune => cmd_context_prune(&db_path, format),
         Commands::Search {
             query,
             top_k,
+            offset,
             threshold,
             mode,
+            raw_query,
             rrf_k,
+            semantic_weight,
+            bm25_weight,
             buffer,
             preview,
             preview_len,
+            highlight,
+            title_boost,
+            buffer_type,
+            min_results,
+            diversity,
+            tag,
+            buffer_meta,
+            auto_embed,
+            ids_only,
+            no_cache,
+            nprobe,
+            metric,
+            timeout_ms,
         } => cmd_search(
             &db_path,
             query,
             *top_k,
+            *offset,
             *threshold,
             mode,
             *rrf_k,
+            *semantic_weight,
+            *bm25_weight,
             buffer.as_deref(),
-            *preview,
             *preview_len,
+            *title_boost,
+            buffer_type.as_deref(),
+            *min_results,
+            *diversity,
+            tag,
+            buffer_meta,
+            SearchFlags {
+                raw_query: *raw_query,
+                preview: *preview,
+                highlight: *highlight,
+                auto_embed: *auto_embed,
+                ids_only: *ids_only,
+                no_cache: *no_cache,
+            },
+            *nprobe,
+            metric,
+            *timeout_ms,
             format,
         ),
         Commands::Aggregate {
@@ -165,6 +431,9 @@ pub fn execute(cli: &Cli) -> Result<String> {
             group_by,
             sort_by,
             output_buffer,
+            explain,
+            dedup_threshold,
+            chunk_findings,
         } => cmd_aggregate(
             &db_path,
             buffer.as_deref(),
@@ -172,6 +441,9 @@ pub fn execute(cli: &Cli) -> Result<String> {
             group_by,
             sort_by,
             output_buffer.as_deref(),
+            *explain,
+            *dedup_threshold,
+            *chunk_findings,
             format,
         ),
         Commands::Dispatch {
@@ -192,33 +464,179 @@ pub fn execute(cli: &Cli) -> Result<String> {
             format,
         ),
         Commands::Chunk(chunk_cmd) => match chunk_cmd {
-            ChunkCommands::Get { id, metadata } => cmd_chunk_get(&db_path, *id, *metadata, format),
+            ChunkCommands::Get {
+                id,
+                metadata,
+                next,
+                prev,
+                context,
+                resolve_refs,
+                stream: _,
+                verify_hash,
+                wrap,
+            } => {
+                let wrap = ChunkWrap::parse(wrap)?;
+                if let Some(radius) = context {
+                    return cmd_chunk_context(&db_path, *id, *radius, wrap, format);
+                }
+                let direction = if *next {
+                    Some(ChunkDirection::Next)
+                } else if *prev {
+                    Some(ChunkDirection::Prev)
+                } else {
+                    None
+                };
+                cmd_chunk_get(
+                    &db_path,
+                    *id,
+                    *metadata,
+                    direction,
+                    *verify_hash,
+                    *resolve_refs,
+                    wrap,
+                    format,
+                )
+            }
             ChunkCommands::List {
                 buffer,
+                all,
+                limit,
+                offset,
                 preview,
                 preview_len,
-            } => cmd_chunk_list(&db_path, buffer, *preview, *preview_len, format),
-            ChunkCommands::Embed { buffer, force } => {
-                cmd_chunk_embed(&db_path, buffer, *force, format)
+                verify_hash,
+                sample,
+                seed,
+            } => {
+                let page = (*limit, *offset);
+                let preview = (*preview, *preview_len);
+                let sample = sample.map(|n| (n, *seed));
+                match buffer.as_deref() {
+                    Some(buffer) if !*all => cmd_chunk_list(
+                        &db_path,
+                        buffer,
+                        page,
+                        preview,
+                        *verify_hash,
+                        sample,
+                        format,
+                    ),
+                    _ => cmd_chunk_list_all(&db_path, page, preview, *verify_hash, sample, format),
+                }
+            }
+            ChunkCommands::Embed {
+                buffer,
+                force,
+                older_than,
+            } => cmd_chunk_embed(&db_path, buffer, *force, older_than.as_deref(), format),
+            ChunkCommands::ReembedMissing { buffer, force } => {
+                cmd_chunk_reembed_missing(&db_path, buffer.as_deref(), *force, format)
             }
+            ChunkCommands::BuildIvf {
+                clusters,
+                iterations,
+                nprobe,
+                samples,
+                seed,
+            } => cmd_chunk_build_ivf(
+                &db_path,
+                *clusters,
+                *iterations,
+                (*nprobe, *samples),
+                *seed,
+                format,
+            ),
+            ChunkCommands::IvfRecall {
+                nprobe,
+                top_k,
+                samples,
+                seed,
+            } => cmd_chunk_ivf_recall(&db_path, *nprobe, *top_k, *samples, *seed, format),
             ChunkCommands::Status => cmd_chunk_status(&db_path, format),
         },
+        Commands::GenCorpus {
+            buffers,
+            chunks_per_buffer,
+            seed,
+            embed,
+        } => cmd_gen_corpus(
+            &db_path,
+            *buffers,
+            *chunks_per_buffer,
+            *seed,
+            *embed,
+            format,
+        ),
+    }
+}
+
+/// Executes the CLI command, writing output to `out`.
+///
+/// Streaming commands (`chunk get --stream` in text format) copy content
+/// from the database to `out` incrementally; all others write the output
+/// of [`execute`]. A closed pipe (e.g. `| head`) is treated as success.
+///
+/// # Errors
+///
+/// Returns an error if the command fails or output cannot be written.
+pub fn execute_to(cli: &Cli, out: &mut dyn IoWrite) -> Result<()> {
+    if let Commands::Chunk(ChunkCommands::Get {
+        id, stream: true, ..
+    }) = &cli.command
+        && OutputFormat::parse(&cli.format) == OutputFormat::Text
+    {
+        let options = cli.storage_options()?;
+        STORAGE_OPTIONS.with(|current| *current.borrow_mut() = options);
+        return cmd_chunk_stream(&cli.get_db_path(), *id, out);
+    }
+
+    let output = execute(cli)?;
+    ignore_broken_pipe(out.write_all(output.as_bytes()).and_then(|()| out.flush()))
+}
+
+/// Maps a write result to the crate error type, treating a closed pipe as success.
+fn ignore_broken_pipe(result: io::Result<()>) -> Result<()> {
+    match result {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => Err(IoError::WriteFailed {
+            path: "stdout".to_string(),
+            reason: e.to_string(),
+        }
+        .into()),
+        _ => Ok(()),
     }
 }
 
 /// Opens storage and ensures it's initialized.
 fn open_storage(db_path: &std::path::Path) -> Result<SqliteStorage> {
-    let storage = SqliteStorage::open(db_path)?;
+    let mut storage = open_database(db_path)?;
 
     if !storage.is_initialized()? {
         return Err(StorageError::NotInitialized.into());
     }
 
+    // Bring databases created by older versions up to the current schema.
+    // This writes even for read-only commands; migrations are all-or-nothing
+    storage.init()?;
+
     Ok(storage)
 }
 
 /// Resolves a buffer identifier (ID or name) to a buffer.
+///
+/// Buffers in the trash are rejected; see [`find_buffer`].
 fn resolve_buffer(storage: &SqliteStorage, identifier: &str) -> Result<Buffer> {
+    let buffer = find_buffer(storage, identifier)?;
+    if buffer.is_deleted() {
+        return Err(CommandError::ExecutionFailed(format!(
+            "buffer '{identifier}' is in the trash; run 'rlm-rs restore {identifier}' first"
+        ))
+        .into());
+    }
+    Ok(buffer)
+}
+
+/// Resolves a buffer by ID or name, including buffers in the trash.
+fn find_buffer(storage: &SqliteStorage, identifier: &str) -> Result<Buffer> {
     // Try as ID first
     if let Ok(id) = identifier.parse::<i64>()
         && let Some(buffer) = storage.get_buffer(id)?
@@ -264,7 +682,7 @@ fn cmd_init(db_path: &std::path::Path, force: bool, _format: OutputFormat) -> Re
         })?;
     }
 
-    let mut storage = SqliteStorage::open(db_path)?;
+    let mut storage = open_database(db_path)?;
     storage.init()?;
 
     // Initialize empty context
@@ -277,159 +695,1811 @@ fn cmd_init(db_path: &std::path::Path, force: bool, _format: OutputFormat) -> Re
     ))
 }
 
-fn cmd_status(db_path: &std::path::Path, format: OutputFormat) -> Result<String> {
+fn cmd_status(db_path: &std::path::Path, detailed: bool, format: OutputFormat) -> Result<String> {
     let storage = open_storage(db_path)?;
     let stats = storage.stats()?;
-    Ok(format_status(&stats, format))
+    let details = if detailed {
+        Some(storage.detailed_stats()?)
+    } else {
+        None
+    };
+    Ok(format_status(&stats, details.as_ref(), format))
 }
 
-fn cmd_reset(db_path: &std::path::Path, yes: bool, _format: OutputFormat) -> Result<String> {
-    if !yes {
-        // In a real implementation, we'd prompt the user
-        // For now, require --yes flag
-        return Err(CommandError::ExecutionFailed(
-            "Use --yes to confirm reset. This will delete all data.".to_string(),
-        )
-        .into());
-    }
-
-    let mut storage = open_storage(db_path)?;
-    storage.reset()?;
-
-    // Reinitialize with empty context
-    let context = Context::new();
-    storage.save_context(&context)?;
-
-    Ok("RLM state reset successfully.\n".to_string())
-}
+/// Order in which `health` reports its checks.
+const HEALTH_CHECKS: [&str; 5] = ["database", "initialized", "schema", "fts5", "embedder"];
 
-fn cmd_load(
-    db_path: &std::path::Path,
-    file: &std::path::Path,
-    name: Option<&str>,
-    chunker_name: &str,
-    chunk_size: usize,
-    overlap: usize,
-    format: OutputFormat,
-) -> Result<String> {
-    let mut storage = open_storage(db_path)?;
+/// Runs every readiness check; failures are reported, not returned.
+#[allow(clippy::too_many_lines)]
+fn cmd_health(db_path: &std::path::Path, probe_embedder: bool, format: OutputFormat) -> String {
+    // A failed check carries its error; a passed one its details
+    let check = |result: Result<serde_json::Value>| match result {
+        Ok(mut details) => {
+            details["ok"] = serde_json::Value::Bool(true);
+            details
+        }
+        Err(e) => serde_json::json!({ "ok": false, "error": e.to_string() }),
+    };
+    let mut checks = serde_json::Map::new();
 
-    // Read file content
-    let content = read_file(file)?;
+    // Opening a missing path would create the database, so check first
+    let storage = if db_path.exists() {
+        open_database(db_path)
+    } else {
+        Err(
+            CommandError::ExecutionFailed("database not found; run 'rlm-rs init'".to_string())
+                .into(),
+        )
+    };
+    let path = db_path.to_string_lossy();
+    let (storage, database) = match storage {
+        Ok(storage) => (Some(storage), Ok(serde_json::json!({ "path": path }))),
+        Err(e) => (None, Err(e)),
+    };
+    checks.insert("database".to_string(), check(database));
 
-    // Create buffer
-    let buffer_name = name
-        .map(String::from)
-        .or_else(|| file.file_name().and_then(|n| n.to_str()).map(String::from));
+    let skipped = |reason: &str| -> Result<serde_json::Value> {
+        Err(CommandError::ExecutionFailed(reason.to_string()).into())
+    };
+    let initialized = match &storage {
+        Some(storage) => storage.is_initialized().and_then(|initialized| {
+            if initialized {
+                Ok(serde_json::json!({}))
+            } else {
+                skipped("schema not created; run 'rlm-rs init'")
+            }
+        }),
+        None => skipped("database unavailable"),
+    };
+    let is_initialized = initialized.is_ok();
+    checks.insert("initialized".to_string(), check(initialized));
+    checks.insert(
+        "schema".to_string(),
+        check(match &storage {
+            Some(storage) if is_initialized => storage.schema_version().and_then(|version| {
+                let details = serde_json::json!({
+                    "version": version,
+                    "expected": CURRENT_SCHEMA_VERSION,
+                });
+                if version == Some(CURRENT_SCHEMA_VERSION) {
+                    Ok(details)
+                } else {
+                    Err(CommandError::ExecutionFailed(format!(
+                        "schema version {} is not the expected {CURRENT_SCHEMA_VERSION}; run 'rlm-rs init' to migrate",
+                        version.map_or_else(|| "unknown".to_string(), |v| v.to_string())
+                    ))
+                    .into())
+                }
+            }),
+            _ => skipped("database not initialized"),
+        }),
+    );
+
+    // FTS5 support belongs to the linked SQLite, so probe without a database if needed
+    let fts5 = match &storage {
+        Some(storage) => storage.probe_fts5(),
+        None => SqliteStorage::in_memory().and_then(|storage| storage.probe_fts5()),
+    };
+    checks.insert(
+        "fts5".to_string(),
+        check(fts5.map(|()| serde_json::json!({}))),
+    );
+
+    let embedder = create_embedder().and_then(|embedder| {
+        let mut details = serde_json::json!({
+            "model": embedder.model_name(),
+            "dimensions": embedder.dimensions(),
+        });
+        if probe_embedder {
+            let embedding = embedder.embed("rlm-rs health check")?;
+            if embedding.len() != embedder.dimensions() {
+                return Err(CommandError::ExecutionFailed(format!(
+                    "embedder returned {} dimensions, expected {}",
+                    embedding.len(),
+                    embedder.dimensions()
+                ))
+                .into());
+            }
+            details["probed"] = serde_json::Value::Bool(true);
+        }
+        Ok(details)
+    });
+    checks.insert("embedder".to_string(), check(embedder));
+
+    // Informational only; a database too broken to count already failed a check
+    let counts = storage
+        .as_ref()
+        .filter(|_| is_initialized)
+        .and_then(|storage| {
+            let stats = storage.stats().ok()?;
+            Some(serde_json::json!({
+                "buffers": stats.buffer_count,
+                "chunks": stats.chunk_count,
+                "embeddings": storage.embedding_count().ok()?,
+            }))
+        });
+    let passed = |c: &serde_json::Value| c["ok"].as_bool() == Some(true);
+    let ok = checks.values().all(passed);
 
-    let mut buffer = Buffer::from_file(file.to_path_buf(), content.clone());
-    buffer.name = buffer_name;
-    buffer.compute_hash();
+    match format {
+        OutputFormat::Text => {
+            let mut output = format!("Health: {}\n", if ok { "OK" } else { "FAILED" });
+            for name in HEALTH_CHECKS {
+                let c = &checks[name];
+                let detail = match (name, passed(c)) {
+                    (_, false) => c["error"].as_str().unwrap_or_default().to_string(),
+                    ("database", true) => c["path"].as_str().unwrap_or_default().to_string(),
+                    ("schema", true) => format!("v{}", c["version"]),
+                    ("embedder", true) => format!(
+                        "{}, {} dimensions",
+                        c["model"].as_str().unwrap_or_default(),
+                        c["dimensions"]
+                    ),
+                    _ => String::new(),
+                };
+                let status = if passed(c) { "ok" } else { "FAIL" };
+                let _ = writeln!(output, "  {name:<12} {status:<5} {detail}");
+            }
+            if let Some(counts) = &counts {
+                let _ = writeln!(
+                    output,
+                    "Buffers: {}, chunks: {}, embeddings: {}",
+                    counts["buffers"], counts["chunks"], counts["embeddings"]
+                );
+            }
+            output
+        }
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            let json = serde_json::json!({
+                "ok": ok,
+                "checks": checks,
+                "counts": counts,
+            });
+            serde_json::to_string_pretty(&json).unwrap_or_default()
+        }
+    }
+}
 
-    // Add buffer to storage
-    let buffer_id = storage.add_buffer(&buffer)?;
+/// Environment variables that select or configure the embedding backend.
+const EMBEDDER_ENV_VARS: [&str; 5] = [
+    "OPENAI_API_KEY",
+    "OPENAI_BASE_URL",
+    "RLM_OPENAI_MODEL",
+    "RLM_OLLAMA_URL",
+    "RLM_OLLAMA_MODEL",
+];
+
+/// Returns where a global setting's value came from: `flag`, `env`, or
+/// `default`.
+///
+/// Clap applies a flag over its environment variable over the default but
+/// doesn't record which one won, so the value is compared with each; a
+/// flag repeating the environment's value is reported as `env`.
+fn setting_source<T>(value: Option<&T>, env: Option<&str>, default: Option<&T>) -> &'static str
+where
+    T: PartialEq + std::str::FromStr,
+{
+    let Some(value) = value else {
+        return "default";
+    };
+    let from_env = env
+        .and_then(|var| std::env::var(var).ok())
+        .and_then(|raw| raw.parse::<T>().ok());
+    if from_env.as_ref() == Some(value) {
+        "env"
+    } else if default == Some(value) {
+        "default"
+    } else {
+        "flag"
+    }
+}
 
-    // Chunk the content
-    let chunker = create_chunker(chunker_name)?;
-    let meta = ChunkerMetadata::with_size_and_overlap(chunk_size, overlap);
-    let chunks = chunker.chunk(buffer_id, &content, Some(&meta))?;
+/// Like [`setting_source`], but reports values matching the config file's as `config`.
+fn configured_source<T>(
+    value: &T,
+    config: Option<&T>,
+    env: Option<&str>,
+    default: Option<&T>,
+) -> &'static str
+where
+    T: PartialEq + std::str::FromStr,
+{
+    match setting_source(Some(value), env, default) {
+        "env" => "env",
+        _ if config == Some(value) => "config",
+        source => source,
+    }
+}
 
-    // Store chunks
-    storage.add_chunks(buffer_id, &chunks)?;
+/// A `config show` row for a setting only the config file can change.
+fn config_default<T>(
+    name: &'static str,
+    configured: Option<T>,
+    default: T,
+) -> (&'static str, serde_json::Value, &'static str)
+where
+    serde_json::Value: From<T>,
+{
+    configured.map_or_else(
+        || (name, default.into(), "default"),
+        |value| (name, value.into(), "config"),
+    )
+}
 
-    // Generate embeddings for semantic search (automatic during load)
-    let embedder = create_embedder()?;
-    let embedded_count = embed_buffer_chunks(&mut storage, embedder.as_ref(), buffer_id)?;
+#[allow(clippy::too_many_lines)]
+fn cmd_config(cli: &Cli, print: bool, format: OutputFormat) -> Result<String> {
+    if !print {
+        return Err(CommandError::InvalidArgument(
+            "nothing to do; use `config show` to show the configuration".to_string(),
+        )
+        .into());
+    }
 
-    // Update buffer with chunk count
-    let mut updated_buffer =
-        storage
-            .get_buffer(buffer_id)?
-            .ok_or_else(|| StorageError::BufferNotFound {
-                identifier: buffer_id.to_string(),
-            })?;
-    updated_buffer.set_chunk_count(chunks.len());
-    storage.update_buffer(&updated_buffer)?;
+    let config = &cli.config;
+    let auto = || serde_json::Value::from("auto");
+    let embedder_source = if EMBEDDER_ENV_VARS
+        .iter()
+        .any(|var| std::env::var_os(var).is_some_and(|value| !value.is_empty()))
+    {
+        "env"
+    } else {
+        "default"
+    };
+    let (model, dimensions) = match create_embedder() {
+        Ok(embedder) => (
+            serde_json::json!(embedder.model_name()),
+            serde_json::json!(embedder.dimensions()),
+        ),
+        Err(e) => (
+            serde_json::json!(format!("unavailable ({e})")),
+            serde_json::Value::Null,
+        ),
+    };
 
-    // Update context
-    if let Some(mut context) = storage.load_context()? {
-        context.add_buffer(buffer_id);
-        storage.save_context(&context)?;
-    }
+    let settings: Vec<(&str, serde_json::Value, &str)> = vec![
+        (
+            "config_file",
+            config
+                .path
+                .as_ref()
+                .map_or_else(|| serde_json::json!("none"), |path| serde_json::json!(path)),
+            if config.path.is_some() {
+                "config"
+            } else {
+                "default"
+            },
+        ),
+        (
+            "db_path",
+            serde_json::json!(cli.get_db_path().display().to_string()),
+            if cli.db_path.is_none() && config.db_path.is_some() {
+                "config"
+            } else {
+                setting_source(cli.db_path.as_ref(), Some("RLM_DB_PATH"), None)
+            },
+        ),
+        (
+            "format",
+            serde_json::json!(cli.format),
+            configured_source(
+                &cli.format,
+                config.format.as_ref(),
+                None,
+                Some(&"text".to_string()),
+            ),
+        ),
+        (
+            "journal",
+            serde_json::json!(cli.journal),
+            setting_source(
+                Some(&cli.journal),
+                Some("RLM_JOURNAL_MODE"),
+                Some(&"wal".to_string()),
+            ),
+        ),
+        (
+            "cache_size_kib",
+            cli.cache_size.map_or_else(auto, Into::into),
+            setting_source(cli.cache_size.as_ref(), Some("RLM_CACHE_SIZE"), None),
+        ),
+        (
+            "mmap_size",
+            cli.mmap_size.map_or_else(auto, Into::into),
+            setting_source(cli.mmap_size.as_ref(), Some("RLM_MMAP_SIZE"), None),
+        ),
+        (
+            "embed_batch_size",
+            serde_json::json!(cli.embed_batch_size),
+            setting_source(
+                Some(&cli.embed_batch_size),
+                Some("RLM_EMBED_BATCH_SIZE"),
+                Some(&DEFAULT_EMBED_BATCH_SIZE),
+            ),
+        ),
+        (
+            "threads",
+            serde_json::json!(rayon::current_num_threads()),
+            setting_source(cli.threads.as_ref(), Some("RLM_THREADS"), None),
+        ),
+        (
+            "embedding_dim",
+            cli.embedding_dim.map_or_else(auto, Into::into),
+            setting_source(cli.embedding_dim.as_ref(), Some("RLM_EMBEDDING_DIM"), None),
+        ),
+        ("model", model, embedder_source),
+        ("model_dimensions", dimensions, embedder_source),
+        config_default("chunker", config.chunker.as_deref(), "semantic"),
+        config_default("chunk_size", config.chunk_size, DEFAULT_CHUNK_SIZE),
+        config_default("overlap", config.overlap, DEFAULT_OVERLAP),
+        config_default("search_mode", config.search_mode.as_deref(), "hybrid"),
+    ];
 
     match format {
-        OutputFormat::Text => Ok(format!(
-            "Loaded buffer {} (ID: {}) with {} chunks ({} embedded) from {}\n",
-            updated_buffer.name.as_deref().unwrap_or("unnamed"),
-            buffer_id,
-            chunks.len(),
-            embedded_count,
-            file.display()
-        )),
+        OutputFormat::Text => {
+            let mut output = String::from("Configuration\n=============\n\n");
+            for (name, value, source) in &settings {
+                let value = value
+                    .as_str()
+                    .map_or_else(|| value.to_string(), ToString::to_string);
+                let _ = writeln!(output, "{name:<18} {value:<30} {source}");
+            }
+            Ok(output)
+        }
         OutputFormat::Json | OutputFormat::Ndjson => {
-            let result = serde_json::json!({
-                "buffer_id": buffer_id,
-                "name": updated_buffer.name,
-                "chunk_count": chunks.len(),
-                "embedded_count": embedded_count,
-                "size": content.len(),
-                "source": file.to_string_lossy()
-            });
-            Ok(serde_json::to_string_pretty(&result).unwrap_or_default())
+            let json: serde_json::Map<String, serde_json::Value> = settings
+                .into_iter()
+                .map(|(name, value, source)| {
+                    (
+                        name.to_string(),
+                        serde_json::json!({ "value": value, "source": source }),
+                    )
+                })
+                .collect();
+            Ok(serde_json::to_string_pretty(&json).unwrap_or_default())
         }
     }
 }
 
-fn cmd_list_buffers(db_path: &std::path::Path, format: OutputFormat) -> Result<String> {
-    let storage = open_storage(db_path)?;
-    let buffers = storage.list_buffers()?;
-    Ok(format_buffer_list(&buffers, format))
+/// Generates a completion script for `shell`; the output format doesn't apply.
+fn cmd_completions(shell: clap_complete::Shell) -> Result<String> {
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut command, name, &mut script);
+    String::from_utf8(script).map_err(|e| {
+        CommandError::ExecutionFailed(format!("completion script is not UTF-8: {e}")).into()
+    })
 }
 
-fn cmd_show_buffer(
+/// Loads a chunk's embedding, failing if the chunk or its embedding is missing.
+fn chunk_embedding(storage: &SqliteStorage, chunk_id: i64) -> Result<Vec<f32>> {
+    if storage.get_chunk(chunk_id)?.is_none() {
+        return Err(StorageError::ChunkNotFound { id: chunk_id }.into());
+    }
+    storage.get_embedding(chunk_id)?.ok_or_else(|| {
+        CommandError::ExecutionFailed(format!(
+            "chunk {chunk_id} has no embedding; run `chunk embed` on its buffer first"
+        ))
+        .into()
+    })
+}
+
+fn cmd_diff_embeddings(
     db_path: &std::path::Path,
-    identifier: &str,
-    show_chunks: bool,
+    a: i64,
+    b: i64,
+    top: usize,
     format: OutputFormat,
 ) -> Result<String> {
     let storage = open_storage(db_path)?;
-    let buffer = resolve_buffer(&storage, identifier)?;
+    let vector_a = chunk_embedding(&storage, a)?;
+    let vector_b = chunk_embedding(&storage, b)?;
+    if vector_a.len() != vector_b.len() {
+        return Err(CommandError::ExecutionFailed(format!(
+            "embedding dimensions differ: chunk {a} has {}, chunk {b} has {}",
+            vector_a.len(),
+            vector_b.len()
+        ))
+        .into());
+    }
 
-    let chunks = if show_chunks {
-        Some(storage.get_chunks(buffer.id.unwrap_or(0))?)
-    } else {
-        None
-    };
+    let cosine = cosine_similarity(&vector_a, &vector_b);
+    let distance = euclidean_distance(&vector_a, &vector_b);
+    let squared_distance = distance * distance;
 
-    Ok(format_buffer(&buffer, chunks.as_deref(), format))
-}
+    // Largest absolute differences first; ties keep dimension order
+    let mut dimensions: Vec<(usize, f32, f32)> = vector_a
+        .iter()
+        .zip(&vector_b)
+        .enumerate()
+        .map(|(dimension, (x, y))| (dimension, *x, *y))
+        .collect();
+    dimensions.sort_by(|l, r| (r.1 - r.2).abs().total_cmp(&(l.1 - l.2).abs()));
+    dimensions.truncate(top);
+    // Share of the squared distance each dimension accounts for
+    let share = |x: f32, y: f32| {
+        if squared_distance > 0.0 {
+            (x - y) * (x - y) / squared_distance
+        } else {
+            0.0
+        }
+    };
 
-fn cmd_delete_buffer(
-    db_path: &std::path::Path,
-    identifier: &str,
-    yes: bool,
+    match format {
+        OutputFormat::Text => {
+            let mut output = format!("Chunk {a} vs chunk {b} ({} dimensions)\n\n", vector_a.len());
+            let _ = writeln!(output, "Cosine similarity:  {cosine:.6}");
+            let _ = writeln!(output, "Euclidean distance: {distance:.6}");
+            if !dimensions.is_empty() {
+                let _ = writeln!(
+                    output,
+                    "\nTop {} dimensions by difference:",
+                    dimensions.len()
+                );
+                let _ = writeln!(
+                    output,
+                    "{:>9}  {:>10}  {:>10}  {:>10}  {:>6}",
+                    "dimension", "a", "b", "a - b", "share"
+                );
+                for (dimension, x, y) in &dimensions {
+                    let _ = writeln!(
+                        output,
+                        "{dimension:>9}  {x:>10.6}  {y:>10.6}  {:>10.6}  {:>5.1}%",
+                        x - y,
+                        share(*x, *y) * 100.0
+                    );
+                }
+            }
+            Ok(output)
+        }
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            let top_dimensions: Vec<serde_json::Value> = dimensions
+                .iter()
+                .map(|(dimension, x, y)| {
+                    serde_json::json!({
+                        "dimension": dimension,
+                        "a": x,
+                        "b": y,
+                        "difference": x - y,
+                        "share": share(*x, *y),
+                    })
+                })
+                .collect();
+            let json = serde_json::json!({
+                "a": a,
+                "b": b,
+                "dimensions": vector_a.len(),
+                "cosine_similarity": cosine,
+                "euclidean_distance": distance,
+                "top_dimensions": top_dimensions,
+            });
+            Ok(serde_json::to_string_pretty(&json).unwrap_or_default())
+        }
+    }
+}
+
+fn cmd_reset(db_path: &std::path::Path, yes: bool, _format: OutputFormat) -> Result<String> {
+    if !yes {
+        // In a real implementation, we'd prompt the user
+        // For now, require --yes flag
+        return Err(CommandError::ExecutionFailed(
+            "Use --yes to confirm reset. This will delete all data.".to_string(),
+        )
+        .into());
+    }
+
+    let mut storage = open_storage(db_path)?;
+    storage.reset()?;
+
+    // Reinitialize with empty context
+    let context = Context::new();
+    storage.save_context(&context)?;
+
+    Ok("RLM state reset successfully.\n".to_string())
+}
+
+fn cmd_analyze(db_path: &std::path::Path, format: OutputFormat) -> Result<String> {
+    let storage = open_storage(db_path)?;
+    let analyzed = storage.analyze()?;
+
+    match format {
+        OutputFormat::Text => Ok(format!(
+            "Analyzed database: refreshed planner statistics for {analyzed} tables/indexes\n"
+        )),
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            let json = serde_json::json!({
+                "analyzed": true,
+                "statistics_count": analyzed
+            });
+            Ok(serde_json::to_string_pretty(&json).unwrap_or_default())
+        }
+    }
+}
+
+fn cmd_compact(db_path: &std::path::Path, format: OutputFormat) -> Result<String> {
+    let mut storage = open_storage(db_path)?;
+    let bytes_before = storage.stats()?.db_size.unwrap_or(0);
+    storage.compact()?;
+    let bytes_after = storage.stats()?.db_size.unwrap_or(0);
+    let bytes_reclaimed = bytes_before.saturating_sub(bytes_after);
+
+    match format {
+        OutputFormat::Text => Ok(format!(
+            "Compacted database: {bytes_before} -> {bytes_after} bytes ({bytes_reclaimed} reclaimed)\n"
+        )),
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            let json = serde_json::json!({
+                "bytes_before": bytes_before,
+                "bytes_after": bytes_after,
+                "bytes_reclaimed": bytes_reclaimed
+            });
+            Ok(serde_json::to_string_pretty(&json).unwrap_or_default())
+        }
+    }
+}
+
+fn cmd_usage(db_path: &std::path::Path, format: OutputFormat) -> Result<String> {
+    let storage = open_storage(db_path)?;
+    let usage = storage.storage_usage()?;
+    Ok(format_storage_usage(&usage, format))
+}
+
+fn cmd_fix_dimensions(
+    db_path: &std::path::Path,
+    target_dim: Option<usize>,
+    reembed: bool,
+    format: OutputFormat,
+) -> Result<String> {
+    let mut storage = open_storage(db_path)?;
+    let embedder = create_embedder()?;
+    let target = target_dim.unwrap_or_else(|| embedder.dimensions());
+    if reembed && target != embedder.dimensions() {
+        return Err(CommandError::InvalidArgument(format!(
+            "--reembed produces {}-dimension embeddings, but --target-dim is {target}",
+            embedder.dimensions()
+        ))
+        .into());
+    }
+
+    let mismatched = storage.get_chunks_with_other_dimensions(target)?;
+    let (deleted, reembedded) = if mismatched.is_empty() {
+        (0, 0)
+    } else if reembed {
+        let stored = crate::search::reembed_chunks(
+            &mut storage,
+            embedder.as_ref(),
+            &mismatched,
+            embed_batch_size(),
+        )?;
+        (0, stored)
+    } else {
+        (storage.delete_embeddings_with_other_dimensions(target)?, 0)
+    };
+
+    match format {
+        OutputFormat::Text => {
+            if mismatched.is_empty() {
+                return Ok(format!(
+                    "All embeddings have {target} dimensions. Nothing to fix.\n"
+                ));
+            }
+            let mut output = format!(
+                "Found {} embedding(s) without {target} dimensions.\n",
+                mismatched.len()
+            );
+            if reembed {
+                output.push_str(&format!(
+                    "Re-embedded {reembedded} chunk(s) using model '{}'.\n",
+                    embedder.model_name()
+                ));
+            } else {
+                output.push_str(&format!(
+                    "Deleted {deleted} embedding(s). Run `chunk embed` to regenerate them.\n"
+                ));
+            }
+            Ok(output)
+        }
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            let json = serde_json::json!({
+                "target_dimensions": target,
+                "mismatched": mismatched.len(),
+                "deleted": deleted,
+                "reembedded": reembedded,
+            });
+            Ok(serde_json::to_string_pretty(&json).unwrap_or_default())
+        }
+    }
+}
+
+#[allow(clippy::too_many_lines)]
+fn cmd_reembed_all(
+    db_path: &std::path::Path,
+    model_check: bool,
+    format: OutputFormat,
+) -> Result<String> {
+    let mut storage = open_storage(db_path)?;
+    let embedder = create_embedder()?;
+    let dimensions = embedder.dimensions();
+    let model = embedder.model_name();
+
+    let drifted = storage.get_drifted_embeddings(dimensions, model_check.then_some(model))?;
+
+    // Group by buffer, tracking each chunk's stored model
+    let mut by_buffer: std::collections::BTreeMap<i64, Vec<(i64, Option<String>)>> =
+        std::collections::BTreeMap::new();
+    for (chunk_id, buffer_id, model_name) in drifted {
+        by_buffer
+            .entry(buffer_id)
+            .or_default()
+            .push((chunk_id, model_name));
+    }
+
+    // Migrate buffer by buffer, counting (mismatched, regenerated) per
+    // stored model; a failed buffer keeps its old embeddings
+    let mut by_model: std::collections::BTreeMap<Option<String>, (usize, usize)> =
+        std::collections::BTreeMap::new();
+    let mut regenerated = 0;
+    let mut failed: Vec<(i64, String)> = Vec::new();
+    for (&buffer_id, rows) in &by_buffer {
+        let chunk_ids: Vec<i64> = rows.iter().map(|(chunk_id, _)| *chunk_id).collect();
+        let migrated = match crate::search::reembed_chunks_atomic(
+            &mut storage,
+            embedder.as_ref(),
+            &chunk_ids,
+            embed_batch_size(),
+        ) {
+            Ok(stored) => {
+                regenerated += stored;
+                true
+            }
+            Err(e) => {
+                failed.push((buffer_id, e.to_string()));
+                false
+            }
+        };
+        for (_, model_name) in rows {
+            let counts = by_model.entry(model_name.clone()).or_default();
+            counts.0 += 1;
+            if migrated {
+                counts.1 += 1;
+            }
+        }
+    }
+    let mismatched: usize = by_model.values().map(|(count, _)| count).sum();
+
+    // Bulk embedding sweep: refresh planner statistics for the new data
+    if regenerated > 0 {
+        storage.analyze()?;
+    }
+
+    match format {
+        OutputFormat::Text => {
+            if mismatched == 0 {
+                return Ok(if model_check {
+                    format!(
+                        "All embeddings match model '{model}' ({dimensions} dimensions). Nothing to re-embed.\n"
+                    )
+                } else {
+                    format!("All embeddings have {dimensions} dimensions. Nothing to re-embed.\n")
+                });
+            }
+            let mut output = format!(
+                "Re-embedded {regenerated} of {mismatched} drifted embedding(s) in {} buffer(s) using model '{model}' ({dimensions} dimensions).\n",
+                by_buffer.len()
+            );
+            for (model_name, (count, done)) in &by_model {
+                let _ = writeln!(
+                    output,
+                    "  {}: {count} mismatched, {done} regenerated",
+                    model_name.as_deref().unwrap_or("(unknown model)")
+                );
+            }
+            for (buffer_id, error) in &failed {
+                let _ = writeln!(
+                    output,
+                    "Failed to re-embed buffer {buffer_id} (left unchanged): {error}"
+                );
+            }
+            Ok(output)
+        }
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            let json = serde_json::json!({
+                "model": model,
+                "dimensions": dimensions,
+                "model_check": model_check,
+                "buffers": by_buffer.len(),
+                "mismatched": mismatched,
+                "regenerated": regenerated,
+                "by_model": by_model
+                    .iter()
+                    .map(|(model_name, (count, done))| serde_json::json!({
+                        "model": model_name,
+                        "mismatched": count,
+                        "regenerated": done
+                    }))
+                    .collect::<Vec<_>>(),
+                "failed": failed
+                    .iter()
+                    .map(|(buffer_id, error)| serde_json::json!({
+                        "buffer_id": buffer_id,
+                        "error": error
+                    }))
+                    .collect::<Vec<_>>()
+            });
+            Ok(serde_json::to_string_pretty(&json).unwrap_or_default())
+        }
+    }
+}
+
+/// Source reported by `load -` for content read from stdin.
+const STDIN_SOURCE: &str = "<stdin>";
+
+#[allow(clippy::too_many_arguments, clippy::too_many_lines)]
+fn cmd_load(
+    db_path: &std::path::Path,
+    file: &std::path::Path,
+    name: Option<&str>,
+    chunker_name: &str,
+    chunk_size: usize,
+    overlap: usize,
+    chunk_unit: &str,
+    encoding: Option<&str>,
+    normalize_whitespace: bool,
+    format: OutputFormat,
+) -> Result<String> {
+    // Validate the chunker before touching storage
+    let chunker = create_chunker(chunker_name)?;
+    let mut meta = ChunkerMetadata::with_size_and_overlap(chunk_size, overlap);
+    if chunk_unit == "tokens" {
+        if !matches!(chunker.name(), "fixed" | "semantic") {
+            return Err(CommandError::InvalidArgument(format!(
+                "--chunk-unit tokens is not supported by the {} chunker",
+                chunker.name()
+            ))
+            .into());
+        }
+        meta = meta.token_budget(TokenBudget::new(chunk_size, overlap));
+    }
+
+    // `-` reads stdin, which has no file name to fall back on
+    let from_stdin = file.as_os_str() == "-";
+    if from_stdin && name.is_none() {
+        return Err(CommandError::InvalidArgument(
+            "--name is required when loading from stdin".to_string(),
+        )
+        .into());
+    }
+    let source = if from_stdin {
+        STDIN_SOURCE.to_string()
+    } else {
+        file.to_string_lossy().into_owned()
+    };
+    let from_url = is_url(&source);
+
+    let mut storage = open_storage(db_path)?;
+
+    // Read raw content; its content type (from the download or the file
+    // extension) selects a text extractor, if any
+    let mut download = None;
+    let (bytes, content_type) = if from_url {
+        let fetched = fetch_url(&source)?;
+        let content_type = fetched.content_type.clone();
+        download = Some((fetched.final_url, fetched.content_type));
+        (fetched.bytes, content_type)
+    } else if from_stdin {
+        let mut bytes = Vec::new();
+        io::stdin()
+            .read_to_end(&mut bytes)
+            .map_err(|e| IoError::ReadFailed {
+                path: STDIN_SOURCE.to_string(),
+                reason: e.to_string(),
+            })?;
+        (bytes, None)
+    } else {
+        let content_type = file
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_lowercase);
+        (FileReader::open(file)?.read_bytes()?, content_type)
+    };
+
+    // Extract or transcode to UTF-8 text
+    let extractor = content_type.as_deref().and_then(create_extractor);
+    let decoded = match &extractor {
+        Some(extractor) => extractor.extract(bytes, encoding),
+        None => decode_text(bytes, encoding),
+    }
+    .map_err(|reason| IoError::ReadFailed {
+        path: source.clone(),
+        reason,
+    })?;
+    let extracted_from = extractor.as_ref().map(|extractor| extractor.name());
+    let content = if normalize_whitespace {
+        crate::io::normalize_whitespace(&decoded.content)
+    } else {
+        decoded.content
+    };
+
+    // Create buffer
+    let buffer_name = name.map(String::from).or_else(|| {
+        if from_url {
+            buffer_name_from_url(&source)
+        } else {
+            file.file_name().and_then(|n| n.to_str()).map(String::from)
+        }
+    });
+
+    let mut buffer = if from_stdin {
+        Buffer::from_content(content)
+    } else {
+        Buffer::from_file(file.to_path_buf(), content)
+    };
+    buffer.name = buffer_name;
+    if let Some((_, content_type)) = &download {
+        buffer.metadata.content_type.clone_from(content_type);
+    }
+    buffer.metadata.source_encoding = decoded.encoding.map(String::from);
+    buffer.metadata.whitespace_normalized = normalize_whitespace;
+
+    // Embeddings are generated automatically during load
+    let embedder = create_embedder()?;
+    let (buffer_id, chunk_count, embedded_count) = ingest_buffer(
+        &mut storage,
+        &mut buffer,
+        chunker.as_ref(),
+        &meta,
+        embedder.as_ref(),
+    )?;
+
+    match format {
+        OutputFormat::Text => {
+            let mut output = format!(
+                "Loaded buffer {} (ID: {}) with {} chunks ({} embedded) from {}\n",
+                buffer.name.as_deref().unwrap_or("unnamed"),
+                buffer_id,
+                chunk_count,
+                embedded_count,
+                source
+            );
+            if let Some((final_url, _)) = &download
+                && *final_url != source
+            {
+                output.push_str(&format!("Redirected to {final_url}\n"));
+            }
+            if let Some(encoding) = decoded.encoding {
+                output.push_str(&format!("Transcoded from {encoding} to UTF-8\n"));
+            }
+            if let Some(extracted_from) = extracted_from {
+                output.push_str(&format!("Extracted text from {extracted_from}\n"));
+            }
+            if normalize_whitespace {
+                output.push_str("Normalized whitespace before chunking\n");
+            }
+            Ok(output)
+        }
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            let mut result = serde_json::json!({
+                "buffer_id": buffer_id,
+                "name": buffer.name,
+                "chunk_count": chunk_count,
+                "embedded_count": embedded_count,
+                "size": buffer.content.len(),
+                "source": source,
+                "source_encoding": decoded.encoding,
+                "extracted_from": extracted_from,
+                "whitespace_normalized": normalize_whitespace,
+            });
+            if let Some((final_url, content_type)) = &download {
+                result["final_url"] = serde_json::json!(final_url);
+                result["content_type"] = serde_json::json!(content_type);
+            }
+            Ok(serde_json::to_string_pretty(&result).unwrap_or_default())
+        }
+    }
+}
+
+/// Stores a new buffer, chunks and embeds it, and adds it to the context.
+///
+/// On success `buffer` carries its ID and chunk count. Returns the buffer
+/// ID, the number of chunks, and the number of chunks embedded.
+fn ingest_buffer(
+    storage: &mut SqliteStorage,
+    buffer: &mut Buffer,
+    chunker: &dyn Chunker,
+    meta: &ChunkerMetadata,
+    embedder: &dyn Embedder,
+) -> Result<(i64, usize, usize)> {
+    buffer.compute_hash();
+    let buffer_id = storage.add_buffer(buffer)?;
+
+    let chunks = chunker.chunk(buffer_id, &buffer.content, Some(meta))?;
+    storage.add_chunks(buffer_id, &chunks)?;
+    let embedded_count = embed_buffer_chunks_with_progress(
+        storage,
+        embedder,
+        buffer_id,
+        embed_batch_size(),
+        embed_progress(),
+    )?;
+
+    buffer.id = Some(buffer_id);
+    buffer.set_chunk_count(chunks.len());
+    storage.update_buffer(buffer)?;
+
+    if let Some(mut context) = storage.load_context()? {
+        context.add_buffer(buffer_id);
+        storage.save_context(&context)?;
+    }
+
+    Ok((buffer_id, chunks.len(), embedded_count))
+}
+
+#[allow(clippy::too_many_lines)]
+fn cmd_import_dir(
+    db_path: &std::path::Path,
+    dir: &std::path::Path,
+    glob: Option<&str>,
+    (chunker_name, chunk_size, overlap): (&str, usize, usize),
+    recursive: bool,
+    format: OutputFormat,
+) -> Result<String> {
+    // Validate the chunker before touching storage
+    let chunker = create_chunker(chunker_name)?;
+    let meta = ChunkerMetadata::with_size_and_overlap(chunk_size, overlap);
+
+    let files: Vec<(String, std::path::PathBuf)> = list_files(dir, recursive)?
+        .into_iter()
+        .map(|relative| (relative_path_string(&relative), dir.join(relative)))
+        .filter(|(name, _)| glob.is_none_or(|pattern| glob_match(pattern, name)))
+        .collect();
+
+    let mut storage = open_storage(db_path)?;
+    let embedder = create_embedder()?;
+
+    let mut loaded = Vec::new();
+    let mut skipped = Vec::new();
+    let mut failed = Vec::new();
+    for (name, path) in files {
+        if storage.get_buffer_by_name(&name)?.is_some() {
+            let error = StorageError::BufferNameTaken { name: name.clone() };
+            failed.push(serde_json::json!({ "path": name, "error": error.to_string() }));
+            continue;
+        }
+        let bytes = match FileReader::open(&path).and_then(|reader| reader.read_bytes()) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                failed.push(serde_json::json!({ "path": name, "error": e.to_string() }));
+                continue;
+            }
+        };
+        // Undecodable content is treated as binary
+        let decoded = match decode_text(bytes, None) {
+            Ok(decoded) => decoded,
+            Err(reason) => {
+                skipped.push(serde_json::json!({ "path": name, "reason": reason }));
+                continue;
+            }
+        };
+
+        let mut buffer = Buffer::from_file(path, decoded.content);
+        buffer.name = Some(name.clone());
+        buffer.metadata.source_encoding = decoded.encoding.map(String::from);
+        let (buffer_id, chunk_count, embedded_count) = ingest_buffer(
+            &mut storage,
+            &mut buffer,
+            chunker.as_ref(),
+            &meta,
+            embedder.as_ref(),
+        )?;
+        loaded.push(serde_json::json!({
+            "path": name,
+            "buffer_id": buffer_id,
+            "chunk_count": chunk_count,
+            "embedded_count": embedded_count,
+        }));
+    }
+
+    // Bulk insert: refresh planner statistics for the new data
+    if !loaded.is_empty() {
+        storage.analyze()?;
+    }
+
+    match format {
+        OutputFormat::Text => {
+            let mut output = String::new();
+            for entry in &loaded {
+                let _ = writeln!(
+                    output,
+                    "Loaded {} (ID: {}) with {} chunks",
+                    entry["path"].as_str().unwrap_or_default(),
+                    entry["buffer_id"],
+                    entry["chunk_count"]
+                );
+            }
+            for entry in &skipped {
+                let _ = writeln!(
+                    output,
+                    "Skipped {}: {}",
+                    entry["path"].as_str().unwrap_or_default(),
+                    entry["reason"].as_str().unwrap_or_default()
+                );
+            }
+            for entry in &failed {
+                let _ = writeln!(
+                    output,
+                    "Failed {}: {}",
+                    entry["path"].as_str().unwrap_or_default(),
+                    entry["error"].as_str().unwrap_or_default()
+                );
+            }
+            let _ = writeln!(
+                output,
+                "Imported {}: {} loaded, {} skipped, {} failed",
+                dir.display(),
+                loaded.len(),
+                skipped.len(),
+                failed.len()
+            );
+            Ok(output)
+        }
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            let json = serde_json::json!({
+                "directory": dir.to_string_lossy(),
+                "loaded": loaded,
+                "skipped": skipped,
+                "failed": failed,
+            });
+            Ok(serde_json::to_string_pretty(&json).unwrap_or_default())
+        }
+    }
+}
+
+/// Preview length (characters) for `explain-chunking` output.
+const EXPLAIN_PREVIEW_LEN: usize = 60;
+
+#[allow(clippy::too_many_lines)]
+fn cmd_explain_chunking(
+    file: &std::path::Path,
+    chunker_name: &str,
+    chunk_size: usize,
+    overlap: usize,
+    format: OutputFormat,
+) -> Result<String> {
+    let content = read_file(file)?;
+    let chunker = create_chunker(chunker_name)?;
+    let meta = ChunkerMetadata::with_size_and_overlap(chunk_size, overlap);
+    let chunks = chunker.chunk(0, &content, Some(&meta))?;
+
+    // 1-based inclusive line ranges, from chunk metadata when the chunker
+    // recorded them, otherwise located via the newline offsets
+    let newlines: Vec<usize> = content.match_indices('\n').map(|(i, _)| i).collect();
+    let line_of = |offset: usize| newlines.partition_point(|&i| i < offset) + 1;
+    let line_ranges: Vec<(usize, usize)> = chunks
+        .iter()
+        .map(|chunk| {
+            chunk.metadata.line_range.as_ref().map_or_else(
+                || {
+                    let range = &chunk.byte_range;
+                    let last_byte = range.end.saturating_sub(1).max(range.start);
+                    (line_of(range.start), line_of(last_byte))
+                },
+                |lines| (lines.start, lines.end),
+            )
+        })
+        .collect();
+    let previews: Vec<String> = chunks
+        .iter()
+        .map(|chunk| make_preview(&chunk.content, EXPLAIN_PREVIEW_LEN))
+        .collect();
+
+    match format {
+        OutputFormat::Text => {
+            let mut output = String::new();
+            let _ = writeln!(
+                output,
+                "Chunking plan for {} ({}, chunk size {chunk_size}, overlap {overlap}): {} chunks, {} bytes\n",
+                file.display(),
+                chunker.name(),
+                chunks.len(),
+                content.len()
+            );
+            let _ = writeln!(
+                output,
+                "{:<6} {:<16} {:<12} {:<8} {:<7} Preview",
+                "Index", "Bytes", "Lines", "Size", "Tokens"
+            );
+            output.push_str(&"-".repeat(78));
+            output.push('\n');
+
+            for ((chunk, (line_start, line_end)), preview) in
+                chunks.iter().zip(&line_ranges).zip(&previews)
+            {
+                let _ = writeln!(
+                    output,
+                    "{:<6} {:<16} {:<12} {:<8} {:<7} {}",
+                    chunk.index,
+                    format!("{}..{}", chunk.byte_range.start, chunk.byte_range.end),
+                    format!("{line_start}-{line_end}"),
+                    chunk.size(),
+                    chunk.estimate_tokens(),
+                    preview
+                );
+            }
+            Ok(output)
+        }
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            let json = serde_json::json!({
+                "file": file.to_string_lossy(),
+                "chunker": chunker.name(),
+                "chunk_size": chunk_size,
+                "overlap": overlap,
+                "total_bytes": content.len(),
+                "chunk_count": chunks.len(),
+                "chunks": chunks.iter().zip(&line_ranges).zip(&previews).map(
+                    |((chunk, (line_start, line_end)), preview)| {
+                        serde_json::json!({
+                            "index": chunk.index,
+                            "byte_range": {
+                                "start": chunk.byte_range.start,
+                                "end": chunk.byte_range.end
+                            },
+                            "line_range": {
+                                "start": line_start,
+                                "end": line_end
+                            },
+                            "size": chunk.size(),
+                            "token_count": chunk.estimate_tokens(),
+                            "has_overlap": chunk.metadata.has_overlap,
+                            "title": chunk.title(),
+                            "preview": preview
+                        })
+                    }
+                ).collect::<Vec<_>>()
+            });
+            Ok(serde_json::to_string_pretty(&json).unwrap_or_default())
+        }
+    }
+}
+
+fn cmd_list_buffers(
+    db_path: &std::path::Path,
+    tags: &[String],
+    sample: Option<(usize, Option<u64>)>,
+    fields: &[String],
+    format: OutputFormat,
+) -> Result<String> {
+    let tags = normalize_tags(tags)?;
+    let fields = parse_buffer_fields(fields)?;
+    let storage = open_storage(db_path)?;
+    let mut buffers = storage.list_buffers()?;
+    if !tags.is_empty() {
+        let tagged: std::collections::HashSet<i64> =
+            storage.buffers_with_tags(&tags)?.into_iter().collect();
+        buffers.retain(|b| b.id.is_some_and(|id| tagged.contains(&id)));
+    }
+    if let Some((n, seed)) = sample {
+        // Shuffle every buffer so the sample is drawn from the listed ones
+        let listed: std::collections::HashSet<i64> = buffers.iter().filter_map(|b| b.id).collect();
+        let sampled: std::collections::HashSet<i64> = storage
+            .sample_buffer_ids(usize::MAX, seed)?
+            .into_iter()
+            .filter(|id| listed.contains(id))
+            .take(n)
+            .collect();
+        buffers.retain(|b| b.id.is_some_and(|id| sampled.contains(&id)));
+    }
+    if null_separated(format) {
+        return Ok(format_buffer_list_null(&buffers));
+    }
+    let coverage = storage.embedding_coverage()?;
+    Ok(format_buffer_list(&buffers, &coverage, &fields, format))
+}
+
+/// Validates `list --fields`, dropping blanks and repeats.
+fn parse_buffer_fields(fields: &[String]) -> Result<Vec<String>> {
+    let mut parsed: Vec<String> = Vec::new();
+    for field in fields.iter().map(|f| f.trim()).filter(|f| !f.is_empty()) {
+        if !BUFFER_LIST_FIELDS.contains(&field) {
+            return Err(CommandError::InvalidArgument(format!(
+                "unknown field '{field}' (expected one of: {})",
+                BUFFER_LIST_FIELDS.join(", ")
+            ))
+            .into());
+        }
+        if !parsed.iter().any(|f| f == field) {
+            parsed.push(field.to_string());
+        }
+    }
+    Ok(parsed)
+}
+
+fn cmd_show_buffer(
+    db_path: &std::path::Path,
+    identifier: &str,
+    show_chunks: bool,
+    format: OutputFormat,
+) -> Result<String> {
+    let storage = open_storage(db_path)?;
+    let buffer = resolve_buffer(&storage, identifier)?;
+
+    let chunks = if show_chunks {
+        Some(storage.get_chunks(buffer.id.unwrap_or(0))?)
+    } else {
+        None
+    };
+
+    Ok(format_buffer(&buffer, chunks.as_deref(), format))
+}
+
+fn cmd_delete_buffer(
+    db_path: &std::path::Path,
+    identifier: &str,
+    yes: bool,
+    soft: bool,
     _format: OutputFormat,
 ) -> Result<String> {
-    if !yes {
+    // A soft delete can be undone, so it needs no confirmation
+    if !yes && !soft {
+        return Err(CommandError::ExecutionFailed(
+            "Use --yes to confirm deletion, or --soft to move the buffer to the trash.".to_string(),
+        )
+        .into());
+    }
+
+    let mut storage = open_storage(db_path)?;
+    // A trashed buffer can still be deleted for good
+    let buffer = if soft {
+        resolve_buffer(&storage, identifier)?
+    } else {
+        find_buffer(&storage, identifier)?
+    };
+    let buffer_id = buffer.id.unwrap_or(0);
+    let buffer_name = buffer.name.unwrap_or_else(|| format!("{buffer_id}"));
+
+    // Trashed buffers stay in the context so a restore picks up where it left off
+    if soft {
+        storage.trash_buffer(buffer_id)?;
+        return Ok(format!(
+            "Moved buffer to trash: {buffer_name} (undo with 'rlm-rs restore {buffer_id}')\n"
+        ));
+    }
+
+    storage.delete_buffer(buffer_id)?;
+
+    // Update context
+    if let Some(mut context) = storage.load_context()? {
+        context.remove_buffer(buffer_id);
+        storage.save_context(&context)?;
+    }
+
+    Ok(format!("Deleted buffer: {buffer_name}\n"))
+}
+
+fn cmd_list_trash(db_path: &std::path::Path, format: OutputFormat) -> Result<String> {
+    let storage = open_storage(db_path)?;
+    let trash = storage.list_trash()?;
+    Ok(format_trash_list(&trash, format))
+}
+
+fn cmd_restore(
+    db_path: &std::path::Path,
+    identifier: &str,
+    format: OutputFormat,
+) -> Result<String> {
+    let mut storage = open_storage(db_path)?;
+
+    // Match within the trash, so a live buffer sharing the name is skipped
+    let trash = storage.list_trash()?;
+    let buffer = trash
+        .iter()
+        .find(|b| identifier.parse::<i64>().is_ok_and(|id| b.id == Some(id)))
+        .or_else(|| trash.iter().find(|b| b.name.as_deref() == Some(identifier)))
+        .ok_or_else(|| {
+            CommandError::ExecutionFailed(format!("no buffer '{identifier}' in the trash"))
+        })?;
+    let buffer_id = buffer.id.unwrap_or(0);
+    storage.restore_buffer(buffer_id)?;
+
+    let buffer_name = buffer
+        .name
+        .clone()
+        .unwrap_or_else(|| format!("{buffer_id}"));
+    match format {
+        OutputFormat::Text => Ok(format!("Restored buffer: {buffer_name}\n")),
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            let json = serde_json::json!({
+                "buffer_id": buffer_id,
+                "name": buffer.name,
+                "restored": true,
+            });
+            Ok(serde_json::to_string_pretty(&json).unwrap_or_default())
+        }
+    }
+}
+
+fn cmd_purge(db_path: &std::path::Path, yes: bool, format: OutputFormat) -> Result<String> {
+    if !yes {
+        return Err(CommandError::ExecutionFailed(
+            "Use --yes to permanently delete the trashed buffers.".to_string(),
+        )
+        .into());
+    }
+
+    let mut storage = open_storage(db_path)?;
+    let purged = storage.purge_trash()?;
+
+    if !purged.is_empty()
+        && let Some(mut context) = storage.load_context()?
+    {
+        for &buffer_id in &purged {
+            context.remove_buffer(buffer_id);
+        }
+        storage.save_context(&context)?;
+    }
+
+    match format {
+        OutputFormat::Text => Ok(if purged.is_empty() {
+            "Trash is empty.\n".to_string()
+        } else {
+            format!("Purged {} buffer(s) from the trash\n", purged.len())
+        }),
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            let json = serde_json::json!({ "purged": purged });
+            Ok(serde_json::to_string_pretty(&json).unwrap_or_default())
+        }
+    }
+}
+
+#[allow(clippy::too_many_lines)]
+fn cmd_merge(
+    db_path: &std::path::Path,
+    sources: &[String],
+    name: &str,
+    (chunker_name, chunk_size, overlap): (&str, usize, usize),
+    separator: &str,
+    delete_sources: bool,
+    format: OutputFormat,
+) -> Result<String> {
+    let name = name.trim();
+    if name.is_empty() {
         return Err(
-            CommandError::ExecutionFailed("Use --yes to confirm deletion.".to_string()).into(),
+            CommandError::InvalidArgument("buffer name cannot be empty".to_string()).into(),
         );
     }
+    // Validate the chunker before touching storage
+    let chunker = create_chunker(chunker_name)?;
+    let meta = ChunkerMetadata::with_size_and_overlap(chunk_size, overlap);
 
     let mut storage = open_storage(db_path)?;
-    let buffer = resolve_buffer(&storage, identifier)?;
-    let buffer_id = buffer.id.unwrap_or(0);
-    let buffer_name = buffer.name.unwrap_or_else(|| format!("{buffer_id}"));
 
-    storage.delete_buffer(buffer_id)?;
+    // Resolve in command-line order; a buffer named twice is merged once
+    let mut buffers: Vec<Buffer> = Vec::with_capacity(sources.len());
+    let mut duplicates = Vec::new();
+    for identifier in sources {
+        let buffer = resolve_buffer(&storage, identifier)?;
+        if buffers.iter().any(|b| b.id == buffer.id) {
+            duplicates.push(identifier.clone());
+        } else {
+            buffers.push(buffer);
+        }
+    }
+    let source_ids: Vec<i64> = buffers.iter().filter_map(|b| b.id).collect();
 
-    // Update context
+    // The name may only be reused from a source that is about to be deleted
+    if let Some(existing) = storage.get_buffer_by_name(name)?
+        && !(delete_sources && source_ids.contains(&existing.id.unwrap_or(0)))
+    {
+        return Err(StorageError::BufferNameTaken {
+            name: name.to_string(),
+        }
+        .into());
+    }
+
+    let content = buffers
+        .iter()
+        .map(|b| b.content.as_str())
+        .collect::<Vec<_>>()
+        .join(separator);
+    let mut merged = Buffer::from_named(name.to_string(), content);
+    // Keep the content type only when every source agrees
+    let first_type = buffers
+        .first()
+        .and_then(|b| b.metadata.content_type.clone());
+    if buffers
+        .iter()
+        .all(|b| b.metadata.content_type == first_type)
+    {
+        merged.metadata.content_type = first_type;
+    }
+    merged.compute_hash();
+
+    let buffer_id = storage.add_buffer(&merged)?;
+    let chunks = chunker.chunk(buffer_id, &merged.content, Some(&meta))?;
+    storage.add_chunks(buffer_id, &chunks)?;
+    let embedder = create_embedder()?;
+    let embedded_count = embed_buffer_chunks_with_progress(
+        &mut storage,
+        embedder.as_ref(),
+        buffer_id,
+        embed_batch_size(),
+        embed_progress(),
+    )?;
+    merged.id = Some(buffer_id);
+    merged.set_chunk_count(chunks.len());
+    storage.update_buffer(&merged)?;
+
+    if delete_sources {
+        for &id in &source_ids {
+            storage.delete_buffer(id)?;
+        }
+    }
     if let Some(mut context) = storage.load_context()? {
-        context.remove_buffer(buffer_id);
+        if delete_sources {
+            for &id in &source_ids {
+                context.remove_buffer(id);
+            }
+        }
+        context.add_buffer(buffer_id);
         storage.save_context(&context)?;
     }
 
-    Ok(format!("Deleted buffer: {buffer_name}\n"))
+    match format {
+        OutputFormat::Text => {
+            let names: Vec<String> = buffers
+                .iter()
+                .map(|b| {
+                    b.name
+                        .clone()
+                        .unwrap_or_else(|| b.id.unwrap_or(0).to_string())
+                })
+                .collect();
+            let mut output = format!(
+                "Merged {} buffer(s) into {name} (ID: {buffer_id}) with {} chunks ({embedded_count} embedded): {}\n",
+                buffers.len(),
+                chunks.len(),
+                names.join(", ")
+            );
+            if !duplicates.is_empty() {
+                let _ = writeln!(
+                    output,
+                    "Skipped duplicate source(s): {}",
+                    duplicates.join(", ")
+                );
+            }
+            if delete_sources {
+                let _ = writeln!(output, "Deleted {} source buffer(s)", source_ids.len());
+            }
+            Ok(output)
+        }
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            let json = serde_json::json!({
+                "source_ids": source_ids,
+                "new_buffer_id": buffer_id,
+                "name": name,
+                "size": merged.content.len(),
+                "chunk_count": chunks.len(),
+                "embedded_count": embedded_count,
+                "duplicates": duplicates,
+                "sources_deleted": delete_sources,
+            });
+            Ok(serde_json::to_string_pretty(&json).unwrap_or_default())
+        }
+    }
+}
+
+fn cmd_diff(db_path: &std::path::Path, a: &str, b: &str, format: OutputFormat) -> Result<String> {
+    let storage = open_storage(db_path)?;
+    let a_buffer = resolve_buffer(&storage, a)?;
+    let b_buffer = resolve_buffer(&storage, b)?;
+    let diff = storage.diff_buffers(a_buffer.id.unwrap_or(0), b_buffer.id.unwrap_or(0))?;
+
+    match format {
+        OutputFormat::Text => {
+            let label = |buffer: &Buffer| {
+                let id = buffer.id.unwrap_or(0);
+                buffer
+                    .name
+                    .as_ref()
+                    .map_or_else(|| format!("ID {id}"), |name| format!("{name} (ID {id})"))
+            };
+            let mut output = format!(
+                "--- {}\n+++ {}\nUnchanged: {}, added: {}, removed: {}\n",
+                label(&a_buffer),
+                label(&b_buffer),
+                diff.unchanged,
+                diff.added,
+                diff.removed
+            );
+            if diff.strategies_differ() {
+                let _ = writeln!(
+                    output,
+                    "Note: chunk strategies differ ({} vs {}); chunks were matched by normalized content",
+                    diff.a_strategy.as_deref().unwrap_or("unknown"),
+                    diff.b_strategy.as_deref().unwrap_or("unknown")
+                );
+            }
+            for hunk in &diff.hunks {
+                let _ = writeln!(
+                    output,
+                    "@@ -{},{} +{},{} @@",
+                    hunk.a_start + 1,
+                    hunk.removed.len(),
+                    hunk.b_start + 1,
+                    hunk.added.len()
+                );
+                for chunk in &hunk.removed {
+                    let _ = writeln!(output, "-[{}] {}", chunk.index, chunk.preview);
+                }
+                for chunk in &hunk.added {
+                    let _ = writeln!(output, "+[{}] {}", chunk.index, chunk.preview);
+                }
+            }
+            Ok(output)
+        }
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            let mut json = serde_json::to_value(&diff).unwrap_or_default();
+            json["strategies_differ"] = serde_json::Value::Bool(diff.strategies_differ());
+            Ok(serde_json::to_string_pretty(&json).unwrap_or_default())
+        }
+    }
+}
+
+fn cmd_rename(
+    db_path: &std::path::Path,
+    identifier: &str,
+    new_name: &str,
+    format: OutputFormat,
+) -> Result<String> {
+    let new_name = new_name.trim();
+    if new_name.is_empty() {
+        return Err(
+            CommandError::InvalidArgument("buffer name cannot be empty".to_string()).into(),
+        );
+    }
+
+    let mut storage = open_storage(db_path)?;
+    let buffer = resolve_buffer(&storage, identifier)?;
+    let buffer_id = buffer.id.unwrap_or(0);
+    storage.rename_buffer(buffer_id, new_name)?;
+
+    match format {
+        OutputFormat::Text => Ok(format!(
+            "Renamed buffer {buffer_id}: {} \u{2192} {new_name}\n",
+            buffer.name.as_deref().unwrap_or("unnamed")
+        )),
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            let json = serde_json::json!({
+                "buffer_id": buffer_id,
+                "old_name": buffer.name,
+                "new_name": new_name,
+            });
+            Ok(serde_json::to_string_pretty(&json).unwrap_or_default())
+        }
+    }
+}
+
+/// Trims tags and rejects empty ones.
+/// Parses `KEY=VALUE` buffer metadata filters.
+///
+/// Values are read like `buffer-meta` values: as JSON if valid, otherwise as
+/// a string, so `version=2` matches a value set with `buffer-meta b version 2`.
+fn parse_meta_filters(filters: &[String]) -> Result<Vec<(String, serde_json::Value)>> {
+    filters
+        .iter()
+        .map(|filter| {
+            let (key, raw) = filter.split_once('=').ok_or_else(|| {
+                CommandError::InvalidArgument(format!(
+                    "--buffer-meta expects KEY=VALUE, got '{filter}'"
+                ))
+            })?;
+            let key = key.trim();
+            if key.is_empty() {
+                return Err(CommandError::InvalidArgument(
+                    "metadata key cannot be empty".to_string(),
+                )
+                .into());
+            }
+            let value = serde_json::from_str(raw)
+                .unwrap_or_else(|_| serde_json::Value::String(raw.to_string()));
+            Ok((key.to_string(), value))
+        })
+        .collect()
+}
+
+fn normalize_tags(tags: &[String]) -> Result<Vec<String>> {
+    tags.iter()
+        .map(|tag| {
+            let tag = tag.trim();
+            if tag.is_empty() {
+                return Err(
+                    CommandError::InvalidArgument("tags cannot be empty".to_string()).into(),
+                );
+            }
+            Ok(tag.to_string())
+        })
+        .collect()
+}
+
+fn cmd_tag(
+    db_path: &std::path::Path,
+    identifier: Option<&str>,
+    add: &[String],
+    remove: &[String],
+    format: OutputFormat,
+) -> Result<String> {
+    let add = normalize_tags(add)?;
+    let remove = normalize_tags(remove)?;
+
+    let Some(identifier) = identifier else {
+        if !add.is_empty() || !remove.is_empty() {
+            return Err(CommandError::InvalidArgument(
+                "--add and --remove require a buffer".to_string(),
+            )
+            .into());
+        }
+        let storage = open_storage(db_path)?;
+        let tags = storage.list_tags()?;
+        return Ok(match format {
+            OutputFormat::Text => {
+                if tags.is_empty() {
+                    return Ok("No tags.\n".to_string());
+                }
+                let mut output = String::new();
+                let _ = writeln!(output, "{:<24} {:>8}", "Tag", "Buffers");
+                output.push_str(&"-".repeat(33));
+                output.push('\n');
+                for (tag, count) in &tags {
+                    let _ = writeln!(output, "{tag:<24} {count:>8}");
+                }
+                output
+            }
+            OutputFormat::Json | OutputFormat::Ndjson => {
+                let tags: Vec<_> = tags
+                    .iter()
+                    .map(|(tag, count)| serde_json::json!({ "tag": tag, "buffer_count": count }))
+                    .collect();
+                serde_json::to_string_pretty(&tags).unwrap_or_default()
+            }
+        });
+    };
+
+    let mut storage = open_storage(db_path)?;
+    let buffer = resolve_buffer(&storage, identifier)?;
+    let buffer_id = buffer.id.unwrap_or(0);
+    let buffer_name = buffer.name.unwrap_or_else(|| buffer_id.to_string());
+
+    let mut added = Vec::new();
+    for tag in &add {
+        if storage.add_tag(buffer_id, tag)? {
+            added.push(tag.clone());
+        }
+    }
+    let mut removed = Vec::new();
+    for tag in &remove {
+        if storage.remove_tag(buffer_id, tag)? {
+            removed.push(tag.clone());
+        }
+    }
+    let tags = storage.buffer_tags(buffer_id)?;
+
+    match format {
+        OutputFormat::Text => {
+            let mut output = String::new();
+            if !added.is_empty() {
+                let _ = writeln!(output, "Added: {}", added.join(", "));
+            }
+            if !removed.is_empty() {
+                let _ = writeln!(output, "Removed: {}", removed.join(", "));
+            }
+            let shown = if tags.is_empty() {
+                "(none)".to_string()
+            } else {
+                tags.join(", ")
+            };
+            let _ = writeln!(output, "Tags for {buffer_name}: {shown}");
+            Ok(output)
+        }
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            let json = serde_json::json!({
+                "buffer_id": buffer_id,
+                "buffer_name": buffer_name,
+                "added": added,
+                "removed": removed,
+                "tags": tags,
+            });
+            Ok(serde_json::to_string_pretty(&json).unwrap_or_default())
+        }
+    }
+}
+
+fn cmd_buffer_meta(
+    db_path: &std::path::Path,
+    identifier: &str,
+    key: Option<&str>,
+    value: Option<&str>,
+    delete: bool,
+    format: OutputFormat,
+) -> Result<String> {
+    let mut storage = open_storage(db_path)?;
+    let buffer = resolve_buffer(&storage, identifier)?;
+    let buffer_id = buffer.id.unwrap_or(0);
+    let buffer_name = buffer.name.unwrap_or_else(|| buffer_id.to_string());
+
+    let Some(key) = key else {
+        let metadata = storage.get_buffer_metadata(buffer_id)?;
+        return Ok(match format {
+            OutputFormat::Text => {
+                if metadata.is_empty() {
+                    return Ok(format!("No metadata for {buffer_name}.\n"));
+                }
+                let mut output = format!("Metadata for {buffer_name}:\n");
+                for (key, value) in &metadata {
+                    let _ = writeln!(output, "  {key}: {value}");
+                }
+                output
+            }
+            OutputFormat::Json | OutputFormat::Ndjson => {
+                let json = serde_json::json!({
+                    "buffer_id": buffer_id,
+                    "buffer_name": buffer_name,
+                    "metadata": metadata,
+                });
+                serde_json::to_string_pretty(&json).unwrap_or_default()
+            }
+        });
+    };
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(
+            CommandError::InvalidArgument("metadata key cannot be empty".to_string()).into(),
+        );
+    }
+
+    let (action, value) = if delete {
+        let previous = storage.update_buffer_metadata(buffer_id, key, None)?;
+        if previous.is_none() {
+            return Err(CommandError::ExecutionFailed(format!(
+                "buffer '{buffer_name}' has no metadata key '{key}'"
+            ))
+            .into());
+        }
+        ("deleted", None)
+    } else if let Some(raw) = value {
+        let value = serde_json::from_str(raw)
+            .unwrap_or_else(|_| serde_json::Value::String(raw.to_string()));
+        storage.update_buffer_metadata(buffer_id, key, Some(value.clone()))?;
+        ("set", Some(value))
+    } else {
+        let value = storage.get_buffer_metadata(buffer_id)?.remove(key);
+        if value.is_none() {
+            return Err(CommandError::ExecutionFailed(format!(
+                "buffer '{buffer_name}' has no metadata key '{key}'"
+            ))
+            .into());
+        }
+        ("get", value)
+    };
+
+    match format {
+        OutputFormat::Text => Ok(match (action, &value) {
+            ("get", Some(value)) => match value {
+                serde_json::Value::String(s) => format!("{s}\n"),
+                other => format!("{other}\n"),
+            },
+            ("set", Some(value)) => format!("Set {key} = {value} on {buffer_name}\n"),
+            _ => format!("Deleted {key} from {buffer_name}\n"),
+        }),
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            let json = serde_json::json!({
+                "buffer_id": buffer_id,
+                "buffer_name": buffer_name,
+                "action": action,
+                "key": key,
+                "value": value,
+            });
+            Ok(serde_json::to_string_pretty(&json).unwrap_or_default())
+        }
+    }
 }
 
 fn cmd_peek(
@@ -486,6 +2556,9 @@ fn cmd_grep(
         });
     }
 
+    if null_separated(format) {
+        return Ok(format_grep_null(&matches));
+    }
     Ok(format_grep_matches(&matches, pattern, format))
 }
 
@@ -610,15 +2683,147 @@ fn cmd_add_buffer(
     }
 }
 
-#[allow(clippy::too_many_arguments, clippy::redundant_clone)]
+/// Outcome of replacing a buffer's content.
+struct BufferUpdate {
+    old_chunk_count: usize,
+    new_chunk_count: usize,
+    saved_version: Option<i64>,
+    embed_result: Option<crate::search::IncrementalEmbedResult>,
+    /// Embeddings of unchanged chunks kept without embedding.
+    kept_embeddings: usize,
+    /// Chunks left without an embedding, if the buffer was embedded before
+    /// and embedding was not requested.
+    unembedded_chunks: Option<usize>,
+}
+
+/// Replaces a buffer's content, re-chunking it and optionally embedding
+/// the new chunks.
+///
+/// Embedding is controlled by `embed` alone. Either way, chunks whose text
+/// is unchanged keep their embeddings: with `embed` they are reused by
+/// incremental embedding, and without it they are copied to the new chunks
+/// so that a later `chunk embed` only embeds the rest.
+///
+/// With `keep_versions`, the previous content is saved as a version first
+/// and only that many versions are kept.
+fn replace_buffer_content(
+    storage: &mut SqliteStorage,
+    buffer: &Buffer,
+    new_content: String,
+    (strategy, chunk_size, overlap): (&str, usize, usize),
+    embed: bool,
+    keep_versions: Option<usize>,
+) -> Result<BufferUpdate> {
+    let buffer_id = buffer
+        .id
+        .ok_or_else(|| CommandError::ExecutionFailed("Buffer has no ID".to_string()))?;
+
+    // Old chunks stay until the new ones are embedded, so unchanged chunk
+    // text can reuse their embeddings
+    let old_chunk_ids: Vec<i64> = storage
+        .get_chunks(buffer_id)?
+        .iter()
+        .filter_map(|c| c.id)
+        .collect();
+    let old_chunk_count = old_chunk_ids.len();
+    let had_embeddings = storage.get_embedding_stats(buffer_id)?.embedded_chunks > 0;
+
+    // Re-chunk before updating, so a bad strategy leaves the buffer as is
+    let chunker = create_chunker(strategy)?;
+    let meta = ChunkerMetadata::with_size_and_overlap(chunk_size, overlap);
+    let chunks = chunker.chunk(buffer_id, &new_content, Some(&meta))?;
+    let new_chunk_count = chunks.len();
+
+    let saved_version = keep_versions
+        .map(|keep| storage.save_buffer_version(buffer_id, keep))
+        .transpose()?;
+
+    // Update buffer content
+    let mut metadata = buffer.metadata.clone();
+    metadata.line_ending = LineEnding::detect(&new_content).map(|e| e.name().to_string());
+    metadata.whitespace_normalized = false;
+    let updated_buffer = Buffer {
+        id: Some(buffer_id),
+        name: buffer.name.clone(),
+        content: new_content,
+        source: buffer.source.clone(),
+        metadata,
+    };
+    storage.update_buffer(&updated_buffer)?;
+    storage.add_chunks(buffer_id, &chunks)?;
+
+    // Embed the new chunks, or only keep the embeddings of unchanged ones
+    let embedded = if embed {
+        create_embedder()
+            .and_then(|embedder| {
+                crate::search::embed_buffer_chunks_incremental_with_progress(
+                    storage,
+                    embedder.as_ref(),
+                    buffer_id,
+                    false,
+                    embed_batch_size(),
+                    embed_progress(),
+                )
+            })
+            .map(|result| (Some(result), 0))
+    } else {
+        storage
+            .copy_embeddings_by_content_hash(&old_chunk_ids, buffer_id)
+            .map(|kept| (None, kept))
+    };
+
+    // Delete the old chunks (this cascades to embeddings) even if embedding
+    // failed, so the buffer never holds both versions
+    storage.delete_chunks_by_id(&old_chunk_ids)?;
+    let (embed_result, kept_embeddings) = embedded?;
+
+    let unembedded_chunks = if had_embeddings && !embed {
+        Some(storage.get_chunks_without_embedding(buffer_id)?.len())
+    } else {
+        None
+    };
+
+    Ok(BufferUpdate {
+        old_chunk_count,
+        new_chunk_count,
+        saved_version,
+        embed_result,
+        kept_embeddings,
+        unembedded_chunks,
+    })
+}
+
+/// Appends the embedding outcome of a buffer update to text output.
+fn write_update_embeddings(output: &mut String, update: &BufferUpdate, buffer_name: &str) {
+    if let Some(ref result) = update.embed_result {
+        let _ = writeln!(
+            output,
+            "Embedded {} chunks using model '{}' ({} reused from unchanged content)",
+            result.embedded_count, result.model_name, result.reused_count
+        );
+    }
+    if update.kept_embeddings > 0 {
+        let _ = writeln!(
+            output,
+            "Kept {} embeddings of unchanged chunks",
+            update.kept_embeddings
+        );
+    }
+    if let Some(unembedded) = update.unembedded_chunks.filter(|&n| n > 0) {
+        let _ = writeln!(
+            output,
+            "{unembedded} chunks need embedding; run 'rlm-rs chunk embed {buffer_name}' to embed them"
+        );
+    }
+}
+
 fn cmd_update_buffer(
     db_path: &std::path::Path,
     identifier: &str,
     content: Option<&str>,
     embed: bool,
-    strategy: &str,
-    chunk_size: usize,
-    overlap: usize,
+    chunking: (&str, usize, usize),
+    keep_versions: Option<usize>,
     format: OutputFormat,
 ) -> Result<String> {
     let mut storage = open_storage(db_path)?;
@@ -640,43 +2845,15 @@ fn cmd_update_buffer(
     };
 
     let content_size = new_content.len();
-
-    // Get old chunk count for comparison
-    let old_chunk_count = storage.chunk_count(buffer_id)?;
-
-    // Delete existing chunks (this cascades to embeddings)
-    storage.delete_chunks(buffer_id)?;
-
-    // Update buffer content
-    let updated_buffer = Buffer {
-        id: Some(buffer_id),
-        name: buffer.name.clone(),
-        content: new_content.clone(),
-        source: buffer.source.clone(),
-        metadata: buffer.metadata.clone(),
-    };
-    storage.update_buffer(&updated_buffer)?;
-
-    // Re-chunk the content
-    let chunker = create_chunker(strategy)?;
-    let meta = ChunkerMetadata::with_size_and_overlap(chunk_size, overlap);
-    let chunks = chunker.chunk(buffer_id, &new_content, Some(&meta))?;
-    let new_chunk_count = chunks.len();
-    storage.add_chunks(buffer_id, &chunks)?;
-
-    // Optionally embed the new chunks
-    let embed_result = if embed {
-        let embedder = create_embedder()?;
-        let result = crate::search::embed_buffer_chunks_incremental(
-            &mut storage,
-            embedder.as_ref(),
-            buffer_id,
-            false,
-        )?;
-        Some(result)
-    } else {
-        None
-    };
+    let strategy = chunking.0;
+    let update = replace_buffer_content(
+        &mut storage,
+        &buffer,
+        new_content,
+        chunking,
+        embed,
+        keep_versions,
+    )?;
 
     match format {
         OutputFormat::Text => {
@@ -687,14 +2864,12 @@ fn cmd_update_buffer(
             ));
             output.push_str(&format!(
                 "Chunks: {} -> {} (using {} strategy)\n",
-                old_chunk_count, new_chunk_count, strategy
+                update.old_chunk_count, update.new_chunk_count, strategy
             ));
-            if let Some(ref result) = embed_result {
-                output.push_str(&format!(
-                    "Embedded {} chunks using model '{}'\n",
-                    result.embedded_count, result.model_name
-                ));
+            if let Some(version) = update.saved_version {
+                output.push_str(&format!("Saved previous content as version {version}\n"));
             }
+            write_update_embeddings(&mut output, &update, &buffer_name);
             Ok(output)
         }
         OutputFormat::Json | OutputFormat::Ndjson => {
@@ -702,13 +2877,103 @@ fn cmd_update_buffer(
                 "buffer_id": buffer_id,
                 "buffer_name": buffer_name,
                 "content_size": content_size,
-                "old_chunk_count": old_chunk_count,
-                "new_chunk_count": new_chunk_count,
+                "old_chunk_count": update.old_chunk_count,
+                "new_chunk_count": update.new_chunk_count,
                 "strategy": strategy,
-                "embedded": embed_result.as_ref().map(|r| serde_json::json!({
+                "saved_version": update.saved_version,
+                "embedded": update.embed_result.as_ref().map(|r| serde_json::json!({
+                    "count": r.embedded_count,
+                    "reused": r.reused_count,
+                    "model": r.model_name
+                })),
+                "kept_embeddings": update.kept_embeddings,
+                "unembedded_chunks": update.unembedded_chunks
+            });
+            Ok(serde_json::to_string_pretty(&json).unwrap_or_default())
+        }
+    }
+}
+
+fn cmd_history(
+    db_path: &std::path::Path,
+    identifier: &str,
+    format: OutputFormat,
+) -> Result<String> {
+    let storage = open_storage(db_path)?;
+    let buffer = resolve_buffer(&storage, identifier)?;
+    let buffer_id = buffer
+        .id
+        .ok_or_else(|| CommandError::ExecutionFailed("Buffer has no ID".to_string()))?;
+    let buffer_name = buffer.name.unwrap_or_else(|| buffer_id.to_string());
+
+    let versions = storage.list_buffer_versions(buffer_id)?;
+    Ok(format_buffer_versions(&buffer_name, &versions, format))
+}
+
+fn cmd_revert_buffer(
+    db_path: &std::path::Path,
+    identifier: &str,
+    version: i64,
+    embed: bool,
+    keep_versions: usize,
+    format: OutputFormat,
+) -> Result<String> {
+    let mut storage = open_storage(db_path)?;
+    let buffer = resolve_buffer(&storage, identifier)?;
+    let buffer_id = buffer
+        .id
+        .ok_or_else(|| CommandError::ExecutionFailed("Buffer has no ID".to_string()))?;
+    let buffer_name = buffer.name.clone().unwrap_or_else(|| buffer_id.to_string());
+
+    let content = storage
+        .get_buffer_version(buffer_id, version)?
+        .ok_or_else(|| {
+            CommandError::InvalidArgument(format!(
+                "buffer '{buffer_name}' has no version {version} (see 'rlm-rs history {buffer_name}')"
+            ))
+        })?;
+    let content_size = content.len();
+
+    let update = replace_buffer_content(
+        &mut storage,
+        &buffer,
+        content,
+        ("semantic", DEFAULT_CHUNK_SIZE, DEFAULT_OVERLAP),
+        embed,
+        Some(keep_versions),
+    )?;
+
+    match format {
+        OutputFormat::Text => {
+            let mut output = format!(
+                "Reverted buffer '{buffer_name}' to version {version} ({content_size} bytes)\n"
+            );
+            if let Some(saved) = update.saved_version {
+                output.push_str(&format!("Saved previous content as version {saved}\n"));
+            }
+            output.push_str(&format!(
+                "Chunks: {} -> {}\n",
+                update.old_chunk_count, update.new_chunk_count
+            ));
+            write_update_embeddings(&mut output, &update, &buffer_name);
+            Ok(output)
+        }
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            let json = serde_json::json!({
+                "buffer_id": buffer_id,
+                "buffer_name": buffer_name,
+                "version": version,
+                "content_size": content_size,
+                "saved_version": update.saved_version,
+                "old_chunk_count": update.old_chunk_count,
+                "new_chunk_count": update.new_chunk_count,
+                "embedded": update.embed_result.as_ref().map(|r| serde_json::json!({
                     "count": r.embedded_count,
+                    "reused": r.reused_count,
                     "model": r.model_name
-                }))
+                })),
+                "kept_embeddings": update.kept_embeddings,
+                "unembedded_chunks": update.unembedded_chunks
             });
             Ok(serde_json::to_string_pretty(&json).unwrap_or_default())
         }
@@ -744,6 +3009,173 @@ fn meets_relevance_threshold(relevance: &str, min_relevance: &str) -> bool {
     relevance_order(relevance) <= relevance_order(min_relevance)
 }
 
+/// An aggregate input entry that isn't a valid analyst finding.
+#[derive(Debug, serde::Serialize)]
+struct MalformedFinding {
+    /// Position in a JSON array input.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    index: Option<usize>,
+    /// 1-based line number in NDJSON input.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line: Option<usize>,
+    error: String,
+}
+
+impl MalformedFinding {
+    /// Describes where the entry is, e.g. `entry 3` or `line 7`.
+    fn location(&self) -> String {
+        match (self.index, self.line) {
+            (_, Some(line)) => format!("line {line}"),
+            (index, None) => format!("entry {}", index.unwrap_or_default()),
+        }
+    }
+}
+
+/// Parses analyst findings from a JSON array or from NDJSON (one finding
+/// per line), setting aside entries that aren't valid findings.
+///
+/// Input starting with `[` is a JSON array and must be valid JSON as a
+/// whole; anything else is read line by line, skipping blank lines.
+fn parse_findings(input: &str) -> Result<(Vec<AnalystFinding>, Vec<MalformedFinding>)> {
+    let mut findings = Vec::new();
+    let mut malformed = Vec::new();
+
+    if input.trim_start().starts_with('[') {
+        let entries: Vec<serde_json::Value> = serde_json::from_str(input)
+            .map_err(|e| CommandError::ExecutionFailed(format!("Invalid JSON input: {e}")))?;
+        for (index, entry) in entries.into_iter().enumerate() {
+            match serde_json::from_value::<AnalystFinding>(entry) {
+                Ok(finding) => findings.push(finding),
+                Err(e) => malformed.push(MalformedFinding {
+                    index: Some(index),
+                    line: None,
+                    error: e.to_string(),
+                }),
+            }
+        }
+    } else {
+        for (number, line) in input.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<AnalystFinding>(line) {
+                Ok(finding) => findings.push(finding),
+                Err(e) => malformed.push(MalformedFinding {
+                    index: None,
+                    line: Some(number + 1),
+                    error: e.to_string(),
+                }),
+            }
+        }
+    }
+
+    Ok((findings, malformed))
+}
+
+/// Stores `unique` findings as a new buffer with one chunk per finding, and
+/// embeds the chunks so the findings are searchable.
+///
+/// The buffer holds one finding per line. Each chunk records the relevance
+/// and source chunk ID of the first analyst finding that reported it.
+/// Returns the number of chunks and the number embedded.
+fn store_finding_chunks(
+    storage: &mut SqliteStorage,
+    name: &str,
+    sorted: &[AnalystFinding],
+    unique: &[&str],
+) -> Result<(usize, usize)> {
+    let mut content = String::new();
+    let mut entries: Vec<(std::ops::Range<usize>, &AnalystFinding)> = Vec::new();
+    let mut stored: Vec<&str> = Vec::new();
+    for f in sorted {
+        for finding in &f.findings {
+            if !unique.contains(&finding.as_str()) || stored.contains(&finding.as_str()) {
+                continue;
+            }
+            stored.push(finding);
+            let start = content.len();
+            content.push_str(finding);
+            entries.push((start..content.len(), f));
+            content.push('\n');
+        }
+    }
+
+    let mut buffer = Buffer::from_named(name.to_string(), content);
+    let buffer_id = storage.add_buffer(&buffer)?;
+    let chunks: Vec<Chunk> = entries
+        .into_iter()
+        .enumerate()
+        .map(|(index, (range, source))| {
+            let mut chunk = Chunk::with_strategy(
+                buffer_id,
+                buffer.content[range.clone()].to_string(),
+                range,
+                index,
+                "findings",
+            );
+            chunk.set_token_count(chunk.estimate_tokens());
+            chunk.set_custom_value("relevance", serde_json::json!(source.relevance));
+            chunk.set_custom_value("source_chunk_id", serde_json::json!(source.chunk_id));
+            chunk
+        })
+        .collect();
+    storage.add_chunks(buffer_id, &chunks)?;
+    buffer.id = Some(buffer_id);
+    buffer.set_chunk_count(chunks.len());
+    storage.update_buffer(&buffer)?;
+
+    let embedder = create_embedder()?;
+    let embedded_count = embed_buffer_chunks_with_progress(
+        storage,
+        embedder.as_ref(),
+        buffer_id,
+        embed_batch_size(),
+        embed_progress(),
+    )?;
+    Ok((chunks.len(), embedded_count))
+}
+
+/// Merges findings that paraphrase an earlier one, returning how many were
+/// dropped.
+///
+/// A finding is dropped when its embedding's cosine similarity to any kept
+/// finding reaches `threshold`; the first occurrence is kept.
+fn dedup_by_similarity(
+    findings: &mut Vec<&str>,
+    embedder: &dyn Embedder,
+    threshold: f32,
+) -> Result<usize> {
+    let mut embeddings = Vec::with_capacity(findings.len());
+    for batch in findings.chunks(embed_batch_size().max(1)) {
+        embeddings.extend(embedder.embed_batch(batch)?);
+    }
+
+    let mut kept: Vec<usize> = Vec::with_capacity(findings.len());
+    for (i, embedding) in embeddings.iter().enumerate() {
+        if !kept
+            .iter()
+            .any(|&k| cosine_similarity(&embeddings[k], embedding) >= threshold)
+        {
+            kept.push(i);
+        }
+    }
+
+    let merged = findings.len() - kept.len();
+    let deduped: Vec<&str> = kept.into_iter().map(|i| findings[i]).collect();
+    *findings = deduped;
+    Ok(merged)
+}
+
+/// Describes why a finding with `relevance` is dropped at `min_relevance`.
+fn relevance_drop_reason(relevance: &str, min_relevance: &str) -> String {
+    if relevance_order(relevance) > relevance_order("none") {
+        "unrecognized relevance".to_string()
+    } else {
+        format!("below minimum relevance '{min_relevance}'")
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn cmd_aggregate(
     db_path: &std::path::Path,
     buffer: Option<&str>,
@@ -751,8 +3183,20 @@ fn cmd_aggregate(
     group_by: &str,
     sort_by: &str,
     output_buffer: Option<&str>,
+    explain: bool,
+    dedup_threshold: Option<f32>,
+    chunk_findings: bool,
     format: OutputFormat,
 ) -> Result<String> {
+    if let Some(threshold) = dedup_threshold
+        && !(threshold > 0.0 && threshold <= 1.0)
+    {
+        return Err(CommandError::InvalidArgument(format!(
+            "--dedup-threshold must be in (0, 1], got {threshold}"
+        ))
+        .into());
+    }
+
     let mut storage = open_storage(db_path)?;
 
     // Read findings from buffer or stdin
@@ -767,15 +3211,21 @@ fn cmd_aggregate(
         buf
     };
 
-    // Parse findings
-    let findings: Vec<AnalystFinding> = serde_json::from_str(&input)
-        .map_err(|e| CommandError::ExecutionFailed(format!("Invalid JSON input: {e}")))?;
+    let (findings, malformed) = parse_findings(&input)?;
 
-    // Filter by relevance
+    // Filter by relevance, counting what is dropped at each level
+    let mut dropped: std::collections::BTreeMap<String, usize> = std::collections::BTreeMap::new();
     let filtered: Vec<_> = findings
         .into_iter()
-        .filter(|f| meets_relevance_threshold(&f.relevance, min_relevance))
+        .filter(|f| {
+            let keep = meets_relevance_threshold(&f.relevance, min_relevance);
+            if !keep {
+                *dropped.entry(f.relevance.clone()).or_default() += 1;
+            }
+            keep
+        })
         .collect();
+    let dropped_count: usize = dropped.values().sum();
 
     // Sort findings
     let mut sorted = filtered;
@@ -822,6 +3272,13 @@ fn cmd_aggregate(
             }
         }
     }
+    let near_duplicates = match dedup_threshold {
+        Some(threshold) => {
+            let embedder = create_embedder()?;
+            dedup_by_similarity(&mut all_findings, embedder.as_ref(), threshold)?
+        }
+        None => 0,
+    };
 
     // Build summary stats
     let total_findings = sorted.len();
@@ -831,10 +3288,20 @@ fn cmd_aggregate(
     let unique_findings_count = all_findings.len();
 
     // Store in output buffer if requested
+    let mut finding_chunks = None;
     if let Some(out_name) = output_buffer {
-        let output_content = serde_json::to_string_pretty(&sorted).unwrap_or_default();
-        let out_buffer = Buffer::from_named(out_name.to_string(), output_content);
-        storage.add_buffer(&out_buffer)?;
+        if chunk_findings {
+            finding_chunks = Some(store_finding_chunks(
+                &mut storage,
+                out_name,
+                &sorted,
+                &all_findings,
+            )?);
+        } else {
+            let output_content = serde_json::to_string_pretty(&sorted).unwrap_or_default();
+            let out_buffer = Buffer::from_named(out_name.to_string(), output_content);
+            storage.add_buffer(&out_buffer)?;
+        }
     }
 
     match format {
@@ -845,7 +3312,37 @@ fn cmd_aggregate(
                 "Relevance: {} high, {} medium, {} low\n",
                 high_count, medium_count, low_count
             ));
-            output.push_str(&format!("Unique findings: {}\n\n", unique_findings_count));
+            output.push_str(&format!("Unique findings: {}\n", unique_findings_count));
+            if let Some(threshold) = dedup_threshold {
+                let _ = writeln!(
+                    output,
+                    "Near-duplicates merged: {near_duplicates} (similarity >= {threshold})"
+                );
+            }
+            if explain {
+                let _ = writeln!(
+                    output,
+                    "Filtered out: {dropped_count} (minimum relevance: {min_relevance})"
+                );
+                for (relevance, count) in &dropped {
+                    let _ = writeln!(
+                        output,
+                        "  {relevance}: {count} ({})",
+                        relevance_drop_reason(relevance, min_relevance)
+                    );
+                }
+                let _ = writeln!(output, "Malformed entries: {}", malformed.len());
+                for entry in &malformed {
+                    let _ = writeln!(output, "  {}: {}", entry.location(), entry.error);
+                }
+            } else if !malformed.is_empty() {
+                let _ = writeln!(
+                    output,
+                    "Skipped {} malformed entries (see --explain)",
+                    malformed.len()
+                );
+            }
+            output.push('\n');
 
             for (group, items) in &grouped {
                 output.push_str(&format!("## {} ({} chunks)\n", group, items.len()));
@@ -867,55 +3364,344 @@ fn cmd_aggregate(
                     output_buffer.unwrap_or("")
                 ));
             }
+            if let Some((chunk_count, embedded)) = finding_chunks {
+                let _ = writeln!(
+                    output,
+                    "Stored {chunk_count} findings as chunks ({embedded} embedded)"
+                );
+            }
+
+            Ok(output)
+        }
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            let mut json = serde_json::json!({
+                "summary": {
+                    "total_findings": total_findings,
+                    "high_relevance": high_count,
+                    "medium_relevance": medium_count,
+                    "low_relevance": low_count,
+                    "unique_findings": unique_findings_count,
+                    "dedup_threshold": dedup_threshold,
+                    "near_duplicates_merged": near_duplicates
+                },
+                "grouped": grouped,
+                "findings": sorted,
+                "all_findings_deduplicated": all_findings,
+                "output_buffer": output_buffer,
+                "output_chunks": finding_chunks.map(|(chunk_count, _)| chunk_count),
+                "embedded_chunks": finding_chunks.map(|(_, embedded)| embedded),
+                "malformed": malformed
+            });
+            if explain {
+                json["explain"] = serde_json::json!({
+                    "min_relevance": min_relevance,
+                    "filtered_out": dropped_count,
+                    "by_relevance": dropped
+                        .iter()
+                        .map(|(relevance, count)| serde_json::json!({
+                            "relevance": relevance,
+                            "count": count,
+                            "reason": relevance_drop_reason(relevance, min_relevance)
+                        }))
+                        .collect::<Vec<_>>()
+                });
+            }
+            Ok(serde_json::to_string_pretty(&json).unwrap_or_default())
+        }
+    }
+}
+
+fn cmd_export_buffers(
+    db_path: &std::path::Path,
+    output: Option<&std::path::Path>,
+    pretty: bool,
+    format: OutputFormat,
+) -> Result<String> {
+    let storage = open_storage(db_path)?;
+    let content = match format {
+        OutputFormat::Text => storage.export_buffers()?,
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            // A buffer array that import-buffers can read back, each with the
+            // hash it verifies content against
+            let mut buffers = storage.list_buffers()?;
+            for buffer in &mut buffers {
+                if buffer.metadata.content_hash.is_none() {
+                    buffer.compute_hash();
+                }
+            }
+            let json = if pretty {
+                serde_json::to_string_pretty(&buffers)
+            } else {
+                serde_json::to_string(&buffers)
+            };
+            json.map_err(|e| StorageError::Serialization(e.to_string()))?
+        }
+    };
+
+    if let Some(path) = output {
+        write_file(path, &content)?;
+        Ok(format!("Exported buffers to: {}\n", path.display()))
+    } else {
+        // Write to stdout
+        let stdout = io::stdout();
+        let mut handle = stdout.lock();
+        handle.write_all(content.as_bytes()).map_err(|e| {
+            CommandError::ExecutionFailed(format!("Failed to write to stdout: {e}"))
+        })?;
+        Ok(String::new()) // Content already written
+    }
+}
+
+fn cmd_import_buffers(
+    db_path: &std::path::Path,
+    input: &std::path::Path,
+    merge: bool,
+    chunk: bool,
+    chunking: (&str, usize, usize),
+    embed: bool,
+    format: OutputFormat,
+) -> Result<String> {
+    let mut storage = open_storage(db_path)?;
+    let buffers: Vec<Buffer> = serde_json::from_str(&read_file(input)?).map_err(|e| {
+        CommandError::InvalidArgument(format!(
+            "{} is not a buffer dump from 'export-buffers --format json': {e}",
+            input.display()
+        ))
+    })?;
+
+    // Resolve collisions before writing anything so a rejected import
+    // leaves storage untouched
+    let mut existing_ids = Vec::with_capacity(buffers.len());
+    for buffer in &buffers {
+        let existing = match &buffer.name {
+            Some(name) => storage.get_buffer_by_name(name)?.and_then(|b| b.id),
+            None => None,
+        };
+        if let (Some(_), Some(name)) = (existing, &buffer.name)
+            && !merge
+        {
+            return Err(CommandError::ExecutionFailed(format!(
+                "buffer '{name}' already exists (use --merge to update it)"
+            ))
+            .into());
+        }
+        existing_ids.push(existing);
+    }
+
+    let (strategy, chunk_size, overlap) = chunking;
+    let chunk_meta = ChunkerMetadata::with_size_and_overlap(chunk_size, overlap);
+    if chunk {
+        // Reject an unknown strategy before writing anything
+        create_chunker(strategy)?;
+    }
+    let embedder = if embed {
+        Some(create_embedder()?)
+    } else {
+        None
+    };
+
+    let mut warnings = Vec::new();
+    let mut imported = Vec::with_capacity(buffers.len());
+    let mut context = storage.load_context()?;
+
+    for (mut buffer, existing) in buffers.into_iter().zip(existing_ids) {
+        let label = buffer
+            .name
+            .clone()
+            .unwrap_or_else(|| "<unnamed>".to_string());
+
+        // Verify the exported hash, then store a fresh one
+        let exported_hash = buffer.metadata.content_hash.take();
+        buffer.compute_hash();
+        if let Some(exported) = exported_hash
+            && buffer.metadata.content_hash.as_deref() != Some(exported.as_str())
+        {
+            warnings.push(format!(
+                "content hash mismatch for buffer '{label}'; content may have been modified"
+            ));
+        }
+        buffer.metadata.size = buffer.content.len();
+        buffer.metadata.chunk_count = None;
+
+        // Without --chunk, a merged buffer keeps its chunks if the content is
+        // unchanged, else is re-chunked with the strategy that made them
+        let rechunk_with = if chunk {
+            Some(create_chunker(strategy)?)
+        } else if let Some(id) = existing
+            && storage
+                .get_buffer(id)?
+                .is_none_or(|b| b.content != buffer.content)
+        {
+            storage
+                .get_chunks(id)?
+                .first()
+                .map(|c| {
+                    c.metadata
+                        .strategy
+                        .as_deref()
+                        .and_then(|name| create_chunker(name).ok())
+                        .map_or_else(|| create_chunker(strategy), Ok)
+                })
+                .transpose()?
+        } else {
+            None
+        };
+
+        let buffer_id = if let Some(id) = existing {
+            buffer.id = Some(id);
+            if rechunk_with.is_some() {
+                storage.delete_chunks(id)?;
+            }
+            storage.update_buffer(&buffer)?;
+            id
+        } else {
+            buffer.id = None;
+            storage.add_buffer(&buffer)?
+        };
+
+        if let Some(chunker) = rechunk_with {
+            let chunks = chunker.chunk(buffer_id, &buffer.content, Some(&chunk_meta))?;
+            storage.add_chunks(buffer_id, &chunks)?;
+            buffer.id = Some(buffer_id);
+            buffer.set_chunk_count(chunks.len());
+            storage.update_buffer(&buffer)?;
+
+            if let Some(embedder) = &embedder {
+                embed_buffer_chunks_with_progress(
+                    &mut storage,
+                    embedder.as_ref(),
+                    buffer_id,
+                    embed_batch_size(),
+                    embed_progress(),
+                )?;
+            }
+        }
+
+        if let Some(context) = context.as_mut() {
+            context.add_buffer(buffer_id);
+        }
+        imported.push((buffer_id, label, existing.is_some()));
+    }
 
+    if let Some(context) = &context {
+        storage.save_context(context)?;
+    }
+
+    let updated = imported.iter().filter(|(_, _, updated)| *updated).count();
+    let added = imported.len() - updated;
+
+    match format {
+        OutputFormat::Text => {
+            let mut output = format!(
+                "Imported {} buffer(s) from {} ({added} added, {updated} updated)\n",
+                imported.len(),
+                input.display()
+            );
+            for warning in &warnings {
+                let _ = writeln!(output, "Warning: {warning}");
+            }
             Ok(output)
         }
         OutputFormat::Json | OutputFormat::Ndjson => {
             let json = serde_json::json!({
-                "summary": {
-                    "total_findings": total_findings,
-                    "high_relevance": high_count,
-                    "medium_relevance": medium_count,
-                    "low_relevance": low_count,
-                    "unique_findings": unique_findings_count
-                },
-                "grouped": grouped,
-                "findings": sorted,
-                "all_findings_deduplicated": all_findings,
-                "output_buffer": output_buffer
+                "imported": imported.len(),
+                "added": added,
+                "updated": updated,
+                "buffers": imported.iter().map(|(id, name, updated)| serde_json::json!({
+                    "id": id,
+                    "name": name,
+                    "action": if *updated { "updated" } else { "added" }
+                })).collect::<Vec<_>>(),
+                "warnings": warnings
             });
             Ok(serde_json::to_string_pretty(&json).unwrap_or_default())
         }
     }
 }
 
-fn cmd_export_buffers(
-    db_path: &std::path::Path,
-    output: Option<&std::path::Path>,
-    _pretty: bool,
-    _format: OutputFormat,
-) -> Result<String> {
-    let storage = open_storage(db_path)?;
-    let content = storage.export_buffers()?;
+/// Type of a value given to `var` or `global` with `--type`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ContextValueType {
+    String,
+    Integer,
+    Float,
+    Boolean,
+    List,
+    Map,
+}
 
-    if let Some(path) = output {
-        write_file(path, &content)?;
-        Ok(format!("Exported buffers to: {}\n", path.display()))
-    } else {
-        // Write to stdout
-        let stdout = io::stdout();
-        let mut handle = stdout.lock();
-        handle.write_all(content.as_bytes()).map_err(|e| {
-            CommandError::ExecutionFailed(format!("Failed to write to stdout: {e}"))
-        })?;
-        Ok(String::new()) // Content already written
+impl ContextValueType {
+    fn parse(name: &str) -> Result<Self> {
+        match name.to_lowercase().as_str() {
+            "string" => Ok(Self::String),
+            "integer" | "int" => Ok(Self::Integer),
+            "float" | "number" => Ok(Self::Float),
+            "boolean" | "bool" => Ok(Self::Boolean),
+            "list" => Ok(Self::List),
+            "map" => Ok(Self::Map),
+            _ => Err(CommandError::InvalidArgument(format!(
+                "unknown value type '{name}' (expected string, integer, float, boolean, list, or map)"
+            ))
+            .into()),
+        }
+    }
+
+    /// Parses `raw` as a value of this type.
+    fn value(self, raw: &str) -> Result<ContextValue> {
+        let invalid = |expected: &str| {
+            CommandError::InvalidArgument(format!("'{raw}' is not a valid {expected}"))
+        };
+        let json = || serde_json::from_str::<serde_json::Value>(raw).ok();
+        let value = match self {
+            Self::String => ContextValue::String(raw.to_string()),
+            Self::Integer => {
+                ContextValue::Integer(raw.trim().parse().map_err(|_| invalid("integer"))?)
+            }
+            Self::Float => ContextValue::Float(raw.trim().parse().map_err(|_| invalid("float"))?),
+            Self::Boolean => match raw.trim().to_lowercase().as_str() {
+                "true" | "yes" | "1" => ContextValue::Boolean(true),
+                "false" | "no" | "0" => ContextValue::Boolean(false),
+                _ => return Err(invalid("boolean").into()),
+            },
+            Self::List => ContextValue::from_json(
+                json()
+                    .filter(serde_json::Value::is_array)
+                    .ok_or_else(|| invalid("JSON array"))?,
+            ),
+            Self::Map => ContextValue::from_json(
+                json()
+                    .filter(serde_json::Value::is_object)
+                    .ok_or_else(|| invalid("JSON object"))?,
+            ),
+        };
+        Ok(value)
+    }
+}
+
+/// Formats a context variable or global for `var`/`global` output.
+///
+/// Text shows the value with its type, lists and maps as indented JSON;
+/// JSON keeps the tagged form the value is stored in.
+fn format_context_value(name: &str, value: &ContextValue, format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Text => match value {
+            ContextValue::List(_) | ContextValue::Map(_) => format!(
+                "{name} ({}) = {}\n",
+                value.type_name(),
+                serde_json::to_string_pretty(&value.to_json()).unwrap_or_default()
+            ),
+            _ => format!("{name} ({}) = {value}\n", value.type_name()),
+        },
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            serde_json::to_string_pretty(value).unwrap_or_default()
+        }
     }
 }
 
 fn cmd_variable(
     db_path: &std::path::Path,
     name: &str,
-    value: Option<&str>,
+    value: Option<ContextValue>,
     delete: bool,
     format: OutputFormat,
 ) -> Result<String> {
@@ -929,26 +3715,22 @@ fn cmd_variable(
     }
 
     if let Some(v) = value {
-        context.set_variable(name.to_string(), ContextValue::String(v.to_string()));
+        let output = format!("Set variable: {name} = {v} ({})\n", v.type_name());
+        context.set_variable(name.to_string(), v);
         storage.save_context(&context)?;
-        Ok(format!("Set variable: {name} = {v}\n"))
+        Ok(output)
     } else {
-        context.get_variable(name).map_or_else(
-            || Ok(format!("Variable '{name}' not found\n")),
-            |v| match format {
-                OutputFormat::Text => Ok(format!("{name} = {v:?}\n")),
-                OutputFormat::Json | OutputFormat::Ndjson => {
-                    Ok(serde_json::to_string_pretty(v).unwrap_or_default())
-                }
-            },
-        )
+        Ok(context.get_variable(name).map_or_else(
+            || format!("Variable '{name}' not found\n"),
+            |v| format_context_value(name, v, format),
+        ))
     }
 }
 
 fn cmd_global(
     db_path: &std::path::Path,
     name: &str,
-    value: Option<&str>,
+    value: Option<ContextValue>,
     delete: bool,
     format: OutputFormat,
 ) -> Result<String> {
@@ -962,19 +3744,68 @@ fn cmd_global(
     }
 
     if let Some(v) = value {
-        context.set_global(name.to_string(), ContextValue::String(v.to_string()));
+        let output = format!("Set global: {name} = {v} ({})\n", v.type_name());
+        context.set_global(name.to_string(), v);
         storage.save_context(&context)?;
-        Ok(format!("Set global: {name} = {v}\n"))
+        Ok(output)
     } else {
-        context.get_global(name).map_or_else(
-            || Ok(format!("Global '{name}' not found\n")),
-            |v| match format {
-                OutputFormat::Text => Ok(format!("{name} = {v:?}\n")),
-                OutputFormat::Json | OutputFormat::Ndjson => {
-                    Ok(serde_json::to_string_pretty(v).unwrap_or_default())
-                }
-            },
-        )
+        Ok(context.get_global(name).map_or_else(
+            || format!("Global '{name}' not found\n"),
+            |v| format_context_value(name, v, format),
+        ))
+    }
+}
+
+fn cmd_context_buffers(db_path: &std::path::Path, format: OutputFormat) -> Result<String> {
+    let storage = open_storage(db_path)?;
+    let context = storage.load_context()?.unwrap_or_else(Context::new);
+
+    let refs = context
+        .buffer_ids
+        .iter()
+        .map(|&id| Ok((id, storage.get_buffer(id)?)))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(format_context_buffers(&refs, format))
+}
+
+fn cmd_context_prune(db_path: &std::path::Path, format: OutputFormat) -> Result<String> {
+    let mut storage = open_storage(db_path)?;
+    let Some(mut context) = storage.load_context()? else {
+        return Ok(match format {
+            OutputFormat::Text => "No context to prune.\n".to_string(),
+            OutputFormat::Json | OutputFormat::Ndjson => {
+                let json = serde_json::json!({ "pruned": [], "remaining": 0 });
+                serde_json::to_string_pretty(&json).unwrap_or_default()
+            }
+        });
+    };
+
+    let existing: std::collections::HashSet<i64> = storage.buffer_ids()?.into_iter().collect();
+    let pruned = context.retain_buffers(&existing);
+    if !pruned.is_empty() {
+        storage.save_context(&context)?;
+    }
+
+    match format {
+        OutputFormat::Text => {
+            if pruned.is_empty() {
+                return Ok("No dangling buffer references.\n".to_string());
+            }
+            let ids: Vec<String> = pruned.iter().map(ToString::to_string).collect();
+            Ok(format!(
+                "Pruned {} dangling buffer reference(s): {}\n",
+                pruned.len(),
+                ids.join(", ")
+            ))
+        }
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            let json = serde_json::json!({
+                "pruned": pruned,
+                "remaining": context.buffer_count(),
+            });
+            Ok(serde_json::to_string_pretty(&json).unwrap_or_default())
+        }
     }
 }
 
@@ -1110,20 +3941,140 @@ fn cmd_dispatch(
 
 // ==================== Search Commands ====================
 
-#[allow(clippy::too_many_arguments)]
+/// On/off switches of `search`.
+#[derive(Debug, Clone, Copy, Default)]
+#[allow(clippy::struct_excessive_bools)]
+struct SearchFlags {
+    /// Pass the query to FTS5 unescaped.
+    raw_query: bool,
+    /// Include content previews.
+    preview: bool,
+    /// Include highlighted match snippets.
+    highlight: bool,
+    /// Embed unembedded chunks before a semantic search.
+    auto_embed: bool,
+    /// Print only chunk IDs.
+    ids_only: bool,
+    /// Bypass the search result cache.
+    no_cache: bool,
+}
+
+#[allow(clippy::too_many_arguments, clippy::too_many_lines)]
 fn cmd_search(
     db_path: &std::path::Path,
     query: &str,
     top_k: usize,
+    offset: usize,
     threshold: f32,
     mode: &str,
     rrf_k: u32,
+    semantic_weight: f64,
+    bm25_weight: f64,
     buffer_filter: Option<&str>,
-    preview: bool,
     preview_len: usize,
+    title_boost: f64,
+    buffer_type: Option<&str>,
+    min_results: usize,
+    diversity: Option<f32>,
+    tags: &[String],
+    buffer_meta: &[String],
+    flags: SearchFlags,
+    nprobe: Option<usize>,
+    metric: &str,
+    timeout_ms: Option<u64>,
     format: OutputFormat,
 ) -> Result<String> {
-    let storage = open_storage(db_path)?;
+    let SearchFlags {
+        raw_query,
+        preview,
+        highlight,
+        auto_embed,
+        ids_only,
+        no_cache,
+    } = flags;
+    let tags = normalize_tags(tags)?;
+    let metric = SimilarityMetric::parse(metric).ok_or_else(|| {
+        CommandError::InvalidArgument(format!(
+            "unknown metric '{metric}' (expected cosine, dot, or euclidean)"
+        ))
+    })?;
+    let meta_filters = parse_meta_filters(buffer_meta)?;
+    if let Some(d) = diversity
+        && !(0.0..=1.0).contains(&d)
+    {
+        return Err(CommandError::InvalidArgument(format!(
+            "--diversity must be between 0.0 and 1.0, got {d}"
+        ))
+        .into());
+    }
+    for (flag, weight) in [
+        ("--semantic-weight", semantic_weight),
+        ("--bm25-weight", bm25_weight),
+    ] {
+        if !weight.is_finite() || weight < 0.0 {
+            return Err(CommandError::InvalidArgument(format!(
+                "{flag} must be a non-negative number, got {weight}"
+            ))
+            .into());
+        }
+    }
+    if semantic_weight == 0.0 && bm25_weight == 0.0 {
+        return Err(CommandError::InvalidArgument(
+            "--semantic-weight and --bm25-weight can't both be 0".to_string(),
+        )
+        .into());
+    }
+
+    let mut storage = open_storage(db_path)?;
+
+    // Content types are stored as lowercase extensions ("rs", not ".RS")
+    let buffer_type = buffer_type.map(|ct| ct.trim_start_matches('.').to_lowercase());
+    if let Some(ref content_type) = buffer_type {
+        let known = storage.list_content_types()?;
+        if !known.contains(content_type) {
+            // Keep pipelines clean: no matches means no IDs
+            if ids_only {
+                return Ok(format_search_results(
+                    &[],
+                    query,
+                    mode,
+                    offset,
+                    false,
+                    None,
+                    None,
+                    false,
+                    true,
+                    format,
+                ));
+            }
+            let known = if known.is_empty() {
+                "none".to_string()
+            } else {
+                known.join(", ")
+            };
+            let note = format!("No buffers with content type '{content_type}' (known: {known})");
+            return Ok(match format {
+                OutputFormat::Text => format!("{note}\n"),
+                OutputFormat::Json | OutputFormat::Ndjson => {
+                    let json = serde_json::json!({
+                        "query": query,
+                        "mode": mode,
+                        "offset": offset,
+                        "count": 0,
+                        "has_more": false,
+                        "results": [],
+                        "note": note
+                    });
+                    if format.is_streaming() {
+                        format_ndjson_records(json, "results")
+                    } else {
+                        serde_json::to_string_pretty(&json).unwrap_or_default()
+                    }
+                }
+            });
+        }
+    }
+
     let embedder = create_embedder()?;
 
     // Determine search mode
@@ -1133,12 +4084,30 @@ fn cmd_search(
         _ => (true, true), // hybrid is default
     };
 
+    // One extra result tells whether another page follows
     let config = SearchConfig::new()
-        .with_top_k(top_k)
+        .with_top_k(top_k + 1)
+        .with_offset(offset)
         .with_threshold(threshold)
         .with_rrf_k(rrf_k)
+        .with_weights(semantic_weight, bm25_weight)
         .with_semantic(use_semantic)
-        .with_bm25(use_bm25);
+        .with_bm25(use_bm25)
+        .with_raw_query(raw_query)
+        .with_title_boost(title_boost)
+        .with_metric(metric);
+    let config = match buffer_type {
+        Some(content_type) => config.with_buffer_type(content_type),
+        None => config,
+    };
+    let config = match diversity {
+        Some(d) => config.with_mmr_lambda(1.0 - d),
+        None => config,
+    };
+    let config = match nprobe {
+        Some(n) => config.with_ivf_nprobe(n),
+        None => config,
+    };
 
     // If buffer filter is specified, validate it exists
     let buffer_id = if let Some(identifier) = buffer_filter {
@@ -1148,29 +4117,162 @@ fn cmd_search(
         None
     };
 
-    let results = hybrid_search(&storage, embedder.as_ref(), query, &config)?;
+    // Restrict candidates to the filtered buffer, buffers bearing all tags,
+    // and buffers matching all metadata filters
+    let mut candidate_buffers: Option<Vec<i64>> = buffer_id.map(|bid| vec![bid]);
+    let mut restrict = |matching: Vec<i64>| {
+        candidate_buffers = Some(match candidate_buffers.take() {
+            Some(ids) => ids.into_iter().filter(|id| matching.contains(id)).collect(),
+            None => matching,
+        });
+    };
+    if !tags.is_empty() {
+        restrict(storage.buffers_with_tags(&tags)?);
+    }
+    if !meta_filters.is_empty() {
+        restrict(storage.buffers_with_metadata(&meta_filters)?);
+    }
 
-    // Filter by buffer if specified
-    let mut results: Vec<SearchResult> = if let Some(bid) = buffer_id {
-        let buffer_chunks: std::collections::HashSet<i64> = storage
-            .get_chunks(bid)?
-            .iter()
-            .filter_map(|c| c.id)
-            .collect();
-        results
-            .into_iter()
-            .filter(|r| buffer_chunks.contains(&r.chunk_id))
-            .collect()
+    // Embed what semantic search would otherwise skip
+    let auto_embedded = if auto_embed && use_semantic {
+        let buffer_ids = match &candidate_buffers {
+            Some(ids) => ids.clone(),
+            None => storage.buffer_ids()?,
+        };
+        Some(embed_missing_chunks(
+            &mut storage,
+            embedder.as_ref(),
+            &buffer_ids,
+            embed_batch_size(),
+        )?)
     } else {
-        results
+        None
+    };
+    // Filter inside the search, before ranked lists are cut to a page
+    let config = match candidate_buffers {
+        Some(ids) => {
+            let mut chunk_ids = std::collections::HashSet::new();
+            for bid in ids {
+                chunk_ids.extend(storage.get_chunks(bid)?.iter().filter_map(|c| c.id));
+            }
+            config.with_chunk_filter(chunk_ids)
+        }
+        None => config,
+    };
+    // Started after auto-embedding, so only the search itself is bounded
+    let deadline = timeout_ms.map(|ms| Arc::new(SearchDeadline::after(Duration::from_millis(ms))));
+    let config = match &deadline {
+        Some(d) => config.with_deadline(Arc::clone(d)),
+        None => config,
+    };
+    let partial = || deadline.as_ref().is_some_and(|d| d.expired());
+    let run = |config: &SearchConfig| hybrid_search(&storage, embedder.as_ref(), query, config);
+
+    // Everything that shapes the results; the corpus version covers content
+    let cache_key = (!no_cache).then(|| {
+        content_hash(
+            &serde_json::json!([
+                query,
+                embedder.model_name(),
+                use_semantic,
+                use_bm25,
+                raw_query,
+                top_k,
+                offset,
+                threshold,
+                rrf_k,
+                semantic_weight,
+                bm25_weight,
+                buffer_id,
+                title_boost,
+                config.buffer_type,
+                min_results,
+                diversity,
+                tags,
+                buffer_meta,
+                nprobe,
+                metric.as_str(),
+            ])
+            .to_string(),
+        )
+    });
+    let cached: Option<(Vec<SearchResult>, Option<String>)> = cache_key
+        .as_deref()
+        .and_then(|key| storage.cached_search(key).ok().flatten())
+        .and_then(|json| serde_json::from_str(&json).ok());
+
+    let (mut results, relaxation, partial) = if let Some((results, relaxation)) = cached {
+        (results, relaxation, false)
+    } else {
+        let mut results = run(&config)?;
+
+        // Relax progressively until enough results, keeping the best attempt;
+        // relaxed retries share the deadline, so stop once it has hit
+        let mut relaxation = None;
+        if results.len() < min_results && !partial() {
+            for (step, relaxed) in config.relaxations() {
+                let candidates = run(&relaxed)?;
+                let enough = candidates.len() >= min_results;
+                if candidates.len() > results.len() {
+                    results = candidates;
+                    relaxation = Some(step);
+                }
+                if enough || partial() {
+                    break;
+                }
+            }
+        }
+
+        // The cache is best-effort: a read-only or unmigrated database
+        // still searches, just without reuse. Partial results would hide
+        // the complete ones from later searches, so they are never stored.
+        let partial = partial();
+        if !partial
+            && let Some(key) = &cache_key
+            && let Ok(json) = serde_json::to_string(&(&results, &relaxation))
+        {
+            let _ = storage.cache_search(key, &json);
+        }
+        (results, relaxation, partial)
     };
+    let has_more = results.len() > top_k;
+    results.truncate(top_k);
 
     // Populate content previews if requested
     if preview {
         crate::search::populate_previews(&storage, &mut results, preview_len)?;
     }
+    if highlight {
+        crate::search::populate_snippets(&storage, &mut results, query, raw_query)?;
+    }
+
+    if null_separated(format) {
+        if ids_only {
+            return Ok(null_records(results.iter().map(|r| r.chunk_id.to_string())));
+        }
+        // chunk_id<TAB>score<TAB>preview, preview empty unless requested
+        return Ok(null_records(results.iter().map(|r| {
+            format!(
+                "{}\t{:.4}\t{}",
+                r.chunk_id,
+                r.score,
+                r.content_preview.as_deref().unwrap_or_default()
+            )
+        })));
+    }
 
-    Ok(format_search_results(&results, query, mode, format))
+    Ok(format_search_results(
+        &results,
+        query,
+        mode,
+        offset,
+        has_more,
+        relaxation.as_deref(),
+        auto_embedded,
+        partial,
+        ids_only,
+        format,
+    ))
 }
 
 /// Formats a score for display, using scientific notation for very small values.
@@ -1184,24 +4286,61 @@ fn format_score(score: f64) -> String {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn format_search_results(
     results: &[SearchResult],
     query: &str,
     mode: &str,
+    offset: usize,
+    has_more: bool,
+    relaxation: Option<&str>,
+    auto_embedded: Option<usize>,
+    partial: bool,
+    ids_only: bool,
     format: OutputFormat,
 ) -> String {
+    if ids_only {
+        let ids: Vec<i64> = results.iter().map(|r| r.chunk_id).collect();
+        return match format {
+            OutputFormat::Text => ids.iter().fold(String::new(), |mut out, id| {
+                let _ = writeln!(out, "{id}");
+                out
+            }),
+            OutputFormat::Json => serde_json::to_string(&ids).unwrap_or_default(),
+            OutputFormat::Ndjson => format_ndjson(&ids),
+        };
+    }
+
     match format {
         OutputFormat::Text => {
+            let mut output = String::new();
+            if let Some(count) = auto_embedded.filter(|&n| n > 0) {
+                let _ = writeln!(output, "Embedded {count} chunk(s) before searching\n");
+            }
+            if partial {
+                let _ = writeln!(
+                    output,
+                    "Search hit the --timeout-ms deadline; results are partial\n"
+                );
+            }
             if results.is_empty() {
-                return format!("No results found for query: \"{query}\"\n");
+                let _ = writeln!(output, "No results found for query: \"{query}\"");
+                return output;
             }
 
-            let mut output = String::new();
+            let page = if offset > 0 {
+                format!(", after the first {offset}")
+            } else {
+                String::new()
+            };
             let _ = writeln!(
                 output,
-                "Search results for \"{query}\" ({mode} mode, {} results):\n",
+                "Search results for \"{query}\" ({mode} mode, {} results{page}):\n",
                 results.len()
             );
+            if let Some(step) = relaxation {
+                let _ = writeln!(output, "Relaxed search to find more results: {step}\n");
+            }
             let _ = writeln!(
                 output,
                 "{:<10} {:<12} {:<12} {:<12}",
@@ -1224,12 +4363,26 @@ fn format_search_results(
                     result.chunk_id, result.score, semantic, bm25
                 );
 
+                if let Some(ref title) = result.title {
+                    let _ = writeln!(output, "  Title: {title}");
+                }
+
                 // Show content preview if available
                 if let Some(ref preview) = result.content_preview {
                     let _ = writeln!(output, "  Preview: {preview}");
                 }
+                if let Some(ref snippet) = result.snippet {
+                    let _ = writeln!(output, "  Match: {snippet}");
+                }
             }
 
+            if has_more {
+                let _ = writeln!(
+                    output,
+                    "\nMore results available: --offset {}",
+                    offset + results.len()
+                );
+            }
             output.push_str("\nUse 'rlm-rs chunk get <id>' to retrieve chunk content.\n");
             output
         }
@@ -1237,7 +4390,12 @@ fn format_search_results(
             let json = serde_json::json!({
                 "query": query,
                 "mode": mode,
+                "offset": offset,
                 "count": results.len(),
+                "has_more": has_more,
+                "relaxation": relaxation,
+                "auto_embedded": auto_embedded,
+                "partial": partial,
                 "results": results.iter().map(|r| {
                     let mut obj = serde_json::json!({
                         "chunk_id": r.chunk_id,
@@ -1245,35 +4403,166 @@ fn format_search_results(
                         "index": r.index,
                         "score": r.score,
                         "semantic_score": r.semantic_score,
-                        "bm25_score": r.bm25_score
+                        "bm25_score": r.bm25_score,
+                        "title": r.title
                     });
                     if let Some(ref preview) = r.content_preview {
                         obj["content_preview"] = serde_json::json!(preview);
                     }
+                    if let Some(ref snippet) = r.snippet {
+                        obj["snippet"] = serde_json::json!(snippet);
+                    }
                     obj
                 }).collect::<Vec<_>>()
             });
-            serde_json::to_string_pretty(&json).unwrap_or_default()
+            if format.is_streaming() {
+                format_ndjson_records(json, "results")
+            } else {
+                serde_json::to_string_pretty(&json).unwrap_or_default()
+            }
         }
     }
 }
 
 // ==================== Chunk Commands ====================
 
+/// How `chunk get` wraps content in text output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChunkWrap {
+    None,
+    Xml,
+    Markdown,
+}
+
+impl ChunkWrap {
+    fn parse(name: &str) -> Result<Self> {
+        match name.to_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "xml" => Ok(Self::Xml),
+            "markdown" | "md" => Ok(Self::Markdown),
+            _ => Err(CommandError::InvalidArgument(format!(
+                "unknown wrap style '{name}' (expected none, xml, or markdown)"
+            ))
+            .into()),
+        }
+    }
+}
+
+/// Wraps chunk content for pasting into a prompt.
+///
+/// XML content is left unescaped, as prompt conventions expect the literal
+/// text; only attribute values are escaped. Markdown fences are made longer
+/// than any backtick run in the content so they can't close early.
+fn wrap_chunk(chunk: &Chunk, buffer: Option<&Buffer>, wrap: ChunkWrap) -> String {
+    let id = chunk.id.unwrap_or(0);
+    let buffer_label = buffer
+        .and_then(|b| b.name.clone())
+        .unwrap_or_else(|| chunk.buffer_id.to_string());
+    let content = &chunk.content;
+    let newline = if content.ends_with('\n') { "" } else { "\n" };
+
+    match wrap {
+        ChunkWrap::None => content.clone(),
+        ChunkWrap::Xml => {
+            let escape = |value: &str| {
+                value
+                    .replace('&', "&amp;")
+                    .replace('"', "&quot;")
+                    .replace('<', "&lt;")
+                    .replace('>', "&gt;")
+            };
+            format!(
+                "<chunk id=\"{id}\" buffer=\"{}\" index=\"{}\">\n{content}{newline}</chunk>\n",
+                escape(&buffer_label),
+                chunk.index
+            )
+        }
+        ChunkWrap::Markdown => {
+            let longest_run = content
+                .split(|c: char| c != '`')
+                .map(str::len)
+                .max()
+                .unwrap_or(0);
+            let fence = "`".repeat((longest_run + 1).max(3));
+            let language = buffer
+                .and_then(|b| b.metadata.content_type.as_deref())
+                .unwrap_or_default();
+            format!(
+                "### Chunk {id} ({buffer_label}, index {})\n\n{fence}{language}\n{content}{newline}{fence}\n",
+                chunk.index
+            )
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn cmd_chunk_get(
     db_path: &std::path::Path,
     chunk_id: i64,
     include_metadata: bool,
+    direction: Option<ChunkDirection>,
+    verify_hash: bool,
+    resolve_depth: Option<usize>,
+    wrap: ChunkWrap,
     format: OutputFormat,
 ) -> Result<String> {
     let storage = open_storage(db_path)?;
 
-    let chunk = storage
+    let mut chunk = storage
         .get_chunk(chunk_id)?
         .ok_or(StorageError::ChunkNotFound { id: chunk_id })?;
 
+    if let Some(direction) = direction {
+        chunk = storage
+            .get_adjacent_chunk(chunk_id, direction)?
+            .ok_or_else(|| {
+                let (edge, neighbor) = match direction {
+                    ChunkDirection::Next => ("end", "next"),
+                    ChunkDirection::Prev => ("start", "previous"),
+                };
+                CommandError::ExecutionFailed(format!(
+                    "Reached {edge} of buffer {}: chunk {chunk_id} (index {}) has no {neighbor} chunk",
+                    chunk.buffer_id, chunk.index
+                ))
+            })?;
+    }
+
+    let hash_valid = if verify_hash {
+        chunk.verify_hash()
+    } else {
+        None
+    };
+    if hash_valid == Some(false) {
+        return Err(CommandError::ExecutionFailed(format!(
+            "Chunk {} content hash mismatch: stored {}, computed {}",
+            chunk.id.unwrap_or(0),
+            chunk.metadata.content_hash.as_deref().unwrap_or_default(),
+            content_hash(&chunk.content)
+        ))
+        .into());
+    }
+
+    // The hash covers the stored content, so references are resolved after
+    // verifying it
+    let refs_resolved = match resolve_depth {
+        Some(max_depth) => {
+            let mut path = vec![chunk.id.unwrap_or(chunk_id)];
+            let (content, count) =
+                resolve_chunk_refs(&storage, &chunk.content, &mut path, max_depth)?;
+            chunk.content = content;
+            Some(count)
+        }
+        None => None,
+    };
+
     match format {
         OutputFormat::Text => {
+            let wrapped = if wrap == ChunkWrap::None {
+                None
+            } else {
+                let buffer = storage.get_buffer(chunk.buffer_id)?;
+                Some(wrap_chunk(&chunk, buffer.as_ref(), wrap))
+            };
             if include_metadata {
                 let mut output = String::new();
                 let _ = writeln!(output, "Chunk ID: {}", chunk.id.unwrap_or(0));
@@ -1285,19 +4574,36 @@ fn cmd_chunk_get(
                     chunk.byte_range.start, chunk.byte_range.end
                 );
                 let _ = writeln!(output, "Size: {} bytes", chunk.size());
+                let _ = writeln!(
+                    output,
+                    "Content hash: {}",
+                    chunk.metadata.content_hash.as_deref().unwrap_or("(none)")
+                );
+                if verify_hash {
+                    let status = if hash_valid.is_some() {
+                        "ok"
+                    } else {
+                        "no stored hash"
+                    };
+                    let _ = writeln!(output, "Hash check: {status}");
+                }
+                if let Some(count) = refs_resolved {
+                    let _ = writeln!(output, "References resolved: {count}");
+                }
                 output.push_str("---\n");
-                output.push_str(&chunk.content);
-                if !chunk.content.ends_with('\n') {
+                let content = wrapped.as_deref().unwrap_or(&chunk.content);
+                output.push_str(content);
+                if !content.ends_with('\n') {
                     output.push('\n');
                 }
                 Ok(output)
             } else {
                 // Plain content output for pass-by-reference use case
-                Ok(chunk.content)
+                Ok(wrapped.unwrap_or(chunk.content))
             }
         }
         OutputFormat::Json | OutputFormat::Ndjson => {
-            let json = serde_json::json!({
+            let mut json = serde_json::json!({
                 "chunk_id": chunk.id,
                 "buffer_id": chunk.buffer_id,
                 "index": chunk.index,
@@ -1306,25 +4612,238 @@ fn cmd_chunk_get(
                     "end": chunk.byte_range.end
                 },
                 "size": chunk.size(),
+                "content_hash": chunk.metadata.content_hash,
                 "content": chunk.content
             });
+            if verify_hash {
+                json["hash_valid"] = serde_json::json!(hash_valid);
+            }
+            if let Some(count) = refs_resolved {
+                json["refs_resolved"] = serde_json::json!(count);
+            }
             Ok(serde_json::to_string_pretty(&json).unwrap_or_default())
         }
     }
 }
 
+/// Substitutes the chunk references in `content` (see [`find_chunk_refs`])
+/// with the referenced chunks' content, resolving their references in turn.
+///
+/// `path` holds the chunks being resolved, outermost first. Returns the
+/// resolved content and the number of references substituted.
+fn resolve_chunk_refs(
+    storage: &SqliteStorage,
+    content: &str,
+    path: &mut Vec<i64>,
+    max_depth: usize,
+) -> Result<(String, usize)> {
+    let refs = find_chunk_refs(content);
+    if refs.is_empty() {
+        return Ok((content.to_string(), 0));
+    }
+    if path.len() > max_depth {
+        return Err(CommandError::ExecutionFailed(format!(
+            "chunk references nested deeper than --resolve-refs={max_depth} (at chunk {})",
+            path.last().copied().unwrap_or_default()
+        ))
+        .into());
+    }
+
+    let mut resolved = String::with_capacity(content.len());
+    let mut count = 0;
+    let mut last = 0;
+    for (range, id) in refs {
+        if path.contains(&id) {
+            let cycle: Vec<String> = path
+                .iter()
+                .chain(std::iter::once(&id))
+                .map(ToString::to_string)
+                .collect();
+            return Err(CommandError::ExecutionFailed(format!(
+                "chunk reference cycle: {}",
+                cycle.join(" -> ")
+            ))
+            .into());
+        }
+        let chunk = storage
+            .get_chunk(id)?
+            .ok_or(StorageError::ChunkNotFound { id })?;
+
+        path.push(id);
+        let (nested, nested_count) = resolve_chunk_refs(storage, &chunk.content, path, max_depth)?;
+        path.pop();
+
+        resolved.push_str(&content[last..range.start]);
+        resolved.push_str(&nested);
+        count += 1 + nested_count;
+        last = range.end;
+    }
+    resolved.push_str(&content[last..]);
+    Ok((resolved, count))
+}
+
+/// Prints a chunk together with its neighbors within `radius` indices.
+fn cmd_chunk_context(
+    db_path: &std::path::Path,
+    chunk_id: i64,
+    radius: usize,
+    wrap: ChunkWrap,
+    format: OutputFormat,
+) -> Result<String> {
+    let storage = open_storage(db_path)?;
+    let hit = storage
+        .get_chunk(chunk_id)?
+        .ok_or(StorageError::ChunkNotFound { id: chunk_id })?;
+    let chunks = storage.get_chunk_neighbors(chunk_id, radius)?;
+
+    match format {
+        OutputFormat::Text => {
+            let buffer = if wrap == ChunkWrap::None {
+                None
+            } else {
+                storage.get_buffer(hit.buffer_id)?
+            };
+            let mut output = String::new();
+            for chunk in &chunks {
+                if wrap == ChunkWrap::None {
+                    let marker = if chunk.id == Some(chunk_id) {
+                        " [hit]"
+                    } else {
+                        ""
+                    };
+                    let _ = writeln!(
+                        output,
+                        "--- chunk {} (buffer {}, index {}){marker} ---",
+                        chunk.id.unwrap_or(0),
+                        chunk.buffer_id,
+                        chunk.index
+                    );
+                    output.push_str(&chunk.content);
+                    if !chunk.content.ends_with('\n') {
+                        output.push('\n');
+                    }
+                } else {
+                    if !output.is_empty() {
+                        output.push('\n');
+                    }
+                    output.push_str(&wrap_chunk(chunk, buffer.as_ref(), wrap));
+                }
+            }
+            Ok(output)
+        }
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            let json = serde_json::json!({
+                "chunk_id": chunk_id,
+                "buffer_id": hit.buffer_id,
+                "index": hit.index,
+                "radius": radius,
+                "chunks": chunks.iter().map(|chunk| serde_json::json!({
+                    "chunk_id": chunk.id,
+                    "index": chunk.index,
+                    "hit": chunk.id == Some(chunk_id),
+                    "byte_range": {
+                        "start": chunk.byte_range.start,
+                        "end": chunk.byte_range.end
+                    },
+                    "size": chunk.size(),
+                    "content_hash": chunk.metadata.content_hash,
+                    "content": chunk.content
+                })).collect::<Vec<_>>()
+            });
+            if format.is_streaming() {
+                Ok(format_ndjson_records(json, "chunks"))
+            } else {
+                Ok(serde_json::to_string_pretty(&json).unwrap_or_default())
+            }
+        }
+    }
+}
+
+fn cmd_chunk_stream(db_path: &std::path::Path, chunk_id: i64, out: &mut dyn IoWrite) -> Result<()> {
+    let storage = open_storage(db_path)?;
+    let mut reader = storage.chunk_content_reader(chunk_id)?;
+    ignore_broken_pipe(io::copy(&mut reader, out).and_then(|_| out.flush()))
+}
+
+/// Summarizes `--verify-hash` results for chunk listings.
+fn format_hash_check(chunks: &[Chunk]) -> String {
+    let mut ok = 0;
+    let mut missing = 0;
+    let mut mismatched = Vec::new();
+    for chunk in chunks {
+        match chunk.verify_hash() {
+            Some(true) => ok += 1,
+            Some(false) => mismatched.push(chunk.id.unwrap_or(0).to_string()),
+            None => missing += 1,
+        }
+    }
+
+    let mut output = format!(
+        "\nHash check: {ok} ok, {} mismatched, {missing} without hash\n",
+        mismatched.len()
+    );
+    if !mismatched.is_empty() {
+        let _ = writeln!(output, "Mismatched chunk IDs: {}", mismatched.join(", "));
+    }
+    output
+}
+
+/// Adds `content_hash`, and `hash_valid` when verifying, to a chunk's JSON.
+fn add_hash_fields(obj: &mut serde_json::Value, chunk: &Chunk, verify_hash: bool) {
+    obj["content_hash"] = serde_json::json!(chunk.metadata.content_hash);
+    if verify_hash {
+        obj["hash_valid"] = serde_json::json!(chunk.verify_hash());
+    }
+}
+
+/// Loads a random sample of chunks, ordered by buffer then index.
+fn sample_chunks(
+    storage: &SqliteStorage,
+    buffer_id: Option<i64>,
+    (n, seed): (usize, Option<u64>),
+) -> Result<Vec<Chunk>> {
+    let mut chunks = Vec::with_capacity(n);
+    for id in storage.sample_chunk_ids(buffer_id, n, seed)? {
+        if let Some(chunk) = storage.get_chunk(id)? {
+            chunks.push(chunk);
+        }
+    }
+    chunks.sort_by_key(|c| (c.buffer_id, c.index));
+    Ok(chunks)
+}
+
+/// Returns the IDs of chunks whose stored hash doesn't match their content.
+fn hash_mismatches(chunks: &[Chunk]) -> Vec<i64> {
+    chunks
+        .iter()
+        .filter(|c| c.verify_hash() == Some(false))
+        .filter_map(|c| c.id)
+        .collect()
+}
+
 fn cmd_chunk_list(
     db_path: &std::path::Path,
     identifier: &str,
-    show_preview: bool,
-    preview_len: usize,
+    (limit, offset): (Option<usize>, usize),
+    (show_preview, preview_len): (bool, usize),
+    verify_hash: bool,
+    sample: Option<(usize, Option<u64>)>,
     format: OutputFormat,
 ) -> Result<String> {
     let storage = open_storage(db_path)?;
     let buffer = resolve_buffer(&storage, identifier)?;
     let buffer_id = buffer.id.unwrap_or(0);
 
-    let chunks = storage.get_chunks(buffer_id)?;
+    let chunks: Vec<Chunk> = if let Some(sample) = sample {
+        sample_chunks(&storage, Some(buffer_id), sample)?
+    } else {
+        storage
+            .get_chunks(buffer_id)?
+            .into_iter()
+            .skip(offset)
+            .take(limit.unwrap_or(usize::MAX))
+            .collect()
+    };
 
     match format {
         OutputFormat::Text => {
@@ -1338,7 +4857,12 @@ fn cmd_chunk_list(
             let mut output = String::new();
             let _ = writeln!(
                 output,
-                "Chunks for buffer '{}' ({} chunks):\n",
+                "{} for buffer '{}' ({} chunks):\n",
+                if sample.is_some() {
+                    "Random sample of chunks"
+                } else {
+                    "Chunks"
+                },
                 buffer.name.as_deref().unwrap_or(&buffer_id.to_string()),
                 chunks.len()
             );
@@ -1353,17 +4877,7 @@ fn cmd_chunk_list(
                 output.push('\n');
 
                 for chunk in &chunks {
-                    let preview: String = chunk
-                        .content
-                        .chars()
-                        .take(preview_len)
-                        .map(|c| if c == '\n' { ' ' } else { c })
-                        .collect();
-                    let preview = if chunk.content.len() > preview_len {
-                        format!("{preview}...")
-                    } else {
-                        preview
-                    };
+                    let preview = make_preview(&chunk.content, preview_len);
 
                     let _ = writeln!(
                         output,
@@ -1378,59 +4892,382 @@ fn cmd_chunk_list(
             } else {
                 let _ = writeln!(
                     output,
-                    "{:<8} {:<6} {:<12} {:<12}",
+                    "{:<8} {:<6} {:<12} {:<12} Title",
                     "ID", "Index", "Start", "Size"
                 );
-                output.push_str(&"-".repeat(40));
+                output.push_str(&"-".repeat(70));
                 output.push('\n');
 
                 for chunk in &chunks {
                     let _ = writeln!(
                         output,
-                        "{:<8} {:<6} {:<12} {:<12}",
+                        "{:<8} {:<6} {:<12} {:<12} {}",
                         chunk.id.unwrap_or(0),
                         chunk.index,
                         chunk.byte_range.start,
-                        chunk.size()
+                        chunk.size(),
+                        chunk
+                            .title()
+                            .map_or_else(String::new, |t| truncate_str(&t, 40))
                     );
                 }
             }
 
+            if verify_hash {
+                output.push_str(&format_hash_check(&chunks));
+            }
+
             Ok(output)
         }
         OutputFormat::Json | OutputFormat::Ndjson => {
-            let json = serde_json::json!({
+            let mut json = serde_json::json!({
                 "buffer_id": buffer_id,
                 "buffer_name": buffer.name,
+                "sample": sample.map(|(n, seed)| serde_json::json!({ "size": n, "seed": seed })),
+                "chunk_count": chunks.len(),
+                "chunks": chunks.iter().map(|c| {
+                    let mut obj = serde_json::json!({
+                        "id": c.id,
+                        "index": c.index,
+                        "byte_range": {
+                            "start": c.byte_range.start,
+                            "end": c.byte_range.end
+                        },
+                        "size": c.size(),
+                        "title": c.title()
+                    });
+                    add_hash_fields(&mut obj, c, verify_hash);
+                    if show_preview {
+                        obj["preview"] = make_preview(&c.content, preview_len).into();
+                    }
+                    obj
+                }).collect::<Vec<_>>()
+            });
+            if verify_hash {
+                json["hash_mismatches"] = serde_json::json!(hash_mismatches(&chunks));
+            }
+            Ok(if format.is_streaming() {
+                format_ndjson_records(json, "chunks")
+            } else {
+                serde_json::to_string_pretty(&json).unwrap_or_default()
+            })
+        }
+    }
+}
+
+fn cmd_chunk_list_all(
+    db_path: &std::path::Path,
+    (limit, offset): (Option<usize>, usize),
+    (show_preview, preview_len): (bool, usize),
+    verify_hash: bool,
+    sample: Option<(usize, Option<u64>)>,
+    format: OutputFormat,
+) -> Result<String> {
+    let storage = open_storage(db_path)?;
+    let total = storage.stats()?.chunk_count;
+    let chunks = match sample {
+        Some(sample) => sample_chunks(&storage, None, sample)?,
+        None => storage.list_chunks(limit, offset)?,
+    };
+
+    let preview_of = |chunk: &Chunk| make_preview(&chunk.content, preview_len);
+
+    match format {
+        OutputFormat::Text => {
+            if chunks.is_empty() {
+                return Ok(if total == 0 {
+                    "No chunks found.\n".to_string()
+                } else {
+                    format!("No chunks at offset {offset} ({total} total).\n")
+                });
+            }
+
+            let mut output = String::new();
+            if sample.is_some() {
+                let _ = writeln!(
+                    output,
+                    "Random sample of {} of {total} chunks across all buffers:\n",
+                    chunks.len()
+                );
+            } else {
+                let _ = writeln!(
+                    output,
+                    "Chunks {}-{} of {total} across all buffers:\n",
+                    offset + 1,
+                    offset + chunks.len()
+                );
+            }
+            let _ = writeln!(
+                output,
+                "{:<8} {:<8} {:<6} {:<12} {:<12} {}",
+                "ID",
+                "Buffer",
+                "Index",
+                "Start",
+                "Size",
+                if show_preview { "Preview" } else { "Title" }
+            );
+            output.push_str(&"-".repeat(79));
+            output.push('\n');
+
+            for chunk in &chunks {
+                let last = if show_preview {
+                    preview_of(chunk)
+                } else {
+                    chunk
+                        .title()
+                        .map_or_else(String::new, |t| truncate_str(&t, 40))
+                };
+                let _ = writeln!(
+                    output,
+                    "{:<8} {:<8} {:<6} {:<12} {:<12} {}",
+                    chunk.id.unwrap_or(0),
+                    chunk.buffer_id,
+                    chunk.index,
+                    chunk.byte_range.start,
+                    chunk.size(),
+                    last
+                );
+            }
+
+            if verify_hash {
+                output.push_str(&format_hash_check(&chunks));
+            }
+
+            Ok(output)
+        }
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            let mut json = serde_json::json!({
+                "total_chunks": total,
+                "offset": offset,
+                "limit": limit,
+                "sample": sample.map(|(n, seed)| serde_json::json!({ "size": n, "seed": seed })),
                 "chunk_count": chunks.len(),
                 "chunks": chunks.iter().map(|c| {
                     let mut obj = serde_json::json!({
                         "id": c.id,
+                        "buffer_id": c.buffer_id,
                         "index": c.index,
                         "byte_range": {
                             "start": c.byte_range.start,
                             "end": c.byte_range.end
                         },
-                        "size": c.size()
+                        "size": c.size(),
+                        "title": c.title()
                     });
+                    add_hash_fields(&mut obj, c, verify_hash);
                     if show_preview {
-                        let preview: String = c.content.chars().take(preview_len).collect();
-                        obj["preview"] = serde_json::Value::String(preview);
+                        obj["preview"] = make_preview(&c.content, preview_len).into();
                     }
                     obj
                 }).collect::<Vec<_>>()
             });
+            if verify_hash {
+                json["hash_mismatches"] = serde_json::json!(hash_mismatches(&chunks));
+            }
+            Ok(if format.is_streaming() {
+                format_ndjson_records(json, "chunks")
+            } else {
+                serde_json::to_string_pretty(&json).unwrap_or_default()
+            })
+        }
+    }
+}
+
+/// Parses a duration like `90s`, `30m`, `12h`, `7d` or `2w` into seconds.
+///
+/// A bare number is taken as seconds.
+fn parse_duration_secs(value: &str) -> Result<i64> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let multiplier = match unit.trim() {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => 0,
+    };
+
+    amount
+        .parse::<i64>()
+        .ok()
+        .filter(|_| multiplier > 0)
+        .and_then(|n| n.checked_mul(multiplier))
+        .ok_or_else(|| {
+            CommandError::InvalidArgument(format!(
+                "invalid duration '{value}' (expected e.g. 90s, 30m, 12h, 7d, 2w)"
+            ))
+            .into()
+        })
+}
+
+#[allow(clippy::too_many_lines)]
+fn cmd_chunk_reembed_missing(
+    db_path: &std::path::Path,
+    identifier: Option<&str>,
+    force: bool,
+    format: OutputFormat,
+) -> Result<String> {
+    let mut storage = open_storage(db_path)?;
+    let buffer = identifier
+        .map(|identifier| resolve_buffer(&storage, identifier))
+        .transpose()?;
+    let buffer_id = buffer.as_ref().and_then(|b| b.id);
+
+    let missing = if force {
+        storage.get_chunk_ids(buffer_id)?
+    } else {
+        storage.get_chunks_missing_embedding(buffer_id)?
+    };
+
+    // Batch across buffers, reporting progress after each batch. Only load
+    // the model when there is something to embed.
+    let mut progress = String::new();
+    let mut embedded = 0;
+    let mut batches = 0;
+    let model_name = if missing.is_empty() {
+        None
+    } else {
+        let model = create_embedder()?;
+        for batch in missing.chunks(embed_batch_size().max(1)) {
+            embedded +=
+                crate::search::reembed_chunks(&mut storage, model.as_ref(), batch, batch.len())?;
+            batches += 1;
+            let _ = writeln!(progress, "  {embedded}/{} embedded", missing.len());
+        }
+        // Bulk embedding sweep: refresh planner statistics for the new data
+        storage.analyze()?;
+        Some(model.model_name())
+    };
+
+    let scope = buffer.as_ref().map_or_else(
+        || "any buffer".to_string(),
+        |b| format!("buffer '{}'", b.name.as_deref().unwrap_or("unnamed")),
+    );
+    let selection = if force {
+        "chunks"
+    } else {
+        "chunks missing embeddings"
+    };
+    match format {
+        OutputFormat::Text => Ok(match model_name {
+            None if force => format!("No chunks in {scope}.\n"),
+            None => format!("No chunks in {scope} are missing embeddings.\n"),
+            Some(model) => format!(
+                "{progress}Embedded {embedded} of {} {selection} in {scope} using model '{model}'.\n",
+                missing.len()
+            ),
+        }),
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            let json = serde_json::json!({
+                "buffer_id": buffer_id,
+                "force": force,
+                "missing_count": missing.len(),
+                "embedded_count": embedded,
+                "batches": batches,
+                "model": model_name,
+            });
             Ok(serde_json::to_string_pretty(&json).unwrap_or_default())
         }
     }
 }
 
+fn cmd_chunk_build_ivf(
+    db_path: &std::path::Path,
+    clusters: Option<usize>,
+    iterations: usize,
+    (nprobe, samples): (usize, usize),
+    seed: u64,
+    format: OutputFormat,
+) -> Result<String> {
+    let mut storage = open_storage(db_path)?;
+    let build = build_ivf_index(&mut storage, clusters, iterations, seed)?;
+    let recall = estimate_ivf_recall(&storage, nprobe, DEFAULT_TOP_K, samples, seed)?;
+
+    match format {
+        OutputFormat::Text => {
+            if build.clusters == 0 {
+                return Ok(
+                    "No embeddings to cluster; run 'rlm-rs chunk embed' first.\n".to_string(),
+                );
+            }
+            let mut output = format!(
+                "Built IVF index: {} clusters over {} embeddings ({} dimensions, {} iterations).\n",
+                build.clusters, build.assigned, build.dimensions, build.iterations
+            );
+            if build.skipped > 0 {
+                output.push_str(&format!(
+                    "Left {} embeddings of other dimensions unassigned (always scanned).\n",
+                    build.skipped
+                ));
+            }
+            if let Some(recall) = recall {
+                output.push_str(&format_ivf_recall(&recall));
+            }
+            Ok(output)
+        }
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            let json = serde_json::json!({
+                "clusters": build.clusters,
+                "dimensions": build.dimensions,
+                "assigned": build.assigned,
+                "skipped": build.skipped,
+                "iterations": build.iterations,
+                "recall": recall,
+            });
+            Ok(serde_json::to_string_pretty(&json).unwrap_or_default())
+        }
+    }
+}
+
+fn cmd_chunk_ivf_recall(
+    db_path: &std::path::Path,
+    nprobe: usize,
+    top_k: usize,
+    samples: usize,
+    seed: u64,
+    format: OutputFormat,
+) -> Result<String> {
+    let storage = open_storage(db_path)?;
+    let recall = estimate_ivf_recall(&storage, nprobe, top_k, samples, seed)?.ok_or_else(|| {
+        CommandError::ExecutionFailed(
+            "no IVF index (run 'rlm-rs chunk build-ivf' first)".to_string(),
+        )
+    })?;
+
+    match format {
+        OutputFormat::Text => Ok(format_ivf_recall(&recall)),
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            Ok(serde_json::to_string_pretty(&recall).unwrap_or_default())
+        }
+    }
+}
+
+/// Formats an IVF recall estimate as one line of text.
+fn format_ivf_recall(recall: &IvfRecall) -> String {
+    format!(
+        "Estimated recall@{} at nprobe {}: {:.1}% (scanning {:.1}% of embeddings, {} sampled queries)\n",
+        recall.top_k,
+        recall.nprobe,
+        recall.recall * 100.0,
+        recall.scanned * 100.0,
+        recall.samples
+    )
+}
+
 fn cmd_chunk_embed(
     db_path: &std::path::Path,
     identifier: &str,
     force: bool,
+    older_than: Option<&str>,
     format: OutputFormat,
 ) -> Result<String> {
+    let older_than_secs = older_than.map(parse_duration_secs).transpose()?;
+
     let mut storage = open_storage(db_path)?;
     let buffer = resolve_buffer(&storage, identifier)?;
     let buffer_id = buffer.id.unwrap_or(0);
@@ -1438,13 +5275,27 @@ fn cmd_chunk_embed(
 
     let embedder = create_embedder()?;
 
-    // Use incremental embedding (force_reembed = force flag)
-    let result = crate::search::embed_buffer_chunks_incremental(
-        &mut storage,
-        embedder.as_ref(),
-        buffer_id,
-        force,
-    )?;
+    let result = if let Some(secs) = older_than_secs {
+        // Refresh only embeddings created before the cutoff
+        let cutoff = crate::io::current_timestamp().saturating_sub(secs);
+        crate::search::reembed_stale_chunks(
+            &mut storage,
+            embedder.as_ref(),
+            buffer_id,
+            cutoff,
+            embed_batch_size(),
+        )?
+    } else {
+        // Use incremental embedding (force_reembed = force flag)
+        crate::search::embed_buffer_chunks_incremental_with_progress(
+            &mut storage,
+            embedder.as_ref(),
+            buffer_id,
+            force,
+            embed_batch_size(),
+            embed_progress(),
+        )?
+    };
 
     // Check for model version mismatch warning
     let model_warning = if !force {
@@ -1471,7 +5322,12 @@ fn cmd_chunk_embed(
                 output.push('\n');
             }
 
-            if !result.had_changes() {
+            if let (Some(duration), false) = (older_than, result.had_changes()) {
+                output.push_str(&format!(
+                    "Buffer '{buffer_name}' has no embeddings older than {duration} ({} chunks).\n",
+                    result.total_chunks
+                ));
+            } else if !result.had_changes() {
                 output.push_str(&format!(
                     "Buffer '{buffer_name}' already fully embedded ({} chunks). Use --force to re-embed.\n",
                     result.total_chunks
@@ -1483,7 +5339,18 @@ fn cmd_chunk_embed(
                         result.embedded_count, result.model_name
                     ));
                 }
-                if result.replaced_count > 0 {
+                if result.reused_count > 0 {
+                    output.push_str(&format!(
+                        "Reused {} embeddings from chunks with identical content.\n",
+                        result.reused_count
+                    ));
+                }
+                if let Some(duration) = older_than {
+                    output.push_str(&format!(
+                        "Re-embedded {} chunks with embeddings older than {duration}.\n",
+                        result.replaced_count
+                    ));
+                } else if result.replaced_count > 0 {
                     output.push_str(&format!(
                         "Re-embedded {} chunks with updated model.\n",
                         result.replaced_count
@@ -1504,11 +5371,14 @@ fn cmd_chunk_embed(
                 "buffer_name": buffer_name,
                 "embedded_count": result.embedded_count,
                 "replaced_count": result.replaced_count,
+                "reused_count": result.reused_count,
+                "computed_count": result.computed_count(),
                 "skipped_count": result.skipped_count,
                 "total_chunks": result.total_chunks,
                 "model": result.model_name,
                 "had_changes": result.had_changes(),
                 "completion_percentage": result.completion_percentage(),
+                "older_than": older_than,
                 "model_warning": model_warning
             });
             Ok(serde_json::to_string_pretty(&json).unwrap_or_default())
@@ -1520,22 +5390,20 @@ fn cmd_chunk_status(db_path: &std::path::Path, format: OutputFormat) -> Result<S
     let storage = open_storage(db_path)?;
     let buffers = storage.list_buffers()?;
 
+    let coverage: std::collections::HashMap<i64, EmbeddingCoverage> = storage
+        .embedding_coverage()?
+        .into_iter()
+        .map(|c| (c.buffer_id, c))
+        .collect();
+
     let mut buffer_stats: Vec<(String, i64, usize, usize)> = Vec::new();
 
     for buffer in &buffers {
         let buffer_id = buffer.id.unwrap_or(0);
         let buffer_name = buffer.name.clone().unwrap_or_else(|| buffer_id.to_string());
-        let chunks = storage.get_chunks(buffer_id)?;
-        let chunk_count = chunks.len();
-
-        let mut embedded_count = 0;
-        for chunk in &chunks {
-            if let Some(cid) = chunk.id
-                && storage.has_embedding(cid)?
-            {
-                embedded_count += 1;
-            }
-        }
+        let (chunk_count, embedded_count) = coverage
+            .get(&buffer_id)
+            .map_or((0, 0), |c| (c.total_chunks, c.embedded_chunks));
 
         buffer_stats.push((buffer_name, buffer_id, chunk_count, embedded_count));
     }
@@ -1604,14 +5472,67 @@ fn cmd_chunk_status(db_path: &std::path::Path, format: OutputFormat) -> Result<S
     }
 }
 
-/// Truncates a string to max length with ellipsis.
+fn cmd_gen_corpus(
+    db_path: &std::path::Path,
+    buffers: usize,
+    chunks_per_buffer: usize,
+    seed: u64,
+    embed: bool,
+    format: OutputFormat,
+) -> Result<String> {
+    let mut storage = open_storage(db_path)?;
+    let config = CorpusConfig::new(buffers, chunks_per_buffer, seed);
+
+    let embedder = FallbackEmbedder::new(embedding_dimensions().unwrap_or(DEFAULT_DIMENSIONS));
+    let summary = populate_corpus(
+        &mut storage,
+        &config,
+        embed.then_some(&embedder as &dyn Embedder),
+    )?;
+
+    if let Some(mut context) = storage.load_context()? {
+        for id in &summary.buffer_ids {
+            context.add_buffer(*id);
+        }
+        storage.save_context(&context)?;
+    }
+
+    // Bulk insert: refresh planner statistics for the new data
+    storage.analyze()?;
+
+    match format {
+        OutputFormat::Text => Ok(format!(
+            "Generated {} buffers with {} chunks ({} embedded, {} bytes) from seed {}\n",
+            summary.buffer_ids.len(),
+            summary.chunk_count,
+            summary.embedded_count,
+            summary.total_bytes,
+            seed
+        )),
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            let result = serde_json::json!({
+                "seed": seed,
+                "buffer_ids": summary.buffer_ids,
+                "chunk_count": summary.chunk_count,
+                "embedded_count": summary.embedded_count,
+                "total_bytes": summary.total_bytes
+            });
+            Ok(serde_json::to_string_pretty(&result).unwrap_or_default())
+        }
+    }
+}
+
+/// Truncates a string to max length (in bytes) with ellipsis.
+///
+/// Cuts at a UTF-8 character boundary, so the result may be shorter.
 fn truncate_str(s: &str, max_len: usize) -> String {
     if s.len() <= max_len {
         s.to_string()
     } else if max_len <= 3 {
-        s[..max_len].to_string()
+        s[..crate::io::find_char_boundary(s, max_len)].to_string()
     } else {
-        format!("{}...", &s[..max_len - 3])
+        let end = crate::io::find_char_boundary(s, max_len - 3);
+        format!("{}...", &s[..end])
     }
 }
 
@@ -1655,7 +5576,7 @@ mod tests {
         let (_temp_dir, db_path) = setup();
         cmd_init(&db_path, false, OutputFormat::Text).unwrap();
 
-        let result = cmd_status(&db_path, OutputFormat::Text);
+        let result = cmd_status(&db_path, false, OutputFormat::Text);
         assert!(result.is_ok());
         assert!(result.unwrap().contains("Buffers"));
     }
@@ -1674,6 +5595,67 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_parse_duration_secs() {
+        assert_eq!(parse_duration_secs("90").unwrap(), 90);
+        assert_eq!(parse_duration_secs("90s").unwrap(), 90);
+        assert_eq!(parse_duration_secs("30m").unwrap(), 1800);
+        assert_eq!(parse_duration_secs("12h").unwrap(), 43_200);
+        assert_eq!(parse_duration_secs("7d").unwrap(), 604_800);
+        assert_eq!(parse_duration_secs("2w").unwrap(), 1_209_600);
+        assert!(parse_duration_secs("").is_err());
+        assert!(parse_duration_secs("d").is_err());
+        assert!(parse_duration_secs("5y").is_err());
+        assert!(parse_duration_secs("-5m").is_err());
+    }
+
+    #[test]
+    fn test_cmd_explain_chunking() {
+        let (temp_dir, db_path) = setup();
+        let file_path = temp_dir.path().join("plan.txt");
+        std::fs::write(&file_path, "line one\nline two\nline three\nline four\n").unwrap();
+
+        let result = cmd_explain_chunking(&file_path, "fixed", 20, 0, OutputFormat::Json).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(json["chunker"], "fixed");
+        assert!(json["chunk_count"].as_u64().unwrap() > 1);
+        assert_eq!(json["chunks"][0]["byte_range"]["start"], 0);
+        assert_eq!(json["chunks"][0]["line_range"]["start"], 1);
+
+        let text = cmd_explain_chunking(&file_path, "fixed", 20, 0, OutputFormat::Text).unwrap();
+        assert!(text.contains("Chunking plan for"));
+
+        // Dry run: no database is created
+        assert!(!db_path.exists());
+        assert!(cmd_explain_chunking(&file_path, "bogus", 20, 0, OutputFormat::Text).is_err());
+    }
+
+    #[test]
+    fn test_cmd_analyze() {
+        let (_temp_dir, db_path) = setup();
+        cmd_init(&db_path, false, OutputFormat::Text).unwrap();
+        cmd_gen_corpus(&db_path, 1, 3, 1, false, OutputFormat::Text).unwrap();
+
+        let result = cmd_analyze(&db_path, OutputFormat::Text).unwrap();
+        assert!(result.contains("Analyzed database"));
+
+        let result = cmd_analyze(&db_path, OutputFormat::Json).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(json["analyzed"], true);
+    }
+
+    #[test]
+    fn test_cmd_usage() {
+        let (_temp_dir, db_path) = setup();
+        cmd_init(&db_path, false, OutputFormat::Text).unwrap();
+        cmd_gen_corpus(&db_path, 1, 3, 1, true, OutputFormat::Text).unwrap();
+
+        let result = cmd_usage(&db_path, OutputFormat::Json).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(json["embedding_count"], 3);
+        assert!(json["chunk_content_bytes"].as_u64().unwrap() > 0);
+    }
+
     #[test]
     fn test_cmd_add_buffer() {
         let (_temp_dir, db_path) = setup();
@@ -1695,14 +5677,14 @@ mod tests {
         cmd_init(&db_path, false, OutputFormat::Text).unwrap();
 
         // Empty list
-        let result = cmd_list_buffers(&db_path, OutputFormat::Text);
+        let result = cmd_list_buffers(&db_path, &[], None, &[], OutputFormat::Text);
         assert!(result.is_ok());
         assert!(result.unwrap().contains("No buffers"));
 
         // Add a buffer
         cmd_add_buffer(&db_path, "test", Some("content"), OutputFormat::Text).unwrap();
 
-        let result = cmd_list_buffers(&db_path, OutputFormat::Text);
+        let result = cmd_list_buffers(&db_path, &[], None, &[], OutputFormat::Text);
         assert!(result.is_ok());
         assert!(result.unwrap().contains("test"));
     }
@@ -1713,7 +5695,13 @@ mod tests {
         cmd_init(&db_path, false, OutputFormat::Text).unwrap();
 
         // Set variable
-        let result = cmd_variable(&db_path, "key", Some("value"), false, OutputFormat::Text);
+        let result = cmd_variable(
+            &db_path,
+            "key",
+            Some(ContextValue::String("value".to_string())),
+            false,
+            OutputFormat::Text,
+        );
         assert!(result.is_ok());
 
         // Get variable
@@ -1760,4 +5748,26 @@ mod tests {
         let result = truncate_str("hello", 4);
         assert_eq!(result, "h...");
     }
+
+    #[test]
+    fn test_truncate_str_multibyte() {
+        // Byte limits inside a multi-byte character must not panic
+        assert_eq!(truncate_str("世界世界", 7), "世...");
+        assert_eq!(truncate_str("😀😀", 2), "");
+        assert_eq!(truncate_str("a😀bc", 6), "a...");
+    }
+
+    #[test]
+    fn test_cmd_gen_corpus() {
+        let (_temp_dir, db_path) = setup();
+        cmd_init(&db_path, false, OutputFormat::Text).unwrap();
+
+        let result = cmd_gen_corpus(&db_path, 2, 3, 42, true, OutputFormat::Json).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(json["chunk_count"], 6);
+        assert_eq!(json["embedded_count"], 6);
+
+        let storage = open_storage(&db_path).unwrap();
+        assert_eq!(storage.list_buffers().unwrap().len(), 2);
+    }
 }
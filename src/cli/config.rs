@@ -0,0 +1,139 @@
+//! Per-project configuration file.
+//!
+//! `.rlm/config.toml` sets project defaults for the database path, chunking,
+//! search mode, and output format. It is found by walking up from the
+//! current directory, like `.git`. Values apply only where no flag or
+//! environment variable was given:
+//!
+//! ```toml
+//! db_path = "state/rlm.db"   # relative to the directory holding .rlm/
+//! chunker = "markdown"
+//! chunk_size = 50000
+//! overlap = 500
+//! search_mode = "bm25"
+//! format = "json"
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+
+/// Location of the config file relative to a project root.
+pub const CONFIG_FILE: &str = ".rlm/config.toml";
+
+/// Project defaults read from [`CONFIG_FILE`].
+///
+/// Every field is optional; unset fields fall back to the built-in defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Database path. Relative paths are resolved against the project root.
+    pub db_path: Option<PathBuf>,
+
+    /// Chunking strategy for commands that chunk content.
+    pub chunker: Option<String>,
+
+    /// Chunk size in characters (or tokens with `--unit tokens`).
+    pub chunk_size: Option<usize>,
+
+    /// Overlap between chunks.
+    pub overlap: Option<usize>,
+
+    /// Search mode for `search` and `dispatch --query`.
+    pub search_mode: Option<String>,
+
+    /// Output format (text, json, ndjson).
+    pub format: Option<String>,
+
+    /// File the configuration was read from, if any.
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+}
+
+impl Config {
+    /// Parses a config file, resolving a relative `db_path` against the
+    /// project root (the directory containing `.rlm/`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, is not valid TOML, or
+    /// contains unknown keys.
+    pub fn load(path: &Path) -> Result<Self> {
+        let invalid = |reason: String| Error::Config {
+            message: format!("{}: {reason}", path.display()),
+        };
+        let text = std::fs::read_to_string(path).map_err(|e| invalid(e.to_string()))?;
+        let mut config: Self = toml::from_str(&text).map_err(|e| invalid(e.to_string()))?;
+
+        let root = path
+            .parent()
+            .and_then(Path::parent)
+            .unwrap_or_else(|| Path::new(""));
+        config.db_path = config.db_path.map(|db_path| root.join(db_path));
+        config.path = Some(path.to_path_buf());
+        Ok(config)
+    }
+
+    /// Finds and loads the nearest [`CONFIG_FILE`] in `start` or one of its
+    /// ancestors, returning the default (empty) config if there is none.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a config file is found but cannot be loaded.
+    pub fn discover(start: &Path) -> Result<Self> {
+        start
+            .ancestors()
+            .map(|dir| dir.join(CONFIG_FILE))
+            .find(|path| path.is_file())
+            .map_or_else(|| Ok(Self::default()), |path| Self::load(&path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_config(root: &Path, text: &str) -> PathBuf {
+        let path = root.join(CONFIG_FILE);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, text).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_discover_walks_up() {
+        let temp = TempDir::new().unwrap();
+        let path = write_config(
+            temp.path(),
+            "db_path = \"state/rlm.db\"\nchunker = \"markdown\"\nchunk_size = 500\n",
+        );
+        let nested = temp.path().join("a/b");
+        std::fs::create_dir_all(&nested).unwrap();
+
+        let config = Config::discover(&nested).unwrap();
+        assert_eq!(config.path, Some(path));
+        assert_eq!(config.db_path, Some(temp.path().join("state/rlm.db")));
+        assert_eq!(config.chunker.as_deref(), Some("markdown"));
+        assert_eq!(config.chunk_size, Some(500));
+        assert_eq!(config.overlap, None);
+    }
+
+    #[test]
+    fn test_discover_without_file() {
+        let temp = TempDir::new().unwrap();
+        assert_eq!(Config::discover(temp.path()).unwrap(), Config::default());
+    }
+
+    #[test]
+    fn test_load_rejects_unknown_keys() {
+        let temp = TempDir::new().unwrap();
+        let path = write_config(temp.path(), "chunkr = \"fixed\"\n");
+        let err = Config::load(&path).unwrap_err();
+        assert!(err.to_string().contains("chunkr"), "{err}");
+
+        write_config(temp.path(), "chunk_size = \"big\"\n");
+        assert!(Config::load(&path).is_err());
+    }
+}
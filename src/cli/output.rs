@@ -4,7 +4,9 @@
 
 use crate::core::{Buffer, Chunk, Context};
 use crate::storage::traits::StorageStats;
+use crate::storage::{BufferVersion, DetailedStats, EmbeddingCoverage, StorageUsage};
 use serde::Serialize;
+use std::collections::HashMap;
 use std::fmt::Write;
 
 /// Output format options.
@@ -38,11 +40,32 @@ impl OutputFormat {
 }
 
 /// Formats a status response.
+///
+/// With `details`, text output gains embedding and per-buffer sections and
+/// JSON output gains the [`DetailedStats`] fields next to the aggregate ones.
 #[must_use]
-pub fn format_status(stats: &StorageStats, format: OutputFormat) -> String {
-    match format {
-        OutputFormat::Text => format_status_text(stats),
-        OutputFormat::Json | OutputFormat::Ndjson => format_json(stats),
+pub fn format_status(
+    stats: &StorageStats,
+    details: Option<&DetailedStats>,
+    format: OutputFormat,
+) -> String {
+    match (format, details) {
+        (OutputFormat::Text, None) => format_status_text(stats),
+        (OutputFormat::Text, Some(details)) => {
+            let mut output = format_status_text(stats);
+            output.push_str(&format_status_details_text(details));
+            output
+        }
+        (OutputFormat::Json | OutputFormat::Ndjson, None) => format_json(stats),
+        (OutputFormat::Json | OutputFormat::Ndjson, Some(details)) => {
+            let mut json = serde_json::to_value(stats).unwrap_or_default();
+            if let (Some(json), Ok(serde_json::Value::Object(extra))) =
+                (json.as_object_mut(), serde_json::to_value(details))
+            {
+                json.extend(extra);
+            }
+            format_json(&json)
+        }
     }
 }
 
@@ -50,7 +73,15 @@ fn format_status_text(stats: &StorageStats) -> String {
     let mut output = String::new();
     output.push_str("RLM-RS Status\n");
     output.push_str("=============\n\n");
-    let _ = writeln!(output, "  Buffers:       {}", stats.buffer_count);
+    if stats.trashed_buffer_count > 0 {
+        let _ = writeln!(
+            output,
+            "  Buffers:       {} ({} in trash)",
+            stats.buffer_count, stats.trashed_buffer_count
+        );
+    } else {
+        let _ = writeln!(output, "  Buffers:       {}", stats.buffer_count);
+    }
     let _ = writeln!(output, "  Chunks:        {}", stats.chunk_count);
     let _ = writeln!(
         output,
@@ -63,22 +94,180 @@ fn format_status_text(stats: &StorageStats) -> String {
         if stats.has_context { "yes" } else { "no" }
     );
     let _ = writeln!(output, "  Schema:        v{}", stats.schema_version);
+    let _ = writeln!(output, "  Corpus:        rev {}", stats.corpus_version);
     if let Some(size) = stats.db_size {
         let _ = writeln!(output, "  DB size:       {size} bytes");
     }
     output
 }
 
+fn format_status_details_text(details: &DetailedStats) -> String {
+    let mut output = String::new();
+    output.push_str("\nEmbeddings\n----------\n");
+    let _ = writeln!(
+        output,
+        "  Embedded:      {} ({} missing)",
+        details.embedded_chunks, details.unembedded_chunks
+    );
+    let _ = writeln!(
+        output,
+        "  Vectors:       {}",
+        format_size(details.embedding_bytes)
+    );
+    let models = if details.models.is_empty() {
+        "none".to_string()
+    } else {
+        details.models.join(", ")
+    };
+    let _ = writeln!(output, "  Models:        {models}");
+
+    if !details.buffers.is_empty() {
+        output.push_str("\nBuffers\n-------\n");
+        for buffer in &details.buffers {
+            let _ = writeln!(
+                output,
+                "  [{}] {}: {} chunks, {} embedded, {} missing, avg {:.0} B/chunk, {} vectors",
+                buffer.buffer_id,
+                buffer.name.as_deref().unwrap_or("unnamed"),
+                buffer.chunk_count,
+                buffer.embedded_chunks,
+                buffer.unembedded_chunks,
+                buffer.avg_chunk_size,
+                format_size(buffer.embedding_bytes)
+            );
+        }
+    }
+    output
+}
+
+/// Formats a storage usage breakdown.
+#[must_use]
+pub fn format_storage_usage(usage: &StorageUsage, format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Text => format_storage_usage_text(usage),
+        OutputFormat::Json | OutputFormat::Ndjson => format_json(usage),
+    }
+}
+
+fn format_storage_usage_text(usage: &StorageUsage) -> String {
+    let mut output = String::new();
+    output.push_str("Storage Usage\n");
+    output.push_str("=============\n\n");
+    let _ = writeln!(
+        output,
+        "  Buffer content:  {}",
+        format_size(usage.buffer_content_bytes)
+    );
+    let _ = writeln!(
+        output,
+        "  Chunk content:   {}",
+        format_size(usage.chunk_content_bytes)
+    );
+    let _ = writeln!(
+        output,
+        "  Embeddings:      {} ({} vectors)",
+        format_size(usage.embedding_bytes),
+        usage.embedding_count
+    );
+    let fts = usage
+        .fts_bytes
+        .map_or_else(|| "n/a (dbstat unavailable)".to_string(), format_size);
+    let _ = writeln!(output, "  FTS index:       {fts}");
+    let _ = writeln!(output, "  Database:        {}", format_size(usage.db_bytes));
+
+    if !usage.tables.is_empty() {
+        output.push_str("\nOn-disk size by table/index:\n");
+        for table in &usage.tables {
+            let _ = writeln!(output, "  {:<32} {}", table.name, format_size(table.bytes));
+        }
+    }
+    output
+}
+
 /// Formats a buffer list.
 #[must_use]
-pub fn format_buffer_list(buffers: &[Buffer], format: OutputFormat) -> String {
+pub fn format_buffer_list(
+    buffers: &[Buffer],
+    coverage: &[EmbeddingCoverage],
+    fields: &[String],
+    format: OutputFormat,
+) -> String {
+    let coverage: HashMap<i64, &EmbeddingCoverage> =
+        coverage.iter().map(|c| (c.buffer_id, c)).collect();
+    let coverage_of = |buffer: &Buffer| buffer.id.and_then(|id| coverage.get(&id).copied());
+
     match format {
-        OutputFormat::Text => format_buffer_list_text(buffers),
-        OutputFormat::Json | OutputFormat::Ndjson => format_json(&buffers),
+        OutputFormat::Text => format_buffer_list_text(buffers, coverage_of),
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            let buffers: Vec<serde_json::Value> = buffers
+                .iter()
+                .map(|buffer| {
+                    let mut value = serde_json::to_value(buffer).unwrap_or_default();
+                    let coverage = coverage_of(buffer);
+                    value["embedded_count"] =
+                        serde_json::json!(coverage.map(|c| c.embedded_chunks));
+                    value["embedded_percent"] =
+                        serde_json::json!(coverage.map(EmbeddingCoverage::percentage));
+                    if fields.is_empty() {
+                        value
+                    } else {
+                        project_buffer_fields(&value, fields)
+                    }
+                })
+                .collect();
+            if format.is_streaming() {
+                format_ndjson(&buffers)
+            } else {
+                format_json(&buffers)
+            }
+        }
     }
 }
 
-fn format_buffer_list_text(buffers: &[Buffer]) -> String {
+/// Fields `list --fields` can select: the buffer's own fields, its metadata
+/// fields (flattened), and the embedding coverage columns.
+pub const BUFFER_LIST_FIELDS: &[&str] = &[
+    "id",
+    "name",
+    "source",
+    "content",
+    "metadata",
+    "content_type",
+    "created_at",
+    "updated_at",
+    "size",
+    "line_count",
+    "chunk_count",
+    "content_hash",
+    "source_encoding",
+    "line_ending",
+    "whitespace_normalized",
+    "custom",
+    "embedded_count",
+    "embedded_percent",
+];
+
+/// Keeps only `fields` of a listed buffer, looking in its metadata for
+/// fields it doesn't have itself. Fields without a value are `null`.
+fn project_buffer_fields(value: &serde_json::Value, fields: &[String]) -> serde_json::Value {
+    let projected: serde_json::Map<String, serde_json::Value> = fields
+        .iter()
+        .map(|field| {
+            let found = value
+                .get(field)
+                .or_else(|| value.get("metadata").and_then(|m| m.get(field)))
+                .cloned()
+                .unwrap_or_default();
+            (field.clone(), found)
+        })
+        .collect();
+    serde_json::Value::Object(projected)
+}
+
+fn format_buffer_list_text<'a>(
+    buffers: &[Buffer],
+    coverage_of: impl Fn(&Buffer) -> Option<&'a EmbeddingCoverage>,
+) -> String {
     if buffers.is_empty() {
         return "No buffers found.\n".to_string();
     }
@@ -87,10 +276,10 @@ fn format_buffer_list_text(buffers: &[Buffer]) -> String {
     output.push_str("Buffers:\n");
     let _ = writeln!(
         output,
-        "{:<6} {:<20} {:<12} {:<8} Source",
-        "ID", "Name", "Size", "Chunks"
+        "{:<6} {:<20} {:<12} {:<8} {:<9} Source",
+        "ID", "Name", "Size", "Chunks", "Embedded"
     );
-    output.push_str(&"-".repeat(70));
+    output.push_str(&"-".repeat(80));
     output.push('\n');
 
     for buffer in buffers {
@@ -101,6 +290,9 @@ fn format_buffer_list_text(buffers: &[Buffer]) -> String {
             .metadata
             .chunk_count
             .map_or_else(|| "-".to_string(), |c| c.to_string());
+        let embedded = coverage_of(buffer)
+            .filter(|c| c.total_chunks > 0)
+            .map_or_else(|| "-".to_string(), |c| format!("{:.0}%", c.percentage()));
         let source = buffer
             .source
             .as_ref()
@@ -108,11 +300,12 @@ fn format_buffer_list_text(buffers: &[Buffer]) -> String {
 
         let _ = writeln!(
             output,
-            "{:<6} {:<20} {:<12} {:<8} {}",
+            "{:<6} {:<20} {:<12} {:<8} {:<9} {}",
             id,
             truncate(name, 20),
             size,
             chunks,
+            embedded,
             truncate(&source, 30)
         );
     }
@@ -120,6 +313,193 @@ fn format_buffer_list_text(buffers: &[Buffer]) -> String {
     output
 }
 
+/// Formats buffers as NUL-terminated `id<TAB>name` records.
+///
+/// Unnamed buffers get an empty name. Names are written verbatim, so a
+/// record can be split on its first tab even if the name contains
+/// newlines.
+#[must_use]
+pub fn format_buffer_list_null(buffers: &[Buffer]) -> String {
+    null_records(buffers.iter().map(|buffer| {
+        format!(
+            "{}\t{}",
+            buffer.id.unwrap_or(0),
+            buffer.name.as_deref().unwrap_or_default()
+        )
+    }))
+}
+
+/// Formats the buffers in the trash.
+///
+/// JSON output lists the buffers as stored, including `deleted_at`.
+#[must_use]
+pub fn format_trash_list(buffers: &[Buffer], format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Text => format_trash_list_text(buffers),
+        OutputFormat::Json | OutputFormat::Ndjson => format_json(&buffers),
+    }
+}
+
+fn format_trash_list_text(buffers: &[Buffer]) -> String {
+    if buffers.is_empty() {
+        return "Trash is empty.\n".to_string();
+    }
+
+    let mut output = String::new();
+    output.push_str("Trash:\n");
+    let _ = writeln!(
+        output,
+        "{:<6} {:<20} {:<12} {:<8} Deleted at (unix)",
+        "ID", "Name", "Size", "Chunks"
+    );
+    output.push_str(&"-".repeat(70));
+    output.push('\n');
+
+    for buffer in buffers {
+        let id = buffer.id.map_or_else(|| "-".to_string(), |i| i.to_string());
+        let chunks = buffer
+            .metadata
+            .chunk_count
+            .map_or_else(|| "-".to_string(), |c| c.to_string());
+        let _ = writeln!(
+            output,
+            "{:<6} {:<20} {:<12} {:<8} {}",
+            id,
+            truncate(buffer.name.as_deref().unwrap_or("-"), 20),
+            format_size(buffer.metadata.size),
+            chunks,
+            buffer.metadata.deleted_at.unwrap_or(0)
+        );
+    }
+
+    output
+}
+
+/// Formats the saved versions of a buffer, newest first.
+#[must_use]
+pub fn format_buffer_versions(
+    buffer_name: &str,
+    versions: &[BufferVersion],
+    format: OutputFormat,
+) -> String {
+    match format {
+        OutputFormat::Text => format_buffer_versions_text(buffer_name, versions),
+        OutputFormat::Json | OutputFormat::Ndjson => format_json(&versions),
+    }
+}
+
+fn format_buffer_versions_text(buffer_name: &str, versions: &[BufferVersion]) -> String {
+    if versions.is_empty() {
+        return format!(
+            "No saved versions of '{buffer_name}'. Use 'rlm-rs update --history' to keep them.\n"
+        );
+    }
+
+    let mut output = String::new();
+    let _ = writeln!(output, "History of '{buffer_name}':");
+    let _ = writeln!(
+        output,
+        "{:<8} {:<12} {:<18} Saved at (unix)",
+        "Version", "Size", "Hash"
+    );
+    output.push_str(&"-".repeat(60));
+    output.push('\n');
+
+    for version in versions {
+        let _ = writeln!(
+            output,
+            "{:<8} {:<12} {:<18} {}",
+            version.version,
+            format_size(version.size),
+            truncate(&version.content_hash, 16),
+            version.created_at
+        );
+    }
+
+    output
+}
+
+/// Formats the buffers referenced by the context.
+///
+/// Each entry pairs a referenced buffer ID with the resolved buffer, or
+/// `None` when the reference is dangling (the buffer no longer exists).
+#[must_use]
+pub fn format_context_buffers(refs: &[(i64, Option<Buffer>)], format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Text => format_context_buffers_text(refs),
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            let buffers: Vec<_> = refs
+                .iter()
+                .map(|(id, buffer)| {
+                    serde_json::json!({
+                        "id": id,
+                        "name": buffer.as_ref().and_then(|b| b.name.as_deref()),
+                        "size": buffer.as_ref().map(|b| b.metadata.size),
+                        "chunk_count": buffer.as_ref().and_then(|b| b.metadata.chunk_count),
+                        "dangling": buffer.is_none(),
+                    })
+                })
+                .collect();
+            let dangling = refs.iter().filter(|(_, b)| b.is_none()).count();
+            format_json(&serde_json::json!({
+                "buffer_count": refs.len(),
+                "dangling_count": dangling,
+                "buffers": buffers,
+            }))
+        }
+    }
+}
+
+fn format_context_buffers_text(refs: &[(i64, Option<Buffer>)]) -> String {
+    if refs.is_empty() {
+        return "No buffers in context.\n".to_string();
+    }
+
+    let mut output = String::new();
+    output.push_str("Context buffers:\n");
+    let _ = writeln!(
+        output,
+        "{:<6} {:<20} {:<12} {:<8} Status",
+        "ID", "Name", "Size", "Chunks"
+    );
+    output.push_str(&"-".repeat(60));
+    output.push('\n');
+
+    for (id, buffer) in refs {
+        if let Some(buffer) = buffer {
+            let name = buffer.name.as_deref().unwrap_or("-");
+            let chunks = buffer
+                .metadata
+                .chunk_count
+                .map_or_else(|| "-".to_string(), |c| c.to_string());
+            let _ = writeln!(
+                output,
+                "{:<6} {:<20} {:<12} {:<8} ok",
+                id,
+                truncate(name, 20),
+                format_size(buffer.metadata.size),
+                chunks
+            );
+        } else {
+            let _ = writeln!(
+                output,
+                "{:<6} {:<20} {:<12} {:<8} dangling",
+                id, "-", "-", "-"
+            );
+        }
+    }
+
+    let dangling = refs.iter().filter(|(_, b)| b.is_none()).count();
+    if dangling > 0 {
+        let _ = writeln!(
+            output,
+            "\n{dangling} dangling reference(s) to deleted buffers"
+        );
+    }
+
+    output
+}
+
 /// Formats a single buffer.
 #[must_use]
 pub fn format_buffer(buffer: &Buffer, chunks: Option<&[Chunk]>, format: OutputFormat) -> String {
@@ -158,6 +538,27 @@ fn format_buffer_text(buffer: &Buffer, chunks: Option<&[Chunk]>) -> String {
     if let Some(ref source) = buffer.source {
         let _ = writeln!(output, "  Source:       {}", source.display());
     }
+    if let Some(ref encoding) = buffer.metadata.source_encoding {
+        let _ = writeln!(output, "  Encoding:     {encoding}");
+    }
+    if let Some(ref line_ending) = buffer.metadata.line_ending {
+        let _ = writeln!(output, "  Line ending:  {line_ending}");
+    }
+    if buffer.metadata.whitespace_normalized {
+        output.push_str("  Whitespace:   normalized at load\n");
+    }
+    if let Some(serde_json::Value::Object(custom)) = buffer
+        .metadata
+        .custom
+        .as_deref()
+        .and_then(|c| serde_json::from_str(c).ok())
+        && !custom.is_empty()
+    {
+        output.push_str("  Metadata:\n");
+        for (key, value) in &custom {
+            let _ = writeln!(output, "    {key}: {value}");
+        }
+    }
 
     if let Some(chunks) = chunks {
         output.push('\n');
@@ -249,6 +650,31 @@ fn format_grep_text(matches: &[GrepMatch], pattern: &str) -> String {
     output
 }
 
+/// Formats grep matches as NUL-terminated `offset<TAB>snippet` records.
+///
+/// Snippets are written verbatim, including any newlines.
+#[must_use]
+pub fn format_grep_null(matches: &[GrepMatch]) -> String {
+    null_records(
+        matches
+            .iter()
+            .map(|m| format!("{}\t{}", m.offset, m.snippet)),
+    )
+}
+
+/// Joins records with a trailing NUL after each, like `find -print0`.
+///
+/// Used by the `--null` text mode; no headers or summaries are written,
+/// and an empty input yields empty output.
+pub fn null_records(records: impl IntoIterator<Item = String>) -> String {
+    let mut output = String::new();
+    for record in records {
+        output.push_str(&record);
+        output.push('\0');
+    }
+    output
+}
+
 /// Formats chunk indices.
 #[must_use]
 pub fn format_chunk_indices(indices: &[(usize, usize)], format: OutputFormat) -> String {
@@ -313,6 +739,33 @@ fn format_json<T: Serialize>(value: &T) -> String {
     serde_json::to_string_pretty(value).unwrap_or_else(|_| "{}".to_string())
 }
 
+/// Formats values as NDJSON: one compact JSON value per line.
+#[must_use]
+pub fn format_ndjson<T: Serialize>(values: impl IntoIterator<Item = T>) -> String {
+    values
+        .into_iter()
+        .filter_map(|value| serde_json::to_string(&value).ok())
+        .map(|line| line + "\n")
+        .collect()
+}
+
+/// Formats a JSON object holding a list of records as NDJSON.
+///
+/// The first line is the object without its `records` array (counts,
+/// query, ...), followed by one line per record, so consumers can process
+/// records as they arrive.
+#[must_use]
+pub fn format_ndjson_records(mut json: serde_json::Value, records: &str) -> String {
+    let items = json
+        .as_object_mut()
+        .and_then(|object| object.remove(records));
+    let mut output = format_ndjson([&json]);
+    if let Some(serde_json::Value::Array(items)) = items {
+        output.push_str(&format_ndjson(&items));
+    }
+    output
+}
+
 /// Formats an error for output.
 ///
 /// When format is JSON, returns a structured error object.
@@ -354,14 +807,25 @@ const fn get_error_details(error: &crate::Error) -> (&'static str, Option<&'stat
                 "ChunkNotFound",
                 Some("Run 'rlm-rs chunk list <buffer>' to see valid chunk IDs"),
             ),
+            StorageError::BufferNameTaken { .. } => (
+                "BufferNameTaken",
+                Some("Choose another name or rename the existing buffer first"),
+            ),
+            StorageError::InvalidQuery { .. } => (
+                "InvalidQuery",
+                Some("Check the FTS5 query syntax, or drop --raw-query to match terms literally"),
+            ),
             StorageError::ContextNotFound => ("ContextNotFound", Some("Context not yet created")),
             StorageError::Database(_) => ("DatabaseError", None),
             StorageError::Migration(_) => ("MigrationError", None),
+            StorageError::SchemaTooNew { .. } => (
+                "SchemaTooNew",
+                Some("Upgrade rlm-rs to the version that created this database"),
+            ),
             StorageError::Transaction(_) => ("TransactionError", None),
             StorageError::Serialization(_) => ("SerializationError", None),
-            #[cfg(feature = "usearch-hnsw")]
+            #[cfg(any(feature = "usearch-hnsw", feature = "sqlite-vec"))]
             StorageError::VectorSearch(_) => ("VectorSearchError", None),
-            #[cfg(feature = "fastembed-embeddings")]
             StorageError::Embedding(_) => {
                 ("EmbeddingError", Some("Check disk space and try again"))
             }
@@ -406,6 +870,10 @@ const fn get_error_details(error: &crate::Error) -> (&'static str, Option<&'stat
         },
         crate::Error::InvalidState { .. } => ("InvalidState", None),
         crate::Error::Config { .. } => ("ConfigError", None),
+        crate::Error::Fts5Unavailable { .. } => (
+            "Fts5Unavailable",
+            Some("Use a SQLite build with FTS5 enabled (e.g. the bundled SQLite)"),
+        ),
         crate::Error::Search(_) => ("SearchError", None),
     }
 }
@@ -424,14 +892,17 @@ fn format_size(bytes: usize) -> String {
     }
 }
 
-/// Truncates a string to max length with ellipsis.
+/// Truncates a string to max length (in bytes) with ellipsis.
+///
+/// Cuts at a UTF-8 character boundary, so the result may be shorter.
 fn truncate(s: &str, max_len: usize) -> String {
     if s.len() <= max_len {
         s.to_string()
     } else if max_len <= 3 {
-        s[..max_len].to_string()
+        s[..crate::io::find_char_boundary(s, max_len)].to_string()
     } else {
-        format!("{}...", &s[..max_len - 3])
+        let end = crate::io::find_char_boundary(s, max_len - 3);
+        format!("{}...", &s[..end])
     }
 }
 
@@ -459,6 +930,28 @@ mod tests {
         assert!(!OutputFormat::Text.is_streaming());
     }
 
+    #[test]
+    fn test_format_ndjson_records() {
+        let json = serde_json::json!({
+            "query": "q",
+            "count": 2,
+            "results": [{ "id": 1 }, { "id": 2 }]
+        });
+        let output = format_ndjson_records(json, "results");
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 3);
+        let header: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(header, serde_json::json!({ "query": "q", "count": 2 }));
+        assert_eq!(lines[1], r#"{"id":1}"#);
+        assert_eq!(lines[2], r#"{"id":2}"#);
+        assert!(output.ends_with('\n'));
+
+        // No records leaves just the header
+        let json = serde_json::json!({ "count": 0, "results": [] });
+        assert_eq!(format_ndjson_records(json, "results"), "{\"count\":0}\n");
+        assert_eq!(format_ndjson([1, 2]), "1\n2\n");
+    }
+
     #[test]
     fn test_format_size() {
         assert_eq!(format_size(100), "100 B");
@@ -485,16 +978,20 @@ mod tests {
             total_content_size: 1024,
             has_context: true,
             schema_version: 1,
+            corpus_version: 12,
+            trashed_buffer_count: 1,
             db_size: Some(4096),
         };
 
-        let text = format_status(&stats, OutputFormat::Text);
-        assert!(text.contains("Buffers:       2"));
+        let text = format_status(&stats, None, OutputFormat::Text);
+        assert!(text.contains("Buffers:       2 (1 in trash)"));
         assert!(text.contains("Chunks:        10"));
         assert!(text.contains("DB size:"));
+        assert!(text.contains("Corpus:        rev 12"));
 
-        let json = format_status(&stats, OutputFormat::Json);
+        let json = format_status(&stats, None, OutputFormat::Json);
         assert!(json.contains("\"buffer_count\": 2"));
+        assert!(json.contains("\"corpus_version\": 12"));
     }
 
     #[test]
@@ -505,21 +1002,118 @@ mod tests {
             total_content_size: 0,
             has_context: false,
             schema_version: 1,
+            corpus_version: 0,
+            trashed_buffer_count: 0,
             db_size: None,
         };
 
-        let text = format_status(&stats, OutputFormat::Text);
+        let text = format_status(&stats, None, OutputFormat::Text);
         assert!(text.contains("Context:       no"));
         assert!(!text.contains("DB size:"));
+        assert!(!text.contains("trash"));
+    }
+
+    #[test]
+    fn test_format_status_detailed() {
+        use crate::storage::BufferStats;
+
+        let stats = StorageStats {
+            buffer_count: 1,
+            chunk_count: 4,
+            ..StorageStats::default()
+        };
+        let details = DetailedStats {
+            embedded_chunks: 3,
+            unembedded_chunks: 1,
+            embedding_bytes: 4608,
+            models: vec!["model-a".to_string(), "model-b".to_string()],
+            buffers: vec![BufferStats {
+                buffer_id: 7,
+                name: Some("docs".to_string()),
+                chunk_count: 4,
+                embedded_chunks: 3,
+                unembedded_chunks: 1,
+                avg_chunk_size: 1500.4,
+                embedding_bytes: 4608,
+            }],
+        };
+
+        let text = format_status(&stats, Some(&details), OutputFormat::Text);
+        assert!(text.starts_with(&format_status(&stats, None, OutputFormat::Text)));
+        assert!(text.contains("Embedded:      3 (1 missing)"));
+        assert!(text.contains("Models:        model-a, model-b"));
+        assert!(text.contains("[7] docs: 4 chunks, 3 embedded, 1 missing, avg 1500 B/chunk"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&format_status(&stats, Some(&details), OutputFormat::Json))
+                .unwrap();
+        assert_eq!(json["buffer_count"], 1);
+        assert_eq!(json["embedding_bytes"], 4608);
+        assert_eq!(json["buffers"][0]["unembedded_chunks"], 1);
+    }
+
+    #[test]
+    fn test_format_storage_usage() {
+        let usage = StorageUsage {
+            buffer_content_bytes: 2048,
+            chunk_content_bytes: 2100,
+            embedding_bytes: 1536,
+            embedding_count: 1,
+            fts_bytes: Some(4096),
+            db_bytes: 16384,
+            tables: vec![crate::storage::TableUsage {
+                name: "chunks".to_string(),
+                bytes: 8192,
+            }],
+        };
+
+        let text = format_storage_usage(&usage, OutputFormat::Text);
+        assert!(text.contains("Embeddings:      1.5 KB (1 vectors)"));
+        assert!(text.contains("FTS index:       4.0 KB"));
+        assert!(text.contains("chunks"));
+
+        let no_dbstat = StorageUsage {
+            fts_bytes: None,
+            tables: Vec::new(),
+            ..usage
+        };
+        let text = format_storage_usage(&no_dbstat, OutputFormat::Text);
+        assert!(text.contains("dbstat unavailable"));
+        assert!(!text.contains("table/index"));
+
+        let json = format_storage_usage(&usage, OutputFormat::Json);
+        assert!(json.contains("\"fts_bytes\": 4096"));
+    }
+
+    #[test]
+    fn test_format_context_buffers() {
+        let mut buffer = Buffer::from_named("notes".to_string(), "content".to_string());
+        buffer.id = Some(1);
+        let refs = vec![(1, Some(buffer)), (7, None)];
+
+        let text = format_context_buffers(&refs, OutputFormat::Text);
+        assert!(text.contains("notes"));
+        assert!(text.contains("dangling"));
+        assert!(text.contains("1 dangling reference(s)"));
+
+        let json = format_context_buffers(&refs, OutputFormat::Json);
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["buffer_count"], 2);
+        assert_eq!(value["dangling_count"], 1);
+        assert_eq!(value["buffers"][0]["name"], "notes");
+        assert_eq!(value["buffers"][1]["dangling"], true);
+
+        let empty = format_context_buffers(&[], OutputFormat::Text);
+        assert!(empty.contains("No buffers in context"));
     }
 
     #[test]
     fn test_format_buffer_list_empty() {
         let buffers: Vec<Buffer> = vec![];
-        let text = format_buffer_list(&buffers, OutputFormat::Text);
+        let text = format_buffer_list(&buffers, &[], &[], OutputFormat::Text);
         assert!(text.contains("No buffers found"));
 
-        let json = format_buffer_list(&buffers, OutputFormat::Json);
+        let json = format_buffer_list(&buffers, &[], &[], OutputFormat::Json);
         assert!(json.contains("[]"));
     }
 
@@ -531,12 +1125,69 @@ mod tests {
         buffer.metadata.chunk_count = Some(3);
 
         let buffers = vec![buffer];
-        let text = format_buffer_list(&buffers, OutputFormat::Text);
+        let coverage = [EmbeddingCoverage {
+            buffer_id: 1,
+            total_chunks: 3,
+            embedded_chunks: 2,
+        }];
+        let text = format_buffer_list(&buffers, &coverage, &[], OutputFormat::Text);
         assert!(text.contains("test"));
         assert!(text.contains('1'));
+        assert!(text.contains("Embedded"));
+        assert!(text.contains("67%"));
 
-        let json = format_buffer_list(&buffers, OutputFormat::Json);
+        let json = format_buffer_list(&buffers, &coverage, &[], OutputFormat::Json);
         assert!(json.contains("\"name\": \"test\""));
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value[0]["embedded_count"], 2);
+        assert!(value[0]["embedded_percent"].as_f64().unwrap() > 66.0);
+
+        // Buffers missing from the coverage report show no percentage
+        let text = format_buffer_list(&buffers, &[], &[], OutputFormat::Text);
+        assert!(!text.contains('%'));
+        let json = format_buffer_list(&buffers, &[], &[], OutputFormat::Json);
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert!(value[0]["embedded_count"].is_null());
+    }
+
+    #[test]
+    fn test_format_buffer_list_fields() {
+        let mut buffer = Buffer::from_named("test".to_string(), "content".to_string());
+        buffer.id = Some(1);
+        buffer.metadata.chunk_count = Some(3);
+        let coverage = [EmbeddingCoverage {
+            buffer_id: 1,
+            total_chunks: 3,
+            embedded_chunks: 3,
+        }];
+        let fields: Vec<String> = [
+            "id",
+            "name",
+            "size",
+            "chunk_count",
+            "embedded_count",
+            "custom",
+        ]
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+
+        let json = format_buffer_list(&[buffer.clone()], &coverage, &fields, OutputFormat::Json);
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let object = value[0].as_object().unwrap();
+        assert_eq!(object.len(), fields.len());
+        assert_eq!(value[0]["id"], 1);
+        assert_eq!(value[0]["name"], "test");
+        assert_eq!(value[0]["size"], 7);
+        assert_eq!(value[0]["chunk_count"], 3);
+        assert_eq!(value[0]["embedded_count"], 3);
+        // Unset fields are present as null
+        assert!(value[0]["custom"].is_null());
+        assert!(!json.contains("content_hash"));
+
+        let ndjson = format_buffer_list(&[buffer], &coverage, &fields, OutputFormat::Ndjson);
+        assert_eq!(ndjson.lines().count(), 1);
+        assert!(!ndjson.contains("metadata"));
     }
 
     #[test]
@@ -599,6 +1250,27 @@ mod tests {
         assert!(text.ends_with("---\n"));
     }
 
+    #[test]
+    fn test_format_null_records() {
+        let mut named = Buffer::from_named("multi\nline".to_string(), "a".to_string());
+        named.id = Some(1);
+        let mut unnamed = Buffer::from_content("b".to_string());
+        unnamed.id = Some(2);
+        assert_eq!(
+            format_buffer_list_null(&[named, unnamed]),
+            "1\tmulti\nline\x002\t\0"
+        );
+
+        let matches = vec![GrepMatch {
+            offset: 4,
+            matched: "x".to_string(),
+            snippet: "a\nx b".to_string(),
+        }];
+        assert_eq!(format_grep_null(&matches), "4\ta\nx b\0");
+
+        assert!(format_buffer_list_null(&[]).is_empty());
+    }
+
     #[test]
     fn test_format_grep_matches_empty() {
         let matches: Vec<GrepMatch> = vec![];
@@ -675,6 +1347,16 @@ mod tests {
         assert!(json.contains("\"variables\""));
     }
 
+    #[test]
+    fn test_format_error_fts5_unavailable() {
+        let err = crate::Error::Fts5Unavailable {
+            reason: "no such module: fts5".to_string(),
+        };
+        let json = format_error(&err, OutputFormat::Json);
+        assert!(json.contains("\"Fts5Unavailable\""));
+        assert!(json.contains("FTS5 enabled"));
+    }
+
     #[test]
     fn test_format_json_error() {
         // Test that format_json handles errors gracefully
@@ -2,10 +2,16 @@
 //!
 //! Defines the CLI structure using clap derive macros.
 
-use clap::{Parser, Subcommand};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, Parser, Subcommand};
 use std::path::PathBuf;
 
+use super::config::Config;
 use crate::chunking::{DEFAULT_CHUNK_SIZE, DEFAULT_OVERLAP};
+use crate::embedding::DEFAULT_EMBED_BATCH_SIZE;
+use crate::io::DEFAULT_PREVIEW_LEN;
+use crate::search::{DEFAULT_IVF_ITERATIONS, DEFAULT_IVF_NPROBE, DEFAULT_RECALL_SAMPLES};
+use crate::storage::{DEFAULT_KEEP_VERSIONS, JournalMode, SqliteOptions};
 
 /// RLM-RS: Recursive Language Model REPL for Claude Code.
 ///
@@ -18,7 +24,8 @@ use crate::chunking::{DEFAULT_CHUNK_SIZE, DEFAULT_OVERLAP};
 pub struct Cli {
     /// Path to the RLM database file.
     ///
-    /// Defaults to `.rlm/rlm-state.db` in the current directory.
+    /// Defaults to `db_path` from `.rlm/config.toml`, else
+    /// `.rlm/rlm-state.db` in the current directory.
     #[arg(short, long, env = "RLM_DB_PATH")]
     pub db_path: Option<PathBuf>,
 
@@ -30,6 +37,66 @@ pub struct Cli {
     #[arg(long, default_value = "text", global = true)]
     pub format: String,
 
+    /// Write NUL-terminated records in text mode for `list`, `grep`, and `search`.
+    ///
+    /// Like `find -print0`: no headers, fields separated by tabs with free
+    /// text last, each record ending in a NUL byte. Pair with `xargs -0`.
+    #[arg(short = '0', long = "null", global = true)]
+    pub null: bool,
+
+    /// `SQLite` journal mode (delete, wal, memory).
+    ///
+    /// Use `delete` on network filesystems where WAL files are problematic.
+    #[arg(
+        long,
+        env = "RLM_JOURNAL_MODE",
+        default_value = "wal",
+        value_parser = ["delete", "wal", "memory"],
+        global = true
+    )]
+    pub journal: String,
+
+    /// `SQLite` page cache size in KiB (default: scaled to database size).
+    #[arg(long, env = "RLM_CACHE_SIZE", global = true)]
+    pub cache_size: Option<u64>,
+
+    /// `SQLite` memory-map size in bytes, 0 to disable (default: scaled to database size).
+    #[arg(long, env = "RLM_MMAP_SIZE", global = true)]
+    pub mmap_size: Option<u64>,
+
+    /// Maximum chunks sent to the embedder per batch.
+    ///
+    /// Lower it if a remote embedding backend rejects large payloads.
+    #[arg(
+        long,
+        env = "RLM_EMBED_BATCH_SIZE",
+        default_value_t = DEFAULT_EMBED_BATCH_SIZE,
+        value_parser = parse_positive,
+        global = true
+    )]
+    pub embed_batch_size: usize,
+
+    /// Worker threads for parallel chunking and embedding (default: available parallelism).
+    #[arg(long, env = "RLM_THREADS", value_parser = parse_positive, global = true)]
+    pub threads: Option<usize>,
+
+    /// Embedding dimensions for custom models (default: the model's native size).
+    ///
+    /// Sizes the fallback embedder, requests this many dimensions from
+    /// OpenAI-compatible endpoints, and rejects embeddings of any other size.
+    #[arg(long, env = "RLM_EMBEDDING_DIM", value_parser = parse_positive, global = true)]
+    pub embedding_dim: Option<usize>,
+
+    /// Also write the command's output to this file (overwritten).
+    ///
+    /// Output is written in the selected `--format`; errors are not copied.
+    #[arg(long, value_name = "FILE", global = true)]
+    pub tee: Option<PathBuf>,
+
+    /// Project defaults from `.rlm/config.toml`; see [`Cli::apply_config`].
+    #[arg(skip)]
+    pub config: Config,
+
     /// The subcommand to execute.
     #[command(subcommand)]
     pub command: Commands,
@@ -53,7 +120,74 @@ pub enum Commands {
     },
 
     /// Show current RLM state status.
-    Status,
+    ///
+    /// With `--detailed`, also shows embedding coverage, vector bytes, the
+    /// embedding models in use, and per-buffer chunk counts and sizes.
+    #[command(after_help = r#"Examples:
+  rlm-rs status                  # Aggregate counts
+  rlm-rs status --detailed       # Per-buffer and embedding breakdown
+  rlm-rs --format json status --detailed | jq '.buffers'
+"#)]
+    Status {
+        /// Add per-buffer and embedding breakdowns.
+        #[arg(long)]
+        detailed: bool,
+    },
+
+    /// Check whether the database and embedder are ready for use.
+    ///
+    /// Reports each check (database, initialized, schema, fts5, embedder)
+    /// with an overall `ok`, plus buffer/chunk/embedding counts. Never
+    /// creates the database.
+    #[command(after_help = r#"Examples:
+  rlm-rs health                                  # Human-readable summary
+  rlm-rs --format json health | jq -e .ok        # Readiness gate (exit 1 if not ok)
+  rlm-rs health --probe-embedder                 # Also embed a test string
+"#)]
+    Health {
+        /// Embed a short test string to verify the model loads.
+        ///
+        /// May download the model on first use.
+        #[arg(long)]
+        probe_embedder: bool,
+    },
+
+    /// Show the effective configuration.
+    ///
+    /// `show` (or `--print`) lists the resolved global settings, chunking
+    /// defaults, search mode, and embedding model, each with its source: a
+    /// flag, an environment variable, `.rlm/config.toml`, or the built-in
+    /// default.
+    #[command(arg_required_else_help = true)]
+    #[command(after_help = r#"Examples:
+  rlm-rs config show                             # Settings and their sources
+  RLM_THREADS=2 rlm-rs config show               # Shows threads from env
+  rlm-rs --format json config show | jq '.db_path'
+"#)]
+    Config {
+        /// Print the resolved configuration and where each value came from.
+        #[arg(long)]
+        print: bool,
+
+        /// Config action.
+        #[command(subcommand)]
+        action: Option<ConfigCommands>,
+    },
+
+    /// Print a shell completion script to stdout.
+    ///
+    /// Covers every subcommand, including nested ones such as `chunk get`.
+    #[command(after_help = r#"Examples:
+  rlm-rs completions bash > /etc/bash_completion.d/rlm-rs
+  rlm-rs completions zsh > "${fpath[1]}/_rlm-rs"
+  rlm-rs completions fish > ~/.config/fish/completions/rlm-rs.fish
+  rlm-rs completions powershell >> $PROFILE
+"#)]
+    Completions {
+        /// Shell to generate completions for (bash, zsh, fish, powershell, elvish).
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
 
     /// Reset RLM state (delete all data).
     Reset {
@@ -62,6 +196,95 @@ pub enum Commands {
         yes: bool,
     },
 
+    /// Refresh `SQLite` query planner statistics (ANALYZE).
+    ///
+    /// Run after large loads or deletes so searches use good query plans.
+    #[command(after_help = r#"Examples:
+  rlm-rs analyze                 # Refresh planner statistics
+  rlm-rs --format json analyze   # JSON output
+"#)]
+    Analyze,
+
+    /// Reclaim disk space freed by deletions (VACUUM).
+    ///
+    /// Rebuilds the database file and truncates the write-ahead log.
+    #[command(after_help = r#"Examples:
+  rlm-rs compact                 # Shrink the database after deletes
+  rlm-rs --format json compact   # JSON output with bytes reclaimed
+"#)]
+    Compact,
+
+    /// Show how much space content, embeddings, and the FTS index use.
+    ///
+    /// Per-table sizes come from `SQLite`'s `dbstat` table when available.
+    #[command(after_help = r#"Examples:
+  rlm-rs usage                   # Storage breakdown
+  rlm-rs --format json usage     # JSON output
+"#)]
+    Usage,
+
+    /// Repair embeddings whose dimensions don't match the current embedder.
+    ///
+    /// Embeddings from a previous model (or a differently configured one)
+    /// can't be compared with current query vectors. By default they are
+    /// deleted; `--reembed` regenerates them with the current embedder.
+    #[command(after_help = r#"Examples:
+  rlm-rs fix-dimensions                        # Delete mismatched embeddings
+  rlm-rs fix-dimensions --reembed              # Re-embed mismatched chunks
+  rlm-rs fix-dimensions --target-dim 1024      # Keep only 1024-dim embeddings
+  rlm-rs --format json fix-dimensions | jq '.mismatched'
+"#)]
+    FixDimensions {
+        /// Expected embedding dimensions (default: current embedder's).
+        #[arg(long)]
+        target_dim: Option<usize>,
+
+        /// Re-embed mismatched chunks instead of deleting their embeddings.
+        #[arg(long)]
+        reembed: bool,
+    },
+
+    /// Re-embed every chunk whose embedding drifted from the current embedder.
+    ///
+    /// Scans all buffers for embeddings whose dimensions differ from the
+    /// current embedder's, and with `--model-check` also those from another
+    /// model, then regenerates them one buffer at a time. Each buffer is
+    /// migrated in a single transaction, so a failure never leaves a buffer
+    /// half-migrated; failed buffers are reported and keep their old
+    /// embeddings until the next run.
+    #[command(after_help = r#"Examples:
+  rlm-rs reembed-all                           # Fix dimension mismatches
+  rlm-rs reembed-all --model-check             # Also re-embed other models' vectors
+  rlm-rs --format json reembed-all | jq '.by_model'
+"#)]
+    ReembedAll {
+        /// Also re-embed same-size embeddings made by a different model.
+        #[arg(long)]
+        model_check: bool,
+    },
+
+    /// Compare the embeddings of two chunks.
+    ///
+    /// Reports cosine similarity, Euclidean distance, and the dimensions
+    /// that contribute most to the difference, for debugging embedding
+    /// quality.
+    #[command(after_help = r#"Examples:
+  rlm-rs diff-embeddings 12 40               # Similarity and top 10 dimensions
+  rlm-rs diff-embeddings 12 40 --top 3
+  rlm-rs --format json diff-embeddings 12 40 | jq '.cosine_similarity'
+"#)]
+    DiffEmbeddings {
+        /// First chunk ID.
+        a: i64,
+
+        /// Second chunk ID.
+        b: i64,
+
+        /// Number of most-different dimensions to list.
+        #[arg(long, default_value = "10")]
+        top: usize,
+    },
+
     /// Load a context file into a buffer.
     #[command(after_help = r#"Examples:
   rlm-rs load large_file.txt                      # Load with semantic chunking
@@ -69,17 +292,108 @@ pub enum Commands {
   rlm-rs load src/lib.rs --chunker code           # Code-aware chunking
   rlm-rs load doc.md --chunker fixed --chunk-size 2000
   rlm-rs load big.log --chunker parallel          # Parallel for large files
+  rlm-rs load notes.txt --encoding windows-1252   # Force a source encoding
+  curl -s https://example.com/doc | rlm-rs load - --name doc  # Read stdin
+  rlm-rs load https://example.com/guide.md        # Fetch a URL (`http` feature)
   rlm-rs --format json load file.txt | jq '.buffer_id'
 "#)]
     Load {
-        /// Path to the context file.
+        /// Path to the context file, `-` to read stdin, or an http(s) URL.
+        ///
+        /// URLs require the `http` feature.
         file: PathBuf,
 
-        /// Optional name for the buffer.
+        /// Optional name for the buffer (required when reading stdin).
         #[arg(short, long)]
         name: Option<String>,
 
-        /// Chunking strategy (fixed, semantic, code, parallel).
+        /// Chunking strategy (fixed, semantic, code, markdown, csv, json, parallel).
+        #[arg(short, long, default_value = "semantic")]
+        chunker: String,
+
+        /// Chunk size in characters.
+        #[arg(long, default_value_t = DEFAULT_CHUNK_SIZE)]
+        chunk_size: usize,
+
+        /// Overlap between chunks in characters.
+        #[arg(long, default_value_t = DEFAULT_OVERLAP)]
+        overlap: usize,
+
+        /// Unit for --chunk-size and --overlap (chars, tokens).
+        ///
+        /// `tokens` is supported by the fixed and semantic chunkers.
+        #[arg(long, default_value = "chars", value_parser = ["chars", "tokens"])]
+        chunk_unit: String,
+
+        /// Source encoding (e.g. utf-16le, windows-1252) instead of detecting it.
+        ///
+        /// Encodings other than UTF-8 require the `encoding` feature.
+        #[arg(long)]
+        encoding: Option<String>,
+
+        /// Canonicalize whitespace before chunking: LF line endings, no
+        /// trailing whitespace, and single spaces within lines.
+        ///
+        /// Leading indentation and line breaks are kept. Off by default, as
+        /// the stored content then differs from the source.
+        #[arg(long, visible_alias = "canonicalize-whitespace")]
+        normalize_whitespace: bool,
+    },
+
+    /// Load every file in a directory as a buffer named by its relative path.
+    ///
+    /// Files that can't be decoded as text (e.g. binaries) are skipped and
+    /// files that can't be read are reported as failures; neither stops the
+    /// import. Hidden files and directories are ignored.
+    #[command(after_help = r#"Examples:
+  rlm-rs import-dir docs                          # Top-level files only
+  rlm-rs import-dir src -r --glob '*.rs' --chunker code
+  rlm-rs import-dir . -r --glob '{src,tests}/**/*.{rs,toml}'
+  rlm-rs --format json import-dir notes -r | jq '.skipped'
+"#)]
+    ImportDir {
+        /// Directory to import.
+        dir: PathBuf,
+
+        /// Only import files matching this glob (`*`, `?`, `**`, `{a,b}`).
+        ///
+        /// A pattern without `/` matches file names, e.g. `*.md`.
+        #[arg(short, long)]
+        glob: Option<String>,
+
+        /// Chunking strategy (fixed, semantic, code, markdown, csv, json, parallel).
+        #[arg(short, long, default_value = "semantic")]
+        chunker: String,
+
+        /// Chunk size in characters.
+        #[arg(long, default_value_t = DEFAULT_CHUNK_SIZE)]
+        chunk_size: usize,
+
+        /// Overlap between chunks in characters.
+        #[arg(long, default_value_t = DEFAULT_OVERLAP)]
+        overlap: usize,
+
+        /// Descend into subdirectories.
+        #[arg(short, long)]
+        recursive: bool,
+    },
+
+    /// Preview how a file would be chunked, without touching the database.
+    ///
+    /// Prints each chunk's index, byte range, line range, size, token
+    /// estimate, and a short preview so chunker settings can be tuned
+    /// before loading.
+    #[command(after_help = r#"Examples:
+  rlm-rs explain-chunking doc.md                          # Default semantic chunking
+  rlm-rs explain-chunking src/lib.rs --chunker code       # Code-aware boundaries
+  rlm-rs explain-chunking big.log --chunk-size 1000 --overlap 100
+  rlm-rs --format json explain-chunking doc.md | jq '.chunk_count'
+"#)]
+    ExplainChunking {
+        /// Path to the file to chunk.
+        file: PathBuf,
+
+        /// Chunking strategy (fixed, semantic, code, markdown, csv, json, parallel).
         #[arg(short, long, default_value = "semantic")]
         chunker: String,
 
@@ -97,9 +411,29 @@ pub enum Commands {
     #[command(after_help = r#"Examples:
   rlm-rs list                            # List all buffers
   rlm-rs ls                              # Alias for list
+  rlm-rs list --tag api --tag v2         # Buffers tagged api and v2
+  rlm-rs list --sample 5 --seed 42       # Reproducible random sample
   rlm-rs --format json list | jq '.[].name'
+  rlm-rs --format json list --fields id,name,size,chunk_count
 "#)]
-    ListBuffers,
+    ListBuffers {
+        /// Only list buffers with this tag (repeatable; all must match).
+        #[arg(long)]
+        tag: Vec<String>,
+
+        /// List N buffers chosen at random.
+        #[arg(long, value_name = "N")]
+        sample: Option<usize>,
+
+        /// Seed for a reproducible --sample.
+        #[arg(long, requires = "sample")]
+        seed: Option<u64>,
+
+        /// Only include these fields per buffer in JSON output
+        /// (comma-separated, e.g. `id,name,size,chunk_count`).
+        #[arg(long, value_delimiter = ',', value_name = "FIELDS")]
+        fields: Vec<String>,
+    },
 
     /// Show buffer details.
     #[command(name = "show")]
@@ -119,7 +453,15 @@ pub enum Commands {
     },
 
     /// Delete a buffer.
-    #[command(name = "delete", alias = "rm")]
+    #[command(
+        name = "delete",
+        alias = "rm",
+        after_help = r#"Examples:
+  rlm-rs delete notes.txt --yes                 # Delete permanently
+  rlm-rs delete notes.txt --soft                # Move to the trash
+  rlm-rs restore notes.txt                      # Undo a soft delete
+"#
+    )]
     DeleteBuffer {
         /// Buffer ID or name.
         buffer: String,
@@ -127,6 +469,104 @@ pub enum Commands {
         /// Skip confirmation prompt.
         #[arg(short = 'y', long)]
         yes: bool,
+
+        /// Move the buffer to the trash instead of deleting it (no --yes needed).
+        ///
+        /// Trashed buffers are hidden from listings, status, and search
+        /// until restored with `restore` or removed with `purge`.
+        #[arg(long)]
+        soft: bool,
+    },
+
+    /// List buffers in the trash, most recently deleted first.
+    #[command(after_help = r#"Examples:
+  rlm-rs list-trash                             # Show trashed buffers
+  rlm-rs --format json list-trash               # JSON output
+"#)]
+    ListTrash,
+
+    /// Restore a buffer from the trash.
+    #[command(after_help = r#"Examples:
+  rlm-rs restore notes.txt                      # Restore by name
+  rlm-rs restore 3                              # Restore by ID
+"#)]
+    Restore {
+        /// Trashed buffer ID or name.
+        buffer: String,
+    },
+
+    /// Permanently delete every buffer in the trash with its chunks and embeddings.
+    #[command(after_help = r#"Examples:
+  rlm-rs purge --yes                            # Empty the trash
+"#)]
+    Purge {
+        /// Skip confirmation prompt.
+        #[arg(short = 'y', long)]
+        yes: bool,
+    },
+
+    /// Rename a buffer, keeping its chunks and embeddings.
+    #[command(after_help = r#"Examples:
+  rlm-rs rename notes.txt meeting-notes         # Rename by name
+  rlm-rs rename 3 api-spec                      # Rename by ID
+  rlm-rs --format json rename 3 api-spec        # JSON output
+"#)]
+    Rename {
+        /// Buffer ID or name.
+        buffer: String,
+
+        /// New buffer name (must not be used by another buffer).
+        new_name: String,
+    },
+
+    /// Add or remove buffer tags.
+    ///
+    /// Without `--add` or `--remove`, shows the buffer's tags. Without a
+    /// buffer, lists every tag in use with its buffer count.
+    #[command(after_help = r#"Examples:
+  rlm-rs tag docs --add api --add v2     # Tag a buffer
+  rlm-rs tag docs --remove v2            # Remove a tag
+  rlm-rs tag docs                        # Show a buffer's tags
+  rlm-rs tag                             # List all tags with counts
+"#)]
+    Tag {
+        /// Buffer ID or name (omit to list all tags).
+        buffer: Option<String>,
+
+        /// Tag to add (repeatable).
+        #[arg(long)]
+        add: Vec<String>,
+
+        /// Tag to remove (repeatable).
+        #[arg(long)]
+        remove: Vec<String>,
+    },
+
+    /// Get, set, or delete custom key/value metadata on a buffer.
+    ///
+    /// Without a key, lists all metadata. Values are parsed as JSON when
+    /// possible (numbers, booleans, objects) and stored as strings otherwise.
+    #[command(after_help = r#"Examples:
+  rlm-rs buffer-meta docs project rlm              # Set a string value
+  rlm-rs buffer-meta docs stars 42                 # Set a JSON number
+  rlm-rs buffer-meta docs project                  # Get one key
+  rlm-rs buffer-meta docs                          # List all keys
+  rlm-rs buffer-meta docs project --delete         # Delete a key
+"#)]
+    BufferMeta {
+        /// Buffer ID or name.
+        buffer: String,
+
+        /// Metadata key (omit to list all metadata).
+        key: Option<String>,
+
+        /// Value to store under the key.
+        #[arg(conflicts_with = "delete")]
+        value: Option<String>,
+
+        /// Delete the key.
+        #[arg(short, long, requires = "key")]
+        delete: bool,
     },
 
     /// Peek at buffer content.
@@ -200,6 +640,66 @@ pub enum Commands {
         prefix: String,
     },
 
+    /// Merge several buffers into a new one, re-chunked and embedded.
+    ///
+    /// Source contents are concatenated in the order given, joined by
+    /// `--separator`. Sources named more than once are merged once.
+    #[command(after_help = r#"Examples:
+  rlm-rs merge part1 part2 part3 --name book          # Merge into 'book'
+  rlm-rs merge 4 5 --name api --chunker fixed         # Fixed-size chunks
+  rlm-rs merge ch1 ch2 --name book --delete-sources   # Remove the parts
+  rlm-rs merge a b --name ab --separator $'\n---\n'   # Custom separator
+  rlm-rs --format json merge a b --name ab | jq '.new_buffer_id'
+"#)]
+    Merge {
+        /// Buffer IDs or names to merge, in order.
+        #[arg(required = true)]
+        sources: Vec<String>,
+
+        /// Name for the merged buffer.
+        #[arg(short, long)]
+        name: String,
+
+        /// Chunking strategy (fixed, semantic, code, markdown, csv, json, parallel).
+        #[arg(short, long, default_value = "semantic")]
+        chunker: String,
+
+        /// Chunk size in characters.
+        #[arg(long, default_value_t = DEFAULT_CHUNK_SIZE)]
+        chunk_size: usize,
+
+        /// Overlap between chunks in characters.
+        #[arg(long, default_value_t = DEFAULT_OVERLAP)]
+        overlap: usize,
+
+        /// Text inserted between source buffers.
+        #[arg(long, default_value = "\n\n")]
+        separator: String,
+
+        /// Delete the source buffers after merging.
+        #[arg(long)]
+        delete_sources: bool,
+    },
+
+    /// Compare the chunks of two buffers.
+    ///
+    /// Chunks are matched by their whitespace-normalized content, so
+    /// buffers chunked with different strategies can still be compared;
+    /// their chunk boundaries rarely line up, though, and the output notes
+    /// when strategies differ.
+    #[command(after_help = r#"Examples:
+  rlm-rs diff draft-v1 draft-v2                 # Changed regions between versions
+  rlm-rs diff 3 7                               # Compare by ID
+  rlm-rs --format json diff old new | jq '.added'
+"#)]
+    Diff {
+        /// First (old) buffer ID or name.
+        a: String,
+
+        /// Second (new) buffer ID or name.
+        b: String,
+    },
+
     /// Add text to a buffer (intermediate results).
     AddBuffer {
         /// Buffer name.
@@ -211,12 +711,16 @@ pub enum Commands {
 
     /// Update an existing buffer with new content.
     ///
-    /// Re-chunks the buffer and incrementally updates embeddings.
+    /// Re-chunks the buffer. Chunks whose text is unchanged keep their
+    /// embeddings; other chunks are embedded only with `--embed`, or later
+    /// with `chunk embed`, which embeds just the chunks still missing one.
     #[command(after_help = r#"Examples:
   cat updated.txt | rlm-rs update main-source   # Update from stdin
   rlm-rs update my-buffer "new content"         # Update with inline content
   rlm-rs update my-buffer --embed               # Update and generate embeddings
-  rlm-rs update my-buffer --chunk-size 500      # Custom chunk size"#)]
+  rlm-rs update my-buffer --no-embed-on-update  # Embed nothing; unchanged chunks keep theirs
+  rlm-rs update my-buffer --chunk-size 500      # Custom chunk size
+  rlm-rs update my-buffer --history             # Save the old content first"#)]
     #[command(alias = "update")]
     UpdateBuffer {
         /// Buffer ID or name.
@@ -226,9 +730,15 @@ pub enum Commands {
         content: Option<String>,
 
         /// Automatically embed new chunks after update.
-        #[arg(short, long)]
+        #[arg(short, long, overrides_with = "no_embed_on_update")]
         embed: bool,
 
+        /// Don't embed new chunks (overrides an earlier `--embed`).
+        ///
+        /// Unchanged chunks still keep their embeddings.
+        #[arg(long, overrides_with = "embed")]
+        no_embed_on_update: bool,
+
         /// Chunking strategy (semantic, fixed, parallel).
         #[arg(long, default_value = "semantic")]
         strategy: String,
@@ -240,9 +750,62 @@ pub enum Commands {
         /// Chunk overlap in characters.
         #[arg(long, default_value_t = DEFAULT_OVERLAP)]
         overlap: usize,
+
+        /// Save the previous content as a version (see `history`).
+        #[arg(long)]
+        history: bool,
+
+        /// Number of saved versions to keep for the buffer.
+        #[arg(long, default_value_t = DEFAULT_KEEP_VERSIONS, value_parser = parse_positive, requires = "history")]
+        keep_versions: usize,
+    },
+
+    /// List the saved versions of a buffer, newest first.
+    ///
+    /// Versions are saved by `update --history` and `revert-buffer`.
+    #[command(after_help = r#"Examples:
+  rlm-rs history my-buffer                      # Show saved versions
+  rlm-rs --format json history my-buffer        # JSON output
+"#)]
+    History {
+        /// Buffer ID or name.
+        buffer: String,
+    },
+
+    /// Restore a buffer to a saved version.
+    ///
+    /// The current content is saved as a new version first, so a revert can
+    /// itself be reverted. The buffer is re-chunked with the default strategy.
+    #[command(after_help = r#"Examples:
+  rlm-rs revert-buffer my-buffer 3              # Restore version 3
+  rlm-rs revert my-buffer 3 --embed             # Restore and re-embed
+"#)]
+    #[command(alias = "revert")]
+    RevertBuffer {
+        /// Buffer ID or name.
+        buffer: String,
+
+        /// Version number to restore (see `history`).
+        #[arg(id = "version_number", value_name = "VERSION")]
+        version: i64,
+
+        /// Automatically embed new chunks after reverting.
+        #[arg(short, long)]
+        embed: bool,
+
+        /// Number of saved versions to keep for the buffer.
+        #[arg(long, default_value_t = DEFAULT_KEEP_VERSIONS, value_parser = parse_positive)]
+        keep_versions: usize,
     },
 
     /// Export all buffers to a file.
+    ///
+    /// With `--format json`, writes a JSON array of buffers (name, content,
+    /// source, metadata) that `import-buffers` can read back.
+    #[command(after_help = r#"Examples:
+  rlm-rs export-buffers > all.txt                          # Concatenated content
+  rlm-rs --format json export-buffers -o dump.json --pretty  # Re-importable dump
+"#)]
     ExportBuffers {
         /// Output file path (stdout if not specified).
         #[arg(short, long)]
@@ -253,8 +816,53 @@ pub enum Commands {
         pretty: bool,
     },
 
+    /// Import buffers from a JSON dump written by `export-buffers`.
+    ///
+    /// Fails on a name collision unless `--merge` is given, in which case
+    /// the existing buffer's content is replaced. Without `--chunk`, a
+    /// changed buffer is re-chunked with the strategy of its old chunks.
+    #[command(after_help = r#"Examples:
+  rlm-rs import-buffers dump.json                   # Add buffers, error on collisions
+  rlm-rs import-buffers dump.json --merge           # Update same-named buffers
+  rlm-rs import-buffers dump.json --chunk --embed   # Re-chunk and embed on import
+"#)]
+    ImportBuffers {
+        /// JSON dump file.
+        input: PathBuf,
+
+        /// Update existing buffers with the same name instead of failing.
+        #[arg(short, long)]
+        merge: bool,
+
+        /// Chunk imported buffers (otherwise they are stored unchunked).
+        #[arg(short, long)]
+        chunk: bool,
+
+        /// Embed chunks after chunking.
+        #[arg(short, long, requires = "chunk")]
+        embed: bool,
+
+        /// Chunking strategy (semantic, fixed, parallel).
+        #[arg(long, default_value = "semantic")]
+        strategy: String,
+
+        /// Chunk size in characters.
+        #[arg(long, default_value_t = DEFAULT_CHUNK_SIZE)]
+        chunk_size: usize,
+
+        /// Chunk overlap in characters.
+        #[arg(long, default_value_t = DEFAULT_OVERLAP)]
+        overlap: usize,
+    },
+
     /// Set or get context variables.
     #[command(name = "var")]
+    #[command(after_help = r#"Examples:
+  rlm-rs var current_chunk 3             # Stored as the string "3"
+  rlm-rs var current_chunk 3 --type int  # Stored as an integer
+  rlm-rs var seen '[1, 2]' --type list   # Lists and maps are given as JSON
+  rlm-rs var current_chunk               # Print the value
+"#)]
     Variable {
         /// Variable name.
         name: String,
@@ -262,6 +870,11 @@ pub enum Commands {
         /// Value to set (omit to get current value).
         value: Option<String>,
 
+        /// Type of the value to set: string, integer, float, boolean,
+        /// list, or map (lists and maps are given as JSON).
+        #[arg(long = "type", value_name = "TYPE", default_value = "string")]
+        value_type: String,
+
         /// Delete the variable.
         #[arg(short, long)]
         delete: bool,
@@ -275,21 +888,60 @@ pub enum Commands {
         /// Value to set (omit to get current value).
         value: Option<String>,
 
+        /// Type of the value to set: string, integer, float, boolean,
+        /// list, or map (lists and maps are given as JSON).
+        #[arg(long = "type", value_name = "TYPE", default_value = "string")]
+        value_type: String,
+
         /// Delete the variable.
         #[arg(short, long)]
         delete: bool,
     },
 
+    /// List buffers referenced by the context.
+    ///
+    /// Resolves each referenced buffer ID to its name and size and flags
+    /// dangling references to buffers that no longer exist.
+    #[command(after_help = r#"Examples:
+  rlm-rs context-buffers                          # List context buffers
+  rlm-rs --format json context-buffers | jq '.dangling_count'
+"#)]
+    ContextBuffers,
+
+    /// Remove dangling buffer references from the context.
+    ///
+    /// Drops context buffer IDs that no longer exist in storage, e.g. after
+    /// buffers were deleted without going through `delete`.
+    #[command(after_help = r#"Examples:
+  rlm-rs context-prune                            # Drop dangling references
+  rlm-rs --format json context-prune | jq '.pruned'
+"#)]
+    ContextPrune,
+
     /// Search chunks using hybrid semantic + BM25 search.
     ///
     /// Returns chunk IDs and scores. Use `chunk get <id>` to retrieve content.
     #[command(after_help = r#"Examples:
   rlm-rs search "error handling"                  # Hybrid search (default)
   rlm-rs search "authentication" -k 5             # Top 5 results
+  rlm-rs search "authentication" -k 5 --offset 5  # Results 6-10
   rlm-rs search "config" --mode bm25              # BM25 keyword search only
   rlm-rs search "API" --mode semantic             # Semantic search only
+  rlm-rs search '"connection pool" AND timeout' --raw-query  # FTS5 syntax
   rlm-rs search "bug fix" --buffer main-source    # Filter by buffer
   rlm-rs search "auth" --preview                  # Include content preview
+  rlm-rs search "token refresh" --highlight       # Show where keywords matched
+  rlm-rs search "setup" --title-boost 0.5         # Favor chunks titled "setup"
+  rlm-rs search "E0502" --bm25-weight 2           # Lean on exact keyword matches
+  rlm-rs search "parse" --buffer-type rs          # Only Rust source buffers
+  rlm-rs search "rare term" --min-results 3       # Relax until 3 results
+  rlm-rs search "license" --diversity 0.5         # Suppress near-duplicates
+  rlm-rs search "auth" --tag api --tag v2         # Only buffers tagged api and v2
+  rlm-rs search "auth" --buffer-meta project=alpha  # Only buffers with that metadata
+  rlm-rs search "auth" --auto-embed               # Embed un-embedded chunks first
+  rlm-rs search "auth" --no-cache                 # Recompute, bypassing cached results
+  rlm-rs search "auth" --nprobe 8                 # Scan 8 IVF clusters (see chunk build-ivf)
+  rlm-rs search "auth" --ids-only | xargs -I{} rlm-rs chunk get {}
   rlm-rs --format json search "test" | jq '.results[].chunk_id'
 "#)]
     Search {
@@ -300,7 +952,11 @@ pub enum Commands {
         #[arg(short = 'k', long, default_value = "10")]
         top_k: usize,
 
-        /// Minimum similarity threshold (0.0-1.0).
+        /// Skip this many top-ranked results (page with `--offset N*top_k`).
+        #[arg(long, default_value = "0")]
+        offset: usize,
+
+        /// Minimum similarity threshold (0.0-1.0 for cosine; see --metric).
         #[arg(short, long, default_value = "0.3")]
         threshold: f32,
 
@@ -308,10 +964,24 @@ pub enum Commands {
         #[arg(short, long, default_value = "hybrid")]
         mode: String,
 
+        /// Pass the query to FTS5 as-is (phrases, AND/OR/NOT, `NEAR()`).
+        ///
+        /// By default any query term may match and FTS5 syntax is literal.
+        #[arg(long)]
+        raw_query: bool,
+
         /// RRF k parameter for rank fusion.
         #[arg(long, default_value = "60")]
         rrf_k: u32,
 
+        /// Weight of semantic ranks in hybrid fusion (0 ignores them).
+        #[arg(long, default_value = "1.0")]
+        semantic_weight: f64,
+
+        /// Weight of BM25 ranks in hybrid fusion (0 ignores them).
+        #[arg(long, default_value = "1.0")]
+        bm25_weight: f64,
+
         /// Filter by buffer ID or name.
         #[arg(short, long)]
         buffer: Option<String>,
@@ -320,26 +990,97 @@ pub enum Commands {
         #[arg(short, long)]
         preview: bool,
 
-        /// Preview length in characters.
-        #[arg(long, default_value = "150")]
+        /// Preview length in characters (grapheme clusters).
+        #[arg(long, default_value_t = DEFAULT_PREVIEW_LEN)]
         preview_len: usize,
+
+        /// Show an excerpt around each keyword match, marked like `[match]`.
+        ///
+        /// Only BM25 matches have one; ignored in semantic mode.
+        #[arg(long)]
+        highlight: bool,
+
+        /// Boost results whose chunk title matches query terms (0 disables).
+        #[arg(long, default_value = "0.0")]
+        title_boost: f64,
+
+        /// Only search buffers with this content type (e.g. rs, md, txt).
+        #[arg(long)]
+        buffer_type: Option<String>,
+
+        /// Relax threshold/mode until at least this many results (0 disables).
+        #[arg(long, default_value = "0")]
+        min_results: usize,
+
+        /// Diversify results with MMR re-ranking (0.0-1.0, higher = more diverse).
+        #[arg(long)]
+        diversity: Option<f32>,
+
+        /// Only search buffers with this tag (repeatable; all must match).
+        #[arg(long)]
+        tag: Vec<String>,
+
+        /// Only search buffers whose custom metadata has KEY equal to VALUE
+        /// (repeatable; all must match).
+        #[arg(long, value_name = "KEY=VALUE")]
+        buffer_meta: Vec<String>,
+
+        /// Embed candidate chunks that have no embedding before searching.
+        ///
+        /// Writes to the database; ignored in bm25 mode.
+        #[arg(long)]
+        auto_embed: bool,
+
+        /// Output only matching chunk IDs (one per line, or a JSON array).
+        #[arg(long, conflicts_with = "preview")]
+        ids_only: bool,
+
+        /// Neither read nor write the search results cache.
+        #[arg(long)]
+        no_cache: bool,
+
+        /// Scan only the N nearest IVF clusters in semantic search
+        /// (requires `chunk build-ivf`; higher = better recall, slower).
+        #[arg(long, value_parser = parse_positive)]
+        nprobe: Option<usize>,
+
+        /// Similarity metric for semantic search: cosine, dot, euclidean.
+        ///
+        /// --threshold applies on the metric's scale: cosine in [-1, 1], dot
+        /// unbounded, euclidean (1 / (1 + distance)) in (0, 1].
+        #[arg(long, default_value = "cosine")]
+        metric: String,
+
+        /// Stop scoring after this many milliseconds and return the results
+        /// ranked so far, flagged as partial.
+        ///
+        /// Bounds the semantic scan and the BM25 query; partial results are
+        /// never cached.
+        #[arg(long, value_name = "MS")]
+        timeout_ms: Option<u64>,
     },
 
     /// Aggregate findings from analyst subagents.
     ///
     /// Reads JSON findings from stdin or a buffer, groups by relevance,
-    /// deduplicates, and outputs a synthesizer-ready report.
+    /// deduplicates, and outputs a synthesizer-ready report. Input is a JSON
+    /// array or NDJSON (one finding per line); entries that aren't valid
+    /// findings are skipped and reported.
     #[command(after_help = r#"Examples:
   cat findings.json | rlm-rs aggregate           # Aggregate from stdin
   rlm-rs aggregate --buffer findings             # Read from buffer
   rlm-rs aggregate --min-relevance medium        # Filter low relevance
+  rlm-rs aggregate --explain                     # Report filtered and malformed entries
+  rlm-rs aggregate --dedup-threshold 0.9         # Merge paraphrased findings
+  rlm-rs aggregate -o findings --chunk-findings  # Searchable findings buffer
   rlm-rs --format json aggregate | jq '.findings'
 
 Input format (JSON array of analyst findings):
 [
   {"chunk_id": 12, "relevance": "high", "findings": ["..."], "summary": "..."},
   {"chunk_id": 27, "relevance": "medium", "findings": ["..."], "summary": "..."}
-]"#)]
+]
+or NDJSON with one finding object per line."#)]
     Aggregate {
         /// Read findings from a buffer instead of stdin.
         #[arg(short, long)]
@@ -360,6 +1101,22 @@ Input format (JSON array of analyst findings):
         /// Store aggregated results in a new buffer with this name.
         #[arg(short, long)]
         output_buffer: Option<String>,
+
+        /// Report how many findings were filtered out at each relevance
+        /// level and why, and list malformed entries.
+        #[arg(long)]
+        explain: bool,
+
+        /// Also merge findings whose embeddings have at least this cosine
+        /// similarity (0-1] to an earlier finding, catching paraphrases.
+        #[arg(long)]
+        dedup_threshold: Option<f32>,
+
+        /// Store each unique finding as its own embedded chunk in the output
+        /// buffer, tagged with its relevance and source chunk ID, so the
+        /// findings can be searched.
+        #[arg(long, requires = "output_buffer")]
+        chunk_findings: bool,
     },
 
     /// Dispatch chunks for parallel subagent processing.
@@ -400,6 +1157,40 @@ Input format (JSON array of analyst findings):
     /// Chunk operations (get, list, embed).
     #[command(subcommand)]
     Chunk(ChunkCommands),
+
+    /// Generate a deterministic synthetic corpus (testing/benchmarking).
+    ///
+    /// Populates the database with lorem-like buffers and chunks derived
+    /// from a seed. The same seed always produces the same content.
+    #[command(hide = true)]
+    #[command(after_help = r#"Examples:
+  rlm-rs gen-corpus                               # 10 buffers x 20 chunks, seed 42
+  rlm-rs gen-corpus --buffers 100 --seed 7        # Larger corpus
+  rlm-rs gen-corpus --chunks-per-buffer 50 --embed  # Include fallback embeddings"#)]
+    GenCorpus {
+        /// Number of buffers to create.
+        #[arg(long, default_value = "10")]
+        buffers: usize,
+
+        /// Number of chunks per buffer.
+        #[arg(long, default_value = "20")]
+        chunks_per_buffer: usize,
+
+        /// Seed for the pseudo-random generator.
+        #[arg(long, default_value = "42")]
+        seed: u64,
+
+        /// Also store hash-based fallback embeddings for every chunk.
+        #[arg(short, long)]
+        embed: bool,
+    },
+}
+
+/// Config subcommands.
+#[derive(Subcommand, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigCommands {
+    /// Print the resolved configuration (same as `--print`).
+    Show,
 }
 
 /// Chunk subcommands for pass-by-reference retrieval.
@@ -411,7 +1202,13 @@ pub enum ChunkCommands {
     /// pass-by-reference retrieval mechanism for subagents.
     #[command(after_help = r#"Examples:
   rlm-rs chunk get 42                    # Get chunk content
-  rlm-rs chunk get 42 --metadata         # Include byte range, token count
+  rlm-rs chunk get 42 --metadata         # Include byte range, content hash
+  rlm-rs chunk get 42 --verify-hash      # Fail if content doesn't match its hash
+  rlm-rs chunk get 42 --next             # Chunk after 42 in the same buffer
+  rlm-rs chunk get 42 --prev             # Chunk before 42 in the same buffer
+  rlm-rs chunk get 42 --context 1        # Chunks 41..=43 of the same buffer
+  rlm-rs chunk get 42 --stream | less    # Stream large chunk content
+  rlm-rs chunk get 42 --wrap xml         # <chunk id="42" ...>content</chunk>
   rlm-rs --format json chunk get 42      # JSON output for programmatic use
 "#)]
     Get {
@@ -421,31 +1218,116 @@ pub enum ChunkCommands {
         /// Include metadata in output.
         #[arg(short, long)]
         metadata: bool,
+
+        /// Return the next chunk (index + 1) in the same buffer.
+        #[arg(long, conflicts_with = "prev")]
+        next: bool,
+
+        /// Return the previous chunk (index - 1) in the same buffer.
+        #[arg(long)]
+        prev: bool,
+
+        /// Also return the chunks within RADIUS indices of this one in the
+        /// same buffer, ordered by index.
+        #[arg(
+            long,
+            value_name = "RADIUS",
+            conflicts_with_all = ["metadata", "next", "prev", "stream", "verify_hash"]
+        )]
+        context: Option<usize>,
+
+        /// Replace `@chunk:<id>` references in the content with the
+        /// referenced chunks' content, recursively up to DEPTH levels
+        /// (default: 8).
+        ///
+        /// Reference cycles, missing chunks, and deeper nesting are errors.
+        #[arg(
+            long,
+            value_name = "DEPTH",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "8",
+            value_parser = parse_positive,
+            conflicts_with_all = ["context", "stream"]
+        )]
+        resolve_refs: Option<usize>,
+
+        /// Stream content straight from the database to stdout (text only).
+        ///
+        /// Avoids loading multi-megabyte chunks into memory when piping.
+        #[arg(long, conflicts_with_all = ["metadata", "next", "prev", "verify_hash"])]
+        stream: bool,
+
+        /// Recompute the content hash and fail if it doesn't match.
+        #[arg(long)]
+        verify_hash: bool,
+
+        /// Wrap text content for prompts: none, xml, markdown.
+        ///
+        /// `xml` emits `<chunk id=".." buffer=".." index="..">`; `markdown`
+        /// a heading and a fenced block tagged with the buffer's type.
+        #[arg(
+            long,
+            default_value = "none",
+            value_name = "STYLE",
+            conflicts_with = "stream"
+        )]
+        wrap: String,
     },
 
-    /// List chunks for a buffer.
+    /// List chunks for a buffer, or across all buffers with --all.
     #[command(after_help = r#"Examples:
   rlm-rs chunk list main-source          # List chunk IDs
   rlm-rs chunk list 1 --preview          # Show content preview
-  rlm-rs --format json chunk list 1 | jq '.[].id'
+  rlm-rs chunk list --all --limit 50     # First 50 chunks of the corpus
+  rlm-rs chunk list --all --offset 50 --limit 50
+  rlm-rs chunk list 1 --verify-hash      # Report chunks whose hash mismatches
+  rlm-rs chunk list --all --sample 20 --seed 7  # Spot-check 20 random chunks
+  rlm-rs --format json chunk list 1 | jq '.chunks[].id'
 "#)]
     List {
         /// Buffer ID or name.
-        buffer: String,
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        buffer: Option<String>,
+
+        /// List chunks across every buffer, ordered by buffer then index.
+        #[arg(long)]
+        all: bool,
+
+        /// Maximum number of chunks to list.
+        #[arg(long)]
+        limit: Option<usize>,
+
+        /// Number of chunks to skip before listing.
+        #[arg(long, default_value = "0")]
+        offset: usize,
 
         /// Show content preview.
         #[arg(short, long)]
         preview: bool,
 
-        /// Preview length in characters.
-        #[arg(long, default_value = "100")]
+        /// Preview length in characters (grapheme clusters).
+        #[arg(long, default_value_t = DEFAULT_PREVIEW_LEN)]
         preview_len: usize,
+
+        /// Recompute content hashes and report mismatches.
+        #[arg(long)]
+        verify_hash: bool,
+
+        /// List N chunks chosen at random instead of paging.
+        #[arg(long, value_name = "N", conflicts_with_all = ["limit", "offset"])]
+        sample: Option<usize>,
+
+        /// Seed for a reproducible --sample.
+        #[arg(long, requires = "sample")]
+        seed: Option<u64>,
     },
 
     /// Generate embeddings for buffer chunks.
     #[command(after_help = r#"Examples:
   rlm-rs chunk embed main-source         # Generate embeddings
   rlm-rs chunk embed 1 --force           # Re-embed existing chunks
+  rlm-rs chunk embed 1 --older-than 7d   # Refresh embeddings older than a week
 "#)]
     Embed {
         /// Buffer ID or name.
@@ -454,6 +1336,94 @@ pub enum ChunkCommands {
         /// Re-embed even if already embedded.
         #[arg(short, long)]
         force: bool,
+
+        /// Re-embed only chunks whose embedding is older than this duration
+        /// (e.g. 90s, 30m, 12h, 7d, 2w).
+        #[arg(long, conflicts_with = "force")]
+        older_than: Option<String>,
+    },
+
+    /// Embed only the chunks that have no embedding yet.
+    ///
+    /// Fills gaps left by interrupted or failed embedding runs, or by
+    /// imports that lacked embeddings, across the whole corpus or in one
+    /// buffer; existing embeddings are untouched. Chunks are batched across
+    /// buffers.
+    #[command(
+        visible_alias = "embed-missing",
+        after_help = r#"Examples:
+  rlm-rs chunk reembed-missing           # Fill gaps across all buffers
+  rlm-rs chunk reembed-missing notes.md  # Fill gaps in one buffer
+  rlm-rs chunk embed-missing --force     # Re-embed every chunk
+  rlm-rs --format json chunk reembed-missing
+"#
+    )]
+    ReembedMissing {
+        /// Buffer ID or name (default: all buffers).
+        buffer: Option<String>,
+
+        /// Re-embed every chunk, not just those missing an embedding.
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Cluster stored embeddings into an IVF index.
+    ///
+    /// Semantic searches with `--nprobe N` then scan only the N clusters
+    /// nearest to the query. Embeddings added later join their nearest
+    /// cluster; rebuild to re-cluster. Reports the estimated recall.
+    #[command(after_help = r#"Examples:
+  rlm-rs chunk build-ivf                 # About sqrt(n) clusters
+  rlm-rs chunk build-ivf --clusters 64   # Fixed cluster count
+  rlm-rs chunk build-ivf --nprobe 8      # Report recall when scanning 8 clusters
+  rlm-rs --format json chunk build-ivf | jq '.recall'
+"#)]
+    BuildIvf {
+        /// Number of clusters (default: square root of the embedding count).
+        #[arg(long, value_parser = parse_positive)]
+        clusters: Option<usize>,
+
+        /// Maximum k-means iterations.
+        #[arg(long, default_value_t = DEFAULT_IVF_ITERATIONS, value_parser = parse_positive)]
+        iterations: usize,
+
+        /// Clusters scanned per query when estimating recall.
+        #[arg(long, default_value_t = DEFAULT_IVF_NPROBE, value_parser = parse_positive)]
+        nprobe: usize,
+
+        /// Stored embeddings sampled as queries when estimating recall.
+        #[arg(long, default_value_t = DEFAULT_RECALL_SAMPLES)]
+        samples: usize,
+
+        /// Seed for cluster initialization and sampling.
+        #[arg(long, default_value = "42")]
+        seed: u64,
+    },
+
+    /// Estimate IVF search recall at a given nprobe.
+    ///
+    /// Compares the nearest neighbors found by scanning the probed clusters
+    /// with an exact scan, using stored embeddings as queries.
+    #[command(after_help = r#"Examples:
+  rlm-rs chunk ivf-recall                # Recall at the default nprobe
+  rlm-rs chunk ivf-recall --nprobe 16 -k 20
+"#)]
+    IvfRecall {
+        /// Clusters scanned per query.
+        #[arg(long, default_value_t = DEFAULT_IVF_NPROBE, value_parser = parse_positive)]
+        nprobe: usize,
+
+        /// Number of nearest neighbors compared per query.
+        #[arg(short = 'k', long, default_value = "10", value_parser = parse_positive)]
+        top_k: usize,
+
+        /// Stored embeddings sampled as queries.
+        #[arg(long, default_value_t = DEFAULT_RECALL_SAMPLES)]
+        samples: usize,
+
+        /// Seed for sampling.
+        #[arg(long, default_value = "42")]
+        seed: u64,
     },
 
     /// Show embedding status for buffers.
@@ -461,13 +1431,161 @@ pub enum ChunkCommands {
 }
 
 impl Cli {
-    /// Returns the database path, using the default if not specified.
+    /// Returns the database path: `--db-path`/`RLM_DB_PATH`, then the
+    /// config file's `db_path`, then the default.
     #[must_use]
     pub fn get_db_path(&self) -> PathBuf {
         self.db_path
             .clone()
+            .or_else(|| self.config.db_path.clone())
             .unwrap_or_else(|| PathBuf::from(crate::storage::DEFAULT_DB_PATH))
     }
+
+    /// Fills settings left at their built-in defaults from `config`.
+    ///
+    /// `matches` are the matches `self` was parsed from; any value given as
+    /// a flag or through an environment variable is kept. The config is
+    /// stored in [`Cli::config`] for [`Cli::get_db_path`] and `config show`.
+    pub fn apply_config(&mut self, config: Config, matches: &ArgMatches) {
+        if let Some(format) = &config.format
+            && defaulted(matches, "format")
+        {
+            self.format.clone_from(format);
+        }
+
+        if let Some((_, sub)) = matches.subcommand() {
+            let (chunker, chunk_size, overlap, mode) = match &mut self.command {
+                Commands::Load {
+                    chunker,
+                    chunk_size,
+                    overlap,
+                    ..
+                }
+                | Commands::ImportDir {
+                    chunker,
+                    chunk_size,
+                    overlap,
+                    ..
+                }
+                | Commands::ExplainChunking {
+                    chunker,
+                    chunk_size,
+                    overlap,
+                    ..
+                }
+                | Commands::Merge {
+                    chunker,
+                    chunk_size,
+                    overlap,
+                    ..
+                } => (Some(chunker), Some(chunk_size), Some(overlap), None),
+                Commands::ChunkIndices {
+                    chunk_size,
+                    overlap,
+                    ..
+                }
+                | Commands::WriteChunks {
+                    chunk_size,
+                    overlap,
+                    ..
+                }
+                | Commands::UpdateBuffer {
+                    chunk_size,
+                    overlap,
+                    ..
+                }
+                | Commands::ImportBuffers {
+                    chunk_size,
+                    overlap,
+                    ..
+                } => (None, Some(chunk_size), Some(overlap), None),
+                Commands::Search { mode, .. } | Commands::Dispatch { mode, .. } => {
+                    (None, None, None, Some(mode))
+                }
+                _ => (None, None, None, None),
+            };
+
+            if let (Some(chunker), Some(value)) = (chunker, &config.chunker)
+                && defaulted(sub, "chunker")
+            {
+                chunker.clone_from(value);
+            }
+            if let (Some(chunk_size), Some(value)) = (chunk_size, config.chunk_size)
+                && defaulted(sub, "chunk_size")
+            {
+                *chunk_size = value;
+            }
+            if let (Some(overlap), Some(value)) = (overlap, config.overlap)
+                && defaulted(sub, "overlap")
+            {
+                *overlap = value;
+            }
+            if let (Some(mode), Some(value)) = (mode, &config.search_mode)
+                && defaulted(sub, "mode")
+            {
+                mode.clone_from(value);
+            }
+        }
+
+        self.config = config;
+    }
+
+    /// Returns `SQLite` connection options derived from global flags.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the journal mode is not recognized.
+    pub fn storage_options(&self) -> crate::Result<SqliteOptions> {
+        let journal_mode =
+            JournalMode::parse(&self.journal).ok_or_else(|| crate::Error::Config {
+                message: format!(
+                    "invalid journal mode '{}' (expected delete, wal, or memory)",
+                    self.journal
+                ),
+            })?;
+        let mut options = SqliteOptions::new().with_journal_mode(journal_mode);
+        options.cache_size_kib = self.cache_size;
+        options.mmap_size = self.mmap_size;
+        options.embedding_dimensions = self.embedding_dim;
+        Ok(options)
+    }
+
+    /// Sizes the global rayon pool from `--threads`.
+    ///
+    /// Must be called once, before any parallel work; without `--threads`
+    /// rayon's default of one thread per available core is kept.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the global pool was already initialized.
+    pub fn configure_thread_pool(&self) -> crate::Result<()> {
+        let Some(threads) = self.threads else {
+            return Ok(());
+        };
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .map_err(|e| crate::Error::Config {
+                message: format!("failed to configure {threads} worker threads: {e}"),
+            })
+    }
+}
+
+/// Returns `true` if argument `id` was neither passed nor set from the environment.
+fn defaulted(matches: &ArgMatches, id: &str) -> bool {
+    !matches!(
+        matches.value_source(id),
+        Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+    )
+}
+
+/// Parses a count that must be at least 1.
+fn parse_positive(value: &str) -> std::result::Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(0) => Err("value must be at least 1".to_string()),
+        Ok(size) => Ok(size),
+        Err(e) => Err(e.to_string()),
+    }
 }
 
 #[cfg(test)]
@@ -487,7 +1605,16 @@ mod tests {
             db_path: None,
             verbose: false,
             format: "text".to_string(),
-            command: Commands::Status,
+            null: false,
+            journal: "wal".to_string(),
+            cache_size: None,
+            mmap_size: None,
+            embed_batch_size: DEFAULT_EMBED_BATCH_SIZE,
+            threads: None,
+            embedding_dim: None,
+            tee: None,
+            config: Config::default(),
+            command: Commands::Status { detailed: false },
         };
         assert_eq!(
             cli.get_db_path(),
@@ -501,8 +1628,370 @@ mod tests {
             db_path: Some(PathBuf::from("/custom/path.db")),
             verbose: false,
             format: "text".to_string(),
-            command: Commands::Status,
+            null: false,
+            journal: "wal".to_string(),
+            cache_size: None,
+            mmap_size: None,
+            embed_batch_size: DEFAULT_EMBED_BATCH_SIZE,
+            threads: None,
+            embedding_dim: None,
+            tee: None,
+            config: Config::default(),
+            command: Commands::Status { detailed: false },
         };
         assert_eq!(cli.get_db_path(), PathBuf::from("/custom/path.db"));
     }
+
+    #[test]
+    fn test_apply_config_precedence() {
+        use clap::FromArgMatches;
+
+        let config = Config {
+            db_path: Some(PathBuf::from("/project/state.db")),
+            chunker: Some("markdown".to_string()),
+            chunk_size: Some(500),
+            search_mode: Some("bm25".to_string()),
+            format: Some("json".to_string()),
+            ..Config::default()
+        };
+        let parse = |args: &[&str]| {
+            let matches = Cli::command().try_get_matches_from(args).unwrap();
+            let mut cli = Cli::from_arg_matches(&matches).unwrap();
+            cli.apply_config(config.clone(), &matches);
+            cli
+        };
+
+        // Config values replace built-in defaults
+        let cli = parse(&["rlm-rs", "load", "doc.md"]);
+        assert_eq!(cli.format, "json");
+        assert_eq!(cli.get_db_path(), PathBuf::from("/project/state.db"));
+        assert!(matches!(
+            cli.command,
+            Commands::Load { ref chunker, chunk_size: 500, overlap, .. }
+                if chunker == "markdown" && overlap == DEFAULT_OVERLAP
+        ));
+
+        // Flags win over the config, even when equal to the built-in default
+        let cli = parse(&[
+            "rlm-rs",
+            "--format",
+            "text",
+            "load",
+            "doc.md",
+            "--chunker",
+            "semantic",
+            "--chunk-size",
+            "100",
+        ]);
+        assert_eq!(cli.format, "text");
+        assert!(matches!(
+            cli.command,
+            Commands::Load { ref chunker, chunk_size: 100, .. } if chunker == "semantic"
+        ));
+
+        let cli = parse(&["rlm-rs", "search", "query"]);
+        assert!(matches!(cli.command, Commands::Search { ref mode, .. } if mode == "bm25"));
+        let cli = parse(&["rlm-rs", "search", "query", "--mode", "hybrid"]);
+        assert!(matches!(cli.command, Commands::Search { ref mode, .. } if mode == "hybrid"));
+    }
+
+    #[test]
+    fn test_storage_options() {
+        let mut cli = Cli {
+            db_path: None,
+            verbose: false,
+            format: "text".to_string(),
+            null: false,
+            journal: "delete".to_string(),
+            cache_size: Some(4096),
+            mmap_size: None,
+            embed_batch_size: DEFAULT_EMBED_BATCH_SIZE,
+            threads: None,
+            embedding_dim: None,
+            tee: None,
+            config: Config::default(),
+            command: Commands::Status { detailed: false },
+        };
+        let options = cli.storage_options().unwrap();
+        assert_eq!(options.journal_mode, JournalMode::Delete);
+        assert_eq!(options.cache_size_kib, Some(4096));
+        assert_eq!(options.mmap_size, None);
+
+        cli.journal = "bogus".to_string();
+        assert!(cli.storage_options().is_err());
+    }
+
+    #[test]
+    fn test_embed_batch_size_flag() {
+        let cli = Cli::try_parse_from(["rlm-rs", "--embed-batch-size", "8", "status"]).unwrap();
+        assert_eq!(cli.embed_batch_size, 8);
+
+        let cli = Cli::try_parse_from(["rlm-rs", "status"]).unwrap();
+        assert_eq!(cli.embed_batch_size, DEFAULT_EMBED_BATCH_SIZE);
+
+        assert!(Cli::try_parse_from(["rlm-rs", "--embed-batch-size", "0", "status"]).is_err());
+    }
+
+    #[test]
+    fn test_null_flag() {
+        let cli = Cli::try_parse_from(["rlm-rs", "-0", "list"]).unwrap();
+        assert!(cli.null);
+        let cli = Cli::try_parse_from(["rlm-rs", "list", "--null"]).unwrap();
+        assert!(cli.null);
+    }
+
+    #[test]
+    fn test_tee_flag() {
+        let cli = Cli::try_parse_from(["rlm-rs", "search", "q", "--tee", "out.txt"]).unwrap();
+        assert_eq!(cli.tee, Some(PathBuf::from("out.txt")));
+
+        let cli = Cli::try_parse_from(["rlm-rs", "status"]).unwrap();
+        assert_eq!(cli.tee, None);
+    }
+
+    #[test]
+    fn test_embedding_dim_flag() {
+        let cli =
+            Cli::try_parse_from(["rlm-rs", "load", "f.txt", "--embedding-dim", "384"]).unwrap();
+        assert_eq!(cli.embedding_dim, Some(384));
+        assert_eq!(
+            cli.storage_options().unwrap().embedding_dimensions,
+            Some(384)
+        );
+
+        assert!(Cli::try_parse_from(["rlm-rs", "--embedding-dim", "0", "status"]).is_err());
+    }
+
+    #[test]
+    fn test_threads_flag() {
+        let cli = Cli::try_parse_from(["rlm-rs", "--threads", "2", "status"]).unwrap();
+        assert_eq!(cli.threads, Some(2));
+
+        let cli = Cli::try_parse_from(["rlm-rs", "status"]).unwrap();
+        assert_eq!(cli.threads, None);
+        assert!(cli.configure_thread_pool().is_ok());
+
+        assert!(Cli::try_parse_from(["rlm-rs", "--threads", "0", "status"]).is_err());
+    }
+
+    #[test]
+    fn test_update_history_flags() {
+        let cli = Cli::try_parse_from(["rlm-rs", "update", "doc", "x", "--history"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::UpdateBuffer {
+                history: true,
+                keep_versions: DEFAULT_KEEP_VERSIONS,
+                ..
+            }
+        ));
+
+        // Retention only applies when history is on
+        assert!(
+            Cli::try_parse_from(["rlm-rs", "update", "doc", "x", "--keep-versions", "3"]).is_err()
+        );
+
+        // The last of --embed and --no-embed-on-update wins
+        let cli = Cli::try_parse_from([
+            "rlm-rs",
+            "update",
+            "doc",
+            "x",
+            "--embed",
+            "--no-embed-on-update",
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::UpdateBuffer {
+                embed: false,
+                no_embed_on_update: true,
+                ..
+            }
+        ));
+        let cli =
+            Cli::try_parse_from(["rlm-rs", "update", "doc", "x", "--no-embed-on-update", "-e"])
+                .unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::UpdateBuffer {
+                embed: true,
+                no_embed_on_update: false,
+                ..
+            }
+        ));
+        assert!(
+            Cli::try_parse_from(["rlm-rs", "revert", "doc", "2", "--keep-versions", "0"]).is_err()
+        );
+        let cli = Cli::try_parse_from(["rlm-rs", "revert-buffer", "doc", "2"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::RevertBuffer { version: 2, .. }
+        ));
+    }
+
+    #[test]
+    fn test_config_requires_action() {
+        assert!(Cli::try_parse_from(["rlm-rs", "config"]).is_err());
+        let cli = Cli::try_parse_from(["rlm-rs", "config", "--print"]).unwrap();
+        assert!(matches!(cli.command, Commands::Config { print: true, .. }));
+        let cli = Cli::try_parse_from(["rlm-rs", "config", "show"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Config {
+                print: false,
+                action: Some(ConfigCommands::Show)
+            }
+        ));
+    }
+
+    #[test]
+    fn test_ivf_flags() {
+        let cli = Cli::try_parse_from(["rlm-rs", "chunk", "build-ivf"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Chunk(ChunkCommands::BuildIvf {
+                clusters: None,
+                nprobe: DEFAULT_IVF_NPROBE,
+                ..
+            })
+        ));
+        let cli = Cli::try_parse_from(["rlm-rs", "search", "q", "--nprobe", "8"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Search {
+                nprobe: Some(8),
+                ..
+            }
+        ));
+
+        // Probing no clusters would find nothing
+        assert!(Cli::try_parse_from(["rlm-rs", "search", "q", "--nprobe", "0"]).is_err());
+        assert!(Cli::try_parse_from(["rlm-rs", "chunk", "build-ivf", "--clusters", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_search_metric_default() {
+        let cli = Cli::try_parse_from(["rlm-rs", "search", "q"]).unwrap();
+        assert!(matches!(cli.command, Commands::Search { metric, .. } if metric == "cosine"));
+        let cli = Cli::try_parse_from(["rlm-rs", "search", "q", "--metric", "dot"]).unwrap();
+        assert!(matches!(cli.command, Commands::Search { metric, .. } if metric == "dot"));
+        let cli = Cli::try_parse_from(["rlm-rs", "search", "q", "--timeout-ms", "250"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Search {
+                timeout_ms: Some(250),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_embed_missing_alias() {
+        let cli = Cli::try_parse_from(["rlm-rs", "chunk", "embed-missing", "--force"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Chunk(ChunkCommands::ReembedMissing {
+                buffer: None,
+                force: true,
+            })
+        ));
+    }
+
+    #[test]
+    fn test_load_normalize_whitespace_alias() {
+        for flag in ["--normalize-whitespace", "--canonicalize-whitespace"] {
+            let cli = Cli::try_parse_from(["rlm-rs", "load", "a.txt", flag]).unwrap();
+            assert!(matches!(
+                cli.command,
+                Commands::Load {
+                    normalize_whitespace: true,
+                    ..
+                }
+            ));
+        }
+        let cli = Cli::try_parse_from(["rlm-rs", "load", "a.txt"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Load {
+                normalize_whitespace: false,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_chunk_get_context() {
+        let cli = Cli::try_parse_from(["rlm-rs", "chunk", "get", "42", "--context", "2"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Chunk(ChunkCommands::Get {
+                context: Some(2),
+                ..
+            })
+        ));
+        assert!(
+            Cli::try_parse_from(["rlm-rs", "chunk", "get", "42", "--context", "1", "--next"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_diff_embeddings() {
+        let cli = Cli::try_parse_from(["rlm-rs", "diff-embeddings", "3", "7"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::DiffEmbeddings {
+                a: 3,
+                b: 7,
+                top: 10
+            }
+        ));
+        assert!(Cli::try_parse_from(["rlm-rs", "diff-embeddings", "3"]).is_err());
+    }
+
+    #[test]
+    fn test_completions_shell() {
+        let cli = Cli::try_parse_from(["rlm-rs", "completions", "zsh"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Completions {
+                shell: clap_complete::Shell::Zsh
+            }
+        ));
+        assert!(Cli::try_parse_from(["rlm-rs", "completions", "tcsh"]).is_err());
+    }
+
+    #[test]
+    fn test_chunk_get_resolve_refs() {
+        let cli = Cli::try_parse_from(["rlm-rs", "chunk", "get", "42", "--resolve-refs"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Chunk(ChunkCommands::Get {
+                resolve_refs: Some(depth),
+                ..
+            }) if depth == crate::core::DEFAULT_REF_DEPTH
+        ));
+        let cli =
+            Cli::try_parse_from(["rlm-rs", "chunk", "get", "42", "--resolve-refs=2"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Chunk(ChunkCommands::Get {
+                resolve_refs: Some(2),
+                ..
+            })
+        ));
+        assert!(Cli::try_parse_from(["rlm-rs", "chunk", "get", "42", "--resolve-refs=0"]).is_err());
+    }
+
+    #[test]
+    fn test_list_fields_comma_separated() {
+        let cli =
+            Cli::try_parse_from(["rlm-rs", "list", "--fields", "id,name", "--fields", "size"])
+                .unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::ListBuffers { fields, .. } if fields == ["id", "name", "size"]
+        ));
+    }
 }
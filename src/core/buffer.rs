@@ -3,7 +3,8 @@
 //! Buffers represent text content loaded into the RLM system, typically
 //! from files or direct input. Each buffer can be chunked for processing.
 
-use crate::io::{current_timestamp, find_char_boundary};
+use crate::core::hash::content_hash;
+use crate::io::{LineEnding, current_timestamp, find_char_boundary};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -61,6 +62,29 @@ pub struct BufferMetadata {
 
     /// SHA-256 hash of content (for deduplication).
     pub content_hash: Option<String>,
+
+    /// Encoding the source was transcoded from (`None` for plain UTF-8).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_encoding: Option<String>,
+
+    /// Dominant line ending of the content (`LF`, `CRLF`, or `CR`), as
+    /// detected at load; `None` when unknown or without line breaks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_ending: Option<String>,
+
+    /// Whether whitespace was canonicalized at load (see
+    /// [`normalize_whitespace`](crate::io::normalize_whitespace)), so the
+    /// content may differ from the source.
+    #[serde(default)]
+    pub whitespace_normalized: bool,
+
+    /// Custom key/value metadata as a JSON object string.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom: Option<String>,
+
+    /// Unix timestamp when the buffer was moved to the trash (`None` if live).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<i64>,
 }
 
 impl Buffer {
@@ -82,6 +106,7 @@ impl Buffer {
     #[must_use]
     pub fn from_content(content: String) -> Self {
         let size = content.len();
+        let line_ending = detect_line_ending(&content);
         let now = current_timestamp();
         Self {
             id: None,
@@ -90,6 +115,7 @@ impl Buffer {
             content,
             metadata: BufferMetadata {
                 size,
+                line_ending,
                 created_at: now,
                 updated_at: now,
                 ..Default::default()
@@ -119,6 +145,7 @@ impl Buffer {
     #[must_use]
     pub fn from_file(path: PathBuf, content: String) -> Self {
         let size = content.len();
+        let line_ending = detect_line_ending(&content);
         let content_type = infer_content_type(&path);
         let name = path
             .file_name()
@@ -134,6 +161,7 @@ impl Buffer {
             metadata: BufferMetadata {
                 content_type,
                 size,
+                line_ending,
                 created_at: now,
                 updated_at: now,
                 ..Default::default()
@@ -217,6 +245,12 @@ impl Buffer {
         &self.content[start..]
     }
 
+    /// Checks if the buffer has been moved to the trash.
+    #[must_use]
+    pub const fn is_deleted(&self) -> bool {
+        self.metadata.deleted_at.is_some()
+    }
+
     /// Checks if the buffer is empty.
     #[must_use]
     pub const fn is_empty(&self) -> bool {
@@ -249,12 +283,7 @@ impl Buffer {
 
     /// Computes and sets the content hash.
     pub fn compute_hash(&mut self) {
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
-
-        let mut hasher = DefaultHasher::new();
-        self.content.hash(&mut hasher);
-        self.metadata.content_hash = Some(format!("{:016x}", hasher.finish()));
+        self.metadata.content_hash = Some(content_hash(&self.content));
     }
 }
 
@@ -265,6 +294,11 @@ fn infer_content_type(path: &std::path::Path) -> Option<String> {
         .map(str::to_lowercase)
 }
 
+/// Names the dominant line ending of content, for [`BufferMetadata`].
+fn detect_line_ending(content: &str) -> Option<String> {
+    LineEnding::detect(content).map(|ending| ending.name().to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -324,6 +358,18 @@ mod tests {
         assert_eq!(buffer.metadata.line_count, Some(3));
     }
 
+    #[test]
+    fn test_buffer_line_ending() {
+        let buffer = Buffer::from_content("a\r\nb\r\n".to_string());
+        assert_eq!(buffer.metadata.line_ending.as_deref(), Some("CRLF"));
+        let buffer = Buffer::from_file(PathBuf::from("a.txt"), "a\nb".to_string());
+        assert_eq!(buffer.metadata.line_ending.as_deref(), Some("LF"));
+        assert_eq!(
+            Buffer::from_content("a".to_string()).metadata.line_ending,
+            None
+        );
+    }
+
     #[test]
     fn test_buffer_display_name() {
         let buffer1 = Buffer::from_named("named".to_string(), String::new());
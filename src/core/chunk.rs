@@ -4,10 +4,93 @@
 //! Each chunk maintains its position within the original buffer and
 //! metadata for tracking and processing.
 
+use crate::core::hash::content_hash;
 use crate::io::{current_timestamp, find_char_boundary};
 use serde::{Deserialize, Serialize};
 use std::ops::Range;
 
+/// Maximum length of a derived chunk title in bytes.
+pub const MAX_TITLE_LEN: usize = 80;
+
+/// Derives a short human-readable title from chunk text.
+///
+/// Uses the first non-empty line, with Markdown heading markers and
+/// trailing block openers (`{`, `:`) removed, truncated to
+/// [`MAX_TITLE_LEN`] bytes at a character boundary.
+///
+/// # Examples
+///
+/// ```
+/// use rlm_rs::core::derive_title;
+///
+/// assert_eq!(derive_title("\n## Setup\nbody"), Some("Setup".to_string()));
+/// assert_eq!(derive_title("fn main() {\n}"), Some("fn main()".to_string()));
+/// assert_eq!(derive_title("   \n"), None);
+/// ```
+#[must_use]
+pub fn derive_title(text: &str) -> Option<String> {
+    let line = text.lines().map(str::trim).find(|l| !l.is_empty())?;
+    let line = line
+        .trim_start_matches('#')
+        .trim_end_matches(['{', ':'])
+        .trim();
+    if line.is_empty() {
+        return None;
+    }
+    let end = find_char_boundary(line, MAX_TITLE_LEN);
+    Some(line[..end].trim_end().to_string())
+}
+
+/// Prefix of a chunk reference: `@chunk:<id>` stands for the content of
+/// chunk `<id>`.
+pub const CHUNK_REF_PREFIX: &str = "@chunk:";
+
+/// Default nesting limit when resolving chunk references.
+pub const DEFAULT_REF_DEPTH: usize = 8;
+
+/// Finds chunk references in text.
+///
+/// A reference is [`CHUNK_REF_PREFIX`] followed by a decimal chunk ID, with
+/// no word character directly before or after it (so `user@chunk:1` and
+/// `@chunk:1a` are not references). Returns the byte range and chunk ID of
+/// each reference, in order.
+///
+/// # Examples
+///
+/// ```
+/// use rlm_rs::core::find_chunk_refs;
+///
+/// let refs = find_chunk_refs("Intro: @chunk:42, then @chunk:7.");
+/// assert_eq!(refs, vec![(7..16, 42), (23..31, 7)]);
+/// ```
+#[must_use]
+pub fn find_chunk_refs(text: &str) -> Vec<(Range<usize>, i64)> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let mut refs = Vec::new();
+    let mut from = 0;
+    while let Some(offset) = text[from..].find(CHUNK_REF_PREFIX) {
+        let start = from + offset;
+        let digits_start = start + CHUNK_REF_PREFIX.len();
+        let end = digits_start
+            + text[digits_start..]
+                .bytes()
+                .take_while(u8::is_ascii_digit)
+                .count();
+        from = digits_start;
+
+        let preceded = text[..start].chars().next_back().is_some_and(is_word);
+        let followed = text[end..].chars().next().is_some_and(is_word);
+        if !preceded
+            && !followed
+            && let Ok(id) = text[digits_start..end].parse::<i64>()
+        {
+            refs.push((start..end, id));
+            from = end;
+        }
+    }
+    refs
+}
+
 /// Estimates token count for a text string with improved accuracy.
 ///
 /// This uses a heuristic that accounts for word boundaries, punctuation,
@@ -263,12 +346,18 @@ impl Chunk {
 
     /// Computes and sets the content hash.
     pub fn compute_hash(&mut self) {
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
+        self.metadata.content_hash = Some(content_hash(&self.content));
+    }
 
-        let mut hasher = DefaultHasher::new();
-        self.content.hash(&mut hasher);
-        self.metadata.content_hash = Some(format!("{:016x}", hasher.finish()));
+    /// Checks the stored content hash against the content.
+    ///
+    /// Returns `None` if no hash is stored, otherwise whether it matches.
+    #[must_use]
+    pub fn verify_hash(&self) -> Option<bool> {
+        self.metadata
+            .content_hash
+            .as_deref()
+            .map(|hash| hash == content_hash(&self.content))
     }
 
     /// Returns a preview of the chunk content (first N characters).
@@ -286,6 +375,126 @@ impl Chunk {
         }
     }
 
+    /// Returns a value stored under `key` in the custom metadata JSON object.
+    #[must_use]
+    pub fn custom_value(&self, key: &str) -> Option<serde_json::Value> {
+        let custom = self.metadata.custom.as_deref()?;
+        let mut value: serde_json::Value = serde_json::from_str(custom).ok()?;
+        value.get_mut(key).map(serde_json::Value::take)
+    }
+
+    /// Stores `value` under `key` in the custom metadata JSON object.
+    ///
+    /// Other keys in the custom metadata JSON object are preserved. Custom
+    /// metadata that is not a JSON object is replaced.
+    pub fn set_custom_value(&mut self, key: &str, value: serde_json::Value) {
+        let mut map = self
+            .metadata
+            .custom
+            .as_deref()
+            .and_then(|c| serde_json::from_str::<serde_json::Value>(c).ok())
+            .and_then(|v| match v {
+                serde_json::Value::Object(map) => Some(map),
+                _ => None,
+            })
+            .unwrap_or_default();
+        map.insert(key.to_string(), value);
+        self.metadata.custom = Some(serde_json::Value::Object(map).to_string());
+    }
+
+    /// Returns the chunk title stored in custom metadata, if any.
+    #[must_use]
+    pub fn title(&self) -> Option<String> {
+        self.custom_value("title")?.as_str().map(String::from)
+    }
+
+    /// Sets the chunk title in custom metadata.
+    ///
+    /// Other keys in the custom metadata JSON object are preserved. Custom
+    /// metadata that is not a JSON object is replaced.
+    pub fn set_title(&mut self, title: &str) {
+        self.set_custom_value("title", serde_json::Value::String(title.to_string()));
+    }
+
+    /// Returns the Markdown heading path stored in custom metadata.
+    ///
+    /// Empty when the chunk has no recorded heading path (e.g. it was not
+    /// produced by the Markdown chunker, or precedes the first heading).
+    #[must_use]
+    pub fn heading_path(&self) -> Vec<String> {
+        self.custom_value("heading_path")
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default()
+    }
+
+    /// Sets the Markdown heading path (outermost heading first) in custom metadata.
+    pub fn set_heading_path(&mut self, path: &[String]) {
+        self.set_custom_value("heading_path", serde_json::json!(path));
+    }
+
+    /// Returns the 1-based range of table rows (first, last) stored in
+    /// custom metadata by the CSV chunker, if any.
+    #[must_use]
+    pub fn row_range(&self) -> Option<(usize, usize)> {
+        self.custom_value("rows")
+            .and_then(|v| serde_json::from_value(v).ok())
+    }
+
+    /// Sets the 1-based range of table rows (first, last) in custom metadata.
+    pub fn set_row_range(&mut self, first: usize, last: usize) {
+        self.set_custom_value("rows", serde_json::json!([first, last]));
+    }
+
+    /// Returns the 0-based range of JSON values (first, last) stored in
+    /// custom metadata by the JSON chunker, if any.
+    #[must_use]
+    pub fn element_range(&self) -> Option<(usize, usize)> {
+        self.custom_value("elements")
+            .and_then(|v| serde_json::from_value(v).ok())
+    }
+
+    /// Sets the 0-based range of JSON values (first, last) in custom metadata.
+    pub fn set_element_range(&mut self, first: usize, last: usize) {
+        self.set_custom_value("elements", serde_json::json!([first, last]));
+    }
+
+    /// Returns whether the chunk is only part of a JSON value, and so not
+    /// valid JSON on its own.
+    #[must_use]
+    pub fn is_json_fragment(&self) -> bool {
+        self.custom_value("json_fragment")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Marks whether the chunk is only part of a JSON value.
+    pub fn set_json_fragment(&mut self, fragment: bool) {
+        self.set_custom_value("json_fragment", serde_json::Value::Bool(fragment));
+    }
+
+    /// Returns the source language detected by the code chunker, if any.
+    #[must_use]
+    pub fn language(&self) -> Option<String> {
+        self.custom_value("language")?.as_str().map(String::from)
+    }
+
+    /// Sets the source language in custom metadata.
+    pub fn set_language(&mut self, language: &str) {
+        self.set_custom_value("language", serde_json::Value::String(language.to_string()));
+    }
+
+    /// Returns the name of the top-level function, class, or type the
+    /// chunk belongs to, if any.
+    #[must_use]
+    pub fn symbol(&self) -> Option<String> {
+        self.custom_value("symbol")?.as_str().map(String::from)
+    }
+
+    /// Sets the enclosing top-level symbol name in custom metadata.
+    pub fn set_symbol(&mut self, symbol: &str) {
+        self.set_custom_value("symbol", serde_json::Value::String(symbol.to_string()));
+    }
+
     /// Checks if this chunk's byte range overlaps with another range.
     #[must_use]
     pub const fn overlaps_with(&self, other_range: &Range<usize>) -> bool {
@@ -516,6 +725,18 @@ mod tests {
         assert_eq!(chunk1.metadata.content_hash, chunk2.metadata.content_hash);
     }
 
+    #[test]
+    fn test_chunk_verify_hash() {
+        let mut chunk = Chunk::new(1, "Hello".to_string(), 0..5, 0);
+        assert_eq!(chunk.verify_hash(), None);
+
+        chunk.compute_hash();
+        assert_eq!(chunk.verify_hash(), Some(true));
+
+        chunk.content.push('!');
+        assert_eq!(chunk.verify_hash(), Some(false));
+    }
+
     #[test]
     fn test_chunk_builder() {
         let chunk = ChunkBuilder::new()
@@ -580,4 +801,99 @@ mod tests {
         assert_eq!(find_char_boundary(s, 8), 6); // Still middle of '世'
         assert_eq!(find_char_boundary(s, 9), 9); // Start of '界'
     }
+
+    #[test]
+    fn test_derive_title() {
+        assert_eq!(
+            derive_title("# Introduction\nText"),
+            Some("Introduction".to_string())
+        );
+        assert_eq!(
+            derive_title("def handler(event):\n    pass"),
+            Some("def handler(event)".to_string())
+        );
+        assert_eq!(derive_title(""), None);
+        assert_eq!(derive_title("###\n"), None);
+
+        let long = "\u{4E16}".repeat(60);
+        let title = derive_title(&long).unwrap();
+        assert!(title.len() <= MAX_TITLE_LEN);
+    }
+
+    #[test]
+    fn test_find_chunk_refs() {
+        assert_eq!(find_chunk_refs("@chunk:1"), vec![(0..8, 1)]);
+        assert_eq!(
+            find_chunk_refs("(@chunk:12)\n@chunk:3"),
+            vec![(1..10, 12), (12..20, 3)]
+        );
+        // Word characters on either side, or a missing ID, aren't references
+        assert!(find_chunk_refs("user@chunk:1 @chunk:2a @chunk: @chunk:x").is_empty());
+        assert!(find_chunk_refs("@chunk:99999999999999999999").is_empty());
+        assert!(find_chunk_refs("plain text").is_empty());
+    }
+
+    #[test]
+    fn test_chunk_title_roundtrip() {
+        let mut chunk = Chunk::new(1, "test".to_string(), 0..4, 0);
+        assert_eq!(chunk.title(), None);
+
+        chunk.metadata.custom = Some(r#"{"lang":"rs"}"#.to_string());
+        chunk.set_title("Overview");
+        assert_eq!(chunk.title(), Some("Overview".to_string()));
+        // Existing custom keys are preserved
+        assert!(chunk.metadata.custom.as_deref().unwrap().contains("lang"));
+
+        chunk.metadata.custom = Some("not json".to_string());
+        assert_eq!(chunk.title(), None);
+        chunk.set_title("Replaced");
+        assert_eq!(chunk.title(), Some("Replaced".to_string()));
+    }
+
+    #[test]
+    fn test_chunk_heading_path_roundtrip() {
+        let mut chunk = Chunk::new(1, "test".to_string(), 0..4, 0);
+        assert!(chunk.heading_path().is_empty());
+
+        chunk.set_title("Overview");
+        let path = vec!["Installation".to_string(), "From Source".to_string()];
+        chunk.set_heading_path(&path);
+        assert_eq!(chunk.heading_path(), path);
+        assert_eq!(chunk.title(), Some("Overview".to_string()));
+    }
+
+    #[test]
+    fn test_chunk_row_range_roundtrip() {
+        let mut chunk = Chunk::new(1, "test".to_string(), 0..4, 0);
+        assert_eq!(chunk.row_range(), None);
+
+        chunk.set_row_range(11, 20);
+        assert_eq!(chunk.row_range(), Some((11, 20)));
+    }
+
+    #[test]
+    fn test_chunk_element_range_and_fragment() {
+        let mut chunk = Chunk::new(1, "test".to_string(), 0..4, 0);
+        assert_eq!(chunk.element_range(), None);
+        assert!(!chunk.is_json_fragment());
+
+        chunk.set_element_range(0, 9);
+        chunk.set_json_fragment(true);
+        assert_eq!(chunk.element_range(), Some((0, 9)));
+        assert!(chunk.is_json_fragment());
+    }
+
+    #[test]
+    fn test_chunk_language_and_symbol() {
+        let mut chunk = Chunk::new(1, "test".to_string(), 0..4, 0);
+        assert_eq!(chunk.language(), None);
+        assert_eq!(chunk.symbol(), None);
+
+        chunk.set_title("fn main()");
+        chunk.set_language("rust");
+        chunk.set_symbol("main");
+        assert_eq!(chunk.language(), Some("rust".to_string()));
+        assert_eq!(chunk.symbol(), Some("main".to_string()));
+        assert_eq!(chunk.title(), Some("fn main()".to_string()));
+    }
 }
@@ -5,7 +5,8 @@
 
 use crate::io::current_timestamp;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
 
 /// Represents the RLM execution context.
 ///
@@ -55,7 +56,9 @@ pub struct ContextMetadata {
 /// Context value types supporting common data types.
 ///
 /// This provides a type-safe way to store heterogeneous values
-/// in the context while maintaining serializability.
+/// in the context while maintaining serializability. Values are stored
+/// tagged with their variant name (`{"type": "Integer", "value": 3}`), so
+/// contexts saved before a variant existed keep deserializing.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type", content = "value")]
 pub enum ContextValue {
@@ -74,8 +77,8 @@ pub enum ContextValue {
     /// List of values.
     List(Vec<Self>),
 
-    /// Nested map of values.
-    Map(HashMap<String, Self>),
+    /// Nested map of values, ordered by key so output is stable.
+    Map(BTreeMap<String, Self>),
 
     /// Null/None value.
     Null,
@@ -214,6 +217,27 @@ impl Context {
         }
     }
 
+    /// Keeps only buffer IDs present in `existing`.
+    ///
+    /// Used to drop dangling references to buffers that were deleted
+    /// without updating the context.
+    ///
+    /// # Returns
+    ///
+    /// The removed buffer IDs, in their original order.
+    pub fn retain_buffers(&mut self, existing: &HashSet<i64>) -> Vec<i64> {
+        let (kept, removed): (Vec<i64>, Vec<i64>) = self
+            .buffer_ids
+            .iter()
+            .copied()
+            .partition(|id| existing.contains(id));
+        if !removed.is_empty() {
+            self.buffer_ids = kept;
+            self.touch();
+        }
+        removed
+    }
+
     /// Resets the context to empty state, preserving metadata.
     pub fn reset(&mut self) {
         self.variables.clear();
@@ -247,6 +271,87 @@ impl Context {
     }
 }
 
+impl ContextValue {
+    /// Returns the lowercase name of the value's kind (e.g. `"integer"`).
+    #[must_use]
+    pub const fn type_name(&self) -> &'static str {
+        match self {
+            Self::String(_) => "string",
+            Self::Integer(_) => "integer",
+            Self::Float(_) => "float",
+            Self::Boolean(_) => "boolean",
+            Self::List(_) => "list",
+            Self::Map(_) => "map",
+            Self::Null => "null",
+        }
+    }
+
+    /// Converts plain JSON into a context value.
+    ///
+    /// Whole numbers that fit an `i64` become [`Integer`](Self::Integer),
+    /// other numbers [`Float`](Self::Float); arrays and objects convert
+    /// recursively.
+    #[must_use]
+    pub fn from_json(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => Self::Null,
+            serde_json::Value::Bool(b) => Self::Boolean(b),
+            serde_json::Value::Number(n) => n.as_i64().map_or_else(
+                || Self::Float(n.as_f64().unwrap_or(f64::NAN)),
+                Self::Integer,
+            ),
+            serde_json::Value::String(s) => Self::String(s),
+            serde_json::Value::Array(items) => {
+                Self::List(items.into_iter().map(Self::from_json).collect())
+            }
+            serde_json::Value::Object(map) => Self::Map(
+                map.into_iter()
+                    .map(|(key, value)| (key, Self::from_json(value)))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Converts the value to plain JSON, without the variant tags used
+    /// when it is stored.
+    ///
+    /// Non-finite floats, which JSON can't represent, become `null`.
+    #[must_use]
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            Self::String(s) => serde_json::Value::String(s.clone()),
+            Self::Integer(n) => serde_json::Value::from(*n),
+            Self::Float(n) => serde_json::Number::from_f64(*n)
+                .map_or(serde_json::Value::Null, serde_json::Value::Number),
+            Self::Boolean(b) => serde_json::Value::Bool(*b),
+            Self::List(items) => {
+                serde_json::Value::Array(items.iter().map(Self::to_json).collect())
+            }
+            Self::Map(map) => serde_json::Value::Object(
+                map.iter()
+                    .map(|(key, value)| (key.clone(), value.to_json()))
+                    .collect(),
+            ),
+            Self::Null => serde_json::Value::Null,
+        }
+    }
+}
+
+/// Strings print as-is, floats always with a decimal point, and lists and
+/// maps as compact JSON.
+impl fmt::Display for ContextValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::String(s) => f.write_str(s),
+            Self::Integer(n) => write!(f, "{n}"),
+            Self::Float(n) => write!(f, "{n:?}"),
+            Self::Boolean(b) => write!(f, "{b}"),
+            Self::List(_) | Self::Map(_) => write!(f, "{}", self.to_json()),
+            Self::Null => f.write_str("null"),
+        }
+    }
+}
+
 impl From<String> for ContextValue {
     fn from(s: String) -> Self {
         Self::String(s)
@@ -363,6 +468,21 @@ mod tests {
         assert_eq!(ctx.buffer_count(), 1);
     }
 
+    #[test]
+    fn test_retain_buffers() {
+        let mut ctx = Context::new();
+        for id in [1, 7, 2, 9] {
+            ctx.add_buffer(id);
+        }
+
+        let existing: HashSet<i64> = [1, 2, 3].into_iter().collect();
+        assert_eq!(ctx.retain_buffers(&existing), vec![7, 9]);
+        assert_eq!(ctx.buffer_ids, vec![1, 2]);
+
+        // Nothing left to prune
+        assert!(ctx.retain_buffers(&existing).is_empty());
+    }
+
     #[test]
     fn test_context_reset() {
         let mut ctx = Context::new();
@@ -413,6 +533,78 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_context_value_round_trip() {
+        let mut map = BTreeMap::new();
+        map.insert("b".to_string(), ContextValue::Boolean(false));
+        map.insert(
+            "a".to_string(),
+            ContextValue::List(vec![1i64.into(), "x".into()]),
+        );
+        let values = [
+            ContextValue::String("text".to_string()),
+            ContextValue::Integer(-3),
+            ContextValue::Float(2.5),
+            ContextValue::Boolean(true),
+            ContextValue::Map(map),
+            ContextValue::Null,
+        ];
+        for value in values {
+            let json = serde_json::to_string(&value).unwrap();
+            let back: ContextValue = serde_json::from_str(&json).unwrap();
+            assert_eq!(back, value, "{json}");
+        }
+    }
+
+    #[test]
+    fn test_string_only_context_still_deserializes() {
+        // As saved by versions that only stored strings
+        let saved = r#"{
+            "variables": {"key": {"type": "String", "value": "value"}},
+            "globals": {},
+            "buffer_ids": [],
+            "cwd": null,
+            "metadata": {"created_at": 1, "updated_at": 1, "version": 1}
+        }"#;
+        let ctx: Context = serde_json::from_str(saved).unwrap();
+        assert_eq!(ctx.get_variable("key"), Some(&"value".into()));
+    }
+
+    #[test]
+    fn test_context_value_json() {
+        let value = ContextValue::from_json(serde_json::json!({
+            "count": 3,
+            "ratio": 0.5,
+            "tags": ["a", true, null]
+        }));
+        let expected: BTreeMap<String, ContextValue> = [
+            ("count".to_string(), ContextValue::Integer(3)),
+            ("ratio".to_string(), ContextValue::Float(0.5)),
+            (
+                "tags".to_string(),
+                ContextValue::List(vec!["a".into(), true.into(), ContextValue::Null]),
+            ),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(value, ContextValue::Map(expected));
+        assert_eq!(
+            value.to_string(),
+            r#"{"count":3,"ratio":0.5,"tags":["a",true,null]}"#
+        );
+        assert_eq!(value.type_name(), "map");
+
+        assert_eq!(ContextValue::Float(42.0).to_string(), "42.0");
+        assert_eq!(
+            ContextValue::Float(f64::NAN).to_json(),
+            serde_json::Value::Null
+        );
+        assert_eq!(
+            ContextValue::String("plain".to_string()).to_string(),
+            "plain"
+        );
+    }
+
     #[test]
     fn test_touch_updates_timestamp() {
         let mut ctx = Context::new();
@@ -0,0 +1,264 @@
+//! Chunk-level comparison of two buffers.
+//!
+//! Chunks are aligned by a hash of their whitespace-normalized content, so
+//! reformatting alone does not count as a change, and the aligned sequences
+//! are diffed (longest common subsequence) into unified-diff-style hunks.
+
+use crate::core::{Chunk, content_hash};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Preview length, in grapheme clusters, of chunks listed in hunks.
+const DIFF_PREVIEW_LEN: usize = 80;
+
+/// Largest LCS table (cells) computed for the region between the common
+/// prefix and suffix; larger regions are reported as a single hunk.
+const MAX_LCS_CELLS: usize = 4_000_000;
+
+/// A chunk listed in a [`DiffHunk`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiffChunk {
+    /// Chunk ID.
+    pub id: Option<i64>,
+    /// Chunk index within its buffer.
+    pub index: usize,
+    /// Single-line content preview.
+    pub preview: String,
+}
+
+/// A changed region: consecutive chunks removed from the first buffer and
+/// the chunks added in their place in the second.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiffHunk {
+    /// Position of the region in the first buffer's chunk list.
+    pub a_start: usize,
+    /// Position of the region in the second buffer's chunk list.
+    pub b_start: usize,
+    /// Chunks only in the first buffer.
+    pub removed: Vec<DiffChunk>,
+    /// Chunks only in the second buffer.
+    pub added: Vec<DiffChunk>,
+}
+
+/// Result of comparing the chunk sets of two buffers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BufferDiff {
+    /// First (old) buffer ID.
+    pub a_id: i64,
+    /// Second (new) buffer ID.
+    pub b_id: i64,
+    /// Chunking strategy of the first buffer, if recorded.
+    pub a_strategy: Option<String>,
+    /// Chunking strategy of the second buffer, if recorded.
+    pub b_strategy: Option<String>,
+    /// Chunks present in both buffers.
+    pub unchanged: usize,
+    /// Chunks only in the second buffer.
+    pub added: usize,
+    /// Chunks only in the first buffer.
+    pub removed: usize,
+    /// Changed regions, in order.
+    pub hunks: Vec<DiffHunk>,
+}
+
+impl BufferDiff {
+    /// Returns `true` if the buffers were chunked with different strategies.
+    ///
+    /// Chunk boundaries then rarely line up, so most chunks show as changed
+    /// even when the text is similar.
+    #[must_use]
+    pub fn strategies_differ(&self) -> bool {
+        self.a_strategy != self.b_strategy
+    }
+
+    /// Returns `true` if both buffers have the same chunks.
+    #[must_use]
+    pub const fn is_identical(&self) -> bool {
+        self.added == 0 && self.removed == 0
+    }
+}
+
+/// Hashes chunk content with runs of whitespace collapsed and ends trimmed.
+fn normalized_hash(content: &str) -> String {
+    content_hash(&content.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// Compares two buffers' chunks, given in chunk order.
+///
+/// # Examples
+///
+/// ```
+/// use rlm_rs::core::{Chunk, diff_chunks};
+///
+/// let a = vec![Chunk::new(1, "same".to_string(), 0..4, 0)];
+/// let b = vec![
+///     Chunk::new(2, "same".to_string(), 0..4, 0),
+///     Chunk::new(2, "new".to_string(), 4..7, 1),
+/// ];
+/// let diff = diff_chunks(1, &a, 2, &b);
+/// assert_eq!((diff.unchanged, diff.added, diff.removed), (1, 1, 0));
+/// ```
+#[must_use]
+pub fn diff_chunks(a_id: i64, a: &[Chunk], b_id: i64, b: &[Chunk]) -> BufferDiff {
+    // Intern hashes so the LCS compares integers
+    let mut ids: HashMap<String, usize> = HashMap::new();
+    let mut intern = |chunk: &Chunk| {
+        let next = ids.len();
+        *ids.entry(normalized_hash(&chunk.content)).or_insert(next)
+    };
+    let a_keys: Vec<usize> = a.iter().map(&mut intern).collect();
+    let b_keys: Vec<usize> = b.iter().map(&mut intern).collect();
+
+    let matches = align(&a_keys, &b_keys);
+    let strategy = |chunks: &[Chunk]| chunks.first().and_then(|c| c.metadata.strategy.clone());
+
+    BufferDiff {
+        a_id,
+        b_id,
+        a_strategy: strategy(a),
+        b_strategy: strategy(b),
+        unchanged: matches.len(),
+        added: b.len() - matches.len(),
+        removed: a.len() - matches.len(),
+        hunks: hunks(a, b, &matches),
+    }
+}
+
+/// Returns the matched `(a, b)` positions of a longest common subsequence.
+fn align(a: &[usize], b: &[usize]) -> Vec<(usize, usize)> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let a_mid = &a[prefix..a.len() - suffix];
+    let b_mid = &b[prefix..b.len() - suffix];
+
+    let mut matches: Vec<(usize, usize)> = (0..prefix).map(|pos| (pos, pos)).collect();
+    let (rows, cols) = (a_mid.len(), b_mid.len());
+    if rows > 0 && cols > 0 && (rows + 1).saturating_mul(cols + 1) <= MAX_LCS_CELLS {
+        // lcs[row][col] = LCS length of a_mid[row..] and b_mid[col..]
+        let width = cols + 1;
+        let mut lcs = vec![0u32; (rows + 1) * width];
+        for row in (0..rows).rev() {
+            for col in (0..cols).rev() {
+                lcs[row * width + col] = if a_mid[row] == b_mid[col] {
+                    lcs[(row + 1) * width + col + 1] + 1
+                } else {
+                    lcs[(row + 1) * width + col].max(lcs[row * width + col + 1])
+                };
+            }
+        }
+        let (mut row, mut col) = (0, 0);
+        while row < rows && col < cols {
+            if a_mid[row] == b_mid[col] {
+                matches.push((prefix + row, prefix + col));
+                row += 1;
+                col += 1;
+            } else if lcs[(row + 1) * width + col] >= lcs[row * width + col + 1] {
+                row += 1;
+            } else {
+                col += 1;
+            }
+        }
+    }
+    matches.extend((0..suffix).map(|k| (a.len() - suffix + k, b.len() - suffix + k)));
+    matches
+}
+
+/// Groups the chunks between consecutive matches into hunks.
+fn hunks(a: &[Chunk], b: &[Chunk], matches: &[(usize, usize)]) -> Vec<DiffHunk> {
+    let listed = |chunks: &[Chunk]| -> Vec<DiffChunk> {
+        chunks
+            .iter()
+            .map(|c| DiffChunk {
+                id: c.id,
+                index: c.index,
+                preview: crate::io::make_preview(&c.content, DIFF_PREVIEW_LEN),
+            })
+            .collect()
+    };
+
+    let mut hunks = Vec::new();
+    let (mut a_pos, mut b_pos) = (0, 0);
+    // A sentinel match past both ends flushes the trailing region
+    for &(a_next, b_next) in matches.iter().chain(std::iter::once(&(a.len(), b.len()))) {
+        if a_next > a_pos || b_next > b_pos {
+            hunks.push(DiffHunk {
+                a_start: a_pos,
+                b_start: b_pos,
+                removed: listed(&a[a_pos..a_next]),
+                added: listed(&b[b_pos..b_next]),
+            });
+        }
+        a_pos = a_next + 1;
+        b_pos = b_next + 1;
+    }
+    hunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunks(buffer_id: i64, texts: &[&str]) -> Vec<Chunk> {
+        texts
+            .iter()
+            .enumerate()
+            .map(|(i, text)| {
+                let mut chunk = Chunk::new(buffer_id, (*text).to_string(), i..i + 1, i);
+                chunk.metadata.strategy = Some("fixed".to_string());
+                chunk
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_identical() {
+        let a = chunks(1, &["a", "b"]);
+        let diff = diff_chunks(1, &a, 2, &chunks(2, &["a", "b"]));
+        assert!(diff.is_identical());
+        assert_eq!(diff.unchanged, 2);
+        assert!(diff.hunks.is_empty());
+        assert!(!diff.strategies_differ());
+    }
+
+    #[test]
+    fn test_diff_hunks() {
+        let a = chunks(1, &["intro", "old middle", "shared", "dropped", "outro"]);
+        let b = chunks(2, &["intro", "new middle", "shared", "outro", "appendix"]);
+        let diff = diff_chunks(1, &a, 2, &b);
+        assert_eq!((diff.unchanged, diff.added, diff.removed), (3, 2, 2));
+
+        assert_eq!(diff.hunks.len(), 3);
+        let hunk = &diff.hunks[0];
+        assert_eq!((hunk.a_start, hunk.b_start), (1, 1));
+        assert_eq!(hunk.removed[0].preview, "old middle");
+        assert_eq!(hunk.added[0].preview, "new middle");
+        assert_eq!(diff.hunks[1].removed[0].preview, "dropped");
+        assert!(diff.hunks[1].added.is_empty());
+        assert_eq!(diff.hunks[2].added[0].preview, "appendix");
+        assert_eq!(diff.hunks[2].b_start, 4);
+    }
+
+    #[test]
+    fn test_diff_ignores_whitespace_and_strategy() {
+        let a = chunks(1, &["one  two\n", "three"]);
+        let mut b = chunks(2, &["one two", "three"]);
+        b[0].metadata.strategy = Some("semantic".to_string());
+        let diff = diff_chunks(1, &a, 2, &b);
+        assert!(diff.is_identical());
+        assert!(diff.strategies_differ());
+    }
+
+    #[test]
+    fn test_diff_empty() {
+        let a = chunks(1, &["x"]);
+        let diff = diff_chunks(1, &a, 2, &[]);
+        assert_eq!((diff.unchanged, diff.added, diff.removed), (0, 0, 1));
+        assert_eq!(diff.hunks.len(), 1);
+        assert_eq!(diff.b_strategy, None);
+    }
+}
@@ -0,0 +1,52 @@
+//! Deterministic content hashing.
+//!
+//! Buffer and chunk hashes are persisted and compared across runs, so they
+//! use 64-bit FNV-1a rather than `DefaultHasher`, whose algorithm may change
+//! between Rust releases.
+
+/// FNV-1a 64-bit offset basis.
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// FNV-1a 64-bit prime.
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// Returns the content hash of `content` as 16 lowercase hex digits.
+///
+/// The hash is stable across platforms and Rust releases. It detects
+/// accidental changes such as corruption, not deliberate tampering.
+///
+/// # Examples
+///
+/// ```
+/// use rlm_rs::core::content_hash;
+///
+/// assert_eq!(content_hash(""), "cbf29ce484222325");
+/// assert_eq!(content_hash("hello"), content_hash("hello"));
+/// assert_ne!(content_hash("hello"), content_hash("hellp"));
+/// ```
+#[must_use]
+pub fn content_hash(content: &str) -> String {
+    let hash = content.bytes().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+    });
+    format!("{hash:016x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_hash_known_vectors() {
+        // Reference values for 64-bit FNV-1a
+        assert_eq!(content_hash(""), "cbf29ce484222325");
+        assert_eq!(content_hash("a"), "af63dc4c8601ec8c");
+        assert_eq!(content_hash("foobar"), "85944171f73967e8");
+    }
+
+    #[test]
+    fn test_content_hash_distinguishes_content() {
+        assert_ne!(content_hash("Hello"), content_hash("hello"));
+        assert_eq!(content_hash("Hello").len(), 16);
+    }
+}
@@ -7,7 +7,14 @@
 pub mod buffer;
 pub mod chunk;
 pub mod context;
+pub mod diff;
+pub mod hash;
 
 pub use buffer::{Buffer, BufferMetadata};
-pub use chunk::{Chunk, ChunkMetadata, estimate_tokens_for_text};
+pub use chunk::{
+    CHUNK_REF_PREFIX, Chunk, ChunkMetadata, DEFAULT_REF_DEPTH, MAX_TITLE_LEN, derive_title,
+    estimate_tokens_for_text, find_chunk_refs,
+};
 pub use context::{Context, ContextValue};
+pub use diff::{BufferDiff, DiffChunk, DiffHunk, diff_chunks};
+pub use hash::content_hash;
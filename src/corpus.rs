@@ -0,0 +1,356 @@
+//! Deterministic synthetic corpus generation.
+//!
+//! Populates a database with lorem-like buffers and chunks derived from a
+//! seed, so that search and embedding behavior can be reproduced and
+//! benchmarked at scale. The same seed always yields byte-identical content.
+
+use crate::core::{Buffer, Chunk};
+use crate::embedding::Embedder;
+use crate::error::Result;
+use crate::search::embed_buffer_chunks;
+use crate::storage::{SqliteStorage, Storage};
+
+/// Strategy name recorded on generated chunks.
+pub const CORPUS_STRATEGY: &str = "synthetic";
+
+/// Vocabulary used to build lorem-like sentences.
+const WORDS: &[&str] = &[
+    "lorem",
+    "ipsum",
+    "dolor",
+    "sit",
+    "amet",
+    "consectetur",
+    "adipiscing",
+    "elit",
+    "sed",
+    "do",
+    "eiusmod",
+    "tempor",
+    "incididunt",
+    "ut",
+    "labore",
+    "et",
+    "dolore",
+    "magna",
+    "aliqua",
+    "enim",
+    "ad",
+    "minim",
+    "veniam",
+    "quis",
+    "nostrud",
+    "exercitation",
+    "ullamco",
+    "laboris",
+    "nisi",
+    "aliquip",
+    "ex",
+    "ea",
+    "commodo",
+    "consequat",
+    "duis",
+    "aute",
+    "irure",
+    "reprehenderit",
+    "voluptate",
+    "velit",
+    "esse",
+    "cillum",
+    "fugiat",
+    "nulla",
+    "pariatur",
+    "excepteur",
+    "sint",
+    "occaecat",
+    "cupidatat",
+    "proident",
+    "sunt",
+    "culpa",
+    "officia",
+    "deserunt",
+    "mollit",
+    "anim",
+    "id",
+    "est",
+    "laborum",
+];
+
+/// Small seeded pseudo-random generator (`SplitMix64`).
+///
+/// Not suitable for cryptographic use; chosen because it is tiny, fast and
+/// stable across platforms and releases, which keeps corpora reproducible.
+#[derive(Debug, Clone)]
+pub struct CorpusRng {
+    state: u64,
+}
+
+impl CorpusRng {
+    /// Creates a generator from a seed.
+    #[must_use]
+    pub const fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Returns the next pseudo-random `u64`.
+    pub const fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a value in `[low, high]` (inclusive).
+    #[allow(clippy::cast_possible_truncation)]
+    pub const fn range(&mut self, low: usize, high: usize) -> usize {
+        if high <= low {
+            return low;
+        }
+        low + (self.next_u64() % ((high - low + 1) as u64)) as usize
+    }
+}
+
+/// Configuration for corpus generation.
+#[derive(Debug, Clone)]
+pub struct CorpusConfig {
+    /// Number of buffers to create.
+    pub buffers: usize,
+    /// Number of chunks per buffer.
+    pub chunks_per_buffer: usize,
+    /// Seed for the pseudo-random generator.
+    pub seed: u64,
+    /// Sentences per chunk (inclusive range).
+    pub sentences_per_chunk: (usize, usize),
+    /// Words per sentence (inclusive range).
+    pub words_per_sentence: (usize, usize),
+}
+
+impl Default for CorpusConfig {
+    fn default() -> Self {
+        Self {
+            buffers: 10,
+            chunks_per_buffer: 20,
+            seed: 42,
+            sentences_per_chunk: (3, 8),
+            words_per_sentence: (6, 16),
+        }
+    }
+}
+
+impl CorpusConfig {
+    /// Creates a configuration with the given sizes and seed.
+    #[must_use]
+    pub fn new(buffers: usize, chunks_per_buffer: usize, seed: u64) -> Self {
+        Self {
+            buffers,
+            chunks_per_buffer,
+            seed,
+            ..Self::default()
+        }
+    }
+}
+
+/// Summary of a populated corpus.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CorpusSummary {
+    /// IDs of the created buffers, in creation order.
+    pub buffer_ids: Vec<i64>,
+    /// Total chunks created.
+    pub chunk_count: usize,
+    /// Total embeddings stored (0 when no embedder was given).
+    pub embedded_count: usize,
+    /// Total content size in bytes.
+    pub total_bytes: usize,
+}
+
+/// Generates a single lorem-like sentence.
+fn sentence(rng: &mut CorpusRng, config: &CorpusConfig) -> String {
+    let (min_words, max_words) = config.words_per_sentence;
+    let count = rng.range(min_words.max(1), max_words.max(1));
+    let mut words: Vec<&str> = Vec::with_capacity(count);
+    for _ in 0..count {
+        words.push(WORDS[rng.range(0, WORDS.len() - 1)]);
+    }
+    let mut text = words.join(" ");
+    if let Some(first) = text.get_mut(0..1) {
+        first.make_ascii_uppercase();
+    }
+    text.push('.');
+    text
+}
+
+/// Generates the chunk texts for one buffer.
+///
+/// Each chunk is a paragraph of sentences; paragraphs are joined with a
+/// blank line when assembled into buffer content.
+#[must_use]
+pub fn generate_paragraphs(rng: &mut CorpusRng, config: &CorpusConfig) -> Vec<String> {
+    let (min_sentences, max_sentences) = config.sentences_per_chunk;
+    (0..config.chunks_per_buffer)
+        .map(|_| {
+            let count = rng.range(min_sentences.max(1), max_sentences.max(1));
+            (0..count)
+                .map(|_| sentence(rng, config))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect()
+}
+
+/// Populates storage with a deterministic synthetic corpus.
+///
+/// Buffers are named `corpus-<seed>-<n>`. Chunks align exactly with the
+/// generated paragraphs, so chunk boundaries are stable for a given seed.
+/// When an embedder is provided, every chunk is embedded as well.
+///
+/// # Errors
+///
+/// Returns an error if storage or embedding operations fail.
+pub fn populate_corpus(
+    storage: &mut SqliteStorage,
+    config: &CorpusConfig,
+    embedder: Option<&dyn Embedder>,
+) -> Result<CorpusSummary> {
+    let mut rng = CorpusRng::new(config.seed);
+    let mut summary = CorpusSummary::default();
+
+    // Skip the per-chunk count triggers and reconcile once at the end
+    let auto_chunk_count = storage.auto_chunk_count();
+    storage.set_auto_chunk_count(false);
+    let result = populate_buffers(storage, config, embedder, &mut rng, &mut summary);
+    storage.set_auto_chunk_count(auto_chunk_count);
+    result?;
+    storage.recompute_chunk_counts()?;
+
+    Ok(summary)
+}
+
+/// Adds the corpus buffers, chunks, and embeddings to `summary`.
+fn populate_buffers(
+    storage: &mut SqliteStorage,
+    config: &CorpusConfig,
+    embedder: Option<&dyn Embedder>,
+    rng: &mut CorpusRng,
+    summary: &mut CorpusSummary,
+) -> Result<()> {
+    for n in 0..config.buffers {
+        let paragraphs = generate_paragraphs(rng, config);
+        let content = paragraphs.join("\n\n");
+
+        let mut buffer = Buffer::from_named(format!("corpus-{}-{n}", config.seed), content);
+        buffer.metadata.content_type = Some("text".to_string());
+        buffer.set_chunk_count(paragraphs.len());
+        buffer.compute_hash();
+        let buffer_id = storage.add_buffer(&buffer)?;
+
+        let mut chunks = Vec::with_capacity(paragraphs.len());
+        let mut offset = 0;
+        for (index, paragraph) in paragraphs.into_iter().enumerate() {
+            let end = offset + paragraph.len();
+            let mut chunk =
+                Chunk::with_strategy(buffer_id, paragraph, offset..end, index, CORPUS_STRATEGY);
+            chunk.set_token_count(chunk.estimate_tokens());
+            chunk.compute_hash();
+            chunks.push(chunk);
+            offset = end + 2;
+        }
+        storage.add_chunks(buffer_id, &chunks)?;
+
+        if let Some(embedder) = embedder {
+            summary.embedded_count += embed_buffer_chunks(storage, embedder, buffer_id)?;
+        }
+
+        summary.chunk_count += chunks.len();
+        summary.total_bytes += buffer.size();
+        summary.buffer_ids.push(buffer_id);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embedding::FallbackEmbedder;
+
+    fn setup() -> SqliteStorage {
+        let mut storage = SqliteStorage::in_memory().unwrap();
+        storage.init().unwrap();
+        storage
+    }
+
+    #[test]
+    fn test_rng_deterministic() {
+        let mut a = CorpusRng::new(7);
+        let mut b = CorpusRng::new(7);
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+        assert_ne!(CorpusRng::new(1).next_u64(), CorpusRng::new(2).next_u64());
+    }
+
+    #[test]
+    fn test_rng_range_bounds() {
+        let mut rng = CorpusRng::new(3);
+        for _ in 0..1000 {
+            let v = rng.range(5, 9);
+            assert!((5..=9).contains(&v));
+        }
+        assert_eq!(rng.range(4, 4), 4);
+        assert_eq!(rng.range(4, 2), 4);
+    }
+
+    #[test]
+    fn test_generate_paragraphs_deterministic() {
+        let config = CorpusConfig::new(1, 5, 99);
+        let a = generate_paragraphs(&mut CorpusRng::new(99), &config);
+        let b = generate_paragraphs(&mut CorpusRng::new(99), &config);
+        assert_eq!(a, b);
+        assert_eq!(a.len(), 5);
+        assert!(a.iter().all(|p| p.ends_with('.')));
+    }
+
+    #[test]
+    fn test_populate_corpus() {
+        let mut storage = setup();
+        let config = CorpusConfig::new(3, 4, 1);
+        let summary = populate_corpus(&mut storage, &config, None).unwrap();
+
+        assert_eq!(summary.buffer_ids.len(), 3);
+        assert_eq!(summary.chunk_count, 12);
+        assert_eq!(summary.embedded_count, 0);
+
+        let buffer = storage.get_buffer(summary.buffer_ids[0]).unwrap().unwrap();
+        assert_eq!(buffer.name.as_deref(), Some("corpus-1-0"));
+        for chunk in storage.get_chunks(summary.buffer_ids[0]).unwrap() {
+            assert_eq!(&buffer.content[chunk.byte_range.clone()], chunk.content);
+        }
+    }
+
+    #[test]
+    fn test_populate_corpus_same_seed_same_content() {
+        let config = CorpusConfig::new(2, 3, 2024);
+        let mut first = setup();
+        let mut second = setup();
+        let a = populate_corpus(&mut first, &config, None).unwrap();
+        let b = populate_corpus(&mut second, &config, None).unwrap();
+
+        for (id_a, id_b) in a.buffer_ids.iter().zip(&b.buffer_ids) {
+            let buf_a = first.get_buffer(*id_a).unwrap().unwrap();
+            let buf_b = second.get_buffer(*id_b).unwrap().unwrap();
+            assert_eq!(buf_a.content, buf_b.content);
+        }
+    }
+
+    #[test]
+    fn test_populate_corpus_with_embeddings() {
+        let mut storage = setup();
+        let embedder = FallbackEmbedder::new(64);
+        let config = CorpusConfig::new(2, 3, 5);
+        let summary = populate_corpus(&mut storage, &config, Some(&embedder)).unwrap();
+
+        assert_eq!(summary.embedded_count, 6);
+        assert_eq!(storage.embedding_count().unwrap(), 6);
+    }
+}
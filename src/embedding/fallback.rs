@@ -1,21 +1,31 @@
 //! Hash-based fallback embedder.
 //!
 //! Provides deterministic pseudo-embeddings when `FastEmbed` is not available.
-//! Uses content hashing to generate reproducible embeddings that cluster
-//! similar text together (based on word overlap, not semantics).
+//! Words and their character tri-grams are hashed into the vector, so texts
+//! sharing words or word fragments score a nonzero similarity (based on
+//! lexical overlap, not semantics).
 
 use crate::Result;
 use crate::embedding::Embedder;
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
+
+/// Weight of a whole-word feature.
+const WORD_WEIGHT: f32 = 1.0;
+
+/// Weight of a character tri-gram feature.
+const TRIGRAM_WEIGHT: f32 = 0.5;
 
 /// Hash-based fallback embedder.
 ///
 /// Generates deterministic pseudo-embeddings using a combination of:
 /// - Word-level hashing for vocabulary capture
-/// - Character n-gram hashing for fuzzy matching
+/// - Character tri-gram hashing within each word, padded at word
+///   boundaries, so inflections and compounds ("embed", "embedding")
+///   overlap
 /// - Normalization to unit length for cosine similarity
 ///
+/// Features are hashed with FNV-1a, so vectors are identical across
+/// platforms and Rust versions.
+///
 /// This is NOT semantic similarity - it's based on lexical overlap.
 /// Use `FastEmbed` for true semantic understanding.
 ///
@@ -35,20 +45,39 @@ pub struct FallbackEmbedder {
 
 impl FallbackEmbedder {
     /// Creates a new fallback embedder with the specified dimensions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `dimensions` is zero.
     #[must_use]
     pub const fn new(dimensions: usize) -> Self {
+        assert!(dimensions > 0, "embedding dimensions must be positive");
         Self { dimensions }
     }
 
-    /// Hashes a string to a u64 value.
-    fn hash_string(s: &str) -> u64 {
-        let mut hasher = DefaultHasher::new();
-        s.hash(&mut hasher);
-        hasher.finish()
+    /// Hashes a feature with 64-bit FNV-1a, which is stable everywhere.
+    fn hash_feature(kind: u8, feature: &str) -> u64 {
+        const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0100_0000_01b3;
+        // The kind byte keeps a word apart from a same-spelled tri-gram
+        std::iter::once(kind)
+            .chain(feature.bytes())
+            .fold(OFFSET_BASIS, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+            })
+    }
+
+    /// Adds a feature to its hashed dimension, with a hashed sign so
+    /// collisions between unrelated features tend to cancel out.
+    #[allow(clippy::cast_possible_truncation)]
+    fn add_feature(&self, embedding: &mut [f32], kind: u8, feature: &str, weight: f32) {
+        let hash = Self::hash_feature(kind, feature);
+        let idx = (hash % self.dimensions as u64) as usize;
+        let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+        embedding[idx] += sign * weight;
     }
 
     /// Generates a pseudo-embedding from text.
-    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
     fn generate_embedding(&self, text: &str) -> Vec<f32> {
         let mut embedding = vec![0.0f32; self.dimensions];
 
@@ -64,28 +93,21 @@ impl FallbackEmbedder {
             })
             .collect();
 
-        // Split into words
-        let words: Vec<&str> = normalized.split_whitespace().collect();
-
-        // Word-level hashing (primary signal)
-        for word in &words {
-            let hash = Self::hash_string(word);
-            let idx = (hash as usize) % self.dimensions;
-            // Use hash bits to determine sign and magnitude
-            let sign = if (hash >> 32) & 1 == 0 { 1.0 } else { -1.0 };
-            let magnitude = 1.0 + ((hash >> 16) & 0xFF) as f32 / 255.0;
-            embedding[idx] += sign * magnitude;
-        }
+        let mut trigram = String::new();
+        for word in normalized.split_whitespace() {
+            // Whole words (primary signal)
+            self.add_feature(&mut embedding, b'w', word, WORD_WEIGHT);
 
-        // Character trigram hashing (secondary signal for fuzzy matching)
-        let chars: Vec<char> = normalized.chars().collect();
-        if chars.len() >= 3 {
-            for window in chars.windows(3) {
-                let trigram: String = window.iter().collect();
-                let hash = Self::hash_string(&trigram);
-                let idx = (hash as usize) % self.dimensions;
-                let sign = if (hash >> 32) & 1 == 0 { 0.5 } else { -0.5 };
-                embedding[idx] += sign;
+            // Tri-grams of the word padded with spaces, so prefixes and
+            // suffixes count as fragments of their own (fuzzy matching)
+            let padded: Vec<char> = std::iter::once(' ')
+                .chain(word.chars())
+                .chain(std::iter::once(' '))
+                .collect();
+            for window in padded.windows(3) {
+                trigram.clear();
+                trigram.extend(window);
+                self.add_feature(&mut embedding, b't', &trigram, TRIGRAM_WEIGHT);
             }
         }
 
@@ -107,7 +129,7 @@ impl Embedder for FallbackEmbedder {
     }
 
     fn model_name(&self) -> &'static str {
-        "fallback-hash-v1"
+        "fallback-ngram-v2"
     }
 
     fn embed(&self, text: &str) -> Result<Vec<f32>> {
@@ -146,6 +168,15 @@ mod tests {
         let embedder = FallbackEmbedder::new(DEFAULT_DIMENSIONS);
         let emb = embedder.embed("test").unwrap();
         assert_eq!(emb.len(), DEFAULT_DIMENSIONS);
+
+        let embedder = FallbackEmbedder::new(384);
+        assert_eq!(embedder.embed("test").unwrap().len(), 384);
+    }
+
+    #[test]
+    #[should_panic(expected = "embedding dimensions must be positive")]
+    fn test_zero_dimensions() {
+        let _ = FallbackEmbedder::new(0);
     }
 
     #[test]
@@ -172,6 +203,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_shared_substrings_score_higher() {
+        let embedder = FallbackEmbedder::new(DEFAULT_DIMENSIONS);
+        let similarity = |a: &str, b: &str| {
+            cosine_similarity(&embedder.embed(a).unwrap(), &embedder.embed(b).unwrap())
+        };
+
+        let related = similarity("programming language", "programming");
+        let unrelated = similarity("programming language", "banana");
+        assert!(
+            related > unrelated,
+            "shared words should score higher: {related} vs {unrelated}"
+        );
+        assert!(related > 0.5, "{related}");
+
+        // Word fragments alone still give a signal
+        assert!(similarity("embedding", "embeddings") > 0.5);
+        assert!(similarity("embedding", "embeddings") > similarity("embedding", "tokenizer"));
+    }
+
+    #[test]
+    fn test_stable_across_builds() {
+        // FNV-1a, unlike `DefaultHasher`, is fixed by its specification:
+        // this is FNV-1a("w")
+        assert_eq!(
+            FallbackEmbedder::hash_feature(b'w', ""),
+            0xaf63_ea4c_8602_0456
+        );
+    }
+
     #[test]
     fn test_batch_embedding() {
         let embedder = FallbackEmbedder::new(DEFAULT_DIMENSIONS);
@@ -0,0 +1,100 @@
+//! Minimal HTTP server for testing remote embedders without a network.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::Arc;
+
+/// A request received by the mock server.
+pub struct MockRequest {
+    /// Request path, e.g. `/api/embeddings`.
+    pub path: String,
+    /// Header names (lowercased) and values.
+    pub headers: Vec<(String, String)>,
+    /// JSON request body.
+    pub body: serde_json::Value,
+}
+
+impl MockRequest {
+    /// Returns the value of a header by lowercase name.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Serves each request on a local port with `handler`, which returns the
+/// status code and JSON body. Returns the base URL.
+pub fn serve<F>(handler: F) -> String
+where
+    F: Fn(&MockRequest) -> (u16, serde_json::Value) + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let handler = Arc::new(handler);
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { break };
+            let handler = Arc::clone(&handler);
+            std::thread::spawn(move || {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let path = request_line
+                    .split_whitespace()
+                    .nth(1)
+                    .unwrap_or_default()
+                    .to_string();
+
+                let mut headers = Vec::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line == "\r\n" || line.is_empty() {
+                        break;
+                    }
+                    if let Some((key, value)) = line.split_once(':') {
+                        headers.push((key.trim().to_ascii_lowercase(), value.trim().to_string()));
+                    }
+                }
+
+                let content_length = headers
+                    .iter()
+                    .find(|(key, _)| key == "content-length")
+                    .map_or(0, |(_, value)| value.parse().unwrap());
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+
+                let request = MockRequest {
+                    path,
+                    headers,
+                    body: serde_json::from_slice(&body).unwrap_or_default(),
+                };
+                let (status, response) = handler(&request);
+                let response = response.to_string();
+                write!(
+                    stream,
+                    "HTTP/1.1 {status} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{response}",
+                    response.len()
+                )
+                .unwrap();
+            });
+        }
+    });
+
+    url
+}
+
+/// Returns a local URL with nothing listening on it.
+pub fn unreachable_url() -> String {
+    // Bind then drop to get a free port
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    format!("http://127.0.0.1:{port}")
+}
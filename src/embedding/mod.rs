@@ -1,24 +1,52 @@
 //! Embedding generation for semantic search.
 //!
-//! Provides embedding generation using fastembed (when available) or a
-//! hash-based fallback for deterministic pseudo-embeddings.
+//! Provides embedding generation using a remote OpenAI-compatible API, a
+//! local Ollama server, or fastembed (when available), or a hash-based
+//! fallback for deterministic pseudo-embeddings.
 //!
 //! # Feature Flags
 //!
+//! - `openai-embeddings`: Enables `OpenAiEmbedder`, used when `OPENAI_API_KEY` is set
+//! - `ollama-embeddings`: Enables `OllamaEmbedder`, used when an Ollama server is reachable
 //! - `fastembed-embeddings`: Enables `FastEmbed` with BGE-M3 (1024 dimensions, 8192 token max)
-//! - Without the feature: Uses hash-based fallback (deterministic but not semantic)
+//! - Without either feature: Uses hash-based fallback (deterministic but not semantic)
 
 mod fallback;
 
 #[cfg(feature = "fastembed-embeddings")]
 mod fastembed_impl;
 
+#[cfg(feature = "ollama-embeddings")]
+mod ollama;
+
+#[cfg(feature = "openai-embeddings")]
+mod openai;
+
+#[cfg(all(
+    test,
+    any(feature = "ollama-embeddings", feature = "openai-embeddings")
+))]
+#[allow(clippy::unwrap_used)]
+mod mock_http;
+
 pub use fallback::FallbackEmbedder;
 
 #[cfg(feature = "fastembed-embeddings")]
 pub use fastembed_impl::FastEmbedEmbedder;
 
+#[cfg(feature = "ollama-embeddings")]
+pub use ollama::{
+    DEFAULT_OLLAMA_MODEL, DEFAULT_OLLAMA_URL, OLLAMA_MODEL_ENV, OLLAMA_URL_ENV, OllamaEmbedder,
+};
+
+#[cfg(feature = "openai-embeddings")]
+pub use openai::{
+    DEFAULT_OPENAI_DIMENSIONS, DEFAULT_OPENAI_MODEL, DEFAULT_OPENAI_URL, OPENAI_API_KEY_ENV,
+    OPENAI_DIMENSIONS_ENV, OPENAI_MODEL_ENV, OPENAI_URL_ENV, OpenAiEmbedder,
+};
+
 use crate::Result;
+use crate::error::StorageError;
 
 /// Default embedding dimensions for the BGE-M3 model.
 ///
@@ -26,6 +54,12 @@ use crate::Result;
 /// All vector backends should use this constant for consistency.
 pub const DEFAULT_DIMENSIONS: usize = 1024;
 
+/// Default number of chunk texts sent to the embedder per batch.
+///
+/// Bounds request payloads for remote backends and peak memory for local
+/// models; each batch is stored before the next one is embedded.
+pub const DEFAULT_EMBED_BATCH_SIZE: usize = 64;
+
 /// Trait for embedding generators.
 ///
 /// Implementations must be thread-safe (`Send + Sync`) to support parallel
@@ -71,28 +105,85 @@ pub trait Embedder: Send + Sync {
 
 /// Creates the default embedder based on available features.
 ///
-/// - With `fastembed-embeddings`: Returns `FastEmbedEmbedder`
-/// - Without: Returns `FallbackEmbedder`
+/// - With `openai-embeddings`: Returns `OpenAiEmbedder` if `OPENAI_API_KEY`
+///   is set
+/// - Otherwise, with `ollama-embeddings`: Returns `OllamaEmbedder` if the
+///   server configured by `RLM_OLLAMA_URL` / `RLM_OLLAMA_MODEL` is reachable
+/// - Otherwise, with `fastembed-embeddings`: Returns `FastEmbedEmbedder`
+/// - Otherwise: Returns `FallbackEmbedder`
 ///
 /// # Errors
 ///
-/// Returns an error if embedder initialization fails.
-#[cfg(feature = "fastembed-embeddings")]
+/// Returns an error if embedder initialization fails, e.g. an invalid
+/// `RLM_OPENAI_DIMENSIONS` (never fails for fallback).
 pub fn create_embedder() -> Result<Box<dyn Embedder>> {
-    Ok(Box::new(FastEmbedEmbedder::new()?))
+    create_embedder_with_dimensions(None)
 }
 
-/// Creates the default embedder based on available features.
+/// Creates the default embedder with an optional dimension override.
 ///
-/// - With `fastembed-embeddings`: Returns `FastEmbedEmbedder`
-/// - Without: Returns `FallbackEmbedder`
+/// Backend selection follows [`create_embedder`]. With `Some(dimensions)`,
+/// the fallback embedder is sized accordingly and OpenAI-compatible
+/// endpoints are asked for that many dimensions; backends with a fixed size
+/// (Ollama, fastembed) must already produce it.
 ///
 /// # Errors
 ///
-/// Returns an error if embedder initialization fails (never fails for fallback).
+/// Returns an error if embedder initialization fails, or if the selected
+/// backend cannot produce the requested dimensions.
+pub fn create_embedder_with_dimensions(dimensions: Option<usize>) -> Result<Box<dyn Embedder>> {
+    #[cfg(feature = "openai-embeddings")]
+    if std::env::var_os(OPENAI_API_KEY_ENV).is_some_and(|key| !key.is_empty()) {
+        let mut embedder = OpenAiEmbedder::from_env()?;
+        if let Some(dimensions) = dimensions {
+            embedder = embedder.with_dimensions(dimensions);
+        }
+        return Ok(Box::new(embedder));
+    }
+
+    #[cfg(feature = "ollama-embeddings")]
+    if let Ok(embedder) = OllamaEmbedder::from_env() {
+        return check_dimensions(Box::new(embedder), dimensions);
+    }
+
+    create_local_embedder(dimensions)
+}
+
+/// Creates the in-process embedder.
+#[cfg(feature = "fastembed-embeddings")]
+fn create_local_embedder(dimensions: Option<usize>) -> Result<Box<dyn Embedder>> {
+    check_dimensions(Box::new(FastEmbedEmbedder::new()?), dimensions)
+}
+
+/// Creates the in-process embedder.
 #[cfg(not(feature = "fastembed-embeddings"))]
-pub fn create_embedder() -> Result<Box<dyn Embedder>> {
-    Ok(Box::new(FallbackEmbedder::new(DEFAULT_DIMENSIONS)))
+#[allow(clippy::unnecessary_wraps)]
+fn create_local_embedder(dimensions: Option<usize>) -> Result<Box<dyn Embedder>> {
+    Ok(Box::new(FallbackEmbedder::new(
+        dimensions.unwrap_or(DEFAULT_DIMENSIONS),
+    )))
+}
+
+/// Rejects an embedder whose fixed size differs from the requested one.
+#[cfg_attr(
+    not(any(feature = "ollama-embeddings", feature = "fastembed-embeddings")),
+    allow(dead_code)
+)]
+fn check_dimensions(
+    embedder: Box<dyn Embedder>,
+    dimensions: Option<usize>,
+) -> Result<Box<dyn Embedder>> {
+    match dimensions {
+        Some(expected) if embedder.dimensions() != expected => {
+            Err(StorageError::Embedding(format!(
+                "{} produces {} dimensions, expected {expected}",
+                embedder.model_name(),
+                embedder.dimensions()
+            ))
+            .into())
+        }
+        _ => Ok(embedder),
+    }
 }
 
 /// Computes cosine similarity between two embedding vectors.
@@ -120,10 +211,151 @@ pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
     dot / (mag_a * mag_b)
 }
 
+/// Computes the Euclidean (L2) distance between two embedding vectors.
+///
+/// Returns 0.0 if vectors have different lengths, like [`cosine_similarity`].
+///
+/// # Examples
+///
+/// ```
+/// use rlm_rs::embedding::euclidean_distance;
+///
+/// assert_eq!(euclidean_distance(&[0.0, 0.0], &[3.0, 4.0]), 5.0);
+/// ```
+#[must_use]
+pub fn euclidean_distance(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| (x - y) * (x - y))
+        .sum::<f32>()
+        .sqrt()
+}
+
+/// How embedding vectors are compared in semantic search.
+///
+/// Each metric scores higher for more similar vectors, but on its own
+/// scale, so a similarity threshold means something different for each.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SimilarityMetric {
+    /// Cosine of the angle between vectors, in `[-1, 1]`.
+    #[default]
+    Cosine,
+    /// Dot product, unbounded; equals cosine for normalized vectors and
+    /// also rewards magnitude for unnormalized ones.
+    Dot,
+    /// `1 / (1 + d)` for Euclidean distance `d`, in `(0, 1]`; 1 means
+    /// identical vectors.
+    EuclideanInverse,
+}
+
+impl SimilarityMetric {
+    /// Parses a metric name ("cosine", "dot", "euclidean").
+    ///
+    /// Returns `None` for unrecognized names.
+    #[must_use]
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "cosine" => Some(Self::Cosine),
+            "dot" => Some(Self::Dot),
+            "euclidean" | "l2" => Some(Self::EuclideanInverse),
+            _ => None,
+        }
+    }
+
+    /// Returns the metric name accepted by [`parse`](Self::parse).
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Cosine => "cosine",
+            Self::Dot => "dot",
+            Self::EuclideanInverse => "euclidean",
+        }
+    }
+}
+
+/// Scores two embedding vectors with `metric`; higher is more similar.
+///
+/// Returns 0.0 if vectors have different lengths, like [`cosine_similarity`].
+///
+/// # Examples
+///
+/// ```
+/// use rlm_rs::embedding::{SimilarityMetric, similarity};
+///
+/// let a = [3.0, 4.0];
+/// assert_eq!(similarity(SimilarityMetric::Dot, &a, &a), 25.0);
+/// assert_eq!(similarity(SimilarityMetric::EuclideanInverse, &a, &a), 1.0);
+/// ```
+#[must_use]
+pub fn similarity(metric: SimilarityMetric, a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+
+    match metric {
+        SimilarityMetric::Cosine => cosine_similarity(a, b),
+        SimilarityMetric::Dot => a.iter().zip(b).map(|(x, y)| x * y).sum(),
+        SimilarityMetric::EuclideanInverse => 1.0 / (1.0 + euclidean_distance(a, b)),
+    }
+}
+
+/// Tolerance within which a vector's L2 norm counts as 1.
+const UNIT_NORM_TOLERANCE: f32 = 1e-4;
+
+/// Scales an embedding vector to unit L2 length.
+///
+/// Zero vectors are returned unchanged. The dot product of two normalized
+/// vectors equals their cosine similarity, without the magnitude work.
+///
+/// # Examples
+///
+/// ```
+/// use rlm_rs::embedding::normalize_embedding;
+///
+/// assert_eq!(normalize_embedding(&[3.0, 4.0]), vec![0.6, 0.8]);
+/// ```
+#[must_use]
+pub fn normalize_embedding(embedding: &[f32]) -> Vec<f32> {
+    let norm = embedding.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        embedding.to_vec()
+    } else {
+        embedding.iter().map(|v| v / norm).collect()
+    }
+}
+
+/// Returns `true` if an embedding vector has unit L2 length (within 1e-4).
+#[must_use]
+pub fn is_normalized(embedding: &[f32]) -> bool {
+    let norm = embedding.iter().map(|v| v * v).sum::<f32>().sqrt();
+    (norm - 1.0).abs() < UNIT_NORM_TOLERANCE
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_normalize_embedding() {
+        let a = [3.0, -4.0, 12.0];
+        let b = [0.5, 2.0, -1.5];
+        let (unit_a, unit_b) = (normalize_embedding(&a), normalize_embedding(&b));
+        assert!(!is_normalized(&a));
+        assert!(is_normalized(&unit_a) && is_normalized(&unit_b));
+
+        // Dot product of normalized vectors equals cosine of the originals
+        let dot = similarity(SimilarityMetric::Dot, &unit_a, &unit_b);
+        assert!((dot - cosine_similarity(&a, &b)).abs() < 1e-6);
+
+        // Zero vectors can't be scaled and stay as they are
+        assert_eq!(normalize_embedding(&[0.0, 0.0]), vec![0.0, 0.0]);
+        assert!(!is_normalized(&[0.0, 0.0]));
+    }
+
     #[test]
     fn test_cosine_similarity_identical() {
         let a = vec![1.0, 0.0, 0.0];
@@ -140,6 +372,46 @@ mod tests {
         assert!(sim.abs() < 1e-6);
     }
 
+    #[test]
+    fn test_euclidean_distance() {
+        assert!((euclidean_distance(&[1.0, 0.0], &[0.0, 1.0]) - 2.0_f32.sqrt()).abs() < 1e-6);
+        assert!(euclidean_distance(&[0.5, 0.5], &[0.5, 0.5]).abs() < 1e-6);
+        assert!(euclidean_distance(&[1.0], &[1.0, 2.0]).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_similarity_metrics() {
+        let a = [1.0, 0.0];
+        let scaled = [3.0, 0.0];
+        let near = [0.0, 1.0];
+
+        // Cosine ignores magnitude; dot rewards it
+        assert!((similarity(SimilarityMetric::Cosine, &a, &scaled) - 1.0).abs() < 1e-6);
+        assert!((similarity(SimilarityMetric::Dot, &a, &scaled) - 3.0).abs() < 1e-6);
+        assert!(similarity(SimilarityMetric::Dot, &a, &near).abs() < 1e-6);
+
+        // Euclidean inverse is 1 for identical vectors, falling with distance
+        let euclidean = |x: &[f32], y: &[f32]| similarity(SimilarityMetric::EuclideanInverse, x, y);
+        assert!((euclidean(&a, &a) - 1.0).abs() < 1e-6);
+        assert!((euclidean(&a, &scaled) - 1.0 / 3.0).abs() < 1e-6);
+        assert!(euclidean(&a, &near) > euclidean(&a, &scaled));
+
+        for metric in [
+            SimilarityMetric::Cosine,
+            SimilarityMetric::Dot,
+            SimilarityMetric::EuclideanInverse,
+        ] {
+            assert_eq!(SimilarityMetric::parse(metric.as_str()), Some(metric));
+            assert!(similarity(metric, &a, &[1.0]).abs() < 1e-6);
+        }
+        assert_eq!(
+            SimilarityMetric::parse("L2"),
+            Some(SimilarityMetric::EuclideanInverse)
+        );
+        assert_eq!(SimilarityMetric::parse("manhattan"), None);
+        assert_eq!(SimilarityMetric::default(), SimilarityMetric::Cosine);
+    }
+
     #[test]
     fn test_cosine_similarity_opposite() {
         let a = vec![1.0, 0.0, 0.0];
@@ -170,6 +442,21 @@ mod tests {
         assert_eq!(embedder.dimensions(), DEFAULT_DIMENSIONS);
     }
 
+    #[test]
+    fn test_check_dimensions() {
+        let embedder = check_dimensions(Box::new(FallbackEmbedder::new(8)), Some(8)).unwrap();
+        assert_eq!(embedder.dimensions(), 8);
+        assert!(check_dimensions(Box::new(FallbackEmbedder::new(8)), None).is_ok());
+
+        let err = check_dimensions(Box::new(FallbackEmbedder::new(8)), Some(16))
+            .err()
+            .unwrap();
+        assert!(
+            err.to_string()
+                .contains("produces 8 dimensions, expected 16")
+        );
+    }
+
     #[test]
     fn test_embed_batch_default_impl() {
         // Test the default embed_batch implementation (lines 62-63)
@@ -0,0 +1,220 @@
+//! Ollama-backed semantic embedder.
+//!
+//! Sends texts to a local Ollama server's `/api/embeddings` endpoint, so
+//! semantic search works without downloading ONNX weights. Only available
+//! when the `ollama-embeddings` feature is enabled.
+
+use std::time::Duration;
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+use crate::embedding::Embedder;
+use crate::error::{ChunkingError, StorageError};
+
+/// Default Ollama server URL.
+pub const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434";
+
+/// Default Ollama embedding model.
+pub const DEFAULT_OLLAMA_MODEL: &str = "nomic-embed-text";
+
+/// Environment variable overriding the Ollama server URL.
+pub const OLLAMA_URL_ENV: &str = "RLM_OLLAMA_URL";
+
+/// Environment variable overriding the Ollama embedding model.
+pub const OLLAMA_MODEL_ENV: &str = "RLM_OLLAMA_MODEL";
+
+/// How long to wait for a connection before treating the server as down.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long a single embedding request may take.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Text used to probe the server and learn the model's dimensions.
+const PROBE_TEXT: &str = "dimension probe";
+
+#[derive(Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    prompt: &'a str,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    embedding: Vec<f32>,
+}
+
+/// Embedder backed by an Ollama server.
+///
+/// Construction probes the server once to check it is reachable and to
+/// learn the model's embedding dimensions. Batches are embedded with
+/// concurrent requests on the rayon pool.
+///
+/// # Examples
+///
+/// ```no_run
+/// use rlm_rs::embedding::{Embedder, OllamaEmbedder};
+///
+/// let embedder = OllamaEmbedder::connect("http://localhost:11434", "nomic-embed-text")?;
+/// let embedding = embedder.embed("Hello, world!")?;
+/// assert_eq!(embedding.len(), embedder.dimensions());
+/// # Ok::<(), rlm_rs::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct OllamaEmbedder {
+    agent: ureq::Agent,
+    endpoint: String,
+    model: String,
+    /// `ollama/<model>`, stored with embeddings to detect model changes.
+    model_name: &'static str,
+    dimensions: usize,
+}
+
+impl OllamaEmbedder {
+    /// Connects to an Ollama server and probes the model.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the server is unreachable or the model returns
+    /// no embedding (e.g. it is not pulled or not an embedding model).
+    pub fn connect(base_url: &str, model: &str) -> Result<Self> {
+        let agent = ureq::AgentBuilder::new()
+            .timeout_connect(CONNECT_TIMEOUT)
+            .timeout(REQUEST_TIMEOUT)
+            .build();
+
+        let mut embedder = Self {
+            agent,
+            endpoint: format!("{}/api/embeddings", base_url.trim_end_matches('/')),
+            model: model.to_string(),
+            // One embedder is created per process, so leaking the name is
+            // bounded and lets it satisfy `Embedder::model_name`
+            model_name: Box::leak(format!("ollama/{model}").into_boxed_str()),
+            dimensions: 0,
+        };
+
+        let probe = embedder.request(PROBE_TEXT)?;
+        if probe.is_empty() {
+            return Err(StorageError::Embedding(format!(
+                "Ollama model '{model}' returned an empty embedding"
+            ))
+            .into());
+        }
+        embedder.dimensions = probe.len();
+
+        Ok(embedder)
+    }
+
+    /// Connects using [`OLLAMA_URL_ENV`] and [`OLLAMA_MODEL_ENV`], falling
+    /// back to [`DEFAULT_OLLAMA_URL`] and [`DEFAULT_OLLAMA_MODEL`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the server is unreachable or the model is unusable.
+    pub fn from_env() -> Result<Self> {
+        let base_url =
+            std::env::var(OLLAMA_URL_ENV).unwrap_or_else(|_| DEFAULT_OLLAMA_URL.to_string());
+        let model =
+            std::env::var(OLLAMA_MODEL_ENV).unwrap_or_else(|_| DEFAULT_OLLAMA_MODEL.to_string());
+        Self::connect(&base_url, &model)
+    }
+
+    /// Returns the Ollama model used for embeddings.
+    #[must_use]
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Sends one embedding request.
+    fn request(&self, text: &str) -> Result<Vec<f32>> {
+        let response: EmbeddingResponse = self
+            .agent
+            .post(&self.endpoint)
+            .send_json(EmbeddingRequest {
+                model: &self.model,
+                prompt: text,
+            })
+            .map_err(|e| StorageError::Embedding(format!("Ollama request failed: {e}")))?
+            .into_json()
+            .map_err(|e| StorageError::Embedding(format!("Invalid Ollama response: {e}")))?;
+        Ok(response.embedding)
+    }
+}
+
+impl Embedder for OllamaEmbedder {
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    fn model_name(&self) -> &'static str {
+        self.model_name
+    }
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        if text.is_empty() {
+            return Err(ChunkingError::InvalidConfig {
+                reason: "Cannot embed empty text".to_string(),
+            }
+            .into());
+        }
+
+        let embedding = self.request(text)?;
+        if embedding.len() != self.dimensions {
+            return Err(StorageError::Embedding(format!(
+                "Ollama returned {} dimensions, expected {}",
+                embedding.len(),
+                self.dimensions
+            ))
+            .into());
+        }
+        Ok(embedding)
+    }
+
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        // The endpoint takes one prompt per request; keep several in flight
+        texts.par_iter().map(|text| self.embed(text)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embedding::mock_http;
+
+    /// Answers each prompt with `[len(prompt), 1.0, 0.0]`.
+    fn mock_server() -> String {
+        mock_http::serve(|request| {
+            assert_eq!(request.path, "/api/embeddings");
+            let prompt = request.body["prompt"].as_str().unwrap_or_default();
+            (
+                200,
+                serde_json::json!({ "embedding": [prompt.len(), 1.0, 0.0] }),
+            )
+        })
+    }
+
+    #[test]
+    fn test_connect_probes_dimensions() {
+        let embedder = OllamaEmbedder::connect(&mock_server(), "test-model").unwrap();
+        assert_eq!(embedder.dimensions(), 3);
+        assert_eq!(embedder.model(), "test-model");
+        assert_eq!(embedder.model_name(), "ollama/test-model");
+    }
+
+    #[test]
+    fn test_embed_batch_preserves_order() {
+        let embedder = OllamaEmbedder::connect(&mock_server(), "test-model").unwrap();
+        let embeddings = embedder.embed_batch(&["a", "bbb", "cc"]).unwrap();
+        let lengths: Vec<f32> = embeddings.iter().map(|e| e[0]).collect();
+        assert_eq!(lengths, vec![1.0, 3.0, 2.0]);
+
+        assert!(embedder.embed("").is_err());
+        assert!(embedder.embed_batch(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_connect_unreachable() {
+        assert!(OllamaEmbedder::connect(&mock_http::unreachable_url(), "m").is_err());
+    }
+}
@@ -0,0 +1,326 @@
+//! OpenAI-compatible remote embedder.
+//!
+//! Sends texts to any endpoint implementing the OpenAI `/embeddings` API,
+//! e.g. `text-embedding-3-small`. Only available when the
+//! `openai-embeddings` feature is enabled.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+use crate::embedding::Embedder;
+use crate::error::{ChunkingError, StorageError};
+
+/// Default OpenAI API base URL.
+pub const DEFAULT_OPENAI_URL: &str = "https://api.openai.com/v1";
+
+/// Default OpenAI embedding model.
+pub const DEFAULT_OPENAI_MODEL: &str = "text-embedding-3-small";
+
+/// Dimensions of [`DEFAULT_OPENAI_MODEL`].
+pub const DEFAULT_OPENAI_DIMENSIONS: usize = 1536;
+
+/// Environment variable holding the API key.
+pub const OPENAI_API_KEY_ENV: &str = "OPENAI_API_KEY";
+
+/// Environment variable overriding the API base URL.
+pub const OPENAI_URL_ENV: &str = "OPENAI_BASE_URL";
+
+/// Environment variable overriding the embedding model.
+pub const OPENAI_MODEL_ENV: &str = "RLM_OPENAI_MODEL";
+
+/// Environment variable overriding the embedding dimensions.
+pub const OPENAI_DIMENSIONS_ENV: &str = "RLM_OPENAI_DIMENSIONS";
+
+/// How long a single embedding request may take.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [&'a str],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dimensions: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+/// Embedder backed by an OpenAI-compatible `/embeddings` endpoint.
+///
+/// A batch is sent as a single request. Responses whose vectors don't have
+/// [`dimensions`](Embedder::dimensions) entries are rejected, so embeddings
+/// from a misconfigured model never reach storage.
+///
+/// # Examples
+///
+/// ```no_run
+/// use rlm_rs::embedding::{Embedder, OpenAiEmbedder};
+///
+/// let embedder = OpenAiEmbedder::new("sk-...", "text-embedding-3-small").with_dimensions(512);
+/// let embeddings = embedder.embed_batch(&["first", "second"])?;
+/// assert_eq!(embeddings[0].len(), 512);
+/// # Ok::<(), rlm_rs::Error>(())
+/// ```
+pub struct OpenAiEmbedder {
+    agent: ureq::Agent,
+    endpoint: String,
+    api_key: String,
+    model: String,
+    /// `openai/<model>`, stored with embeddings to detect model changes.
+    model_name: &'static str,
+    dimensions: usize,
+    /// Whether to ask the endpoint for `dimensions` explicitly.
+    request_dimensions: bool,
+}
+
+impl OpenAiEmbedder {
+    /// Creates an embedder for `model` using [`DEFAULT_OPENAI_URL`] and
+    /// [`DEFAULT_OPENAI_DIMENSIONS`].
+    #[must_use]
+    pub fn new(api_key: &str, model: &str) -> Self {
+        Self {
+            agent: ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build(),
+            endpoint: format!("{DEFAULT_OPENAI_URL}/embeddings"),
+            api_key: api_key.to_string(),
+            model: model.to_string(),
+            // One embedder is created per process, so leaking the name is
+            // bounded and lets it satisfy `Embedder::model_name`
+            model_name: Box::leak(format!("openai/{model}").into_boxed_str()),
+            dimensions: DEFAULT_OPENAI_DIMENSIONS,
+            request_dimensions: false,
+        }
+    }
+
+    /// Sets the API base URL (e.g. a self-hosted OpenAI-compatible server).
+    #[must_use]
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.endpoint = format!("{}/embeddings", base_url.trim_end_matches('/'));
+        self
+    }
+
+    /// Sets the embedding dimensions, requesting them from the endpoint.
+    #[must_use]
+    pub const fn with_dimensions(mut self, dimensions: usize) -> Self {
+        self.dimensions = dimensions;
+        self.request_dimensions = true;
+        self
+    }
+
+    /// Creates an embedder from [`OPENAI_API_KEY_ENV`], [`OPENAI_URL_ENV`],
+    /// [`OPENAI_MODEL_ENV`], and [`OPENAI_DIMENSIONS_ENV`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the API key is not set or the dimensions are not
+    /// a positive integer.
+    pub fn from_env() -> Result<Self> {
+        let api_key = std::env::var(OPENAI_API_KEY_ENV)
+            .ok()
+            .filter(|key| !key.is_empty())
+            .ok_or_else(|| StorageError::Embedding(format!("{OPENAI_API_KEY_ENV} is not set")))?;
+        let model =
+            std::env::var(OPENAI_MODEL_ENV).unwrap_or_else(|_| DEFAULT_OPENAI_MODEL.to_string());
+
+        let mut embedder = Self::new(&api_key, &model);
+        if let Ok(base_url) = std::env::var(OPENAI_URL_ENV) {
+            embedder = embedder.with_base_url(&base_url);
+        }
+        if let Ok(dimensions) = std::env::var(OPENAI_DIMENSIONS_ENV) {
+            let dimensions = dimensions
+                .parse()
+                .ok()
+                .filter(|&d: &usize| d > 0)
+                .ok_or_else(|| {
+                    StorageError::Embedding(format!(
+                        "{OPENAI_DIMENSIONS_ENV} must be a positive integer, got '{dimensions}'"
+                    ))
+                })?;
+            embedder = embedder.with_dimensions(dimensions);
+        }
+        Ok(embedder)
+    }
+
+    /// Returns the model used for embeddings.
+    #[must_use]
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Maps a failed request to an embedding error, including the API's
+    /// error message when the response carries one.
+    fn request_error(error: ureq::Error) -> StorageError {
+        match error {
+            ureq::Error::Status(status, response) => {
+                let message = response
+                    .into_json::<serde_json::Value>()
+                    .ok()
+                    .and_then(|body| body["error"]["message"].as_str().map(String::from))
+                    .unwrap_or_else(|| "no error message".to_string());
+                StorageError::Embedding(format!("OpenAI request failed ({status}): {message}"))
+            }
+            ureq::Error::Transport(transport) => {
+                StorageError::Embedding(format!("OpenAI request failed: {transport}"))
+            }
+        }
+    }
+}
+
+impl Embedder for OpenAiEmbedder {
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    fn model_name(&self) -> &'static str {
+        self.model_name
+    }
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        self.embed_batch(&[text])?.pop().ok_or_else(|| {
+            StorageError::Embedding("No embedding returned from OpenAI".to_string()).into()
+        })
+    }
+
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        if texts.iter().any(|t| t.is_empty()) {
+            return Err(ChunkingError::InvalidConfig {
+                reason: "Cannot embed empty text".to_string(),
+            }
+            .into());
+        }
+
+        let response: EmbeddingResponse = self
+            .agent
+            .post(&self.endpoint)
+            .set("Authorization", &format!("Bearer {}", self.api_key))
+            .send_json(EmbeddingRequest {
+                model: &self.model,
+                input: texts,
+                dimensions: self.request_dimensions.then_some(self.dimensions),
+            })
+            .map_err(Self::request_error)?
+            .into_json()
+            .map_err(|e| StorageError::Embedding(format!("Invalid OpenAI response: {e}")))?;
+
+        if response.data.len() != texts.len() {
+            return Err(StorageError::Embedding(format!(
+                "OpenAI returned {} embeddings for {} texts",
+                response.data.len(),
+                texts.len()
+            ))
+            .into());
+        }
+
+        // Results carry their input index and need not arrive in order
+        let mut data = response.data;
+        data.sort_by_key(|d| d.index);
+
+        data.into_iter()
+            .map(|d| {
+                if d.embedding.len() == self.dimensions {
+                    Ok(d.embedding)
+                } else {
+                    Err(StorageError::Embedding(format!(
+                        "OpenAI returned {} dimensions, expected {}",
+                        d.embedding.len(),
+                        self.dimensions
+                    ))
+                    .into())
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embedding::mock_http;
+
+    /// Answers each input with `[len(input), 1.0, 0.0]`, in reverse order,
+    /// and rejects requests without the expected key.
+    fn mock_server() -> String {
+        mock_http::serve(|request| {
+            assert_eq!(request.path, "/v1/embeddings");
+            if request.header("authorization") != Some("Bearer test-key") {
+                return (
+                    401,
+                    serde_json::json!({ "error": { "message": "Incorrect API key" } }),
+                );
+            }
+            let inputs = request.body["input"]
+                .as_array()
+                .cloned()
+                .unwrap_or_default();
+            let data: Vec<_> = inputs
+                .iter()
+                .enumerate()
+                .rev()
+                .map(|(index, input)| {
+                    let len = input.as_str().unwrap_or_default().len();
+                    serde_json::json!({ "index": index, "embedding": [len, 1.0, 0.0] })
+                })
+                .collect();
+            (200, serde_json::json!({ "data": data }))
+        })
+    }
+
+    fn embedder(url: &str, key: &str) -> OpenAiEmbedder {
+        OpenAiEmbedder::new(key, "test-model")
+            .with_base_url(&format!("{url}/v1"))
+            .with_dimensions(3)
+    }
+
+    #[test]
+    fn test_embed_batch_single_request_in_order() {
+        let embedder = embedder(&mock_server(), "test-key");
+        assert_eq!(embedder.dimensions(), 3);
+        assert_eq!(embedder.model_name(), "openai/test-model");
+
+        let embeddings = embedder.embed_batch(&["a", "bbb", "cc"]).unwrap();
+        let lengths: Vec<f32> = embeddings.iter().map(|e| e[0]).collect();
+        assert_eq!(lengths, vec![1.0, 3.0, 2.0]);
+
+        assert!((embedder.embed("abcd").unwrap()[0] - 4.0).abs() < 1e-6);
+        assert!(embedder.embed("").is_err());
+        assert!(embedder.embed_batch(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_dimension_mismatch_is_error() {
+        let embedder = OpenAiEmbedder::new("test-key", "test-model")
+            .with_base_url(&format!("{}/v1", mock_server()));
+        assert_eq!(embedder.dimensions(), DEFAULT_OPENAI_DIMENSIONS);
+        assert!(embedder.embed("text").is_err());
+    }
+
+    #[test]
+    fn test_api_error_message() {
+        let err = embedder(&mock_server(), "wrong-key")
+            .embed("text")
+            .unwrap_err();
+        assert!(err.to_string().contains("Incorrect API key"));
+    }
+
+    #[test]
+    fn test_unreachable() {
+        assert!(
+            embedder(&mock_http::unreachable_url(), "test-key")
+                .embed("text")
+                .is_err()
+        );
+    }
+}
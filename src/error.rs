@@ -44,6 +44,17 @@ pub enum Error {
         /// Description of the configuration error.
         message: String,
     },
+
+    /// The linked `SQLite` library was built without the FTS5 extension.
+    #[error(
+        "SQLite FTS5 extension is not available ({reason}). rlm-rs requires a SQLite build \
+         with FTS5 enabled (SQLITE_ENABLE_FTS5); build with the bundled SQLite or link \
+         against a system SQLite compiled with FTS5"
+    )]
+    Fts5Unavailable {
+        /// Underlying `SQLite` error from the availability probe.
+        reason: String,
+    },
 }
 
 /// Storage-specific errors for database operations.
@@ -75,10 +86,37 @@ pub enum StorageError {
         id: i64,
     },
 
+    /// Another buffer already uses the name.
+    #[error("buffer name already in use: {name}")]
+    BufferNameTaken {
+        /// The conflicting name.
+        name: String,
+    },
+
+    /// Full-text query rejected by FTS5 (raw query mode).
+    #[error("invalid full-text query '{query}': {reason}")]
+    InvalidQuery {
+        /// The query as given.
+        query: String,
+        /// The FTS5 error message.
+        reason: String,
+    },
+
     /// Schema migration error.
     #[error("migration error: {0}")]
     Migration(String),
 
+    /// Database schema is newer than this build supports.
+    #[error(
+        "database schema v{found} was created by a newer rlm-rs (this version supports up to v{supported}); upgrade the tool"
+    )]
+    SchemaTooNew {
+        /// Schema version stored in the database.
+        found: u32,
+        /// Newest schema version this build understands.
+        supported: u32,
+    },
+
     /// Transaction error.
     #[error("transaction error: {0}")]
     Transaction(String),
@@ -88,12 +126,11 @@ pub enum StorageError {
     Serialization(String),
 
     /// Vector search error (feature-gated).
-    #[cfg(feature = "usearch-hnsw")]
+    #[cfg(any(feature = "usearch-hnsw", feature = "sqlite-vec"))]
     #[error("vector search error: {0}")]
     VectorSearch(String),
 
-    /// Embedding error (feature-gated).
-    #[cfg(feature = "fastembed-embeddings")]
+    /// Embedding error (model failure or dimension mismatch).
     #[error("embedding error: {0}")]
     Embedding(String),
 }
@@ -345,6 +382,12 @@ mod tests {
             identifier: "test-buffer".to_string(),
         };
         assert_eq!(err.to_string(), "buffer not found: test-buffer");
+
+        let err = StorageError::SchemaTooNew {
+            found: 99,
+            supported: 12,
+        };
+        assert!(err.to_string().contains("created by a newer rlm-rs"));
     }
 
     #[test]
@@ -412,6 +455,17 @@ mod tests {
         assert_eq!(err.to_string(), "configuration error: bad config");
     }
 
+    #[test]
+    fn test_error_fts5_unavailable() {
+        let err = Error::Fts5Unavailable {
+            reason: "no such module: fts5".to_string(),
+        };
+        let msg = err.to_string();
+        assert!(msg.contains("FTS5 extension is not available"));
+        assert!(msg.contains("no such module: fts5"));
+        assert!(msg.contains("SQLITE_ENABLE_FTS5"));
+    }
+
     #[test]
     fn test_storage_error_variants() {
         let err = StorageError::Database("connection failed".to_string());
@@ -423,6 +477,20 @@ mod tests {
         let err = StorageError::ChunkNotFound { id: 42 };
         assert_eq!(err.to_string(), "chunk not found: 42");
 
+        let err = StorageError::BufferNameTaken {
+            name: "notes".to_string(),
+        };
+        assert_eq!(err.to_string(), "buffer name already in use: notes");
+
+        let err = StorageError::InvalidQuery {
+            query: "a AND".to_string(),
+            reason: "fts5: syntax error near \"\"".to_string(),
+        };
+        assert!(
+            err.to_string()
+                .starts_with("invalid full-text query 'a AND'")
+        );
+
         let err = StorageError::Migration("schema error".to_string());
         assert!(err.to_string().contains("schema error"));
 
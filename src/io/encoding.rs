@@ -0,0 +1,293 @@
+//! Text encoding detection and transcoding to UTF-8.
+//!
+//! Without the `encoding` feature, input must be UTF-8. With it, a byte
+//! order mark selects UTF-8, UTF-16LE, or UTF-16BE; BOM-less input is
+//! checked for UTF-16 by its NUL byte pattern, then read as UTF-8 if valid
+//! and otherwise as Windows-1252.
+
+/// Text decoded from raw file bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedText {
+    /// The content as UTF-8.
+    pub content: String,
+    /// Name of the encoding the content was transcoded from, or `None` for
+    /// plain UTF-8 without a byte order mark.
+    pub encoding: Option<&'static str>,
+}
+
+/// Decodes raw bytes into UTF-8 text.
+///
+/// # Arguments
+///
+/// * `bytes` - The raw content.
+/// * `label` - Encoding to use instead of detecting one (e.g. `utf-16le`,
+///   `windows-1252`); any WHATWG encoding label is accepted.
+///
+/// # Errors
+///
+/// Returns a description of the problem if the label is unknown or the
+/// content can't be decoded.
+///
+/// # Examples
+///
+/// ```
+/// use rlm_rs::io::decode_text;
+///
+/// let decoded = decode_text(b"plain".to_vec(), None).unwrap();
+/// assert_eq!(decoded.content, "plain");
+/// assert_eq!(decoded.encoding, None);
+/// ```
+#[cfg(feature = "encoding")]
+pub fn decode_text(
+    bytes: Vec<u8>,
+    label: Option<&str>,
+) -> std::result::Result<DecodedText, String> {
+    use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, WINDOWS_1252};
+
+    if let Some(label) = label {
+        let encoding = Encoding::for_label(label.trim().as_bytes())
+            .ok_or_else(|| format!("unknown encoding '{label}'"))?;
+        let (content, had_errors) = encoding.decode_with_bom_removal(&bytes);
+        if had_errors {
+            return Err(format!("content is not valid {}", encoding.name()));
+        }
+        return Ok(DecodedText {
+            content: content.into_owned(),
+            encoding: Some(encoding.name()),
+        });
+    }
+
+    if let Some((encoding, bom_len)) = Encoding::for_bom(&bytes) {
+        let (content, had_errors) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+        if had_errors {
+            return Err(format!(
+                "content has a {} byte order mark but is not valid {0}",
+                encoding.name()
+            ));
+        }
+        return Ok(DecodedText {
+            content: content.into_owned(),
+            encoding: Some(encoding.name()),
+        });
+    }
+
+    // ASCII text in BOM-less UTF-16 is also valid UTF-8, so sniff it first
+    let sniffed = sniff_utf16(&bytes);
+    let bytes = if sniffed.is_some() {
+        bytes
+    } else {
+        match String::from_utf8(bytes) {
+            Ok(content) => {
+                return Ok(DecodedText {
+                    content,
+                    encoding: None,
+                });
+            }
+            Err(e) => e.into_bytes(),
+        }
+    };
+
+    let encoding = match sniffed {
+        Some(Utf16::Le) => UTF_16LE,
+        Some(Utf16::Be) => UTF_16BE,
+        // Windows-1252 maps every byte, so only binary data is rejected
+        None if bytes.contains(&0) => {
+            return Err("content is binary or in an unrecognized encoding".to_string());
+        }
+        None => WINDOWS_1252,
+    };
+    let (content, had_errors) = encoding.decode_without_bom_handling(&bytes);
+    if had_errors {
+        return Err(format!(
+            "content looks like {} but is invalid",
+            encoding.name()
+        ));
+    }
+    Ok(DecodedText {
+        content: content.into_owned(),
+        encoding: Some(encoding.name()),
+    })
+}
+
+/// Decodes raw bytes as strict UTF-8.
+///
+/// Only UTF-8 labels are accepted; other encodings require the `encoding`
+/// feature.
+///
+/// # Errors
+///
+/// Returns a description of the problem if the label isn't UTF-8 or the
+/// content isn't valid UTF-8.
+///
+/// # Examples
+///
+/// ```
+/// use rlm_rs::io::decode_text;
+///
+/// let decoded = decode_text(b"plain".to_vec(), None).unwrap();
+/// assert_eq!(decoded.content, "plain");
+/// assert_eq!(decoded.encoding, None);
+/// ```
+#[cfg(not(feature = "encoding"))]
+pub fn decode_text(
+    bytes: Vec<u8>,
+    label: Option<&str>,
+) -> std::result::Result<DecodedText, String> {
+    if let Some(label) = label
+        && !matches!(label.trim().to_ascii_lowercase().as_str(), "utf-8" | "utf8")
+    {
+        return Err(format!(
+            "encoding '{label}' requires the `encoding` feature; only UTF-8 is supported"
+        ));
+    }
+
+    let utf16_bom = match bytes.get(..2) {
+        Some([0xff, 0xfe]) => Some("UTF-16LE"),
+        Some([0xfe, 0xff]) => Some("UTF-16BE"),
+        _ => None,
+    };
+    String::from_utf8(bytes)
+        .map(|content| DecodedText {
+            content,
+            encoding: None,
+        })
+        .map_err(|e| {
+            let offset = e.utf8_error().valid_up_to();
+            utf16_bom.map_or_else(
+                || format!("invalid UTF-8 at byte {offset}"),
+                |name| {
+                    format!(
+                        "invalid UTF-8 at byte {offset}: content has a {name} byte order mark; \
+                         rebuild with the `encoding` feature to transcode it"
+                    )
+                },
+            )
+        })
+}
+
+/// UTF-16 byte order.
+#[cfg(feature = "encoding")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Utf16 {
+    Le,
+    Be,
+}
+
+/// Guesses whether BOM-less content is UTF-16 from where its NUL bytes fall.
+///
+/// Mostly-ASCII UTF-16 text has a NUL high byte in nearly every code unit,
+/// so NULs cluster at odd offsets (little-endian) or even offsets
+/// (big-endian). Samples at most the first 4 KB.
+#[cfg(feature = "encoding")]
+fn sniff_utf16(bytes: &[u8]) -> Option<Utf16> {
+    const SAMPLE_LEN: usize = 4096;
+
+    if bytes.len() < 2 || !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+    let sample = &bytes[..bytes.len().min(SAMPLE_LEN)];
+    let units = sample.len() / 2;
+    let (mut even_nuls, mut odd_nuls) = (0, 0);
+    for pair in sample.chunks_exact(2) {
+        even_nuls += usize::from(pair[0] == 0);
+        odd_nuls += usize::from(pair[1] == 0);
+    }
+
+    // At least 40% of code units with a NUL on one side and almost none on the other
+    let dominant = |nuls: usize| nuls * 5 >= units * 2;
+    let rare = |nuls: usize| nuls * 20 <= units;
+    if dominant(odd_nuls) && rare(even_nuls) {
+        Some(Utf16::Le)
+    } else if dominant(even_nuls) && rare(odd_nuls) {
+        Some(Utf16::Be)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_plain_utf8() {
+        let decoded = decode_text("héllo".as_bytes().to_vec(), None).unwrap();
+        assert_eq!(decoded.content, "héllo");
+        assert_eq!(decoded.encoding, None);
+
+        let decoded = decode_text(b"abc".to_vec(), Some("UTF-8")).unwrap();
+        assert_eq!(decoded.content, "abc");
+    }
+
+    #[cfg(not(feature = "encoding"))]
+    #[test]
+    fn test_decode_strict_without_feature() {
+        let err = decode_text(vec![b'o', b'k', 0xff], None).unwrap_err();
+        assert!(err.contains("at byte 2"));
+
+        let err = decode_text(vec![0xff, 0xfe, b'a', 0], None).unwrap_err();
+        assert!(err.contains("UTF-16LE byte order mark"));
+
+        let err = decode_text(b"abc".to_vec(), Some("windows-1252")).unwrap_err();
+        assert!(err.contains("`encoding` feature"));
+    }
+
+    #[cfg(feature = "encoding")]
+    fn utf16(text: &str, little_endian: bool, bom: bool) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let units = bom.then_some(0xfeff).into_iter().chain(text.encode_utf16());
+        for unit in units {
+            let pair = if little_endian {
+                unit.to_le_bytes()
+            } else {
+                unit.to_be_bytes()
+            };
+            bytes.extend_from_slice(&pair);
+        }
+        bytes
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn test_decode_utf16_bom() {
+        let decoded = decode_text(utf16("Grüße, 世界", true, true), None).unwrap();
+        assert_eq!(decoded.content, "Grüße, 世界");
+        assert_eq!(decoded.encoding, Some("UTF-16LE"));
+
+        let decoded = decode_text(utf16("Grüße, 世界", false, true), None).unwrap();
+        assert_eq!(decoded.content, "Grüße, 世界");
+        assert_eq!(decoded.encoding, Some("UTF-16BE"));
+
+        // A UTF-8 BOM is stripped and recorded
+        let decoded = decode_text(b"\xef\xbb\xbfabc".to_vec(), None).unwrap();
+        assert_eq!(decoded.content, "abc");
+        assert_eq!(decoded.encoding, Some("UTF-8"));
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn test_decode_sniffed_and_forced() {
+        let decoded = decode_text(utf16("plain ascii text", true, false), None).unwrap();
+        assert_eq!(decoded.content, "plain ascii text");
+        assert_eq!(decoded.encoding, Some("UTF-16LE"));
+
+        let decoded = decode_text(utf16("plain ascii text", false, false), None).unwrap();
+        assert_eq!(decoded.encoding, Some("UTF-16BE"));
+
+        // 0xe9 is é in Windows-1252 but invalid on its own in UTF-8
+        let decoded = decode_text(b"caf\xe9".to_vec(), None).unwrap();
+        assert_eq!(decoded.content, "café");
+        assert_eq!(decoded.encoding, Some("windows-1252"));
+
+        let decoded = decode_text(b"caf\xe9".to_vec(), Some("latin1")).unwrap();
+        assert_eq!(decoded.content, "café");
+        assert_eq!(decoded.encoding, Some("windows-1252"));
+
+        // A forced UTF-16 encoding strips its own BOM
+        let decoded = decode_text(utf16("hi", false, true), Some("utf-16be")).unwrap();
+        assert_eq!(decoded.content, "hi");
+
+        assert!(decode_text(b"abc".to_vec(), Some("klingon")).is_err());
+        assert!(decode_text(vec![0xff, 0x00, 0x00, 0x01, 0x00], None).is_err());
+    }
+}
@@ -0,0 +1,406 @@
+//! Plain-text extraction from rich document formats.
+//!
+//! `load` runs documents through a [`TextExtractor`] picked by content type
+//! before chunking, since raw markup and binary formats chunk poorly. HTML
+//! extraction needs the `extract-html` feature and PDF extraction the
+//! `extract-pdf` feature; without them, and for any other content type,
+//! content is loaded unchanged.
+
+use super::encoding::DecodedText;
+
+/// Converts a document format to plain text.
+pub trait TextExtractor {
+    /// Name of the format handled (e.g. `html`).
+    fn name(&self) -> &'static str;
+
+    /// Extracts the text of a document.
+    ///
+    /// `encoding` is the `--encoding` label, for text-based formats.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the problem if the document can't be read.
+    fn extract(
+        &self,
+        bytes: Vec<u8>,
+        encoding: Option<&str>,
+    ) -> std::result::Result<DecodedText, String>;
+}
+
+/// Returns the extractor for a content type (a file extension such as
+/// `html` or `pdf`), or `None` if content of that type is loaded as-is.
+///
+/// # Examples
+///
+/// ```
+/// use rlm_rs::io::create_extractor;
+///
+/// assert!(create_extractor("txt").is_none());
+/// ```
+#[must_use]
+#[cfg_attr(
+    not(any(feature = "extract-html", feature = "extract-pdf")),
+    allow(unused_variables, clippy::missing_const_for_fn)
+)]
+pub fn create_extractor(content_type: &str) -> Option<Box<dyn TextExtractor>> {
+    #[cfg(feature = "extract-html")]
+    if ["html", "htm", "xhtml"]
+        .iter()
+        .any(|html| content_type.eq_ignore_ascii_case(html))
+    {
+        return Some(Box::new(HtmlExtractor));
+    }
+
+    #[cfg(feature = "extract-pdf")]
+    if content_type.eq_ignore_ascii_case("pdf") {
+        return Some(Box::new(PdfExtractor));
+    }
+
+    None
+}
+
+/// Extracts the text of HTML documents.
+///
+/// Drops scripts, styles, and comments, and keeps the document's shape:
+/// headings become `#`-prefixed lines, list items `- ` lines, block
+/// elements start new lines or paragraphs, and `<pre>` keeps its
+/// whitespace. Entities are decoded.
+#[cfg(feature = "extract-html")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HtmlExtractor;
+
+#[cfg(feature = "extract-html")]
+impl TextExtractor for HtmlExtractor {
+    fn name(&self) -> &'static str {
+        "html"
+    }
+
+    fn extract(
+        &self,
+        bytes: Vec<u8>,
+        encoding: Option<&str>,
+    ) -> std::result::Result<DecodedText, String> {
+        let decoded = super::decode_text(bytes, encoding)?;
+        Ok(DecodedText {
+            content: html_to_text(&decoded.content),
+            encoding: decoded.encoding,
+        })
+    }
+}
+
+/// Extracts the text of PDF documents.
+#[cfg(feature = "extract-pdf")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PdfExtractor;
+
+#[cfg(feature = "extract-pdf")]
+impl TextExtractor for PdfExtractor {
+    fn name(&self) -> &'static str {
+        "pdf"
+    }
+
+    fn extract(
+        &self,
+        bytes: Vec<u8>,
+        _encoding: Option<&str>,
+    ) -> std::result::Result<DecodedText, String> {
+        let content = pdf_extract::extract_text_from_mem(&bytes)
+            .map_err(|e| format!("cannot extract PDF text: {e}"))?;
+        Ok(DecodedText {
+            content: content.trim().to_string(),
+            encoding: None,
+        })
+    }
+}
+
+/// Elements whose content is dropped.
+#[cfg(feature = "extract-html")]
+const SKIPPED_ELEMENTS: &[&str] = &["script", "style", "noscript", "template", "svg"];
+
+/// Elements that start and end a paragraph.
+#[cfg(feature = "extract-html")]
+const PARAGRAPH_ELEMENTS: &[&str] = &[
+    "p",
+    "blockquote",
+    "table",
+    "ul",
+    "ol",
+    "dl",
+    "hr",
+    "section",
+    "article",
+];
+
+/// Elements that start and end a line.
+#[cfg(feature = "extract-html")]
+const LINE_ELEMENTS: &[&str] = &[
+    "div",
+    "tr",
+    "dt",
+    "dd",
+    "title",
+    "header",
+    "footer",
+    "nav",
+    "main",
+    "aside",
+    "figure",
+    "figcaption",
+    "form",
+    "details",
+    "summary",
+    "address",
+];
+
+/// Converts HTML to plain text; see [`HtmlExtractor`].
+#[cfg(feature = "extract-html")]
+fn html_to_text(html: &str) -> String {
+    let mut out = String::with_capacity(html.len() / 2);
+    let mut skipping: Option<String> = None;
+    let mut pre_depth = 0usize;
+    let mut rest = html;
+
+    while let Some(lt) = rest.find('<') {
+        if skipping.is_none() {
+            push_text(&mut out, &rest[..lt], pre_depth > 0);
+        }
+        rest = &rest[lt..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        // A `<` that doesn't open a tag is text
+        let opens_tag = rest[1..]
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || matches!(c, '/' | '!' | '?'));
+        let Some(gt) = rest.find('>').filter(|_| opens_tag) else {
+            if skipping.is_none() {
+                push_text(&mut out, "<", pre_depth > 0);
+            }
+            rest = &rest[1..];
+            continue;
+        };
+        let tag = &rest[1..gt];
+        rest = &rest[gt + 1..];
+
+        let closing = tag.starts_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        if let Some(skipped) = &skipping {
+            if closing && name == *skipped {
+                skipping = None;
+            }
+            continue;
+        }
+
+        match name.as_str() {
+            "" => {}
+            name if SKIPPED_ELEMENTS.contains(&name) && !closing && !tag.ends_with('/') => {
+                skipping = Some(name.to_string());
+            }
+            "br" => line_break(&mut out),
+            "li" if !closing => {
+                line_break(&mut out);
+                out.push_str("- ");
+            }
+            "td" | "th" if !closing && !out.is_empty() && !out.ends_with(['\n', '\t']) => {
+                trim_trailing_spaces(&mut out);
+                out.push('\t');
+            }
+            "pre" => {
+                paragraph_break(&mut out);
+                if closing {
+                    pre_depth = pre_depth.saturating_sub(1);
+                } else {
+                    pre_depth += 1;
+                }
+            }
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                paragraph_break(&mut out);
+                if !closing {
+                    let level = usize::from(name.as_bytes()[1] - b'0');
+                    out.push_str(&"#".repeat(level));
+                    out.push(' ');
+                }
+            }
+            name if PARAGRAPH_ELEMENTS.contains(&name) => paragraph_break(&mut out),
+            name if LINE_ELEMENTS.contains(&name) => line_break(&mut out),
+            _ => {}
+        }
+    }
+    if skipping.is_none() {
+        push_text(&mut out, rest, pre_depth > 0);
+    }
+
+    trim_trailing_spaces(&mut out);
+    out.trim_matches('\n').to_string()
+}
+
+/// Appends decoded text, collapsing whitespace outside `<pre>`.
+#[cfg(feature = "extract-html")]
+fn push_text(out: &mut String, text: &str, preformatted: bool) {
+    let text = decode_entities(text);
+    if preformatted {
+        out.push_str(&text);
+        return;
+    }
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !out.is_empty() && !out.ends_with(char::is_whitespace) {
+                out.push(' ');
+            }
+        } else {
+            out.push(c);
+        }
+    }
+}
+
+#[cfg(feature = "extract-html")]
+fn trim_trailing_spaces(out: &mut String) {
+    let len = out.trim_end_matches([' ', '\t']).len();
+    out.truncate(len);
+}
+
+/// Ends the current line, unless it is empty.
+#[cfg(feature = "extract-html")]
+fn line_break(out: &mut String) {
+    trim_trailing_spaces(out);
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+/// Ends the current paragraph with a blank line.
+#[cfg(feature = "extract-html")]
+fn paragraph_break(out: &mut String) {
+    line_break(out);
+    if !out.is_empty() && !out.ends_with("\n\n") {
+        out.push('\n');
+    }
+}
+
+/// Decodes character references and common named entities.
+#[cfg(feature = "extract-html")]
+fn decode_entities(text: &str) -> std::borrow::Cow<'_, str> {
+    if !text.contains('&') {
+        return std::borrow::Cow::Borrowed(text);
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| decode_entity(&rest[1..=end]).map(|c| (c, end + 2)));
+        if let Some((c, len)) = decoded {
+            out.push(c);
+            rest = &rest[len..];
+        } else {
+            out.push('&');
+            rest = &rest[1..];
+        }
+    }
+    out.push_str(rest);
+    std::borrow::Cow::Owned(out)
+}
+
+/// Decodes one entity, given without its `&` and `;`.
+#[cfg(feature = "extract-html")]
+fn decode_entity(entity: &str) -> Option<char> {
+    if let Some(number) = entity.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    Some(match entity {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => ' ',
+        "ndash" => '\u{2013}',
+        "mdash" => '\u{2014}',
+        "hellip" => '\u{2026}',
+        "lsquo" => '\u{2018}',
+        "rsquo" => '\u{2019}',
+        "ldquo" => '\u{201c}',
+        "rdquo" => '\u{201d}',
+        "copy" => '\u{a9}',
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_types_pass_through() {
+        assert!(create_extractor("txt").is_none());
+        assert!(create_extractor("md").is_none());
+    }
+
+    #[cfg(not(feature = "extract-html"))]
+    #[test]
+    fn test_html_requires_feature() {
+        assert!(create_extractor("html").is_none());
+    }
+
+    #[cfg(feature = "extract-html")]
+    #[test]
+    fn test_create_html_extractor() {
+        let extractor = create_extractor("HTML").unwrap();
+        assert_eq!(extractor.name(), "html");
+        let decoded = extractor
+            .extract(b"<p>Hello&nbsp;<b>world</b></p>".to_vec(), None)
+            .unwrap();
+        assert_eq!(decoded.content, "Hello world");
+    }
+
+    #[cfg(feature = "extract-html")]
+    #[test]
+    fn test_html_to_text_structure() {
+        let html = r"<!DOCTYPE html>
+<html><head><title>Guide</title>
+<style>p { color: red; }</style>
+<script>if (a < b) { run(); }</script></head>
+<body>
+  <h1>Install</h1>
+  <!-- hidden -->
+  <p>Run   the
+     installer.<br>Then restart.</p>
+  <ul><li>One</li><li>Two &amp; three</li></ul>
+  <pre>fn main() {
+    run();
+}</pre>
+  <table><tr><th>Key</th><th>Value</th></tr><tr><td>a</td><td>1 &lt; 2</td></tr></table>
+</body></html>";
+        assert_eq!(
+            html_to_text(html),
+            "Guide\n\n# Install\n\nRun the installer.\nThen restart.\n\n- One\n- Two & three\n\n\
+             fn main() {\n    run();\n}\n\nKey\tValue\na\t1 < 2"
+        );
+    }
+
+    #[cfg(feature = "extract-html")]
+    #[test]
+    fn test_decode_entities() {
+        assert_eq!(
+            decode_entities("a &lt;b&gt; &#65;&#x42; &bogus; & c"),
+            "a <b> AB &bogus; & c"
+        );
+        assert_eq!(html_to_text("1 < 2 <3"), "1 < 2 <3");
+    }
+}
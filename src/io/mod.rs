@@ -1,10 +1,34 @@
 //! I/O utilities for RLM-RS.
 //!
 //! Provides file reading with memory mapping support for efficient
-//! handling of large files, along with Unicode utilities.
+//! handling of large files (and optional gzip/zstd decompression and
+//! encoding transcoding), fetching from URLs (with the `http` feature),
+//! text extraction from HTML and PDF documents (with the `extract-html` and
+//! `extract-pdf` features), along with Unicode utilities and directory
+//! traversal.
 
+pub mod encoding;
+pub mod extract;
 pub mod reader;
 pub mod unicode;
+pub mod url;
+pub mod walk;
 
-pub use reader::{FileReader, read_file, read_file_mmap, write_chunks, write_file};
-pub use unicode::{current_timestamp, find_char_boundary, validate_utf8};
+pub use encoding::{DecodedText, decode_text};
+#[cfg(feature = "extract-html")]
+pub use extract::HtmlExtractor;
+#[cfg(feature = "extract-pdf")]
+pub use extract::PdfExtractor;
+pub use extract::{TextExtractor, create_extractor};
+pub use reader::{
+    Compression, FileReader, LineEnding, normalize_whitespace, read_file, read_file_decoded,
+    read_file_mmap, write_chunks, write_file,
+};
+pub use unicode::{
+    DEFAULT_PREVIEW_LEN, current_timestamp, find_char_boundary, find_utf8_boundary, make_preview,
+    validate_utf8,
+};
+pub use url::{
+    MAX_REDIRECTS, UrlContent, buffer_name_from_url, content_type_from_mime, fetch_url, is_url,
+};
+pub use walk::{glob_match, list_files, relative_path_string};
@@ -1,22 +1,165 @@
 //! File reading utilities with memory mapping support.
 //!
 //! Provides efficient file reading for both small and large files,
-//! with automatic detection of when to use memory mapping.
+//! with automatic detection of when to use memory mapping. With the
+//! `compression` feature, gzip and zstd files are decompressed
+//! transparently; with the `encoding` feature, non-UTF-8 text is
+//! transcoded (see [`crate::io::encoding`]).
 
 // Memory mapping requires unsafe but is well-documented and safe for read-only access
 #![allow(unsafe_code)]
 
 use crate::error::{IoError, Result};
+use crate::io::encoding::{DecodedText, decode_text};
 use memmap2::Mmap;
 use std::fs::File;
-use std::io::Read;
+use std::io::{Read, Seek, SeekFrom};
 use std::path::Path;
 
 /// Threshold for using memory mapping (1MB).
 const MMAP_THRESHOLD: u64 = 1024 * 1024;
 
 /// Maximum file size to read into memory (1GB).
-const MAX_FILE_SIZE: u64 = 1024 * 1024 * 1024;
+///
+/// Also bounds the decompressed size of compressed files and downloads.
+pub(crate) const MAX_FILE_SIZE: u64 = 1024 * 1024 * 1024;
+
+/// Compression format detected from a file's magic bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// gzip (`1f 8b`).
+    Gzip,
+    /// Zstandard (`28 b5 2f fd`).
+    Zstd,
+}
+
+impl Compression {
+    /// Detects the compression format from the first bytes of a file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rlm_rs::io::Compression;
+    ///
+    /// assert_eq!(Compression::detect(&[0x1f, 0x8b, 0x08]), Some(Compression::Gzip));
+    /// assert_eq!(Compression::detect(b"plain text"), None);
+    /// ```
+    #[must_use]
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(&[0x1f, 0x8b]) {
+            Some(Self::Gzip)
+        } else if bytes.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Self::Zstd)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the format name.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Zstd => "zstd",
+        }
+    }
+}
+
+/// Line ending style of text content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    /// Unix style (`\n`).
+    Lf,
+    /// Windows style (`\r\n`).
+    Crlf,
+    /// Classic Mac OS style (`\r`).
+    Cr,
+}
+
+impl LineEnding {
+    /// Detects the dominant line ending of `text`.
+    ///
+    /// Counts each style and returns the most frequent one, preferring LF,
+    /// then CRLF, on ties. Returns `None` for text without line breaks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rlm_rs::io::LineEnding;
+    ///
+    /// assert_eq!(LineEnding::detect("a\r\nb\r\nc\n"), Some(LineEnding::Crlf));
+    /// assert_eq!(LineEnding::detect("one line"), None);
+    /// ```
+    #[must_use]
+    pub fn detect(text: &str) -> Option<Self> {
+        let (mut lf, mut crlf, mut cr) = (0usize, 0usize, 0usize);
+        let mut bytes = text.bytes().peekable();
+        while let Some(byte) = bytes.next() {
+            match byte {
+                b'\n' => lf += 1,
+                b'\r' if bytes.next_if_eq(&b'\n').is_some() => crlf += 1,
+                b'\r' => cr += 1,
+                _ => {}
+            }
+        }
+
+        [(Self::Lf, lf), (Self::Crlf, crlf), (Self::Cr, cr)]
+            .into_iter()
+            .filter(|&(_, count)| count > 0)
+            .reduce(|best, next| if next.1 > best.1 { next } else { best })
+            .map(|(ending, _)| ending)
+    }
+
+    /// Returns the conventional name (`LF`, `CRLF`, or `CR`).
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Lf => "LF",
+            Self::Crlf => "CRLF",
+            Self::Cr => "CR",
+        }
+    }
+}
+
+/// Canonicalizes whitespace in `text` for consistent chunking and hashing.
+///
+/// Converts CRLF and CR line endings to LF, trims trailing whitespace from
+/// every line, and collapses each run of whitespace within a line to a
+/// single space. Leading indentation is kept, so code and nested lists keep
+/// their structure, and line breaks are never merged.
+///
+/// # Examples
+///
+/// ```
+/// use rlm_rs::io::normalize_whitespace;
+///
+/// assert_eq!(
+///     normalize_whitespace("  a \t b  \r\nc\t\r\n"),
+///     "  a b\nc\n"
+/// );
+/// ```
+#[must_use]
+pub fn normalize_whitespace(text: &str) -> String {
+    let unified = text.replace("\r\n", "\n").replace('\r', "\n");
+    let mut normalized = String::with_capacity(unified.len());
+    for (i, line) in unified.split('\n').enumerate() {
+        if i > 0 {
+            normalized.push('\n');
+        }
+        let body = line.trim_start();
+        if body.is_empty() {
+            continue;
+        }
+        normalized.push_str(&line[..line.len() - body.len()]);
+        for (j, word) in body.split_whitespace().enumerate() {
+            if j > 0 {
+                normalized.push(' ');
+            }
+            normalized.push_str(word);
+        }
+    }
+    normalized
+}
 
 /// File reader with support for memory mapping.
 ///
@@ -98,22 +241,79 @@ impl FileReader {
         &self.path
     }
 
+    /// Detects whether the file is gzip or zstd compressed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file's first bytes can't be read.
+    pub fn compression(&self) -> Result<Option<Compression>> {
+        let mut magic = Vec::with_capacity(4);
+        let mut file = &self.file;
+        file.take(4)
+            .read_to_end(&mut magic)
+            .and_then(|_| file.seek(SeekFrom::Start(0)))
+            .map_err(|e| IoError::ReadFailed {
+                path: self.path.clone(),
+                reason: e.to_string(),
+            })?;
+        Ok(Compression::detect(&magic))
+    }
+
     /// Reads the file content as a string.
     ///
-    /// Uses memory mapping for large files.
+    /// Uses memory mapping for large files. Compressed files are
+    /// stream-decompressed instead (requires the `compression` feature).
+    /// With the `encoding` feature, non-UTF-8 content is transcoded; see
+    /// [`read_decoded`](Self::read_decoded).
     ///
     /// # Errors
     ///
-    /// Returns an error if reading fails or content is not valid UTF-8.
+    /// Returns an error if reading or decompression fails, the file is
+    /// compressed but the `compression` feature is disabled, or content
+    /// can't be decoded.
     pub fn read_to_string(&self) -> Result<String> {
-        if self.size >= MMAP_THRESHOLD {
-            self.read_mmap()
-        } else {
-            self.read_direct()
-        }
+        self.read_decoded(None).map(|decoded| decoded.content)
+    }
+
+    /// Reads the file content as UTF-8 text, reporting the source encoding.
+    ///
+    /// # Arguments
+    ///
+    /// * `encoding` - Encoding label to use instead of detecting one.
+    ///   Without the `encoding` feature, only UTF-8 is accepted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading or decompression fails, the encoding is
+    /// unknown or unsupported, or the content can't be decoded.
+    pub fn read_decoded(&self, encoding: Option<&str>) -> Result<DecodedText> {
+        let bytes = self.read_bytes()?;
+        decode_text(bytes, encoding).map_err(|reason| {
+            IoError::ReadFailed {
+                path: self.path.clone(),
+                reason,
+            }
+            .into()
+        })
     }
 
-    /// Reads the file content as bytes.
+    /// Reads the file content as bytes, decompressing gzip or zstd files.
+    ///
+    /// Pass the result to [`decode_text`] to tell undecodable (e.g. binary)
+    /// content apart from read failures.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading or decompression fails, or the file is
+    /// compressed but the `compression` feature is disabled.
+    pub fn read_bytes(&self) -> Result<Vec<u8>> {
+        self.compression()?.map_or_else(
+            || self.read_to_bytes(),
+            |compression| self.read_compressed(compression),
+        )
+    }
+
+    /// Reads the raw file content as bytes, without decompression.
     ///
     /// # Errors
     ///
@@ -126,16 +326,49 @@ impl FileReader {
         }
     }
 
-    /// Reads using memory mapping.
-    fn read_mmap(&self) -> Result<String> {
-        let bytes = self.read_mmap_bytes()?;
-        String::from_utf8(bytes).map_err(|e| {
-            IoError::ReadFailed {
+    /// Stream-decompresses the file into memory.
+    #[cfg(feature = "compression")]
+    fn read_compressed(&self, compression: Compression) -> Result<Vec<u8>> {
+        let read_error = |e: std::io::Error| IoError::ReadFailed {
+            path: self.path.clone(),
+            reason: format!("{} decompression failed: {e}", compression.name()),
+        };
+
+        let decoder: Box<dyn Read + '_> = match compression {
+            // Multi-member streams are common for rotated and appended logs
+            Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(&self.file)),
+            Compression::Zstd => {
+                Box::new(zstd::stream::read::Decoder::new(&self.file).map_err(read_error)?)
+            }
+        };
+
+        let mut bytes = Vec::new();
+        decoder
+            .take(MAX_FILE_SIZE + 1)
+            .read_to_end(&mut bytes)
+            .map_err(read_error)?;
+        if bytes.len() as u64 > MAX_FILE_SIZE {
+            return Err(IoError::ReadFailed {
                 path: self.path.clone(),
-                reason: format!("invalid UTF-8: {e}"),
+                reason: format!("decompressed content exceeds {MAX_FILE_SIZE} bytes"),
             }
-            .into()
-        })
+            .into());
+        }
+
+        Ok(bytes)
+    }
+
+    /// Reports that compressed input needs the `compression` feature.
+    #[cfg(not(feature = "compression"))]
+    fn read_compressed(&self, compression: Compression) -> Result<Vec<u8>> {
+        Err(IoError::ReadFailed {
+            path: self.path.clone(),
+            reason: format!(
+                "file is {}-compressed; rebuild with the `compression` feature or decompress it first",
+                compression.name()
+            ),
+        }
+        .into())
     }
 
     /// Reads bytes using memory mapping.
@@ -151,18 +384,6 @@ impl FileReader {
         Ok(mmap.to_vec())
     }
 
-    /// Reads directly into memory.
-    fn read_direct(&self) -> Result<String> {
-        let bytes = self.read_direct_bytes()?;
-        String::from_utf8(bytes).map_err(|e| {
-            IoError::ReadFailed {
-                path: self.path.clone(),
-                reason: format!("invalid UTF-8: {e}"),
-            }
-            .into()
-        })
-    }
-
     /// Reads bytes directly into memory.
     #[allow(clippy::cast_possible_truncation)]
     fn read_direct_bytes(&self) -> Result<Vec<u8>> {
@@ -200,6 +421,10 @@ impl FileReader {
 
 /// Reads a file to string, automatically choosing the best method.
 ///
+/// gzip and zstd files are decompressed when the `compression` feature is
+/// enabled, and non-UTF-8 text is transcoded when the `encoding` feature
+/// is enabled.
+///
 /// # Arguments
 ///
 /// * `path` - Path to the file.
@@ -219,10 +444,24 @@ pub fn read_file<P: AsRef<Path>>(path: P) -> Result<String> {
     FileReader::open(path)?.read_to_string()
 }
 
+/// Reads a file as UTF-8 text, reporting the encoding it was stored in.
+///
+/// Like [`read_file`], but `encoding` can force a specific encoding and
+/// the detected one is returned alongside the content.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read, the encoding is unknown
+/// or unsupported, or the content can't be decoded.
+pub fn read_file_decoded<P: AsRef<Path>>(path: P, encoding: Option<&str>) -> Result<DecodedText> {
+    FileReader::open(path)?.read_decoded(encoding)
+}
+
 /// Reads a file using memory mapping.
 ///
 /// This is useful for very large files that shouldn't be fully loaded
-/// into memory.
+/// into memory. The mapping holds the raw file bytes; compressed files are
+/// not decompressed (use [`read_file`] for that).
 ///
 /// # Arguments
 ///
@@ -331,6 +570,42 @@ mod tests {
         assert_eq!(content, "Hello, world!");
     }
 
+    #[test]
+    fn test_line_ending_detect() {
+        assert_eq!(LineEnding::detect("a\nb\n"), Some(LineEnding::Lf));
+        assert_eq!(LineEnding::detect("a\rb\rc"), Some(LineEnding::Cr));
+        assert_eq!(LineEnding::detect(""), None);
+        // Dominant style wins; ties prefer LF
+        assert_eq!(LineEnding::detect("a\r\nb\r\nc\n"), Some(LineEnding::Crlf));
+        assert_eq!(LineEnding::detect("a\r\nb\n"), Some(LineEnding::Lf));
+        assert_eq!(LineEnding::Crlf.name(), "CRLF");
+
+        // Reading keeps CRLF intact
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("crlf.txt");
+        std::fs::write(&path, "one\r\ntwo\r\n").unwrap();
+        let content = read_file(&path).unwrap();
+        assert_eq!(content, "one\r\ntwo\r\n");
+        assert_eq!(LineEnding::detect(&content), Some(LineEnding::Crlf));
+    }
+
+    #[test]
+    fn test_normalize_whitespace() {
+        assert_eq!(normalize_whitespace("a  b\t\tc"), "a b c");
+        assert_eq!(
+            normalize_whitespace("one \r\ntwo\t\rthree"),
+            "one\ntwo\nthree"
+        );
+        // Indentation and blank lines survive; whitespace-only lines empty out
+        assert_eq!(
+            normalize_whitespace("fn f() {\n\tlet  x = 1;   \n \t \n}\n"),
+            "fn f() {\n\tlet x = 1;\n\n}\n"
+        );
+        assert_eq!(normalize_whitespace(""), "");
+        let once = normalize_whitespace(" x \r\n  y\u{a0}\u{a0}z ");
+        assert_eq!(normalize_whitespace(&once), once);
+    }
+
     #[test]
     fn test_read_nonexistent_file() {
         let result = read_file("/nonexistent/path/file.txt");
@@ -418,6 +693,94 @@ mod tests {
         assert_eq!(content, "Hello, 世界! 🌍");
     }
 
+    #[test]
+    fn test_compression_detect() {
+        assert_eq!(Compression::detect(&[0x1f, 0x8b]), Some(Compression::Gzip));
+        assert_eq!(
+            Compression::detect(&[0x28, 0xb5, 0x2f, 0xfd, 0x00]),
+            Some(Compression::Zstd)
+        );
+        assert_eq!(Compression::detect(&[0x28, 0xb5]), None);
+        assert_eq!(Compression::detect(&[]), None);
+
+        // Detection rewinds, so plain files still read in full
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("plain.txt");
+        std::fs::write(&file_path, "plain").unwrap();
+        let reader = FileReader::open(&file_path).unwrap();
+        assert_eq!(reader.compression().unwrap(), None);
+        assert_eq!(reader.read_to_string().unwrap(), "plain");
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_read_compressed_files() {
+        use std::io::Write;
+
+        let temp_dir = TempDir::new().unwrap();
+        let text = "compressed log line\n".repeat(100);
+
+        let gz_path = temp_dir.path().join("log.txt.gz");
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(text.as_bytes()).unwrap();
+        std::fs::write(&gz_path, encoder.finish().unwrap()).unwrap();
+        assert_eq!(read_file(&gz_path).unwrap(), text);
+
+        let zst_path = temp_dir.path().join("log.txt.zst");
+        std::fs::write(&zst_path, zstd::encode_all(text.as_bytes(), 0).unwrap()).unwrap();
+        assert_eq!(read_file(&zst_path).unwrap(), text);
+
+        // Without transcoding, decompressed content must still be UTF-8
+        if cfg!(not(feature = "encoding")) {
+            let bad_path = temp_dir.path().join("bad.gz");
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(&[b'o', b'k', 0xff]).unwrap();
+            std::fs::write(&bad_path, encoder.finish().unwrap()).unwrap();
+            let err = read_file(&bad_path).unwrap_err();
+            assert!(err.to_string().contains("at byte 2"));
+        }
+    }
+
+    #[cfg(not(feature = "compression"))]
+    #[test]
+    fn test_read_compressed_requires_feature() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("log.gz");
+        std::fs::write(&file_path, [0x1f, 0x8b, 0x08, 0x00]).unwrap();
+
+        let err = read_file(&file_path).unwrap_err();
+        assert!(err.to_string().contains("`compression` feature"));
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn test_read_file_decoded_utf16() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("utf16.txt");
+        let mut bytes = vec![0xff, 0xfe];
+        bytes.extend("Grüße".encode_utf16().flat_map(u16::to_le_bytes));
+        std::fs::write(&file_path, bytes).unwrap();
+
+        let decoded = read_file_decoded(&file_path, None).unwrap();
+        assert_eq!(decoded.content, "Grüße");
+        assert_eq!(decoded.encoding, Some("UTF-16LE"));
+        assert_eq!(read_file(&file_path).unwrap(), "Grüße");
+    }
+
+    #[cfg(not(feature = "encoding"))]
+    #[test]
+    fn test_read_file_decoded_strict() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("cp1252.txt");
+        std::fs::write(&file_path, b"caf\xe9").unwrap();
+
+        let err = read_file_decoded(&file_path, None).unwrap_err();
+        assert!(err.to_string().contains("invalid UTF-8 at byte 3"));
+        let err = read_file_decoded(&file_path, Some("windows-1252")).unwrap_err();
+        assert!(err.to_string().contains("`encoding` feature"));
+    }
+
     #[test]
     fn test_read_to_bytes() {
         let temp_dir = TempDir::new().unwrap();
@@ -483,6 +846,7 @@ mod tests {
         assert_eq!(bytes.len(), large_content.len());
     }
 
+    #[cfg(not(feature = "encoding"))]
     #[test]
     fn test_read_invalid_utf8() {
         let temp_dir = TempDir::new().unwrap();
@@ -495,6 +859,7 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[cfg(not(feature = "encoding"))]
     #[test]
     fn test_read_invalid_utf8_via_mmap() {
         let temp_dir = TempDir::new().unwrap();
@@ -3,7 +3,7 @@
 //! Provides helpers for proper Unicode handling including
 //! character boundary detection and validation.
 
-use unicode_segmentation::UnicodeSegmentation;
+use unicode_segmentation::{GraphemeCursor, UnicodeSegmentation};
 
 /// Finds a valid UTF-8 character boundary at or before the given position.
 ///
@@ -27,10 +27,30 @@ use unicode_segmentation::UnicodeSegmentation;
 /// ```
 #[must_use]
 pub const fn find_char_boundary(s: &str, pos: usize) -> usize {
-    if pos >= s.len() {
-        return s.len();
+    find_utf8_boundary(s.as_bytes(), pos)
+}
+
+/// Finds a UTF-8 sequence boundary at or before `pos` in raw bytes.
+///
+/// Unlike [`find_char_boundary`], the input need not be valid UTF-8: the
+/// result is `bytes.len()`, `0`, or the offset of the nearest byte at or
+/// before `pos` that is not a continuation byte, so slicing at it never
+/// splits a well-formed sequence. It never exceeds `pos` or `bytes.len()`.
+///
+/// # Examples
+///
+/// ```
+/// use rlm_rs::io::unicode::find_utf8_boundary;
+///
+/// let bytes = "a😀".as_bytes(); // '😀' is 4 bytes at offset 1
+/// assert_eq!(find_utf8_boundary(bytes, 4), 1);
+/// assert_eq!(find_utf8_boundary(&[0x80, 0x80, b'a'], 1), 0);
+/// ```
+#[must_use]
+pub const fn find_utf8_boundary(bytes: &[u8], pos: usize) -> usize {
+    if pos >= bytes.len() {
+        return bytes.len();
     }
-    let bytes = s.as_bytes();
     let mut boundary = pos;
     // UTF-8 continuation bytes start with 10xxxxxx (0x80-0xBF)
     while boundary > 0 && (bytes[boundary] & 0xC0) == 0x80 {
@@ -63,6 +83,48 @@ pub const fn find_char_boundary_forward(s: &str, pos: usize) -> usize {
     boundary
 }
 
+/// Finds a grapheme cluster boundary at or before the given position.
+///
+/// Like [`find_char_boundary`], but never splits a user-perceived character
+/// such as an emoji ZWJ sequence, a flag, or a letter with combining marks.
+///
+/// # Examples
+///
+/// ```
+/// use rlm_rs::io::unicode::find_grapheme_boundary;
+///
+/// let s = "ae\u{301}b"; // 'e' + combining acute accent
+/// assert_eq!(find_grapheme_boundary(s, 2), 1); // Would split "e\u{301}"
+/// assert_eq!(find_grapheme_boundary(s, 4), 4);
+/// ```
+#[must_use]
+pub fn find_grapheme_boundary(s: &str, pos: usize) -> usize {
+    let pos = find_char_boundary(s, pos);
+    if pos == 0 || pos == s.len() {
+        return pos;
+    }
+    let mut cursor = GraphemeCursor::new(pos, s.len(), true);
+    match cursor.is_boundary(s, 0) {
+        Ok(false) => cursor.prev_boundary(s, 0).ok().flatten().unwrap_or(0),
+        // The whole string is provided, so no more context can be requested
+        Ok(true) | Err(_) => pos,
+    }
+}
+
+/// Finds a grapheme cluster boundary at or after the given position.
+#[must_use]
+pub fn find_grapheme_boundary_forward(s: &str, pos: usize) -> usize {
+    let pos = find_char_boundary_forward(s, pos);
+    if pos == 0 || pos == s.len() {
+        return pos;
+    }
+    let mut cursor = GraphemeCursor::new(pos, s.len(), true);
+    match cursor.is_boundary(s, 0) {
+        Ok(false) => cursor.next_boundary(s, 0).ok().flatten().unwrap_or(s.len()),
+        Ok(true) | Err(_) => pos,
+    }
+}
+
 /// Validates that a byte slice is valid UTF-8.
 ///
 /// # Arguments
@@ -126,6 +188,42 @@ pub fn truncate_graphemes(s: &str, max_graphemes: usize) -> &str {
     &s[..end_byte]
 }
 
+/// Default preview length, in grapheme clusters, for content previews.
+pub const DEFAULT_PREVIEW_LEN: usize = 100;
+
+/// Renders a single-line preview of `content`.
+///
+/// Keeps the first `len` grapheme clusters, so combining marks and emoji
+/// sequences are never split, and replaces each whitespace grapheme (such
+/// as a newline or `\r\n`) with a space. Appends `...` when content was
+/// cut off.
+///
+/// # Examples
+///
+/// ```
+/// use rlm_rs::io::make_preview;
+///
+/// assert_eq!(make_preview("line one\nline two", 8), "line one...");
+/// assert_eq!(make_preview("short", 100), "short");
+/// ```
+#[must_use]
+pub fn make_preview(content: &str, len: usize) -> String {
+    let mut preview = String::with_capacity(content.len().min(len * 4) + 3);
+    let mut graphemes = content.graphemes(true);
+
+    for grapheme in graphemes.by_ref().take(len) {
+        if grapheme.chars().all(char::is_whitespace) {
+            preview.push(' ');
+        } else {
+            preview.push_str(grapheme);
+        }
+    }
+    if graphemes.next().is_some() {
+        preview.push_str("...");
+    }
+    preview
+}
+
 /// Finds the byte position of the nth grapheme cluster.
 ///
 /// # Arguments
@@ -245,6 +343,7 @@ pub fn current_timestamp() -> i64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn test_find_char_boundary() {
@@ -258,12 +357,102 @@ mod tests {
         assert_eq!(find_char_boundary(s, 100), s.len());
     }
 
+    #[test]
+    fn test_find_char_boundary_four_byte() {
+        // '😀' (U+1F600) occupies bytes 1..5
+        let s = "a😀b";
+        assert_eq!(find_char_boundary(s, 1), 1);
+        for pos in 2..5 {
+            assert_eq!(find_char_boundary(s, pos), 1);
+            assert_eq!(find_char_boundary_forward(s, pos), 5);
+        }
+        assert_eq!(find_char_boundary(s, 5), 5);
+        assert_eq!(find_char_boundary(s, 6), 6);
+        assert_eq!(find_char_boundary("😀", 3), 0);
+        assert_eq!(find_char_boundary("", 0), 0);
+        assert_eq!(find_char_boundary("", 5), 0);
+    }
+
+    #[test]
+    fn test_find_utf8_boundary_invalid_input() {
+        // Lone continuation bytes walk back to the start
+        assert_eq!(find_utf8_boundary(&[0x80, 0xBF, 0x80], 2), 0);
+        // Truncated 4-byte sequence
+        assert_eq!(find_utf8_boundary(&[b'a', 0xF0, 0x9F, 0x98], 3), 1);
+        // Invalid lead bytes are treated as boundaries
+        assert_eq!(find_utf8_boundary(&[0xFF, 0xFE], 1), 1);
+        assert_eq!(find_utf8_boundary(&[0xFF], 9), 1);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_find_char_boundary_is_nearest_boundary_at_or_before(
+            s in any::<String>(),
+            pos in 0usize..512,
+        ) {
+            let target = pos.min(s.len());
+            let boundary = find_char_boundary(&s, pos);
+            prop_assert!(s.is_char_boundary(boundary));
+            prop_assert!(boundary <= target);
+            prop_assert!((boundary + 1..=target).all(|i| !s.is_char_boundary(i)));
+            // Slicing at the result never panics
+            let _ = &s[..boundary];
+        }
+
+        #[test]
+        fn prop_find_char_boundary_forward_is_nearest_boundary_at_or_after(
+            s in any::<String>(),
+            pos in 0usize..512,
+        ) {
+            let target = pos.min(s.len());
+            let boundary = find_char_boundary_forward(&s, pos);
+            prop_assert!(s.is_char_boundary(boundary));
+            prop_assert!(boundary >= target);
+            prop_assert!((target..boundary).all(|i| !s.is_char_boundary(i)));
+        }
+
+        #[test]
+        fn prop_find_utf8_boundary_on_arbitrary_bytes(
+            bytes in prop::collection::vec(any::<u8>(), 0..64),
+            pos in 0usize..80,
+        ) {
+            let boundary = find_utf8_boundary(&bytes, pos);
+            prop_assert!(boundary <= pos.min(bytes.len()));
+            prop_assert!(
+                boundary == 0 || boundary == bytes.len() || bytes[boundary] & 0xC0 != 0x80
+            );
+            if let Ok(s) = std::str::from_utf8(&bytes) {
+                prop_assert!(s.is_char_boundary(boundary));
+            }
+        }
+    }
+
     #[test]
     fn test_find_char_boundary_forward() {
         let s = "Hello 世界!";
         assert_eq!(find_char_boundary_forward(s, 7), 9); // Middle of '世', moves forward
     }
 
+    #[test]
+    fn test_find_grapheme_boundary() {
+        // Family emoji: four people joined by ZWJ, 25 bytes, one grapheme
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}\u{200D}\u{1F466}";
+        let s = format!("a{family}b");
+        for pos in 2..=25 {
+            assert_eq!(find_grapheme_boundary(&s, pos), 1);
+            assert_eq!(find_grapheme_boundary_forward(&s, pos), 26);
+        }
+        assert_eq!(find_grapheme_boundary(&s, 1), 1);
+        assert_eq!(find_grapheme_boundary(&s, 26), 26);
+        assert_eq!(find_grapheme_boundary(&s, 100), s.len());
+
+        // Skin tone modifier and regional indicator flag
+        let s = "\u{1F44D}\u{1F3FD}\u{1F1EF}\u{1F1F5}";
+        assert_eq!(find_grapheme_boundary(s, 4), 0);
+        assert_eq!(find_grapheme_boundary(s, 12), 8);
+        assert_eq!(find_grapheme_boundary_forward(s, 12), 16);
+    }
+
     #[test]
     fn test_validate_utf8() {
         assert!(validate_utf8(b"Hello").is_ok());
@@ -281,6 +470,23 @@ mod tests {
         assert_eq!(grapheme_count(""), 0);
     }
 
+    #[test]
+    fn test_make_preview() {
+        assert_eq!(make_preview("Hello", 5), "Hello");
+        assert_eq!(make_preview("Hello", 3), "Hel...");
+        assert_eq!(make_preview("", 3), "");
+        assert_eq!(make_preview("abc", 0), "...");
+        assert_eq!(make_preview("a\r\nb\tc", 10), "a b c");
+
+        // Counts graphemes, not scalar values: "e\u{301}" is one grapheme
+        assert_eq!(make_preview("e\u{301}e\u{301}x", 2), "e\u{301}e\u{301}...");
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        assert_eq!(
+            make_preview(&format!("{family}{family}"), 1),
+            format!("{family}...")
+        );
+    }
+
     #[test]
     fn test_truncate_graphemes() {
         assert_eq!(truncate_graphemes("Hello", 3), "Hel");
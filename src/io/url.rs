@@ -0,0 +1,350 @@
+//! Fetching load sources from URLs.
+//!
+//! `load` accepts `http://` and `https://` URLs as well as paths. Fetching
+//! requires the `http` feature; without it URLs are still recognized, and
+//! rejected with an error naming the feature.
+
+use crate::error::{IoError, Result};
+
+/// Maximum number of redirects followed when fetching a URL.
+pub const MAX_REDIRECTS: u32 = 5;
+
+/// Content downloaded from a URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UrlContent {
+    /// URL the content was served from, after redirects.
+    pub final_url: String,
+
+    /// Content type (e.g. `html`, `md`): from the `Content-Type` header, or
+    /// the URL's file extension when the header is missing or generic.
+    pub content_type: Option<String>,
+
+    /// Response body, at most the file size limit.
+    pub bytes: Vec<u8>,
+}
+
+/// Returns `true` if `source` is an `http://` or `https://` URL.
+///
+/// # Examples
+///
+/// ```
+/// use rlm_rs::io::is_url;
+///
+/// assert!(is_url("https://example.com/notes.md"));
+/// assert!(!is_url("notes.md"));
+/// ```
+#[must_use]
+pub fn is_url(source: &str) -> bool {
+    let scheme = source
+        .split_once("://")
+        .map(|(scheme, _)| scheme.to_ascii_lowercase());
+    matches!(scheme.as_deref(), Some("http" | "https"))
+}
+
+/// Splits a URL into its host and path, without query or fragment.
+fn host_and_path(url: &str) -> (&str, &str) {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let rest = rest.split(['?', '#']).next().unwrap_or_default();
+    rest.split_once('/').unwrap_or((rest, ""))
+}
+
+/// Names a buffer after a URL: the last segment of its path, or its host
+/// when the path is empty.
+///
+/// # Examples
+///
+/// ```
+/// use rlm_rs::io::buffer_name_from_url;
+///
+/// let name = buffer_name_from_url("https://example.com/docs/guide.md?raw=1");
+/// assert_eq!(name.as_deref(), Some("guide.md"));
+/// assert_eq!(buffer_name_from_url("https://example.com/").as_deref(), Some("example.com"));
+/// ```
+#[must_use]
+pub fn buffer_name_from_url(url: &str) -> Option<String> {
+    let (host, path) = host_and_path(url);
+    path.rsplit('/')
+        .find(|segment| !segment.is_empty())
+        .or_else(|| Some(host).filter(|host| !host.is_empty()))
+        .map(String::from)
+}
+
+/// Returns the lowercase file extension of a URL's path, if any.
+#[cfg(feature = "http")]
+fn url_extension(url: &str) -> Option<String> {
+    let (_, path) = host_and_path(url);
+    let file = path.rsplit('/').next()?;
+    file.rsplit_once('.')
+        .map(|(stem, ext)| (stem, ext.to_ascii_lowercase()))
+        .filter(|(stem, ext)| !stem.is_empty() && !ext.is_empty())
+        .map(|(_, ext)| ext)
+}
+
+/// Maps a `Content-Type` header to a content type, as used for
+/// [`BufferMetadata::content_type`](crate::core::BufferMetadata::content_type).
+///
+/// Returns `None` for generic types (`text/plain`,
+/// `application/octet-stream`) and unknown ones.
+#[must_use]
+pub fn content_type_from_mime(mime: &str) -> Option<&'static str> {
+    let essence = mime
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    Some(match essence.as_str() {
+        "text/markdown" | "text/x-markdown" => "md",
+        "text/html" | "application/xhtml+xml" => "html",
+        "application/json" => "json",
+        "application/pdf" => "pdf",
+        "application/xml" | "text/xml" => "xml",
+        "text/csv" => "csv",
+        "application/yaml" | "application/x-yaml" | "text/yaml" => "yaml",
+        "application/toml" => "toml",
+        "application/javascript" | "text/javascript" => "js",
+        "text/x-python" => "py",
+        "text/x-rust" => "rs",
+        _ => return None,
+    })
+}
+
+/// Derives the content type of a download from its header and URL.
+#[cfg(feature = "http")]
+fn detect_content_type(mime: Option<&str>, url: &str) -> Option<String> {
+    mime.and_then(content_type_from_mime)
+        .map(String::from)
+        .or_else(|| url_extension(url))
+        .or_else(|| {
+            mime.filter(|m| {
+                m.trim_start()
+                    .to_ascii_lowercase()
+                    .starts_with("text/plain")
+            })
+            .map(|_| "txt".to_string())
+        })
+}
+
+/// Fetches a URL for loading as a buffer.
+///
+/// Follows up to [`MAX_REDIRECTS`] redirects. Downloads are bounded by the
+/// same size limit as files.
+///
+/// # Errors
+///
+/// Returns an error if the request fails, the server answers with a
+/// non-2xx status, there are too many redirects, the download exceeds the
+/// size limit, or the `http` feature is disabled.
+#[cfg(feature = "http")]
+pub fn fetch_url(url: &str) -> Result<UrlContent> {
+    fetch_url_limited(url, super::reader::MAX_FILE_SIZE)
+}
+
+/// Reports that loading URLs needs the `http` feature.
+///
+/// # Errors
+///
+/// Always: URLs can only be fetched with the `http` feature.
+#[cfg(not(feature = "http"))]
+pub fn fetch_url(url: &str) -> Result<UrlContent> {
+    Err(IoError::ReadFailed {
+        path: url.to_string(),
+        reason:
+            "loading URLs requires the `http` feature; rebuild with it or download the file first"
+                .to_string(),
+    }
+    .into())
+}
+
+/// Fetches a URL, failing if the body exceeds `max_size` bytes.
+#[cfg(feature = "http")]
+fn fetch_url_limited(url: &str, max_size: u64) -> Result<UrlContent> {
+    use std::io::Read;
+    use std::time::Duration;
+
+    let failed = |reason: String| IoError::ReadFailed {
+        path: url.to_string(),
+        reason,
+    };
+
+    let agent = ureq::AgentBuilder::new()
+        .timeout_connect(Duration::from_secs(10))
+        .timeout(Duration::from_secs(300))
+        .redirects(MAX_REDIRECTS)
+        .build();
+    let response = agent.get(url).call().map_err(|e| {
+        failed(match e {
+            ureq::Error::Status(code, response) => {
+                format!("server returned HTTP {code} {}", response.status_text())
+            }
+            ureq::Error::Transport(transport)
+                if matches!(transport.kind(), ureq::ErrorKind::TooManyRedirects) =>
+            {
+                format!("more than {MAX_REDIRECTS} redirects")
+            }
+            ureq::Error::Transport(transport) => transport.to_string(),
+        })
+    })?;
+
+    if let Some(length) = response
+        .header("Content-Length")
+        .and_then(|length| length.trim().parse::<u64>().ok())
+        && length > max_size
+    {
+        return Err(failed(format!(
+            "download too large: {length} bytes (max: {max_size} bytes)"
+        ))
+        .into());
+    }
+
+    let final_url = response.get_url().to_string();
+    let mime = response.header("Content-Type").map(String::from);
+    let mut bytes = Vec::new();
+    response
+        .into_reader()
+        .take(max_size + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| failed(e.to_string()))?;
+    if bytes.len() as u64 > max_size {
+        return Err(failed(format!("download too large: exceeds {max_size} bytes")).into());
+    }
+
+    Ok(UrlContent {
+        content_type: detect_content_type(mime.as_deref(), &final_url),
+        final_url,
+        bytes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_url() {
+        assert!(is_url("http://example.com"));
+        assert!(is_url("HTTPS://example.com/a.md"));
+        assert!(!is_url("ftp://example.com/a.md"));
+        assert!(!is_url("./http/notes.md"));
+        assert!(!is_url("-"));
+    }
+
+    #[test]
+    fn test_buffer_name_from_url() {
+        let name = buffer_name_from_url;
+        assert_eq!(
+            name("https://example.com/a/b/c.txt").as_deref(),
+            Some("c.txt")
+        );
+        assert_eq!(name("https://example.com/a/b/").as_deref(), Some("b"));
+        assert_eq!(
+            name("https://example.com/doc#intro").as_deref(),
+            Some("doc")
+        );
+        assert_eq!(name("https://example.com").as_deref(), Some("example.com"));
+        assert_eq!(name("https://").as_deref(), None);
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_detect_content_type() {
+        let url = "https://example.com/src/lib.rs?plain=1";
+        // Specific headers win, generic ones defer to the extension
+        assert_eq!(
+            detect_content_type(Some("text/markdown; charset=utf-8"), url).as_deref(),
+            Some("md")
+        );
+        assert_eq!(
+            detect_content_type(Some("text/plain; charset=utf-8"), url).as_deref(),
+            Some("rs")
+        );
+        assert_eq!(
+            detect_content_type(Some("text/plain"), "https://example.com/readme").as_deref(),
+            Some("txt")
+        );
+        assert_eq!(
+            detect_content_type(None, "https://example.com/").as_deref(),
+            None
+        );
+        assert_eq!(content_type_from_mime("application/octet-stream"), None);
+    }
+
+    #[cfg(not(feature = "http"))]
+    #[test]
+    fn test_fetch_requires_feature() {
+        let err = fetch_url("https://example.com/doc.md").unwrap_err();
+        assert!(err.to_string().contains("`http` feature"));
+    }
+
+    /// Serves raw HTTP responses chosen by request path; returns the base URL.
+    #[cfg(feature = "http")]
+    fn serve(respond: fn(&str) -> String) -> String {
+        use std::io::{BufRead, BufReader, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { break };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line == "\r\n" || line.is_empty() {
+                        break;
+                    }
+                }
+                let path = request_line.split_whitespace().nth(1).unwrap_or_default();
+                let _ = stream.write_all(respond(path).as_bytes());
+            }
+        });
+        url
+    }
+
+    #[cfg(feature = "http")]
+    fn routes(path: &str) -> String {
+        let response = |status: &str, headers: &str, body: &str| {
+            format!(
+                "HTTP/1.1 {status}\r\n{headers}Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+        };
+        match path {
+            "/guide.md" => response(
+                "200 OK",
+                "Content-Type: text/markdown; charset=utf-8\r\n",
+                "# Guide\n",
+            ),
+            "/old" => response("301 Moved Permanently", "Location: /guide.md\r\n", ""),
+            "/loop" => response("302 Found", "Location: /loop\r\n", ""),
+            "/big.txt" => response("200 OK", "Content-Type: text/plain\r\n", &"x".repeat(100)),
+            _ => response("404 Not Found", "", "missing"),
+        }
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_fetch_url() {
+        let base = serve(routes);
+
+        let content = fetch_url(&format!("{base}/guide.md")).unwrap();
+        assert_eq!(content.bytes, b"# Guide\n");
+        assert_eq!(content.content_type.as_deref(), Some("md"));
+
+        // Redirects are followed and reported
+        let content = fetch_url(&format!("{base}/old")).unwrap();
+        assert_eq!(content.bytes, b"# Guide\n");
+        assert_eq!(content.final_url, format!("{base}/guide.md"));
+
+        let err = fetch_url(&format!("{base}/missing")).unwrap_err();
+        assert!(err.to_string().contains("HTTP 404"), "{err}");
+        let err = fetch_url(&format!("{base}/loop")).unwrap_err();
+        assert!(err.to_string().contains("redirects"), "{err}");
+
+        let err = fetch_url_limited(&format!("{base}/big.txt"), 10).unwrap_err();
+        assert!(err.to_string().contains("too large"), "{err}");
+        assert!(fetch_url_limited(&format!("{base}/big.txt"), 100).is_ok());
+    }
+}
@@ -0,0 +1,204 @@
+//! Directory traversal and glob matching for bulk imports.
+
+use crate::error::{IoError, Result};
+use std::path::{Path, PathBuf};
+
+/// Lists the files under `dir`, as paths relative to it, in sorted order.
+///
+/// Hidden entries (names starting with `.`, such as `.git`) are skipped.
+/// Symbolic links to files are listed; links to directories are not
+/// followed.
+///
+/// # Arguments
+///
+/// * `dir` - The directory to list.
+/// * `recursive` - Whether to descend into subdirectories.
+///
+/// # Errors
+///
+/// Returns an error if `dir` is not a directory or can't be read.
+pub fn list_files<P: AsRef<Path>>(dir: P, recursive: bool) -> Result<Vec<PathBuf>> {
+    let dir = dir.as_ref();
+    if !dir.is_dir() {
+        return Err(IoError::ReadFailed {
+            path: dir.to_string_lossy().to_string(),
+            reason: "not a directory".to_string(),
+        }
+        .into());
+    }
+    let mut files = Vec::new();
+    collect_files(dir, Path::new(""), recursive, &mut files)?;
+    Ok(files)
+}
+
+fn collect_files(
+    root: &Path,
+    relative: &Path,
+    recursive: bool,
+    files: &mut Vec<PathBuf>,
+) -> Result<()> {
+    let dir = root.join(relative);
+    let read_failed = |e: std::io::Error| IoError::ReadFailed {
+        path: dir.to_string_lossy().to_string(),
+        reason: e.to_string(),
+    };
+
+    let mut entries = std::fs::read_dir(&dir)
+        .map_err(read_failed)?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(read_failed)?;
+    entries.sort_by_key(std::fs::DirEntry::file_name);
+
+    for entry in entries {
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let path = relative.join(entry.file_name());
+        let file_type = entry.file_type().map_err(read_failed)?;
+        if file_type.is_dir() {
+            if recursive {
+                collect_files(root, &path, recursive, files)?;
+            }
+        } else if file_type.is_file() || entry.path().is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Returns a relative path as a string with `/` separators.
+#[must_use]
+pub fn relative_path_string(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Matches a `/`-separated relative path against a glob pattern.
+///
+/// Supports `*` (any run of characters except `/`), `?` (one character
+/// except `/`), `**` (anything, including `/`; `**/` also matches no
+/// directory), and `{a,b}` alternatives. A pattern without `/` is matched
+/// against the file name alone, so `*.rs` matches `src/lib.rs`.
+///
+/// # Examples
+///
+/// ```
+/// use rlm_rs::io::glob_match;
+///
+/// assert!(glob_match("*.{rs,md}", "src/lib.rs"));
+/// assert!(glob_match("src/**/*.rs", "src/cli/parser.rs"));
+/// assert!(!glob_match("src/*.rs", "src/cli/parser.rs"));
+/// ```
+#[must_use]
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    let subject = if pattern.contains('/') {
+        path
+    } else {
+        path.rsplit('/').next().unwrap_or(path)
+    };
+    let subject: Vec<char> = subject.chars().collect();
+    expand_braces(pattern).iter().any(|alternative| {
+        let pattern: Vec<char> = alternative.chars().collect();
+        matches(&pattern, &subject)
+    })
+}
+
+/// Expands `{a,b}` groups into one pattern per alternative.
+fn expand_braces(pattern: &str) -> Vec<String> {
+    let Some(open) = pattern.find('{') else {
+        return vec![pattern.to_string()];
+    };
+    let Some(close) = pattern[open..].find('}').map(|i| open + i) else {
+        return vec![pattern.to_string()];
+    };
+    let (prefix, suffix) = (&pattern[..open], &pattern[close + 1..]);
+    pattern[open + 1..close]
+        .split(',')
+        .flat_map(|alternative| expand_braces(&format!("{prefix}{alternative}{suffix}")))
+        .collect()
+}
+
+fn matches(pattern: &[char], subject: &[char]) -> bool {
+    match pattern {
+        [] => subject.is_empty(),
+        ['*', '*', rest @ ..] => {
+            // `**/` may also match no directory at all
+            if let ['/', after @ ..] = rest
+                && matches(after, subject)
+            {
+                return true;
+            }
+            (0..=subject.len()).any(|i| matches(rest, &subject[i..]))
+        }
+        ['*', rest @ ..] => {
+            for i in 0..=subject.len() {
+                if matches(rest, &subject[i..]) {
+                    return true;
+                }
+                if subject.get(i) == Some(&'/') {
+                    break;
+                }
+            }
+            false
+        }
+        ['?', rest @ ..] => {
+            matches!(subject.first(), Some(&c) if c != '/') && matches(rest, &subject[1..])
+        }
+        [c, rest @ ..] => subject.first() == Some(c) && matches(rest, &subject[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*.rs", "lib.rs"));
+        assert!(glob_match("*.rs", "src/lib.rs"));
+        assert!(!glob_match("*.rs", "lib.rs.bak"));
+        assert!(glob_match("src/*.rs", "src/lib.rs"));
+        assert!(!glob_match("src/*.rs", "src/cli/mod.rs"));
+        assert!(glob_match("src/**/*.rs", "src/lib.rs"));
+        assert!(glob_match("src/**/*.rs", "src/cli/mod.rs"));
+        assert!(glob_match("**", "a/b/c"));
+        assert!(glob_match("doc?.md", "docs/doc1.md"));
+        assert!(!glob_match("doc?.md", "doc12.md"));
+        assert!(glob_match("*.{rs,toml}", "Cargo.toml"));
+        assert!(!glob_match("*.{rs,toml}", "README.md"));
+    }
+
+    #[test]
+    fn test_list_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("sub/deeper")).unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        for file in [
+            "b.txt",
+            "a.txt",
+            "sub/c.txt",
+            "sub/deeper/d.txt",
+            ".hidden",
+            ".git/e",
+        ] {
+            std::fs::write(root.join(file), "x").unwrap();
+        }
+
+        let names = |files: Vec<PathBuf>| -> Vec<String> {
+            files.iter().map(|p| relative_path_string(p)).collect()
+        };
+        assert_eq!(
+            names(list_files(root, false).unwrap()),
+            vec!["a.txt", "b.txt"]
+        );
+        assert_eq!(
+            names(list_files(root, true).unwrap()),
+            vec!["a.txt", "b.txt", "sub/c.txt", "sub/deeper/d.txt"]
+        );
+        assert!(list_files(root.join("a.txt"), false).is_err());
+    }
+}
@@ -23,6 +23,7 @@
 pub mod chunking;
 pub mod cli;
 pub mod core;
+pub mod corpus;
 pub mod embedding;
 pub mod error;
 pub mod io;
@@ -47,13 +48,20 @@ pub use cli::{Cli, Commands, OutputFormat};
 // Re-export embedding types
 #[cfg(feature = "fastembed-embeddings")]
 pub use embedding::FastEmbedEmbedder;
+#[cfg(feature = "ollama-embeddings")]
+pub use embedding::OllamaEmbedder;
+#[cfg(feature = "openai-embeddings")]
+pub use embedding::OpenAiEmbedder;
 pub use embedding::{
-    DEFAULT_DIMENSIONS, Embedder, FallbackEmbedder, cosine_similarity, create_embedder,
+    DEFAULT_DIMENSIONS, DEFAULT_EMBED_BATCH_SIZE, Embedder, FallbackEmbedder, SimilarityMetric,
+    cosine_similarity, create_embedder, create_embedder_with_dimensions, euclidean_distance,
+    is_normalized, normalize_embedding, similarity,
 };
 
 // Re-export search types
 pub use search::{
-    DEFAULT_SIMILARITY_THRESHOLD, DEFAULT_TOP_K, RrfConfig, SearchConfig, SearchResult,
-    buffer_fully_embedded, embed_buffer_chunks, hybrid_search, reciprocal_rank_fusion, search_bm25,
+    DEFAULT_SIMILARITY_THRESHOLD, DEFAULT_TOP_K, RrfConfig, SearchConfig, SearchDeadline,
+    SearchResult, buffer_fully_embedded, embed_buffer_chunks, embed_buffer_chunks_batched,
+    embed_buffer_chunks_with_progress, hybrid_search, reciprocal_rank_fusion, search_bm25,
     search_semantic, weighted_rrf,
 };
@@ -4,29 +4,91 @@
 
 #![allow(clippy::print_stdout, clippy::print_stderr)]
 
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches};
 use rlm_rs::cli::output::{OutputFormat, format_error};
-use rlm_rs::cli::{Cli, execute};
+use rlm_rs::cli::{Cli, Config, execute_to};
+use rlm_rs::error::IoError;
+use std::fs::File;
 use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
 
+/// Copies everything written to stdout into a file as well (`--tee`).
+struct Tee<W: Write> {
+    out: W,
+    file: io::BufWriter<File>,
+    path: PathBuf,
+}
+
+impl<W: Write> Tee<W> {
+    fn create(out: W, path: &Path) -> rlm_rs::Result<Self> {
+        let file = File::create(path).map_err(|e| IoError::WriteFailed {
+            path: path.display().to_string(),
+            reason: e.to_string(),
+        })?;
+        Ok(Self {
+            out,
+            file: io::BufWriter::new(file),
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// Names the tee file in its errors, which are otherwise reported as stdout's.
+    fn file_error(&self, e: &io::Error) -> io::Error {
+        io::Error::new(e.kind(), format!("{}: {e}", self.path.display()))
+    }
+}
+
+impl<W: Write> Write for Tee<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Both sides take the whole buffer so a partial write can't
+        // duplicate bytes in the file on retry
+        if let Err(e) = self.file.write_all(buf) {
+            return Err(self.file_error(&e));
+        }
+        self.out.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if let Err(e) = self.file.flush() {
+            return Err(self.file_error(&e));
+        }
+        self.out.flush()
+    }
+}
+
 fn main() -> ExitCode {
-    let cli = Cli::parse();
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    // Project defaults from .rlm/config.toml fill in what flags left unset
+    let configured = std::env::current_dir()
+        .map_err(|e| rlm_rs::Error::Config {
+            message: format!("cannot determine current directory: {e}"),
+        })
+        .and_then(|dir| Config::discover(&dir))
+        .map(|config| cli.apply_config(config, &matches));
     let format = OutputFormat::parse(&cli.format);
 
-    match execute(&cli) {
-        Ok(output) => {
-            if !output.is_empty() {
-                // Handle broken pipe gracefully (e.g., when piped to `head` or `jq`)
-                if let Err(e) = write!(io::stdout(), "{output}")
-                    && e.kind() != io::ErrorKind::BrokenPipe
-                {
-                    eprintln!("Error writing to stdout: {e}");
-                    return ExitCode::FAILURE;
-                }
-            }
-            ExitCode::SUCCESS
+    // Buffered so streamed output is written in large blocks; released
+    // before any error is printed
+    let stdout = io::BufWriter::new(io::stdout().lock());
+    let result = configured.and_then(|()| {
+        if let Some(path) = &cli.tee {
+            // Created up front so a bad path fails before the command runs
+            Tee::create(stdout, path).and_then(|mut out| {
+                cli.configure_thread_pool()
+                    .and_then(|()| execute_to(&cli, &mut out))
+            })
+        } else {
+            let mut out = stdout;
+            cli.configure_thread_pool()
+                .and_then(|()| execute_to(&cli, &mut out))
         }
+    });
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
         Err(e) => {
             let error_output = format_error(&e, format);
             match format {
@@ -0,0 +1,235 @@
+//! Least-recently-used cache of query embeddings.
+//!
+//! Long-lived callers that search with the same query text repeatedly can
+//! attach a [`QueryEmbeddingCache`] to [`SearchConfig`](super::SearchConfig)
+//! so that [`hybrid_search`](super::hybrid_search) embeds each distinct
+//! query only once per model.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+
+use crate::embedding::Embedder;
+use crate::error::Result;
+
+/// Default number of query embeddings kept by [`QueryEmbeddingCache`].
+pub const DEFAULT_QUERY_CACHE_SIZE: usize = 128;
+
+/// Cache key: embedder model name and query text.
+type CacheKey = (&'static str, String);
+
+#[derive(Debug, Default)]
+struct CacheState {
+    /// Embedding and last-use tick per key.
+    entries: HashMap<CacheKey, (Vec<f32>, u64)>,
+    /// Monotonic counter used to order entries by last use.
+    tick: u64,
+}
+
+/// Thread-safe LRU cache of query embeddings, keyed by model and query.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+/// use rlm_rs::embedding::{DEFAULT_DIMENSIONS, FallbackEmbedder};
+/// use rlm_rs::search::{QueryEmbeddingCache, SearchConfig};
+///
+/// let cache = Arc::new(QueryEmbeddingCache::new(64));
+/// let embedder = FallbackEmbedder::new(DEFAULT_DIMENSIONS);
+///
+/// let first = cache.get_or_embed(&embedder, "error handling").unwrap();
+/// let second = cache.get_or_embed(&embedder, "error handling").unwrap();
+/// assert_eq!(first, second);
+/// assert_eq!(cache.len(), 1);
+///
+/// let config = SearchConfig::new().with_query_cache(cache);
+/// ```
+#[derive(Debug)]
+pub struct QueryEmbeddingCache {
+    capacity: usize,
+    state: Mutex<CacheState>,
+}
+
+impl QueryEmbeddingCache {
+    /// Creates a cache holding at most `capacity` embeddings (minimum 1).
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            state: Mutex::new(CacheState::default()),
+        }
+    }
+
+    /// Returns the cached embedding of `query` for this embedder's model,
+    /// embedding and caching it on a miss.
+    ///
+    /// The lock is not held while embedding, so concurrent misses on the
+    /// same query may both call the embedder.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if embedding fails; failures are not cached.
+    pub fn get_or_embed(&self, embedder: &dyn Embedder, query: &str) -> Result<Vec<f32>> {
+        let key = (embedder.model_name(), query.to_string());
+
+        if let Some(embedding) = self.lookup(&key) {
+            return Ok(embedding);
+        }
+
+        let embedding = embedder.embed(query)?;
+        self.insert(key, embedding.clone());
+        Ok(embedding)
+    }
+
+    /// Returns the maximum number of cached embeddings.
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of cached embeddings.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Returns whether the cache is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all cached embeddings.
+    pub fn clear(&self) {
+        self.lock().entries.clear();
+    }
+
+    fn lookup(&self, key: &CacheKey) -> Option<Vec<f32>> {
+        let mut state = self.lock();
+        state.tick += 1;
+        let tick = state.tick;
+        state.entries.get_mut(key).map(|(embedding, last_used)| {
+            *last_used = tick;
+            embedding.clone()
+        })
+    }
+
+    fn insert(&self, key: CacheKey, embedding: Vec<f32>) {
+        let mut state = self.lock();
+        state.tick += 1;
+        let tick = state.tick;
+
+        if !state.entries.contains_key(&key) && state.entries.len() >= self.capacity {
+            let oldest = state
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                state.entries.remove(&oldest);
+            }
+        }
+
+        state.entries.insert(key, (embedding, tick));
+    }
+
+    /// Locks the state, recovering from a poisoned lock since entries are
+    /// always left consistent.
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Default for QueryEmbeddingCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_QUERY_CACHE_SIZE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embedding::{DEFAULT_DIMENSIONS, FallbackEmbedder};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Embedder that counts `embed` calls.
+    struct CountingEmbedder {
+        inner: FallbackEmbedder,
+        calls: AtomicUsize,
+    }
+
+    impl CountingEmbedder {
+        fn new() -> Self {
+            Self {
+                inner: FallbackEmbedder::new(DEFAULT_DIMENSIONS),
+                calls: AtomicUsize::new(0),
+            }
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    impl Embedder for CountingEmbedder {
+        fn dimensions(&self) -> usize {
+            self.inner.dimensions()
+        }
+
+        fn model_name(&self) -> &'static str {
+            self.inner.model_name()
+        }
+
+        fn embed(&self, text: &str) -> Result<Vec<f32>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            self.inner.embed(text)
+        }
+    }
+
+    #[test]
+    fn test_cache_hit_skips_embedder() {
+        let cache = QueryEmbeddingCache::new(4);
+        let embedder = CountingEmbedder::new();
+
+        let first = cache.get_or_embed(&embedder, "query").unwrap();
+        let second = cache.get_or_embed(&embedder, "query").unwrap();
+        assert_eq!(first, second);
+        assert_eq!(embedder.calls(), 1);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let cache = QueryEmbeddingCache::new(2);
+        let embedder = CountingEmbedder::new();
+
+        cache.get_or_embed(&embedder, "a").unwrap();
+        cache.get_or_embed(&embedder, "b").unwrap();
+        // Touch "a" so "b" becomes the oldest
+        cache.get_or_embed(&embedder, "a").unwrap();
+        cache.get_or_embed(&embedder, "c").unwrap();
+        assert_eq!(cache.len(), 2);
+        assert_eq!(embedder.calls(), 3);
+
+        cache.get_or_embed(&embedder, "a").unwrap();
+        assert_eq!(embedder.calls(), 3);
+        cache.get_or_embed(&embedder, "b").unwrap();
+        assert_eq!(embedder.calls(), 4);
+    }
+
+    #[test]
+    fn test_cache_clear_and_capacity() {
+        let cache = QueryEmbeddingCache::new(0);
+        assert_eq!(cache.capacity(), 1);
+        assert_eq!(
+            QueryEmbeddingCache::default().capacity(),
+            DEFAULT_QUERY_CACHE_SIZE
+        );
+
+        let embedder = CountingEmbedder::new();
+        cache.get_or_embed(&embedder, "a").unwrap();
+        assert!(!cache.is_empty());
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+}
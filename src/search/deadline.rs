@@ -0,0 +1,97 @@
+//! Time limits for searches.
+//!
+//! A [`SearchDeadline`] attached to a [`SearchConfig`](super::SearchConfig)
+//! bounds the semantic scan and the BM25 query. Search paths that run out of
+//! time return what they have scored so far and record it on the deadline,
+//! so the caller can report the results as partial.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// A point in time after which searches stop early.
+///
+/// Shared between the caller and the search through an `Arc`; after the
+/// search, [`expired`](Self::expired) tells whether any path was cut short.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+/// use std::time::Duration;
+/// use rlm_rs::search::{SearchConfig, SearchDeadline};
+///
+/// let deadline = Arc::new(SearchDeadline::after(Duration::from_millis(200)));
+/// let config = SearchConfig::new().with_deadline(Arc::clone(&deadline));
+/// // ... run hybrid_search with config ...
+/// let partial = deadline.expired();
+/// ```
+#[derive(Debug)]
+pub struct SearchDeadline {
+    /// `None` when the timeout is too large to represent, i.e. never.
+    at: Option<Instant>,
+    expired: AtomicBool,
+}
+
+impl SearchDeadline {
+    /// Creates a deadline `timeout` from now.
+    #[must_use]
+    pub fn after(timeout: Duration) -> Self {
+        Self {
+            at: Instant::now().checked_add(timeout),
+            expired: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the time left before the deadline (zero once it has passed).
+    #[must_use]
+    pub fn remaining(&self) -> Duration {
+        self.at.map_or(Duration::MAX, |at| {
+            at.saturating_duration_since(Instant::now())
+        })
+    }
+
+    /// Returns `true` if the deadline has passed, recording that a search
+    /// path was cut short.
+    pub fn check(&self) -> bool {
+        let passed = self.at.is_some_and(|at| Instant::now() >= at);
+        if passed {
+            self.mark_expired();
+        }
+        passed
+    }
+
+    /// Records that a search path was cut short.
+    pub fn mark_expired(&self) {
+        self.expired.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if any search path stopped early at this deadline.
+    #[must_use]
+    pub fn expired(&self) -> bool {
+        self.expired.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deadline_records_expiry() {
+        let deadline = SearchDeadline::after(Duration::from_secs(60));
+        assert!(!deadline.check());
+        assert!(!deadline.expired());
+        assert!(deadline.remaining() > Duration::ZERO);
+
+        let deadline = SearchDeadline::after(Duration::ZERO);
+        assert!(!deadline.expired());
+        assert!(deadline.check());
+        assert!(deadline.expired());
+        assert_eq!(deadline.remaining(), Duration::ZERO);
+
+        // Timeouts too large to represent never pass
+        let deadline = SearchDeadline::after(Duration::MAX);
+        assert!(!deadline.check());
+        assert_eq!(deadline.remaining(), Duration::MAX);
+    }
+}
@@ -0,0 +1,167 @@
+//! In-memory approximate nearest-neighbor index over stored embeddings.
+//!
+//! [`SemanticIndex`] loads every chunk embedding into an HNSW index so
+//! semantic queries no longer compare against each stored vector. The
+//! index remembers the storage [`EmbeddingFingerprint`] it was built from;
+//! [`hybrid_search`](super::hybrid_search) falls back to a brute-force scan
+//! once storage has changed.
+
+use crate::embedding::DEFAULT_DIMENSIONS;
+use crate::error::{Result, SearchError};
+use crate::search::hnsw::{HnswConfig, HnswIndex};
+use crate::storage::{EmbeddingFingerprint, SqliteStorage};
+
+/// Approximate nearest-neighbor index over all stored chunk embeddings.
+///
+/// Requires the `usearch-hnsw` feature; without it [`SemanticIndex::build`]
+/// returns [`SearchError::FeatureNotEnabled`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use rlm_rs::search::{SearchConfig, SemanticIndex};
+/// use rlm_rs::storage::SqliteStorage;
+///
+/// let storage = SqliteStorage::open("rlm.db").unwrap();
+/// let index = SemanticIndex::build(&storage).unwrap();
+/// let config = SearchConfig::new().with_semantic_index(Arc::new(index));
+/// ```
+#[derive(Debug)]
+pub struct SemanticIndex {
+    hnsw: HnswIndex,
+    fingerprint: EmbeddingFingerprint,
+}
+
+impl SemanticIndex {
+    /// Builds an index over every embedding in storage.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `usearch-hnsw` feature is not enabled,
+    /// embeddings cannot be read, or they have mixed dimensions.
+    pub fn build(storage: &SqliteStorage) -> Result<Self> {
+        if !HnswIndex::is_available() {
+            return Err(SearchError::FeatureNotEnabled {
+                feature: "usearch-hnsw".to_string(),
+            }
+            .into());
+        }
+
+        let fingerprint = storage.embedding_fingerprint()?;
+        let embeddings = storage.get_all_embeddings()?;
+        let dimensions = embeddings
+            .first()
+            .map(|(_, embedding)| embedding.len())
+            .or_else(|| storage.embedding_dimensions())
+            .unwrap_or(DEFAULT_DIMENSIONS);
+
+        let mut hnsw = HnswIndex::new(&HnswConfig::with_dimensions(dimensions))?;
+        if !embeddings.is_empty() {
+            hnsw.add_batch(&embeddings)?;
+        }
+
+        Ok(Self { hnsw, fingerprint })
+    }
+
+    /// Returns the `top_k` nearest chunks as `(chunk_id, similarity)` pairs,
+    /// most similar first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query dimensions don't match the index.
+    pub fn query(&self, embedding: &[f32], top_k: usize) -> Result<Vec<(i64, f32)>> {
+        Ok(self
+            .hnsw
+            .search(embedding, top_k)?
+            .into_iter()
+            .map(|result| (result.chunk_id, result.similarity))
+            .collect())
+    }
+
+    /// Returns the number of indexed embeddings.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.fingerprint.count
+    }
+
+    /// Returns whether the index has no embeddings.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.fingerprint.count == 0
+    }
+
+    /// Returns the storage fingerprint the index was built from.
+    #[must_use]
+    pub const fn fingerprint(&self) -> EmbeddingFingerprint {
+        self.fingerprint
+    }
+
+    /// Returns whether storage embeddings changed since the index was built.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the storage query fails.
+    pub fn is_stale(&self, storage: &SqliteStorage) -> Result<bool> {
+        Ok(storage.embedding_fingerprint()? != self.fingerprint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Storage;
+
+    fn setup() -> SqliteStorage {
+        let mut storage = SqliteStorage::in_memory().unwrap();
+        storage.init().unwrap();
+        storage
+    }
+
+    #[test]
+    #[cfg(not(feature = "usearch-hnsw"))]
+    fn test_semantic_index_requires_feature() {
+        let storage = setup();
+        assert!(SemanticIndex::build(&storage).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "usearch-hnsw")]
+    fn test_semantic_index_build_query_and_staleness() {
+        use crate::core::{Buffer, Chunk};
+
+        let mut storage = setup();
+        let buffer_id = storage
+            .add_buffer(&Buffer::from_content("abc".to_string()))
+            .unwrap();
+        let chunks: Vec<Chunk> = (0..3)
+            .map(|i| Chunk::new(buffer_id, "x".to_string(), i..i + 1, i))
+            .collect();
+        storage.add_chunks(buffer_id, &chunks).unwrap();
+        let ids: Vec<i64> = storage
+            .get_chunks(buffer_id)
+            .unwrap()
+            .iter()
+            .filter_map(|c| c.id)
+            .collect();
+        storage
+            .store_embedding(ids[0], &[1.0, 0.0, 0.0], None)
+            .unwrap();
+        storage
+            .store_embedding(ids[1], &[0.0, 1.0, 0.0], None)
+            .unwrap();
+
+        let index = SemanticIndex::build(&storage).unwrap();
+        assert_eq!(index.len(), 2);
+        assert!(!index.is_stale(&storage).unwrap());
+
+        let results = index.query(&[0.9, 0.1, 0.0], 2).unwrap();
+        assert_eq!(results[0].0, ids[0]);
+        assert!(results[0].1 > results[1].1);
+
+        storage
+            .store_embedding(ids[2], &[0.0, 0.0, 1.0], None)
+            .unwrap();
+        assert!(index.is_stale(&storage).unwrap());
+    }
+}
@@ -0,0 +1,458 @@
+//! Inverted-file (IVF) index for pre-filtered semantic search.
+//!
+//! Embeddings are clustered with spherical k-means, and the centroids and
+//! cluster assignments are stored in `SQLite` (see
+//! [`SqliteStorage::replace_ivf_index`]). A query is compared with the
+//! centroids first and only the chunks in the `nprobe` nearest clusters are
+//! scanned, trading recall for speed. Embeddings stored after a build are
+//! assigned to their nearest centroid; the clusters themselves only change
+//! when [`build_ivf_index`] runs again.
+
+// Counts and fractions are small enough for f64
+#![allow(clippy::cast_precision_loss)]
+
+use crate::corpus::CorpusRng;
+use crate::embedding::{cosine_similarity, normalize_embedding};
+use crate::error::Result;
+use crate::storage::SqliteStorage;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// Default number of clusters scanned per query.
+pub const DEFAULT_IVF_NPROBE: usize = 4;
+
+/// Default number of k-means iterations.
+pub const DEFAULT_IVF_ITERATIONS: usize = 10;
+
+/// Default number of sampled queries for recall estimates.
+pub const DEFAULT_RECALL_SAMPLES: usize = 50;
+
+/// Chunk IDs paired with their embeddings.
+type Embeddings = Vec<(i64, Vec<f32>)>;
+
+/// Result of clustering with [`kmeans`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Clustering {
+    /// Unit-length cluster centroids.
+    pub centroids: Vec<Vec<f32>>,
+    /// Index into `centroids` for each input vector.
+    pub assignments: Vec<usize>,
+    /// Iterations run before the assignments stopped changing.
+    pub iterations: usize,
+}
+
+/// Summary of an IVF index build.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct IvfBuild {
+    /// Number of clusters.
+    pub clusters: usize,
+    /// Dimensions of the clustered embeddings.
+    pub dimensions: usize,
+    /// Embeddings assigned to a cluster.
+    pub assigned: usize,
+    /// Embeddings of other dimensions, left unassigned (always scanned).
+    pub skipped: usize,
+    /// k-means iterations run.
+    pub iterations: usize,
+}
+
+/// Estimated recall of IVF search compared with an exact scan.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct IvfRecall {
+    /// Clusters scanned per query.
+    pub nprobe: usize,
+    /// Neighbors compared per query.
+    pub top_k: usize,
+    /// Number of sampled queries.
+    pub samples: usize,
+    /// Fraction of the exact top-k neighbors that IVF search also found.
+    pub recall: f64,
+    /// Average fraction of embeddings scanned per query.
+    pub scanned: f64,
+}
+
+/// Returns the default number of clusters for `n` embeddings (about `sqrt(n)`).
+#[must_use]
+pub const fn default_cluster_count(n: usize) -> usize {
+    let k = n.isqrt();
+    if k == 0 { 1 } else { k }
+}
+
+/// Clusters vectors with spherical k-means (cosine similarity).
+///
+/// Seeds the first centroid with `seed` and each following one with the
+/// vector least similar to those picked so far, then stops once
+/// assignments no longer change or after `iterations` rounds. A cluster
+/// that loses all its members keeps its previous centroid. `k` is capped at
+/// the number of vectors.
+#[must_use]
+pub fn kmeans(vectors: &[Vec<f32>], k: usize, iterations: usize, seed: u64) -> Clustering {
+    let k = k.min(vectors.len());
+    if k == 0 {
+        return Clustering::default();
+    }
+    let dimensions = vectors[0].len();
+
+    // Farthest-point seeding spreads the initial centroids across the data
+    let first = CorpusRng::new(seed).range(0, vectors.len() - 1);
+    let mut centroids = vec![normalize_embedding(&vectors[first])];
+    let mut closest: Vec<f32> = vectors
+        .iter()
+        .map(|vector| cosine_similarity(vector, &centroids[0]))
+        .collect();
+    while centroids.len() < k {
+        let next = closest
+            .iter()
+            .enumerate()
+            .min_by(|a, b| a.1.total_cmp(b.1))
+            .map_or(0, |(i, _)| i);
+        let centroid = normalize_embedding(&vectors[next]);
+        for (sim, vector) in closest.iter_mut().zip(vectors) {
+            *sim = sim.max(cosine_similarity(vector, &centroid));
+        }
+        centroids.push(centroid);
+    }
+
+    let assign = |centroids: &[Vec<f32>]| -> Vec<usize> {
+        vectors
+            .par_iter()
+            .map(|vector| nearest_centroid(vector, centroids))
+            .collect()
+    };
+
+    let mut assignments = assign(&centroids);
+    let mut run = 1;
+    while run < iterations {
+        let mut sums = vec![vec![0.0_f32; dimensions]; k];
+        for (vector, &cluster) in vectors.iter().zip(&assignments) {
+            let unit = normalize_embedding(vector);
+            for (sum, value) in sums[cluster].iter_mut().zip(unit) {
+                *sum += value;
+            }
+        }
+        for (centroid, sum) in centroids.iter_mut().zip(sums) {
+            if sum.iter().any(|v| *v != 0.0) {
+                *centroid = normalize_embedding(&sum);
+            }
+        }
+
+        run += 1;
+        let next = assign(&centroids);
+        if next == assignments {
+            break;
+        }
+        assignments = next;
+    }
+
+    Clustering {
+        centroids,
+        assignments,
+        iterations: run,
+    }
+}
+
+/// Clusters the stored embeddings and replaces the IVF index.
+///
+/// Embeddings of the most common dimension are clustered into `clusters`
+/// groups ([`default_cluster_count`] when `None`); others are left
+/// unassigned. Embeddings of trashed buffers are left unassigned too.
+///
+/// # Errors
+///
+/// Returns an error if embeddings can't be read or the index can't be
+/// stored.
+pub fn build_ivf_index(
+    storage: &mut SqliteStorage,
+    clusters: Option<usize>,
+    iterations: usize,
+    seed: u64,
+) -> Result<IvfBuild> {
+    let embeddings = storage.get_all_embeddings()?;
+    let total = embeddings.len();
+
+    let mut by_dimensions: HashMap<usize, usize> = HashMap::new();
+    for (_, embedding) in &embeddings {
+        *by_dimensions.entry(embedding.len()).or_default() += 1;
+    }
+    let dimensions = by_dimensions
+        .into_iter()
+        .max_by_key(|&(dimensions, count)| (count, dimensions))
+        .map_or(0, |(dimensions, _)| dimensions);
+
+    let (ids, vectors): (Vec<i64>, Vec<Vec<f32>>) = embeddings
+        .into_iter()
+        .filter(|(_, embedding)| embedding.len() == dimensions)
+        .unzip();
+    let k = clusters.unwrap_or_else(|| default_cluster_count(vectors.len()));
+    let clustering = kmeans(&vectors, k, iterations, seed);
+
+    let assignments: Vec<(i64, usize)> = ids
+        .iter()
+        .copied()
+        .zip(clustering.assignments.iter().copied())
+        .collect();
+    storage.replace_ivf_index(&clustering.centroids, &assignments)?;
+
+    Ok(IvfBuild {
+        clusters: clustering.centroids.len(),
+        dimensions,
+        assigned: assignments.len(),
+        skipped: total - assignments.len(),
+        iterations: clustering.iterations,
+    })
+}
+
+/// Gets the embeddings to scan for `query`: those in the `nprobe` clusters
+/// nearest to it, plus any not assigned to a cluster.
+///
+/// Returns `None` when there is no IVF index for the query's dimensions,
+/// in which case every embedding should be scanned.
+///
+/// # Errors
+///
+/// Returns an error if the index or embeddings can't be read.
+pub fn probe(
+    storage: &SqliteStorage,
+    query: &[f32],
+    nprobe: usize,
+    content_type: Option<&str>,
+) -> Result<Option<Embeddings>> {
+    let centroids = storage.ivf_centroids()?;
+    let probed = nearest_clusters(query, &centroids, nprobe);
+    if probed.is_empty() {
+        return Ok(None);
+    }
+    storage
+        .get_embeddings_in_clusters(&probed, content_type)
+        .map(Some)
+}
+
+/// Estimates the recall of IVF search at `nprobe` against an exact scan.
+///
+/// Up to `samples` stored embeddings (picked with `seed`) serve as queries;
+/// recall is the fraction of each one's exact `top_k` neighbors that the
+/// probed clusters also return. Returns `None` without an IVF index.
+///
+/// # Errors
+///
+/// Returns an error if the index or embeddings can't be read.
+pub fn estimate_ivf_recall(
+    storage: &SqliteStorage,
+    nprobe: usize,
+    top_k: usize,
+    samples: usize,
+    seed: u64,
+) -> Result<Option<IvfRecall>> {
+    let centroids = storage.ivf_centroids()?;
+    let Some(dimensions) = centroids.first().map(|(_, c)| c.len()) else {
+        return Ok(None);
+    };
+    let embeddings: Embeddings = storage
+        .get_all_embeddings()?
+        .into_iter()
+        .filter(|(_, embedding)| embedding.len() == dimensions)
+        .collect();
+    if embeddings.is_empty() || top_k == 0 {
+        return Ok(None);
+    }
+    let assignments: HashMap<i64, i64> = storage.ivf_assignments()?.into_iter().collect();
+
+    let mut rng = CorpusRng::new(seed);
+    let mut order: Vec<usize> = (0..embeddings.len()).collect();
+    let samples = samples.min(order.len());
+    for i in 0..samples {
+        let j = rng.range(i, order.len() - 1);
+        order.swap(i, j);
+    }
+
+    let (hits, expected, scanned) = order[..samples]
+        .par_iter()
+        .map(|&i| {
+            let query = &embeddings[i].1;
+            let exact = top_ids(query, embeddings.iter(), top_k);
+
+            let probed: HashSet<i64> = nearest_clusters(query, &centroids, nprobe)
+                .into_iter()
+                .collect();
+            let candidates: Vec<&(i64, Vec<f32>)> = embeddings
+                .iter()
+                .filter(|(id, _)| {
+                    assignments
+                        .get(id)
+                        .is_none_or(|cluster| probed.contains(cluster))
+                })
+                .collect();
+            let approx: HashSet<i64> = top_ids(query, candidates.iter().copied(), top_k)
+                .into_iter()
+                .collect();
+
+            let hits = exact.iter().filter(|id| approx.contains(id)).count();
+            let scanned = candidates.len() as f64 / embeddings.len() as f64;
+            (hits, exact.len(), scanned)
+        })
+        .reduce(|| (0, 0, 0.0), |a, b| (a.0 + b.0, a.1 + b.1, a.2 + b.2));
+
+    Ok(Some(IvfRecall {
+        nprobe,
+        top_k,
+        samples,
+        recall: if expected == 0 {
+            1.0
+        } else {
+            hits as f64 / expected as f64
+        },
+        scanned: if samples == 0 {
+            0.0
+        } else {
+            scanned / samples as f64
+        },
+    }))
+}
+
+/// Returns the IDs of the `nprobe` centroids most similar to `query`,
+/// ignoring centroids of other dimensions.
+fn nearest_clusters(query: &[f32], centroids: &[(i64, Vec<f32>)], nprobe: usize) -> Vec<i64> {
+    let mut scored: Vec<(i64, f32)> = centroids
+        .iter()
+        .filter(|(_, centroid)| centroid.len() == query.len())
+        .map(|(id, centroid)| (*id, cosine_similarity(query, centroid)))
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    scored.into_iter().take(nprobe).map(|(id, _)| id).collect()
+}
+
+/// Returns the index of the centroid most similar to `vector`.
+fn nearest_centroid(vector: &[f32], centroids: &[Vec<f32>]) -> usize {
+    centroids
+        .iter()
+        .enumerate()
+        .map(|(i, centroid)| (i, cosine_similarity(vector, centroid)))
+        .fold((0, f32::NEG_INFINITY), |best, (i, sim)| {
+            if sim > best.1 { (i, sim) } else { best }
+        })
+        .0
+}
+
+/// Returns the IDs of the `k` embeddings most similar to `query`.
+fn top_ids<'a>(
+    query: &[f32],
+    embeddings: impl Iterator<Item = &'a (i64, Vec<f32>)>,
+    k: usize,
+) -> Vec<i64> {
+    let mut scored: Vec<(i64, f32)> = embeddings
+        .map(|(id, embedding)| (*id, cosine_similarity(query, embedding)))
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    scored.into_iter().take(k).map(|(id, _)| id).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Buffer, Chunk};
+    use crate::storage::Storage;
+
+    /// Two tight groups of vectors around the x and y axes.
+    fn two_groups() -> Vec<Vec<f32>> {
+        (0..10)
+            .map(|i| {
+                let jitter = i as f32 * 0.01;
+                if i % 2 == 0 {
+                    vec![1.0, jitter, 0.0]
+                } else {
+                    vec![jitter, 1.0, 0.0]
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_default_cluster_count() {
+        assert_eq!(default_cluster_count(0), 1);
+        assert_eq!(default_cluster_count(3), 1);
+        assert_eq!(default_cluster_count(100), 10);
+    }
+
+    #[test]
+    fn test_kmeans_separates_groups() {
+        let vectors = two_groups();
+        let clustering = kmeans(&vectors, 2, DEFAULT_IVF_ITERATIONS, 7);
+        assert_eq!(clustering.centroids.len(), 2);
+        for (i, &cluster) in clustering.assignments.iter().enumerate() {
+            assert_eq!(cluster, clustering.assignments[i % 2]);
+        }
+        assert_ne!(clustering.assignments[0], clustering.assignments[1]);
+
+        // k is capped at the number of vectors
+        assert_eq!(kmeans(&vectors[..1], 5, 3, 7).centroids.len(), 1);
+        assert_eq!(kmeans(&[], 5, 3, 7), Clustering::default());
+    }
+
+    #[test]
+    fn test_build_probe_and_recall() {
+        let mut storage = SqliteStorage::in_memory().unwrap();
+        storage.init().unwrap();
+        let buffer_id = storage
+            .add_buffer(&Buffer::from_content("ivf".to_string()))
+            .unwrap();
+        let chunks: Vec<Chunk> = (0..10)
+            .map(|i| Chunk::new(buffer_id, format!("chunk {i}"), i..i + 1, i))
+            .collect();
+        storage.add_chunks(buffer_id, &chunks).unwrap();
+        let ids: Vec<i64> = storage
+            .get_chunks(buffer_id)
+            .unwrap()
+            .iter()
+            .filter_map(|c| c.id)
+            .collect();
+        let batch: Vec<(i64, Vec<f32>)> = ids.iter().copied().zip(two_groups()).collect();
+        storage.store_embeddings_batch(&batch, None).unwrap();
+
+        assert!(
+            probe(&storage, &[1.0, 0.0, 0.0], 1, None)
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            estimate_ivf_recall(&storage, 1, 3, 10, 1)
+                .unwrap()
+                .is_none()
+        );
+
+        let build = build_ivf_index(&mut storage, Some(2), DEFAULT_IVF_ITERATIONS, 7).unwrap();
+        assert_eq!(build.clusters, 2);
+        assert_eq!(build.assigned, 10);
+        assert_eq!(build.skipped, 0);
+
+        // One probe scans only the x-axis group
+        let candidates = probe(&storage, &[1.0, 0.0, 0.0], 1, None).unwrap().unwrap();
+        assert_eq!(candidates.len(), 5);
+        assert!(
+            candidates
+                .iter()
+                .all(|(_, e)| (e[0] - 1.0).abs() < f32::EPSILON)
+        );
+
+        // New embeddings join their nearest cluster
+        let extra = storage
+            .add_buffer(&Buffer::from_content("late".to_string()))
+            .unwrap();
+        storage
+            .add_chunks(extra, &[Chunk::new(extra, "late".to_string(), 0..4, 0)])
+            .unwrap();
+        let late_id = storage.get_chunks(extra).unwrap()[0].id.unwrap();
+        storage
+            .store_embedding(late_id, &[0.9, 0.1, 0.0], None)
+            .unwrap();
+        let candidates = probe(&storage, &[1.0, 0.0, 0.0], 1, None).unwrap().unwrap();
+        assert!(candidates.iter().any(|(id, _)| *id == late_id));
+
+        // Recall is exact when every cluster is probed
+        let full = estimate_ivf_recall(&storage, 2, 3, 10, 1).unwrap().unwrap();
+        assert!((full.recall - 1.0).abs() < f64::EPSILON);
+        assert!((full.scanned - 1.0).abs() < f64::EPSILON);
+        let partial = estimate_ivf_recall(&storage, 1, 3, 10, 1).unwrap().unwrap();
+        assert!(partial.scanned < 1.0);
+    }
+}
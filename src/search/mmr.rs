@@ -0,0 +1,140 @@
+//! Maximal Marginal Relevance (MMR) re-ranking.
+//!
+//! Re-orders a ranked list so that each pick balances relevance against
+//! similarity to the items already picked, reducing near-duplicates.
+//! Based on: Carbonell, Goldstein (1998) - "The Use of MMR, Diversity-Based
+//! Reranking for Reordering Documents and Producing Summaries"
+
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+
+use crate::embedding::cosine_similarity;
+
+/// Re-ranks candidates with Maximal Marginal Relevance.
+///
+/// Each step selects the remaining candidate maximizing
+/// `lambda * relevance - (1 - lambda) * max_sim`, where `relevance` is the
+/// candidate's score normalized by the highest score and `max_sim` is its
+/// highest cosine similarity to an already selected candidate. A candidate
+/// without an embedding has similarity 0 to everything, so it is treated
+/// as maximally diverse.
+///
+/// # Arguments
+///
+/// * `candidates` - `(item_id, score)` pairs, higher scores more relevant.
+/// * `embeddings` - Embeddings for the candidates that have one.
+/// * `lambda` - Relevance weight in `[0, 1]`; `1.0` keeps the original order.
+///
+/// # Returns
+///
+/// All candidate IDs in MMR selection order.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use rlm_rs::search::mmr_rerank;
+///
+/// let candidates = vec![(1, 1.0), (2, 0.9), (3, 0.5)];
+/// let embeddings = HashMap::from([
+///     (1, vec![1.0, 0.0]),
+///     (2, vec![1.0, 0.0]), // duplicate of 1
+///     (3, vec![0.0, 1.0]),
+/// ]);
+///
+/// let order = mmr_rerank(&candidates, &embeddings, 0.5);
+/// assert_eq!(order, vec![1, 3, 2]);
+/// ```
+#[must_use]
+pub fn mmr_rerank<S: BuildHasher>(
+    candidates: &[(i64, f64)],
+    embeddings: &HashMap<i64, Vec<f32>, S>,
+    lambda: f32,
+) -> Vec<i64> {
+    let lambda = f64::from(lambda.clamp(0.0, 1.0));
+    let max_score = candidates
+        .iter()
+        .map(|(_, score)| *score)
+        .fold(0.0_f64, f64::max);
+
+    // (id, normalized relevance, highest similarity to the selected set)
+    let mut remaining: Vec<(i64, f64, f64)> = candidates
+        .iter()
+        .map(|&(id, score)| {
+            let relevance = if max_score > 0.0 {
+                score / max_score
+            } else {
+                0.0
+            };
+            (id, relevance, 0.0)
+        })
+        .collect();
+    let mut selected = Vec::with_capacity(remaining.len());
+
+    while !remaining.is_empty() {
+        let mut best = 0;
+        let mut best_mmr = f64::NEG_INFINITY;
+        for (i, &(_, relevance, max_sim)) in remaining.iter().enumerate() {
+            let mmr = lambda.mul_add(relevance, -(1.0 - lambda) * max_sim);
+            if mmr > best_mmr {
+                best = i;
+                best_mmr = mmr;
+            }
+        }
+
+        let (picked, _, _) = remaining.remove(best);
+        selected.push(picked);
+
+        // Update each remaining candidate's similarity to the selected set
+        if let Some(picked_embedding) = embeddings.get(&picked) {
+            for (id, _, max_sim) in &mut remaining {
+                if let Some(embedding) = embeddings.get(id) {
+                    let sim = f64::from(cosine_similarity(picked_embedding, embedding));
+                    *max_sim = max_sim.max(sim);
+                }
+            }
+        }
+    }
+
+    selected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mmr_lambda_one_keeps_order() {
+        let candidates = vec![(1, 0.9), (2, 0.8), (3, 0.7)];
+        let embeddings = HashMap::from([(1, vec![1.0]), (2, vec![1.0]), (3, vec![1.0])]);
+        assert_eq!(mmr_rerank(&candidates, &embeddings, 1.0), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_mmr_demotes_duplicates() {
+        let candidates = vec![(1, 1.0), (2, 0.95), (3, 0.9), (4, 0.6)];
+        let embeddings = HashMap::from([
+            (1, vec![1.0, 0.0, 0.0]),
+            (2, vec![1.0, 0.0, 0.0]),
+            (3, vec![0.99, 0.1, 0.0]),
+            (4, vec![0.0, 0.0, 1.0]),
+        ]);
+        let order = mmr_rerank(&candidates, &embeddings, 0.5);
+        assert_eq!(order[0], 1);
+        assert_eq!(order[1], 4);
+        assert_eq!(order.len(), 4);
+    }
+
+    #[test]
+    fn test_mmr_missing_embeddings_are_diverse() {
+        let candidates = vec![(1, 1.0), (2, 0.9), (3, 0.5)];
+        // Chunk 3 has no embedding; chunk 2 duplicates chunk 1
+        let embeddings = HashMap::from([(1, vec![1.0, 0.0]), (2, vec![1.0, 0.0])]);
+        assert_eq!(mmr_rerank(&candidates, &embeddings, 0.5), vec![1, 3, 2]);
+    }
+
+    #[test]
+    fn test_mmr_empty() {
+        assert!(mmr_rerank(&[], &HashMap::new(), 0.5).is_empty());
+    }
+}
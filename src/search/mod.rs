@@ -7,17 +7,40 @@
 //! - **Semantic Search**: Vector similarity using embeddings
 //! - **BM25 Search**: Full-text search using `SQLite` `FTS5`
 //! - **Hybrid Search**: Combines both using Reciprocal Rank Fusion
+//! - **MMR Re-ranking**: Optional Maximal Marginal Relevance pass to diversify results
 //! - **HNSW Index**: Optional scalable approximate nearest neighbor search (requires `usearch-hnsw` feature)
+//! - **Semantic Index**: Prebuilt HNSW index over stored embeddings, used by hybrid search for large corpora
+//! - **Query Cache**: Optional LRU cache of query embeddings for long-lived callers
+//! - **IVF Index**: Optional clustered index that limits semantic scans to the nearest clusters
 
+mod cache;
+mod deadline;
 pub mod hnsw;
+mod index;
+mod ivf;
+mod mmr;
 mod rrf;
 
+pub use cache::{DEFAULT_QUERY_CACHE_SIZE, QueryEmbeddingCache};
+pub use deadline::SearchDeadline;
 pub use hnsw::{HnswConfig, HnswIndex, HnswResult};
+pub use index::SemanticIndex;
+pub use ivf::{
+    Clustering, DEFAULT_IVF_ITERATIONS, DEFAULT_IVF_NPROBE, DEFAULT_RECALL_SAMPLES, IvfBuild,
+    IvfRecall, build_ivf_index, default_cluster_count, estimate_ivf_recall, kmeans,
+};
+pub use mmr::mmr_rerank;
 pub use rrf::{RrfConfig, reciprocal_rank_fusion, weighted_rrf};
 
-use crate::embedding::{Embedder, cosine_similarity};
+use crate::core::Chunk;
+use crate::embedding::{
+    DEFAULT_EMBED_BATCH_SIZE, Embedder, SimilarityMetric, normalize_embedding, similarity,
+};
 use crate::error::Result;
 use crate::storage::{SqliteStorage, Storage};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
 
 /// Default similarity threshold for semantic search.
 pub const DEFAULT_SIMILARITY_THRESHOLD: f32 = 0.3;
@@ -25,8 +48,11 @@ pub const DEFAULT_SIMILARITY_THRESHOLD: f32 = 0.3;
 /// Default number of results to return.
 pub const DEFAULT_TOP_K: usize = 10;
 
+/// Default embedding count up to which semantic search scans every vector.
+pub const DEFAULT_BRUTE_FORCE_LIMIT: usize = 10_000;
+
 /// Search result with chunk ID and combined score.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResult {
     /// Chunk ID.
     pub chunk_id: i64,
@@ -42,6 +68,12 @@ pub struct SearchResult {
     pub bm25_score: Option<f64>,
     /// Content preview (first N characters, if requested).
     pub content_preview: Option<String>,
+    /// Chunk title from chunk metadata (if any).
+    pub title: Option<String>,
+    /// Excerpt around the BM25 match with matched terms in `[` `]` (if
+    /// requested).
+    #[serde(default)]
+    pub snippet: Option<String>,
 }
 
 /// Configuration for hybrid search.
@@ -49,24 +81,98 @@ pub struct SearchResult {
 pub struct SearchConfig {
     /// Maximum number of results to return.
     pub top_k: usize,
+    /// Number of top-ranked results to skip, for paging past `top_k`.
+    ///
+    /// Applied after fusion, boosting, re-ranking, and `chunk_filter`, so
+    /// consecutive pages never overlap or leave gaps.
+    pub offset: usize,
     /// Minimum similarity threshold for semantic results.
     pub similarity_threshold: f32,
     /// RRF k parameter (default 60).
     pub rrf_k: u32,
+    /// Weight of the semantic list in hybrid rank fusion (default 1.0).
+    ///
+    /// Each list contributes `weight / (rrf_k + rank)` to a chunk's score,
+    /// so weights scale a list's influence at every rank, while `rrf_k`
+    /// controls how quickly that influence decays down the list. `0.0`
+    /// ranks by the other list alone.
+    pub semantic_weight: f64,
+    /// Weight of the BM25 list in hybrid rank fusion (default 1.0).
+    pub bm25_weight: f64,
     /// Whether to include semantic search.
     pub use_semantic: bool,
     /// Whether to include BM25 search.
     pub use_bm25: bool,
+    /// Pass the query to FTS5 untouched (phrase, boolean, and `NEAR()`
+    /// syntax) instead of matching any of its terms literally.
+    pub raw_query: bool,
+    /// Score boost for results whose chunk title matches query terms.
+    ///
+    /// A result's score is multiplied by `1 + title_boost * f`, where `f` is
+    /// the fraction of query terms found in its title. `0.0` disables it.
+    pub title_boost: f64,
+    /// Restrict candidates to buffers with this `content_type` (e.g. "rs", "md").
+    pub buffer_type: Option<String>,
+    /// Restrict candidates to these chunk IDs (`None` allows all).
+    ///
+    /// Applied before ranked lists are cut to size, so filtered-out chunks
+    /// never take the place of matching ones.
+    pub chunk_filter: Option<Arc<HashSet<i64>>>,
+    /// Embedding count above which `semantic_index` is used instead of a
+    /// brute-force scan.
+    pub brute_force_limit: usize,
+    /// Prebuilt approximate nearest-neighbor index.
+    ///
+    /// Ignored when missing, stale, or when filtering by `buffer_type`.
+    pub semantic_index: Option<Arc<SemanticIndex>>,
+    /// Maximal Marginal Relevance lambda in `[0, 1]` (`None` disables).
+    ///
+    /// Lower values favor diversity over relevance when ordering results.
+    pub mmr_lambda: Option<f32>,
+    /// Cache of query embeddings shared across searches (`None` disables).
+    ///
+    /// Off by default; one-shot searches embed the query only once anyway.
+    pub query_cache: Option<Arc<QueryEmbeddingCache>>,
+    /// Number of IVF clusters to scan (`None` scans every embedding).
+    ///
+    /// Only used once an IVF index has been built with [`build_ivf_index`];
+    /// higher values raise recall at the cost of speed.
+    pub ivf_nprobe: Option<usize>,
+    /// How query and chunk embeddings are compared (default cosine).
+    ///
+    /// `similarity_threshold` applies on the metric's own scale. The
+    /// semantic index and `sqlite-vec` k-NN query only rank by cosine, so
+    /// other metrics always scan (IVF-probed) embeddings.
+    pub metric: SimilarityMetric,
+    /// Time limit for the semantic scan and BM25 query (`None` is unbounded).
+    ///
+    /// Paths that hit the deadline contribute what they scored so far and
+    /// mark it expired; see [`SearchDeadline::expired`].
+    pub deadline: Option<Arc<SearchDeadline>>,
 }
 
 impl Default for SearchConfig {
     fn default() -> Self {
         Self {
             top_k: DEFAULT_TOP_K,
+            offset: 0,
             similarity_threshold: DEFAULT_SIMILARITY_THRESHOLD,
             rrf_k: 60,
+            semantic_weight: 1.0,
+            bm25_weight: 1.0,
             use_semantic: true,
             use_bm25: true,
+            raw_query: false,
+            title_boost: 0.0,
+            buffer_type: None,
+            chunk_filter: None,
+            brute_force_limit: DEFAULT_BRUTE_FORCE_LIMIT,
+            semantic_index: None,
+            mmr_lambda: None,
+            query_cache: None,
+            ivf_nprobe: None,
+            metric: SimilarityMetric::Cosine,
+            deadline: None,
         }
     }
 }
@@ -97,6 +203,8 @@ impl SearchResult {
                 semantic_score,
                 bm25_score,
                 content_preview: None,
+                title: chunk.title(),
+                snippet: None,
             })
     }
 }
@@ -107,7 +215,8 @@ impl SearchResult {
 ///
 /// * `storage` - The storage backend.
 /// * `results` - Search results to populate.
-/// * `preview_len` - Maximum preview length in characters.
+/// * `preview_len` - Maximum preview length in grapheme clusters; see
+///   [`make_preview`](crate::io::make_preview).
 ///
 /// # Errors
 ///
@@ -119,24 +228,44 @@ pub fn populate_previews(
 ) -> Result<()> {
     for result in results.iter_mut() {
         if let Some(chunk) = storage.get_chunk(result.chunk_id)? {
-            let content = &chunk.content;
-            let preview = if content.len() <= preview_len {
-                content.clone()
-            } else {
-                // Find a valid UTF-8 boundary
-                let end = crate::io::find_char_boundary(content, preview_len);
-                let mut preview = content[..end].to_string();
-                if end < content.len() {
-                    preview.push_str("...");
-                }
-                preview
-            };
-            result.content_preview = Some(preview);
+            result.content_preview = Some(crate::io::make_preview(&chunk.content, preview_len));
         }
     }
     Ok(())
 }
 
+/// Default maximum snippet length in tokens.
+pub const DEFAULT_SNIPPET_TOKENS: u8 = 16;
+
+/// Populates highlighted match snippets for search results.
+///
+/// Only results found by BM25 get a snippet; semantic matches have no
+/// lexical span to highlight.
+///
+/// # Arguments
+///
+/// * `storage` - The storage backend.
+/// * `results` - Search results to populate.
+/// * `query` - The query the results were found with.
+/// * `raw` - Whether `query` is raw FTS5 syntax (see [`SearchConfig::raw_query`]).
+///
+/// # Errors
+///
+/// Returns an error if a snippet query fails.
+pub fn populate_snippets(
+    storage: &SqliteStorage,
+    results: &mut [SearchResult],
+    query: &str,
+    raw: bool,
+) -> Result<()> {
+    for result in results.iter_mut().filter(|r| r.bm25_score.is_some()) {
+        result.snippet = storage
+            .fts_snippet(result.chunk_id, query, raw, DEFAULT_SNIPPET_TOKENS)?
+            .map(|s| s.split_whitespace().collect::<Vec<_>>().join(" "));
+    }
+    Ok(())
+}
+
 impl SearchConfig {
     /// Creates a new search config with default values.
     #[must_use]
@@ -151,6 +280,13 @@ impl SearchConfig {
         self
     }
 
+    /// Sets the number of top-ranked results to skip.
+    #[must_use]
+    pub const fn with_offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
     /// Sets the similarity threshold.
     #[must_use]
     pub const fn with_threshold(mut self, threshold: f32) -> Self {
@@ -165,6 +301,21 @@ impl SearchConfig {
         self
     }
 
+    /// Sets the semantic and BM25 list weights for hybrid rank fusion.
+    #[must_use]
+    pub const fn with_weights(mut self, semantic: f64, bm25: f64) -> Self {
+        self.semantic_weight = semantic;
+        self.bm25_weight = bm25;
+        self
+    }
+
+    /// Returns `true` if the fusion weights differ from the equal default.
+    #[must_use]
+    #[allow(clippy::float_cmp)]
+    pub fn is_weighted(&self) -> bool {
+        self.semantic_weight != 1.0 || self.bm25_weight != 1.0
+    }
+
     /// Enables or disables semantic search.
     #[must_use]
     pub const fn with_semantic(mut self, enabled: bool) -> Self {
@@ -178,6 +329,176 @@ impl SearchConfig {
         self.use_bm25 = enabled;
         self
     }
+
+    /// Passes the query to FTS5 untouched instead of matching any term.
+    #[must_use]
+    pub const fn with_raw_query(mut self, raw: bool) -> Self {
+        self.raw_query = raw;
+        self
+    }
+
+    /// Sets the title match boost (0.0 disables).
+    #[must_use]
+    pub const fn with_title_boost(mut self, boost: f64) -> Self {
+        self.title_boost = boost;
+        self
+    }
+
+    /// Restricts search to buffers with the given content type.
+    #[must_use]
+    pub fn with_buffer_type(mut self, content_type: impl Into<String>) -> Self {
+        self.buffer_type = Some(content_type.into());
+        self
+    }
+
+    /// Restricts search to the given chunks.
+    #[must_use]
+    pub fn with_chunk_filter(mut self, chunk_ids: HashSet<i64>) -> Self {
+        self.chunk_filter = Some(Arc::new(chunk_ids));
+        self
+    }
+
+    /// Returns `true` if `chunk_id` passes the chunk filter.
+    fn allows(&self, chunk_id: i64) -> bool {
+        self.chunk_filter
+            .as_ref()
+            .is_none_or(|ids| ids.contains(&chunk_id))
+    }
+
+    /// Returns how many ranked results a page needs: `offset + top_k`.
+    const fn depth(&self) -> usize {
+        self.offset + self.top_k
+    }
+
+    /// Enables MMR re-ranking with the given relevance weight.
+    #[must_use]
+    pub const fn with_mmr_lambda(mut self, lambda: f32) -> Self {
+        self.mmr_lambda = Some(lambda);
+        self
+    }
+
+    /// Sets the embedding count above which the semantic index is used.
+    #[must_use]
+    pub const fn with_brute_force_limit(mut self, limit: usize) -> Self {
+        self.brute_force_limit = limit;
+        self
+    }
+
+    /// Attaches a prebuilt semantic index for large corpora.
+    #[must_use]
+    pub fn with_semantic_index(mut self, index: Arc<SemanticIndex>) -> Self {
+        self.semantic_index = Some(index);
+        self
+    }
+
+    /// Scans only the `nprobe` nearest IVF clusters in semantic search.
+    #[must_use]
+    pub const fn with_ivf_nprobe(mut self, nprobe: usize) -> Self {
+        self.ivf_nprobe = Some(nprobe);
+        self
+    }
+
+    /// Sets the similarity metric for semantic search.
+    #[must_use]
+    pub const fn with_metric(mut self, metric: SimilarityMetric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    /// Attaches a query embedding cache shared across searches.
+    #[must_use]
+    pub fn with_query_cache(mut self, cache: Arc<QueryEmbeddingCache>) -> Self {
+        self.query_cache = Some(cache);
+        self
+    }
+
+    /// Bounds the search by a deadline shared with the caller.
+    #[must_use]
+    pub fn with_deadline(mut self, deadline: Arc<SearchDeadline>) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Returns `true` if the deadline has passed (always `false` without one).
+    fn deadline_passed(&self) -> bool {
+        self.deadline.as_ref().is_some_and(|d| d.check())
+    }
+
+    /// Returns progressively looser variants of this config, mildest first.
+    ///
+    /// Used to retry a search that returned too few results: a
+    /// [`raw_query`](Self::raw_query) is first matched as plain OR-ed terms
+    /// (its own AND, NEAR, or phrase syntax may be what filters results out),
+    /// then the similarity threshold is halved, then dropped to zero, then a
+    /// single-mode search is widened to hybrid. Later steps keep the earlier
+    /// ones. Each entry pairs a description of the change with the relaxed
+    /// config.
+    #[must_use]
+    pub fn relaxations(&self) -> Vec<(String, Self)> {
+        let threshold = self.similarity_threshold;
+        let mut steps = Vec::new();
+
+        let base = if self.use_bm25 && self.raw_query {
+            let terms = self.clone().with_raw_query(false);
+            steps.push(("raw query -> OR-ed terms".to_string(), terms.clone()));
+            terms
+        } else {
+            self.clone()
+        };
+
+        if self.use_semantic && threshold > 0.0 {
+            let half = threshold / 2.0;
+            steps.push((
+                format!("threshold {threshold} -> {half}"),
+                base.clone().with_threshold(half),
+            ));
+            steps.push((
+                format!("threshold {threshold} -> 0"),
+                base.clone().with_threshold(0.0),
+            ));
+        }
+
+        if !(self.use_semantic && self.use_bm25) {
+            steps.push((
+                format!("threshold {threshold} -> 0, mode -> hybrid"),
+                base.with_threshold(0.0).with_semantic(true).with_bm25(true),
+            ));
+        }
+
+        steps
+    }
+}
+
+/// Boosts results whose titles contain query terms and re-sorts by score.
+///
+/// Matching is case-insensitive on alphanumeric terms. Results without a
+/// title, or with no matching terms, keep their original score.
+#[allow(clippy::cast_precision_loss)]
+pub fn apply_title_boost(results: &mut [SearchResult], query: &str, boost: f64) {
+    let terms: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    if terms.is_empty() || boost <= 0.0 {
+        return;
+    }
+
+    for result in results.iter_mut() {
+        if let Some(title) = &result.title {
+            let title = title.to_lowercase();
+            let matched = terms.iter().filter(|t| title.contains(t.as_str())).count();
+            if matched > 0 {
+                result.score *= (matched as f64 / terms.len() as f64).mul_add(boost, 1.0);
+            }
+        }
+    }
+
+    results.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
 }
 
 /// Performs hybrid search combining semantic and BM25 results.
@@ -208,28 +529,61 @@ pub fn hybrid_search(
 
     // BM25 search
     if config.use_bm25 {
-        bm25_results = storage.search_fts(query, config.top_k * 2)?;
+        // Filtered-out matches must not crowd out the rest, so fetch all
+        let limit = if config.chunk_filter.is_some() {
+            usize::MAX
+        } else {
+            config.depth() * 2
+        };
+        let content_type = config.buffer_type.as_deref();
+        bm25_results = match &config.deadline {
+            None => storage.search_fts_filtered(query, limit, content_type, config.raw_query)?,
+            // Out of time already: contribute nothing rather than start
+            Some(deadline) if deadline.check() => Vec::new(),
+            Some(deadline) => storage
+                .search_fts_with_timeout(
+                    query,
+                    limit,
+                    content_type,
+                    config.raw_query,
+                    deadline.remaining(),
+                )?
+                .unwrap_or_else(|| {
+                    deadline.mark_expired();
+                    Vec::new()
+                }),
+        };
+        bm25_results.retain(|(chunk_id, _)| config.allows(*chunk_id));
     }
 
+    // Title boosting and MMR may reorder results, so consider the wider candidate set
+    let candidates = if config.title_boost > 0.0 || config.mmr_lambda.is_some() {
+        config.depth() * 2
+    } else {
+        config.depth()
+    };
+
     // If only one type of search is enabled, return those results directly
     if !config.use_semantic {
-        return Ok(bm25_results
+        let results = bm25_results
             .into_iter()
-            .take(config.top_k)
+            .take(candidates)
             .filter_map(|(chunk_id, score)| {
                 SearchResult::from_chunk_id(storage, chunk_id, score, None, Some(score))
             })
-            .collect());
+            .collect();
+        return finish_results(storage, results, query, config);
     }
 
     if !config.use_bm25 {
-        return Ok(semantic_results
+        let results = semantic_results
             .into_iter()
-            .take(config.top_k)
+            .take(candidates)
             .filter_map(|(chunk_id, score)| {
                 SearchResult::from_chunk_id(storage, chunk_id, f64::from(score), Some(score), None)
             })
-            .collect());
+            .collect();
+        return finish_results(storage, results, query, config);
     }
 
     // Combine using RRF
@@ -239,7 +593,17 @@ pub fn hybrid_search(
     let semantic_ranked: Vec<i64> = semantic_results.iter().map(|(id, _)| *id).collect();
     let bm25_ranked: Vec<i64> = bm25_results.iter().map(|(id, _)| *id).collect();
 
-    let fused = reciprocal_rank_fusion(&[&semantic_ranked, &bm25_ranked], &rrf_config);
+    let fused = if config.is_weighted() {
+        weighted_rrf(
+            &[
+                (&semantic_ranked, config.semantic_weight),
+                (&bm25_ranked, config.bm25_weight),
+            ],
+            &rrf_config,
+        )
+    } else {
+        reciprocal_rank_fusion(&[&semantic_ranked, &bm25_ranked], &rrf_config)
+    };
 
     // Build result with original scores
     let semantic_map: std::collections::HashMap<i64, f32> = semantic_results.into_iter().collect();
@@ -247,7 +611,7 @@ pub fn hybrid_search(
 
     let results: Vec<SearchResult> = fused
         .into_iter()
-        .take(config.top_k)
+        .take(candidates)
         .filter_map(|(chunk_id, rrf_score)| {
             SearchResult::from_chunk_id(
                 storage,
@@ -259,47 +623,204 @@ pub fn hybrid_search(
         })
         .collect();
 
-    Ok(results)
+    finish_results(storage, results, query, config)
+}
+
+/// Applies title boosting and MMR re-ranking (if enabled), then keeps the
+/// page of `top_k` results after `offset`.
+fn finish_results(
+    storage: &SqliteStorage,
+    mut results: Vec<SearchResult>,
+    query: &str,
+    config: &SearchConfig,
+) -> Result<Vec<SearchResult>> {
+    if config.title_boost > 0.0 {
+        apply_title_boost(&mut results, query, config.title_boost);
+    }
+    if let Some(lambda) = config.mmr_lambda {
+        results = apply_mmr(storage, results, lambda)?;
+    }
+    Ok(results
+        .into_iter()
+        .skip(config.offset)
+        .take(config.top_k)
+        .collect())
+}
+
+/// Re-orders results with Maximal Marginal Relevance using stored embeddings.
+///
+/// Results whose chunks have no embedding are kept and treated as
+/// maximally diverse. Scores are left unchanged.
+fn apply_mmr(
+    storage: &SqliteStorage,
+    results: Vec<SearchResult>,
+    lambda: f32,
+) -> Result<Vec<SearchResult>> {
+    let mut embeddings = std::collections::HashMap::new();
+    for result in &results {
+        if let Some(embedding) = storage.get_embedding(result.chunk_id)? {
+            embeddings.insert(result.chunk_id, embedding);
+        }
+    }
+
+    let scored: Vec<(i64, f64)> = results.iter().map(|r| (r.chunk_id, r.score)).collect();
+    let order = mmr_rerank(&scored, &embeddings, lambda);
+
+    let mut by_id: std::collections::HashMap<i64, SearchResult> =
+        results.into_iter().map(|r| (r.chunk_id, r)).collect();
+    Ok(order
+        .into_iter()
+        .filter_map(|id| by_id.remove(&id))
+        .collect())
 }
 
+/// Number of embeddings scored between deadline checks in a semantic scan.
+const DEADLINE_CHECK_INTERVAL: usize = 256;
+
 /// Performs semantic similarity search.
 ///
-/// Uses cosine similarity between query embedding and stored chunk embeddings.
+/// Scores stored chunk embeddings against the query embedding with the
+/// configured metric, or uses the semantic index for cosine searches when
+/// the corpus is large enough. With `ivf_nprobe` set and an IVF index
+/// built, only the nearest clusters are scanned. With the `sqlite-vec`
+/// feature, other unfiltered cosine searches run as a k-NN query in
+/// `SQLite` instead of a scan.
 fn semantic_search(
     storage: &SqliteStorage,
     embedder: &dyn Embedder,
     query: &str,
     config: &SearchConfig,
 ) -> Result<Vec<(i64, f32)>> {
-    // Generate query embedding
-    let query_embedding = embedder.embed(query)?;
+    // Generate query embedding, reusing a cached one when available
+    let query_embedding = match &config.query_cache {
+        Some(cache) => cache.get_or_embed(embedder, query)?,
+        None => embedder.embed(query)?,
+    };
+    if config.deadline_passed() {
+        return Ok(Vec::new());
+    }
+
+    // The index returns a fixed number of neighbors, too few once filtered
+    let cosine = config.metric == SimilarityMetric::Cosine;
+    if cosine
+        && config.buffer_type.is_none()
+        && config.chunk_filter.is_none()
+        && let Some(index) = &config.semantic_index
+        && let Some(results) = indexed_search(storage, index, &query_embedding, config)?
+    {
+        return Ok(results);
+    }
+
+    // An explicit nprobe takes precedence over the vec0 k-NN query
+    let probed = config
+        .ivf_nprobe
+        .map(|nprobe| {
+            ivf::probe(
+                storage,
+                &query_embedding,
+                nprobe,
+                config.buffer_type.as_deref(),
+            )
+        })
+        .transpose()?
+        .flatten();
+
+    // Like the index, the vec0 k-NN query can't apply filters
+    #[cfg(feature = "sqlite-vec")]
+    if cosine
+        && probed.is_none()
+        && config.buffer_type.is_none()
+        && config.chunk_filter.is_none()
+        && query_embedding.len() == storage.vec_index_dimensions()
+    {
+        return Ok(storage
+            .search_vec(&query_embedding, config.depth() * 2)?
+            .into_iter()
+            .map(|(chunk_id, distance)| (chunk_id, vec_distance_to_similarity(distance)))
+            .filter(|(_, sim)| *sim >= config.similarity_threshold)
+            .collect());
+    }
 
-    // Get all embeddings from storage
-    let all_embeddings = storage.get_all_embeddings()?;
+    // Get candidate embeddings from storage
+    let all_embeddings = match (probed, config.buffer_type.as_deref()) {
+        (Some(candidates), _) => candidates,
+        (None, Some(content_type)) => storage.get_embeddings_by_content_type(content_type)?,
+        (None, None) => storage.get_all_embeddings()?,
+    };
 
     if all_embeddings.is_empty() {
         return Ok(Vec::new());
     }
 
-    // Calculate similarities
-    let mut similarities: Vec<(i64, f32)> = all_embeddings
-        .iter()
-        .map(|(chunk_id, embedding)| {
-            let sim = cosine_similarity(&query_embedding, embedding);
-            (*chunk_id, sim)
-        })
-        .filter(|(_, sim)| *sim >= config.similarity_threshold)
-        .collect();
+    // Against unit-length embeddings, cosine is the dot product with the
+    // normalized query, skipping the per-vector magnitude work
+    let unit_query =
+        (cosine && storage.embeddings_normalized()?).then(|| normalize_embedding(&query_embedding));
+
+    // Calculate similarities, skipping chunks embedded by a model with other
+    // dimensions (similarity would score them 0 rather than fail). Past the
+    // deadline, keep what has been scored so far.
+    let mut similarities: Vec<(i64, f32)> = Vec::new();
+    for (i, (chunk_id, embedding)) in all_embeddings.iter().enumerate() {
+        if i % DEADLINE_CHECK_INTERVAL == 0 && config.deadline_passed() {
+            break;
+        }
+        if embedding.len() != query_embedding.len() || !config.allows(*chunk_id) {
+            continue;
+        }
+        let sim = unit_query.as_ref().map_or_else(
+            || similarity(config.metric, &query_embedding, embedding),
+            |query| similarity(SimilarityMetric::Dot, query, embedding),
+        );
+        if sim >= config.similarity_threshold {
+            similarities.push((*chunk_id, sim));
+        }
+    }
 
     // Sort by similarity descending
     similarities.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
 
     // Limit results
-    similarities.truncate(config.top_k * 2);
+    similarities.truncate(config.depth() * 2);
 
     Ok(similarities)
 }
 
+/// Converts a `sqlite-vec` cosine distance to the cosine similarity scale
+/// used by [`SimilarityMetric::Cosine`] and the similarity threshold.
+#[cfg(feature = "sqlite-vec")]
+const fn vec_distance_to_similarity(distance: f32) -> f32 {
+    1.0 - distance
+}
+
+/// Queries the semantic index if it is fresh and the corpus exceeds
+/// `brute_force_limit`.
+///
+/// Returns `None` when the caller should fall back to a brute-force scan.
+fn indexed_search(
+    storage: &SqliteStorage,
+    index: &SemanticIndex,
+    query_embedding: &[f32],
+    config: &SearchConfig,
+) -> Result<Option<Vec<(i64, f32)>>> {
+    let fingerprint = storage.embedding_fingerprint()?;
+    if fingerprint.count <= config.brute_force_limit || fingerprint != index.fingerprint() {
+        return Ok(None);
+    }
+
+    // A query the index cannot answer (e.g. dimension mismatch) falls back too
+    let Ok(results) = index.query(query_embedding, config.depth() * 2) else {
+        return Ok(None);
+    };
+
+    Ok(Some(
+        results
+            .into_iter()
+            .filter(|(_, sim)| *sim >= config.similarity_threshold)
+            .collect(),
+    ))
+}
+
 /// Performs semantic-only search.
 ///
 /// # Arguments
@@ -345,7 +866,7 @@ pub fn search_bm25(
     query: &str,
     top_k: usize,
 ) -> Result<Vec<SearchResult>> {
-    let results = storage.search_fts(query, top_k)?;
+    let results = storage.search_fts(query, top_k, false)?;
 
     Ok(results
         .into_iter()
@@ -355,92 +876,405 @@ pub fn search_bm25(
         .collect())
 }
 
-/// Generates and stores embeddings for all chunks in a buffer.
+/// Re-embeds chunks whose embedding was created before `cutoff`.
+///
+/// Chunks without an embedding are left alone; use
+/// [`embed_buffer_chunks_incremental`] for those.
 ///
 /// # Arguments
 ///
-/// * `storage` - The storage backend (mutable for storing embeddings).
+/// * `storage` - The storage backend.
 /// * `embedder` - The embedding generator.
-/// * `buffer_id` - The buffer ID to process.
-///
-/// # Returns
-///
-/// The number of chunks embedded.
+/// * `buffer_id` - The buffer to process.
+/// * `cutoff` - Unix timestamp; embeddings created before it are refreshed.
+/// * `batch_size` - Maximum chunks per embedder call.
 ///
 /// # Errors
 ///
 /// Returns an error if embedding generation or storage fails.
-pub fn embed_buffer_chunks(
+pub fn reembed_stale_chunks(
     storage: &mut SqliteStorage,
     embedder: &dyn Embedder,
     buffer_id: i64,
-) -> Result<usize> {
-    let chunks = storage.get_chunks(buffer_id)?;
+    cutoff: i64,
+    batch_size: usize,
+) -> Result<IncrementalEmbedResult> {
+    let total_chunks = storage.chunk_count(buffer_id)?;
+    let stale = storage.get_chunks_embedded_before(buffer_id, cutoff)?;
+    let replaced_count = embed_chunk_ids(storage, embedder, buffer_id, &stale, batch_size)?;
+
+    Ok(IncrementalEmbedResult {
+        embedded_count: 0,
+        skipped_count: total_chunks - replaced_count,
+        replaced_count,
+        reused_count: 0,
+        total_chunks,
+        model_name: embedder.model_name().to_string(),
+    })
+}
 
-    if chunks.is_empty() {
+/// Embeds the given chunks of a buffer and stores the vectors.
+///
+/// Returns the number of embeddings stored.
+fn embed_chunk_ids(
+    storage: &mut SqliteStorage,
+    embedder: &dyn Embedder,
+    buffer_id: i64,
+    chunk_ids: &[i64],
+    batch_size: usize,
+) -> Result<usize> {
+    if chunk_ids.is_empty() {
         return Ok(0);
     }
 
-    // Collect chunk texts for batch embedding
-    let texts: Vec<&str> = chunks.iter().map(|c| c.content.as_str()).collect();
-
-    // Generate embeddings in batch
-    let embeddings = embedder.embed_batch(&texts)?;
-
-    // Prepare batch for storage
-    let batch: Vec<(i64, Vec<f32>)> = chunks
+    let all_chunks = storage.get_chunks(buffer_id)?;
+    let chunks_to_embed: Vec<&Chunk> = all_chunks
         .iter()
-        .zip(embeddings)
-        .filter_map(|(chunk, embedding)| chunk.id.map(|id| (id, embedding)))
+        .filter(|c| c.id.is_some_and(|id| chunk_ids.contains(&id)))
         .collect();
 
-    let count = batch.len();
-
-    // Store embeddings with model name for version tracking
-    storage.store_embeddings_batch(&batch, Some(embedder.model_name()))?;
-
-    Ok(count)
+    embed_chunks(storage, embedder, &chunks_to_embed, batch_size, None)
 }
 
-/// Checks if a buffer has all chunks embedded.
+/// Embeds chunks that have no embedding yet in the given buffers.
+///
+/// Chunks with an embedding from any model are left alone, so this is
+/// cheap once a buffer is fully embedded. Used by `search --auto-embed`.
+///
+/// Returns the number of embeddings stored.
 ///
 /// # Errors
 ///
-/// Returns an error if the check fails.
-pub fn buffer_fully_embedded(storage: &SqliteStorage, buffer_id: i64) -> Result<bool> {
-    let chunk_count = storage.chunk_count(buffer_id)?;
-    if chunk_count == 0 {
-        return Ok(true);
+/// Returns an error if embedding generation or storage fails.
+pub fn embed_missing_chunks(
+    storage: &mut SqliteStorage,
+    embedder: &dyn Embedder,
+    buffer_ids: &[i64],
+    batch_size: usize,
+) -> Result<usize> {
+    let mut stored = 0;
+    for &buffer_id in buffer_ids {
+        let missing = storage.get_chunks_without_embedding(buffer_id)?;
+        stored += embed_chunk_ids(storage, embedder, buffer_id, &missing, batch_size)?;
     }
+    Ok(stored)
+}
 
-    // Count embeddings for this buffer's chunks
-    let chunks = storage.get_chunks(buffer_id)?;
-    let mut embedded_count = 0;
-
-    for chunk in &chunks {
-        if let Some(id) = chunk.id
-            && storage.has_embedding(id)?
-        {
-            embedded_count += 1;
+/// Re-embeds the given chunks, which may belong to any buffer, replacing
+/// their stored embeddings.
+///
+/// Used to repair embeddings whose dimensions no longer match the current
+/// embedder. IDs of chunks that no longer exist are ignored.
+///
+/// Returns the number of embeddings stored.
+///
+/// # Errors
+///
+/// Returns an error if embedding generation or storage fails.
+pub fn reembed_chunks(
+    storage: &mut SqliteStorage,
+    embedder: &dyn Embedder,
+    chunk_ids: &[i64],
+    batch_size: usize,
+) -> Result<usize> {
+    let mut chunks = Vec::with_capacity(chunk_ids.len());
+    for &id in chunk_ids {
+        if let Some(chunk) = storage.get_chunk(id)? {
+            chunks.push(chunk);
         }
     }
+    let refs: Vec<&Chunk> = chunks.iter().collect();
 
-    Ok(embedded_count == chunk_count)
+    embed_chunks(storage, embedder, &refs, batch_size, None)
 }
 
-/// Checks if existing embeddings were created with a different model.
+/// Re-embeds the given chunks and replaces their stored embeddings in a
+/// single transaction.
 ///
-/// Returns `Some(existing_model)` if there's a model mismatch, `None` otherwise.
+/// Unlike [`reembed_chunks`], every embedding is generated before any is
+/// stored, so a failure leaves all of the chunks' existing embeddings in
+/// place. Used to migrate one buffer at a time. IDs of chunks that no longer
+/// exist are ignored.
+///
+/// Returns the number of embeddings stored.
 ///
 /// # Errors
 ///
-/// Returns an error if the query fails.
-pub fn check_model_mismatch(
-    storage: &SqliteStorage,
-    buffer_id: i64,
-    current_model: &str,
-) -> Result<Option<String>> {
-    let models = storage.get_embedding_models(buffer_id)?;
+/// Returns an error if embedding generation or storage fails; nothing is
+/// stored in that case.
+pub fn reembed_chunks_atomic(
+    storage: &mut SqliteStorage,
+    embedder: &dyn Embedder,
+    chunk_ids: &[i64],
+    batch_size: usize,
+) -> Result<usize> {
+    let mut chunks = Vec::with_capacity(chunk_ids.len());
+    for &id in chunk_ids {
+        if let Some(chunk) = storage.get_chunk(id)? {
+            chunks.push(chunk);
+        }
+    }
+
+    let mut embeddings = Vec::with_capacity(chunks.len());
+    for group in chunks.chunks(batch_size.max(1)) {
+        let texts: Vec<&str> = group.iter().map(|c| c.content.as_str()).collect();
+        let vectors = embedder.embed_batch(&texts)?;
+        embeddings.extend(
+            group
+                .iter()
+                .zip(vectors)
+                .filter_map(|(chunk, embedding)| chunk.id.map(|id| (id, embedding))),
+        );
+    }
+
+    storage.store_embeddings_batch(&embeddings, Some(embedder.model_name()))?;
+    Ok(embeddings.len())
+}
+
+/// Embeds chunks in batches of at most `batch_size`, storing each batch
+/// before embedding the next.
+///
+/// If a batch fails, the batches before it stay stored, so an incremental
+/// re-run only embeds what is missing. A `batch_size` of 0 is treated as 1.
+/// `progress` is called after each stored batch with `(done, total)`.
+///
+/// Returns the number of embeddings stored.
+fn embed_chunks(
+    storage: &mut SqliteStorage,
+    embedder: &dyn Embedder,
+    chunks: &[&Chunk],
+    batch_size: usize,
+    progress: Option<&dyn Fn(usize, usize)>,
+) -> Result<usize> {
+    let mut count = 0;
+
+    for group in chunks.chunks(batch_size.max(1)) {
+        let texts: Vec<&str> = group.iter().map(|c| c.content.as_str()).collect();
+        let embeddings = embedder.embed_batch(&texts)?;
+
+        let batch: Vec<(i64, Vec<f32>)> = group
+            .iter()
+            .zip(embeddings)
+            .filter_map(|(chunk, embedding)| chunk.id.map(|id| (id, embedding)))
+            .collect();
+
+        // Store with model name for version tracking
+        storage.store_embeddings_batch(&batch, Some(embedder.model_name()))?;
+        count += batch.len();
+        if let Some(progress) = progress {
+            progress(count, chunks.len());
+        }
+    }
+
+    Ok(count)
+}
+
+/// Embeds chunks like [`embed_chunks`], but reuses stored embeddings of
+/// chunks with the same content hash and model instead of recomputing them.
+///
+/// Chunks that share a hash are embedded once. Chunks without a hash are
+/// always embedded. `progress` counts reused chunks as done along with
+/// embedded ones.
+///
+/// Returns the number of embeddings stored and how many of them were reused.
+fn embed_chunks_reusing(
+    storage: &mut SqliteStorage,
+    embedder: &dyn Embedder,
+    chunks: &[&Chunk],
+    batch_size: usize,
+    progress: Option<&dyn Fn(usize, usize)>,
+) -> Result<(usize, usize)> {
+    let model = embedder.model_name();
+    let mut reused = Vec::new();
+    let mut to_compute = Vec::new();
+    let mut duplicates = Vec::new();
+    let mut pending = HashSet::new();
+
+    for &chunk in chunks {
+        let (Some(id), Some(hash)) = (chunk.id, chunk.metadata.content_hash.as_deref()) else {
+            to_compute.push(chunk);
+            continue;
+        };
+        if pending.contains(hash) {
+            duplicates.push(chunk);
+            continue;
+        }
+        // Same model name can still differ in dimensions (e.g. OpenAI's
+        // configurable dimensions), so only reuse matching vectors
+        match storage.get_embedding_by_content_hash(hash, model)? {
+            Some(embedding) if embedding.len() == embedder.dimensions() => {
+                reused.push((id, embedding));
+            }
+            _ => {
+                pending.insert(hash);
+                to_compute.push(chunk);
+            }
+        }
+    }
+
+    let total = chunks.len();
+    let report = |done: usize| {
+        if let Some(progress) = progress {
+            progress(done, total);
+        }
+    };
+
+    storage.store_embeddings_batch(&reused, Some(model))?;
+    if !reused.is_empty() {
+        report(reused.len());
+    }
+    let offset = reused.len();
+    let after_reused = |done: usize, _: usize| report(offset + done);
+    let mut stored = offset
+        + embed_chunks(
+            storage,
+            embedder,
+            &to_compute,
+            batch_size,
+            Some(&after_reused),
+        )?;
+    let mut reused_count = reused.len();
+
+    // Duplicates of just-computed chunks now find their embedding in storage
+    let mut copies = Vec::with_capacity(duplicates.len());
+    let mut missing = Vec::new();
+    for chunk in duplicates {
+        let hash = chunk.metadata.content_hash.as_deref().unwrap_or_default();
+        match (
+            chunk.id,
+            storage.get_embedding_by_content_hash(hash, model)?,
+        ) {
+            (Some(id), Some(embedding)) => copies.push((id, embedding)),
+            _ => missing.push(chunk),
+        }
+    }
+    storage.store_embeddings_batch(&copies, Some(model))?;
+    stored += copies.len();
+    if !copies.is_empty() {
+        report(stored);
+    }
+    let offset = stored;
+    let after_copies = |done: usize, _: usize| report(offset + done);
+    stored += embed_chunks(storage, embedder, &missing, batch_size, Some(&after_copies))?;
+    reused_count += copies.len();
+
+    Ok((stored, reused_count))
+}
+
+/// Generates and stores embeddings for all chunks in a buffer.
+///
+/// Embeds in batches of [`DEFAULT_EMBED_BATCH_SIZE`]; see
+/// [`embed_buffer_chunks_batched`].
+///
+/// # Arguments
+///
+/// * `storage` - The storage backend (mutable for storing embeddings).
+/// * `embedder` - The embedding generator.
+/// * `buffer_id` - The buffer ID to process.
+///
+/// # Returns
+///
+/// The number of chunks embedded.
+///
+/// # Errors
+///
+/// Returns an error if embedding generation or storage fails.
+pub fn embed_buffer_chunks(
+    storage: &mut SqliteStorage,
+    embedder: &dyn Embedder,
+    buffer_id: i64,
+) -> Result<usize> {
+    embed_buffer_chunks_batched(storage, embedder, buffer_id, DEFAULT_EMBED_BATCH_SIZE)
+}
+
+/// Generates and stores embeddings for all chunks in a buffer, at most
+/// `batch_size` chunks per embedder call.
+///
+/// Each batch is stored before the next is embedded. If a batch fails, the
+/// earlier batches remain stored and the error is returned; running
+/// [`embed_buffer_chunks_incremental`] afterwards embeds only the rest.
+///
+/// # Arguments
+///
+/// * `storage` - The storage backend (mutable for storing embeddings).
+/// * `embedder` - The embedding generator.
+/// * `buffer_id` - The buffer ID to process.
+/// * `batch_size` - Maximum chunks per embedder call (0 is treated as 1).
+///
+/// # Returns
+///
+/// The number of chunks embedded.
+///
+/// # Errors
+///
+/// Returns an error if embedding generation or storage fails.
+pub fn embed_buffer_chunks_batched(
+    storage: &mut SqliteStorage,
+    embedder: &dyn Embedder,
+    buffer_id: i64,
+    batch_size: usize,
+) -> Result<usize> {
+    embed_buffer_chunks_with_progress(storage, embedder, buffer_id, batch_size, None)
+}
+
+/// Like [`embed_buffer_chunks_batched`], calling `progress` with
+/// `(done, total)` chunk counts after each batch is stored.
+///
+/// # Errors
+///
+/// Returns an error if embedding generation or storage fails.
+pub fn embed_buffer_chunks_with_progress(
+    storage: &mut SqliteStorage,
+    embedder: &dyn Embedder,
+    buffer_id: i64,
+    batch_size: usize,
+    progress: Option<&dyn Fn(usize, usize)>,
+) -> Result<usize> {
+    let chunks = storage.get_chunks(buffer_id)?;
+    let chunks: Vec<&Chunk> = chunks.iter().collect();
+
+    embed_chunks(storage, embedder, &chunks, batch_size, progress)
+}
+
+/// Checks if a buffer has all chunks embedded.
+///
+/// # Errors
+///
+/// Returns an error if the check fails.
+pub fn buffer_fully_embedded(storage: &SqliteStorage, buffer_id: i64) -> Result<bool> {
+    let chunk_count = storage.chunk_count(buffer_id)?;
+    if chunk_count == 0 {
+        return Ok(true);
+    }
+
+    // Count embeddings for this buffer's chunks
+    let chunks = storage.get_chunks(buffer_id)?;
+    let mut embedded_count = 0;
+
+    for chunk in &chunks {
+        if let Some(id) = chunk.id
+            && storage.has_embedding(id)?
+        {
+            embedded_count += 1;
+        }
+    }
+
+    Ok(embedded_count == chunk_count)
+}
+
+/// Checks if existing embeddings were created with a different model.
+///
+/// Returns `Some(existing_model)` if there's a model mismatch, `None` otherwise.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub fn check_model_mismatch(
+    storage: &SqliteStorage,
+    buffer_id: i64,
+    current_model: &str,
+) -> Result<Option<String>> {
+    let models = storage.get_embedding_models(buffer_id)?;
 
     // If no embeddings exist, no mismatch
     if models.is_empty() {
@@ -499,6 +1333,9 @@ pub struct IncrementalEmbedResult {
     pub skipped_count: usize,
     /// Number of embeddings that were replaced (different model).
     pub replaced_count: usize,
+    /// Number of the new and replaced embeddings copied from chunks with
+    /// identical content rather than computed.
+    pub reused_count: usize,
     /// Total chunks in the buffer.
     pub total_chunks: usize,
     /// Model name used for embedding.
@@ -512,6 +1349,12 @@ impl IncrementalEmbedResult {
         self.embedded_count > 0 || self.replaced_count > 0
     }
 
+    /// Returns the number of embeddings computed by the embedder.
+    #[must_use]
+    pub const fn computed_count(&self) -> usize {
+        self.embedded_count + self.replaced_count - self.reused_count
+    }
+
     /// Returns the percentage of chunks now embedded.
     #[must_use]
     #[allow(clippy::cast_precision_loss)] // Acceptable for percentage calculation
@@ -532,7 +1375,9 @@ impl IncrementalEmbedResult {
 /// - Have an embedding from a different model (if `force_reembed` is true)
 ///
 /// This is more efficient than `embed_buffer_chunks` for large buffers
-/// where only a few chunks have changed.
+/// where only a few chunks have changed. A chunk whose content hash matches
+/// an already embedded chunk (same model, any buffer) reuses that embedding,
+/// and chunks with identical content are embedded once.
 ///
 /// # Arguments
 ///
@@ -540,6 +1385,7 @@ impl IncrementalEmbedResult {
 /// * `embedder` - The embedder to use.
 /// * `buffer_id` - The buffer to embed.
 /// * `force_reembed` - If true, re-embeds chunks with different models.
+/// * `batch_size` - Maximum chunks per embedder call.
 ///
 /// # Returns
 ///
@@ -553,6 +1399,32 @@ pub fn embed_buffer_chunks_incremental(
     embedder: &dyn Embedder,
     buffer_id: i64,
     force_reembed: bool,
+    batch_size: usize,
+) -> Result<IncrementalEmbedResult> {
+    embed_buffer_chunks_incremental_with_progress(
+        storage,
+        embedder,
+        buffer_id,
+        force_reembed,
+        batch_size,
+        None,
+    )
+}
+
+/// Like [`embed_buffer_chunks_incremental`], calling `progress` with
+/// `(done, total)` counts of the chunks needing embedding after each batch
+/// is stored. Reused embeddings count as done.
+///
+/// # Errors
+///
+/// Returns an error if embedding generation or storage fails.
+pub fn embed_buffer_chunks_incremental_with_progress(
+    storage: &mut SqliteStorage,
+    embedder: &dyn Embedder,
+    buffer_id: i64,
+    force_reembed: bool,
+    batch_size: usize,
+    progress: Option<&dyn Fn(usize, usize)>,
 ) -> Result<IncrementalEmbedResult> {
     let current_model = embedder.model_name();
     let stats = storage.get_embedding_stats(buffer_id)?;
@@ -572,41 +1444,27 @@ pub fn embed_buffer_chunks_incremental(
             embedded_count: 0,
             skipped_count: total_chunks,
             replaced_count: 0,
+            reused_count: 0,
             total_chunks,
             model_name: current_model.to_string(),
         });
     }
 
-    // Load the chunks we need to embed
-    let all_chunks = storage.get_chunks(buffer_id)?;
-    let chunks_to_embed: Vec<_> = all_chunks
-        .iter()
-        .filter(|c| c.id.is_some_and(|id| chunk_ids_to_embed.contains(&id)))
-        .collect();
-
     // Count how many are replacements (had embeddings before)
     let mut replaced_count = 0;
-    for chunk in &chunks_to_embed {
-        if let Some(id) = chunk.id
-            && storage.has_embedding(id)?
-        {
+    for id in &chunk_ids_to_embed {
+        if storage.has_embedding(*id)? {
             replaced_count += 1;
         }
     }
 
-    // Generate embeddings
-    let texts: Vec<&str> = chunks_to_embed.iter().map(|c| c.content.as_str()).collect();
-    let embeddings = embedder.embed_batch(&texts)?;
-
-    // Store embeddings
-    let batch: Vec<(i64, Vec<f32>)> = chunks_to_embed
+    let all_chunks = storage.get_chunks(buffer_id)?;
+    let chunks_to_embed: Vec<&Chunk> = all_chunks
         .iter()
-        .zip(embeddings)
-        .filter_map(|(chunk, embedding)| chunk.id.map(|id| (id, embedding)))
+        .filter(|c| c.id.is_some_and(|id| chunk_ids_to_embed.contains(&id)))
         .collect();
-
-    let embedded_count = batch.len();
-    storage.store_embeddings_batch(&batch, Some(current_model))?;
+    let (embedded_count, reused_count) =
+        embed_chunks_reusing(storage, embedder, &chunks_to_embed, batch_size, progress)?;
 
     let new_embeddings = embedded_count - replaced_count;
     let skipped_count = total_chunks - embedded_count;
@@ -615,6 +1473,7 @@ pub fn embed_buffer_chunks_incremental(
         embedded_count: new_embeddings,
         skipped_count,
         replaced_count,
+        reused_count,
         total_chunks,
         model_name: current_model.to_string(),
     })
@@ -626,6 +1485,7 @@ mod tests {
     use crate::core::{Buffer, Chunk};
     use crate::embedding::{DEFAULT_DIMENSIONS, FallbackEmbedder};
     use crate::storage::Storage;
+    use std::time::Duration;
 
     fn setup_storage() -> SqliteStorage {
         let mut storage = SqliteStorage::in_memory().unwrap();
@@ -676,8 +1536,13 @@ mod tests {
         assert_eq!(config.top_k, DEFAULT_TOP_K);
         assert!((config.similarity_threshold - DEFAULT_SIMILARITY_THRESHOLD).abs() < f32::EPSILON);
         assert_eq!(config.rrf_k, 60);
+        assert!(!config.is_weighted());
         assert!(config.use_semantic);
         assert!(config.use_bm25);
+        assert_eq!(config.brute_force_limit, DEFAULT_BRUTE_FORCE_LIMIT);
+        assert!(config.semantic_index.is_none());
+        assert!(config.query_cache.is_none());
+        assert!(config.ivf_nprobe.is_none());
     }
 
     #[test]
@@ -696,6 +1561,47 @@ mod tests {
         assert!(config.use_bm25);
     }
 
+    #[test]
+    fn test_search_config_relaxations() {
+        let steps = SearchConfig::new().with_threshold(0.4).relaxations();
+        assert_eq!(steps.len(), 2);
+        assert!((steps[0].1.similarity_threshold - 0.2).abs() < f32::EPSILON);
+        assert!(steps[1].1.similarity_threshold.abs() < f32::EPSILON);
+
+        let steps = SearchConfig::new()
+            .with_threshold(0.4)
+            .with_bm25(false)
+            .relaxations();
+        assert_eq!(steps.len(), 3);
+        let widened = &steps[2].1;
+        assert!(widened.use_semantic && widened.use_bm25);
+        assert!(steps[2].0.contains("hybrid"));
+
+        // BM25-only has no threshold to relax, only the mode
+        let steps = SearchConfig::new().with_semantic(false).relaxations();
+        assert_eq!(steps.len(), 1);
+
+        let steps = SearchConfig::new().with_threshold(0.0).relaxations();
+        assert!(steps.is_empty());
+
+        // Raw FTS5 syntax is dropped first, and stays dropped
+        let steps = SearchConfig::new()
+            .with_threshold(0.4)
+            .with_raw_query(true)
+            .relaxations();
+        assert_eq!(steps.len(), 3);
+        assert!(steps[0].0.contains("raw query"));
+        assert!(steps.iter().all(|(_, config)| !config.raw_query));
+        assert!(
+            SearchConfig::new()
+                .with_raw_query(true)
+                .with_bm25(false)
+                .relaxations()
+                .iter()
+                .all(|(step, _)| !step.contains("raw query"))
+        );
+    }
+
     #[test]
     fn test_search_bm25() {
         let storage = setup_storage_with_chunks();
@@ -726,6 +1632,147 @@ mod tests {
         assert_eq!(count, 3); // We created 3 chunks
     }
 
+    /// Embedder that records batch sizes and fails on a chosen call.
+    struct BatchRecorder {
+        inner: FallbackEmbedder,
+        batches: std::sync::Mutex<Vec<usize>>,
+        fail_on_call: Option<usize>,
+    }
+
+    impl BatchRecorder {
+        fn new(fail_on_call: Option<usize>) -> Self {
+            Self {
+                inner: FallbackEmbedder::new(DEFAULT_DIMENSIONS),
+                batches: std::sync::Mutex::new(Vec::new()),
+                fail_on_call,
+            }
+        }
+    }
+
+    impl Embedder for BatchRecorder {
+        fn dimensions(&self) -> usize {
+            self.inner.dimensions()
+        }
+
+        fn model_name(&self) -> &'static str {
+            self.inner.model_name()
+        }
+
+        fn embed(&self, text: &str) -> Result<Vec<f32>> {
+            self.inner.embed(text)
+        }
+
+        fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+            {
+                let mut batches = self.batches.lock().unwrap();
+                if self.fail_on_call == Some(batches.len()) {
+                    return Err(crate::error::Error::InvalidState {
+                        message: "backend unavailable".to_string(),
+                    });
+                }
+                batches.push(texts.len());
+            }
+            self.inner.embed_batch(texts)
+        }
+    }
+
+    #[test]
+    fn test_embed_buffer_chunks_batched() {
+        let mut storage = setup_storage_with_chunks();
+        let embedder = BatchRecorder::new(None);
+
+        let count = embed_buffer_chunks_batched(&mut storage, &embedder, 1, 2).unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(*embedder.batches.lock().unwrap(), vec![2, 1]);
+        assert!(buffer_fully_embedded(&storage, 1).unwrap());
+    }
+
+    #[test]
+    fn test_embed_buffer_chunks_progress() {
+        let mut storage = setup_storage_with_chunks();
+        let embedder = BatchRecorder::new(None);
+        let calls = std::cell::RefCell::new(Vec::new());
+        let progress = |done: usize, total: usize| calls.borrow_mut().push((done, total));
+
+        let count =
+            embed_buffer_chunks_with_progress(&mut storage, &embedder, 1, 2, Some(&progress))
+                .unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(*calls.borrow(), vec![(2, 3), (3, 3)]);
+    }
+
+    #[test]
+    fn test_embed_buffer_chunks_batched_partial_failure() {
+        let mut storage = setup_storage_with_chunks();
+
+        // Second batch fails; the first stays stored
+        let failing = BatchRecorder::new(Some(1));
+        assert!(embed_buffer_chunks_batched(&mut storage, &failing, 1, 2).is_err());
+        assert_eq!(
+            storage.get_chunks_needing_embedding(1, None).unwrap().len(),
+            1
+        );
+
+        // An incremental re-run embeds only the remainder
+        let embedder = BatchRecorder::new(None);
+        let result = embed_buffer_chunks_incremental(&mut storage, &embedder, 1, false, 2).unwrap();
+        assert_eq!(result.embedded_count, 1);
+        assert_eq!(*embedder.batches.lock().unwrap(), vec![1]);
+    }
+
+    #[test]
+    fn test_incremental_embed_reuses_by_content_hash() {
+        let mut storage = setup_storage_with_chunks();
+        let embedder = BatchRecorder::new(None);
+        embed_buffer_chunks_incremental(&mut storage, &embedder, 1, false, 10).unwrap();
+
+        // Two chunks repeat existing content, two share new content
+        let buffer = Buffer::from_named("copy.txt".to_string(), "copy".to_string());
+        let buffer_id = storage.add_buffer(&buffer).unwrap();
+        let texts = [
+            "Rust is a systems programming language",
+            "The quick brown fox jumps over the lazy dog",
+            "Brand new text",
+            "Brand new text",
+        ];
+        let chunks: Vec<Chunk> = texts
+            .iter()
+            .enumerate()
+            .map(|(i, text)| Chunk::new(buffer_id, (*text).to_string(), i..i + 1, i))
+            .collect();
+        storage.add_chunks(buffer_id, &chunks).unwrap();
+
+        let embedder = BatchRecorder::new(None);
+        let calls = std::cell::RefCell::new(Vec::new());
+        let progress = |done: usize, total: usize| calls.borrow_mut().push((done, total));
+        let result = embed_buffer_chunks_incremental_with_progress(
+            &mut storage,
+            &embedder,
+            buffer_id,
+            false,
+            10,
+            Some(&progress),
+        )
+        .unwrap();
+        // Reused, computed, then copied duplicates
+        assert_eq!(*calls.borrow(), vec![(2, 4), (3, 4), (4, 4)]);
+        assert_eq!(result.embedded_count, 4);
+        assert_eq!(result.reused_count, 3);
+        assert_eq!(result.computed_count(), 1);
+        assert_eq!(*embedder.batches.lock().unwrap(), vec![1]);
+        assert!(buffer_fully_embedded(&storage, buffer_id).unwrap());
+
+        let stored = storage.get_chunks(buffer_id).unwrap();
+        let embedding = |i: usize| storage.get_embedding(stored[i].id.unwrap()).unwrap();
+        assert_eq!(embedding(2), embedding(3));
+        assert_eq!(
+            embedding(0).unwrap(),
+            FallbackEmbedder::new(DEFAULT_DIMENSIONS)
+                .embed(texts[0])
+                .unwrap()
+        );
+    }
+
     #[test]
     fn test_embed_buffer_chunks_empty() {
         let mut storage = setup_storage();
@@ -802,6 +1849,296 @@ mod tests {
         assert!(results[0].bm25_score.is_none());
     }
 
+    #[test]
+    fn test_hybrid_search_skips_mismatched_dimensions() {
+        let mut storage = setup_storage_with_chunks();
+        let embedder = FallbackEmbedder::new(DEFAULT_DIMENSIONS);
+        embed_buffer_chunks(&mut storage, &embedder, 1).unwrap();
+
+        // Re-embed one chunk as if by a model with different dimensions
+        let chunk_id = storage.get_chunks(1).unwrap()[0].id.unwrap();
+        storage
+            .store_embedding(chunk_id, &[1.0, 0.0, 0.0], Some("other-model"))
+            .unwrap();
+
+        let config = SearchConfig::new().with_bm25(false).with_threshold(-1.0);
+        let results = hybrid_search(&storage, &embedder, "programming language", &config).unwrap();
+        assert!(!results.is_empty());
+        assert!(results.iter().all(|r| r.chunk_id != chunk_id));
+    }
+
+    #[test]
+    #[cfg(feature = "usearch-hnsw")]
+    fn test_hybrid_search_semantic_index() {
+        let mut storage = setup_storage_with_chunks();
+        let embedder = FallbackEmbedder::new(DEFAULT_DIMENSIONS);
+        embed_buffer_chunks(&mut storage, &embedder, 1).unwrap();
+
+        let base = SearchConfig::new().with_bm25(false).with_threshold(0.0);
+        let brute = hybrid_search(&storage, &embedder, "programming language", &base).unwrap();
+
+        let index = Arc::new(SemanticIndex::build(&storage).unwrap());
+        let config = base
+            .with_brute_force_limit(0)
+            .with_semantic_index(Arc::clone(&index));
+        let indexed = hybrid_search(&storage, &embedder, "programming language", &config).unwrap();
+        assert_eq!(indexed[0].chunk_id, brute[0].chunk_id);
+
+        // A stale index is ignored in favor of a brute-force scan
+        storage.delete_embedding(brute[0].chunk_id).unwrap();
+        assert!(index.is_stale(&storage).unwrap());
+        let fallback = hybrid_search(&storage, &embedder, "programming language", &config).unwrap();
+        assert!(fallback.iter().all(|r| r.chunk_id != brute[0].chunk_id));
+    }
+
+    #[test]
+    #[cfg(feature = "sqlite-vec")]
+    fn test_hybrid_search_sqlite_vec() {
+        let mut storage = setup_storage_with_chunks();
+        let embedder = FallbackEmbedder::new(DEFAULT_DIMENSIONS);
+        embed_buffer_chunks(&mut storage, &embedder, 1).unwrap();
+
+        let config = SearchConfig::new().with_bm25(false).with_threshold(-1.0);
+        let knn = hybrid_search(&storage, &embedder, "programming language", &config).unwrap();
+
+        // A chunk filter forces the brute-force scan; scores must agree
+        let all_ids: HashSet<i64> = knn.iter().map(|r| r.chunk_id).collect();
+        let brute = hybrid_search(
+            &storage,
+            &embedder,
+            "programming language",
+            &config.with_chunk_filter(all_ids),
+        )
+        .unwrap();
+        assert_eq!(knn[0].chunk_id, brute[0].chunk_id);
+        let (a, b) = (
+            knn[0].semantic_score.unwrap(),
+            brute[0].semantic_score.unwrap(),
+        );
+        assert!((a - b).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_hybrid_search_query_cache() {
+        let mut storage = setup_storage_with_chunks();
+        let embedder = FallbackEmbedder::new(DEFAULT_DIMENSIONS);
+        embed_buffer_chunks(&mut storage, &embedder, 1).unwrap();
+
+        let cache = Arc::new(QueryEmbeddingCache::new(8));
+        let config = SearchConfig::new()
+            .with_bm25(false)
+            .with_threshold(-1.0)
+            .with_query_cache(Arc::clone(&cache));
+
+        let uncached = hybrid_search(
+            &storage,
+            &embedder,
+            "rust programming",
+            &SearchConfig::new().with_bm25(false).with_threshold(-1.0),
+        )
+        .unwrap();
+        let first = hybrid_search(&storage, &embedder, "rust programming", &config).unwrap();
+        let second = hybrid_search(&storage, &embedder, "rust programming", &config).unwrap();
+        assert_eq!(cache.len(), 1);
+
+        let ids = |results: &[SearchResult]| results.iter().map(|r| r.chunk_id).collect::<Vec<_>>();
+        assert_eq!(ids(&first), ids(&uncached));
+        assert_eq!(ids(&second), ids(&uncached));
+
+        // BM25-only searches never embed the query
+        let bm25 = config.with_semantic(false).with_bm25(true);
+        hybrid_search(&storage, &embedder, "other query", &bm25).unwrap();
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_hybrid_search_ivf_nprobe() {
+        let mut storage = setup_storage_with_chunks();
+        let embedder = FallbackEmbedder::new(DEFAULT_DIMENSIONS);
+        embed_buffer_chunks(&mut storage, &embedder, 1).unwrap();
+        let config = SearchConfig::new().with_bm25(false).with_threshold(-1.0);
+        let exact = hybrid_search(&storage, &embedder, "rust programming", &config).unwrap();
+
+        // Without an index, nprobe falls back to a full scan
+        let ivf = config.clone().with_ivf_nprobe(1);
+        let unindexed = hybrid_search(&storage, &embedder, "rust programming", &ivf).unwrap();
+        assert_eq!(unindexed.len(), exact.len());
+
+        // One cluster per chunk: a single probe scans one chunk
+        build_ivf_index(&mut storage, Some(3), DEFAULT_IVF_ITERATIONS, 1).unwrap();
+        let probed = hybrid_search(&storage, &embedder, "rust programming", &ivf).unwrap();
+        assert_eq!(probed.len(), 1);
+        let all = config.with_ivf_nprobe(3);
+        let full = hybrid_search(&storage, &embedder, "rust programming", &all).unwrap();
+        assert_eq!(full.len(), exact.len());
+    }
+
+    #[test]
+    fn test_hybrid_search_metrics() {
+        let mut storage = setup_storage_with_chunks();
+        let embedder = FallbackEmbedder::new(DEFAULT_DIMENSIONS);
+        embed_buffer_chunks(&mut storage, &embedder, 1).unwrap();
+        let search = |metric: SimilarityMetric, threshold: f32| {
+            let config = SearchConfig::new()
+                .with_bm25(false)
+                .with_threshold(threshold)
+                .with_metric(metric);
+            hybrid_search(&storage, &embedder, "rust programming", &config).unwrap()
+        };
+        let scores = |results: &[SearchResult]| -> Vec<(i64, f32)> {
+            results
+                .iter()
+                .map(|r| (r.chunk_id, r.semantic_score.unwrap()))
+                .collect()
+        };
+
+        // The fallback embedder normalizes, so dot product equals cosine
+        let cosine = scores(&search(SimilarityMetric::Cosine, -1.0));
+        let dot = scores(&search(SimilarityMetric::Dot, -1.0));
+        assert_eq!(cosine.len(), dot.len());
+        for ((cosine_id, cosine_score), (dot_id, dot_score)) in cosine.iter().zip(&dot) {
+            assert_eq!(cosine_id, dot_id);
+            assert!((cosine_score - dot_score).abs() < 1e-5);
+        }
+
+        // Euclidean inverse scores unit vectors in [1/3, 1]: a threshold
+        // below 1/3 keeps everything, and the best match is the same (chunks
+        // sharing no terms tie at cosine 0, so only the top is compared)
+        let euclidean = scores(&search(SimilarityMetric::EuclideanInverse, 0.3));
+        assert_eq!(euclidean.len(), cosine.len());
+        assert!(
+            euclidean
+                .iter()
+                .all(|(_, s)| (1.0 / 3.0 - 1e-5..=1.0).contains(s))
+        );
+        assert_eq!(euclidean[0].0, cosine[0].0);
+        assert!(search(SimilarityMetric::EuclideanInverse, 1.1).is_empty());
+    }
+
+    #[test]
+    fn test_semantic_search_normalized_matches_cosine() {
+        let mut storage = setup_storage_with_chunks();
+        let embedder = FallbackEmbedder::new(DEFAULT_DIMENSIONS);
+        let scaled: Vec<(i64, Vec<f32>)> = storage
+            .get_chunks(1)
+            .unwrap()
+            .iter()
+            .map(|c| {
+                let embedding = embedder.embed(&c.content).unwrap();
+                (c.id.unwrap(), embedding.iter().map(|v| v * 2.0).collect())
+            })
+            .collect();
+        let config = SearchConfig::new().with_bm25(false).with_threshold(-1.0);
+        let search = |storage: &SqliteStorage| {
+            let mut scores: Vec<(i64, f32)> =
+                hybrid_search(storage, &embedder, "rust programming", &config)
+                    .unwrap()
+                    .iter()
+                    .map(|r| (r.chunk_id, r.semantic_score.unwrap()))
+                    .collect();
+            scores.sort_by_key(|(chunk_id, _)| *chunk_id);
+            scores
+        };
+
+        // Unnormalized vectors take the full cosine path
+        storage.store_embeddings_batch(&scaled, None).unwrap();
+        assert!(!storage.embeddings_normalized().unwrap());
+        let cosine = search(&storage);
+
+        // Normalized at store time, the dot product path scores the same
+        storage.set_normalize_embeddings(true);
+        storage.store_embeddings_batch(&scaled, None).unwrap();
+        assert!(storage.embeddings_normalized().unwrap());
+        let dot = search(&storage);
+
+        assert_eq!(cosine.len(), scaled.len());
+        assert_eq!(cosine.len(), dot.len());
+        for ((cosine_id, cosine_score), (dot_id, dot_score)) in cosine.iter().zip(&dot) {
+            assert_eq!(cosine_id, dot_id);
+            assert!((cosine_score - dot_score).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_hybrid_search_deadline() {
+        let mut storage = setup_storage_with_chunks();
+        let embedder = FallbackEmbedder::new(DEFAULT_DIMENSIONS);
+        embed_buffer_chunks(&mut storage, &embedder, 1).unwrap();
+        let search = |timeout: Duration| {
+            let deadline = Arc::new(SearchDeadline::after(timeout));
+            let config = SearchConfig::new()
+                .with_threshold(0.0)
+                .with_deadline(Arc::clone(&deadline));
+            let results = hybrid_search(&storage, &embedder, "rust", &config).unwrap();
+            (results, deadline.expired())
+        };
+
+        // A generous deadline changes nothing
+        let unbounded = hybrid_search(
+            &storage,
+            &embedder,
+            "rust",
+            &SearchConfig::new().with_threshold(0.0),
+        )
+        .unwrap();
+        let (results, partial) = search(Duration::from_secs(60));
+        assert!(!partial);
+        assert_eq!(
+            results.iter().map(|r| r.chunk_id).collect::<Vec<_>>(),
+            unbounded.iter().map(|r| r.chunk_id).collect::<Vec<_>>()
+        );
+
+        // An expired one stops both paths before they score anything
+        let (results, partial) = search(Duration::ZERO);
+        assert!(partial);
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn test_hybrid_search_mmr_diversifies() {
+        let mut storage = setup_storage();
+        let buffer_id = storage
+            .add_buffer(&Buffer::from_named("dup.txt".to_string(), String::new()))
+            .unwrap();
+        let chunks = vec![
+            Chunk::new(
+                buffer_id,
+                "license boilerplate header".to_string(),
+                0..26,
+                0,
+            ),
+            Chunk::new(
+                buffer_id,
+                "license boilerplate header".to_string(),
+                26..52,
+                1,
+            ),
+            Chunk::new(buffer_id, "parser error recovery".to_string(), 52..73, 2),
+        ];
+        storage.add_chunks(buffer_id, &chunks).unwrap();
+        let embedder = FallbackEmbedder::new(DEFAULT_DIMENSIONS);
+        embed_buffer_chunks(&mut storage, &embedder, buffer_id).unwrap();
+
+        let config = SearchConfig::new()
+            .with_top_k(2)
+            .with_bm25(false)
+            .with_threshold(-1.0);
+        let plain =
+            hybrid_search(&storage, &embedder, "license boilerplate header", &config).unwrap();
+        assert!(plain.iter().all(|r| r.index < 2));
+
+        let diverse = hybrid_search(
+            &storage,
+            &embedder,
+            "license boilerplate header",
+            &config.with_mmr_lambda(0.3),
+        )
+        .unwrap();
+        assert_eq!(diverse.len(), 2);
+        assert!(diverse[0].index < 2);
+        assert_eq!(diverse[1].index, 2);
+    }
+
     #[test]
     fn test_hybrid_search_both() {
         let mut storage = setup_storage_with_chunks();
@@ -819,6 +2156,114 @@ mod tests {
         assert!(!results.is_empty());
     }
 
+    #[test]
+    fn test_populate_snippets_bm25_only() {
+        let mut storage = setup_storage_with_chunks();
+        let embedder = FallbackEmbedder::new(DEFAULT_DIMENSIONS);
+        embed_buffer_chunks(&mut storage, &embedder, 1).unwrap();
+
+        let config = SearchConfig::new().with_threshold(0.0);
+        let mut results = hybrid_search(&storage, &embedder, "programming", &config).unwrap();
+        populate_snippets(&storage, &mut results, "programming", false).unwrap();
+
+        for result in &results {
+            if result.bm25_score.is_some() {
+                let snippet = result.snippet.as_deref().unwrap();
+                assert!(snippet.contains("[programming]"), "{snippet}");
+            } else {
+                assert!(result.snippet.is_none());
+            }
+        }
+        assert!(results.iter().any(|r| r.snippet.is_some()));
+    }
+
+    #[test]
+    fn test_hybrid_search_offset_pages() {
+        let storage = setup_storage_with_chunks();
+        let embedder = FallbackEmbedder::new(DEFAULT_DIMENSIONS);
+        let base = SearchConfig::new().with_semantic(false);
+        let ids = |config: SearchConfig| -> Vec<i64> {
+            hybrid_search(&storage, &embedder, "is the", &config)
+                .unwrap()
+                .iter()
+                .map(|r| r.chunk_id)
+                .collect()
+        };
+
+        let all = ids(base.clone().with_top_k(10));
+        assert_eq!(all.len(), 3);
+        let paged: Vec<i64> = (0..4)
+            .flat_map(|offset| ids(base.clone().with_top_k(1).with_offset(offset)))
+            .collect();
+        assert_eq!(paged, all);
+
+        // Filtered-out chunks leave no gaps in a page
+        let allowed: HashSet<i64> = all[1..].iter().copied().collect();
+        let filtered = base.with_top_k(1).with_chunk_filter(allowed);
+        assert_eq!(ids(filtered.clone()), vec![all[1]]);
+        assert_eq!(ids(filtered.clone().with_offset(1)), vec![all[2]]);
+        assert!(ids(filtered.with_offset(2)).is_empty());
+    }
+
+    #[test]
+    fn test_hybrid_search_extreme_weights() {
+        let mut storage = setup_storage_with_chunks();
+        let embedder = FallbackEmbedder::new(DEFAULT_DIMENSIONS);
+        embed_buffer_chunks(&mut storage, &embedder, 1).unwrap();
+
+        let base = SearchConfig::new().with_threshold(0.0);
+        let ids = |config: &SearchConfig| -> Vec<i64> {
+            hybrid_search(&storage, &embedder, "programming", config)
+                .unwrap()
+                .iter()
+                .map(|r| r.chunk_id)
+                .collect()
+        };
+
+        // A zero BM25 weight ranks by the semantic list alone
+        let semantic_only = ids(&base.clone().with_bm25(false));
+        let weighted = ids(&base.clone().with_weights(1.0, 0.0));
+        assert_eq!(weighted[0], semantic_only[0]);
+
+        // A zero semantic weight puts the only keyword match first
+        let bm25_only = ids(&base.clone().with_semantic(false));
+        assert_eq!(bm25_only.len(), 1);
+        let weighted = ids(&base.with_weights(0.0, 1.0));
+        assert_eq!(weighted[0], bm25_only[0]);
+    }
+
+    #[test]
+    fn test_hybrid_search_buffer_type() {
+        let mut storage = setup_storage_with_chunks();
+        let embedder = FallbackEmbedder::new(DEFAULT_DIMENSIONS);
+        embed_buffer_chunks(&mut storage, &embedder, 1).unwrap();
+
+        let mut code = Buffer::from_named("lib.rs".to_string(), "fn programming()".to_string());
+        code.metadata.content_type = Some("rs".to_string());
+        let code_id = storage.add_buffer(&code).unwrap();
+        let chunks = vec![Chunk::new(
+            code_id,
+            "fn programming()".to_string(),
+            0..16,
+            0,
+        )];
+        storage.add_chunks(code_id, &chunks).unwrap();
+        embed_buffer_chunks(&mut storage, &embedder, code_id).unwrap();
+
+        let config = SearchConfig::new()
+            .with_threshold(0.0)
+            .with_buffer_type("rs");
+        let results = hybrid_search(&storage, &embedder, "programming", &config).unwrap();
+        assert!(!results.is_empty());
+        assert!(results.iter().all(|r| r.buffer_id == code_id));
+
+        let config = SearchConfig::new()
+            .with_threshold(0.0)
+            .with_buffer_type("py");
+        let results = hybrid_search(&storage, &embedder, "programming", &config).unwrap();
+        assert!(results.is_empty());
+    }
+
     #[test]
     fn test_search_semantic() {
         let mut storage = setup_storage_with_chunks();
@@ -851,7 +2296,14 @@ mod tests {
         let embedder = FallbackEmbedder::new(DEFAULT_DIMENSIONS);
 
         // First incremental embed - should embed all 3 chunks
-        let result = embed_buffer_chunks_incremental(&mut storage, &embedder, 1, false).unwrap();
+        let result = embed_buffer_chunks_incremental(
+            &mut storage,
+            &embedder,
+            1,
+            false,
+            DEFAULT_EMBED_BATCH_SIZE,
+        )
+        .unwrap();
         assert_eq!(result.embedded_count, 3);
         assert_eq!(result.skipped_count, 0);
         assert_eq!(result.replaced_count, 0);
@@ -859,7 +2311,14 @@ mod tests {
         assert!(result.had_changes());
 
         // Second incremental embed - should skip all (already embedded)
-        let result2 = embed_buffer_chunks_incremental(&mut storage, &embedder, 1, false).unwrap();
+        let result2 = embed_buffer_chunks_incremental(
+            &mut storage,
+            &embedder,
+            1,
+            false,
+            DEFAULT_EMBED_BATCH_SIZE,
+        )
+        .unwrap();
         assert_eq!(result2.embedded_count, 0);
         assert_eq!(result2.skipped_count, 3);
         assert_eq!(result2.replaced_count, 0);
@@ -872,26 +2331,225 @@ mod tests {
         let embedder = FallbackEmbedder::new(DEFAULT_DIMENSIONS);
 
         // First embed normally
-        embed_buffer_chunks_incremental(&mut storage, &embedder, 1, false).unwrap();
+        embed_buffer_chunks_incremental(
+            &mut storage,
+            &embedder,
+            1,
+            false,
+            DEFAULT_EMBED_BATCH_SIZE,
+        )
+        .unwrap();
 
         // Force re-embed - should replace all 3
-        let result = embed_buffer_chunks_incremental(&mut storage, &embedder, 1, true).unwrap();
+        let result = embed_buffer_chunks_incremental(
+            &mut storage,
+            &embedder,
+            1,
+            true,
+            DEFAULT_EMBED_BATCH_SIZE,
+        )
+        .unwrap();
         // All chunks already have correct model, so no changes needed even with force
         // (force only affects different-model embeddings)
         assert_eq!(result.skipped_count, 3);
         assert!(!result.had_changes());
     }
 
+    #[test]
+    fn test_reembed_stale_chunks() {
+        let mut storage = setup_storage_with_chunks();
+        let embedder = FallbackEmbedder::new(DEFAULT_DIMENSIONS);
+        embed_buffer_chunks_incremental(
+            &mut storage,
+            &embedder,
+            1,
+            false,
+            DEFAULT_EMBED_BATCH_SIZE,
+        )
+        .unwrap();
+
+        // Nothing was embedded before the epoch
+        let result =
+            reembed_stale_chunks(&mut storage, &embedder, 1, 0, DEFAULT_EMBED_BATCH_SIZE).unwrap();
+        assert!(!result.had_changes());
+        assert_eq!(result.skipped_count, 3);
+
+        // Everything was embedded before the far future
+        let result = reembed_stale_chunks(
+            &mut storage,
+            &embedder,
+            1,
+            i64::MAX,
+            DEFAULT_EMBED_BATCH_SIZE,
+        )
+        .unwrap();
+        assert_eq!(result.replaced_count, 3);
+        assert_eq!(result.embedded_count, 0);
+        assert_eq!(result.total_chunks, 3);
+    }
+
+    #[test]
+    fn test_reembed_chunks_fixes_dimensions() {
+        let mut storage = setup_storage_with_chunks();
+        let ids: Vec<i64> = storage
+            .get_chunks(1)
+            .unwrap()
+            .iter()
+            .filter_map(|c| c.id)
+            .collect();
+        storage.store_embedding(ids[0], &[1.0, 0.0], None).unwrap();
+
+        let embedder = FallbackEmbedder::new(DEFAULT_DIMENSIONS);
+        let mismatched = storage
+            .get_chunks_with_other_dimensions(DEFAULT_DIMENSIONS)
+            .unwrap();
+        assert_eq!(mismatched, vec![ids[0]]);
+
+        // Unknown IDs are skipped
+        let stored = reembed_chunks(&mut storage, &embedder, &[ids[0], 9999], 10).unwrap();
+        assert_eq!(stored, 1);
+        assert_eq!(
+            storage.get_embedding(ids[0]).unwrap().unwrap().len(),
+            DEFAULT_DIMENSIONS
+        );
+        assert!(
+            storage
+                .get_chunks_with_other_dimensions(DEFAULT_DIMENSIONS)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_reembed_chunks_atomic() {
+        let mut storage = setup_storage_with_chunks();
+        let ids: Vec<i64> = storage
+            .get_chunks(1)
+            .unwrap()
+            .iter()
+            .filter_map(|c| c.id)
+            .collect();
+        for &id in &ids {
+            storage.store_embedding(id, &[1.0, 0.0], None).unwrap();
+        }
+
+        // A failing second batch stores nothing
+        let failing = BatchRecorder::new(Some(1));
+        assert!(reembed_chunks_atomic(&mut storage, &failing, &ids, 2).is_err());
+        assert_eq!(
+            storage
+                .get_chunks_with_other_dimensions(DEFAULT_DIMENSIONS)
+                .unwrap(),
+            ids
+        );
+
+        let embedder = BatchRecorder::new(None);
+        let stored = reembed_chunks_atomic(&mut storage, &embedder, &ids, 2).unwrap();
+        assert_eq!(stored, 3);
+        assert!(
+            storage
+                .get_chunks_with_other_dimensions(DEFAULT_DIMENSIONS)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_embed_missing_chunks() {
+        let mut storage = setup_storage_with_chunks();
+        let ids: Vec<i64> = storage
+            .get_chunks(1)
+            .unwrap()
+            .iter()
+            .filter_map(|c| c.id)
+            .collect();
+        storage
+            .store_embedding(ids[0], &[1.0; DEFAULT_DIMENSIONS], None)
+            .unwrap();
+
+        // Only the two chunks without an embedding are embedded
+        let embedder = BatchRecorder::new(None);
+        let stored = embed_missing_chunks(&mut storage, &embedder, &[1, 999], 10).unwrap();
+        assert_eq!(stored, 2);
+        assert_eq!(*embedder.batches.lock().unwrap(), vec![2]);
+        assert!(buffer_fully_embedded(&storage, 1).unwrap());
+
+        assert_eq!(
+            embed_missing_chunks(&mut storage, &embedder, &[1], 10).unwrap(),
+            0
+        );
+    }
+
     #[test]
     fn test_incremental_embed_result_completion() {
         let result = IncrementalEmbedResult {
             embedded_count: 2,
             skipped_count: 3,
             replaced_count: 0,
+            reused_count: 1,
             total_chunks: 5,
             model_name: "test".to_string(),
         };
         assert!(result.had_changes());
+        assert_eq!(result.computed_count(), 1);
         assert!((result.completion_percentage() - 100.0).abs() < f64::EPSILON);
     }
+
+    fn result_with_title(chunk_id: i64, score: f64, title: Option<&str>) -> SearchResult {
+        SearchResult {
+            chunk_id,
+            buffer_id: 1,
+            index: 0,
+            score,
+            semantic_score: None,
+            bm25_score: None,
+            content_preview: None,
+            title: title.map(String::from),
+            snippet: None,
+        }
+    }
+
+    #[test]
+    fn test_apply_title_boost_reorders() {
+        let mut results = vec![
+            result_with_title(1, 0.030, Some("Introduction")),
+            result_with_title(2, 0.025, Some("Error handling")),
+            result_with_title(3, 0.020, None),
+        ];
+        apply_title_boost(&mut results, "error handling", 0.5);
+
+        assert_eq!(results[0].chunk_id, 2);
+        assert!((results[0].score - 0.0375).abs() < 1e-9);
+        assert!((results[1].score - 0.030).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_apply_title_boost_disabled() {
+        let mut results = vec![
+            result_with_title(1, 0.030, Some("Introduction")),
+            result_with_title(2, 0.025, Some("Error handling")),
+        ];
+        apply_title_boost(&mut results, "error", 0.0);
+        assert_eq!(results[0].chunk_id, 1);
+        assert!((results[1].score - 0.025).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_hybrid_search_populates_titles() {
+        let mut storage = setup_storage();
+        let buffer = Buffer::from_named("doc".to_string(), "x".to_string());
+        let buffer_id = storage.add_buffer(&buffer).unwrap();
+        let mut chunk = Chunk::new(buffer_id, "Rust ownership rules".to_string(), 0..20, 0);
+        chunk.set_title("Ownership");
+        storage.add_chunks(buffer_id, &[chunk]).unwrap();
+
+        let config = SearchConfig::new()
+            .with_semantic(false)
+            .with_title_boost(0.5);
+        let embedder = FallbackEmbedder::new(DEFAULT_DIMENSIONS);
+        let results = hybrid_search(&storage, &embedder, "ownership", &config).unwrap();
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].title.as_deref(), Some("Ownership"));
+    }
 }
@@ -199,6 +199,19 @@ mod tests {
         assert_eq!(results[0].0, 1);
     }
 
+    #[test]
+    fn test_weighted_rrf_zero_weight() {
+        let list1 = vec![1, 2];
+        let list2 = vec![3, 2, 1];
+        let config = RrfConfig::new(60);
+
+        // A zero weight leaves only list1's ranking; items unique to list2 score 0
+        let results = weighted_rrf(&[(&list1, 1.0), (&list2, 0.0)], &config);
+        assert_eq!(results[0].0, 1);
+        assert_eq!(results[1].0, 2);
+        assert_eq!(results[2], (3, 0.0));
+    }
+
     #[test]
     fn test_rrf_score_formula() {
         let list = vec![1];
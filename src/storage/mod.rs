@@ -9,11 +9,17 @@ pub mod sqlite;
 pub mod traits;
 
 pub use schema::{CURRENT_SCHEMA_VERSION, SCHEMA_SQL};
-pub use sqlite::{EmbeddingStats, SqliteStorage};
-pub use traits::Storage;
+pub use sqlite::{
+    BufferStats, BufferVersion, DetailedStats, EmbeddingCoverage, EmbeddingFingerprint,
+    EmbeddingStats, JournalMode, SqliteOptions, SqliteStorage, StorageUsage, TableUsage,
+};
+pub use traits::{ChunkDirection, Storage};
 
 /// Default database file name.
 pub const DEFAULT_DB_NAME: &str = "rlm-state.db";
 
 /// Default database path relative to project root.
 pub const DEFAULT_DB_PATH: &str = ".rlm/rlm-state.db";
+
+/// Default number of saved versions kept per buffer.
+pub const DEFAULT_KEEP_VERSIONS: usize = 10;
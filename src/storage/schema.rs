@@ -3,10 +3,37 @@
 //! Contains SQL schema and migration logic for the RLM `SQLite` database.
 
 /// Current schema version.
-pub const CURRENT_SCHEMA_VERSION: u32 = 3;
+pub const CURRENT_SCHEMA_VERSION: u32 = 16;
+
+/// Triggers that keep `buffers.chunk_count` in sync with `chunks` (v9).
+///
+/// They're skipped while the `defer_chunk_count` metadata row exists; see
+/// [`DEFER_CHUNK_COUNT_SQL`].
+macro_rules! chunk_count_triggers_sql {
+    () => {
+        r"
+CREATE TRIGGER IF NOT EXISTS chunks_count_ai AFTER INSERT ON chunks
+WHEN NOT EXISTS (SELECT 1 FROM metadata WHERE key = 'defer_chunk_count') BEGIN
+    UPDATE buffers SET chunk_count = COALESCE(chunk_count, 0) + 1 WHERE id = new.buffer_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS chunks_count_ad AFTER DELETE ON chunks
+WHEN NOT EXISTS (SELECT 1 FROM metadata WHERE key = 'defer_chunk_count') BEGIN
+    UPDATE buffers SET chunk_count = MAX(COALESCE(chunk_count, 0) - 1, 0) WHERE id = old.buffer_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS chunks_count_au AFTER UPDATE OF buffer_id ON chunks
+WHEN NOT EXISTS (SELECT 1 FROM metadata WHERE key = 'defer_chunk_count') BEGIN
+    UPDATE buffers SET chunk_count = MAX(COALESCE(chunk_count, 0) - 1, 0) WHERE id = old.buffer_id;
+    UPDATE buffers SET chunk_count = COALESCE(chunk_count, 0) + 1 WHERE id = new.buffer_id;
+END;
+"
+    };
+}
 
 /// SQL schema for initial database setup.
-pub const SCHEMA_SQL: &str = r"
+pub const SCHEMA_SQL: &str = concat!(
+    r"
 -- Schema version tracking
 CREATE TABLE IF NOT EXISTS schema_info (
     key TEXT PRIMARY KEY,
@@ -32,6 +59,12 @@ CREATE TABLE IF NOT EXISTS buffers (
     size INTEGER NOT NULL,
     line_count INTEGER,
     chunk_count INTEGER,
+    tags TEXT NOT NULL DEFAULT '[]',  -- JSON array of tag names (v4)
+    source_encoding TEXT,  -- Encoding the source was transcoded from (v6)
+    custom_metadata TEXT,  -- JSON object of user annotations (v7)
+    deleted_at INTEGER,  -- Set when moved to the trash (v10)
+    line_ending TEXT,  -- Dominant line ending: LF, CRLF, or CR (v13)
+    whitespace_normalized INTEGER NOT NULL DEFAULT 0,  -- Canonicalized at load (v15)
     created_at INTEGER NOT NULL,
     updated_at INTEGER NOT NULL
 );
@@ -42,6 +75,9 @@ CREATE INDEX IF NOT EXISTS idx_buffers_name ON buffers(name);
 -- Index for buffer lookup by hash (deduplication)
 CREATE INDEX IF NOT EXISTS idx_buffers_hash ON buffers(content_hash);
 
+-- Index for separating trashed buffers from live ones (v10)
+CREATE INDEX IF NOT EXISTS idx_buffers_deleted ON buffers(deleted_at);
+
 -- Chunks (segments of buffer content)
 CREATE TABLE IF NOT EXISTS chunks (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -67,6 +103,22 @@ CREATE INDEX IF NOT EXISTS idx_chunks_buffer ON chunks(buffer_id);
 -- Index for chunk ordering
 CREATE INDEX IF NOT EXISTS idx_chunks_order ON chunks(buffer_id, chunk_index);
 
+-- Index for chunk lookup by hash (embedding reuse)
+CREATE INDEX IF NOT EXISTS idx_chunks_hash ON chunks(content_hash);
+
+-- Prior buffer content saved by `update --history` (v11)
+CREATE TABLE IF NOT EXISTS buffer_versions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    buffer_id INTEGER NOT NULL,
+    version INTEGER NOT NULL,  -- 1-based, increasing per buffer
+    content TEXT NOT NULL,
+    content_hash TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,
+    UNIQUE (buffer_id, version),
+    FOREIGN KEY (buffer_id) REFERENCES buffers(id) ON DELETE CASCADE
+);
+
 -- Metadata key-value store for extensibility
 CREATE TABLE IF NOT EXISTS metadata (
     key TEXT PRIMARY KEY,
@@ -82,9 +134,27 @@ CREATE TABLE IF NOT EXISTS chunk_embeddings (
     dimensions INTEGER NOT NULL,
     model_name TEXT,
     created_at INTEGER NOT NULL,
+    normalized INTEGER NOT NULL DEFAULT 0,  -- 1 if stored at unit length (v14)
     FOREIGN KEY (chunk_id) REFERENCES chunks(id) ON DELETE CASCADE
 );
 
+-- IVF clusters of chunk embeddings for pre-filtered semantic search (v12)
+CREATE TABLE IF NOT EXISTS ivf_centroids (
+    id INTEGER PRIMARY KEY,
+    centroid BLOB NOT NULL,  -- f32 array serialized as bytes
+    dimensions INTEGER NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS ivf_assignments (
+    chunk_id INTEGER PRIMARY KEY,
+    centroid_id INTEGER NOT NULL,
+    FOREIGN KEY (chunk_id) REFERENCES chunk_embeddings(chunk_id) ON DELETE CASCADE,
+    FOREIGN KEY (centroid_id) REFERENCES ivf_centroids(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_ivf_assignments_centroid ON ivf_assignments(centroid_id);
+
 -- FTS5 virtual table for BM25 full-text search (v2)
 CREATE VIRTUAL TABLE IF NOT EXISTS chunks_fts USING fts5(
     content,
@@ -104,9 +174,71 @@ END;
 
 CREATE TRIGGER IF NOT EXISTS chunks_au AFTER UPDATE ON chunks BEGIN
     INSERT INTO chunks_fts(chunks_fts, rowid, content) VALUES('delete', old.id, old.content);
-    INSERT INTO chunks_fts(chunks_fts, rowid, content) VALUES (new.id, new.content);
+    INSERT INTO chunks_fts(rowid, content) VALUES (new.id, new.content);
+END;
+
+-- Triggers to keep buffers.chunk_count in sync with chunks (v9)
+",
+    chunk_count_triggers_sql!(),
+    r"
+-- Corpus version, bumped on any buffer/chunk/embedding change (v8)
+INSERT OR IGNORE INTO metadata (key, value, created_at, updated_at)
+VALUES ('corpus_version', '0', strftime('%s', 'now'), strftime('%s', 'now'));
+
+CREATE TABLE IF NOT EXISTS search_cache (
+    key TEXT PRIMARY KEY,  -- Hash of query + search options
+    corpus_version INTEGER NOT NULL,
+    results TEXT NOT NULL,  -- JSON serialized results
+    created_at INTEGER NOT NULL
+);
+
+-- Triggers that bump the corpus version, invalidating cached searches (v8)
+CREATE TRIGGER IF NOT EXISTS corpus_buffers_ai AFTER INSERT ON buffers BEGIN
+    UPDATE metadata SET value = CAST(value AS INTEGER) + 1, updated_at = strftime('%s', 'now')
+    WHERE key = 'corpus_version';
+END;
+
+CREATE TRIGGER IF NOT EXISTS corpus_buffers_au AFTER UPDATE ON buffers BEGIN
+    UPDATE metadata SET value = CAST(value AS INTEGER) + 1, updated_at = strftime('%s', 'now')
+    WHERE key = 'corpus_version';
+END;
+
+CREATE TRIGGER IF NOT EXISTS corpus_buffers_ad AFTER DELETE ON buffers BEGIN
+    UPDATE metadata SET value = CAST(value AS INTEGER) + 1, updated_at = strftime('%s', 'now')
+    WHERE key = 'corpus_version';
+END;
+
+CREATE TRIGGER IF NOT EXISTS corpus_chunks_ai AFTER INSERT ON chunks BEGIN
+    UPDATE metadata SET value = CAST(value AS INTEGER) + 1, updated_at = strftime('%s', 'now')
+    WHERE key = 'corpus_version';
 END;
-";
+
+CREATE TRIGGER IF NOT EXISTS corpus_chunks_au AFTER UPDATE ON chunks BEGIN
+    UPDATE metadata SET value = CAST(value AS INTEGER) + 1, updated_at = strftime('%s', 'now')
+    WHERE key = 'corpus_version';
+END;
+
+CREATE TRIGGER IF NOT EXISTS corpus_chunks_ad AFTER DELETE ON chunks BEGIN
+    UPDATE metadata SET value = CAST(value AS INTEGER) + 1, updated_at = strftime('%s', 'now')
+    WHERE key = 'corpus_version';
+END;
+
+CREATE TRIGGER IF NOT EXISTS corpus_embeddings_ai AFTER INSERT ON chunk_embeddings BEGIN
+    UPDATE metadata SET value = CAST(value AS INTEGER) + 1, updated_at = strftime('%s', 'now')
+    WHERE key = 'corpus_version';
+END;
+
+CREATE TRIGGER IF NOT EXISTS corpus_embeddings_au AFTER UPDATE ON chunk_embeddings BEGIN
+    UPDATE metadata SET value = CAST(value AS INTEGER) + 1, updated_at = strftime('%s', 'now')
+    WHERE key = 'corpus_version';
+END;
+
+CREATE TRIGGER IF NOT EXISTS corpus_embeddings_ad AFTER DELETE ON chunk_embeddings BEGIN
+    UPDATE metadata SET value = CAST(value AS INTEGER) + 1, updated_at = strftime('%s', 'now')
+    WHERE key = 'corpus_version';
+END;
+"
+);
 
 /// SQL to check if schema is initialized.
 pub const CHECK_SCHEMA_SQL: &str = r"
@@ -124,6 +256,22 @@ pub const SET_VERSION_SQL: &str = r"
 INSERT OR REPLACE INTO schema_info (key, value) VALUES ('version', ?);
 ";
 
+/// SQL to pause the `chunk_count` triggers for a deferred-count bulk write.
+///
+/// Run inside the write's transaction, paired with
+/// [`RESUME_CHUNK_COUNT_SQL`] before it commits, so other connections never
+/// see the marker row.
+pub const DEFER_CHUNK_COUNT_SQL: &str = r"
+INSERT OR REPLACE INTO metadata (key, value, created_at, updated_at)
+VALUES ('defer_chunk_count', '1', strftime('%s', 'now'), strftime('%s', 'now'));
+";
+
+/// SQL to resume the `chunk_count` triggers paused by
+/// [`DEFER_CHUNK_COUNT_SQL`].
+pub const RESUME_CHUNK_COUNT_SQL: &str = r"
+DELETE FROM metadata WHERE key = 'defer_chunk_count';
+";
+
 /// Migrations from older schema versions.
 pub struct Migration {
     /// Version this migration upgrades from.
@@ -182,6 +330,192 @@ const MIGRATION_V2_TO_V3: &str = r"
 DELETE FROM chunk_embeddings;
 ";
 
+/// SQL for v3 to v4 migration (adds buffer tags).
+///
+/// Existing buffers start with an empty tag list.
+const MIGRATION_V3_TO_V4: &str = r"
+-- JSON array of tag names per buffer
+ALTER TABLE buffers ADD COLUMN tags TEXT NOT NULL DEFAULT '[]';
+";
+
+/// SQL for v4 to v5 migration (indexes chunk hashes for embedding reuse).
+const MIGRATION_V4_TO_V5: &str = r"
+CREATE INDEX IF NOT EXISTS idx_chunks_hash ON chunks(content_hash);
+";
+
+/// SQL for v5 to v6 migration (records the source encoding of buffers).
+///
+/// Existing buffers were read as UTF-8 and keep a NULL encoding.
+const MIGRATION_V5_TO_V6: &str = r"
+ALTER TABLE buffers ADD COLUMN source_encoding TEXT;
+";
+
+/// SQL for v6 to v7 migration (adds key/value buffer annotations).
+const MIGRATION_V6_TO_V7: &str = r"
+ALTER TABLE buffers ADD COLUMN custom_metadata TEXT;
+";
+
+/// SQL for v7 to v8 migration (adds the corpus version and search cache).
+///
+/// The corpus version, kept in `metadata`, counts buffer, chunk, and
+/// embedding changes; cached results are only reused while it is unchanged.
+const MIGRATION_V7_TO_V8: &str = r"
+INSERT OR IGNORE INTO metadata (key, value, created_at, updated_at)
+VALUES ('corpus_version', '0', strftime('%s', 'now'), strftime('%s', 'now'));
+
+CREATE TABLE IF NOT EXISTS search_cache (
+    key TEXT PRIMARY KEY,  -- Hash of query + search options
+    corpus_version INTEGER NOT NULL,
+    results TEXT NOT NULL,  -- JSON serialized results
+    created_at INTEGER NOT NULL
+);
+
+CREATE TRIGGER IF NOT EXISTS corpus_buffers_ai AFTER INSERT ON buffers BEGIN
+    UPDATE metadata SET value = CAST(value AS INTEGER) + 1, updated_at = strftime('%s', 'now')
+    WHERE key = 'corpus_version';
+END;
+
+CREATE TRIGGER IF NOT EXISTS corpus_buffers_au AFTER UPDATE ON buffers BEGIN
+    UPDATE metadata SET value = CAST(value AS INTEGER) + 1, updated_at = strftime('%s', 'now')
+    WHERE key = 'corpus_version';
+END;
+
+CREATE TRIGGER IF NOT EXISTS corpus_buffers_ad AFTER DELETE ON buffers BEGIN
+    UPDATE metadata SET value = CAST(value AS INTEGER) + 1, updated_at = strftime('%s', 'now')
+    WHERE key = 'corpus_version';
+END;
+
+CREATE TRIGGER IF NOT EXISTS corpus_chunks_ai AFTER INSERT ON chunks BEGIN
+    UPDATE metadata SET value = CAST(value AS INTEGER) + 1, updated_at = strftime('%s', 'now')
+    WHERE key = 'corpus_version';
+END;
+
+CREATE TRIGGER IF NOT EXISTS corpus_chunks_au AFTER UPDATE ON chunks BEGIN
+    UPDATE metadata SET value = CAST(value AS INTEGER) + 1, updated_at = strftime('%s', 'now')
+    WHERE key = 'corpus_version';
+END;
+
+CREATE TRIGGER IF NOT EXISTS corpus_chunks_ad AFTER DELETE ON chunks BEGIN
+    UPDATE metadata SET value = CAST(value AS INTEGER) + 1, updated_at = strftime('%s', 'now')
+    WHERE key = 'corpus_version';
+END;
+
+CREATE TRIGGER IF NOT EXISTS corpus_embeddings_ai AFTER INSERT ON chunk_embeddings BEGIN
+    UPDATE metadata SET value = CAST(value AS INTEGER) + 1, updated_at = strftime('%s', 'now')
+    WHERE key = 'corpus_version';
+END;
+
+CREATE TRIGGER IF NOT EXISTS corpus_embeddings_au AFTER UPDATE ON chunk_embeddings BEGIN
+    UPDATE metadata SET value = CAST(value AS INTEGER) + 1, updated_at = strftime('%s', 'now')
+    WHERE key = 'corpus_version';
+END;
+
+CREATE TRIGGER IF NOT EXISTS corpus_embeddings_ad AFTER DELETE ON chunk_embeddings BEGIN
+    UPDATE metadata SET value = CAST(value AS INTEGER) + 1, updated_at = strftime('%s', 'now')
+    WHERE key = 'corpus_version';
+END;
+";
+
+/// SQL for v8 to v9 migration (maintains `chunk_count` with triggers).
+///
+/// Counts written by older versions may have drifted, so every buffer with
+/// chunks or a stored count is reconciled first.
+const MIGRATION_V8_TO_V9: &str = concat!(
+    r"
+UPDATE buffers SET chunk_count = (
+    SELECT COUNT(*) FROM chunks WHERE chunks.buffer_id = buffers.id
+)
+WHERE chunk_count IS NOT NULL OR id IN (SELECT buffer_id FROM chunks);
+",
+    chunk_count_triggers_sql!()
+);
+
+/// SQL for v9 to v10 migration (adds the buffer trash).
+///
+/// Buffers with a `deleted_at` timestamp are in the trash; existing buffers
+/// are all live.
+const MIGRATION_V9_TO_V10: &str = r"
+ALTER TABLE buffers ADD COLUMN deleted_at INTEGER;
+
+CREATE INDEX IF NOT EXISTS idx_buffers_deleted ON buffers(deleted_at);
+";
+
+/// SQL for v10 to v11 migration (adds buffer version history).
+const MIGRATION_V10_TO_V11: &str = r"
+CREATE TABLE IF NOT EXISTS buffer_versions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    buffer_id INTEGER NOT NULL,
+    version INTEGER NOT NULL,
+    content TEXT NOT NULL,
+    content_hash TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,
+    UNIQUE (buffer_id, version),
+    FOREIGN KEY (buffer_id) REFERENCES buffers(id) ON DELETE CASCADE
+);
+";
+
+/// SQL for v11 to v12 migration (adds the IVF index tables).
+///
+/// The index starts empty; `chunk build-ivf` clusters existing embeddings.
+const MIGRATION_V11_TO_V12: &str = r"
+CREATE TABLE IF NOT EXISTS ivf_centroids (
+    id INTEGER PRIMARY KEY,
+    centroid BLOB NOT NULL,  -- f32 array serialized as bytes
+    dimensions INTEGER NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS ivf_assignments (
+    chunk_id INTEGER PRIMARY KEY,
+    centroid_id INTEGER NOT NULL,
+    FOREIGN KEY (chunk_id) REFERENCES chunk_embeddings(chunk_id) ON DELETE CASCADE,
+    FOREIGN KEY (centroid_id) REFERENCES ivf_centroids(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_ivf_assignments_centroid ON ivf_assignments(centroid_id);
+";
+
+/// SQL for v12 to v13 migration (records each buffer's line ending).
+///
+/// Existing buffers keep an unknown (`NULL`) line ending until reloaded.
+const MIGRATION_V12_TO_V13: &str = r"
+ALTER TABLE buffers ADD COLUMN line_ending TEXT;
+";
+
+/// SQL for v13 to v14 migration (flags embeddings stored at unit length).
+///
+/// Existing embeddings count as unnormalized until re-embedded.
+const MIGRATION_V13_TO_V14: &str = r"
+ALTER TABLE chunk_embeddings ADD COLUMN normalized INTEGER NOT NULL DEFAULT 0;
+";
+
+/// SQL for v14 to v15 migration (records whitespace normalization at load).
+const MIGRATION_V14_TO_V15: &str = r"
+ALTER TABLE buffers ADD COLUMN whitespace_normalized INTEGER NOT NULL DEFAULT 0;
+";
+
+/// SQL for v15 to v16 migration (repairs `chunks_au`, gates count triggers).
+///
+/// The old `chunks_au` re-inserted the row into `chunks_fts` with two values
+/// for three columns, so every `UPDATE chunks` failed. The count triggers
+/// gain a `WHEN` clause so bulk writes can defer them without DDL.
+const MIGRATION_V15_TO_V16: &str = concat!(
+    r"
+DROP TRIGGER IF EXISTS chunks_au;
+
+CREATE TRIGGER chunks_au AFTER UPDATE ON chunks BEGIN
+    INSERT INTO chunks_fts(chunks_fts, rowid, content) VALUES('delete', old.id, old.content);
+    INSERT INTO chunks_fts(rowid, content) VALUES (new.id, new.content);
+END;
+
+DROP TRIGGER IF EXISTS chunks_count_ai;
+DROP TRIGGER IF EXISTS chunks_count_ad;
+DROP TRIGGER IF EXISTS chunks_count_au;
+",
+    chunk_count_triggers_sql!()
+);
+
 /// Available migrations.
 pub const MIGRATIONS: &[Migration] = &[
     Migration {
@@ -194,6 +528,71 @@ pub const MIGRATIONS: &[Migration] = &[
         to_version: 3,
         sql: MIGRATION_V2_TO_V3,
     },
+    Migration {
+        from_version: 3,
+        to_version: 4,
+        sql: MIGRATION_V3_TO_V4,
+    },
+    Migration {
+        from_version: 4,
+        to_version: 5,
+        sql: MIGRATION_V4_TO_V5,
+    },
+    Migration {
+        from_version: 5,
+        to_version: 6,
+        sql: MIGRATION_V5_TO_V6,
+    },
+    Migration {
+        from_version: 6,
+        to_version: 7,
+        sql: MIGRATION_V6_TO_V7,
+    },
+    Migration {
+        from_version: 7,
+        to_version: 8,
+        sql: MIGRATION_V7_TO_V8,
+    },
+    Migration {
+        from_version: 8,
+        to_version: 9,
+        sql: MIGRATION_V8_TO_V9,
+    },
+    Migration {
+        from_version: 9,
+        to_version: 10,
+        sql: MIGRATION_V9_TO_V10,
+    },
+    Migration {
+        from_version: 10,
+        to_version: 11,
+        sql: MIGRATION_V10_TO_V11,
+    },
+    Migration {
+        from_version: 11,
+        to_version: 12,
+        sql: MIGRATION_V11_TO_V12,
+    },
+    Migration {
+        from_version: 12,
+        to_version: 13,
+        sql: MIGRATION_V12_TO_V13,
+    },
+    Migration {
+        from_version: 13,
+        to_version: 14,
+        sql: MIGRATION_V13_TO_V14,
+    },
+    Migration {
+        from_version: 14,
+        to_version: 15,
+        sql: MIGRATION_V14_TO_V15,
+    },
+    Migration {
+        from_version: 15,
+        to_version: 16,
+        sql: MIGRATION_V15_TO_V16,
+    },
 ];
 
 /// Gets migrations needed to upgrade from a version.
@@ -8,14 +8,32 @@
 #![allow(clippy::cast_possible_truncation)]
 #![allow(clippy::cast_sign_loss)]
 
-use crate::core::{Buffer, BufferMetadata, Chunk, ChunkMetadata, Context};
+use crate::core::{
+    Buffer, BufferDiff, BufferMetadata, Chunk, ChunkMetadata, Context, content_hash, diff_chunks,
+};
+use crate::corpus::CorpusRng;
+#[cfg(feature = "sqlite-vec")]
+use crate::embedding::DEFAULT_DIMENSIONS;
+use crate::embedding::{cosine_similarity, is_normalized, normalize_embedding};
 use crate::error::{Result, StorageError};
 use crate::storage::schema::{
-    CHECK_SCHEMA_SQL, CURRENT_SCHEMA_VERSION, GET_VERSION_SQL, SCHEMA_SQL, SET_VERSION_SQL,
+    CHECK_SCHEMA_SQL, CURRENT_SCHEMA_VERSION, DEFER_CHUNK_COUNT_SQL, GET_VERSION_SQL,
+    RESUME_CHUNK_COUNT_SQL, SCHEMA_SQL, SET_VERSION_SQL,
 };
-use crate::storage::traits::{Storage, StorageStats};
-use rusqlite::{Connection, OptionalExtension, params};
+use crate::storage::traits::{ChunkDirection, Storage, StorageStats};
+use rusqlite::{Connection, MAIN_DB, OptionalExtension, Transaction, params};
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+
+/// Buffer columns in the order read by [`SqliteStorage::buffer_from_row`].
+const BUFFER_COLUMNS: &str = "id, name, source_path, content, content_type, content_hash, \
+    size, line_count, chunk_count, created_at, updated_at, source_encoding, \
+    custom_metadata, deleted_at, line_ending, whitespace_normalized";
 
 /// SQLite-based storage implementation.
 ///
@@ -34,11 +52,147 @@ pub struct SqliteStorage {
     conn: Connection,
     /// Path to the database file (None for in-memory).
     path: Option<PathBuf>,
+    /// Required embedding dimensions; `None` accepts any size.
+    embedding_dimensions: Option<usize>,
+    /// Whether embeddings are scaled to unit length before being stored.
+    normalize_embeddings: bool,
+    /// Whether chunk writes update `buffers.chunk_count` immediately.
+    auto_chunk_count: bool,
+}
+
+/// `SQLite` journal mode used for file-backed databases.
+///
+/// WAL is the default. `Delete` avoids the `-wal`/`-shm` side files, which
+/// misbehave on some network filesystems; `Memory` keeps the rollback
+/// journal in memory (faster, but not crash-safe).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum JournalMode {
+    /// Classic rollback journal, deleted after each transaction.
+    Delete,
+    /// Write-ahead logging.
+    #[default]
+    Wal,
+    /// Rollback journal kept in memory.
+    Memory,
+}
+
+impl JournalMode {
+    /// Parses a journal mode name ("delete", "wal", "memory").
+    ///
+    /// Returns `None` for unrecognized names.
+    #[must_use]
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "delete" => Some(Self::Delete),
+            "wal" => Some(Self::Wal),
+            "memory" => Some(Self::Memory),
+            _ => None,
+        }
+    }
+
+    /// Returns the mode name as reported by `PRAGMA journal_mode`.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Delete => "delete",
+            Self::Wal => "wal",
+            Self::Memory => "memory",
+        }
+    }
+}
+
+/// Smallest page cache applied automatically (KiB; matches `SQLite`'s default).
+pub const MIN_AUTO_CACHE_KIB: u64 = 2 * 1024;
+
+/// Largest page cache applied automatically (KiB).
+pub const MAX_AUTO_CACHE_KIB: u64 = 64 * 1024;
+
+/// Largest memory-map size applied automatically (bytes).
+pub const MAX_AUTO_MMAP_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Connection options for [`SqliteStorage::open_with_options`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SqliteOptions {
+    /// Journal mode to apply when opening the database.
+    pub journal_mode: JournalMode,
+    /// Page cache size in KiB (`PRAGMA cache_size`); `None` scales with DB size.
+    pub cache_size_kib: Option<u64>,
+    /// Memory-map size in bytes (`PRAGMA mmap_size`); `None` scales with DB size.
+    pub mmap_size: Option<u64>,
+    /// Required embedding dimensions; stores of any other size are rejected.
+    pub embedding_dimensions: Option<usize>,
+    /// Scale embeddings to unit L2 length before storing them.
+    pub normalize_embeddings: bool,
+}
+
+impl SqliteOptions {
+    /// Creates options with default settings.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the journal mode.
+    #[must_use]
+    pub const fn with_journal_mode(mut self, mode: JournalMode) -> Self {
+        self.journal_mode = mode;
+        self
+    }
+
+    /// Sets an explicit page cache size in KiB.
+    #[must_use]
+    pub const fn with_cache_size_kib(mut self, kib: u64) -> Self {
+        self.cache_size_kib = Some(kib);
+        self
+    }
+
+    /// Sets an explicit memory-map size in bytes (0 disables mmap).
+    #[must_use]
+    pub const fn with_mmap_size(mut self, bytes: u64) -> Self {
+        self.mmap_size = Some(bytes);
+        self
+    }
+
+    /// Requires stored embeddings to have exactly `dimensions` values.
+    #[must_use]
+    pub const fn with_embedding_dimensions(mut self, dimensions: usize) -> Self {
+        self.embedding_dimensions = Some(dimensions);
+        self
+    }
+
+    /// Scales embeddings to unit length before storing them.
+    #[must_use]
+    pub const fn with_normalize_embeddings(mut self, normalize: bool) -> Self {
+        self.normalize_embeddings = normalize;
+        self
+    }
+
+    /// Returns the page cache size (KiB) to use for a database of `db_size` bytes.
+    ///
+    /// Defaults to a quarter of the database size, clamped to
+    /// [`MIN_AUTO_CACHE_KIB`]..=[`MAX_AUTO_CACHE_KIB`].
+    #[must_use]
+    pub fn effective_cache_size_kib(&self, db_size: u64) -> u64 {
+        self.cache_size_kib
+            .unwrap_or_else(|| (db_size / 4 / 1024).clamp(MIN_AUTO_CACHE_KIB, MAX_AUTO_CACHE_KIB))
+    }
+
+    /// Returns the memory-map size (bytes) to use for a database of `db_size` bytes.
+    ///
+    /// Defaults to the database size, capped at [`MAX_AUTO_MMAP_BYTES`], so
+    /// the whole file can be mapped for read-heavy workloads.
+    #[must_use]
+    pub fn effective_mmap_size(&self, db_size: u64) -> u64 {
+        self.mmap_size
+            .unwrap_or_else(|| db_size.min(MAX_AUTO_MMAP_BYTES))
+    }
 }
 
 impl SqliteStorage {
     /// Opens or creates a `SQLite` database at the given path.
     ///
+    /// Uses default [`SqliteOptions`] (WAL journal mode).
+    ///
     /// # Arguments
     ///
     /// * `path` - Path to the database file. Parent directory must exist.
@@ -47,7 +201,24 @@ impl SqliteStorage {
     ///
     /// Returns an error if the database cannot be opened or initialized.
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::open_with_options(path, &SqliteOptions::default())
+    }
+
+    /// Opens or creates a `SQLite` database with explicit connection options.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the database file.
+    /// * `options` - Connection options (journal mode, ...).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database cannot be opened, if `SQLite`
+    /// refuses the requested journal mode (e.g. WAL on read-only media), or
+    /// [`StorageError::SchemaTooNew`] if a newer rlm-rs created the schema.
+    pub fn open_with_options<P: AsRef<Path>>(path: P, options: &SqliteOptions) -> Result<Self> {
         let path = path.as_ref().to_path_buf();
+        let db_size = std::fs::metadata(&path).map_or(0, |m| m.len());
 
         // Ensure parent directory exists
         if let Some(parent) = path.parent()
@@ -56,21 +227,49 @@ impl SqliteStorage {
             std::fs::create_dir_all(parent).map_err(|e| StorageError::Database(e.to_string()))?;
         }
 
+        #[cfg(feature = "sqlite-vec")]
+        register_sqlite_vec();
         let conn = Connection::open(&path).map_err(StorageError::from)?;
 
         // Enable foreign keys
         conn.execute("PRAGMA foreign_keys = ON;", [])
             .map_err(StorageError::from)?;
 
-        // Use WAL mode for better concurrent access (returns result, use query_row)
-        let _: String = conn
-            .query_row("PRAGMA journal_mode = WAL;", [], |row| row.get(0))
+        // Set journal mode; the pragma returns the mode actually in effect
+        let requested = options.journal_mode.as_str();
+        let actual: String = conn
+            .query_row(&format!("PRAGMA journal_mode = {requested};"), [], |row| {
+                row.get(0)
+            })
+            .map_err(StorageError::from)?;
+        if !actual.eq_ignore_ascii_case(requested) {
+            return Err(StorageError::Database(format!(
+                "could not set journal mode to '{requested}' (SQLite kept '{actual}')"
+            ))
+            .into());
+        }
+
+        // Size the page cache and memory map for read-heavy workloads
+        let cache_kib = options.effective_cache_size_kib(db_size);
+        conn.execute_batch(&format!("PRAGMA cache_size = -{cache_kib};"))
+            .map_err(StorageError::from)?;
+        let mmap_size = options.effective_mmap_size(db_size);
+        let _: Option<i64> = conn
+            .query_row(&format!("PRAGMA mmap_size = {mmap_size};"), [], |row| {
+                row.get(0)
+            })
+            .optional()
             .map_err(StorageError::from)?;
 
-        Ok(Self {
+        let storage = Self {
             conn,
             path: Some(path),
-        })
+            embedding_dimensions: options.embedding_dimensions,
+            normalize_embeddings: options.normalize_embeddings,
+            auto_chunk_count: true,
+        };
+        storage.check_schema_compatibility()?;
+        Ok(storage)
     }
 
     /// Creates an in-memory `SQLite` database.
@@ -81,11 +280,19 @@ impl SqliteStorage {
     ///
     /// Returns an error if the database cannot be created.
     pub fn in_memory() -> Result<Self> {
+        #[cfg(feature = "sqlite-vec")]
+        register_sqlite_vec();
         let conn = Connection::open_in_memory().map_err(StorageError::from)?;
         conn.execute("PRAGMA foreign_keys = ON;", [])
             .map_err(StorageError::from)?;
 
-        Ok(Self { conn, path: None })
+        Ok(Self {
+            conn,
+            path: None,
+            embedding_dimensions: None,
+            normalize_embeddings: false,
+            auto_chunk_count: true,
+        })
     }
 
     /// Returns the database path (None for in-memory).
@@ -94,8 +301,107 @@ impl SqliteStorage {
         self.path.as_deref()
     }
 
-    /// Gets the current schema version.
-    fn get_schema_version(&self) -> Result<Option<u32>> {
+    /// Returns the embedding dimensions this connection requires, if any.
+    #[must_use]
+    pub const fn embedding_dimensions(&self) -> Option<usize> {
+        self.embedding_dimensions
+    }
+
+    /// Requires stored embeddings to have `dimensions` values (`None` accepts any).
+    pub const fn set_embedding_dimensions(&mut self, dimensions: Option<usize>) {
+        self.embedding_dimensions = dimensions;
+    }
+
+    /// Returns whether embeddings are scaled to unit length before storing.
+    #[must_use]
+    pub const fn normalize_embeddings(&self) -> bool {
+        self.normalize_embeddings
+    }
+
+    /// Scales embeddings to unit length before storing them (off by default).
+    ///
+    /// Each stored row records whether it has unit length either way, so
+    /// embedders that already normalize get the same benefit without this.
+    pub const fn set_normalize_embeddings(&mut self, normalize: bool) {
+        self.normalize_embeddings = normalize;
+    }
+
+    /// Enables or disables automatic `chunk_count` updates.
+    ///
+    /// By default schema triggers update the buffer's `chunk_count` once per
+    /// inserted or deleted chunk. With this off, `add_chunks` and
+    /// `delete_chunks` pause those triggers for the duration of their
+    /// transaction, so bulk loaders can skip the per-row writes and call
+    /// [`recompute_chunk_counts`](Self::recompute_chunk_counts) once at the
+    /// end; until then stored counts may be stale. Other write paths and
+    /// other connections keep the triggers.
+    pub const fn set_auto_chunk_count(&mut self, enabled: bool) {
+        self.auto_chunk_count = enabled;
+    }
+
+    /// Returns whether chunk writes update `chunk_count` automatically.
+    #[must_use]
+    pub const fn auto_chunk_count(&self) -> bool {
+        self.auto_chunk_count
+    }
+
+    /// Begins a chunk write transaction, pausing the `chunk_count` triggers
+    /// inside it when automatic counts are off.
+    ///
+    /// Pass the transaction to [`finish_chunk_write`](Self::finish_chunk_write)
+    /// to resume the triggers and commit.
+    fn begin_chunk_write(&mut self) -> Result<(Transaction<'_>, bool)> {
+        let deferred = !self.auto_chunk_count;
+        let tx = self.conn.transaction().map_err(StorageError::from)?;
+        if deferred {
+            tx.execute_batch(DEFER_CHUNK_COUNT_SQL)
+                .map_err(StorageError::from)?;
+        }
+        Ok((tx, deferred))
+    }
+
+    /// Resumes any triggers paused by `begin_chunk_write` and commits.
+    fn finish_chunk_write(tx: Transaction<'_>, deferred: bool) -> Result<()> {
+        if deferred {
+            tx.execute_batch(RESUME_CHUNK_COUNT_SQL)
+                .map_err(StorageError::from)?;
+        }
+        tx.commit().map_err(StorageError::from)?;
+        Ok(())
+    }
+
+    /// Sets every buffer's `chunk_count` to its number of stored chunks.
+    ///
+    /// Schema triggers keep counts current on every chunk insert and delete,
+    /// so this is only needed after writes with
+    /// [`set_auto_chunk_count(false)`](Self::set_auto_chunk_count) or edits
+    /// made with the triggers dropped or paused. Returns the number of buffers updated.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the update fails.
+    pub fn recompute_chunk_counts(&mut self) -> Result<usize> {
+        let updated = self
+            .conn
+            .execute(
+                r"
+            UPDATE buffers SET chunk_count = (
+                SELECT COUNT(*) FROM chunks WHERE chunks.buffer_id = buffers.id
+            )
+        ",
+                [],
+            )
+            .map_err(StorageError::from)?;
+        Ok(updated)
+    }
+
+    /// Returns the stored schema version, or `None` if the database has not
+    /// been initialized.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub fn schema_version(&self) -> Result<Option<u32>> {
         let version: Option<String> = self
             .conn
             .query_row(GET_VERSION_SQL, [], |row| row.get(0))
@@ -105,14 +411,87 @@ impl SqliteStorage {
         Ok(version.and_then(|v| v.parse().ok()))
     }
 
-    /// Sets the schema version.
-    fn set_schema_version(&self, version: u32) -> Result<()> {
-        self.conn
-            .execute(SET_VERSION_SQL, params![version.to_string()])
+    /// Fails if the stored schema is newer than [`CURRENT_SCHEMA_VERSION`].
+    ///
+    /// Older builds can't tell which tables or columns a newer schema
+    /// changed, so reading or writing such a database could corrupt it.
+    /// Uninitialized databases pass.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StorageError::SchemaTooNew`] for a newer schema, or an
+    /// error if the version can't be read.
+    pub fn check_schema_compatibility(&self) -> Result<()> {
+        if !self.is_initialized()? {
+            return Ok(());
+        }
+        match self.schema_version()? {
+            Some(found) if found > CURRENT_SCHEMA_VERSION => Err(StorageError::SchemaTooNew {
+                found,
+                supported: CURRENT_SCHEMA_VERSION,
+            }
+            .into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Sets the schema version on `conn` (or a transaction on it).
+    fn set_schema_version(conn: &Connection, version: u32) -> Result<()> {
+        conn.execute(SET_VERSION_SQL, params![version.to_string()])
             .map_err(StorageError::from)?;
         Ok(())
     }
 
+    /// Verifies that the linked `SQLite` supports FTS5.
+    ///
+    /// Creates and drops a throwaway FTS5 table in the temp schema so the
+    /// probe works on read-only and freshly created databases alike.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::Fts5Unavailable`] if FTS5 is missing.
+    pub fn probe_fts5(&self) -> Result<()> {
+        self.conn
+            .execute_batch(
+                "CREATE VIRTUAL TABLE IF NOT EXISTS temp.rlm_fts5_probe USING fts5(x);
+                 DROP TABLE temp.rlm_fts5_probe;",
+            )
+            .map_err(|e| crate::Error::Fts5Unavailable {
+                reason: e.to_string(),
+            })
+    }
+
+    /// Maps a row selected with the standard chunk column list to a `Chunk`.
+    ///
+    /// Expects columns: id, `buffer_id`, content, `byte_start`, `byte_end`,
+    /// `chunk_index`, strategy, `token_count`, `line_start`, `line_end`,
+    /// `has_overlap`, `content_hash`, `custom_metadata`, `created_at`.
+    fn chunk_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Chunk> {
+        let line_start: Option<i64> = row.get(8)?;
+        let line_end: Option<i64> = row.get(9)?;
+        let line_range = match (line_start, line_end) {
+            (Some(s), Some(e)) => Some((s as usize)..(e as usize)),
+            _ => None,
+        };
+
+        Ok(Chunk {
+            id: Some(row.get::<_, i64>(0)?),
+            buffer_id: row.get(1)?,
+            content: row.get(2)?,
+            byte_range: (row.get::<_, i64>(3)? as usize)..(row.get::<_, i64>(4)? as usize),
+            index: row.get::<_, i64>(5)? as usize,
+            metadata: ChunkMetadata {
+                strategy: row.get(6)?,
+                token_count: row.get::<_, Option<i64>>(7)?.map(|c| c as usize),
+                line_range,
+                has_overlap: row.get::<_, i64>(10)? != 0,
+                content_hash: row.get(11)?,
+                custom: row.get(12)?,
+                created_at: row.get(13)?,
+            },
+        })
+    }
+
     /// Returns current Unix timestamp.
     #[allow(clippy::cast_possible_wrap)]
     fn now() -> i64 {
@@ -121,35 +500,87 @@ impl SqliteStorage {
             .map(|d| d.as_secs() as i64)
             .unwrap_or(0)
     }
+
+    /// Maps a row selected with [`BUFFER_COLUMNS`] to a buffer.
+    fn buffer_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Buffer> {
+        Ok(Buffer {
+            id: Some(row.get::<_, i64>(0)?),
+            name: row.get(1)?,
+            source: row.get::<_, Option<String>>(2)?.map(PathBuf::from),
+            content: row.get(3)?,
+            metadata: BufferMetadata {
+                content_type: row.get(4)?,
+                content_hash: row.get(5)?,
+                size: row.get::<_, i64>(6)? as usize,
+                line_count: row.get::<_, Option<i64>>(7)?.map(|c| c as usize),
+                chunk_count: row.get::<_, Option<i64>>(8)?.map(|c| c as usize),
+                created_at: row.get(9)?,
+                updated_at: row.get(10)?,
+                source_encoding: row.get(11)?,
+                custom: row.get(12)?,
+                deleted_at: row.get(13)?,
+                line_ending: row.get(14)?,
+                whitespace_normalized: row.get(15)?,
+            },
+        })
+    }
+
+    /// Returns the buffers selected by `clause` (a `WHERE`/`ORDER BY` tail).
+    fn query_buffers(&self, clause: &str) -> Result<Vec<Buffer>> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!("SELECT {BUFFER_COLUMNS} FROM buffers {clause}"))
+            .map_err(StorageError::from)?;
+
+        let buffers = stmt
+            .query_map([], Self::buffer_from_row)
+            .map_err(StorageError::from)?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(StorageError::from)?;
+
+        Ok(buffers)
+    }
 }
 
 impl Storage for SqliteStorage {
     fn init(&mut self) -> Result<()> {
+        // Full-text search is part of the schema; fail early with a clear error
+        self.probe_fts5()?;
+
         // Check if already initialized
         let is_init: i64 = self
             .conn
             .query_row(CHECK_SCHEMA_SQL, [], |row| row.get(0))
             .map_err(StorageError::from)?;
 
+        if is_init > 0 {
+            // Never migrate (or write to) a schema from the future
+            self.check_schema_compatibility()?;
+        }
+
         if is_init == 0 {
             // Fresh install - create schema
-            self.conn
-                .execute_batch(SCHEMA_SQL)
-                .map_err(StorageError::from)?;
-            self.set_schema_version(CURRENT_SCHEMA_VERSION)?;
-        } else if let Some(current) = self.get_schema_version()?
+            let tx = self.conn.transaction().map_err(StorageError::from)?;
+            tx.execute_batch(SCHEMA_SQL).map_err(StorageError::from)?;
+            Self::set_schema_version(&tx, CURRENT_SCHEMA_VERSION)?;
+            tx.commit().map_err(StorageError::from)?;
+        } else if let Some(current) = self.schema_version()?
             && current < CURRENT_SCHEMA_VERSION
         {
-            // Run migrations
-            let migrations = crate::storage::schema::get_migrations_from(current);
-            for migration in migrations {
-                self.conn
-                    .execute_batch(migration.sql)
+            // Run migrations in one transaction: the ALTERs aren't
+            // idempotent, so a failed step must leave the old version intact
+            let tx = self.conn.transaction().map_err(StorageError::from)?;
+            for migration in crate::storage::schema::get_migrations_from(current) {
+                tx.execute_batch(migration.sql)
                     .map_err(|e| StorageError::Migration(e.to_string()))?;
             }
-            self.set_schema_version(CURRENT_SCHEMA_VERSION)?;
+            Self::set_schema_version(&tx, CURRENT_SCHEMA_VERSION)?;
+            tx.commit().map_err(StorageError::from)?;
         }
 
+        #[cfg(feature = "sqlite-vec")]
+        self.init_vec_index()?;
+
         Ok(())
     }
 
@@ -165,11 +596,15 @@ impl Storage for SqliteStorage {
         self.conn
             .execute_batch(
                 r"
+            DELETE FROM ivf_assignments;
+            DELETE FROM ivf_centroids;
             DELETE FROM chunk_embeddings;
             DELETE FROM chunks;
+            DELETE FROM buffer_versions;
             DELETE FROM buffers;
             DELETE FROM context;
-            DELETE FROM metadata;
+            DELETE FROM metadata WHERE key <> 'corpus_version';
+            DELETE FROM search_cache;
         ",
             )
             .map_err(StorageError::from)?;
@@ -231,8 +666,9 @@ impl Storage for SqliteStorage {
                 r"
             INSERT INTO buffers (
                 name, source_path, content, content_type, content_hash,
-                size, line_count, chunk_count, created_at, updated_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                size, line_count, source_encoding, custom_metadata,
+                line_ending, whitespace_normalized, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         ",
                 params![
                     buffer.name,
@@ -245,7 +681,10 @@ impl Storage for SqliteStorage {
                     buffer.metadata.content_hash,
                     buffer.metadata.size as i64,
                     buffer.metadata.line_count.map(|c| c as i64),
-                    buffer.metadata.chunk_count.map(|c| c as i64),
+                    buffer.metadata.source_encoding,
+                    buffer.metadata.custom,
+                    buffer.metadata.line_ending,
+                    buffer.metadata.whitespace_normalized,
                     now,
                     now,
                 ],
@@ -259,29 +698,9 @@ impl Storage for SqliteStorage {
         let result = self
             .conn
             .query_row(
-                r"
-            SELECT id, name, source_path, content, content_type, content_hash,
-                   size, line_count, chunk_count, created_at, updated_at
-            FROM buffers WHERE id = ?
-        ",
+                &format!("SELECT {BUFFER_COLUMNS} FROM buffers WHERE id = ?"),
                 params![id],
-                |row| {
-                    Ok(Buffer {
-                        id: Some(row.get::<_, i64>(0)?),
-                        name: row.get(1)?,
-                        source: row.get::<_, Option<String>>(2)?.map(PathBuf::from),
-                        content: row.get(3)?,
-                        metadata: BufferMetadata {
-                            content_type: row.get(4)?,
-                            content_hash: row.get(5)?,
-                            size: row.get::<_, i64>(6)? as usize,
-                            line_count: row.get::<_, Option<i64>>(7)?.map(|c| c as usize),
-                            chunk_count: row.get::<_, Option<i64>>(8)?.map(|c| c as usize),
-                            created_at: row.get(9)?,
-                            updated_at: row.get(10)?,
-                        },
-                    })
-                },
+                Self::buffer_from_row,
             )
             .optional()
             .map_err(StorageError::from)?;
@@ -290,10 +709,11 @@ impl Storage for SqliteStorage {
     }
 
     fn get_buffer_by_name(&self, name: &str) -> Result<Option<Buffer>> {
+        // A live buffer wins over a trashed one with the same name
         let id: Option<i64> = self
             .conn
             .query_row(
-                "SELECT id FROM buffers WHERE name = ?",
+                "SELECT id FROM buffers WHERE name = ? ORDER BY deleted_at IS NOT NULL, id LIMIT 1",
                 params![name],
                 |row| row.get(0),
             )
@@ -304,40 +724,7 @@ impl Storage for SqliteStorage {
     }
 
     fn list_buffers(&self) -> Result<Vec<Buffer>> {
-        let mut stmt = self
-            .conn
-            .prepare(
-                r"
-            SELECT id, name, source_path, content, content_type, content_hash,
-                   size, line_count, chunk_count, created_at, updated_at
-            FROM buffers ORDER BY id
-        ",
-            )
-            .map_err(StorageError::from)?;
-
-        let buffers = stmt
-            .query_map([], |row| {
-                Ok(Buffer {
-                    id: Some(row.get::<_, i64>(0)?),
-                    name: row.get(1)?,
-                    source: row.get::<_, Option<String>>(2)?.map(PathBuf::from),
-                    content: row.get(3)?,
-                    metadata: BufferMetadata {
-                        content_type: row.get(4)?,
-                        content_hash: row.get(5)?,
-                        size: row.get::<_, i64>(6)? as usize,
-                        line_count: row.get::<_, Option<i64>>(7)?.map(|c| c as usize),
-                        chunk_count: row.get::<_, Option<i64>>(8)?.map(|c| c as usize),
-                        created_at: row.get(9)?,
-                        updated_at: row.get(10)?,
-                    },
-                })
-            })
-            .map_err(StorageError::from)?
-            .collect::<std::result::Result<Vec<_>, _>>()
-            .map_err(StorageError::from)?;
-
-        Ok(buffers)
+        self.query_buffers("WHERE deleted_at IS NULL ORDER BY id")
     }
 
     #[allow(clippy::cast_possible_wrap)]
@@ -353,8 +740,9 @@ impl Storage for SqliteStorage {
                 r"
             UPDATE buffers SET
                 name = ?, source_path = ?, content = ?, content_type = ?,
-                content_hash = ?, size = ?, line_count = ?, chunk_count = ?,
-                updated_at = ?
+                content_hash = ?, size = ?, line_count = ?,
+                source_encoding = ?, custom_metadata = ?, line_ending = ?,
+                whitespace_normalized = ?, updated_at = ?
             WHERE id = ?
         ",
                 params![
@@ -368,7 +756,10 @@ impl Storage for SqliteStorage {
                     buffer.metadata.content_hash,
                     buffer.metadata.size as i64,
                     buffer.metadata.line_count.map(|c| c as i64),
-                    buffer.metadata.chunk_count.map(|c| c as i64),
+                    buffer.metadata.source_encoding,
+                    buffer.metadata.custom,
+                    buffer.metadata.line_ending,
+                    buffer.metadata.whitespace_normalized,
                     now,
                     id,
                 ],
@@ -389,7 +780,11 @@ impl Storage for SqliteStorage {
     fn buffer_count(&self) -> Result<usize> {
         let count: i64 = self
             .conn
-            .query_row("SELECT COUNT(*) FROM buffers", [], |row| row.get(0))
+            .query_row(
+                "SELECT COUNT(*) FROM buffers WHERE deleted_at IS NULL",
+                [],
+                |row| row.get(0),
+            )
             .map_err(StorageError::from)?;
         Ok(count as usize)
     }
@@ -398,8 +793,8 @@ impl Storage for SqliteStorage {
 
     #[allow(clippy::cast_possible_wrap)]
     fn add_chunks(&mut self, buffer_id: i64, chunks: &[Chunk]) -> Result<()> {
-        let tx = self.conn.transaction().map_err(StorageError::from)?;
         let now = Self::now();
+        let (tx, deferred) = self.begin_chunk_write()?;
 
         {
             let mut stmt = tx
@@ -423,6 +818,13 @@ impl Storage for SqliteStorage {
                     .as_ref()
                     .map_or((None, None), |r| (Some(r.start as i64), Some(r.end as i64)));
 
+                // Every stored chunk gets a hash so its content can be verified
+                let hash = chunk
+                    .metadata
+                    .content_hash
+                    .clone()
+                    .unwrap_or_else(|| content_hash(&chunk.content));
+
                 stmt.execute(params![
                     buffer_id,
                     chunk.content,
@@ -434,7 +836,7 @@ impl Storage for SqliteStorage {
                     line_start,
                     line_end,
                     i64::from(chunk.metadata.has_overlap),
-                    chunk.metadata.content_hash,
+                    hash,
                     custom_meta,
                     now,
                 ])
@@ -442,17 +844,7 @@ impl Storage for SqliteStorage {
             }
         }
 
-        tx.commit().map_err(StorageError::from)?;
-
-        // Update chunk count on buffer
-        self.conn
-            .execute(
-                "UPDATE buffers SET chunk_count = ? WHERE id = ?",
-                params![chunks.len() as i64, buffer_id],
-            )
-            .map_err(StorageError::from)?;
-
-        Ok(())
+        Self::finish_chunk_write(tx, deferred)
     }
 
     fn get_chunks(&self, buffer_id: i64) -> Result<Vec<Chunk>> {
@@ -469,31 +861,7 @@ impl Storage for SqliteStorage {
             .map_err(StorageError::from)?;
 
         let chunks = stmt
-            .query_map(params![buffer_id], |row| {
-                let line_start: Option<i64> = row.get(8)?;
-                let line_end: Option<i64> = row.get(9)?;
-                let line_range = match (line_start, line_end) {
-                    (Some(s), Some(e)) => Some((s as usize)..(e as usize)),
-                    _ => None,
-                };
-
-                Ok(Chunk {
-                    id: Some(row.get::<_, i64>(0)?),
-                    buffer_id: row.get(1)?,
-                    content: row.get(2)?,
-                    byte_range: (row.get::<_, i64>(3)? as usize)..(row.get::<_, i64>(4)? as usize),
-                    index: row.get::<_, i64>(5)? as usize,
-                    metadata: ChunkMetadata {
-                        strategy: row.get(6)?,
-                        token_count: row.get::<_, Option<i64>>(7)?.map(|c| c as usize),
-                        line_range,
-                        has_overlap: row.get::<_, i64>(10)? != 0,
-                        content_hash: row.get(11)?,
-                        custom: row.get(12)?,
-                        created_at: row.get(13)?,
-                    },
-                })
-            })
+            .query_map(params![buffer_id], Self::chunk_from_row)
             .map_err(StorageError::from)?
             .collect::<std::result::Result<Vec<_>, _>>()
             .map_err(StorageError::from)?;
@@ -512,32 +880,7 @@ impl Storage for SqliteStorage {
             FROM chunks WHERE id = ?
         ",
                 params![id],
-                |row| {
-                    let line_start: Option<i64> = row.get(8)?;
-                    let line_end: Option<i64> = row.get(9)?;
-                    let line_range = match (line_start, line_end) {
-                        (Some(s), Some(e)) => Some((s as usize)..(e as usize)),
-                        _ => None,
-                    };
-
-                    Ok(Chunk {
-                        id: Some(row.get::<_, i64>(0)?),
-                        buffer_id: row.get(1)?,
-                        content: row.get(2)?,
-                        byte_range: (row.get::<_, i64>(3)? as usize)
-                            ..(row.get::<_, i64>(4)? as usize),
-                        index: row.get::<_, i64>(5)? as usize,
-                        metadata: ChunkMetadata {
-                            strategy: row.get(6)?,
-                            token_count: row.get::<_, Option<i64>>(7)?.map(|c| c as usize),
-                            line_range,
-                            has_overlap: row.get::<_, i64>(10)? != 0,
-                            content_hash: row.get(11)?,
-                            custom: row.get(12)?,
-                            created_at: row.get(13)?,
-                        },
-                    })
-                },
+                Self::chunk_from_row,
             )
             .optional()
             .map_err(StorageError::from)?;
@@ -545,20 +888,38 @@ impl Storage for SqliteStorage {
         Ok(result)
     }
 
-    fn delete_chunks(&mut self, buffer_id: i64) -> Result<()> {
-        self.conn
-            .execute("DELETE FROM chunks WHERE buffer_id = ?", params![buffer_id])
-            .map_err(StorageError::from)?;
+    fn get_adjacent_chunk(&self, id: i64, direction: ChunkDirection) -> Result<Option<Chunk>> {
+        let offset: i64 = match direction {
+            ChunkDirection::Next => 1,
+            ChunkDirection::Prev => -1,
+        };
 
-        // Update chunk count on buffer
-        self.conn
-            .execute(
-                "UPDATE buffers SET chunk_count = 0 WHERE id = ?",
-                params![buffer_id],
+        let result = self
+            .conn
+            .query_row(
+                r"
+            SELECT c.id, c.buffer_id, c.content, c.byte_start, c.byte_end, c.chunk_index,
+                   c.strategy, c.token_count, c.line_start, c.line_end, c.has_overlap,
+                   c.content_hash, c.custom_metadata, c.created_at
+            FROM chunks c
+            JOIN chunks origin ON origin.id = ?
+            WHERE c.buffer_id = origin.buffer_id
+              AND c.chunk_index = origin.chunk_index + ?
+        ",
+                params![id, offset],
+                Self::chunk_from_row,
             )
+            .optional()
             .map_err(StorageError::from)?;
 
-        Ok(())
+        Ok(result)
+    }
+
+    fn delete_chunks(&mut self, buffer_id: i64) -> Result<()> {
+        let (tx, deferred) = self.begin_chunk_write()?;
+        tx.execute("DELETE FROM chunks WHERE buffer_id = ?", params![buffer_id])
+            .map_err(StorageError::from)?;
+        Self::finish_chunk_write(tx, deferred)
     }
 
     fn chunk_count(&self, buffer_id: i64) -> Result<usize> {
@@ -594,19 +955,39 @@ impl Storage for SqliteStorage {
 
         let chunk_count: i64 = self
             .conn
-            .query_row("SELECT COUNT(*) FROM chunks", [], |row| row.get(0))
+            .query_row(
+                r"
+                SELECT COUNT(*) FROM chunks c
+                JOIN buffers b ON b.id = c.buffer_id
+                WHERE b.deleted_at IS NULL
+            ",
+                [],
+                |row| row.get(0),
+            )
             .map_err(StorageError::from)?;
 
         let total_size: i64 = self
             .conn
-            .query_row("SELECT COALESCE(SUM(size), 0) FROM buffers", [], |row| {
-                row.get(0)
-            })
+            .query_row(
+                "SELECT COALESCE(SUM(size), 0) FROM buffers WHERE deleted_at IS NULL",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(StorageError::from)?;
+
+        let trashed_buffer_count: i64 = self
+            .conn
+            .query_row(
+                "SELECT COUNT(*) FROM buffers WHERE deleted_at IS NOT NULL",
+                [],
+                |row| row.get(0),
+            )
             .map_err(StorageError::from)?;
 
         let has_context = self.load_context()?.is_some();
 
-        let schema_version = self.get_schema_version()?.unwrap_or(0);
+        let schema_version = self.schema_version()?.unwrap_or(0);
+        let corpus_version = self.corpus_version().unwrap_or(0);
 
         let db_size = self
             .path
@@ -619,9 +1000,33 @@ impl Storage for SqliteStorage {
             total_content_size: total_size as usize,
             has_context,
             schema_version,
+            corpus_version,
+            trashed_buffer_count: trashed_buffer_count as usize,
             db_size,
         })
     }
+
+    fn compact(&mut self) -> Result<()> {
+        // VACUUM fails inside a transaction; report it plainly instead
+        if !self.conn.is_autocommit() {
+            return Err(StorageError::Transaction(
+                "cannot compact inside an open transaction".to_string(),
+            )
+            .into());
+        }
+
+        self.conn
+            .execute_batch("VACUUM;")
+            .map_err(StorageError::from)?;
+
+        // In WAL mode the rebuilt pages sit in the log until checkpointed;
+        // elsewhere this is a no-op
+        self.conn
+            .query_row("PRAGMA wal_checkpoint(TRUNCATE);", [], |_| Ok(()))
+            .map_err(StorageError::from)?;
+
+        Ok(())
+    }
 }
 
 // ==================== Embedding & Search Operations ====================
@@ -635,9 +1040,13 @@ impl SqliteStorage {
     /// * `embedding` - The embedding vector (f32 array).
     /// * `model_name` - Optional name of the model that generated the embedding.
     ///
+    /// The embedding is scaled to unit length first if
+    /// [`normalize_embeddings`](Self::normalize_embeddings) is set.
+    ///
     /// # Errors
     ///
-    /// Returns an error if the embedding cannot be stored.
+    /// Returns an error if the embedding cannot be stored, or if its length
+    /// differs from the required [`embedding_dimensions`](Self::embedding_dimensions).
     #[allow(clippy::cast_possible_wrap)]
     pub fn store_embedding(
         &mut self,
@@ -645,6 +1054,8 @@ impl SqliteStorage {
         embedding: &[f32],
         model_name: Option<&str>,
     ) -> Result<()> {
+        self.check_embedding_dimensions(chunk_id, embedding)?;
+        let embedding = self.prepare_embedding(embedding);
         let now = Self::now();
 
         // Serialize f32 array to bytes (little-endian)
@@ -653,16 +1064,66 @@ impl SqliteStorage {
         self.conn
             .execute(
                 r"
-                INSERT OR REPLACE INTO chunk_embeddings (chunk_id, embedding, dimensions, model_name, created_at)
-                VALUES (?, ?, ?, ?, ?)
+                INSERT OR REPLACE INTO chunk_embeddings
+                    (chunk_id, embedding, dimensions, model_name, created_at, normalized)
+                VALUES (?, ?, ?, ?, ?, ?)
             ",
-                params![chunk_id, bytes, embedding.len() as i64, model_name, now],
-            )
-            .map_err(StorageError::from)?;
-
+                params![
+                    chunk_id,
+                    bytes,
+                    embedding.len() as i64,
+                    model_name,
+                    now,
+                    is_normalized(&embedding)
+                ],
+            )
+            .map_err(StorageError::from)?;
+        Self::assign_ivf_clusters(&self.conn, &[(chunk_id, &*embedding)])?;
+
         Ok(())
     }
 
+    /// Scales an embedding to unit length if normalization is enabled.
+    fn prepare_embedding<'a>(&self, embedding: &'a [f32]) -> Cow<'a, [f32]> {
+        if self.normalize_embeddings {
+            Cow::Owned(normalize_embedding(embedding))
+        } else {
+            Cow::Borrowed(embedding)
+        }
+    }
+
+    /// Returns `true` if every stored embedding has unit length.
+    ///
+    /// Cosine similarity then reduces to a dot product. Embeddings stored
+    /// before schema v14 count as unnormalized until re-embedded.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub fn embeddings_normalized(&self) -> Result<bool> {
+        let normalized = self
+            .conn
+            .query_row(
+                "SELECT NOT EXISTS (SELECT 1 FROM chunk_embeddings WHERE normalized = 0)",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(StorageError::from)?;
+        Ok(normalized)
+    }
+
+    /// Rejects an embedding whose length differs from the required dimensions.
+    fn check_embedding_dimensions(&self, chunk_id: i64, embedding: &[f32]) -> Result<()> {
+        match self.embedding_dimensions {
+            Some(expected) if embedding.len() != expected => Err(StorageError::Embedding(format!(
+                "embedding for chunk {chunk_id} has {} dimensions, expected {expected}",
+                embedding.len()
+            ))
+            .into()),
+            _ => Ok(()),
+        }
+    }
+
     /// Retrieves the embedding for a chunk.
     ///
     /// # Errors
@@ -751,15 +1212,26 @@ impl SqliteStorage {
 
     /// Stores embeddings for multiple chunks in a batch.
     ///
+    /// Embeddings are normalized like in [`store_embedding`](Self::store_embedding).
+    ///
     /// # Errors
     ///
-    /// Returns an error if any embedding cannot be stored.
+    /// Returns an error if any embedding cannot be stored or has the wrong
+    /// dimensions; nothing is stored in that case.
     #[allow(clippy::cast_possible_wrap)]
     pub fn store_embeddings_batch(
         &mut self,
         embeddings: &[(i64, Vec<f32>)],
         model_name: Option<&str>,
     ) -> Result<()> {
+        for (chunk_id, embedding) in embeddings {
+            self.check_embedding_dimensions(*chunk_id, embedding)?;
+        }
+        let stored: Vec<(i64, Cow<'_, [f32]>)> = embeddings
+            .iter()
+            .map(|(chunk_id, embedding)| (*chunk_id, self.prepare_embedding(embedding)))
+            .collect();
+
         let tx = self.conn.transaction().map_err(StorageError::from)?;
         let now = Self::now();
 
@@ -767,13 +1239,14 @@ impl SqliteStorage {
             let mut stmt = tx
                 .prepare(
                     r"
-                    INSERT OR REPLACE INTO chunk_embeddings (chunk_id, embedding, dimensions, model_name, created_at)
-                    VALUES (?, ?, ?, ?, ?)
+                    INSERT OR REPLACE INTO chunk_embeddings
+                        (chunk_id, embedding, dimensions, model_name, created_at, normalized)
+                    VALUES (?, ?, ?, ?, ?, ?)
                 ",
                 )
                 .map_err(StorageError::from)?;
 
-            for (chunk_id, embedding) in embeddings {
+            for (chunk_id, embedding) in &stored {
                 let bytes: Vec<u8> = embedding.iter().flat_map(|f| f.to_le_bytes()).collect();
 
                 stmt.execute(params![
@@ -781,11 +1254,17 @@ impl SqliteStorage {
                     bytes,
                     embedding.len() as i64,
                     model_name,
-                    now
+                    now,
+                    is_normalized(embedding)
                 ])
                 .map_err(StorageError::from)?;
             }
         }
+        let stored: Vec<(i64, &[f32])> = stored
+            .iter()
+            .map(|(chunk_id, embedding)| (*chunk_id, embedding.as_ref()))
+            .collect();
+        Self::assign_ivf_clusters(&tx, &stored)?;
 
         tx.commit().map_err(StorageError::from)?;
         Ok(())
@@ -806,77 +1285,365 @@ impl SqliteStorage {
         Ok(())
     }
 
+    /// Gets an embedding generated by `model_name` for any chunk whose
+    /// content hash is `content_hash`.
+    ///
+    /// Chunks with identical content embed identically, so the result can
+    /// stand in for embedding a new chunk with the same hash.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub fn get_embedding_by_content_hash(
+        &self,
+        content_hash: &str,
+        model_name: &str,
+    ) -> Result<Option<Vec<f32>>> {
+        let result: Option<Vec<u8>> = self
+            .conn
+            .query_row(
+                r"
+                SELECT e.embedding FROM chunk_embeddings e
+                JOIN chunks c ON c.id = e.chunk_id
+                WHERE c.content_hash = ? AND e.model_name = ?
+                LIMIT 1
+            ",
+                params![content_hash, model_name],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(StorageError::from)?;
+
+        Ok(result.map(|bytes| {
+            bytes
+                .chunks_exact(4)
+                .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                .collect()
+        }))
+    }
+
+    /// Copies the embeddings of `source_chunk_ids` to chunks of `buffer_id`
+    /// with the same content hash that have no embedding yet.
+    ///
+    /// Model names are kept, so the copies count as embedded by the same
+    /// model. Used to carry embeddings of unchanged text across a re-chunk
+    /// without running an embedder.
+    ///
+    /// Returns the number of embeddings copied.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a query fails or the embeddings cannot be stored.
+    pub fn copy_embeddings_by_content_hash(
+        &mut self,
+        source_chunk_ids: &[i64],
+        buffer_id: i64,
+    ) -> Result<usize> {
+        let mut sources: HashMap<String, (Vec<f32>, Option<String>)> = HashMap::new();
+        {
+            let mut stmt = self
+                .conn
+                .prepare(
+                    r"
+                    SELECT c.content_hash, e.embedding, e.model_name FROM chunks c
+                    JOIN chunk_embeddings e ON e.chunk_id = c.id
+                    WHERE c.id = ? AND c.content_hash IS NOT NULL
+                ",
+                )
+                .map_err(StorageError::from)?;
+            for &id in source_chunk_ids {
+                let row: Option<(String, Vec<u8>, Option<String>)> = stmt
+                    .query_row(params![id], |row| {
+                        Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+                    })
+                    .optional()
+                    .map_err(StorageError::from)?;
+                if let Some((hash, bytes, model)) = row {
+                    let embedding = bytes
+                        .chunks_exact(4)
+                        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                        .collect();
+                    sources.entry(hash).or_insert((embedding, model));
+                }
+            }
+        }
+        if sources.is_empty() {
+            return Ok(0);
+        }
+
+        let targets: Vec<(i64, String)> = {
+            let mut stmt = self
+                .conn
+                .prepare(
+                    r"
+                    SELECT c.id, c.content_hash FROM chunks c
+                    LEFT JOIN chunk_embeddings e ON e.chunk_id = c.id
+                    WHERE c.buffer_id = ? AND c.content_hash IS NOT NULL
+                        AND e.chunk_id IS NULL
+                ",
+                )
+                .map_err(StorageError::from)?;
+            stmt.query_map(params![buffer_id], |row| Ok((row.get(0)?, row.get(1)?)))
+                .map_err(StorageError::from)?
+                .collect::<std::result::Result<_, _>>()
+                .map_err(StorageError::from)?
+        };
+
+        // Stored per model, since a batch carries one model name
+        let mut by_model: HashMap<Option<String>, Vec<(i64, Vec<f32>)>> = HashMap::new();
+        for (chunk_id, hash) in targets {
+            if let Some((embedding, model)) = sources.get(&hash) {
+                by_model
+                    .entry(model.clone())
+                    .or_default()
+                    .push((chunk_id, embedding.clone()));
+            }
+        }
+
+        let mut copied = 0;
+        for (model, embeddings) in by_model {
+            self.store_embeddings_batch(&embeddings, model.as_deref())?;
+            copied += embeddings.len();
+        }
+        Ok(copied)
+    }
+
+    /// Deletes the given chunks (and their embeddings).
+    ///
+    /// Returns the number of chunks deleted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if deletion fails.
+    pub fn delete_chunks_by_id(&mut self, chunk_ids: &[i64]) -> Result<usize> {
+        let tx = self.conn.transaction().map_err(StorageError::from)?;
+        let mut deleted = 0;
+
+        {
+            let mut stmt = tx
+                .prepare("DELETE FROM chunks WHERE id = ?")
+                .map_err(StorageError::from)?;
+            for chunk_id in chunk_ids {
+                deleted += stmt
+                    .execute(params![chunk_id])
+                    .map_err(StorageError::from)?;
+            }
+        }
+
+        tx.commit().map_err(StorageError::from)?;
+        Ok(deleted)
+    }
+
     /// Performs FTS5 BM25 full-text search.
     ///
     /// Returns chunk IDs and their BM25 scores (lower is better match).
     ///
     /// # Arguments
     ///
-    /// * `query` - The search query (supports FTS5 query syntax).
+    /// * `query` - The search query.
     /// * `limit` - Maximum number of results to return.
+    /// * `raw` - Pass `query` to FTS5 `MATCH` untouched, so phrase
+    ///   (`"exact phrase"`), boolean (`a AND b`), and `NEAR()` syntax apply.
+    ///   Otherwise each whitespace-separated term is matched literally and
+    ///   any term may match.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StorageError::InvalidQuery`] if a raw query is not valid
+    /// FTS5 syntax, or another error if the search fails.
+    pub fn search_fts(&self, query: &str, limit: usize, raw: bool) -> Result<Vec<(i64, f64)>> {
+        self.search_fts_filtered(query, limit, None, raw)
+    }
+
+    /// Performs full-text search restricted to buffers of a content type.
+    ///
+    /// Like [`search_fts`](Self::search_fts), but when `content_type` is
+    /// given only chunks whose buffer has that `content_type` are matched.
+    /// The filter is applied in SQL, so `limit` counts matching chunks only.
     ///
     /// # Errors
     ///
     /// Returns an error if the search fails.
-    #[allow(clippy::cast_possible_wrap)]
-    pub fn search_fts(&self, query: &str, limit: usize) -> Result<Vec<(i64, f64)>> {
+    pub fn search_fts_filtered(
+        &self,
+        query: &str,
+        limit: usize,
+        content_type: Option<&str>,
+        raw: bool,
+    ) -> Result<Vec<(i64, f64)>> {
         // FTS5 bm25() returns negative scores, more negative = better match
         // We negate it so higher scores = better match
 
-        // Convert space-separated terms to OR query for more forgiving search
-        // Each term is quoted to escape FTS5 special characters (?, *, ^, etc.)
-        // "CLI tool?" becomes '"CLI" OR "tool?"' so special chars are treated as literals
-        let fts_query = query
-            .split_whitespace()
-            .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
-            .collect::<Vec<_>>()
-            .join(" OR ");
+        let fts_query = Self::fts_match_query(query, raw);
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+        let query_error = |e| Self::fts_query_error(query, raw, e);
 
+        // Trashed buffers never match
         let mut stmt = self
             .conn
             .prepare(
                 r"
-                SELECT rowid, -bm25(chunks_fts) as score
+                SELECT chunks_fts.rowid, -bm25(chunks_fts) as score
                 FROM chunks_fts
-                WHERE chunks_fts MATCH ?
+                JOIN chunks c ON c.id = chunks_fts.rowid
+                JOIN buffers b ON b.id = c.buffer_id
+                WHERE chunks_fts MATCH ?1 AND b.deleted_at IS NULL
+                  AND (?3 IS NULL OR b.content_type = ?3)
                 ORDER BY score DESC
-                LIMIT ?
+                LIMIT ?2
             ",
             )
             .map_err(StorageError::from)?;
 
         let results = stmt
-            .query_map(params![fts_query, limit as i64], |row| {
+            .query_map(params![fts_query, limit, content_type], |row| {
                 Ok((row.get::<_, i64>(0)?, row.get::<_, f64>(1)?))
             })
-            .map_err(StorageError::from)?
+            .map_err(query_error)?
             .collect::<std::result::Result<Vec<_>, _>>()
-            .map_err(StorageError::from)?;
+            .map_err(query_error)?;
 
         Ok(results)
     }
 
+    /// Performs [`search_fts_filtered`](Self::search_fts_filtered), giving
+    /// up after `timeout`.
+    ///
+    /// The running query is interrupted once `timeout` elapses; `None` is
+    /// returned if that stopped it before it finished.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the search fails for any other reason.
+    pub fn search_fts_with_timeout(
+        &self,
+        query: &str,
+        limit: usize,
+        content_type: Option<&str>,
+        raw: bool,
+        timeout: Duration,
+    ) -> Result<Option<Vec<(i64, f64)>>> {
+        let handle = self.conn.get_interrupt_handle();
+        let interrupted = AtomicBool::new(false);
+        let (done, finished) = mpsc::channel::<()>();
+
+        let result = std::thread::scope(|scope| {
+            let interrupted = &interrupted;
+            scope.spawn(move || {
+                // Dropping `done` disconnects the channel when the query ends
+                if finished.recv_timeout(timeout) == Err(RecvTimeoutError::Timeout) {
+                    interrupted.store(true, Ordering::Relaxed);
+                    handle.interrupt();
+                }
+            });
+            let result = self.search_fts_filtered(query, limit, content_type, raw);
+            drop(done);
+            result
+        });
+
+        match result {
+            Err(_) if interrupted.load(Ordering::Relaxed) => Ok(None),
+            result => result.map(Some),
+        }
+    }
+
+    /// Returns an excerpt of a chunk around its matches for an FTS query,
+    /// with each matched term wrapped in `[` and `]`.
+    ///
+    /// Uses the FTS5 `snippet()` function, so `query` is interpreted exactly
+    /// as by [`search_fts`](Self::search_fts) with the same `raw` flag.
+    /// Returns `None` if the chunk does not match the query.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk_id` - The chunk to excerpt.
+    /// * `query` - The search query.
+    /// * `raw` - Whether `query` is raw FTS5 syntax.
+    /// * `max_tokens` - Maximum excerpt length in tokens (1-64).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StorageError::InvalidQuery`] if a raw query is not valid
+    /// FTS5 syntax, or another error if the query fails.
+    pub fn fts_snippet(
+        &self,
+        chunk_id: i64,
+        query: &str,
+        raw: bool,
+        max_tokens: u8,
+    ) -> Result<Option<String>> {
+        self.conn
+            .query_row(
+                r"
+            SELECT snippet(chunks_fts, 0, '[', ']', '...', ?3)
+            FROM chunks_fts
+            WHERE chunks_fts MATCH ?1 AND rowid = ?2
+        ",
+                params![
+                    Self::fts_match_query(query, raw),
+                    chunk_id,
+                    max_tokens.clamp(1, 64)
+                ],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| Self::fts_query_error(query, raw, e))
+    }
+
+    /// Builds the FTS5 `MATCH` expression for a search query.
+    fn fts_match_query(query: &str, raw: bool) -> String {
+        if raw {
+            return query.to_string();
+        }
+        // Convert space-separated terms to OR query for more forgiving search
+        // Each term is quoted to escape FTS5 special characters (?, *, ^, etc.)
+        // "CLI tool?" becomes '"CLI" OR "tool?"' so special chars are treated as literals
+        query
+            .split_whitespace()
+            .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+            .collect::<Vec<_>>()
+            .join(" OR ")
+    }
+
+    /// Maps an FTS query failure to an error.
+    ///
+    /// Only raw queries can be malformed; report why instead of a bare
+    /// database error.
+    fn fts_query_error(query: &str, raw: bool, e: rusqlite::Error) -> crate::error::Error {
+        if raw {
+            StorageError::InvalidQuery {
+                query: query.to_string(),
+                reason: e.to_string(),
+            }
+            .into()
+        } else {
+            StorageError::from(e).into()
+        }
+    }
+
     /// Returns all chunk embeddings for vector similarity search.
     ///
+    /// Chunks of trashed buffers are left out.
+    ///
     /// # Errors
     ///
     /// Returns an error if the query fails.
     pub fn get_all_embeddings(&self) -> Result<Vec<(i64, Vec<f32>)>> {
         let mut stmt = self
             .conn
-            .prepare("SELECT chunk_id, embedding FROM chunk_embeddings")
+            .prepare(
+                r"
+                SELECT e.chunk_id, e.embedding
+                FROM chunk_embeddings e
+                JOIN chunks c ON c.id = e.chunk_id
+                JOIN buffers b ON b.id = c.buffer_id
+                WHERE b.deleted_at IS NULL
+            ",
+            )
             .map_err(StorageError::from)?;
 
         let results = stmt
-            .query_map([], |row| {
-                let chunk_id: i64 = row.get(0)?;
-                let bytes: Vec<u8> = row.get(1)?;
-                let embedding: Vec<f32> = bytes
-                    .chunks_exact(4)
-                    .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
-                    .collect();
-                Ok((chunk_id, embedding))
-            })
+            .query_map([], Self::embedding_from_row)
             .map_err(StorageError::from)?
             .collect::<std::result::Result<Vec<_>, _>>()
             .map_err(StorageError::from)?;
@@ -884,239 +1651,3542 @@ impl SqliteStorage {
         Ok(results)
     }
 
-    /// Counts chunks with embeddings.
+    /// Returns embeddings for chunks whose buffer has the given content type.
+    ///
+    /// Chunks of trashed buffers are left out.
     ///
     /// # Errors
     ///
-    /// Returns an error if the count fails.
-    pub fn embedding_count(&self) -> Result<usize> {
-        let count: i64 = self
+    /// Returns an error if the query fails.
+    pub fn get_embeddings_by_content_type(
+        &self,
+        content_type: &str,
+    ) -> Result<Vec<(i64, Vec<f32>)>> {
+        let mut stmt = self
             .conn
-            .query_row("SELECT COUNT(*) FROM chunk_embeddings", [], |row| {
-                row.get(0)
-            })
+            .prepare(
+                r"
+                SELECT e.chunk_id, e.embedding
+                FROM chunk_embeddings e
+                JOIN chunks c ON c.id = e.chunk_id
+                JOIN buffers b ON b.id = c.buffer_id
+                WHERE b.content_type = ? AND b.deleted_at IS NULL
+            ",
+            )
             .map_err(StorageError::from)?;
-        Ok(count as usize)
+
+        let results = stmt
+            .query_map(params![content_type], Self::embedding_from_row)
+            .map_err(StorageError::from)?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(StorageError::from)?;
+
+        Ok(results)
     }
 
-    /// Checks if a chunk has an embedding.
+    /// Maps a `(chunk_id, embedding)` row, decoding the little-endian f32 blob.
+    fn embedding_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<(i64, Vec<f32>)> {
+        let chunk_id: i64 = row.get(0)?;
+        let bytes: Vec<u8> = row.get(1)?;
+        let embedding: Vec<f32> = bytes
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect();
+        Ok((chunk_id, embedding))
+    }
+
+    /// Returns the IDs of all buffers, in ascending order.
     ///
     /// # Errors
     ///
     /// Returns an error if the query fails.
-    pub fn has_embedding(&self, chunk_id: i64) -> Result<bool> {
-        let count: i64 = self
+    pub fn buffer_ids(&self) -> Result<Vec<i64>> {
+        let mut stmt = self
             .conn
-            .query_row(
-                "SELECT COUNT(*) FROM chunk_embeddings WHERE chunk_id = ?",
-                params![chunk_id],
-                |row| row.get(0),
-            )
+            .prepare("SELECT id FROM buffers ORDER BY id")
             .map_err(StorageError::from)?;
-        Ok(count > 0)
+
+        let results = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(StorageError::from)?
+            .collect::<std::result::Result<Vec<i64>, _>>()
+            .map_err(StorageError::from)?;
+
+        Ok(results)
     }
 
-    /// Gets chunk IDs that need embedding (either no embedding or wrong model).
+    /// Lists every buffer, including those in the trash.
     ///
-    /// This is used for incremental embedding updates. Returns chunks that:
-    /// - Have no embedding at all, OR
-    /// - Have an embedding with a different model name (if `current_model` is provided)
+    /// [`list_buffers`](Storage::list_buffers) omits trashed buffers.
     ///
-    /// # Arguments
+    /// # Errors
     ///
-    /// * `buffer_id` - The buffer to check.
-    /// * `current_model` - Optional model name to check against. If provided,
-    ///   chunks with different models are included.
+    /// Returns an error if the query fails.
+    pub fn list_buffers_including_deleted(&self) -> Result<Vec<Buffer>> {
+        self.query_buffers("ORDER BY id")
+    }
+
+    /// Lists the buffers in the trash, most recently deleted first.
     ///
     /// # Errors
     ///
     /// Returns an error if the query fails.
-    pub fn get_chunks_needing_embedding(
-        &self,
-        buffer_id: i64,
-        current_model: Option<&str>,
-    ) -> Result<Vec<i64>> {
-        let mut results = Vec::new();
+    pub fn list_trash(&self) -> Result<Vec<Buffer>> {
+        self.query_buffers("WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC, id DESC")
+    }
 
-        // Get chunks without any embedding
-        let mut stmt = self
+    /// Moves a buffer to the trash, keeping its chunks and embeddings.
+    ///
+    /// Trashed buffers are left out of listings, status counts, and search
+    /// until [restored](Self::restore_buffer) or [purged](Self::purge_trash).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StorageError::BufferNotFound`] if no live buffer has
+    /// `buffer_id`, or an error if the update fails.
+    pub fn trash_buffer(&mut self, buffer_id: i64) -> Result<()> {
+        let updated = self
             .conn
-            .prepare(
-                r"
-                SELECT c.id FROM chunks c
-                LEFT JOIN chunk_embeddings e ON c.id = e.chunk_id
-                WHERE c.buffer_id = ? AND e.chunk_id IS NULL
-                ",
+            .execute(
+                "UPDATE buffers SET deleted_at = ?1, updated_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+                params![Self::now(), buffer_id],
             )
             .map_err(StorageError::from)?;
+        if updated == 0 {
+            return Err(StorageError::BufferNotFound {
+                identifier: buffer_id.to_string(),
+            }
+            .into());
+        }
+        Ok(())
+    }
 
-        let rows = stmt
-            .query_map(params![buffer_id], |row| row.get(0))
+    /// Restores a buffer from the trash.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StorageError::BufferNotFound`] if no trashed buffer has
+    /// `buffer_id`, or an error if the update fails.
+    pub fn restore_buffer(&mut self, buffer_id: i64) -> Result<()> {
+        let updated = self
+            .conn
+            .execute(
+                "UPDATE buffers SET deleted_at = NULL, updated_at = ? WHERE id = ? AND deleted_at IS NOT NULL",
+                params![Self::now(), buffer_id],
+            )
             .map_err(StorageError::from)?;
-
-        for row in rows {
-            results.push(row.map_err(StorageError::from)?);
+        if updated == 0 {
+            return Err(StorageError::BufferNotFound {
+                identifier: buffer_id.to_string(),
+            }
+            .into());
         }
+        Ok(())
+    }
 
-        // If model specified, also get chunks with different model
-        if let Some(model) = current_model {
-            let mut stmt = self
-                .conn
-                .prepare(
-                    r"
-                    SELECT c.id FROM chunks c
-                    INNER JOIN chunk_embeddings e ON c.id = e.chunk_id
-                    WHERE c.buffer_id = ? AND (e.model_name IS NULL OR e.model_name != ?)
-                    ",
-                )
+    /// Permanently deletes every trashed buffer with its chunks and embeddings.
+    ///
+    /// Returns the IDs of the purged buffers.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the deletion fails.
+    pub fn purge_trash(&mut self) -> Result<Vec<i64>> {
+        let tx = self.conn.transaction().map_err(StorageError::from)?;
+        let ids = {
+            let mut stmt = tx
+                .prepare("SELECT id FROM buffers WHERE deleted_at IS NOT NULL ORDER BY id")
                 .map_err(StorageError::from)?;
+            stmt.query_map([], |row| row.get(0))
+                .map_err(StorageError::from)?
+                .collect::<std::result::Result<Vec<i64>, _>>()
+                .map_err(StorageError::from)?
+        };
+        // Chunks and embeddings are deleted automatically via CASCADE
+        tx.execute("DELETE FROM buffers WHERE deleted_at IS NOT NULL", [])
+            .map_err(StorageError::from)?;
+        tx.commit().map_err(StorageError::from)?;
+        Ok(ids)
+    }
 
-            let rows = stmt
-                .query_map(params![buffer_id, model], |row| row.get(0))
-                .map_err(StorageError::from)?;
+    /// Renames a buffer, keeping its chunks and embeddings.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StorageError::BufferNameTaken`] if another buffer already
+    /// has `new_name`, [`StorageError::BufferNotFound`] if the buffer
+    /// doesn't exist, or an error if the update fails.
+    pub fn rename_buffer(&mut self, buffer_id: i64, new_name: &str) -> Result<()> {
+        let taken: Option<i64> = self
+            .conn
+            .query_row(
+                "SELECT id FROM buffers WHERE name = ? AND id != ? LIMIT 1",
+                params![new_name, buffer_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(StorageError::from)?;
+        if taken.is_some() {
+            return Err(StorageError::BufferNameTaken {
+                name: new_name.to_string(),
+            }
+            .into());
+        }
 
-            for row in rows {
-                results.push(row.map_err(StorageError::from)?);
+        let updated = self
+            .conn
+            .execute(
+                "UPDATE buffers SET name = ?, updated_at = ? WHERE id = ?",
+                params![new_name, Self::now(), buffer_id],
+            )
+            .map_err(StorageError::from)?;
+        if updated == 0 {
+            return Err(StorageError::BufferNotFound {
+                identifier: buffer_id.to_string(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Compares the chunks of two buffers.
+    ///
+    /// Chunks are matched by a hash of their whitespace-normalized content,
+    /// so buffers chunked with different strategies are still comparable;
+    /// [`BufferDiff::strategies_differ`] reports when they were.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StorageError::BufferNotFound`] if either buffer doesn't
+    /// exist, or an error if the chunks can't be loaded.
+    pub fn diff_buffers(&self, a_id: i64, b_id: i64) -> Result<BufferDiff> {
+        for id in [a_id, b_id] {
+            if self.get_buffer(id)?.is_none() {
+                return Err(StorageError::BufferNotFound {
+                    identifier: id.to_string(),
+                }
+                .into());
+            }
+        }
+        let a = self.get_chunks(a_id)?;
+        let b = self.get_chunks(b_id)?;
+        Ok(diff_chunks(a_id, &a, b_id, &b))
+    }
+
+    /// Returns a buffer's custom key/value metadata.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer doesn't exist or its metadata isn't a
+    /// JSON object.
+    pub fn get_buffer_metadata(
+        &self,
+        buffer_id: i64,
+    ) -> Result<serde_json::Map<String, serde_json::Value>> {
+        let json: Option<Option<String>> = self
+            .conn
+            .query_row(
+                "SELECT custom_metadata FROM buffers WHERE id = ?",
+                params![buffer_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(StorageError::from)?;
+
+        let json = json.ok_or_else(|| StorageError::BufferNotFound {
+            identifier: buffer_id.to_string(),
+        })?;
+        json.map_or_else(
+            || Ok(serde_json::Map::new()),
+            |json| serde_json::from_str(&json).map_err(|e| StorageError::from(e).into()),
+        )
+    }
+
+    /// Sets (`Some`) or deletes (`None`) a key in a buffer's custom metadata.
+    ///
+    /// Other keys are preserved.
+    ///
+    /// # Returns
+    ///
+    /// The key's previous value, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer doesn't exist, its metadata can't be
+    /// parsed, or the update fails.
+    pub fn update_buffer_metadata(
+        &mut self,
+        buffer_id: i64,
+        key: &str,
+        value: Option<serde_json::Value>,
+    ) -> Result<Option<serde_json::Value>> {
+        let mut metadata = self.get_buffer_metadata(buffer_id)?;
+        let previous = match value {
+            Some(value) => metadata.insert(key.to_string(), value),
+            None => metadata.remove(key),
+        };
+
+        let json = if metadata.is_empty() {
+            None
+        } else {
+            Some(serde_json::Value::Object(metadata).to_string())
+        };
+        self.conn
+            .execute(
+                "UPDATE buffers SET custom_metadata = ?, updated_at = ? WHERE id = ?",
+                params![json, Self::now(), buffer_id],
+            )
+            .map_err(StorageError::from)?;
+        Ok(previous)
+    }
+
+    /// Returns the IDs of buffers whose custom metadata has every
+    /// `(key, value)` pair in `filters`, in ascending order.
+    ///
+    /// Values must be equal as JSON: the string `"2"` doesn't match the
+    /// number `2`. An empty `filters` slice matches every buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails or metadata can't be parsed.
+    pub fn buffers_with_metadata(
+        &self,
+        filters: &[(String, serde_json::Value)],
+    ) -> Result<Vec<i64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, custom_metadata FROM buffers ORDER BY id")
+            .map_err(StorageError::from)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, Option<String>>(1)?))
+            })
+            .map_err(StorageError::from)?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(StorageError::from)?;
+
+        let mut ids = Vec::new();
+        for (id, json) in rows {
+            let metadata: serde_json::Map<String, serde_json::Value> = match json {
+                Some(json) => serde_json::from_str(&json).map_err(StorageError::from)?,
+                None => serde_json::Map::new(),
+            };
+            if filters
+                .iter()
+                .all(|(key, value)| metadata.get(key) == Some(value))
+            {
+                ids.push(id);
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Returns the tags of a buffer, sorted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer doesn't exist or its tags can't be
+    /// parsed.
+    pub fn buffer_tags(&self, buffer_id: i64) -> Result<Vec<String>> {
+        let json: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT tags FROM buffers WHERE id = ?",
+                params![buffer_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(StorageError::from)?;
+
+        let json = json.ok_or_else(|| StorageError::BufferNotFound {
+            identifier: buffer_id.to_string(),
+        })?;
+        Self::parse_tags(&json)
+    }
+
+    /// Adds a tag to a buffer.
+    ///
+    /// Returns `false` if the buffer already had the tag.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer doesn't exist or the update fails.
+    pub fn add_tag(&mut self, buffer_id: i64, tag: &str) -> Result<bool> {
+        let mut tags = self.buffer_tags(buffer_id)?;
+        if tags.iter().any(|t| t == tag) {
+            return Ok(false);
+        }
+        tags.push(tag.to_string());
+        tags.sort();
+        self.set_buffer_tags(buffer_id, &tags)?;
+        Ok(true)
+    }
+
+    /// Removes a tag from a buffer.
+    ///
+    /// Returns `false` if the buffer didn't have the tag.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer doesn't exist or the update fails.
+    pub fn remove_tag(&mut self, buffer_id: i64, tag: &str) -> Result<bool> {
+        let mut tags = self.buffer_tags(buffer_id)?;
+        let before = tags.len();
+        tags.retain(|t| t != tag);
+        if tags.len() == before {
+            return Ok(false);
+        }
+        self.set_buffer_tags(buffer_id, &tags)?;
+        Ok(true)
+    }
+
+    /// Lists every tag in use with the number of buffers bearing it,
+    /// sorted by tag.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails or tags can't be parsed.
+    pub fn list_tags(&self) -> Result<Vec<(String, usize)>> {
+        let mut counts: std::collections::BTreeMap<String, usize> =
+            std::collections::BTreeMap::new();
+        for (_, tags) in self.all_buffer_tags()? {
+            for tag in tags {
+                *counts.entry(tag).or_default() += 1;
             }
         }
+        Ok(counts.into_iter().collect())
+    }
+
+    /// Returns the IDs of buffers bearing all of `tags`, in ascending order.
+    ///
+    /// An empty `tags` slice matches every buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails or tags can't be parsed.
+    pub fn buffers_with_tags(&self, tags: &[String]) -> Result<Vec<i64>> {
+        Ok(self
+            .all_buffer_tags()?
+            .into_iter()
+            .filter(|(_, buffer_tags)| tags.iter().all(|tag| buffer_tags.contains(tag)))
+            .map(|(id, _)| id)
+            .collect())
+    }
+
+    /// Returns `(buffer_id, tags)` for every buffer, in ascending ID order.
+    fn all_buffer_tags(&self) -> Result<Vec<(i64, Vec<String>)>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, tags FROM buffers ORDER BY id")
+            .map_err(StorageError::from)?;
+
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(StorageError::from)?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(StorageError::from)?;
+
+        rows.into_iter()
+            .map(|(id, json)| Ok((id, Self::parse_tags(&json)?)))
+            .collect()
+    }
+
+    fn set_buffer_tags(&self, buffer_id: i64, tags: &[String]) -> Result<()> {
+        let json = serde_json::to_string(tags).map_err(StorageError::from)?;
+        self.conn
+            .execute(
+                "UPDATE buffers SET tags = ?, updated_at = ? WHERE id = ?",
+                params![json, Self::now(), buffer_id],
+            )
+            .map_err(StorageError::from)?;
+        Ok(())
+    }
+
+    fn parse_tags(json: &str) -> Result<Vec<String>> {
+        serde_json::from_str(json).map_err(|e| StorageError::from(e).into())
+    }
+
+    /// Lists chunks across all buffers, ordered by buffer and chunk index.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - Maximum chunks to return (`None` for all).
+    /// * `offset` - Number of chunks to skip.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub fn list_chunks(&self, limit: Option<usize>, offset: usize) -> Result<Vec<Chunk>> {
+        // SQLite treats a negative LIMIT as unbounded
+        let limit = limit.map_or(-1, |n| i64::try_from(n).unwrap_or(i64::MAX));
+        let offset = i64::try_from(offset).unwrap_or(i64::MAX);
+
+        let mut stmt = self
+            .conn
+            .prepare(
+                r"
+            SELECT id, buffer_id, content, byte_start, byte_end, chunk_index,
+                   strategy, token_count, line_start, line_end, has_overlap,
+                   content_hash, custom_metadata, created_at
+            FROM chunks ORDER BY buffer_id, chunk_index
+            LIMIT ? OFFSET ?
+        ",
+            )
+            .map_err(StorageError::from)?;
+
+        let chunks = stmt
+            .query_map(params![limit, offset], Self::chunk_from_row)
+            .map_err(StorageError::from)?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(StorageError::from)?;
+
+        Ok(chunks)
+    }
+
+    /// Returns up to `n` chunk IDs chosen at random, from one buffer or all.
+    ///
+    /// Without a seed, rows are picked with `ORDER BY RANDOM()`. With a
+    /// seed, IDs are ordered by a hash of the seed and ID, so the same seed
+    /// and data always give the same sample.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub fn sample_chunk_ids(
+        &self,
+        buffer_id: Option<i64>,
+        n: usize,
+        seed: Option<u64>,
+    ) -> Result<Vec<i64>> {
+        buffer_id.map_or_else(
+            || self.sample_ids("SELECT id FROM chunks", [], n, seed),
+            |id| {
+                self.sample_ids(
+                    "SELECT id FROM chunks WHERE buffer_id = ?",
+                    params![id],
+                    n,
+                    seed,
+                )
+            },
+        )
+    }
+
+    /// Returns up to `n` buffer IDs chosen at random; see
+    /// [`sample_chunk_ids`](Self::sample_chunk_ids).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub fn sample_buffer_ids(&self, n: usize, seed: Option<u64>) -> Result<Vec<i64>> {
+        self.sample_ids("SELECT id FROM buffers", [], n, seed)
+    }
+
+    /// Returns up to `n` IDs from `select` (a query yielding one ID column)
+    /// in random order.
+    #[allow(clippy::cast_sign_loss)]
+    fn sample_ids<P: rusqlite::Params>(
+        &self,
+        select: &str,
+        params: P,
+        n: usize,
+        seed: Option<u64>,
+    ) -> Result<Vec<i64>> {
+        let sql = if seed.is_some() {
+            select.to_string()
+        } else {
+            let limit = i64::try_from(n).unwrap_or(i64::MAX);
+            format!("{select} ORDER BY RANDOM() LIMIT {limit}")
+        };
+
+        let mut stmt = self.conn.prepare(&sql).map_err(StorageError::from)?;
+        let mut ids = stmt
+            .query_map(params, |row| row.get::<_, i64>(0))
+            .map_err(StorageError::from)?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(StorageError::from)?;
+
+        if let Some(seed) = seed {
+            ids.sort_by_cached_key(|&id| (CorpusRng::new(seed ^ id as u64).next_u64(), id));
+            ids.truncate(n);
+        }
+
+        Ok(ids)
+    }
+
+    /// Returns the distinct buffer content types, sorted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub fn list_content_types(&self) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT DISTINCT content_type FROM buffers WHERE content_type IS NOT NULL ORDER BY content_type",
+            )
+            .map_err(StorageError::from)?;
+
+        let results = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(StorageError::from)?
+            .collect::<std::result::Result<Vec<String>, _>>()
+            .map_err(StorageError::from)?;
+
+        Ok(results)
+    }
+
+    /// Counts chunks with embeddings.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the count fails.
+    pub fn embedding_count(&self) -> Result<usize> {
+        let count: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM chunk_embeddings", [], |row| {
+                row.get(0)
+            })
+            .map_err(StorageError::from)?;
+        Ok(count as usize)
+    }
+
+    /// Returns a fingerprint of the searchable embeddings.
+    ///
+    /// Used to detect when an in-memory vector index no longer matches
+    /// the stored embeddings. Embeddings of trashed buffers are not
+    /// searchable, so trashing or restoring a buffer changes it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn embedding_fingerprint(&self) -> Result<EmbeddingFingerprint> {
+        let fingerprint = self
+            .conn
+            .query_row(
+                r"
+                SELECT COUNT(*), COALESCE(MAX(e.chunk_id), 0), COALESCE(MAX(e.created_at), 0)
+                FROM chunk_embeddings e
+                JOIN chunks c ON c.id = e.chunk_id
+                JOIN buffers b ON b.id = c.buffer_id
+                WHERE b.deleted_at IS NULL
+            ",
+                [],
+                |row| {
+                    Ok(EmbeddingFingerprint {
+                        count: row.get::<_, i64>(0)? as usize,
+                        max_chunk_id: row.get(1)?,
+                        latest_created_at: row.get(2)?,
+                    })
+                },
+            )
+            .map_err(StorageError::from)?;
+        Ok(fingerprint)
+    }
+
+    /// Checks if a chunk has an embedding.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub fn has_embedding(&self, chunk_id: i64) -> Result<bool> {
+        let count: i64 = self
+            .conn
+            .query_row(
+                "SELECT COUNT(*) FROM chunk_embeddings WHERE chunk_id = ?",
+                params![chunk_id],
+                |row| row.get(0),
+            )
+            .map_err(StorageError::from)?;
+        Ok(count > 0)
+    }
+
+    /// Gets chunk IDs that need embedding (either no embedding or wrong model).
+    ///
+    /// This is used for incremental embedding updates. Returns chunks that:
+    /// - Have no embedding at all, OR
+    /// - Have an embedding with a different model name (if `current_model` is provided)
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer_id` - The buffer to check.
+    /// * `current_model` - Optional model name to check against. If provided,
+    ///   chunks with different models are included.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub fn get_chunks_needing_embedding(
+        &self,
+        buffer_id: i64,
+        current_model: Option<&str>,
+    ) -> Result<Vec<i64>> {
+        let mut results = Vec::new();
+
+        // Get chunks without any embedding
+        let mut stmt = self
+            .conn
+            .prepare(
+                r"
+                SELECT c.id FROM chunks c
+                LEFT JOIN chunk_embeddings e ON c.id = e.chunk_id
+                WHERE c.buffer_id = ? AND e.chunk_id IS NULL
+                ",
+            )
+            .map_err(StorageError::from)?;
+
+        let rows = stmt
+            .query_map(params![buffer_id], |row| row.get(0))
+            .map_err(StorageError::from)?;
+
+        for row in rows {
+            results.push(row.map_err(StorageError::from)?);
+        }
+
+        // If model specified, also get chunks with different model
+        if let Some(model) = current_model {
+            let mut stmt = self
+                .conn
+                .prepare(
+                    r"
+                    SELECT c.id FROM chunks c
+                    INNER JOIN chunk_embeddings e ON c.id = e.chunk_id
+                    WHERE c.buffer_id = ? AND (e.model_name IS NULL OR e.model_name != ?)
+                    ",
+                )
+                .map_err(StorageError::from)?;
+
+            let rows = stmt
+                .query_map(params![buffer_id, model], |row| row.get(0))
+                .map_err(StorageError::from)?;
+
+            for row in rows {
+                results.push(row.map_err(StorageError::from)?);
+            }
+        }
+
+        // Deduplicate (in case of overlap, though shouldn't happen)
+        results.sort_unstable();
+        results.dedup();
+        Ok(results)
+    }
+
+    /// Gets chunks without any embedding for a buffer.
+    ///
+    /// Simpler version of `get_chunks_needing_embedding` when model doesn't matter.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub fn get_chunks_without_embedding(&self, buffer_id: i64) -> Result<Vec<i64>> {
+        self.get_chunks_needing_embedding(buffer_id, None)
+    }
+
+    /// Gets the IDs of chunks without an embedding, across buffers.
+    ///
+    /// Restricted to one buffer when `buffer_id` is given; chunks of
+    /// trashed buffers are never included. Selects the gaps in a single
+    /// query rather than checking buffer by buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub fn get_chunks_missing_embedding(&self, buffer_id: Option<i64>) -> Result<Vec<i64>> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                r"
+                SELECT c.id FROM chunks c
+                JOIN buffers b ON b.id = c.buffer_id
+                LEFT JOIN chunk_embeddings e ON c.id = e.chunk_id
+                WHERE e.chunk_id IS NULL AND b.deleted_at IS NULL
+                  AND (?1 IS NULL OR c.buffer_id = ?1)
+                ORDER BY c.id
+                ",
+            )
+            .map_err(StorageError::from)?;
+
+        let results = stmt
+            .query_map(params![buffer_id], |row| row.get(0))
+            .map_err(StorageError::from)?
+            .collect::<std::result::Result<Vec<i64>, _>>()
+            .map_err(StorageError::from)?;
+
+        Ok(results)
+    }
+
+    /// Gets the IDs of all chunks, across buffers.
+    ///
+    /// Restricted to one buffer when `buffer_id` is given; chunks of
+    /// trashed buffers are never included.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub fn get_chunk_ids(&self, buffer_id: Option<i64>) -> Result<Vec<i64>> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                r"
+                SELECT c.id FROM chunks c
+                JOIN buffers b ON b.id = c.buffer_id
+                WHERE b.deleted_at IS NULL AND (?1 IS NULL OR c.buffer_id = ?1)
+                ORDER BY c.id
+                ",
+            )
+            .map_err(StorageError::from)?;
+
+        let results = stmt
+            .query_map(params![buffer_id], |row| row.get(0))
+            .map_err(StorageError::from)?
+            .collect::<std::result::Result<Vec<i64>, _>>()
+            .map_err(StorageError::from)?;
+
+        Ok(results)
+    }
+
+    /// Gets the chunks within `radius` indices of a chunk in its buffer,
+    /// the chunk itself included, ordered by index.
+    ///
+    /// The window is clamped at the buffer's edges, and indices with no
+    /// chunk (e.g. after deletions) are skipped. Returns an empty list if
+    /// the chunk doesn't exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub fn get_chunk_neighbors(&self, chunk_id: i64, radius: usize) -> Result<Vec<Chunk>> {
+        let radius = i64::try_from(radius).unwrap_or(i64::MAX);
+        let mut stmt = self
+            .conn
+            .prepare(
+                r"
+            SELECT c.id, c.buffer_id, c.content, c.byte_start, c.byte_end, c.chunk_index,
+                   c.strategy, c.token_count, c.line_start, c.line_end, c.has_overlap,
+                   c.content_hash, c.custom_metadata, c.created_at
+            FROM chunks c
+            JOIN chunks origin ON origin.id = ?1
+            WHERE c.buffer_id = origin.buffer_id
+              AND c.chunk_index BETWEEN origin.chunk_index - ?2 AND origin.chunk_index + ?2
+            ORDER BY c.chunk_index
+        ",
+            )
+            .map_err(StorageError::from)?;
+
+        let chunks = stmt
+            .query_map(params![chunk_id, radius], Self::chunk_from_row)
+            .map_err(StorageError::from)?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(StorageError::from)?;
+
+        Ok(chunks)
+    }
+
+    /// Gets chunk IDs whose embedding was created before `cutoff`.
+    ///
+    /// Used to refresh stale embeddings (e.g. after a model update window).
+    /// Chunks without an embedding are not included.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer_id` - The buffer to check.
+    /// * `cutoff` - Unix timestamp; embeddings with `created_at < cutoff` match.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub fn get_chunks_embedded_before(&self, buffer_id: i64, cutoff: i64) -> Result<Vec<i64>> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                r"
+                SELECT c.id FROM chunks c
+                INNER JOIN chunk_embeddings e ON c.id = e.chunk_id
+                WHERE c.buffer_id = ? AND e.created_at < ?
+                ORDER BY c.id
+                ",
+            )
+            .map_err(StorageError::from)?;
+
+        let results = stmt
+            .query_map(params![buffer_id, cutoff], |row| row.get(0))
+            .map_err(StorageError::from)?
+            .collect::<std::result::Result<Vec<i64>, _>>()
+            .map_err(StorageError::from)?;
+
+        Ok(results)
+    }
+
+    /// Gets chunk IDs whose embedding does not have `dimensions` dimensions.
+    ///
+    /// Such embeddings were produced by a different embedder (or a
+    /// differently configured one) and cannot be compared with the current
+    /// query vectors. Covers all buffers.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn get_chunks_with_other_dimensions(&self, dimensions: usize) -> Result<Vec<i64>> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                r"
+                SELECT chunk_id FROM chunk_embeddings
+                WHERE dimensions != ?
+                ORDER BY chunk_id
+                ",
+            )
+            .map_err(StorageError::from)?;
+
+        let results = stmt
+            .query_map(params![dimensions as i64], |row| row.get(0))
+            .map_err(StorageError::from)?
+            .collect::<std::result::Result<Vec<i64>, _>>()
+            .map_err(StorageError::from)?;
+
+        Ok(results)
+    }
+
+    /// Gets embeddings that drifted from the current embedder: those without
+    /// `dimensions` dimensions, and with `model_name` given, those from any
+    /// other model (including embeddings with no recorded model).
+    ///
+    /// Returns `(chunk_id, buffer_id, model_name)` for each, ordered by
+    /// buffer and chunk. Covers all buffers.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn get_drifted_embeddings(
+        &self,
+        dimensions: usize,
+        model_name: Option<&str>,
+    ) -> Result<Vec<(i64, i64, Option<String>)>> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                r"
+                SELECT e.chunk_id, c.buffer_id, e.model_name
+                FROM chunk_embeddings e
+                JOIN chunks c ON c.id = e.chunk_id
+                WHERE e.dimensions != ?1
+                   OR (?2 IS NOT NULL AND e.model_name IS NOT ?2)
+                ORDER BY c.buffer_id, e.chunk_id
+                ",
+            )
+            .map_err(StorageError::from)?;
+
+        let results = stmt
+            .query_map(params![dimensions as i64, model_name], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .map_err(StorageError::from)?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(StorageError::from)?;
+
+        Ok(results)
+    }
+
+    /// Deletes every embedding that does not have `dimensions` dimensions.
+    ///
+    /// # Returns
+    ///
+    /// The number of embeddings deleted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if deletion fails.
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn delete_embeddings_with_other_dimensions(&mut self, dimensions: usize) -> Result<usize> {
+        let deleted = self
+            .conn
+            .execute(
+                "DELETE FROM chunk_embeddings WHERE dimensions != ?",
+                params![dimensions as i64],
+            )
+            .map_err(StorageError::from)?;
+        Ok(deleted)
+    }
+
+    /// Deletes embeddings with a specific model name.
+    ///
+    /// Useful for cleaning up embeddings from old models before re-embedding.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer_id` - The buffer to clean.
+    /// * `model_name` - The model name to match (or None to match NULL).
+    ///
+    /// # Returns
+    ///
+    /// The number of embeddings deleted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if deletion fails.
+    pub fn delete_embeddings_by_model(
+        &mut self,
+        buffer_id: i64,
+        model_name: Option<&str>,
+    ) -> Result<usize> {
+        let deleted = match model_name {
+            Some(name) => self
+                .conn
+                .execute(
+                    r"
+                    DELETE FROM chunk_embeddings
+                    WHERE chunk_id IN (
+                        SELECT id FROM chunks WHERE buffer_id = ?
+                    ) AND model_name = ?
+                    ",
+                    params![buffer_id, name],
+                )
+                .map_err(StorageError::from)?,
+            None => self
+                .conn
+                .execute(
+                    r"
+                    DELETE FROM chunk_embeddings
+                    WHERE chunk_id IN (
+                        SELECT id FROM chunks WHERE buffer_id = ?
+                    ) AND model_name IS NULL
+                    ",
+                    params![buffer_id],
+                )
+                .map_err(StorageError::from)?,
+        };
+        Ok(deleted)
+    }
+
+    /// Gets embedding statistics for a buffer.
+    ///
+    /// Returns counts of embedded vs total chunks, and model breakdown.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub fn get_embedding_stats(&self, buffer_id: i64) -> Result<EmbeddingStats> {
+        // Total chunks
+        let total_chunks: i64 = self
+            .conn
+            .query_row(
+                "SELECT COUNT(*) FROM chunks WHERE buffer_id = ?",
+                params![buffer_id],
+                |row| row.get(0),
+            )
+            .map_err(StorageError::from)?;
+
+        // Embedded chunks
+        let embedded_chunks: i64 = self
+            .conn
+            .query_row(
+                r"
+                SELECT COUNT(*) FROM chunk_embeddings e
+                INNER JOIN chunks c ON e.chunk_id = c.id
+                WHERE c.buffer_id = ?
+                ",
+                params![buffer_id],
+                |row| row.get(0),
+            )
+            .map_err(StorageError::from)?;
+
+        // Model counts
+        let model_counts = self.get_embedding_model_counts(buffer_id)?;
+
+        Ok(EmbeddingStats {
+            total_chunks: total_chunks as usize,
+            embedded_chunks: embedded_chunks as usize,
+            model_counts,
+        })
+    }
+
+    /// Gets chunk and embedding counts for every buffer in one query.
+    ///
+    /// Returns one entry per buffer, ordered by buffer ID, including
+    /// buffers without chunks.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+    pub fn embedding_coverage(&self) -> Result<Vec<EmbeddingCoverage>> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                r"
+                SELECT b.id, COUNT(c.id), COUNT(e.chunk_id)
+                FROM buffers b
+                LEFT JOIN chunks c ON c.buffer_id = b.id
+                LEFT JOIN chunk_embeddings e ON e.chunk_id = c.id
+                GROUP BY b.id
+                ORDER BY b.id
+                ",
+            )
+            .map_err(StorageError::from)?;
+
+        let coverage = stmt
+            .query_map([], |row| {
+                Ok(EmbeddingCoverage {
+                    buffer_id: row.get(0)?,
+                    total_chunks: row.get::<_, i64>(1)? as usize,
+                    embedded_chunks: row.get::<_, i64>(2)? as usize,
+                })
+            })
+            .map_err(StorageError::from)?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(StorageError::from)?;
+
+        Ok(coverage)
+    }
+}
+
+// ==================== sqlite-vec Index ====================
+
+/// SQL for the `vec0` virtual table mirroring `chunk_embeddings` for k-NN
+/// search, indexing vectors of `dimensions` values.
+///
+/// The table is created at [`init`](Storage::init) rather than in the schema
+/// so databases stay readable by builds without the `sqlite-vec` feature.
+/// Each row keeps its embedding's `created_at` so
+/// [`sync_vec_index`](SqliteStorage::sync_vec_index) can spot regenerated
+/// vectors. With `strict`, the triggers reject vectors of other sizes.
+#[cfg(feature = "sqlite-vec")]
+fn vec_index_sql(dimensions: usize, strict: bool) -> String {
+    let guard = if strict {
+        format!(
+            "SELECT RAISE(ABORT, 'embedding dimensions differ from the sqlite-vec index ({dimensions})') WHERE new.dimensions != {dimensions};"
+        )
+    } else {
+        String::new()
+    };
+    format!(
+        r"
+CREATE VIRTUAL TABLE IF NOT EXISTS chunk_vectors USING vec0(
+    chunk_id INTEGER PRIMARY KEY,
+    embedding float[{dimensions}] distance_metric=cosine,
+    +created_at INTEGER
+);
+
+-- TEMP triggers live only on this connection, so builds without the
+-- extension can still write chunk_embeddings. When dimensions are
+-- configured, the guard rejects vectors of other sizes; otherwise those are
+-- kept out of the index (brute-force search still covers them).
+DROP TRIGGER IF EXISTS temp.chunk_vectors_ai;
+DROP TRIGGER IF EXISTS temp.chunk_vectors_au;
+DROP TRIGGER IF EXISTS temp.chunk_vectors_ad;
+
+CREATE TEMP TRIGGER chunk_vectors_ai AFTER INSERT ON main.chunk_embeddings BEGIN
+    {guard}
+    DELETE FROM chunk_vectors WHERE chunk_id = new.chunk_id;
+    INSERT INTO chunk_vectors (chunk_id, embedding, created_at)
+    SELECT new.chunk_id, new.embedding, new.created_at WHERE new.dimensions = {dimensions};
+END;
+
+CREATE TEMP TRIGGER chunk_vectors_au AFTER UPDATE ON main.chunk_embeddings BEGIN
+    {guard}
+    DELETE FROM chunk_vectors WHERE chunk_id = old.chunk_id;
+    INSERT INTO chunk_vectors (chunk_id, embedding, created_at)
+    SELECT new.chunk_id, new.embedding, new.created_at WHERE new.dimensions = {dimensions};
+END;
+
+CREATE TEMP TRIGGER chunk_vectors_ad AFTER DELETE ON main.chunk_embeddings BEGIN
+    DELETE FROM chunk_vectors WHERE chunk_id = old.chunk_id;
+END;
+"
+    )
+}
+
+/// Registers `sqlite-vec` with every connection opened afterwards.
+#[cfg(feature = "sqlite-vec")]
+#[allow(unsafe_code)]
+fn register_sqlite_vec() {
+    use rusqlite::ffi;
+    use std::os::raw::{c_char, c_int};
+
+    type ExtensionInit = unsafe extern "C" fn(
+        *mut ffi::sqlite3,
+        *mut *mut c_char,
+        *const ffi::sqlite3_api_routines,
+    ) -> c_int;
+
+    static REGISTER: std::sync::Once = std::sync::Once::new();
+    REGISTER.call_once(|| {
+        // SAFETY: sqlite3_vec_init is the extension entry point, which has
+        // the signature SQLite expects of auto extensions
+        unsafe {
+            ffi::sqlite3_auto_extension(Some(std::mem::transmute::<*const (), ExtensionInit>(
+                sqlite_vec::sqlite3_vec_init as *const (),
+            )));
+        }
+    });
+}
+
+#[cfg(feature = "sqlite-vec")]
+impl SqliteStorage {
+    /// Returns the dimensions of the `sqlite-vec` index.
+    ///
+    /// This is the configured [`embedding_dimensions`](Self::embedding_dimensions),
+    /// or [`DEFAULT_DIMENSIONS`] when none are configured.
+    #[must_use]
+    pub fn vec_index_dimensions(&self) -> usize {
+        self.embedding_dimensions.unwrap_or(DEFAULT_DIMENSIONS)
+    }
+
+    /// Creates the `chunk_vectors` table and triggers, then backfills it.
+    ///
+    /// A table built for other dimensions, or by a build that didn't track
+    /// `created_at`, is dropped and rebuilt.
+    fn init_vec_index(&mut self) -> Result<()> {
+        let dimensions = self.vec_index_dimensions();
+        let existing: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'chunk_vectors'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(StorageError::from)?;
+        if existing.is_some_and(|sql| {
+            !sql.contains(&format!("float[{dimensions}]")) || !sql.contains("+created_at")
+        }) {
+            self.conn
+                .execute_batch("DROP TABLE chunk_vectors;")
+                .map_err(|e| StorageError::Migration(e.to_string()))?;
+        }
+
+        let sql = vec_index_sql(dimensions, self.embedding_dimensions.is_some());
+        self.conn
+            .execute_batch(&sql)
+            .map_err(|e| StorageError::Migration(e.to_string()))?;
+        self.sync_vec_index()?;
+        Ok(())
+    }
+
+    /// Brings `chunk_vectors` in line with `chunk_embeddings`.
+    ///
+    /// Connections keep the two in sync with triggers; this repairs the
+    /// table after a build without the feature added, deleted, or
+    /// regenerated embeddings (and backfills it the first time). Only embeddings with
+    /// [`vec_index_dimensions`](Self::vec_index_dimensions) are indexed;
+    /// `fix-dimensions` or `reembed-all` migrates the rest. Returns the
+    /// number of vectors written, 0 when the table was already in sync.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a query fails.
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn sync_vec_index(&mut self) -> Result<usize> {
+        let dimensions = self.vec_index_dimensions() as i64;
+        // Count and ID sum catch added or deleted rows, and the created_at sum
+        // regenerated ones (at one-second resolution), without comparing vectors
+        let stored: (i64, i64, i64) = self
+            .conn
+            .query_row(
+                "SELECT COUNT(*), COALESCE(SUM(chunk_id), 0), COALESCE(SUM(created_at), 0)
+                 FROM chunk_embeddings WHERE dimensions = ?",
+                params![dimensions],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .map_err(StorageError::from)?;
+        let indexed: (i64, i64, i64) = self
+            .conn
+            .query_row(
+                "SELECT COUNT(*), COALESCE(SUM(chunk_id), 0), COALESCE(SUM(created_at), 0)
+                 FROM chunk_vectors",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .map_err(StorageError::from)?;
+        if stored == indexed {
+            return Ok(0);
+        }
+
+        let tx = self.conn.transaction().map_err(StorageError::from)?;
+        tx.execute("DELETE FROM chunk_vectors", [])
+            .map_err(StorageError::from)?;
+        let written = tx
+            .execute(
+                "INSERT INTO chunk_vectors (chunk_id, embedding, created_at)
+                 SELECT chunk_id, embedding, created_at FROM chunk_embeddings WHERE dimensions = ?",
+                params![dimensions],
+            )
+            .map_err(StorageError::from)?;
+        tx.commit().map_err(StorageError::from)?;
+        Ok(written)
+    }
+
+    /// Finds the `top_k` chunks nearest to `query` with the `sqlite-vec` index.
+    ///
+    /// Returns `(chunk_id, distance)` pairs, nearest first. The distance is
+    /// cosine distance (`1 - cosine similarity`). Chunks of trashed buffers
+    /// are left out, so fewer than `top_k` pairs may be returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StorageError::VectorSearch`] if `query` doesn't have
+    /// [`vec_index_dimensions`](Self::vec_index_dimensions) values, or an
+    /// error if the query fails.
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn search_vec(&self, query: &[f32], top_k: usize) -> Result<Vec<(i64, f32)>> {
+        let dimensions = self.vec_index_dimensions();
+        if query.len() != dimensions {
+            return Err(StorageError::VectorSearch(format!(
+                "query has {} dimensions, the sqlite-vec index expects {dimensions}",
+                query.len()
+            ))
+            .into());
+        }
+        if top_k == 0 {
+            return Ok(Vec::new());
+        }
+
+        let bytes: Vec<u8> = query.iter().flat_map(|f| f.to_le_bytes()).collect();
+        // vec0 allows no other ORDER BY on a KNN query, so the CTE is kept
+        // from being flattened and ties are broken here instead
+        let mut stmt = self
+            .conn
+            .prepare(
+                r"
+            WITH knn AS MATERIALIZED (
+                SELECT chunk_id, distance FROM chunk_vectors
+                WHERE embedding MATCH ?1 AND k = ?2
+            )
+            SELECT knn.chunk_id, knn.distance
+            FROM knn
+            JOIN chunks c ON c.id = knn.chunk_id
+            JOIN buffers b ON b.id = c.buffer_id
+            WHERE b.deleted_at IS NULL
+        ",
+            )
+            .map_err(StorageError::from)?;
+        let mut results: Vec<(i64, f32)> = stmt
+            .query_map(params![bytes, top_k as i64], |row| {
+                Ok((row.get(0)?, row.get::<_, f64>(1)? as f32))
+            })
+            .map_err(StorageError::from)?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(StorageError::from)?;
+        results.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+        Ok(results)
+    }
+}
+
+// ==================== IVF Index ====================
+
+impl SqliteStorage {
+    /// Replaces the IVF index with new centroids and chunk assignments.
+    ///
+    /// `assignments` pairs chunk IDs with indexes into `centroids`. An empty
+    /// `centroids` clears the index. The corpus version is bumped, since
+    /// cached searches that probed the old clusters are no longer valid.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an assignment refers to a missing centroid or
+    /// embedding, or if the update fails; the old index is kept then.
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn replace_ivf_index(
+        &mut self,
+        centroids: &[Vec<f32>],
+        assignments: &[(i64, usize)],
+    ) -> Result<()> {
+        let tx = self.conn.transaction().map_err(StorageError::from)?;
+        let now = Self::now();
+
+        // Assignments are deleted via CASCADE
+        tx.execute("DELETE FROM ivf_centroids", [])
+            .map_err(StorageError::from)?;
+        {
+            let mut stmt = tx
+                .prepare(
+                    "INSERT INTO ivf_centroids (id, centroid, dimensions, created_at) VALUES (?, ?, ?, ?)",
+                )
+                .map_err(StorageError::from)?;
+            for (id, centroid) in centroids.iter().enumerate() {
+                let bytes: Vec<u8> = centroid.iter().flat_map(|f| f.to_le_bytes()).collect();
+                stmt.execute(params![id as i64, bytes, centroid.len() as i64, now])
+                    .map_err(StorageError::from)?;
+            }
+
+            let mut stmt = tx
+                .prepare("INSERT INTO ivf_assignments (chunk_id, centroid_id) VALUES (?, ?)")
+                .map_err(StorageError::from)?;
+            for (chunk_id, centroid) in assignments {
+                stmt.execute(params![chunk_id, *centroid as i64])
+                    .map_err(StorageError::from)?;
+            }
+        }
+        tx.execute(
+            "UPDATE metadata SET value = CAST(value AS INTEGER) + 1, updated_at = ?
+             WHERE key = 'corpus_version'",
+            params![now],
+        )
+        .map_err(StorageError::from)?;
+
+        tx.commit().map_err(StorageError::from)?;
+        Ok(())
+    }
+
+    /// Returns the IVF centroids as `(centroid_id, vector)` pairs, empty when
+    /// no index has been built.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub fn ivf_centroids(&self) -> Result<Vec<(i64, Vec<f32>)>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, centroid FROM ivf_centroids ORDER BY id")
+            .map_err(StorageError::from)?;
+        let centroids = stmt
+            .query_map([], Self::embedding_from_row)
+            .map_err(StorageError::from)?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(StorageError::from)?;
+        Ok(centroids)
+    }
+
+    /// Returns every IVF assignment as `(chunk_id, centroid_id)` pairs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub fn ivf_assignments(&self) -> Result<Vec<(i64, i64)>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT chunk_id, centroid_id FROM ivf_assignments ORDER BY chunk_id")
+            .map_err(StorageError::from)?;
+        let assignments = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(StorageError::from)?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(StorageError::from)?;
+        Ok(assignments)
+    }
+
+    /// Gets the embeddings assigned to any of `centroid_ids`, plus those not
+    /// assigned to a cluster yet, from live buffers.
+    ///
+    /// Unassigned embeddings (of other dimensions, or stored by a build that
+    /// predates the index) are always included so they stay searchable.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub fn get_embeddings_in_clusters(
+        &self,
+        centroid_ids: &[i64],
+        content_type: Option<&str>,
+    ) -> Result<Vec<(i64, Vec<f32>)>> {
+        let ids = serde_json::to_string(centroid_ids).map_err(StorageError::from)?;
+        let mut stmt = self
+            .conn
+            .prepare(
+                r"
+                SELECT e.chunk_id, e.embedding
+                FROM chunk_embeddings e
+                JOIN chunks c ON c.id = e.chunk_id
+                JOIN buffers b ON b.id = c.buffer_id
+                LEFT JOIN ivf_assignments a ON a.chunk_id = e.chunk_id
+                WHERE b.deleted_at IS NULL
+                  AND (?2 IS NULL OR b.content_type = ?2)
+                  AND (a.centroid_id IS NULL
+                       OR a.centroid_id IN (SELECT value FROM json_each(?1)))
+            ",
+            )
+            .map_err(StorageError::from)?;
+        let results = stmt
+            .query_map(params![ids, content_type], Self::embedding_from_row)
+            .map_err(StorageError::from)?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(StorageError::from)?;
+        Ok(results)
+    }
+
+    /// Assigns freshly stored embeddings to their nearest IVF centroid.
+    ///
+    /// Does nothing without an index. Embeddings whose dimensions differ
+    /// from the centroids are left unassigned.
+    fn assign_ivf_clusters(conn: &Connection, embeddings: &[(i64, &[f32])]) -> Result<()> {
+        let centroids = {
+            let mut stmt = conn
+                .prepare("SELECT id, centroid FROM ivf_centroids ORDER BY id")
+                .map_err(StorageError::from)?;
+            stmt.query_map([], Self::embedding_from_row)
+                .map_err(StorageError::from)?
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .map_err(StorageError::from)?
+        };
+        if centroids.is_empty() {
+            return Ok(());
+        }
+
+        let mut assign = conn
+            .prepare("INSERT OR REPLACE INTO ivf_assignments (chunk_id, centroid_id) VALUES (?, ?)")
+            .map_err(StorageError::from)?;
+        let mut unassign = conn
+            .prepare("DELETE FROM ivf_assignments WHERE chunk_id = ?")
+            .map_err(StorageError::from)?;
+        for (chunk_id, embedding) in embeddings {
+            let nearest = centroids
+                .iter()
+                .filter(|(_, centroid)| centroid.len() == embedding.len())
+                .map(|(id, centroid)| (*id, cosine_similarity(embedding, centroid)))
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+            match nearest {
+                Some((centroid_id, _)) => assign.execute(params![chunk_id, centroid_id]),
+                None => unassign.execute(params![chunk_id]),
+            }
+            .map_err(StorageError::from)?;
+        }
+        Ok(())
+    }
+}
+
+// ==================== Buffer Versions ====================
+
+impl SqliteStorage {
+    /// Saves the current content of a buffer as its next version.
+    ///
+    /// Version numbers start at 1 and keep increasing even after older
+    /// versions are pruned. Only the newest `keep` versions are retained.
+    ///
+    /// Returns the number of the saved version.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StorageError::BufferNotFound`] if the buffer doesn't exist,
+    /// or an error if the insert fails.
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn save_buffer_version(&mut self, buffer_id: i64, keep: usize) -> Result<i64> {
+        let tx = self.conn.transaction().map_err(StorageError::from)?;
+        let content: Option<String> = tx
+            .query_row(
+                "SELECT content FROM buffers WHERE id = ?",
+                params![buffer_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(StorageError::from)?;
+        let Some(content) = content else {
+            return Err(StorageError::BufferNotFound {
+                identifier: buffer_id.to_string(),
+            }
+            .into());
+        };
+
+        let version: i64 = tx
+            .query_row(
+                "SELECT COALESCE(MAX(version), 0) + 1 FROM buffer_versions WHERE buffer_id = ?",
+                params![buffer_id],
+                |row| row.get(0),
+            )
+            .map_err(StorageError::from)?;
+        tx.execute(
+            "INSERT INTO buffer_versions (buffer_id, version, content, content_hash, size, created_at)
+             VALUES (?, ?, ?, ?, ?, ?)",
+            params![
+                buffer_id,
+                version,
+                content,
+                content_hash(&content),
+                content.len() as i64,
+                Self::now(),
+            ],
+        )
+        .map_err(StorageError::from)?;
+        tx.execute(
+            "DELETE FROM buffer_versions WHERE buffer_id = ?1 AND version NOT IN (
+                SELECT version FROM buffer_versions WHERE buffer_id = ?1
+                ORDER BY version DESC LIMIT ?2
+            )",
+            params![buffer_id, keep as i64],
+        )
+        .map_err(StorageError::from)?;
+        tx.commit().map_err(StorageError::from)?;
+        Ok(version)
+    }
+
+    /// Lists the saved versions of a buffer, newest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub fn list_buffer_versions(&self, buffer_id: i64) -> Result<Vec<BufferVersion>> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT version, content_hash, size, created_at FROM buffer_versions
+                 WHERE buffer_id = ? ORDER BY version DESC",
+            )
+            .map_err(StorageError::from)?;
+        let versions = stmt
+            .query_map(params![buffer_id], |row| {
+                Ok(BufferVersion {
+                    version: row.get(0)?,
+                    content_hash: row.get(1)?,
+                    size: row.get::<_, i64>(2)? as usize,
+                    created_at: row.get(3)?,
+                })
+            })
+            .map_err(StorageError::from)?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(StorageError::from)?;
+        Ok(versions)
+    }
+
+    /// Gets the content of a saved buffer version.
+    ///
+    /// Returns `None` if the version doesn't exist or was pruned.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub fn get_buffer_version(&self, buffer_id: i64, version: i64) -> Result<Option<String>> {
+        let content = self
+            .conn
+            .query_row(
+                "SELECT content FROM buffer_versions WHERE buffer_id = ? AND version = ?",
+                params![buffer_id, version],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(StorageError::from)?;
+        Ok(content)
+    }
+}
+
+// ==================== Storage Usage ====================
+
+impl SqliteStorage {
+    /// Reports how much space content, embeddings, and the FTS index use.
+    ///
+    /// Content and embedding totals come from `SUM(LENGTH(...))`. Per-table
+    /// page usage (including the FTS index) comes from the `dbstat` virtual
+    /// table and is omitted when `SQLite` was built without it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a query fails.
+    pub fn storage_usage(&self) -> Result<StorageUsage> {
+        let sum = |sql: &str| -> Result<usize> {
+            let total: i64 = self
+                .conn
+                .query_row(sql, [], |row| row.get(0))
+                .map_err(StorageError::from)?;
+            Ok(total as usize)
+        };
+
+        let buffer_content_bytes =
+            sum("SELECT COALESCE(SUM(LENGTH(CAST(content AS BLOB))), 0) FROM buffers")?;
+        let chunk_content_bytes =
+            sum("SELECT COALESCE(SUM(LENGTH(CAST(content AS BLOB))), 0) FROM chunks")?;
+        let embedding_bytes =
+            sum("SELECT COALESCE(SUM(LENGTH(embedding)), 0) FROM chunk_embeddings")?;
+        let embedding_count = sum("SELECT COUNT(*) FROM chunk_embeddings")?;
+        let page_count = sum("PRAGMA page_count")?;
+        let page_size = sum("PRAGMA page_size")?;
+
+        let tables = self.table_usage()?;
+        let fts_bytes = tables.as_ref().map(|tables| {
+            tables
+                .iter()
+                .filter(|t| t.name.starts_with("chunks_fts"))
+                .map(|t| t.bytes)
+                .sum()
+        });
+
+        Ok(StorageUsage {
+            buffer_content_bytes,
+            chunk_content_bytes,
+            embedding_bytes,
+            embedding_count,
+            fts_bytes,
+            db_bytes: page_count * page_size,
+            tables: tables.unwrap_or_default(),
+        })
+    }
+
+    /// Reports per-buffer chunk and embedding counts, average chunk size,
+    /// embedding bytes, and the embedding models in use.
+    ///
+    /// Buffers in the trash are excluded, as in [`stats`](Storage::stats).
+    /// Embedding bytes come from `SUM(LENGTH(embedding))` and chunk sizes
+    /// are in bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a query fails.
+    #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+    pub fn detailed_stats(&self) -> Result<DetailedStats> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                r"
+                SELECT b.id, b.name, COUNT(c.id), COUNT(e.chunk_id),
+                       AVG(LENGTH(CAST(c.content AS BLOB))),
+                       COALESCE(SUM(LENGTH(e.embedding)), 0)
+                FROM buffers b
+                LEFT JOIN chunks c ON c.buffer_id = b.id
+                LEFT JOIN chunk_embeddings e ON e.chunk_id = c.id
+                WHERE b.deleted_at IS NULL
+                GROUP BY b.id
+                ORDER BY b.id
+                ",
+            )
+            .map_err(StorageError::from)?;
+        let buffers = stmt
+            .query_map([], |row| {
+                let chunk_count = row.get::<_, i64>(2)? as usize;
+                let embedded_chunks = row.get::<_, i64>(3)? as usize;
+                Ok(BufferStats {
+                    buffer_id: row.get(0)?,
+                    name: row.get(1)?,
+                    chunk_count,
+                    embedded_chunks,
+                    unembedded_chunks: chunk_count - embedded_chunks,
+                    avg_chunk_size: row.get::<_, Option<f64>>(4)?.unwrap_or(0.0),
+                    embedding_bytes: row.get::<_, i64>(5)? as usize,
+                })
+            })
+            .map_err(StorageError::from)?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(StorageError::from)?;
+
+        let mut stmt = self
+            .conn
+            .prepare(
+                r"
+                SELECT DISTINCT e.model_name
+                FROM chunk_embeddings e
+                JOIN chunks c ON c.id = e.chunk_id
+                JOIN buffers b ON b.id = c.buffer_id
+                WHERE b.deleted_at IS NULL AND e.model_name IS NOT NULL
+                ORDER BY e.model_name
+                ",
+            )
+            .map_err(StorageError::from)?;
+        let models = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(StorageError::from)?
+            .collect::<std::result::Result<Vec<String>, _>>()
+            .map_err(StorageError::from)?;
+
+        Ok(DetailedStats {
+            embedded_chunks: buffers.iter().map(|b| b.embedded_chunks).sum(),
+            unembedded_chunks: buffers.iter().map(|b| b.unembedded_chunks).sum(),
+            embedding_bytes: buffers.iter().map(|b| b.embedding_bytes).sum(),
+            models,
+            buffers,
+        })
+    }
+
+    /// Returns on-disk bytes per table and index via `dbstat`, largest first.
+    ///
+    /// Returns `None` if the `dbstat` virtual table is not available.
+    fn table_usage(&self) -> Result<Option<Vec<TableUsage>>> {
+        let Ok(mut stmt) = self.conn.prepare(
+            "SELECT name, SUM(pgsize) AS bytes FROM dbstat GROUP BY name ORDER BY bytes DESC, name",
+        ) else {
+            return Ok(None);
+        };
+
+        let tables = stmt
+            .query_map([], |row| {
+                Ok(TableUsage {
+                    name: row.get(0)?,
+                    bytes: row.get::<_, i64>(1)? as usize,
+                })
+            })
+            .map_err(StorageError::from)?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(StorageError::from)?;
+
+        Ok(Some(tables))
+    }
+}
+
+// ==================== Search Cache ====================
+
+impl SqliteStorage {
+    /// Returns the corpus version, which increases whenever a buffer, chunk,
+    /// or embedding is added, updated, or deleted.
+    ///
+    /// Clients can compare it with a previously read value to tell whether
+    /// the corpus changed. It is never reset, not even by `reset`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails (e.g. on a pre-v8 schema).
+    pub fn corpus_version(&self) -> Result<i64> {
+        self.conn
+            .query_row(
+                "SELECT CAST(value AS INTEGER) FROM metadata WHERE key = 'corpus_version'",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|e| StorageError::from(e).into())
+    }
+
+    /// Returns cached search results for `key` if they were stored at the
+    /// current corpus version.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub fn cached_search(&self, key: &str) -> Result<Option<String>> {
+        self.conn
+            .query_row(
+                r"
+            SELECT results FROM search_cache
+            WHERE key = ? AND corpus_version =
+                (SELECT CAST(value AS INTEGER) FROM metadata WHERE key = 'corpus_version')
+        ",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| StorageError::from(e).into())
+    }
+
+    /// Caches search results for `key` at the current corpus version.
+    ///
+    /// Entries from older corpus versions can never be hit again and are
+    /// dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a write fails.
+    pub fn cache_search(&mut self, key: &str, results: &str) -> Result<()> {
+        let version = self.corpus_version()?;
+        let tx = self.conn.transaction().map_err(StorageError::from)?;
+        tx.execute(
+            "DELETE FROM search_cache WHERE corpus_version <> ?",
+            params![version],
+        )
+        .map_err(StorageError::from)?;
+        tx.execute(
+            r"
+            INSERT OR REPLACE INTO search_cache (key, corpus_version, results, created_at)
+            VALUES (?, ?, ?, ?)
+        ",
+            params![key, version, results, Self::now()],
+        )
+        .map_err(StorageError::from)?;
+        tx.commit().map_err(StorageError::from)?;
+        Ok(())
+    }
+}
+
+// ==================== Streaming Operations ====================
+
+impl SqliteStorage {
+    /// Opens an incremental reader over a chunk's content.
+    ///
+    /// Reads directly from the database page by page instead of
+    /// materializing the content, so multi-megabyte chunks can be piped
+    /// without holding them in memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StorageError::ChunkNotFound`] if the chunk does not exist,
+    /// or an error if the content cannot be opened for reading.
+    pub fn chunk_content_reader(&self, chunk_id: i64) -> Result<impl std::io::Read + '_> {
+        let exists = self
+            .conn
+            .query_row(
+                "SELECT 1 FROM chunks WHERE id = ?",
+                params![chunk_id],
+                |_| Ok(()),
+            )
+            .optional()
+            .map_err(StorageError::from)?;
+        if exists.is_none() {
+            return Err(StorageError::ChunkNotFound { id: chunk_id }.into());
+        }
+
+        let blob = self
+            .conn
+            .blob_open(MAIN_DB, "chunks", "content", chunk_id, true)
+            .map_err(StorageError::from)?;
+
+        Ok(blob)
+    }
+}
+
+// ==================== Maintenance Operations ====================
+
+impl SqliteStorage {
+    /// Refreshes the query planner statistics (`ANALYZE`).
+    ///
+    /// Run after bulk loads or deletes so `SQLite` picks good query plans
+    /// for the new data distribution.
+    ///
+    /// # Returns
+    ///
+    /// The number of tables and indexes with planner statistics.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the statement fails.
+    #[allow(clippy::cast_sign_loss)]
+    pub fn analyze(&self) -> Result<usize> {
+        self.conn
+            .execute_batch("ANALYZE;")
+            .map_err(StorageError::from)?;
+
+        let count: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM sqlite_stat1", [], |row| row.get(0))
+            .map_err(StorageError::from)?;
+
+        Ok(count as usize)
+    }
+}
+
+/// Statistics about embeddings for a buffer.
+#[derive(Debug, Clone)]
+pub struct EmbeddingStats {
+    /// Total number of chunks in the buffer.
+    pub total_chunks: usize,
+    /// Number of chunks with embeddings.
+    pub embedded_chunks: usize,
+    /// Count of embeddings by model (`model_name`, count).
+    pub model_counts: Vec<(Option<String>, i64)>,
+}
+
+/// Embedding coverage of one buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmbeddingCoverage {
+    /// Buffer ID.
+    pub buffer_id: i64,
+    /// Number of chunks in the buffer.
+    pub total_chunks: usize,
+    /// Number of chunks with embeddings.
+    pub embedded_chunks: usize,
+}
+
+impl EmbeddingCoverage {
+    /// Returns the percentage of chunks with embeddings (100 when the
+    /// buffer has no chunks).
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn percentage(&self) -> f64 {
+        if self.total_chunks == 0 {
+            100.0
+        } else {
+            self.embedded_chunks as f64 / self.total_chunks as f64 * 100.0
+        }
+    }
+}
+
+/// Summary of the embeddings table at a point in time.
+///
+/// Adding, deleting, or regenerating embeddings changes the fingerprint
+/// (regeneration is detected at one-second resolution).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EmbeddingFingerprint {
+    /// Number of stored embeddings.
+    pub count: usize,
+    /// Highest chunk ID with an embedding (0 when empty).
+    pub max_chunk_id: i64,
+    /// Most recent embedding `created_at` timestamp (0 when empty).
+    pub latest_created_at: i64,
+}
+
+/// A saved version of a buffer's content (without the content itself).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BufferVersion {
+    /// Version number, increasing per buffer.
+    pub version: i64,
+    /// Hash of the saved content.
+    pub content_hash: String,
+    /// Size of the saved content in bytes.
+    pub size: usize,
+    /// When the version was saved (Unix timestamp).
+    pub created_at: i64,
+}
+
+/// Space used by the database, broken down by kind of data.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StorageUsage {
+    /// Total bytes of buffer content.
+    pub buffer_content_bytes: usize,
+    /// Total bytes of chunk content.
+    pub chunk_content_bytes: usize,
+    /// Total bytes of embedding vectors.
+    pub embedding_bytes: usize,
+    /// Number of stored embeddings.
+    pub embedding_count: usize,
+    /// On-disk bytes of the FTS index (`None` without `dbstat`).
+    pub fts_bytes: Option<usize>,
+    /// Database size in bytes (`page_count * page_size`).
+    pub db_bytes: usize,
+    /// On-disk bytes per table and index, largest first (empty without `dbstat`).
+    pub tables: Vec<TableUsage>,
+}
+
+/// Chunk and embedding breakdown of the stored buffers, beyond the
+/// aggregate counts in [`StorageStats`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DetailedStats {
+    /// Number of chunks with embeddings.
+    pub embedded_chunks: usize,
+    /// Number of chunks without embeddings.
+    pub unembedded_chunks: usize,
+    /// Total bytes of embedding vectors.
+    pub embedding_bytes: usize,
+    /// Names of the embedding models in use, sorted.
+    pub models: Vec<String>,
+    /// Per-buffer breakdown, ordered by buffer ID.
+    pub buffers: Vec<BufferStats>,
+}
+
+/// Chunk and embedding counts of one buffer.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BufferStats {
+    /// Buffer ID.
+    pub buffer_id: i64,
+    /// Buffer name, if any.
+    pub name: Option<String>,
+    /// Number of chunks.
+    pub chunk_count: usize,
+    /// Number of chunks with embeddings.
+    pub embedded_chunks: usize,
+    /// Number of chunks without embeddings.
+    pub unembedded_chunks: usize,
+    /// Average chunk size in bytes (0 without chunks).
+    pub avg_chunk_size: f64,
+    /// Total bytes of the buffer's embedding vectors.
+    pub embedding_bytes: usize,
+}
+
+/// On-disk size of a single table or index.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TableUsage {
+    /// Table or index name.
+    pub name: String,
+    /// Bytes used by its pages.
+    pub bytes: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ContextValue;
+
+    fn setup() -> SqliteStorage {
+        let mut storage = SqliteStorage::in_memory().unwrap();
+        storage.init().unwrap();
+        storage
+    }
+
+    #[test]
+    fn test_init() {
+        let mut storage = SqliteStorage::in_memory().unwrap();
+        assert!(storage.init().is_ok());
+        assert!(storage.is_initialized().unwrap());
+    }
+
+    #[test]
+    fn test_probe_fts5() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        assert!(storage.probe_fts5().is_ok());
+        // Probe leaves nothing behind and can be repeated
+        assert!(storage.probe_fts5().is_ok());
+    }
+
+    #[test]
+    fn test_open_with_journal_modes() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+
+        for mode in [JournalMode::Delete, JournalMode::Wal, JournalMode::Memory] {
+            let path = temp_dir.path().join(format!("{}.db", mode.as_str()));
+            let options = SqliteOptions::new().with_journal_mode(mode);
+            let storage = SqliteStorage::open_with_options(&path, &options).unwrap();
+            let actual: String = storage
+                .conn
+                .query_row("PRAGMA journal_mode;", [], |row| row.get(0))
+                .unwrap();
+            assert_eq!(actual, mode.as_str());
+        }
+    }
+
+    #[test]
+    fn test_newer_schema_is_refused() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("future.db");
+        let options = SqliteOptions::new();
+        {
+            let mut storage = SqliteStorage::open_with_options(&path, &options).unwrap();
+            storage.init().unwrap();
+            SqliteStorage::set_schema_version(&storage.conn, CURRENT_SCHEMA_VERSION + 1).unwrap();
+            // Already-open connections are refused at init
+            let err = storage.init().unwrap_err();
+            assert!(matches!(
+                err,
+                crate::Error::Storage(StorageError::SchemaTooNew { .. })
+            ));
+        }
+
+        let result = SqliteStorage::open_with_options(&path, &options);
+        assert!(matches!(
+            result,
+            Err(crate::Error::Storage(StorageError::SchemaTooNew { found, supported }))
+                if found == CURRENT_SCHEMA_VERSION + 1 && supported == CURRENT_SCHEMA_VERSION
+        ));
+    }
+
+    #[test]
+    fn test_open_with_cache_and_mmap_size() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("tuned.db");
+        let options = SqliteOptions::new()
+            .with_cache_size_kib(8192)
+            .with_mmap_size(1024 * 1024);
+        let storage = SqliteStorage::open_with_options(&path, &options).unwrap();
+
+        let cache: i64 = storage
+            .conn
+            .query_row("PRAGMA cache_size;", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(cache, -8192);
+        let mmap: i64 = storage
+            .conn
+            .query_row("PRAGMA mmap_size;", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(mmap, 1024 * 1024);
+    }
+
+    #[test]
+    fn test_effective_sizes_scale_with_db() {
+        let options = SqliteOptions::new();
+        assert_eq!(options.effective_cache_size_kib(0), MIN_AUTO_CACHE_KIB);
+        assert_eq!(
+            options.effective_cache_size_kib(64 * 1024 * 1024),
+            16 * 1024
+        );
+        assert_eq!(
+            options.effective_cache_size_kib(10 * 1024 * 1024 * 1024),
+            MAX_AUTO_CACHE_KIB
+        );
+        assert_eq!(options.effective_mmap_size(1000), 1000);
+        assert_eq!(options.effective_mmap_size(u64::MAX), MAX_AUTO_MMAP_BYTES);
+
+        let explicit = SqliteOptions::new()
+            .with_cache_size_kib(100)
+            .with_mmap_size(0);
+        assert_eq!(explicit.effective_cache_size_kib(u64::MAX), 100);
+        assert_eq!(explicit.effective_mmap_size(u64::MAX), 0);
+    }
+
+    #[test]
+    fn test_chunk_content_reader() {
+        use std::io::Read;
+
+        let mut storage = setup();
+        let content = "Hello, world!".repeat(1000);
+        let buffer_id = storage
+            .add_buffer(&Buffer::from_content(content.clone()))
+            .unwrap();
+        let chunks = vec![Chunk::new(buffer_id, content.clone(), 0..content.len(), 0)];
+        storage.add_chunks(buffer_id, &chunks).unwrap();
+        let chunk_id = storage.get_chunks(buffer_id).unwrap()[0].id.unwrap();
+
+        let mut streamed = String::new();
+        storage
+            .chunk_content_reader(chunk_id)
+            .unwrap()
+            .read_to_string(&mut streamed)
+            .unwrap();
+        assert_eq!(streamed, content);
+
+        assert!(storage.chunk_content_reader(9999).is_err());
+    }
+
+    #[test]
+    fn test_search_filtered_by_content_type() {
+        let mut storage = setup();
+        let mut ids = Vec::new();
+        for (name, content_type) in [("a.rs", "rs"), ("b.md", "md")] {
+            let mut buffer = Buffer::from_named(name.to_string(), "shared term".to_string());
+            buffer.metadata.content_type = Some(content_type.to_string());
+            let buffer_id = storage.add_buffer(&buffer).unwrap();
+            let chunks = vec![Chunk::new(buffer_id, "shared term".to_string(), 0..11, 0)];
+            storage.add_chunks(buffer_id, &chunks).unwrap();
+            let chunk_id = storage.get_chunks(buffer_id).unwrap()[0].id.unwrap();
+            storage
+                .store_embedding(chunk_id, &[1.0, 0.0], None)
+                .unwrap();
+            ids.push(chunk_id);
+        }
+
+        assert_eq!(storage.search_fts("shared", 10, false).unwrap().len(), 2);
+        let rs_only = storage
+            .search_fts_filtered("shared", 10, Some("rs"), false)
+            .unwrap();
+        assert_eq!(rs_only.len(), 1);
+        assert_eq!(rs_only[0].0, ids[0]);
+        assert!(
+            storage
+                .search_fts_filtered("shared", 10, Some("py"), false)
+                .unwrap()
+                .is_empty()
+        );
+
+        let md_embeddings = storage.get_embeddings_by_content_type("md").unwrap();
+        assert_eq!(md_embeddings.len(), 1);
+        assert_eq!(md_embeddings[0], (ids[1], vec![1.0, 0.0]));
+
+        assert_eq!(storage.list_content_types().unwrap(), vec!["md", "rs"]);
+    }
+
+    #[test]
+    fn test_list_chunks_across_buffers() {
+        let mut storage = setup();
+        let a = storage
+            .add_buffer(&Buffer::from_content("aaaa".to_string()))
+            .unwrap();
+        let b = storage
+            .add_buffer(&Buffer::from_content("bb".to_string()))
+            .unwrap();
+        // Insert the later buffer first to check ordering
+        storage
+            .add_chunks(b, &[Chunk::new(b, "bb".to_string(), 0..2, 0)])
+            .unwrap();
+        storage
+            .add_chunks(
+                a,
+                &[
+                    Chunk::new(a, "aa".to_string(), 0..2, 0),
+                    Chunk::new(a, "aa".to_string(), 2..4, 1),
+                ],
+            )
+            .unwrap();
+
+        let all = storage.list_chunks(None, 0).unwrap();
+        let order: Vec<(i64, usize)> = all.iter().map(|c| (c.buffer_id, c.index)).collect();
+        assert_eq!(order, vec![(a, 0), (a, 1), (b, 0)]);
+
+        let page = storage.list_chunks(Some(2), 1).unwrap();
+        let order: Vec<(i64, usize)> = page.iter().map(|c| (c.buffer_id, c.index)).collect();
+        assert_eq!(order, vec![(a, 1), (b, 0)]);
+
+        assert!(storage.list_chunks(Some(5), 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_buffer_ids() {
+        let mut storage = setup();
+        assert!(storage.buffer_ids().unwrap().is_empty());
+
+        let a = storage
+            .add_buffer(&Buffer::from_content("a".to_string()))
+            .unwrap();
+        let b = storage
+            .add_buffer(&Buffer::from_content("b".to_string()))
+            .unwrap();
+        assert_eq!(storage.buffer_ids().unwrap(), vec![a, b]);
+
+        storage.delete_buffer(a).unwrap();
+        assert_eq!(storage.buffer_ids().unwrap(), vec![b]);
+    }
+
+    #[test]
+    fn test_add_chunks_computes_missing_hash() {
+        let mut storage = setup();
+        let buffer_id = storage
+            .add_buffer(&Buffer::from_content("Hello world".to_string()))
+            .unwrap();
+        storage
+            .add_chunks(
+                buffer_id,
+                &[Chunk::new(buffer_id, "Hello".to_string(), 0..5, 0)],
+            )
+            .unwrap();
+
+        let chunk = &storage.get_chunks(buffer_id).unwrap()[0];
+        assert_eq!(
+            chunk.metadata.content_hash.as_deref(),
+            Some(content_hash("Hello").as_str())
+        );
+        assert_eq!(chunk.verify_hash(), Some(true));
+    }
+
+    #[test]
+    fn test_rename_buffer() {
+        let mut storage = setup();
+        let a = storage
+            .add_buffer(&Buffer::from_named("a".to_string(), "x".to_string()))
+            .unwrap();
+        storage
+            .add_buffer(&Buffer::from_named("b".to_string(), "y".to_string()))
+            .unwrap();
+
+        storage.rename_buffer(a, "renamed").unwrap();
+        assert_eq!(
+            storage.get_buffer(a).unwrap().unwrap().name.as_deref(),
+            Some("renamed")
+        );
+        assert!(storage.get_buffer_by_name("a").unwrap().is_none());
+
+        // Keeping the current name is not a collision
+        storage.rename_buffer(a, "renamed").unwrap();
+
+        let err = storage.rename_buffer(a, "b").unwrap_err();
+        assert!(err.to_string().contains("already in use: b"));
+        assert!(storage.rename_buffer(999, "c").is_err());
+    }
+
+    #[test]
+    fn test_buffer_metadata() {
+        let mut storage = setup();
+        let id = storage
+            .add_buffer(&Buffer::from_named("a".to_string(), "x".to_string()))
+            .unwrap();
+        assert!(storage.get_buffer_metadata(id).unwrap().is_empty());
+
+        let previous = storage
+            .update_buffer_metadata(id, "author", Some(serde_json::json!("ada")))
+            .unwrap();
+        assert_eq!(previous, None);
+        storage
+            .update_buffer_metadata(id, "year", Some(serde_json::json!(1843)))
+            .unwrap();
+        let previous = storage
+            .update_buffer_metadata(id, "author", Some(serde_json::json!("lovelace")))
+            .unwrap();
+        assert_eq!(previous, Some(serde_json::json!("ada")));
+
+        let metadata = storage.get_buffer_metadata(id).unwrap();
+        assert_eq!(metadata["author"], "lovelace");
+        assert_eq!(metadata["year"], 1843);
+
+        // Metadata travels with the buffer and survives update_buffer
+        let buffer = storage.get_buffer(id).unwrap().unwrap();
+        assert!(
+            buffer
+                .metadata
+                .custom
+                .as_deref()
+                .unwrap()
+                .contains("lovelace")
+        );
+        storage.update_buffer(&buffer).unwrap();
+        assert_eq!(storage.get_buffer_metadata(id).unwrap().len(), 2);
+
+        storage.update_buffer_metadata(id, "author", None).unwrap();
+        storage.update_buffer_metadata(id, "year", None).unwrap();
+        assert!(
+            storage
+                .get_buffer(id)
+                .unwrap()
+                .unwrap()
+                .metadata
+                .custom
+                .is_none()
+        );
+        assert!(storage.get_buffer_metadata(999).is_err());
+
+        // Filtering by metadata matches JSON values exactly
+        let other = storage
+            .add_buffer(&Buffer::from_named("b".to_string(), "y".to_string()))
+            .unwrap();
+        storage
+            .update_buffer_metadata(id, "project", Some(serde_json::json!("alpha")))
+            .unwrap();
+        storage
+            .update_buffer_metadata(other, "project", Some(serde_json::json!("alpha")))
+            .unwrap();
+        storage
+            .update_buffer_metadata(other, "year", Some(serde_json::json!(2)))
+            .unwrap();
+        let filter = |pairs: &[(&str, serde_json::Value)]| {
+            let pairs: Vec<(String, serde_json::Value)> = pairs
+                .iter()
+                .map(|(k, v)| ((*k).to_string(), v.clone()))
+                .collect();
+            storage.buffers_with_metadata(&pairs).unwrap()
+        };
+        assert_eq!(filter(&[]).len(), 2);
+        assert_eq!(
+            filter(&[("project", serde_json::json!("alpha"))]),
+            vec![id, other]
+        );
+        assert_eq!(
+            filter(&[
+                ("project", serde_json::json!("alpha")),
+                ("year", serde_json::json!(2))
+            ]),
+            vec![other]
+        );
+        assert!(filter(&[("year", serde_json::json!("2"))]).is_empty());
+    }
+
+    #[test]
+    fn test_buffer_tags() {
+        let mut storage = setup();
+        let a = storage
+            .add_buffer(&Buffer::from_content("a".to_string()))
+            .unwrap();
+        let b = storage
+            .add_buffer(&Buffer::from_content("b".to_string()))
+            .unwrap();
+        assert!(storage.buffer_tags(a).unwrap().is_empty());
+
+        assert!(storage.add_tag(a, "rust").unwrap());
+        assert!(storage.add_tag(a, "docs").unwrap());
+        assert!(!storage.add_tag(a, "rust").unwrap());
+        assert!(storage.add_tag(b, "rust").unwrap());
+        assert_eq!(storage.buffer_tags(a).unwrap(), vec!["docs", "rust"]);
+
+        assert_eq!(
+            storage.list_tags().unwrap(),
+            vec![("docs".to_string(), 1), ("rust".to_string(), 2)]
+        );
+        assert_eq!(
+            storage.buffers_with_tags(&["rust".to_string()]).unwrap(),
+            vec![a, b]
+        );
+        assert_eq!(
+            storage
+                .buffers_with_tags(&["rust".to_string(), "docs".to_string()])
+                .unwrap(),
+            vec![a]
+        );
+        assert_eq!(storage.buffers_with_tags(&[]).unwrap(), vec![a, b]);
+
+        assert!(storage.remove_tag(a, "docs").unwrap());
+        assert!(!storage.remove_tag(a, "docs").unwrap());
+        assert_eq!(storage.buffer_tags(a).unwrap(), vec!["rust"]);
+
+        assert!(storage.buffer_tags(999).is_err());
+        assert!(storage.add_tag(999, "x").is_err());
+    }
+
+    #[test]
+    fn test_migration_v3_adds_empty_tags() {
+        let mut storage = SqliteStorage::in_memory().unwrap();
+        storage.init().unwrap();
+        let id = storage
+            .add_buffer(&Buffer::from_content("legacy".to_string()))
+            .unwrap();
+
+        // Roll the database back to a v3 layout without the tags column
+        storage
+            .conn
+            .execute_batch(
+                "ALTER TABLE buffers DROP COLUMN tags;
+                 ALTER TABLE buffers DROP COLUMN source_encoding;
+                 ALTER TABLE buffers DROP COLUMN custom_metadata;
+                 DROP INDEX idx_buffers_deleted;
+                 ALTER TABLE buffers DROP COLUMN deleted_at;
+                 ALTER TABLE buffers DROP COLUMN line_ending;
+                 ALTER TABLE buffers DROP COLUMN whitespace_normalized;
+                 ALTER TABLE chunk_embeddings DROP COLUMN normalized;",
+            )
+            .unwrap();
+        SqliteStorage::set_schema_version(&storage.conn, 3).unwrap();
+
+        storage.init().unwrap();
+        assert_eq!(
+            storage.schema_version().unwrap(),
+            Some(CURRENT_SCHEMA_VERSION)
+        );
+        assert!(storage.buffer_tags(id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_search_fts_raw_query() {
+        let mut storage = setup();
+        let id = storage
+            .add_buffer(&Buffer::from_content("text".to_string()))
+            .unwrap();
+        let chunks = vec![
+            Chunk::new(id, "the quick brown fox".to_string(), 0..19, 0),
+            Chunk::new(id, "brown bears are quick".to_string(), 19..40, 1),
+        ];
+        storage.add_chunks(id, &chunks).unwrap();
+        let count = |query: &str, raw: bool| storage.search_fts(query, 10, raw).unwrap().len();
+
+        // The default rewrite matches any term; raw queries keep FTS5 syntax
+        assert_eq!(count("\"quick brown\"", false), 2);
+        assert_eq!(count("\"quick brown\"", true), 1);
+        assert_eq!(count("fox AND bears", true), 0);
+        assert_eq!(count("quick AND bears", true), 1);
+        assert_eq!(count("NEAR(quick fox, 2)", true), 1);
+        assert_eq!(count("NEAR(brown quick, 0)", true), 1);
+
+        let err = storage.search_fts("quick AND", 10, true).unwrap_err();
+        assert!(
+            matches!(
+                &err,
+                crate::Error::Storage(StorageError::InvalidQuery { query, .. }) if query == "quick AND"
+            ),
+            "{err}"
+        );
+        // Unbalanced quotes are escaped, not an error, in the default mode
+        assert_eq!(count("\"quick", false), 2);
+    }
+
+    #[test]
+    fn test_search_fts_with_timeout() {
+        let mut storage = setup();
+        let id = storage
+            .add_buffer(&Buffer::from_content("text".to_string()))
+            .unwrap();
+        let chunks = vec![
+            Chunk::new(id, "the quick brown fox".to_string(), 0..19, 0),
+            Chunk::new(id, "brown bears are quick".to_string(), 19..40, 1),
+        ];
+        storage.add_chunks(id, &chunks).unwrap();
+        let timeout = Duration::from_secs(60);
+
+        let results = storage
+            .search_fts_with_timeout("quick", 10, None, false, timeout)
+            .unwrap();
+        assert_eq!(
+            results,
+            Some(storage.search_fts("quick", 10, false).unwrap())
+        );
+
+        // Errors other than the timeout still surface
+        assert!(
+            storage
+                .search_fts_with_timeout("quick AND", 10, None, true, timeout)
+                .is_err()
+        );
+        assert_eq!(storage.search_fts("fox", 10, false).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_corpus_version_monotonic() {
+        let mut storage = setup();
+        assert_eq!(storage.corpus_version().unwrap(), 0);
+        assert_eq!(storage.stats().unwrap().corpus_version, 0);
+
+        let id = storage
+            .add_buffer(&Buffer::from_content("text".to_string()))
+            .unwrap();
+        storage
+            .add_chunks(id, &[Chunk::new(id, "text".to_string(), 0..4, 0)])
+            .unwrap();
+        let loaded = storage.corpus_version().unwrap();
+        assert!(loaded >= 2);
+
+        // Reads leave it alone
+        storage.get_chunks(id).unwrap();
+        assert_eq!(storage.corpus_version().unwrap(), loaded);
+
+        storage.delete_buffer(id).unwrap();
+        let deleted = storage.corpus_version().unwrap();
+        assert!(deleted > loaded);
+
+        // Reset clears the corpus but never rewinds the counter
+        storage.reset().unwrap();
+        assert!(storage.corpus_version().unwrap() >= deleted);
+        assert_eq!(
+            storage.stats().unwrap().corpus_version,
+            storage.corpus_version().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_fts_snippet() {
+        let mut storage = setup();
+        let id = storage
+            .add_buffer(&Buffer::from_content("text".to_string()))
+            .unwrap();
+        let chunks = vec![
+            Chunk::new(id, "the quick brown fox".to_string(), 0..19, 0),
+            Chunk::new(id, "lazy dogs sleep".to_string(), 19..34, 1),
+        ];
+        storage.add_chunks(id, &chunks).unwrap();
+        let ids: Vec<i64> = storage
+            .get_chunks(id)
+            .unwrap()
+            .iter()
+            .filter_map(|c| c.id)
+            .collect();
+
+        assert_eq!(
+            storage.fts_snippet(ids[0], "fox quick", false, 16).unwrap(),
+            Some("the [quick] brown [fox]".to_string())
+        );
+        assert_eq!(
+            storage
+                .fts_snippet(ids[0], "\"brown fox\"", true, 16)
+                .unwrap(),
+            Some("the quick [brown fox]".to_string())
+        );
+        // No match in this chunk, so nothing to excerpt
+        assert_eq!(storage.fts_snippet(ids[1], "fox", false, 16).unwrap(), None);
+        assert!(storage.fts_snippet(ids[0], "fox AND", true, 16).is_err());
+    }
+
+    #[test]
+    fn test_search_cache_invalidated_by_corpus_changes() {
+        let mut storage = setup();
+        let v0 = storage.corpus_version().unwrap();
+        storage.cache_search("k", "[1]").unwrap();
+        assert_eq!(storage.cached_search("k").unwrap().as_deref(), Some("[1]"));
+        assert_eq!(storage.cached_search("other").unwrap(), None);
+
+        let id = storage
+            .add_buffer(&Buffer::from_content("text".to_string()))
+            .unwrap();
+        assert!(storage.corpus_version().unwrap() > v0);
+        assert_eq!(storage.cached_search("k").unwrap(), None);
+
+        storage
+            .add_chunks(id, &[Chunk::new(id, "text".to_string(), 0..4, 0)])
+            .unwrap();
+        storage.cache_search("k", "[2]").unwrap();
+        let chunk_id = storage.get_chunks(id).unwrap()[0].id.unwrap();
+        storage
+            .store_embedding(chunk_id, &[0.5, 0.5], Some("test"))
+            .unwrap();
+        assert_eq!(storage.cached_search("k").unwrap(), None);
+
+        // Stale entries are pruned on the next write
+        storage.cache_search("k2", "[3]").unwrap();
+        let rows: i64 = storage
+            .conn
+            .query_row("SELECT COUNT(*) FROM search_cache", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(rows, 1);
+    }
+
+    #[test]
+    fn test_migration_v8_reconciles_chunk_counts() {
+        let mut storage = setup();
+        let id = storage
+            .add_buffer(&Buffer::from_content("legacy".to_string()))
+            .unwrap();
+        storage
+            .add_chunks(id, &[Chunk::new(id, "legacy".to_string(), 0..6, 0)])
+            .unwrap();
+
+        // Roll back to v8: no count triggers and a drifted count
+        storage
+            .conn
+            .execute_batch(
+                "DROP TRIGGER chunks_count_ai;
+                 DROP TRIGGER chunks_count_ad;
+                 DROP TRIGGER chunks_count_au;
+                 DROP INDEX idx_buffers_deleted;
+                 ALTER TABLE buffers DROP COLUMN deleted_at;
+                 ALTER TABLE buffers DROP COLUMN line_ending;
+                 ALTER TABLE buffers DROP COLUMN whitespace_normalized;
+                 ALTER TABLE chunk_embeddings DROP COLUMN normalized;
+                 UPDATE buffers SET chunk_count = 5;",
+            )
+            .unwrap();
+        SqliteStorage::set_schema_version(&storage.conn, 8).unwrap();
+
+        storage.init().unwrap();
+        let count = |s: &SqliteStorage| s.get_buffer(id).unwrap().unwrap().metadata.chunk_count;
+        assert_eq!(count(&storage), Some(1));
+        storage
+            .add_chunks(id, &[Chunk::new(id, "more".to_string(), 6..10, 1)])
+            .unwrap();
+        assert_eq!(count(&storage), Some(2));
+    }
+
+    #[test]
+    fn test_migration_v9_adds_trash() {
+        let mut storage = setup();
+        let id = storage
+            .add_buffer(&Buffer::from_content("legacy".to_string()))
+            .unwrap();
+        storage
+            .conn
+            .execute_batch(
+                "DROP INDEX idx_buffers_deleted;
+                 ALTER TABLE buffers DROP COLUMN deleted_at;
+                 ALTER TABLE buffers DROP COLUMN line_ending;
+                 ALTER TABLE buffers DROP COLUMN whitespace_normalized;
+                 ALTER TABLE chunk_embeddings DROP COLUMN normalized;",
+            )
+            .unwrap();
+        SqliteStorage::set_schema_version(&storage.conn, 9).unwrap();
+
+        storage.init().unwrap();
+        assert_eq!(storage.list_buffers().unwrap().len(), 1);
+        storage.trash_buffer(id).unwrap();
+        assert!(storage.list_buffers().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_migration_v15_repairs_chunk_update_trigger() {
+        let mut storage = setup();
+        let id = storage
+            .add_buffer(&Buffer::from_content("legacy".to_string()))
+            .unwrap();
+        storage
+            .add_chunks(id, &[Chunk::new(id, "legacy".to_string(), 0..6, 0)])
+            .unwrap();
+
+        // Roll back to v15: the broken FTS update trigger
+        storage
+            .conn
+            .execute_batch(
+                "DROP TRIGGER chunks_au;
+                 CREATE TRIGGER chunks_au AFTER UPDATE ON chunks BEGIN
+                     INSERT INTO chunks_fts(chunks_fts, rowid, content) VALUES('delete', old.id, old.content);
+                     INSERT INTO chunks_fts(chunks_fts, rowid, content) VALUES (new.id, new.content);
+                 END;",
+            )
+            .unwrap();
+        SqliteStorage::set_schema_version(&storage.conn, 15).unwrap();
+        let update = "UPDATE chunks SET content = 'renamed' WHERE buffer_id = ?";
+        assert!(storage.conn.execute(update, params![id]).is_err());
+
+        storage.init().unwrap();
+        storage.conn.execute(update, params![id]).unwrap();
+        assert_eq!(storage.search_fts("renamed", 10, false).unwrap().len(), 1);
+        assert!(storage.search_fts("legacy", 10, false).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_failed_migration_rolls_back() {
+        let mut storage = setup();
+        let columns = |storage: &SqliteStorage, table: &str| -> Vec<String> {
+            let mut stmt = storage
+                .conn
+                .prepare(&format!("SELECT name FROM pragma_table_info('{table}')"))
+                .unwrap();
+            stmt.query_map([], |row| row.get(0))
+                .unwrap()
+                .collect::<std::result::Result<_, _>>()
+                .unwrap()
+        };
+
+        // Roll back to v13, but leave v15's column behind so v14 -> v15 fails
+        storage
+            .conn
+            .execute_batch("ALTER TABLE chunk_embeddings DROP COLUMN normalized;")
+            .unwrap();
+        SqliteStorage::set_schema_version(&storage.conn, 13).unwrap();
+        assert!(matches!(
+            storage.init(),
+            Err(crate::Error::Storage(StorageError::Migration(_)))
+        ));
+
+        // v13 -> v14 was undone with the failed step, so a retry can succeed
+        assert_eq!(storage.schema_version().unwrap(), Some(13));
+        assert!(!columns(&storage, "chunk_embeddings").contains(&"normalized".to_string()));
+        storage
+            .conn
+            .execute_batch("ALTER TABLE buffers DROP COLUMN whitespace_normalized;")
+            .unwrap();
+        storage.init().unwrap();
+        assert_eq!(
+            storage.schema_version().unwrap(),
+            Some(CURRENT_SCHEMA_VERSION)
+        );
+        assert!(columns(&storage, "chunk_embeddings").contains(&"normalized".to_string()));
+    }
+
+    #[test]
+    #[cfg(feature = "sqlite-vec")]
+    fn test_search_vec() {
+        let mut storage = setup();
+        let id = storage
+            .add_buffer(&Buffer::from_content("vectors".to_string()))
+            .unwrap();
+        let chunks: Vec<Chunk> = (0..3)
+            .map(|i| Chunk::new(id, format!("chunk {i}"), i..i + 1, i))
+            .collect();
+        storage.add_chunks(id, &chunks).unwrap();
+        let chunk_ids: Vec<i64> = storage
+            .get_chunks(id)
+            .unwrap()
+            .iter()
+            .filter_map(|c| c.id)
+            .collect();
+        let axis = |i: usize| {
+            let mut v = vec![0.0; DEFAULT_DIMENSIONS];
+            v[i] = 1.0;
+            v
+        };
+        storage
+            .store_embedding(chunk_ids[0], &axis(0), None)
+            .unwrap();
+        storage
+            .store_embedding(chunk_ids[1], &axis(1), None)
+            .unwrap();
+        let mut between = axis(0);
+        between[1] = 1.0;
+        storage
+            .store_embedding(chunk_ids[2], &between, None)
+            .unwrap();
+
+        let results = storage.search_vec(&axis(0), 2).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, chunk_ids[0]);
+        assert!(results[0].1.abs() < 1e-6);
+        assert_eq!(results[1].0, chunk_ids[2]);
+
+        // Vectors of other dimensions stay out of the index
+        storage
+            .store_embedding(chunk_ids[0], &[1.0, 0.0], None)
+            .unwrap();
+        assert_eq!(storage.search_vec(&axis(0), 1).unwrap()[0].0, chunk_ids[2]);
+        assert!(storage.search_vec(&[1.0, 0.0], 2).is_err());
+
+        // Deleted embeddings leave the index through the triggers
+        storage
+            .store_embedding(chunk_ids[0], &axis(0), None)
+            .unwrap();
+        storage.delete_embedding(chunk_ids[0]).unwrap();
+        assert_eq!(storage.search_vec(&axis(0), 1).unwrap()[0].0, chunk_ids[2]);
+
+        // Re-initializing backfills a missing table from chunk_embeddings
+        storage
+            .conn
+            .execute_batch(
+                "DROP TRIGGER temp.chunk_vectors_ai;
+                 DROP TRIGGER temp.chunk_vectors_au;
+                 DROP TRIGGER temp.chunk_vectors_ad;
+                 DROP TABLE chunk_vectors;",
+            )
+            .unwrap();
+        storage.init().unwrap();
+        assert_eq!(storage.search_vec(&axis(1), 3).unwrap().len(), 2);
+        assert_eq!(storage.sync_vec_index().unwrap(), 0);
+
+        // A vector regenerated without the triggers (same chunk IDs) is
+        // picked up by its newer created_at
+        storage
+            .conn
+            .execute_batch("DROP TRIGGER temp.chunk_vectors_au;")
+            .unwrap();
+        let bytes: Vec<u8> = axis(0).iter().flat_map(|f| f.to_le_bytes()).collect();
+        storage
+            .conn
+            .execute(
+                "UPDATE chunk_embeddings SET embedding = ?, created_at = created_at + 1
+                 WHERE chunk_id = ?",
+                params![bytes, chunk_ids[1]],
+            )
+            .unwrap();
+        assert_eq!(storage.sync_vec_index().unwrap(), 2);
+        assert_eq!(storage.search_vec(&axis(0), 1).unwrap()[0].0, chunk_ids[1]);
+
+        storage.trash_buffer(id).unwrap();
+        assert!(storage.search_vec(&axis(1), 3).unwrap().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "sqlite-vec")]
+    fn test_search_vec_configured_dimensions() {
+        let mut storage = setup();
+        let id = storage
+            .add_buffer(&Buffer::from_content("vectors".to_string()))
+            .unwrap();
+        let chunks: Vec<Chunk> = (0..2)
+            .map(|i| Chunk::new(id, format!("chunk {i}"), i..i + 1, i))
+            .collect();
+        storage.add_chunks(id, &chunks).unwrap();
+        let chunk_ids: Vec<i64> = storage
+            .get_chunks(id)
+            .unwrap()
+            .iter()
+            .filter_map(|c| c.id)
+            .collect();
+
+        // Re-initializing with configured dimensions rebuilds the index
+        storage.set_embedding_dimensions(Some(4));
+        storage.init().unwrap();
+        assert_eq!(storage.vec_index_dimensions(), 4);
+        storage
+            .store_embedding(chunk_ids[0], &[1.0, 0.0, 0.0, 0.0], None)
+            .unwrap();
+        storage
+            .store_embedding(chunk_ids[1], &[0.0, 1.0, 0.0, 0.0], None)
+            .unwrap();
+        let results = storage.search_vec(&[1.0, 0.1, 0.0, 0.0], 1).unwrap();
+        assert_eq!(results[0].0, chunk_ids[0]);
+        assert!(storage.search_vec(&[1.0; DEFAULT_DIMENSIONS], 1).is_err());
+
+        // Other sizes are rejected rather than left out of the index
+        assert!(
+            storage
+                .store_embedding(chunk_ids[0], &[1.0, 0.0], None)
+                .is_err()
+        );
+        let raw = storage.conn.execute(
+            "UPDATE chunk_embeddings SET embedding = zeroblob(8), dimensions = 2 WHERE chunk_id = ?",
+            params![chunk_ids[0]],
+        );
+        assert!(
+            raw.unwrap_err()
+                .to_string()
+                .contains("sqlite-vec index (4)")
+        );
+        assert_eq!(
+            storage.search_vec(&[1.0, 0.0, 0.0, 0.0], 2).unwrap().len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_buffer_versions() {
+        let mut storage = setup();
+        let id = storage
+            .add_buffer(&Buffer::from_named("doc".to_string(), "v1".to_string()))
+            .unwrap();
+        assert!(storage.list_buffer_versions(id).unwrap().is_empty());
+
+        for (expected, next) in (1_i64..).zip(["v2", "v3", "v4"]) {
+            let version = storage.save_buffer_version(id, 2).unwrap();
+            assert_eq!(version, expected);
+            let mut buffer = storage.get_buffer(id).unwrap().unwrap();
+            buffer.content = next.to_string();
+            storage.update_buffer(&buffer).unwrap();
+        }
+
+        // Only the newest two versions are kept
+        let versions = storage.list_buffer_versions(id).unwrap();
+        assert_eq!(
+            versions.iter().map(|v| v.version).collect::<Vec<_>>(),
+            vec![3, 2]
+        );
+        assert_eq!(versions[0].size, 2);
+        assert_eq!(versions[0].content_hash, content_hash("v3"));
+        assert_eq!(
+            storage.get_buffer_version(id, 2).unwrap().as_deref(),
+            Some("v2")
+        );
+        assert!(storage.get_buffer_version(id, 1).unwrap().is_none());
+
+        assert!(storage.save_buffer_version(999, 2).is_err());
+
+        storage.delete_buffer(id).unwrap();
+        assert!(storage.list_buffer_versions(id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_get_chunks_missing_embedding() {
+        let mut storage = setup();
+        let mut chunk_ids = Vec::new();
+        for text in ["first", "second"] {
+            let id = storage
+                .add_buffer(&Buffer::from_content(text.to_string()))
+                .unwrap();
+            let chunks = vec![
+                Chunk::new(id, format!("{text} a"), 0..1, 0),
+                Chunk::new(id, format!("{text} b"), 1..2, 1),
+            ];
+            storage.add_chunks(id, &chunks).unwrap();
+            chunk_ids.extend(storage.get_chunks(id).unwrap().iter().filter_map(|c| c.id));
+        }
+        let first_buffer = storage.get_chunk(chunk_ids[0]).unwrap().unwrap().buffer_id;
+        let second_buffer = storage.get_chunk(chunk_ids[2]).unwrap().unwrap().buffer_id;
+        storage.store_embedding(chunk_ids[0], &[1.0], None).unwrap();
+        storage.store_embedding(chunk_ids[3], &[1.0], None).unwrap();
+
+        assert_eq!(
+            storage.get_chunks_missing_embedding(None).unwrap(),
+            vec![chunk_ids[1], chunk_ids[2]]
+        );
+        assert_eq!(
+            storage
+                .get_chunks_missing_embedding(Some(first_buffer))
+                .unwrap(),
+            vec![chunk_ids[1]]
+        );
+        assert_eq!(storage.get_chunk_ids(None).unwrap(), chunk_ids);
+        assert_eq!(
+            storage.get_chunk_ids(Some(first_buffer)).unwrap(),
+            chunk_ids[..2]
+        );
+
+        storage.trash_buffer(second_buffer).unwrap();
+        assert_eq!(
+            storage.get_chunks_missing_embedding(None).unwrap(),
+            vec![chunk_ids[1]]
+        );
+        assert_eq!(storage.get_chunk_ids(None).unwrap(), chunk_ids[..2]);
+    }
+
+    #[test]
+    fn test_trash_buffer_lifecycle() {
+        let mut storage = setup();
+        let add = |storage: &mut SqliteStorage, name: &str, text: &str| {
+            let id = storage
+                .add_buffer(&Buffer::from_named(name.to_string(), text.to_string()))
+                .unwrap();
+            storage
+                .add_chunks(id, &[Chunk::new(id, text.to_string(), 0..text.len(), 0)])
+                .unwrap();
+            let chunk_id = storage.get_chunks(id).unwrap()[0].id.unwrap();
+            storage
+                .store_embedding(chunk_id, &[1.0, 0.0], None)
+                .unwrap();
+            id
+        };
+        let kept = add(&mut storage, "kept", "shared words here");
+        let trashed = add(&mut storage, "trashed", "shared words there");
+        let before = storage.embedding_fingerprint().unwrap();
+
+        storage.trash_buffer(trashed).unwrap();
+        assert!(storage.trash_buffer(trashed).is_err());
+
+        // Hidden from listings, counts, and search; still fetchable directly
+        let ids = |buffers: Vec<Buffer>| buffers.iter().filter_map(|b| b.id).collect::<Vec<_>>();
+        assert_eq!(ids(storage.list_buffers().unwrap()), vec![kept]);
+        assert_eq!(
+            ids(storage.list_buffers_including_deleted().unwrap()),
+            vec![kept, trashed]
+        );
+        assert_eq!(ids(storage.list_trash().unwrap()), vec![trashed]);
+        assert!(storage.get_buffer(trashed).unwrap().unwrap().is_deleted());
+        assert_eq!(storage.buffer_count().unwrap(), 1);
+        let stats = storage.stats().unwrap();
+        assert_eq!(stats.chunk_count, 1);
+        assert_eq!(stats.trashed_buffer_count, 1);
+        assert_eq!(storage.search_fts("shared", 10, false).unwrap().len(), 1);
+        assert_eq!(storage.get_all_embeddings().unwrap().len(), 1);
+        assert_ne!(storage.embedding_fingerprint().unwrap(), before);
+
+        storage.restore_buffer(trashed).unwrap();
+        assert!(storage.restore_buffer(trashed).is_err());
+        assert_eq!(storage.search_fts("shared", 10, false).unwrap().len(), 2);
+        assert_eq!(storage.embedding_fingerprint().unwrap(), before);
+
+        // Purging removes only trashed buffers, with their chunks and embeddings
+        storage.trash_buffer(trashed).unwrap();
+        assert_eq!(storage.purge_trash().unwrap(), vec![trashed]);
+        assert!(storage.get_buffer(trashed).unwrap().is_none());
+        assert_eq!(storage.embedding_count().unwrap(), 1);
+        assert!(storage.list_trash().unwrap().is_empty());
+        assert!(storage.purge_trash().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_source_encoding_round_trip() {
+        let mut storage = setup();
+        let mut buffer = Buffer::from_content("legacy".to_string());
+        buffer.metadata.source_encoding = Some("UTF-16LE".to_string());
+        buffer.metadata.line_ending = Some("CRLF".to_string());
+        let id = storage.add_buffer(&buffer).unwrap();
+
+        let mut stored = storage.get_buffer(id).unwrap().unwrap();
+        assert_eq!(stored.metadata.source_encoding.as_deref(), Some("UTF-16LE"));
+        assert_eq!(stored.metadata.line_ending.as_deref(), Some("CRLF"));
+
+        stored.metadata.source_encoding = None;
+        stored.metadata.line_ending = Some("LF".to_string());
+        storage.update_buffer(&stored).unwrap();
+        let listed = &storage.list_buffers().unwrap()[0];
+        assert_eq!(listed.metadata.source_encoding, None);
+        assert_eq!(listed.metadata.line_ending.as_deref(), Some("LF"));
+    }
+
+    #[test]
+    fn test_copy_embeddings_by_content_hash() {
+        let mut storage = setup();
+        let id = storage
+            .add_buffer(&Buffer::from_content("old".to_string()))
+            .unwrap();
+        storage
+            .add_chunks(id, &[Chunk::new(id, "same".to_string(), 0..4, 0)])
+            .unwrap();
+        let old_id = storage.get_chunk_ids(Some(id)).unwrap()[0];
+        storage
+            .store_embedding(old_id, &[0.6, 0.8], Some("model-a"))
+            .unwrap();
+
+        let chunks = vec![
+            Chunk::new(id, "same".to_string(), 0..4, 0),
+            Chunk::new(id, "changed".to_string(), 4..11, 1),
+        ];
+        storage.add_chunks(id, &chunks).unwrap();
+        assert_eq!(
+            storage
+                .copy_embeddings_by_content_hash(&[old_id], id)
+                .unwrap(),
+            1
+        );
+        storage.delete_chunks_by_id(&[old_id]).unwrap();
+
+        let new_ids = storage.get_chunk_ids(Some(id)).unwrap();
+        assert!(storage.get_embedding(new_ids[0]).unwrap().is_some());
+        assert!(storage.get_embedding(new_ids[1]).unwrap().is_none());
+        assert_eq!(
+            storage.get_embedding_models(id).unwrap(),
+            vec!["model-a".to_string()]
+        );
+
+        // Already embedded chunks are left alone
+        assert_eq!(
+            storage
+                .copy_embeddings_by_content_hash(&new_ids, id)
+                .unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_embedding_fingerprint() {
+        let mut storage = setup();
+        assert_eq!(
+            storage.embedding_fingerprint().unwrap(),
+            EmbeddingFingerprint::default()
+        );
+
+        let buffer_id = storage
+            .add_buffer(&Buffer::from_content("Hello, world!".to_string()))
+            .unwrap();
+        let chunks = vec![
+            Chunk::new(buffer_id, "Hello, ".to_string(), 0..7, 0),
+            Chunk::new(buffer_id, "world!".to_string(), 7..13, 1),
+        ];
+        storage.add_chunks(buffer_id, &chunks).unwrap();
+        let ids: Vec<i64> = storage
+            .get_chunks(buffer_id)
+            .unwrap()
+            .iter()
+            .filter_map(|c| c.id)
+            .collect();
+
+        storage.store_embedding(ids[0], &[1.0], None).unwrap();
+        let first = storage.embedding_fingerprint().unwrap();
+        assert_eq!(first.count, 1);
+        assert_eq!(first.max_chunk_id, ids[0]);
+
+        storage.store_embedding(ids[1], &[1.0], None).unwrap();
+        let second = storage.embedding_fingerprint().unwrap();
+        assert_ne!(first, second);
+        assert_eq!(second.max_chunk_id, ids[1]);
+
+        // Regenerating an embedding moves the latest timestamp
+        storage
+            .conn
+            .execute("UPDATE chunk_embeddings SET created_at = 100", [])
+            .unwrap();
+        let backdated = storage.embedding_fingerprint().unwrap();
+        storage.store_embedding(ids[0], &[0.5], None).unwrap();
+        assert_ne!(storage.embedding_fingerprint().unwrap(), backdated);
+    }
+
+    #[test]
+    fn test_sample_ids() {
+        let mut storage = setup();
+        let a = storage
+            .add_buffer(&Buffer::from_named("a".to_string(), "a".repeat(10)))
+            .unwrap();
+        let b = storage
+            .add_buffer(&Buffer::from_named("b".to_string(), "b".to_string()))
+            .unwrap();
+        let chunks: Vec<Chunk> = (0..10)
+            .map(|i| Chunk::new(a, "a".to_string(), i..i + 1, i))
+            .collect();
+        storage.add_chunks(a, &chunks).unwrap();
+        storage
+            .add_chunks(b, &[Chunk::new(b, "b".to_string(), 0..1, 0)])
+            .unwrap();
+
+        // Unseeded samples are drawn from the requested buffer
+        let sample = storage.sample_chunk_ids(Some(b), 5, None).unwrap();
+        assert_eq!(sample.len(), 1);
+        assert_eq!(storage.get_chunk(sample[0]).unwrap().unwrap().buffer_id, b);
+        assert_eq!(storage.sample_chunk_ids(None, 4, None).unwrap().len(), 4);
+
+        // Seeded samples are reproducible and distinct
+        let first = storage.sample_chunk_ids(None, 5, Some(7)).unwrap();
+        assert_eq!(first, storage.sample_chunk_ids(None, 5, Some(7)).unwrap());
+        let distinct: std::collections::HashSet<_> = first.iter().collect();
+        assert_eq!(distinct.len(), 5);
+        let all = storage.sample_chunk_ids(None, 100, Some(7)).unwrap();
+        assert_eq!(all.len(), 11);
+        assert_eq!(&all[..5], first.as_slice());
+
+        let mut buffers = storage.sample_buffer_ids(10, Some(1)).unwrap();
+        buffers.sort_unstable();
+        assert_eq!(buffers, vec![a, b]);
+    }
+
+    #[test]
+    fn test_embedding_coverage() {
+        let mut storage = setup();
+        assert!(storage.embedding_coverage().unwrap().is_empty());
+
+        let a = storage
+            .add_buffer(&Buffer::from_named("a".to_string(), "abc".to_string()))
+            .unwrap();
+        let empty = storage
+            .add_buffer(&Buffer::from_named("empty".to_string(), String::new()))
+            .unwrap();
+        storage
+            .add_chunks(
+                a,
+                &[
+                    Chunk::new(a, "a".to_string(), 0..1, 0),
+                    Chunk::new(a, "b".to_string(), 1..2, 1),
+                    Chunk::new(a, "c".to_string(), 2..3, 2),
+                ],
+            )
+            .unwrap();
+        let first = storage.get_chunks(a).unwrap()[0].id.unwrap();
+        storage.store_embedding(first, &[1.0], None).unwrap();
+
+        let coverage = storage.embedding_coverage().unwrap();
+        assert_eq!(
+            coverage,
+            vec![
+                EmbeddingCoverage {
+                    buffer_id: a,
+                    total_chunks: 3,
+                    embedded_chunks: 1,
+                },
+                EmbeddingCoverage {
+                    buffer_id: empty,
+                    total_chunks: 0,
+                    embedded_chunks: 0,
+                },
+            ]
+        );
+        assert!((coverage[0].percentage() - 100.0 / 3.0).abs() < 1e-9);
+        assert!((coverage[1].percentage() - 100.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_detailed_stats() {
+        let mut storage = setup();
+        let buffer_id = storage
+            .add_buffer(&Buffer::from_named(
+                "doc".to_string(),
+                "Hello, world!".to_string(),
+            ))
+            .unwrap();
+        let empty = storage
+            .add_buffer(&Buffer::from_content(String::new()))
+            .unwrap();
+        let chunks = vec![
+            Chunk::new(buffer_id, "Hello, ".to_string(), 0..7, 0),
+            Chunk::new(buffer_id, "world!".to_string(), 7..13, 1),
+        ];
+        storage.add_chunks(buffer_id, &chunks).unwrap();
+        let chunk_id = storage.get_chunks(buffer_id).unwrap()[0].id.unwrap();
+        storage
+            .store_embedding(chunk_id, &[0.5, 0.5, 0.5], Some("model-a"))
+            .unwrap();
+
+        let details = storage.detailed_stats().unwrap();
+        assert_eq!(details.embedded_chunks, 1);
+        assert_eq!(details.unembedded_chunks, 1);
+        assert_eq!(details.embedding_bytes, 12);
+        assert_eq!(details.models, vec!["model-a".to_string()]);
+        assert_eq!(details.buffers.len(), 2);
+
+        let doc = &details.buffers[0];
+        assert_eq!(doc.buffer_id, buffer_id);
+        assert_eq!(doc.name.as_deref(), Some("doc"));
+        assert_eq!(doc.chunk_count, 2);
+        assert_eq!(doc.embedded_chunks, 1);
+        assert_eq!(doc.unembedded_chunks, 1);
+        assert!((doc.avg_chunk_size - 6.5).abs() < f64::EPSILON);
+        assert_eq!(doc.embedding_bytes, 12);
+
+        assert_eq!(details.buffers[1].buffer_id, empty);
+        assert_eq!(details.buffers[1].chunk_count, 0);
+        assert!(details.buffers[1].avg_chunk_size.abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_storage_usage() {
+        let mut storage = setup();
+        let buffer_id = storage
+            .add_buffer(&Buffer::from_content("Hello, world!".to_string()))
+            .unwrap();
+        let chunks = vec![
+            Chunk::new(buffer_id, "Hello, ".to_string(), 0..7, 0),
+            Chunk::new(buffer_id, "world!".to_string(), 7..13, 1),
+        ];
+        storage.add_chunks(buffer_id, &chunks).unwrap();
+        let chunk_id = storage.get_chunks(buffer_id).unwrap()[0].id.unwrap();
+        storage
+            .store_embedding(chunk_id, &[0.5, 0.5, 0.5], None)
+            .unwrap();
+
+        let usage = storage.storage_usage().unwrap();
+        assert_eq!(usage.buffer_content_bytes, 13);
+        assert_eq!(usage.chunk_content_bytes, 13);
+        assert_eq!(usage.embedding_bytes, 12);
+        assert_eq!(usage.embedding_count, 1);
+        assert!(usage.db_bytes > 0);
+        if let Some(fts_bytes) = usage.fts_bytes {
+            assert!(fts_bytes > 0);
+            assert!(usage.tables.iter().any(|t| t.name == "chunks"));
+        }
+    }
+
+    #[test]
+    fn test_embeddings_with_other_dimensions() {
+        let mut storage = setup();
+        let buffer_id = storage
+            .add_buffer(&Buffer::from_content("abc".to_string()))
+            .unwrap();
+        let chunks = vec![
+            Chunk::new(buffer_id, "a".to_string(), 0..1, 0),
+            Chunk::new(buffer_id, "b".to_string(), 1..2, 1),
+            Chunk::new(buffer_id, "c".to_string(), 2..3, 2),
+        ];
+        storage.add_chunks(buffer_id, &chunks).unwrap();
+        let ids: Vec<i64> = storage
+            .get_chunks(buffer_id)
+            .unwrap()
+            .iter()
+            .filter_map(|c| c.id)
+            .collect();
+        storage.store_embedding(ids[0], &[1.0, 0.0], None).unwrap();
+        storage.store_embedding(ids[1], &[1.0], None).unwrap();
+        storage
+            .store_embedding(ids[2], &[0.0, 1.0, 0.0], None)
+            .unwrap();
+
+        assert_eq!(
+            storage.get_chunks_with_other_dimensions(2).unwrap(),
+            vec![ids[1], ids[2]]
+        );
+        assert_eq!(
+            storage.delete_embeddings_with_other_dimensions(2).unwrap(),
+            2
+        );
+        assert!(
+            storage
+                .get_chunks_with_other_dimensions(2)
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(storage.embedding_count().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_get_drifted_embeddings() {
+        let mut storage = setup();
+        let buffer_id = storage
+            .add_buffer(&Buffer::from_content("abc".to_string()))
+            .unwrap();
+        let chunks = vec![
+            Chunk::new(buffer_id, "a".to_string(), 0..1, 0),
+            Chunk::new(buffer_id, "b".to_string(), 1..2, 1),
+            Chunk::new(buffer_id, "c".to_string(), 2..3, 2),
+        ];
+        storage.add_chunks(buffer_id, &chunks).unwrap();
+        let ids: Vec<i64> = storage
+            .get_chunks(buffer_id)
+            .unwrap()
+            .iter()
+            .filter_map(|c| c.id)
+            .collect();
+        storage
+            .store_embedding(ids[0], &[1.0, 0.0], Some("current"))
+            .unwrap();
+        storage
+            .store_embedding(ids[1], &[0.0, 1.0], Some("old"))
+            .unwrap();
+        storage
+            .store_embedding(ids[2], &[0.0, 1.0, 0.0], Some("current"))
+            .unwrap();
 
-        // Deduplicate (in case of overlap, though shouldn't happen)
-        results.sort_unstable();
-        results.dedup();
-        Ok(results)
+        // Dimensions only
+        assert_eq!(
+            storage.get_drifted_embeddings(2, None).unwrap(),
+            vec![(ids[2], buffer_id, Some("current".to_string()))]
+        );
+        // Dimensions or model
+        assert_eq!(
+            storage.get_drifted_embeddings(2, Some("current")).unwrap(),
+            vec![
+                (ids[1], buffer_id, Some("old".to_string())),
+                (ids[2], buffer_id, Some("current".to_string())),
+            ]
+        );
     }
 
-    /// Gets chunks without any embedding for a buffer.
-    ///
-    /// Simpler version of `get_chunks_needing_embedding` when model doesn't matter.
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if the query fails.
-    pub fn get_chunks_without_embedding(&self, buffer_id: i64) -> Result<Vec<i64>> {
-        self.get_chunks_needing_embedding(buffer_id, None)
+    #[test]
+    fn test_store_embedding_validates_dimensions() {
+        let mut storage = setup();
+        let buffer_id = storage
+            .add_buffer(&Buffer::from_content("ab".to_string()))
+            .unwrap();
+        let chunks = vec![
+            Chunk::new(buffer_id, "a".to_string(), 0..1, 0),
+            Chunk::new(buffer_id, "b".to_string(), 1..2, 1),
+        ];
+        storage.add_chunks(buffer_id, &chunks).unwrap();
+        let ids: Vec<i64> = storage
+            .get_chunks(buffer_id)
+            .unwrap()
+            .iter()
+            .filter_map(|c| c.id)
+            .collect();
+
+        storage.set_embedding_dimensions(Some(2));
+        storage.store_embedding(ids[0], &[1.0, 0.0], None).unwrap();
+        let err = storage
+            .store_embedding(ids[1], &[1.0, 0.0, 0.0], None)
+            .unwrap_err();
+        assert!(err.to_string().contains("has 3 dimensions, expected 2"));
+
+        // A bad vector anywhere in a batch rejects the whole batch
+        let batch = vec![(ids[0], vec![0.0, 1.0]), (ids[1], vec![1.0])];
+        assert!(storage.store_embeddings_batch(&batch, None).is_err());
+        assert_eq!(storage.get_embedding(ids[0]).unwrap(), Some(vec![1.0, 0.0]));
+        assert_eq!(storage.get_embedding(ids[1]).unwrap(), None);
+
+        storage.set_embedding_dimensions(None);
+        storage.store_embedding(ids[1], &[1.0], None).unwrap();
+        assert_eq!(storage.embedding_count().unwrap(), 2);
     }
 
-    /// Deletes embeddings with a specific model name.
-    ///
-    /// Useful for cleaning up embeddings from old models before re-embedding.
-    ///
-    /// # Arguments
-    ///
-    /// * `buffer_id` - The buffer to clean.
-    /// * `model_name` - The model name to match (or None to match NULL).
-    ///
-    /// # Returns
-    ///
-    /// The number of embeddings deleted.
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if deletion fails.
-    pub fn delete_embeddings_by_model(
-        &mut self,
-        buffer_id: i64,
-        model_name: Option<&str>,
-    ) -> Result<usize> {
-        let deleted = match model_name {
-            Some(name) => self
-                .conn
-                .execute(
-                    r"
-                    DELETE FROM chunk_embeddings
-                    WHERE chunk_id IN (
-                        SELECT id FROM chunks WHERE buffer_id = ?
-                    ) AND model_name = ?
-                    ",
-                    params![buffer_id, name],
-                )
-                .map_err(StorageError::from)?,
-            None => self
-                .conn
-                .execute(
-                    r"
-                    DELETE FROM chunk_embeddings
-                    WHERE chunk_id IN (
-                        SELECT id FROM chunks WHERE buffer_id = ?
-                    ) AND model_name IS NULL
-                    ",
-                    params![buffer_id],
-                )
-                .map_err(StorageError::from)?,
-        };
-        Ok(deleted)
+    #[test]
+    fn test_store_embedding_normalizes() {
+        let mut storage = setup();
+        let buffer_id = storage
+            .add_buffer(&Buffer::from_content("ab".to_string()))
+            .unwrap();
+        let chunks = vec![
+            Chunk::new(buffer_id, "a".to_string(), 0..1, 0),
+            Chunk::new(buffer_id, "b".to_string(), 1..2, 1),
+        ];
+        storage.add_chunks(buffer_id, &chunks).unwrap();
+        let ids: Vec<i64> = storage
+            .get_chunks(buffer_id)
+            .unwrap()
+            .iter()
+            .filter_map(|c| c.id)
+            .collect();
+        let close = |a: &[f32], b: &[f32]| a.iter().zip(b).all(|(x, y)| (x - y).abs() < 1e-6);
+
+        // Off by default: vectors are stored as given, and flagged by length
+        assert!(!storage.normalize_embeddings());
+        storage.store_embedding(ids[0], &[1.0, 0.0], None).unwrap();
+        assert!(storage.embeddings_normalized().unwrap());
+        storage.store_embedding(ids[1], &[3.0, 4.0], None).unwrap();
+        assert_eq!(storage.get_embedding(ids[1]).unwrap(), Some(vec![3.0, 4.0]));
+        assert!(!storage.embeddings_normalized().unwrap());
+
+        storage.set_normalize_embeddings(true);
+        storage.store_embedding(ids[1], &[3.0, 4.0], None).unwrap();
+        assert!(close(
+            &storage.get_embedding(ids[1]).unwrap().unwrap(),
+            &[0.6, 0.8]
+        ));
+        assert!(storage.embeddings_normalized().unwrap());
+
+        let batch = vec![(ids[0], vec![0.0, 2.0]), (ids[1], vec![5.0, 0.0])];
+        storage.store_embeddings_batch(&batch, None).unwrap();
+        assert!(close(
+            &storage.get_embedding(ids[0]).unwrap().unwrap(),
+            &[0.0, 1.0]
+        ));
+        assert!(close(
+            &storage.get_embedding(ids[1]).unwrap().unwrap(),
+            &[1.0, 0.0]
+        ));
+        assert!(storage.embeddings_normalized().unwrap());
     }
 
-    /// Gets embedding statistics for a buffer.
-    ///
-    /// Returns counts of embedded vs total chunks, and model breakdown.
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if the query fails.
-    pub fn get_embedding_stats(&self, buffer_id: i64) -> Result<EmbeddingStats> {
-        // Total chunks
-        let total_chunks: i64 = self
+    #[test]
+    fn test_get_chunks_embedded_before() {
+        let mut storage = setup();
+        let buffer_id = storage
+            .add_buffer(&Buffer::from_content("Hello, world!".to_string()))
+            .unwrap();
+        let chunks = vec![
+            Chunk::new(buffer_id, "Hello, ".to_string(), 0..7, 0),
+            Chunk::new(buffer_id, "world!".to_string(), 7..13, 1),
+        ];
+        storage.add_chunks(buffer_id, &chunks).unwrap();
+        let ids: Vec<i64> = storage
+            .get_chunks(buffer_id)
+            .unwrap()
+            .iter()
+            .filter_map(|c| c.id)
+            .collect();
+        storage.store_embedding(ids[0], &[1.0], None).unwrap();
+        storage
             .conn
-            .query_row(
-                "SELECT COUNT(*) FROM chunks WHERE buffer_id = ?",
-                params![buffer_id],
-                |row| row.get(0),
+            .execute(
+                "UPDATE chunk_embeddings SET created_at = 100 WHERE chunk_id = ?",
+                params![ids[0]],
             )
-            .map_err(StorageError::from)?;
+            .unwrap();
 
-        // Embedded chunks
-        let embedded_chunks: i64 = self
-            .conn
-            .query_row(
-                r"
-                SELECT COUNT(*) FROM chunk_embeddings e
-                INNER JOIN chunks c ON e.chunk_id = c.id
-                WHERE c.buffer_id = ?
-                ",
-                params![buffer_id],
-                |row| row.get(0),
-            )
-            .map_err(StorageError::from)?;
+        assert_eq!(
+            storage.get_chunks_embedded_before(buffer_id, 200).unwrap(),
+            vec![ids[0]]
+        );
+        // Unembedded chunks are never stale
+        assert!(
+            storage
+                .get_chunks_embedded_before(buffer_id, 50)
+                .unwrap()
+                .is_empty()
+        );
+    }
 
-        // Model counts
-        let model_counts = self.get_embedding_model_counts(buffer_id)?;
+    #[test]
+    fn test_compact_reclaims_space() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("compact.db");
+        let mut storage = SqliteStorage::open(&path).unwrap();
+        storage.init().unwrap();
 
-        Ok(EmbeddingStats {
-            total_chunks: total_chunks as usize,
-            embedded_chunks: embedded_chunks as usize,
-            model_counts,
-        })
+        let buffer_id = storage
+            .add_buffer(&Buffer::from_content("x".repeat(1024 * 1024)))
+            .unwrap();
+        storage.compact().unwrap();
+        let before = storage.stats().unwrap().db_size.unwrap();
+
+        storage.delete_buffer(buffer_id).unwrap();
+        storage.compact().unwrap();
+        let after = storage.stats().unwrap().db_size.unwrap();
+        assert!(after < before / 2, "{after} >= {before} / 2");
     }
-}
 
-/// Statistics about embeddings for a buffer.
-#[derive(Debug, Clone)]
-pub struct EmbeddingStats {
-    /// Total number of chunks in the buffer.
-    pub total_chunks: usize,
-    /// Number of chunks with embeddings.
-    pub embedded_chunks: usize,
-    /// Count of embeddings by model (`model_name`, count).
-    pub model_counts: Vec<(Option<String>, i64)>,
-}
+    #[test]
+    fn test_compact_rejects_open_transaction() {
+        let mut storage = setup();
+        storage.conn.execute_batch("BEGIN;").unwrap();
+        assert!(storage.compact().is_err());
+        storage.conn.execute_batch("ROLLBACK;").unwrap();
+        assert!(storage.compact().is_ok());
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::core::ContextValue;
+    #[test]
+    fn test_analyze() {
+        let mut storage = setup();
+        let buffer_id = storage
+            .add_buffer(&Buffer::from_content("Hello, world!".to_string()))
+            .unwrap();
+        let chunks = vec![Chunk::new(buffer_id, "Hello".to_string(), 0..5, 0)];
+        storage.add_chunks(buffer_id, &chunks).unwrap();
 
-    fn setup() -> SqliteStorage {
-        let mut storage = SqliteStorage::in_memory().unwrap();
-        storage.init().unwrap();
-        storage
+        let analyzed = storage.analyze().unwrap();
+        assert!(analyzed > 0);
+
+        // Running again is harmless
+        assert!(storage.analyze().is_ok());
     }
 
     #[test]
-    fn test_init() {
-        let mut storage = SqliteStorage::in_memory().unwrap();
-        assert!(storage.init().is_ok());
-        assert!(storage.is_initialized().unwrap());
+    fn test_journal_mode_parse() {
+        assert_eq!(JournalMode::parse("WAL"), Some(JournalMode::Wal));
+        assert_eq!(JournalMode::parse("delete"), Some(JournalMode::Delete));
+        assert_eq!(JournalMode::parse("memory"), Some(JournalMode::Memory));
+        assert_eq!(JournalMode::parse("truncate"), None);
+        assert_eq!(JournalMode::default(), JournalMode::Wal);
     }
 
     #[test]
@@ -1219,6 +5289,236 @@ mod tests {
         assert_eq!(storage.chunk_count(buffer_id).unwrap(), 0);
     }
 
+    #[test]
+    fn test_chunk_counts_maintained_by_triggers() {
+        let mut storage = setup();
+        let stored_count =
+            |s: &SqliteStorage, id| s.get_buffer(id).unwrap().unwrap().metadata.chunk_count;
+
+        // A stale count on a new buffer is ignored; unchunked buffers have none
+        let mut buffer = Buffer::from_named("a".to_string(), "aaaa".to_string());
+        buffer.set_chunk_count(7);
+        let a = storage.add_buffer(&buffer).unwrap();
+        let b = storage
+            .add_buffer(&Buffer::from_named("b".to_string(), "bb".to_string()))
+            .unwrap();
+        assert_eq!(stored_count(&storage, a), None);
+
+        let chunk = |id, i: usize| Chunk::new(id, "x".to_string(), i..i + 1, i);
+        storage.add_chunks(a, &[chunk(a, 0), chunk(a, 1)]).unwrap();
+        storage.add_chunks(a, &[chunk(a, 2)]).unwrap();
+        storage.add_chunks(b, &[chunk(b, 0)]).unwrap();
+        assert_eq!(stored_count(&storage, a), Some(3));
+        assert_eq!(stored_count(&storage, b), Some(1));
+
+        // Writing a buffer back never clobbers its count
+        let mut stale = storage.get_buffer(a).unwrap().unwrap();
+        stale.set_chunk_count(99);
+        storage.update_buffer(&stale).unwrap();
+        assert_eq!(stored_count(&storage, a), Some(3));
+
+        let first = storage.get_chunks(a).unwrap()[0].id.unwrap();
+        assert_eq!(storage.delete_chunks_by_id(&[first]).unwrap(), 1);
+        assert_eq!(stored_count(&storage, a), Some(2));
+
+        // Raw SQL edits are counted too
+        storage
+            .conn
+            .execute("DELETE FROM chunks WHERE buffer_id = ?", params![b])
+            .unwrap();
+        assert_eq!(stored_count(&storage, b), Some(0));
+
+        storage.delete_chunks(a).unwrap();
+        assert_eq!(stored_count(&storage, a), Some(0));
+
+        // The FTS index follows the same inserts and deletes
+        storage
+            .add_chunks(b, &[Chunk::new(b, "needle".to_string(), 0..2, 0)])
+            .unwrap();
+        assert_eq!(storage.search_fts("needle", 10, false).unwrap().len(), 1);
+        storage.delete_chunks(b).unwrap();
+        assert!(storage.search_fts("needle", 10, false).unwrap().is_empty());
+
+        // Recomputing agrees with the triggers
+        assert_eq!(storage.recompute_chunk_counts().unwrap(), 2);
+        assert_eq!(stored_count(&storage, a), Some(0));
+    }
+
+    #[test]
+    fn test_deferred_chunk_counts() {
+        let mut storage = setup();
+        assert!(storage.auto_chunk_count());
+
+        let a = storage
+            .add_buffer(&Buffer::from_named("a".to_string(), "aaaa".to_string()))
+            .unwrap();
+        let b = storage
+            .add_buffer(&Buffer::from_named("b".to_string(), "bb".to_string()))
+            .unwrap();
+        storage
+            .add_chunks(a, &[Chunk::new(a, "aa".to_string(), 0..2, 0)])
+            .unwrap();
+        let stored_count =
+            |s: &SqliteStorage, id| s.get_buffer(id).unwrap().unwrap().metadata.chunk_count;
+        assert_eq!(stored_count(&storage, a), Some(1));
+
+        storage.set_auto_chunk_count(false);
+        storage.delete_chunks(a).unwrap();
+        storage
+            .add_chunks(
+                b,
+                &[
+                    Chunk::new(b, "b".to_string(), 0..1, 0),
+                    Chunk::new(b, "b".to_string(), 1..2, 1),
+                ],
+            )
+            .unwrap();
+        // Stored counts are stale until recomputed
+        assert_eq!(stored_count(&storage, a), Some(1));
+        assert_eq!(stored_count(&storage, b), None);
+
+        // The triggers resume for other write paths
+        let paused: i64 = storage
+            .conn
+            .query_row(
+                "SELECT COUNT(*) FROM metadata WHERE key = 'defer_chunk_count'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(paused, 0);
+
+        assert_eq!(storage.recompute_chunk_counts().unwrap(), 2);
+        assert_eq!(stored_count(&storage, a), Some(0));
+        assert_eq!(stored_count(&storage, b), Some(2));
+
+        storage.set_auto_chunk_count(true);
+        storage
+            .add_chunks(a, &[Chunk::new(a, "aa".to_string(), 0..2, 0)])
+            .unwrap();
+        assert_eq!(stored_count(&storage, a), Some(1));
+    }
+
+    #[test]
+    fn test_get_chunk_neighbors() {
+        let mut storage = setup();
+
+        let buffer = Buffer::from_content("abcdefg".to_string());
+        let buffer_id = storage.add_buffer(&buffer).unwrap();
+        let other = Buffer::from_content("Other".to_string());
+        let other_id = storage.add_buffer(&other).unwrap();
+
+        let chunks: Vec<Chunk> = "abcdefg"
+            .chars()
+            .enumerate()
+            .map(|(i, c)| Chunk::new(buffer_id, c.to_string(), i..i + 1, i))
+            .collect();
+        storage.add_chunks(buffer_id, &chunks).unwrap();
+        storage
+            .add_chunks(
+                other_id,
+                &[Chunk::new(other_id, "Other".to_string(), 0..5, 4)],
+            )
+            .unwrap();
+        let ids: Vec<i64> = storage
+            .get_chunks(buffer_id)
+            .unwrap()
+            .iter()
+            .filter_map(|c| c.id)
+            .collect();
+        let contents =
+            |chunks: Vec<Chunk>| chunks.into_iter().map(|c| c.content).collect::<String>();
+
+        assert_eq!(
+            contents(storage.get_chunk_neighbors(ids[3], 1).unwrap()),
+            "cde"
+        );
+        assert_eq!(
+            contents(storage.get_chunk_neighbors(ids[3], 0).unwrap()),
+            "d"
+        );
+        // Clamped at the buffer's edges, never crossing into another buffer
+        assert_eq!(
+            contents(storage.get_chunk_neighbors(ids[0], 2).unwrap()),
+            "abc"
+        );
+        assert_eq!(
+            contents(storage.get_chunk_neighbors(ids[6], usize::MAX).unwrap()),
+            "abcdefg"
+        );
+
+        // Missing indices are skipped
+        storage
+            .conn
+            .execute("DELETE FROM chunks WHERE id = ?", params![ids[2]])
+            .unwrap();
+        assert_eq!(
+            contents(storage.get_chunk_neighbors(ids[3], 1).unwrap()),
+            "de"
+        );
+
+        assert!(storage.get_chunk_neighbors(9999, 1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_get_adjacent_chunk() {
+        let mut storage = setup();
+
+        let buffer = Buffer::from_content("Hello, world!".to_string());
+        let buffer_id = storage.add_buffer(&buffer).unwrap();
+        let other = Buffer::from_content("Other".to_string());
+        let other_id = storage.add_buffer(&other).unwrap();
+
+        let chunks = vec![
+            Chunk::new(buffer_id, "Hello".to_string(), 0..5, 0),
+            Chunk::new(buffer_id, ", ".to_string(), 5..7, 1),
+            Chunk::new(buffer_id, "world!".to_string(), 7..13, 2),
+        ];
+        storage.add_chunks(buffer_id, &chunks).unwrap();
+        storage
+            .add_chunks(
+                other_id,
+                &[Chunk::new(other_id, "Other".to_string(), 0..5, 3)],
+            )
+            .unwrap();
+
+        let loaded = storage.get_chunks(buffer_id).unwrap();
+        let first = loaded[0].id.unwrap();
+        let middle = loaded[1].id.unwrap();
+        let last = loaded[2].id.unwrap();
+
+        let next = storage
+            .get_adjacent_chunk(middle, ChunkDirection::Next)
+            .unwrap()
+            .unwrap();
+        assert_eq!(next.id, Some(last));
+        let prev = storage
+            .get_adjacent_chunk(middle, ChunkDirection::Prev)
+            .unwrap()
+            .unwrap();
+        assert_eq!(prev.id, Some(first));
+
+        // Buffer edges, and never crosses into another buffer
+        assert!(
+            storage
+                .get_adjacent_chunk(first, ChunkDirection::Prev)
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            storage
+                .get_adjacent_chunk(last, ChunkDirection::Next)
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            storage
+                .get_adjacent_chunk(9999, ChunkDirection::Next)
+                .unwrap()
+                .is_none()
+        );
+    }
+
     #[test]
     fn test_cascade_delete() {
         let mut storage = setup();
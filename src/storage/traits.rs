@@ -93,7 +93,7 @@ pub trait Storage {
     /// Returns an error if the query fails.
     fn get_buffer_by_name(&self, name: &str) -> Result<Option<Buffer>>;
 
-    /// Lists all buffers.
+    /// Lists all buffers, except those moved to the trash.
     ///
     /// # Errors
     ///
@@ -116,7 +116,7 @@ pub trait Storage {
     /// Returns an error if deletion fails.
     fn delete_buffer(&mut self, id: i64) -> Result<()>;
 
-    /// Returns the count of buffers.
+    /// Returns the count of buffers, except those moved to the trash.
     ///
     /// # Errors
     ///
@@ -148,6 +148,17 @@ pub trait Storage {
     /// Returns an error if the query fails.
     fn get_chunk(&self, id: i64) -> Result<Option<Chunk>>;
 
+    /// Retrieves the chunk adjacent to `id` within the same buffer.
+    ///
+    /// Returns `None` if `id` does not exist or it is the first (for
+    /// [`ChunkDirection::Prev`]) or last (for [`ChunkDirection::Next`])
+    /// chunk in its buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    fn get_adjacent_chunk(&self, id: i64, direction: ChunkDirection) -> Result<Option<Chunk>>;
+
     /// Deletes all chunks for a buffer.
     ///
     /// # Errors
@@ -179,21 +190,45 @@ pub trait Storage {
     ///
     /// Returns an error if statistics cannot be gathered.
     fn stats(&self) -> Result<StorageStats>;
+
+    /// Reclaims space left behind by deletions.
+    ///
+    /// Rebuilds the database without free pages and truncates any
+    /// write-ahead log. Must not be called inside an open transaction.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a transaction is open or compaction fails.
+    fn compact(&mut self) -> Result<()>;
+}
+
+/// Direction for navigating between chunks of a buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkDirection {
+    /// The chunk with the next higher index.
+    Next,
+    /// The chunk with the next lower index.
+    Prev,
 }
 
 /// Storage statistics.
 #[derive(Debug, Clone, Default, Serialize)]
 pub struct StorageStats {
-    /// Number of buffers stored.
+    /// Number of buffers stored, excluding the trash.
     pub buffer_count: usize,
-    /// Total number of chunks across all buffers.
+    /// Total number of chunks across all buffers, excluding the trash.
     pub chunk_count: usize,
-    /// Total size of all buffer content in bytes.
+    /// Total size of all buffer content in bytes, excluding the trash.
     pub total_content_size: usize,
     /// Whether a context is stored.
     pub has_context: bool,
     /// Schema version.
     pub schema_version: u32,
+    /// Corpus version, bumped on every buffer, chunk, or embedding change
+    /// (0 before schema v8).
+    pub corpus_version: i64,
+    /// Number of buffers in the trash.
+    pub trashed_buffer_count: usize,
     /// Database file size in bytes (if applicable).
     pub db_size: Option<u64>,
 }
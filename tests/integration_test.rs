@@ -367,16 +367,20 @@ mod search_tests {
             .expect("add_chunks failed");
 
         // Search for "fox"
-        let results = storage.search_fts("fox", 10).expect("search_fts failed");
+        let results = storage
+            .search_fts("fox", 10, false)
+            .expect("search_fts failed");
         assert!(!results.is_empty());
 
         // Search for "Rust"
-        let results = storage.search_fts("Rust", 10).expect("search_fts failed");
+        let results = storage
+            .search_fts("Rust", 10, false)
+            .expect("search_fts failed");
         assert!(!results.is_empty());
 
         // Search for non-existent term
         let results = storage
-            .search_fts("nonexistent", 10)
+            .search_fts("nonexistent", 10, false)
             .expect("search_fts failed");
         assert!(results.is_empty());
     }
@@ -682,8 +686,11 @@ mod property_tests {
 
 /// CLI command integration tests.
 mod cli_tests {
-    use rlm_rs::cli::commands::execute;
-    use rlm_rs::cli::parser::{ChunkCommands, Cli, Commands};
+    use rlm_rs::cli::Config;
+    use rlm_rs::cli::commands::{execute, execute_to};
+    use rlm_rs::cli::parser::{ChunkCommands, Cli, Commands, ConfigCommands};
+    use rlm_rs::embedding::{DEFAULT_DIMENSIONS, DEFAULT_EMBED_BATCH_SIZE};
+    use rlm_rs::storage::DEFAULT_KEEP_VERSIONS;
     use std::path::PathBuf;
     use tempfile::TempDir;
 
@@ -693,6 +700,15 @@ mod cli_tests {
             db_path: Some(db_path),
             verbose: false,
             format: "text".to_string(),
+            null: false,
+            journal: "wal".to_string(),
+            cache_size: None,
+            mmap_size: None,
+            embed_batch_size: DEFAULT_EMBED_BATCH_SIZE,
+            threads: None,
+            embedding_dim: None,
+            tee: None,
+            config: Config::default(),
             command,
         }
     }
@@ -703,6 +719,15 @@ mod cli_tests {
             db_path: Some(db_path),
             verbose: false,
             format: "json".to_string(),
+            null: false,
+            journal: "wal".to_string(),
+            cache_size: None,
+            mmap_size: None,
+            embed_batch_size: DEFAULT_EMBED_BATCH_SIZE,
+            threads: None,
+            embedding_dim: None,
+            tee: None,
+            config: Config::default(),
             command,
         }
     }
@@ -719,6 +744,246 @@ mod cli_tests {
         assert!(db_path.exists());
     }
 
+    #[test]
+    fn test_cmd_journal_mode_delete() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+
+        let mut cli = make_cli(db_path.clone(), Commands::Init { force: false });
+        cli.journal = "delete".to_string();
+        execute(&cli).expect("init");
+
+        let mut cli = make_cli(db_path, Commands::Status { detailed: false });
+        cli.journal = "delete".to_string();
+        execute(&cli).expect("status");
+
+        // Rollback journal mode leaves no WAL side file behind
+        assert!(!temp_dir.path().join("test.db-wal").exists());
+    }
+
+    #[test]
+    fn test_cmd_analyze() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+
+        let output = execute(&make_cli_json(db_path, Commands::Analyze)).expect("analyze");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["analyzed"], true);
+    }
+
+    #[test]
+    fn test_cmd_compact() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+
+        let output = execute(&make_cli_json(db_path, Commands::Compact)).expect("compact");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        let before = json["bytes_before"].as_u64().expect("bytes_before");
+        let reclaimed = json["bytes_reclaimed"].as_u64().expect("bytes_reclaimed");
+        assert!(reclaimed <= before);
+    }
+
+    #[test]
+    fn test_cmd_fix_dimensions() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let file_path = temp_dir.path().join("content.txt");
+        std::fs::write(&file_path, "Dimension repair content").expect("write file");
+
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        execute(&make_cli(
+            db_path.clone(),
+            Commands::Load {
+                file: file_path,
+                name: Some("dims".to_string()),
+                chunker: "fixed".to_string(),
+                chunk_size: 1000,
+                overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
+            },
+        ))
+        .expect("load");
+
+        let fix = |reembed| {
+            let cli = make_cli_json(
+                db_path.clone(),
+                Commands::FixDimensions {
+                    target_dim: None,
+                    reembed,
+                },
+            );
+            let output = execute(&cli).expect("fix-dimensions");
+            serde_json::from_str::<serde_json::Value>(&output).expect("json")
+        };
+        let shrink = || {
+            let conn = rusqlite::Connection::open(&db_path).expect("open db");
+            conn.execute(
+                "UPDATE chunk_embeddings SET embedding = zeroblob(8), dimensions = 2 WHERE chunk_id = 1",
+                [],
+            )
+            .expect("shrink");
+        };
+
+        // Freshly loaded embeddings already match
+        assert_eq!(fix(false)["mismatched"], 0);
+
+        shrink();
+        let json = fix(true);
+        assert_eq!(json["mismatched"], 1);
+        assert_eq!(json["reembedded"], 1);
+        assert_eq!(json["deleted"], 0);
+        assert_eq!(fix(false)["mismatched"], 0);
+
+        shrink();
+        let json = fix(false);
+        assert_eq!(json["mismatched"], 1);
+        assert_eq!(json["deleted"], 1);
+        assert_eq!(fix(false)["mismatched"], 0);
+
+        // Re-embedding can't produce a different target size
+        let cli = make_cli(
+            db_path,
+            Commands::FixDimensions {
+                target_dim: Some(2),
+                reembed: true,
+            },
+        );
+        assert!(execute(&cli).is_err());
+    }
+
+    #[test]
+    fn test_cmd_reembed_all() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let file_path = temp_dir.path().join("content.txt");
+        std::fs::write(&file_path, "Model drift content").expect("write file");
+
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        execute(&make_cli(
+            db_path.clone(),
+            Commands::Load {
+                file: file_path,
+                name: Some("drift".to_string()),
+                chunker: "fixed".to_string(),
+                chunk_size: 1000,
+                overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
+            },
+        ))
+        .expect("load");
+
+        let reembed = |model_check| {
+            let cli = make_cli_json(db_path.clone(), Commands::ReembedAll { model_check });
+            let output = execute(&cli).expect("reembed-all");
+            serde_json::from_str::<serde_json::Value>(&output).expect("json")
+        };
+        let drift = |sql: &str| {
+            let conn = rusqlite::Connection::open(&db_path).expect("open db");
+            conn.execute(sql, []).expect("drift");
+        };
+
+        assert_eq!(reembed(true)["mismatched"], 0);
+
+        drift(
+            "UPDATE chunk_embeddings SET embedding = zeroblob(8), dimensions = 2, model_name = 'tiny' WHERE chunk_id = 1",
+        );
+        let json = reembed(false);
+        assert_eq!(json["mismatched"], 1);
+        assert_eq!(json["regenerated"], 1);
+        assert_eq!(json["buffers"], 1);
+        assert_eq!(json["by_model"][0]["model"], "tiny");
+        assert_eq!(json["by_model"][0]["regenerated"], 1);
+        assert_eq!(json["failed"].as_array().map(Vec::len), Some(0));
+
+        // Same-size vectors from another model only count with --model-check
+        drift("UPDATE chunk_embeddings SET model_name = 'other-model' WHERE chunk_id = 1");
+        assert_eq!(reembed(false)["mismatched"], 0);
+        let json = reembed(true);
+        assert_eq!(json["mismatched"], 1);
+        assert_eq!(json["by_model"][0]["model"], "other-model");
+        assert_eq!(reembed(true)["mismatched"], 0);
+    }
+
+    #[test]
+    fn test_cmd_embedding_dim_override() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        let load = |name: &str, dims: usize| {
+            let file_path = temp_dir.path().join(format!("{name}.txt"));
+            std::fs::write(&file_path, "Embedding dimension content").expect("write file");
+            let mut cli = make_cli(
+                db_path.clone(),
+                Commands::Load {
+                    file: file_path,
+                    name: Some(name.to_string()),
+                    chunker: "fixed".to_string(),
+                    chunk_size: 1000,
+                    overlap: 0,
+                    chunk_unit: "chars".to_string(),
+                    encoding: None,
+                    normalize_whitespace: false,
+                },
+            );
+            cli.embedding_dim = Some(dims);
+            execute(&cli)
+        };
+        let stored_dims = || {
+            let conn = rusqlite::Connection::open(&db_path).expect("open db");
+            let mut stmt = conn
+                .prepare("SELECT DISTINCT dimensions FROM chunk_embeddings ORDER BY dimensions")
+                .expect("prepare");
+            stmt.query_map([], |row| row.get::<_, i64>(0))
+                .expect("query")
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .expect("dims")
+        };
+
+        let native = i64::try_from(DEFAULT_DIMENSIONS).expect("dimensions fit in i64");
+        load("native", DEFAULT_DIMENSIONS).expect("load at native size");
+        assert_eq!(stored_dims(), vec![native]);
+
+        // Resizable embedders honor the override; fixed-size ones refuse it
+        match load("custom", 64) {
+            Ok(_) => assert_eq!(stored_dims(), vec![64, native]),
+            Err(e) => assert!(e.to_string().contains("expected 64"), "{e}"),
+        }
+    }
+
+    #[test]
+    fn test_cmd_explain_chunking() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let file_path = temp_dir.path().join("content.txt");
+        std::fs::write(&file_path, "AAAAAAAAAA\nBBBBBBBBBB\nCCCCCCCCCC\n").expect("write file");
+
+        let output = execute(&make_cli_json(
+            db_path.clone(),
+            Commands::ExplainChunking {
+                file: file_path,
+                chunker: "fixed".to_string(),
+                chunk_size: 11,
+                overlap: 0,
+            },
+        ))
+        .expect("explain-chunking");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert!(json["chunk_count"].as_u64().expect("count") >= 2);
+        assert_eq!(json["chunks"][0]["line_range"]["start"], 1);
+        assert_eq!(json["total_bytes"], 33);
+
+        // Works without an initialized database and doesn't create one
+        assert!(!db_path.exists());
+    }
+
     #[test]
     fn test_cmd_init_force() {
         let temp_dir = TempDir::new().expect("temp dir");
@@ -749,7 +1014,7 @@ mod cli_tests {
         execute(&cli).expect("init");
 
         // Status command
-        let cli = make_cli(db_path, Commands::Status);
+        let cli = make_cli(db_path, Commands::Status { detailed: false });
         let result = execute(&cli);
         assert!(result.is_ok());
         let output = result.expect("status output");
@@ -764,7 +1029,7 @@ mod cli_tests {
         let cli = make_cli(db_path.clone(), Commands::Init { force: false });
         execute(&cli).expect("init");
 
-        let cli = make_cli_json(db_path, Commands::Status);
+        let cli = make_cli_json(db_path, Commands::Status { detailed: false });
         let result = execute(&cli);
         assert!(result.is_ok());
         let output = result.expect("json output");
@@ -772,12 +1037,130 @@ mod cli_tests {
         assert!(output.contains("buffer_count"));
     }
 
+    #[test]
+    fn test_cmd_status_detailed() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let file_path = temp_dir.path().join("content.txt");
+        std::fs::write(&file_path, "Detailed status content").expect("write file");
+
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        execute(&make_cli(
+            db_path.clone(),
+            Commands::Load {
+                file: file_path,
+                name: Some("notes".to_string()),
+                chunker: "fixed".to_string(),
+                chunk_size: 1000,
+                overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
+            },
+        ))
+        .expect("load");
+
+        let output = execute(&make_cli_json(
+            db_path.clone(),
+            Commands::Status { detailed: true },
+        ))
+        .expect("status");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["buffer_count"], 1);
+        assert_eq!(json["embedded_chunks"], 1);
+        assert_eq!(json["unembedded_chunks"], 0);
+        assert!(
+            json["embedding_bytes"]
+                .as_u64()
+                .is_some_and(|bytes| bytes > 0)
+        );
+        assert_eq!(json["models"].as_array().map(Vec::len), Some(1));
+        assert_eq!(json["buffers"][0]["name"], "notes");
+        assert_eq!(json["buffers"][0]["chunk_count"], 1);
+
+        let text = execute(&make_cli(
+            db_path.clone(),
+            Commands::Status { detailed: true },
+        ))
+        .expect("status");
+        assert!(text.contains("] notes: 1 chunks, 1 embedded, 0 missing"));
+
+        // The default output is unchanged
+        let default =
+            execute(&make_cli(db_path, Commands::Status { detailed: false })).expect("status");
+        assert!(text.starts_with(&default));
+        assert!(!default.contains("Embeddings"));
+    }
+
+    #[test]
+    fn test_cmd_health() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let health = || {
+            let cli = make_cli_json(
+                db_path.clone(),
+                Commands::Health {
+                    probe_embedder: false,
+                },
+            );
+            let output = execute(&cli).expect("health");
+            serde_json::from_str::<serde_json::Value>(&output).expect("json")
+        };
+
+        // A missing database fails without being created
+        let json = health();
+        assert_eq!(json["ok"], false);
+        assert_eq!(json["checks"]["database"]["ok"], false);
+        assert_eq!(json["checks"]["schema"]["ok"], false);
+        assert_eq!(json["checks"]["fts5"]["ok"], true);
+        assert!(json["counts"].is_null());
+        assert!(!db_path.exists());
+
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        let json = health();
+        assert_eq!(json["ok"], true);
+        assert_eq!(
+            json["checks"]["schema"]["version"],
+            rlm_rs::storage::CURRENT_SCHEMA_VERSION
+        );
+        assert!(
+            json["checks"]["embedder"]["dimensions"]
+                .as_u64()
+                .expect("dims")
+                > 0
+        );
+        assert_eq!(json["counts"]["buffers"], 0);
+
+        // An outdated schema version fails the schema check
+        rusqlite::Connection::open(&db_path)
+            .expect("open db")
+            .execute(
+                "UPDATE schema_info SET value = '1' WHERE key = 'version'",
+                [],
+            )
+            .expect("downgrade");
+        let json = health();
+        assert_eq!(json["ok"], false);
+        assert_eq!(json["checks"]["schema"]["ok"], false);
+        assert_eq!(json["checks"]["database"]["ok"], true);
+
+        let output = execute(&make_cli(
+            db_path.clone(),
+            Commands::Health {
+                probe_embedder: false,
+            },
+        ))
+        .expect("health");
+        assert!(output.starts_with("Health: FAILED"));
+        assert!(output.contains("schema version 1"));
+    }
+
     #[test]
     fn test_cmd_status_not_initialized() {
         let temp_dir = TempDir::new().expect("temp dir");
         let db_path = temp_dir.path().join("nonexistent.db");
 
-        let cli = make_cli(db_path, Commands::Status);
+        let cli = make_cli(db_path, Commands::Status { detailed: false });
         let result = execute(&cli);
         assert!(result.is_err());
     }
@@ -828,6 +1211,9 @@ mod cli_tests {
                 chunker: "fixed".to_string(),
                 chunk_size: 1000,
                 overlap: 100,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
         );
         let result = execute(&cli);
@@ -855,6 +1241,9 @@ mod cli_tests {
                 chunker: "fixed".to_string(),
                 chunk_size: 1000,
                 overlap: 100,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
         );
         let result = execute(&cli);
@@ -864,6 +1253,168 @@ mod cli_tests {
         assert!(output.contains("buffer_id"));
     }
 
+    #[test]
+    fn test_cmd_load_stdin_requires_name() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+
+        let cli = make_cli(db_path.clone(), Commands::Init { force: false });
+        execute(&cli).expect("init");
+
+        // Rejected before stdin is read
+        let cli = make_cli(
+            db_path,
+            Commands::Load {
+                file: PathBuf::from("-"),
+                name: None,
+                chunker: "fixed".to_string(),
+                chunk_size: 1000,
+                overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
+            },
+        );
+        let err = execute(&cli).expect_err("stdin without name");
+        assert!(err.to_string().contains("--name is required"));
+    }
+
+    #[test]
+    fn test_cmd_import_dir() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let root = temp_dir.path().join("tree");
+        std::fs::create_dir_all(root.join("sub")).expect("mkdir");
+        std::fs::write(root.join("a.txt"), "Top level text.").expect("write");
+        std::fs::write(root.join("sub/b.md"), "# Nested\n\nMarkdown.").expect("write");
+        std::fs::write(root.join("sub/c.txt"), "Nested text.").expect("write");
+        std::fs::write(root.join("sub/blob.bin"), [0xff, 0x00, 0x00, 0x01, 0x00]).expect("write");
+
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+
+        let import = |glob: Option<&str>, recursive| {
+            let cli = make_cli_json(
+                db_path.clone(),
+                Commands::ImportDir {
+                    dir: root.clone(),
+                    glob: glob.map(String::from),
+                    chunker: "fixed".to_string(),
+                    chunk_size: 1000,
+                    overlap: 0,
+                    recursive,
+                },
+            );
+            let output = execute(&cli).expect("import-dir");
+            serde_json::from_str::<serde_json::Value>(&output).expect("json")
+        };
+        let paths = |entries: &serde_json::Value| -> Vec<String> {
+            entries
+                .as_array()
+                .expect("array")
+                .iter()
+                .map(|e| e["path"].as_str().expect("path").to_string())
+                .collect()
+        };
+
+        // Non-recursive imports only the top level
+        let json = import(None, false);
+        assert_eq!(paths(&json["loaded"]), vec!["a.txt"]);
+        assert_eq!(json["loaded"][0]["chunk_count"], 1);
+
+        // The import refreshes planner statistics
+        let stats: i64 = rusqlite::Connection::open(&db_path)
+            .expect("open db")
+            .query_row("SELECT COUNT(*) FROM sqlite_stat1", [], |row| row.get(0))
+            .expect("sqlite_stat1");
+        assert!(stats > 0);
+
+        // Glob filters by file name; existing names fail without stopping the import
+        let json = import(Some("*.txt"), true);
+        assert_eq!(paths(&json["loaded"]), vec!["sub/c.txt"]);
+        assert_eq!(paths(&json["failed"]), vec!["a.txt"]);
+        assert!(
+            json["failed"][0]["error"]
+                .as_str()
+                .expect("error")
+                .contains("already in use")
+        );
+
+        // Binary files are skipped and reported
+        let json = import(Some("sub/**"), true);
+        assert_eq!(paths(&json["loaded"]), vec!["sub/b.md"]);
+        assert_eq!(paths(&json["skipped"]), vec!["sub/blob.bin"]);
+        assert_eq!(paths(&json["failed"]), vec!["sub/c.txt"]);
+
+        let output = execute(&make_cli_json(
+            db_path.clone(),
+            Commands::ShowBuffer {
+                buffer: "sub/b.md".to_string(),
+                chunks: false,
+            },
+        ))
+        .expect("show");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["buffer"]["content"], "# Nested\n\nMarkdown.");
+
+        let cli = make_cli(
+            db_path,
+            Commands::ImportDir {
+                dir: root.join("a.txt"),
+                glob: None,
+                chunker: "fixed".to_string(),
+                chunk_size: 1000,
+                overlap: 0,
+                recursive: false,
+            },
+        );
+        assert!(execute(&cli).is_err());
+    }
+
+    #[test]
+    fn test_cmd_load_chunk_unit_tokens() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let file_path = temp_dir.path().join("content.txt");
+        std::fs::write(&file_path, "Token budgeted sentence here. ".repeat(100))
+            .expect("write file");
+
+        let cli = make_cli(db_path.clone(), Commands::Init { force: false });
+        execute(&cli).expect("init");
+
+        let cli = make_cli_json(
+            db_path.clone(),
+            Commands::Load {
+                file: file_path.clone(),
+                name: None,
+                chunker: "semantic".to_string(),
+                chunk_size: 50,
+                overlap: 5,
+                chunk_unit: "tokens".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
+            },
+        );
+        let output = execute(&cli).expect("load");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert!(json["chunk_count"].as_u64().expect("chunk_count") > 1);
+
+        // Chunkers without token support are rejected
+        let cli = make_cli(
+            db_path,
+            Commands::Load {
+                file: file_path,
+                name: None,
+                chunker: "code".to_string(),
+                chunk_size: 50,
+                overlap: 5,
+                chunk_unit: "tokens".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
+            },
+        );
+        assert!(execute(&cli).is_err());
+    }
+
     #[test]
     fn test_cmd_list_buffers() {
         let temp_dir = TempDir::new().expect("temp dir");
@@ -882,15 +1433,90 @@ mod cli_tests {
                 chunker: "fixed".to_string(),
                 chunk_size: 1000,
                 overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
         );
         execute(&cli).expect("load");
 
-        let cli = make_cli(db_path, Commands::ListBuffers);
+        let cli = make_cli(
+            db_path.clone(),
+            Commands::ListBuffers {
+                tag: vec![],
+                sample: None,
+                seed: None,
+                fields: vec![],
+            },
+        );
         let result = execute(&cli);
         assert!(result.is_ok());
         let output = result.expect("list output");
         assert!(output.contains("mybuffer"));
+        // Load embeds every chunk
+        assert!(output.contains("100%"));
+
+        let cli = make_cli_json(
+            db_path,
+            Commands::ListBuffers {
+                tag: vec![],
+                sample: None,
+                seed: None,
+                fields: vec![],
+            },
+        );
+        let output = execute(&cli).expect("list json");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json[0]["embedded_count"], 1);
+        assert_eq!(json[0]["embedded_percent"], 100.0);
+    }
+
+    #[test]
+    fn test_cmd_list_buffers_fields() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+
+        let file_path = temp_dir.path().join("test.txt");
+        std::fs::write(&file_path, "Some content to list.").expect("write");
+        execute(&make_cli(
+            db_path.clone(),
+            Commands::Load {
+                file: file_path,
+                name: Some("listed".to_string()),
+                chunker: "fixed".to_string(),
+                chunk_size: 1000,
+                overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
+            },
+        ))
+        .expect("load");
+
+        let list = |fields: &[&str]| {
+            execute(&make_cli_json(
+                db_path.clone(),
+                Commands::ListBuffers {
+                    tag: vec![],
+                    sample: None,
+                    seed: None,
+                    fields: fields.iter().map(ToString::to_string).collect(),
+                },
+            ))
+        };
+
+        let output = list(&["id", "name", "size", "chunk_count", "name"]).expect("list");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        let buffer = json[0].as_object().expect("object");
+        assert_eq!(buffer.len(), 4);
+        assert_eq!(json[0]["name"], "listed");
+        assert_eq!(json[0]["size"], 21);
+        assert_eq!(json[0]["chunk_count"], 1);
+        assert!(!output.contains("content"));
+
+        let err = list(&["id", "bogus"]).expect_err("unknown field");
+        assert!(err.to_string().contains("unknown field 'bogus'"));
     }
 
     #[test]
@@ -901,7 +1527,15 @@ mod cli_tests {
         let cli = make_cli(db_path.clone(), Commands::Init { force: false });
         execute(&cli).expect("init");
 
-        let cli = make_cli(db_path, Commands::ListBuffers);
+        let cli = make_cli(
+            db_path,
+            Commands::ListBuffers {
+                tag: vec![],
+                sample: None,
+                seed: None,
+                fields: vec![],
+            },
+        );
         let result = execute(&cli);
         assert!(result.is_ok());
         let output = result.expect("list output");
@@ -926,6 +1560,9 @@ mod cli_tests {
                 chunker: "fixed".to_string(),
                 chunk_size: 1000,
                 overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
         );
         execute(&cli).expect("load");
@@ -977,53 +1614,398 @@ mod cli_tests {
     }
 
     #[test]
-    fn test_cmd_delete_buffer() {
+    fn test_cmd_context_buffers() {
         let temp_dir = TempDir::new().expect("temp dir");
         let db_path = temp_dir.path().join("test.db");
         let file_path = temp_dir.path().join("content.txt");
-        std::fs::write(&file_path, "Delete me").expect("write file");
+        std::fs::write(&file_path, "Context buffer content").expect("write file");
 
         let cli = make_cli(db_path.clone(), Commands::Init { force: false });
         execute(&cli).expect("init");
 
+        let cli = make_cli(db_path.clone(), Commands::ContextBuffers);
+        let output = execute(&cli).expect("context buffers");
+        assert!(output.contains("No buffers in context"));
+
         let cli = make_cli(
             db_path.clone(),
             Commands::Load {
                 file: file_path,
-                name: Some("deleteme".to_string()),
+                name: Some("ctxbuf".to_string()),
                 chunker: "fixed".to_string(),
                 chunk_size: 1000,
                 overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
         );
         execute(&cli).expect("load");
 
-        // Delete requires --yes
-        let cli = make_cli(
-            db_path.clone(),
-            Commands::DeleteBuffer {
-                buffer: "deleteme".to_string(),
-                yes: false,
-            },
-        );
-        let result = execute(&cli);
-        assert!(result.is_err());
+        // Reference a buffer that does not exist
+        {
+            use rlm_rs::storage::{SqliteStorage, Storage};
+            let mut storage = SqliteStorage::open(&db_path).expect("open");
+            let mut context = storage.load_context().expect("load").expect("context");
+            context.add_buffer(999);
+            storage.save_context(&context).expect("save");
+        }
 
-        // Delete with --yes
-        let cli = make_cli(
-            db_path.clone(),
-            Commands::DeleteBuffer {
-                buffer: "deleteme".to_string(),
-                yes: true,
-            },
-        );
-        let result = execute(&cli);
-        assert!(result.is_ok());
+        let cli = make_cli(db_path.clone(), Commands::ContextBuffers);
+        let output = execute(&cli).expect("context buffers");
+        assert!(output.contains("ctxbuf"));
+        assert!(output.contains("999"));
+        assert!(output.contains("1 dangling reference(s)"));
+
+        let cli = make_cli_json(db_path.clone(), Commands::ContextBuffers);
+        let output = execute(&cli).expect("context buffers json");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["buffer_count"], 2);
+        assert_eq!(json["dangling_count"], 1);
+
+        let cli = make_cli_json(db_path.clone(), Commands::ContextPrune);
+        let output = execute(&cli).expect("context prune");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["pruned"], serde_json::json!([999]));
+        assert_eq!(json["remaining"], 1);
+
+        let cli = make_cli(db_path.clone(), Commands::ContextPrune);
+        let output = execute(&cli).expect("context prune again");
+        assert!(output.contains("No dangling buffer references"));
+
+        let cli = make_cli_json(db_path, Commands::ContextBuffers);
+        let output = execute(&cli).expect("context buffers json");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["dangling_count"], 0);
+    }
 
-        // Verify deleted
-        let cli = make_cli(
-            db_path,
-            Commands::ShowBuffer {
+    #[test]
+    fn test_cmd_rename() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        for name in ["draft", "final"] {
+            let cli = make_cli(
+                db_path.clone(),
+                Commands::AddBuffer {
+                    name: name.to_string(),
+                    content: Some(format!("{name} content")),
+                },
+            );
+            execute(&cli).expect("add-buffer");
+        }
+
+        let rename = |buffer: &str, new_name: &str| {
+            make_cli_json(
+                db_path.clone(),
+                Commands::Rename {
+                    buffer: buffer.to_string(),
+                    new_name: new_name.to_string(),
+                },
+            )
+        };
+
+        let output = execute(&rename("draft", "v1")).expect("rename");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["old_name"], "draft");
+        assert_eq!(json["new_name"], "v1");
+        let id = json["buffer_id"].as_i64().expect("buffer_id");
+
+        // The buffer keeps its ID and content under the new name
+        let output = execute(&make_cli_json(
+            db_path.clone(),
+            Commands::ShowBuffer {
+                buffer: "v1".to_string(),
+                chunks: false,
+            },
+        ))
+        .expect("show");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["buffer"]["id"], id);
+        assert_eq!(json["buffer"]["content"], "draft content");
+
+        let err = execute(&rename("v1", "final")).expect_err("collision");
+        assert!(err.to_string().contains("already in use: final"));
+        assert!(execute(&rename("v1", "  ")).is_err());
+        assert!(execute(&rename("missing", "x")).is_err());
+
+        let cli = make_cli(
+            db_path,
+            Commands::Rename {
+                buffer: id.to_string(),
+                new_name: "v2".to_string(),
+            },
+        );
+        let output = execute(&cli).expect("rename by id");
+        assert!(output.contains("v1 \u{2192} v2"));
+    }
+
+    #[test]
+    fn test_cmd_buffer_meta() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        execute(&make_cli(
+            db_path.clone(),
+            Commands::AddBuffer {
+                name: "docs".to_string(),
+                content: Some("annotated".to_string()),
+            },
+        ))
+        .expect("add-buffer");
+
+        let meta = |key: Option<&str>, value: Option<&str>, delete: bool| {
+            make_cli_json(
+                db_path.clone(),
+                Commands::BufferMeta {
+                    buffer: "docs".to_string(),
+                    key: key.map(String::from),
+                    value: value.map(String::from),
+                    delete,
+                },
+            )
+        };
+        let run = |cli| -> serde_json::Value {
+            serde_json::from_str(&execute(&cli).expect("buffer-meta")).expect("json")
+        };
+
+        assert_eq!(
+            run(meta(None, None, false))["metadata"],
+            serde_json::json!({})
+        );
+        run(meta(Some("project"), Some("rlm"), false));
+        let json = run(meta(Some("stars"), Some("42"), false));
+        assert_eq!(json["action"], "set");
+        assert_eq!(json["value"], 42);
+
+        let json = run(meta(Some("project"), None, false));
+        assert_eq!(json["value"], "rlm");
+        assert_eq!(
+            run(meta(None, None, false))["metadata"],
+            serde_json::json!({ "project": "rlm", "stars": 42 })
+        );
+
+        // Shown with the buffer
+        let output = execute(&make_cli(
+            db_path.clone(),
+            Commands::ShowBuffer {
+                buffer: "docs".to_string(),
+                chunks: false,
+            },
+        ))
+        .expect("show");
+        assert!(output.contains("project: \"rlm\""));
+
+        assert_eq!(run(meta(Some("project"), None, true))["action"], "deleted");
+        assert!(execute(&meta(Some("project"), None, false)).is_err());
+        assert!(execute(&meta(Some("project"), None, true)).is_err());
+    }
+
+    #[test]
+    fn test_cmd_merge() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        for (name, content) in [
+            ("one", "First part."),
+            ("two", "Second part."),
+            ("other", "x"),
+        ] {
+            let cli = make_cli(
+                db_path.clone(),
+                Commands::AddBuffer {
+                    name: name.to_string(),
+                    content: Some(content.to_string()),
+                },
+            );
+            execute(&cli).expect("add-buffer");
+        }
+
+        let merge = |sources: &[&str], name: &str, delete_sources| {
+            make_cli_json(
+                db_path.clone(),
+                Commands::Merge {
+                    sources: sources.iter().map(ToString::to_string).collect(),
+                    name: name.to_string(),
+                    chunker: "fixed".to_string(),
+                    chunk_size: 1000,
+                    overlap: 0,
+                    separator: "\n--\n".to_string(),
+                    delete_sources,
+                },
+            )
+        };
+        let show = |buffer: &str| {
+            let cli = make_cli_json(
+                db_path.clone(),
+                Commands::ShowBuffer {
+                    buffer: buffer.to_string(),
+                    chunks: false,
+                },
+            );
+            execute(&cli)
+        };
+
+        // Order follows the command line; the repeated "two" (also as ID 2) is merged once
+        let output = execute(&merge(&["two", "one", "2"], "combined", false)).expect("merge");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["source_ids"], serde_json::json!([2, 1]));
+        assert_eq!(json["duplicates"], serde_json::json!(["2"]));
+        assert_eq!(json["chunk_count"], 1);
+        let new_id = json["new_buffer_id"].as_i64().expect("new_buffer_id");
+
+        let json: serde_json::Value =
+            serde_json::from_str(&show(&new_id.to_string()).expect("show")).expect("json");
+        assert_eq!(json["buffer"]["content"], "Second part.\n--\nFirst part.");
+        assert!(show("one").is_ok());
+
+        // Names in use are rejected unless they belong to a deleted source
+        let err = execute(&merge(&["one", "two"], "other", false)).expect_err("name taken");
+        assert!(err.to_string().contains("already in use"));
+        let output = execute(&merge(&["one", "two"], "one", true)).expect("merge into source");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["sources_deleted"], true);
+        assert!(show("two").is_err());
+        let json: serde_json::Value =
+            serde_json::from_str(&show("one").expect("show")).expect("json");
+        assert_eq!(json["buffer"]["content"], "First part.\n--\nSecond part.");
+
+        assert!(execute(&merge(&["missing"], "m", false)).is_err());
+    }
+
+    #[test]
+    fn test_cmd_diff() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        for (name, chunker, content) in [
+            ("v1", "fixed", "The original text."),
+            ("v2", "fixed", "The revised text."),
+            ("v1-semantic", "semantic", "The  original\ttext."),
+        ] {
+            let file_path = temp_dir.path().join(format!("{name}.txt"));
+            std::fs::write(&file_path, content).expect("write file");
+            let cli = make_cli(
+                db_path.clone(),
+                Commands::Load {
+                    file: file_path,
+                    name: Some(name.to_string()),
+                    chunker: chunker.to_string(),
+                    chunk_size: 1000,
+                    overlap: 0,
+                    chunk_unit: "chars".to_string(),
+                    encoding: None,
+                    normalize_whitespace: false,
+                },
+            );
+            execute(&cli).expect("load");
+        }
+
+        let diff = |a: &str, b: &str| {
+            let cli = make_cli_json(
+                db_path.clone(),
+                Commands::Diff {
+                    a: a.to_string(),
+                    b: b.to_string(),
+                },
+            );
+            let output = execute(&cli).expect("diff");
+            serde_json::from_str::<serde_json::Value>(&output).expect("json")
+        };
+
+        let json = diff("v1", "v2");
+        assert_eq!(json["unchanged"], 0);
+        assert_eq!(json["added"], 1);
+        assert_eq!(json["removed"], 1);
+        assert_eq!(json["strategies_differ"], false);
+        let hunk = &json["hunks"][0];
+        assert_eq!(hunk["removed"][0]["preview"], "The original text.");
+        assert_eq!(hunk["added"][0]["preview"], "The revised text.");
+
+        // Whitespace-only differences match across strategies
+        let json = diff("v1", "v1-semantic");
+        assert_eq!(json["unchanged"], 1);
+        assert_eq!(json["hunks"], serde_json::json!([]));
+        assert_eq!(json["strategies_differ"], true);
+
+        let cli = make_cli(
+            db_path.clone(),
+            Commands::Diff {
+                a: "v1".to_string(),
+                b: "v1-semantic".to_string(),
+            },
+        );
+        let output = execute(&cli).expect("diff");
+        assert!(output.contains("Unchanged: 1, added: 0, removed: 0"));
+        assert!(output.contains("strategies differ (fixed vs semantic)"));
+
+        let cli = make_cli(
+            db_path,
+            Commands::Diff {
+                a: "v1".to_string(),
+                b: "missing".to_string(),
+            },
+        );
+        assert!(execute(&cli).is_err());
+    }
+
+    #[test]
+    fn test_cmd_delete_buffer() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let file_path = temp_dir.path().join("content.txt");
+        std::fs::write(&file_path, "Delete me").expect("write file");
+
+        let cli = make_cli(db_path.clone(), Commands::Init { force: false });
+        execute(&cli).expect("init");
+
+        let cli = make_cli(
+            db_path.clone(),
+            Commands::Load {
+                file: file_path,
+                name: Some("deleteme".to_string()),
+                chunker: "fixed".to_string(),
+                chunk_size: 1000,
+                overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
+            },
+        );
+        execute(&cli).expect("load");
+
+        // Delete requires --yes
+        let cli = make_cli(
+            db_path.clone(),
+            Commands::DeleteBuffer {
+                buffer: "deleteme".to_string(),
+                yes: false,
+                soft: false,
+            },
+        );
+        let result = execute(&cli);
+        assert!(result.is_err());
+
+        // Delete with --yes
+        let cli = make_cli(
+            db_path.clone(),
+            Commands::DeleteBuffer {
+                buffer: "deleteme".to_string(),
+                yes: true,
+                soft: false,
+            },
+        );
+        let result = execute(&cli);
+        assert!(result.is_ok());
+
+        // Verify deleted
+        let cli = make_cli(
+            db_path,
+            Commands::ShowBuffer {
                 buffer: "deleteme".to_string(),
                 chunks: false,
             },
@@ -1032,6 +2014,94 @@ mod cli_tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_cmd_soft_delete_restore_purge() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        for name in ["kept", "trashed"] {
+            let file_path = temp_dir.path().join(format!("{name}.txt"));
+            std::fs::write(&file_path, format!("Recoverable {name} content")).expect("write");
+            execute(&make_cli(
+                db_path.clone(),
+                Commands::Load {
+                    file: file_path,
+                    name: Some(name.to_string()),
+                    chunker: "fixed".to_string(),
+                    chunk_size: 1000,
+                    overlap: 0,
+                    chunk_unit: "chars".to_string(),
+                    encoding: None,
+                    normalize_whitespace: false,
+                },
+            ))
+            .expect("load");
+        }
+        let run = |command: Commands| execute(&make_cli_json(db_path.clone(), command));
+        let json = |command: Commands| {
+            serde_json::from_str::<serde_json::Value>(&run(command).expect("command"))
+                .expect("json")
+        };
+        let status = || json(Commands::Status { detailed: false });
+
+        // --soft needs no confirmation and hides the buffer
+        let output = execute(&make_cli(
+            db_path.clone(),
+            Commands::DeleteBuffer {
+                buffer: "trashed".to_string(),
+                yes: false,
+                soft: true,
+            },
+        ))
+        .expect("soft delete");
+        assert!(output.contains("Moved buffer to trash: trashed"));
+        assert_eq!(status()["buffer_count"], 1);
+        assert_eq!(status()["trashed_buffer_count"], 1);
+        let listed = json(Commands::ListBuffers {
+            tag: vec![],
+            sample: None,
+            seed: None,
+            fields: vec![],
+        });
+        assert_eq!(listed.as_array().map(Vec::len), Some(1));
+        let err = run(Commands::ShowBuffer {
+            buffer: "trashed".to_string(),
+            chunks: false,
+        })
+        .unwrap_err();
+        assert!(err.to_string().contains("is in the trash"), "{err}");
+
+        let trash = json(Commands::ListTrash);
+        assert_eq!(trash[0]["name"], "trashed");
+        assert!(trash[0]["metadata"]["deleted_at"].is_i64());
+
+        let restored = json(Commands::Restore {
+            buffer: "trashed".to_string(),
+        });
+        assert_eq!(restored["restored"], true);
+        assert_eq!(status()["buffer_count"], 2);
+        assert!(
+            run(Commands::Restore {
+                buffer: "trashed".to_string(),
+            })
+            .is_err()
+        );
+
+        // Purge requires --yes and only removes trashed buffers
+        run(Commands::DeleteBuffer {
+            buffer: "trashed".to_string(),
+            yes: false,
+            soft: true,
+        })
+        .expect("soft delete");
+        assert!(run(Commands::Purge { yes: false }).is_err());
+        let purged = json(Commands::Purge { yes: true });
+        assert_eq!(purged["purged"].as_array().map(Vec::len), Some(1));
+        assert_eq!(status()["buffer_count"], 1);
+        assert_eq!(status()["trashed_buffer_count"], 0);
+        assert_eq!(json(Commands::ListTrash), serde_json::json!([]));
+    }
+
     #[test]
     fn test_cmd_peek() {
         let temp_dir = TempDir::new().expect("temp dir");
@@ -1050,6 +2120,9 @@ mod cli_tests {
                 chunker: "fixed".to_string(),
                 chunk_size: 1000,
                 overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
         );
         execute(&cli).expect("load");
@@ -1102,6 +2175,9 @@ mod cli_tests {
                 chunker: "fixed".to_string(),
                 chunk_size: 1000,
                 overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
         );
         execute(&cli).expect("load");
@@ -1154,6 +2230,9 @@ mod cli_tests {
                 chunker: "fixed".to_string(),
                 chunk_size: 1000,
                 overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
         );
         execute(&cli).expect("load");
@@ -1192,6 +2271,9 @@ mod cli_tests {
                 chunker: "fixed".to_string(),
                 chunk_size: 1000,
                 overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
         );
         execute(&cli).expect("load");
@@ -1227,6 +2309,9 @@ mod cli_tests {
                 chunker: "fixed".to_string(),
                 chunk_size: 1000,
                 overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
         );
         execute(&cli).expect("load");
@@ -1293,6 +2378,9 @@ mod cli_tests {
                 chunker: "fixed".to_string(),
                 chunk_size: 1000,
                 overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
         );
         execute(&cli).expect("load");
@@ -1333,6 +2421,106 @@ mod cli_tests {
         assert!(export_path.exists());
     }
 
+    #[test]
+    fn test_cmd_import_buffers_round_trip() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let source_db = temp_dir.path().join("source.db");
+        let target_db = temp_dir.path().join("target.db");
+        let dump_path = temp_dir.path().join("dump.json");
+
+        for db in [&source_db, &target_db] {
+            execute(&make_cli(db.clone(), Commands::Init { force: false })).expect("init");
+        }
+        for (name, content) in [("alpha", "First buffer"), ("beta", "Second buffer")] {
+            let cli = make_cli(
+                source_db.clone(),
+                Commands::AddBuffer {
+                    name: name.to_string(),
+                    content: Some(content.to_string()),
+                },
+            );
+            execute(&cli).expect("add buffer");
+        }
+
+        let cli = make_cli_json(
+            source_db,
+            Commands::ExportBuffers {
+                output: Some(dump_path.clone()),
+                pretty: true,
+            },
+        );
+        execute(&cli).expect("export");
+
+        let import = |merge, chunk| Commands::ImportBuffers {
+            input: dump_path.clone(),
+            merge,
+            chunk,
+            embed: false,
+            strategy: "semantic".to_string(),
+            chunk_size: 1000,
+            overlap: 0,
+        };
+
+        let output =
+            execute(&make_cli_json(target_db.clone(), import(false, true))).expect("import");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["added"], 2);
+        assert_eq!(json["warnings"].as_array().expect("warnings").len(), 0);
+
+        let cli = make_cli(
+            target_db.clone(),
+            Commands::Peek {
+                buffer: "beta".to_string(),
+                start: 0,
+                end: None,
+            },
+        );
+        assert!(execute(&cli).expect("peek").contains("Second buffer"));
+
+        // A name collision fails without --merge
+        assert!(execute(&make_cli(target_db.clone(), import(false, false))).is_err());
+
+        // Tampered content is imported with a warning, updating in place
+        let dump = std::fs::read_to_string(&dump_path).expect("read dump");
+        std::fs::write(&dump_path, dump.replace("First buffer", "Edited buffer"))
+            .expect("write dump");
+        let output =
+            execute(&make_cli_json(target_db.clone(), import(true, false))).expect("merge");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["updated"], 2);
+        assert_eq!(json["added"], 0);
+        let warnings = json["warnings"].as_array().expect("warnings");
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].as_str().expect("warning").contains("alpha"));
+
+        // Without --chunk, the changed buffer is re-chunked and the unchanged
+        // one keeps its chunks
+        {
+            use rlm_rs::storage::{SqliteStorage, Storage};
+            let storage = SqliteStorage::open(&target_db).expect("open");
+            for (name, text) in [("alpha", "Edited buffer"), ("beta", "Second buffer")] {
+                let id = storage
+                    .get_buffer_by_name(name)
+                    .expect("lookup")
+                    .and_then(|b| b.id)
+                    .expect("buffer");
+                let chunks = storage.get_chunks(id).expect("chunks");
+                assert_eq!(chunks.len(), 1, "{name}");
+                assert_eq!(chunks[0].content, text);
+            }
+        }
+
+        let cli = make_cli(
+            target_db,
+            Commands::Peek {
+                buffer: "alpha".to_string(),
+                start: 0,
+                end: None,
+            },
+        );
+        assert!(execute(&cli).expect("peek").contains("Edited buffer"));
+    }
+
     #[test]
     fn test_cmd_variable() {
         let temp_dir = TempDir::new().expect("temp dir");
@@ -1347,6 +2535,7 @@ mod cli_tests {
             Commands::Variable {
                 name: "myvar".to_string(),
                 value: Some("myvalue".to_string()),
+                value_type: "string".to_string(),
                 delete: false,
             },
         );
@@ -1359,6 +2548,7 @@ mod cli_tests {
             Commands::Variable {
                 name: "myvar".to_string(),
                 value: None,
+                value_type: "string".to_string(),
                 delete: false,
             },
         );
@@ -1373,6 +2563,7 @@ mod cli_tests {
             Commands::Variable {
                 name: "myvar".to_string(),
                 value: None,
+                value_type: "string".to_string(),
                 delete: true,
             },
         );
@@ -1394,6 +2585,7 @@ mod cli_tests {
             Commands::Global {
                 name: "globalvar".to_string(),
                 value: Some("globalvalue".to_string()),
+                value_type: "string".to_string(),
                 delete: false,
             },
         );
@@ -1406,6 +2598,7 @@ mod cli_tests {
             Commands::Global {
                 name: "globalvar".to_string(),
                 value: None,
+                value_type: "string".to_string(),
                 delete: false,
             },
         );
@@ -1418,6 +2611,7 @@ mod cli_tests {
             Commands::Global {
                 name: "globalvar".to_string(),
                 value: None,
+                value_type: "string".to_string(),
                 delete: true,
             },
         );
@@ -1447,6 +2641,9 @@ mod cli_tests {
                 chunker: "fixed".to_string(),
                 chunk_size: 1000,
                 overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
         );
         execute(&cli).expect("load");
@@ -1457,18 +2654,82 @@ mod cli_tests {
             Commands::Search {
                 query: "programming".to_string(),
                 top_k: 5,
+                offset: 0,
                 threshold: 0.3,
                 mode: "bm25".to_string(),
+                raw_query: false,
                 rrf_k: 60,
+                semantic_weight: 1.0,
+                bm25_weight: 1.0,
                 buffer: None,
                 preview: false,
                 preview_len: 150,
+                highlight: false,
+                title_boost: 0.0,
+                buffer_type: None,
+                min_results: 0,
+                diversity: None,
+                ids_only: false,
+                tag: vec![],
+                buffer_meta: vec![],
+                auto_embed: false,
+                no_cache: false,
+                nprobe: None,
+                metric: "cosine".to_string(),
+                timeout_ms: None,
             },
         );
         let result = execute(&cli);
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_cmd_search_weights_validated() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let cli = make_cli(db_path.clone(), Commands::Init { force: false });
+        execute(&cli).expect("init");
+
+        let search = |semantic_weight: f64, bm25_weight: f64| {
+            let cli = make_cli(
+                db_path.clone(),
+                Commands::Search {
+                    query: "anything".to_string(),
+                    top_k: 5,
+                    offset: 0,
+                    threshold: 0.3,
+                    mode: "hybrid".to_string(),
+                    raw_query: false,
+                    rrf_k: 60,
+                    semantic_weight,
+                    bm25_weight,
+                    buffer: None,
+                    preview: false,
+                    preview_len: 150,
+                    highlight: false,
+                    title_boost: 0.0,
+                    buffer_type: None,
+                    min_results: 0,
+                    diversity: None,
+                    ids_only: false,
+                    tag: vec![],
+                    buffer_meta: vec![],
+                    auto_embed: false,
+                    no_cache: false,
+                    nprobe: None,
+                    metric: "cosine".to_string(),
+                    timeout_ms: None,
+                },
+            );
+            execute(&cli)
+        };
+
+        let err = search(-1.0, 1.0).expect_err("negative weight").to_string();
+        assert!(err.contains("--semantic-weight"), "{err}");
+        let err = search(0.0, 0.0).expect_err("both zero").to_string();
+        assert!(err.contains("both be 0"), "{err}");
+    }
+
     #[test]
     fn test_cmd_chunk_get() {
         let temp_dir = TempDir::new().expect("temp dir");
@@ -1487,6 +2748,9 @@ mod cli_tests {
                 chunker: "fixed".to_string(),
                 chunk_size: 1000,
                 overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
         );
         execute(&cli).expect("load");
@@ -1497,6 +2761,13 @@ mod cli_tests {
             Commands::Chunk(ChunkCommands::Get {
                 id: 1,
                 metadata: false,
+                next: false,
+                prev: false,
+                context: None,
+                resolve_refs: None,
+                stream: false,
+                verify_hash: false,
+                wrap: "none".to_string(),
             }),
         );
         let result = execute(&cli);
@@ -1508,6 +2779,13 @@ mod cli_tests {
             Commands::Chunk(ChunkCommands::Get {
                 id: 1,
                 metadata: true,
+                next: false,
+                prev: false,
+                context: None,
+                resolve_refs: None,
+                stream: false,
+                verify_hash: false,
+                wrap: "none".to_string(),
             }),
         );
         let result = execute(&cli);
@@ -1517,328 +2795,3147 @@ mod cli_tests {
     }
 
     #[test]
-    fn test_cmd_chunk_get_not_found() {
+    fn test_cmd_chunk_get_next_prev() {
         let temp_dir = TempDir::new().expect("temp dir");
         let db_path = temp_dir.path().join("test.db");
+        let file_path = temp_dir.path().join("content.txt");
+        std::fs::write(&file_path, "AAAAAAAAAABBBBBBBBBBCCCCCCCCCC").expect("write file");
 
         let cli = make_cli(db_path.clone(), Commands::Init { force: false });
         execute(&cli).expect("init");
 
         let cli = make_cli(
-            db_path,
-            Commands::Chunk(ChunkCommands::Get {
-                id: 999,
-                metadata: false,
-            }),
+            db_path.clone(),
+            Commands::Load {
+                file: file_path,
+                name: Some("navbuf".to_string()),
+                chunker: "fixed".to_string(),
+                chunk_size: 10,
+                overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
+            },
         );
-        let result = execute(&cli);
-        assert!(result.is_err());
+        execute(&cli).expect("load");
+
+        let get = |id: i64, next: bool, prev: bool| {
+            execute(&make_cli(
+                db_path.clone(),
+                Commands::Chunk(ChunkCommands::Get {
+                    id,
+                    metadata: false,
+                    next,
+                    prev,
+                    context: None,
+                    resolve_refs: None,
+                    stream: false,
+                    verify_hash: false,
+                    wrap: "none".to_string(),
+                }),
+            ))
+        };
+
+        assert_eq!(get(1, true, false).expect("next"), "BBBBBBBBBB");
+        assert_eq!(get(2, false, true).expect("prev"), "AAAAAAAAAA");
+
+        // Buffer edges report a clear error
+        let err = get(3, true, false).expect_err("end of buffer");
+        assert!(err.to_string().contains("end of buffer"));
+        let err = get(1, false, true).expect_err("start of buffer");
+        assert!(err.to_string().contains("start of buffer"));
     }
 
     #[test]
-    fn test_cmd_chunk_list() {
+    fn test_cmd_chunk_get_context() {
         let temp_dir = TempDir::new().expect("temp dir");
         let db_path = temp_dir.path().join("test.db");
-        let file_path = temp_dir.path().join("content.txt");
-        std::fs::write(&file_path, "Chunk list content").expect("write file");
-
-        let cli = make_cli(db_path.clone(), Commands::Init { force: false });
-        execute(&cli).expect("init");
+        let file_path = temp_dir.path().join("nav.txt");
+        std::fs::write(&file_path, "AAAAAAAAAABBBBBBBBBBCCCCCCCCCCDDDDDDDDDD").expect("write file");
 
-        let cli = make_cli(
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        execute(&make_cli(
             db_path.clone(),
             Commands::Load {
                 file: file_path,
-                name: Some("chunklistbuf".to_string()),
+                name: Some("navbuf".to_string()),
                 chunker: "fixed".to_string(),
-                chunk_size: 1000,
+                chunk_size: 10,
                 overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
-        );
-        execute(&cli).expect("load");
+        ))
+        .expect("load");
 
-        // List chunks
-        let cli = make_cli(
-            db_path.clone(),
-            Commands::Chunk(ChunkCommands::List {
-                buffer: "chunklistbuf".to_string(),
-                preview: false,
-                preview_len: 100,
-            }),
-        );
-        let result = execute(&cli);
-        assert!(result.is_ok());
+        let get = |id: i64, radius: usize, wrap: &str| {
+            Commands::Chunk(ChunkCommands::Get {
+                id,
+                metadata: false,
+                next: false,
+                prev: false,
+                context: Some(radius),
+                resolve_refs: None,
+                stream: false,
+                verify_hash: false,
+                wrap: wrap.to_string(),
+            })
+        };
+
+        let output = execute(&make_cli(db_path.clone(), get(2, 1, "none"))).expect("context");
+        assert!(output.contains("AAAAAAAAAA"));
+        assert!(output.contains("--- chunk 2 (buffer 1, index 1) [hit] ---\nBBBBBBBBBB"));
+        assert!(output.contains("CCCCCCCCCC"));
+        assert!(!output.contains("DDDDDDDDDD"));
+
+        let output = execute(&make_cli(db_path.clone(), get(2, 1, "xml"))).expect("xml");
+        assert_eq!(output.matches("<chunk id=").count(), 3);
+
+        // Clamped at the buffer's start
+        let output = execute(&make_cli_json(db_path.clone(), get(1, 2, "none"))).expect("json");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["chunk_id"], 1);
+        assert_eq!(json["radius"], 2);
+        let chunks = json["chunks"].as_array().expect("chunks");
+        let indices: Vec<_> = chunks.iter().map(|c| c["index"].as_u64()).collect();
+        assert_eq!(indices, [Some(0), Some(1), Some(2)]);
+        assert_eq!(chunks[0]["hit"], true);
+        assert_eq!(chunks[1]["hit"], false);
+
+        assert!(execute(&make_cli(db_path, get(999, 1, "none"))).is_err());
+    }
 
-        // List with preview
-        let cli = make_cli(
-            db_path,
-            Commands::Chunk(ChunkCommands::List {
-                buffer: "chunklistbuf".to_string(),
-                preview: true,
-                preview_len: 50,
+    #[test]
+    fn test_cmd_chunk_get_resolve_refs() {
+        use rlm_rs::core::{Buffer, Chunk};
+        use rlm_rs::storage::{SqliteStorage, Storage};
+
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        {
+            let mut storage = SqliteStorage::open(&db_path).expect("open");
+            let buffer_id = storage
+                .add_buffer(&Buffer::from_named("refs".to_string(), String::new()))
+                .expect("add buffer");
+            let chunks: Vec<Chunk> = [
+                "Intro @chunk:2 and @chunk:3.",
+                "[two: @chunk:3]",
+                "three",
+                "loop @chunk:5",
+                "back @chunk:4",
+            ]
+            .iter()
+            .enumerate()
+            .map(|(i, text)| Chunk::new(buffer_id, (*text).to_string(), 0..text.len(), i))
+            .collect();
+            storage.add_chunks(buffer_id, &chunks).expect("add chunks");
+        }
+
+        let get = |id: i64, resolve_refs: Option<usize>| {
+            Commands::Chunk(ChunkCommands::Get {
+                id,
+                metadata: false,
+                next: false,
+                prev: false,
+                context: None,
+                resolve_refs,
+                stream: false,
+                verify_hash: false,
+                wrap: "none".to_string(),
+            })
+        };
+
+        let output = execute(&make_cli(db_path.clone(), get(1, Some(8)))).expect("resolve");
+        assert_eq!(output, "Intro [two: three] and three.");
+        let output = execute(&make_cli(db_path.clone(), get(1, None))).expect("raw");
+        assert_eq!(output, "Intro @chunk:2 and @chunk:3.");
+
+        let output = execute(&make_cli_json(db_path.clone(), get(1, Some(8)))).expect("json");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["refs_resolved"], 3);
+
+        let err = execute(&make_cli(db_path.clone(), get(1, Some(1)))).expect_err("too deep");
+        assert!(
+            err.to_string().contains("deeper than --resolve-refs=1"),
+            "{err}"
+        );
+        let err = execute(&make_cli(db_path, get(4, Some(8)))).expect_err("cycle");
+        assert!(err.to_string().contains("cycle: 4 -> 5 -> 4"), "{err}");
+    }
+
+    #[test]
+    fn test_cmd_chunk_get_stream() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let file_path = temp_dir.path().join("content.txt");
+        let content = "stream me ".repeat(5000);
+        std::fs::write(&file_path, &content).expect("write file");
+
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        execute(&make_cli(
+            db_path.clone(),
+            Commands::Load {
+                file: file_path,
+                name: Some("big".to_string()),
+                chunker: "fixed".to_string(),
+                chunk_size: 50_000,
+                overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
+            },
+        ))
+        .expect("load");
+
+        let cli = make_cli(
+            db_path,
+            Commands::Chunk(ChunkCommands::Get {
+                id: 1,
+                metadata: false,
+                next: false,
+                prev: false,
+                context: None,
+                resolve_refs: None,
+                stream: true,
+                verify_hash: false,
+                wrap: "none".to_string(),
+            }),
+        );
+        let mut out = Vec::new();
+        execute_to(&cli, &mut out).expect("stream");
+        assert_eq!(String::from_utf8(out).expect("utf8"), content);
+
+        // A closed pipe is not an error
+        let mut closed = ClosedPipe;
+        assert!(execute_to(&cli, &mut closed).is_ok());
+    }
+
+    #[test]
+    fn test_cmd_chunk_get_wrap() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let file_path = temp_dir.path().join("notes.md");
+        std::fs::write(&file_path, "Use ```sh``` fences & <tags>").expect("write file");
+
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        execute(&make_cli(
+            db_path.clone(),
+            Commands::Load {
+                file: file_path,
+                name: Some("a&b".to_string()),
+                chunker: "fixed".to_string(),
+                chunk_size: 1000,
+                overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
+            },
+        ))
+        .expect("load");
+
+        let get = |wrap: &str| {
+            execute(&make_cli(
+                db_path.clone(),
+                Commands::Chunk(ChunkCommands::Get {
+                    id: 1,
+                    metadata: false,
+                    next: false,
+                    prev: false,
+                    context: None,
+                    resolve_refs: None,
+                    stream: false,
+                    verify_hash: false,
+                    wrap: wrap.to_string(),
+                }),
+            ))
+        };
+
+        // Attribute values are escaped; content is passed through verbatim
+        assert_eq!(
+            get("xml").expect("xml"),
+            "<chunk id=\"1\" buffer=\"a&amp;b\" index=\"0\">\nUse ```sh``` fences & <tags>\n</chunk>\n"
+        );
+        // The fence outgrows the content's backtick runs and names the type
+        assert_eq!(
+            get("markdown").expect("markdown"),
+            "### Chunk 1 (a&b, index 0)\n\n````md\nUse ```sh``` fences & <tags>\n````\n"
+        );
+        assert_eq!(get("none").expect("none"), "Use ```sh``` fences & <tags>");
+
+        let err = get("yaml").expect_err("unknown style");
+        assert!(err.to_string().contains("unknown wrap style 'yaml'"));
+    }
+
+    /// Writer that behaves like a pipe whose reader has exited.
+    struct ClosedPipe;
+
+    impl std::io::Write for ClosedPipe {
+        fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_cmd_chunk_get_not_found() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+
+        let cli = make_cli(db_path.clone(), Commands::Init { force: false });
+        execute(&cli).expect("init");
+
+        let cli = make_cli(
+            db_path,
+            Commands::Chunk(ChunkCommands::Get {
+                id: 999,
+                metadata: false,
+                next: false,
+                prev: false,
+                context: None,
+                resolve_refs: None,
+                stream: false,
+                verify_hash: false,
+                wrap: "none".to_string(),
+            }),
+        );
+        let result = execute(&cli);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_cmd_load_utf16() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let file_path = temp_dir.path().join("utf16.txt");
+        let mut bytes = vec![0xff, 0xfe];
+        bytes.extend("Grüße aus UTF-16".encode_utf16().flat_map(u16::to_le_bytes));
+        std::fs::write(&file_path, bytes).expect("write file");
+
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        let load = |encoding: Option<&str>| {
+            make_cli_json(
+                db_path.clone(),
+                Commands::Load {
+                    file: file_path.clone(),
+                    name: Some("utf16".to_string()),
+                    chunker: "fixed".to_string(),
+                    chunk_size: 1000,
+                    overlap: 0,
+                    chunk_unit: "chars".to_string(),
+                    encoding: encoding.map(String::from),
+                    normalize_whitespace: false,
+                },
+            )
+        };
+
+        if cfg!(feature = "encoding") {
+            let output = execute(&load(None)).expect("load");
+            let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+            assert_eq!(json["source_encoding"], "UTF-16LE");
+
+            let output = execute(&make_cli_json(
+                db_path,
+                Commands::ShowBuffer {
+                    buffer: "utf16".to_string(),
+                    chunks: false,
+                },
+            ))
+            .expect("show");
+            let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+            assert_eq!(json["buffer"]["content"], "Grüße aus UTF-16");
+            assert_eq!(json["buffer"]["metadata"]["source_encoding"], "UTF-16LE");
+        } else {
+            // Strict UTF-8 by default, with a hint about the BOM
+            let err = execute(&load(None)).expect_err("strict UTF-8");
+            assert!(err.to_string().contains("UTF-16LE byte order mark"));
+            let err = execute(&load(Some("utf-16le"))).expect_err("no transcoding");
+            assert!(err.to_string().contains("`encoding` feature"));
+        }
+    }
+
+    #[test]
+    fn test_cmd_load_records_line_ending() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let file_path = temp_dir.path().join("crlf.txt");
+        std::fs::write(&file_path, "first line\r\nsecond line\r\n").expect("write file");
+
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        execute(&make_cli(
+            db_path.clone(),
+            Commands::Load {
+                file: file_path,
+                name: Some("crlf".to_string()),
+                chunker: "fixed".to_string(),
+                chunk_size: 1000,
+                overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
+            },
+        ))
+        .expect("load");
+
+        let show = |cli: Cli| execute(&cli).expect("show");
+        let command = || Commands::ShowBuffer {
+            buffer: "crlf".to_string(),
+            chunks: false,
+        };
+        assert!(show(make_cli(db_path.clone(), command())).contains("Line ending:  CRLF"));
+
+        // Content round-trips with its CRLF line endings
+        let json: serde_json::Value =
+            serde_json::from_str(&show(make_cli_json(db_path, command()))).expect("json");
+        assert_eq!(json["buffer"]["content"], "first line\r\nsecond line\r\n");
+        assert_eq!(json["buffer"]["metadata"]["line_ending"], "CRLF");
+    }
+
+    #[cfg(not(feature = "http"))]
+    #[test]
+    fn test_cmd_load_url_requires_http_feature() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+
+        let err = execute(&make_cli(
+            db_path,
+            Commands::Load {
+                file: PathBuf::from("https://example.com/guide.md"),
+                name: None,
+                chunker: "fixed".to_string(),
+                chunk_size: 1000,
+                overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
+            },
+        ))
+        .expect_err("needs the http feature");
+        assert!(err.to_string().contains("`http` feature"), "{err}");
+    }
+
+    #[test]
+    fn test_cmd_load_html_extraction() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let html = "<html><body><h1>Title</h1><p>Body &amp; more</p></body></html>";
+        let file_path = temp_dir.path().join("page.html");
+        std::fs::write(&file_path, html).expect("write file");
+
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        let output = execute(&make_cli_json(
+            db_path.clone(),
+            Commands::Load {
+                file: file_path,
+                name: None,
+                chunker: "fixed".to_string(),
+                chunk_size: 1000,
+                overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
+            },
+        ))
+        .expect("load");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+
+        let show = execute(&make_cli_json(
+            db_path,
+            Commands::ShowBuffer {
+                buffer: "page.html".to_string(),
+                chunks: false,
+            },
+        ))
+        .expect("show");
+        let shown: serde_json::Value = serde_json::from_str(&show).expect("json");
+        // Without the feature, HTML is loaded unchanged
+        if cfg!(feature = "extract-html") {
+            assert_eq!(json["extracted_from"], "html");
+            assert_eq!(shown["buffer"]["content"], "# Title\n\nBody & more");
+        } else {
+            assert!(json["extracted_from"].is_null());
+            assert_eq!(shown["buffer"]["content"], html);
+        }
+    }
+
+    #[test]
+    fn test_cmd_load_normalize_whitespace() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let file_path = temp_dir.path().join("messy.txt");
+        std::fs::write(&file_path, "first  line \t\r\n\tsecond\tline\r\n").expect("write file");
+
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        let output = execute(&make_cli(
+            db_path.clone(),
+            Commands::Load {
+                file: file_path,
+                name: Some("messy".to_string()),
+                chunker: "fixed".to_string(),
+                chunk_size: 1000,
+                overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: true,
+            },
+        ))
+        .expect("load");
+        assert!(output.contains("Normalized whitespace"));
+
+        let command = || Commands::ShowBuffer {
+            buffer: "messy".to_string(),
+            chunks: false,
+        };
+        let text = execute(&make_cli(db_path.clone(), command())).expect("show");
+        assert!(text.contains("Whitespace:   normalized at load"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&execute(&make_cli_json(db_path, command())).expect("show"))
+                .expect("json");
+        assert_eq!(json["buffer"]["content"], "first line\n\tsecond line\n");
+        assert_eq!(json["buffer"]["metadata"]["line_ending"], "LF");
+        assert_eq!(json["buffer"]["metadata"]["whitespace_normalized"], true);
+    }
+
+    #[test]
+    fn test_cmd_chunk_verify_hash() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let file_path = temp_dir.path().join("content.txt");
+        std::fs::write(&file_path, "Hash verification content").expect("write file");
+
+        let cli = make_cli(db_path.clone(), Commands::Init { force: false });
+        execute(&cli).expect("init");
+
+        let cli = make_cli(
+            db_path.clone(),
+            Commands::Load {
+                file: file_path,
+                name: Some("hashbuf".to_string()),
+                chunker: "fixed".to_string(),
+                chunk_size: 1000,
+                overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
+            },
+        );
+        execute(&cli).expect("load");
+
+        let get = |verify_hash| {
+            make_cli_json(
+                db_path.clone(),
+                Commands::Chunk(ChunkCommands::Get {
+                    id: 1,
+                    metadata: false,
+                    next: false,
+                    prev: false,
+                    context: None,
+                    resolve_refs: None,
+                    stream: false,
+                    verify_hash,
+                    wrap: "none".to_string(),
+                }),
+            )
+        };
+        let list = || {
+            make_cli_json(
+                db_path.clone(),
+                Commands::Chunk(ChunkCommands::List {
+                    buffer: Some("hashbuf".to_string()),
+                    all: false,
+                    limit: None,
+                    offset: 0,
+                    preview: false,
+                    preview_len: 100,
+                    verify_hash: true,
+                    sample: None,
+                    seed: None,
+                }),
+            )
+        };
+
+        // Hashes are computed on load and always reported
+        let output = execute(&get(false)).expect("get");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(
+            json["content_hash"],
+            rlm_rs::core::content_hash("Hash verification content")
+        );
+        assert!(json.get("hash_valid").is_none());
+
+        let output = execute(&get(true)).expect("get verified");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["hash_valid"], true);
+
+        let output = execute(&list()).expect("list");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["chunks"][0]["hash_valid"], true);
+        assert_eq!(json["hash_mismatches"], serde_json::json!([]));
+
+        // Corrupt the stored content behind the hash
+        let conn = rusqlite::Connection::open(&db_path).expect("open db");
+        conn.execute("UPDATE chunks SET content = 'tampered' WHERE id = 1", [])
+            .expect("tamper");
+        drop(conn);
+
+        let err = execute(&get(true)).expect_err("mismatch");
+        assert!(err.to_string().contains("hash mismatch"));
+        assert!(execute(&get(false)).is_ok());
+
+        let output = execute(&list()).expect("list");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["chunks"][0]["hash_valid"], false);
+        assert_eq!(json["hash_mismatches"], serde_json::json!([1]));
+    }
+
+    #[test]
+    fn test_cmd_chunk_list() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let file_path = temp_dir.path().join("content.txt");
+        std::fs::write(&file_path, "Chunk list content").expect("write file");
+
+        let cli = make_cli(db_path.clone(), Commands::Init { force: false });
+        execute(&cli).expect("init");
+
+        let cli = make_cli(
+            db_path.clone(),
+            Commands::Load {
+                file: file_path,
+                name: Some("chunklistbuf".to_string()),
+                chunker: "fixed".to_string(),
+                chunk_size: 1000,
+                overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
+            },
+        );
+        execute(&cli).expect("load");
+
+        // List chunks
+        let cli = make_cli(
+            db_path.clone(),
+            Commands::Chunk(ChunkCommands::List {
+                buffer: Some("chunklistbuf".to_string()),
+                all: false,
+                limit: None,
+                offset: 0,
+                preview: false,
+                preview_len: 100,
+                verify_hash: false,
+                sample: None,
+                seed: None,
+            }),
+        );
+        let result = execute(&cli);
+        assert!(result.is_ok());
+
+        // List with preview
+        let cli = make_cli(
+            db_path,
+            Commands::Chunk(ChunkCommands::List {
+                buffer: Some("chunklistbuf".to_string()),
+                all: false,
+                limit: None,
+                offset: 0,
+                preview: true,
+                preview_len: 50,
+                verify_hash: false,
+                sample: None,
+                seed: None,
+            }),
+        );
+        let result = execute(&cli);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_cmd_chunk_list_preview_graphemes() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let file_path = temp_dir.path().join("accents.txt");
+        // Each "e\u{301}" is two scalar values but one grapheme
+        std::fs::write(&file_path, "e\u{301}e\u{301}\ne\u{301}tail").expect("write file");
+
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        execute(&make_cli(
+            db_path.clone(),
+            Commands::Load {
+                file: file_path,
+                name: Some("accents".to_string()),
+                chunker: "fixed".to_string(),
+                chunk_size: 1000,
+                overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
+            },
+        ))
+        .expect("load");
+
+        let output = execute(&make_cli_json(
+            db_path,
+            Commands::Chunk(ChunkCommands::List {
+                buffer: Some("accents".to_string()),
+                all: false,
+                limit: None,
+                offset: 0,
+                preview: true,
+                preview_len: 4,
+                verify_hash: false,
+                sample: None,
+                seed: None,
+            }),
+        ))
+        .expect("chunk list");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["chunks"][0]["preview"], "e\u{301}e\u{301} e\u{301}...");
+    }
+
+    #[test]
+    fn test_cmd_list_sample() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+
+        let cli = make_cli(db_path.clone(), Commands::Init { force: false });
+        execute(&cli).expect("init");
+
+        for n in 0..6 {
+            let file_path = temp_dir.path().join(format!("sample{n}.txt"));
+            std::fs::write(&file_path, "0123456789".repeat(4)).expect("write file");
+            let cli = make_cli(
+                db_path.clone(),
+                Commands::Load {
+                    file: file_path,
+                    name: Some(format!("sample{n}")),
+                    chunker: "fixed".to_string(),
+                    chunk_size: 10,
+                    overlap: 0,
+                    chunk_unit: "chars".to_string(),
+                    encoding: None,
+                    normalize_whitespace: false,
+                },
+            );
+            execute(&cli).expect("load");
+        }
+
+        let list_buffers = |seed| {
+            let output = execute(&make_cli_json(
+                db_path.clone(),
+                Commands::ListBuffers {
+                    tag: vec![],
+                    sample: Some(3),
+                    seed,
+                    fields: vec![],
+                },
+            ))
+            .expect("list");
+            let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+            json.as_array().expect("array").clone()
+        };
+        assert_eq!(list_buffers(None).len(), 3);
+        assert_eq!(list_buffers(Some(42)), list_buffers(Some(42)));
+
+        let list_chunks = |buffer: Option<&str>| {
+            let output = execute(&make_cli_json(
+                db_path.clone(),
+                Commands::Chunk(ChunkCommands::List {
+                    buffer: buffer.map(ToString::to_string),
+                    all: buffer.is_none(),
+                    limit: None,
+                    offset: 0,
+                    preview: false,
+                    preview_len: 100,
+                    verify_hash: false,
+                    sample: Some(5),
+                    seed: Some(7),
+                }),
+            ))
+            .expect("chunk list");
+            serde_json::from_str::<serde_json::Value>(&output).expect("json")
+        };
+
+        let all = list_chunks(None);
+        assert_eq!(all["chunk_count"], 5);
+        assert_eq!(all["total_chunks"], 24);
+        assert_eq!(all["sample"]["seed"], 7);
+        assert_eq!(all["chunks"], list_chunks(None)["chunks"]);
+
+        // A buffer holds only 4 chunks, so the sample is all of them
+        let one = list_chunks(Some("sample0"));
+        assert_eq!(one["chunk_count"], 4);
+        let indexes: Vec<_> = one["chunks"]
+            .as_array()
+            .expect("chunks")
+            .iter()
+            .map(|c| c["index"].as_u64().expect("index"))
+            .collect();
+        assert_eq!(indexes, vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_cmd_chunk_list_all() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        for (name, content) in [("first", "A".repeat(25)), ("second", "B".repeat(15))] {
+            let file_path = temp_dir.path().join(format!("{name}.txt"));
+            std::fs::write(&file_path, content).expect("write file");
+            let cli = make_cli(
+                db_path.clone(),
+                Commands::Load {
+                    file: file_path,
+                    name: Some(name.to_string()),
+                    chunker: "fixed".to_string(),
+                    chunk_size: 10,
+                    overlap: 0,
+                    chunk_unit: "chars".to_string(),
+                    encoding: None,
+                    normalize_whitespace: false,
+                },
+            );
+            execute(&cli).expect("load");
+        }
+
+        let list_all = |limit, offset| {
+            Commands::Chunk(ChunkCommands::List {
+                buffer: None,
+                all: true,
+                limit,
+                offset,
+                preview: false,
+                preview_len: 100,
+                verify_hash: false,
+                sample: None,
+                seed: None,
+            })
+        };
+
+        let output = execute(&make_cli_json(db_path.clone(), list_all(None, 0))).expect("list");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["total_chunks"], 5);
+        let buffers: Vec<i64> = json["chunks"]
+            .as_array()
+            .expect("chunks")
+            .iter()
+            .map(|c| c["buffer_id"].as_i64().expect("buffer_id"))
+            .collect();
+        assert_eq!(buffers, vec![1, 1, 1, 2, 2]);
+
+        let output = execute(&make_cli_json(db_path.clone(), list_all(Some(2), 2))).expect("page");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["chunk_count"], 2);
+        assert_eq!(json["chunks"][0]["buffer_id"], 1);
+        assert_eq!(json["chunks"][0]["index"], 2);
+        assert_eq!(json["chunks"][1]["buffer_id"], 2);
+
+        let output = execute(&make_cli(db_path, list_all(Some(2), 0))).expect("text");
+        assert!(output.contains("Chunks 1-2 of 5 across all buffers"));
+        assert!(output.contains("Buffer"));
+    }
+
+    #[test]
+    fn test_cmd_null_separated_output() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        let cli = make_cli(
+            db_path.clone(),
+            Commands::AddBuffer {
+                name: "two\nlines".to_string(),
+                content: Some("needle one\nneedle two".to_string()),
+            },
+        );
+        execute(&cli).expect("add buffer");
+
+        let mut cli = make_cli(
+            db_path.clone(),
+            Commands::ListBuffers {
+                tag: vec![],
+                sample: None,
+                seed: None,
+                fields: vec![],
+            },
+        );
+        cli.null = true;
+        assert_eq!(execute(&cli).expect("list"), "1\ttwo\nlines\0");
+
+        let mut cli = make_cli(
+            db_path.clone(),
+            Commands::Grep {
+                buffer: "1".to_string(),
+                pattern: "needle".to_string(),
+                max_matches: 10,
+                window: 0,
+                ignore_case: false,
+            },
+        );
+        cli.null = true;
+        assert_eq!(execute(&cli).expect("grep"), "0\tneedle\x0011\tneedle\0");
+
+        // JSON output ignores --null
+        let mut cli = make_cli_json(
+            db_path,
+            Commands::ListBuffers {
+                tag: vec![],
+                sample: None,
+                seed: None,
+                fields: vec![],
+            },
+        );
+        cli.null = true;
+        assert!(!execute(&cli).expect("list json").contains('\0'));
+    }
+
+    #[test]
+    fn test_cmd_chunk_status() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+
+        let cli = make_cli(db_path.clone(), Commands::Init { force: false });
+        execute(&cli).expect("init");
+
+        let cli = make_cli(db_path, Commands::Chunk(ChunkCommands::Status));
+        let result = execute(&cli);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_cmd_search_json() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let file_path = temp_dir.path().join("content.txt");
+        std::fs::write(&file_path, "JSON search test content").expect("write file");
+
+        let cli = make_cli(db_path.clone(), Commands::Init { force: false });
+        execute(&cli).expect("init");
+
+        let cli = make_cli(
+            db_path.clone(),
+            Commands::Load {
+                file: file_path,
+                name: Some("jsonsearch".to_string()),
+                chunker: "fixed".to_string(),
+                chunk_size: 1000,
+                overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
+            },
+        );
+        execute(&cli).expect("load");
+
+        let cli = make_cli_json(
+            db_path,
+            Commands::Search {
+                query: "test".to_string(),
+                top_k: 5,
+                offset: 0,
+                threshold: 0.3,
+                mode: "bm25".to_string(),
+                raw_query: false,
+                rrf_k: 60,
+                semantic_weight: 1.0,
+                bm25_weight: 1.0,
+                buffer: None,
+                preview: false,
+                preview_len: 150,
+                highlight: false,
+                title_boost: 0.0,
+                buffer_type: None,
+                min_results: 0,
+                diversity: None,
+                ids_only: false,
+                tag: vec![],
+                buffer_meta: vec![],
+                auto_embed: false,
+                no_cache: false,
+                nprobe: None,
+                metric: "cosine".to_string(),
+                timeout_ms: None,
+            },
+        );
+        let result = execute(&cli);
+        assert!(result.is_ok());
+        let output = result.expect("json output");
+        assert!(output.contains('{') || output.contains('['));
+    }
+
+    #[test]
+    fn test_cmd_load_semantic_chunker() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let file_path = temp_dir.path().join("content.txt");
+        std::fs::write(
+            &file_path,
+            "First paragraph here.\n\nSecond paragraph.\n\nThird paragraph.",
+        )
+        .expect("write file");
+
+        let cli = make_cli(db_path.clone(), Commands::Init { force: false });
+        execute(&cli).expect("init");
+
+        let cli = make_cli(
+            db_path,
+            Commands::Load {
+                file: file_path,
+                name: Some("semantic".to_string()),
+                chunker: "semantic".to_string(),
+                chunk_size: 1000,
+                overlap: 100,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
+            },
+        );
+        let result = execute(&cli);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_cmd_load_parallel_chunker() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let file_path = temp_dir.path().join("content.txt");
+        std::fs::write(&file_path, "x".repeat(500)).expect("write file");
+
+        let cli = make_cli(db_path.clone(), Commands::Init { force: false });
+        execute(&cli).expect("init");
+
+        let cli = make_cli(
+            db_path,
+            Commands::Load {
+                file: file_path,
+                name: Some("parallel".to_string()),
+                chunker: "parallel".to_string(),
+                chunk_size: 100,
+                overlap: 10,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
+            },
+        );
+        let result = execute(&cli);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_cmd_variable_not_found() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+
+        let cli = make_cli(db_path.clone(), Commands::Init { force: false });
+        execute(&cli).expect("init");
+
+        // Get nonexistent variable
+        let cli = make_cli(
+            db_path,
+            Commands::Variable {
+                name: "nonexistent".to_string(),
+                value: None,
+                value_type: "string".to_string(),
+                delete: false,
+            },
+        );
+        let result = execute(&cli);
+        // Should return a message about variable not found
+        assert!(result.is_ok() || result.is_err());
+    }
+
+    #[test]
+    fn test_cmd_chunk_embed() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let file_path = temp_dir.path().join("content.txt");
+        std::fs::write(&file_path, "Content for embedding test").expect("write file");
+
+        let cli = make_cli(db_path.clone(), Commands::Init { force: false });
+        execute(&cli).expect("init");
+
+        let cli = make_cli(
+            db_path.clone(),
+            Commands::Load {
+                file: file_path,
+                name: Some("embedbuf".to_string()),
+                chunker: "fixed".to_string(),
+                chunk_size: 1000,
+                overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
+            },
+        );
+        execute(&cli).expect("load");
+
+        // Embed chunks
+        let cli = make_cli(
+            db_path.clone(),
+            Commands::Chunk(ChunkCommands::Embed {
+                buffer: "embedbuf".to_string(),
+                force: false,
+                older_than: None,
+            }),
+        );
+        let result = execute(&cli);
+        assert!(result.is_ok());
+
+        // Embed with force
+        let cli = make_cli(
+            db_path,
+            Commands::Chunk(ChunkCommands::Embed {
+                buffer: "embedbuf".to_string(),
+                force: true,
+                older_than: None,
+            }),
+        );
+        let result = execute(&cli);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_cmd_update_buffer_reuses_embeddings() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let file_path = temp_dir.path().join("content.txt");
+        std::fs::write(&file_path, "0123456789abcdefghij").expect("write file");
+
+        let cli = make_cli(db_path.clone(), Commands::Init { force: false });
+        execute(&cli).expect("init");
+
+        let cli = make_cli(
+            db_path.clone(),
+            Commands::Load {
+                file: file_path,
+                name: Some("updatebuf".to_string()),
+                chunker: "fixed".to_string(),
+                chunk_size: 10,
+                overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
+            },
+        );
+        execute(&cli).expect("load");
+
+        // Only the second chunk changes
+        let cli = make_cli_json(
+            db_path,
+            Commands::UpdateBuffer {
+                buffer: "updatebuf".to_string(),
+                content: Some("0123456789ABCDEFGHIJ".to_string()),
+                embed: true,
+                no_embed_on_update: false,
+                strategy: "fixed".to_string(),
+                chunk_size: 10,
+                overlap: 0,
+                history: false,
+                keep_versions: DEFAULT_KEEP_VERSIONS,
+            },
+        );
+        let output = execute(&cli).expect("update");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["old_chunk_count"], 2);
+        assert_eq!(json["new_chunk_count"], 2);
+        assert_eq!(json["embedded"]["count"], 2);
+        assert_eq!(json["embedded"]["reused"], 1);
+    }
+
+    #[test]
+    fn test_cmd_update_buffer_keeps_embeddings_without_embed() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let file_path = temp_dir.path().join("content.txt");
+        std::fs::write(&file_path, "0123456789abcdefghij").expect("write file");
+
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        execute(&make_cli(
+            db_path.clone(),
+            Commands::Load {
+                file: file_path,
+                name: Some("keepbuf".to_string()),
+                chunker: "fixed".to_string(),
+                chunk_size: 10,
+                overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
+            },
+        ))
+        .expect("load");
+
+        // Only the second chunk changes, and nothing is embedded
+        let output = execute(&make_cli_json(
+            db_path.clone(),
+            Commands::UpdateBuffer {
+                buffer: "keepbuf".to_string(),
+                content: Some("0123456789ABCDEFGHIJ".to_string()),
+                embed: false,
+                no_embed_on_update: true,
+                strategy: "fixed".to_string(),
+                chunk_size: 10,
+                overlap: 0,
+                history: false,
+                keep_versions: DEFAULT_KEEP_VERSIONS,
+            },
+        ))
+        .expect("update");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["embedded"], serde_json::Value::Null);
+        assert_eq!(json["kept_embeddings"], 1);
+        assert_eq!(json["unembedded_chunks"], 1);
+
+        // Incremental embedding then embeds only the changed chunk
+        let output = execute(&make_cli_json(
+            db_path,
+            Commands::Chunk(ChunkCommands::Embed {
+                buffer: "keepbuf".to_string(),
+                force: false,
+                older_than: None,
+            }),
+        ))
+        .expect("embed");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["embedded_count"], 1);
+        assert_eq!(json["skipped_count"], 1);
+    }
+
+    #[test]
+    fn test_cmd_chunk_embed_older_than() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let file_path = temp_dir.path().join("content.txt");
+        std::fs::write(&file_path, "Content for staleness checks").expect("write file");
+
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        execute(&make_cli(
+            db_path.clone(),
+            Commands::Load {
+                file: file_path,
+                name: Some("stalebuf".to_string()),
+                chunker: "fixed".to_string(),
+                chunk_size: 1000,
+                overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
+            },
+        ))
+        .expect("load");
+
+        let embed = |older_than: Option<&str>| {
+            execute(&make_cli_json(
+                db_path.clone(),
+                Commands::Chunk(ChunkCommands::Embed {
+                    buffer: "stalebuf".to_string(),
+                    force: false,
+                    older_than: older_than.map(ToString::to_string),
+                }),
+            ))
+        };
+        embed(None).expect("embed");
+
+        // Fresh embeddings are not refreshed
+        let output = embed(Some("1h")).expect("older-than");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["replaced_count"], 0);
+        assert_eq!(json["older_than"], "1h");
+
+        assert!(embed(Some("soon")).is_err());
+    }
+
+    #[test]
+    fn test_cmd_aggregate_explain() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        let findings = r#"[
+            {"chunk_id": 1, "relevance": "high", "findings": ["a"]},
+            {"chunk_id": 2, "relevance": "low", "findings": ["b"]},
+            {"chunk_id": 3, "relevance": "maybe"},
+            {"relevance": "high"},
+            "not an object"
+        ]"#;
+        execute(&make_cli(
+            db_path.clone(),
+            Commands::AddBuffer {
+                name: "findings".to_string(),
+                content: Some(findings.to_string()),
+            },
+        ))
+        .expect("add");
+
+        let aggregate = |explain: bool, cli: fn(PathBuf, Commands) -> Cli| {
+            execute(&cli(
+                db_path.clone(),
+                Commands::Aggregate {
+                    buffer: Some("findings".to_string()),
+                    min_relevance: "medium".to_string(),
+                    group_by: "relevance".to_string(),
+                    sort_by: "relevance".to_string(),
+                    output_buffer: None,
+                    explain,
+                    dedup_threshold: None,
+                    chunk_findings: false,
+                },
+            ))
+            .expect("aggregate")
+        };
+
+        // Malformed entries are skipped, not fatal
+        let json: serde_json::Value =
+            serde_json::from_str(&aggregate(true, make_cli_json)).expect("json");
+        assert_eq!(json["summary"]["total_findings"], 1);
+        assert_eq!(json["malformed"].as_array().map(Vec::len), Some(2));
+        assert_eq!(json["malformed"][0]["index"], 3);
+        assert_eq!(json["explain"]["filtered_out"], 2);
+        assert_eq!(json["explain"]["by_relevance"][0]["relevance"], "low");
+        assert_eq!(
+            json["explain"]["by_relevance"][1]["reason"],
+            "unrecognized relevance"
+        );
+
+        let output = aggregate(true, make_cli);
+        assert!(output.contains("Filtered out: 2 (minimum relevance: medium)"));
+        assert!(output.contains("  low: 1 (below minimum relevance 'medium')"));
+        assert!(output.contains("  entry 4: "));
+
+        let output = aggregate(false, make_cli);
+        assert!(output.contains("Skipped 2 malformed entries (see --explain)"));
+        assert!(!output.contains("Filtered out"));
+    }
+
+    #[test]
+    fn test_cmd_aggregate_ndjson_input() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        let findings = concat!(
+            "{\"chunk_id\": 1, \"relevance\": \"high\", \"findings\": [\"a\"]}\n",
+            "{\"chunk_id\": 2, \"relevance\": \"medium\", \"findin\n",
+            "\n",
+            "{\"chunk_id\": 3, \"relevance\": \"medium\", \"findings\": [\"c\"]}\n",
+        );
+        execute(&make_cli(
+            db_path.clone(),
+            Commands::AddBuffer {
+                name: "findings".to_string(),
+                content: Some(findings.to_string()),
+            },
+        ))
+        .expect("add");
+
+        let output = execute(&make_cli_json(
+            db_path,
+            Commands::Aggregate {
+                buffer: Some("findings".to_string()),
+                min_relevance: "low".to_string(),
+                group_by: "relevance".to_string(),
+                sort_by: "relevance".to_string(),
+                output_buffer: None,
+                explain: false,
+                dedup_threshold: None,
+                chunk_findings: false,
+            },
+        ))
+        .expect("aggregate");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["summary"]["total_findings"], 2);
+        assert_eq!(json["malformed"].as_array().map(Vec::len), Some(1));
+        assert_eq!(json["malformed"][0]["line"], 2);
+        assert!(json["malformed"][0].get("index").is_none());
+    }
+
+    #[test]
+    fn test_cmd_aggregate_dedup_threshold() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        let findings = r#"[
+            {"chunk_id": 1, "relevance": "high", "findings": ["Pool timeout is too low"]},
+            {"chunk_id": 2, "relevance": "high", "findings": ["pool timeout is too low."]},
+            {"chunk_id": 3, "relevance": "medium", "findings": ["Retries ignore the auth header"]}
+        ]"#;
+        execute(&make_cli(
+            db_path.clone(),
+            Commands::AddBuffer {
+                name: "findings".to_string(),
+                content: Some(findings.to_string()),
+            },
+        ))
+        .expect("add");
+
+        let aggregate = |dedup_threshold: Option<f32>| {
+            execute(&make_cli_json(
+                db_path.clone(),
+                Commands::Aggregate {
+                    buffer: Some("findings".to_string()),
+                    min_relevance: "low".to_string(),
+                    group_by: "relevance".to_string(),
+                    sort_by: "relevance".to_string(),
+                    output_buffer: None,
+                    explain: false,
+                    dedup_threshold,
+                    chunk_findings: false,
+                },
+            ))
+        };
+
+        // Exact matching keeps the paraphrase
+        let json: serde_json::Value =
+            serde_json::from_str(&aggregate(None).expect("aggregate")).expect("json");
+        assert_eq!(json["summary"]["unique_findings"], 3);
+        assert_eq!(json["summary"]["near_duplicates_merged"], 0);
+
+        let json: serde_json::Value =
+            serde_json::from_str(&aggregate(Some(0.95)).expect("aggregate")).expect("json");
+        assert_eq!(json["summary"]["unique_findings"], 2);
+        assert_eq!(json["summary"]["near_duplicates_merged"], 1);
+        assert_eq!(
+            json["all_findings_deduplicated"][0],
+            "Pool timeout is too low"
+        );
+
+        assert!(aggregate(Some(1.5)).is_err());
+        assert!(aggregate(Some(0.0)).is_err());
+    }
+
+    #[test]
+    fn test_cmd_aggregate_chunk_findings() {
+        use rlm_rs::storage::{SqliteStorage, Storage};
+
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        let findings = r#"[
+            {"chunk_id": 7, "relevance": "medium", "findings": ["Cache is never evicted"]},
+            {"chunk_id": 3, "relevance": "high", "findings": ["Pool timeout is too low", "Cache is never evicted"]}
+        ]"#;
+        execute(&make_cli(
+            db_path.clone(),
+            Commands::AddBuffer {
+                name: "findings".to_string(),
+                content: Some(findings.to_string()),
+            },
+        ))
+        .expect("add");
+
+        let output = execute(&make_cli_json(
+            db_path.clone(),
+            Commands::Aggregate {
+                buffer: Some("findings".to_string()),
+                min_relevance: "low".to_string(),
+                group_by: "relevance".to_string(),
+                sort_by: "relevance".to_string(),
+                output_buffer: Some("synthesis".to_string()),
+                explain: false,
+                dedup_threshold: None,
+                chunk_findings: true,
+            },
+        ))
+        .expect("aggregate");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["output_chunks"], 2);
+        assert_eq!(json["embedded_chunks"], 2);
+
+        let storage = SqliteStorage::open(&db_path).expect("open");
+        let buffer = storage
+            .get_buffer_by_name("synthesis")
+            .expect("lookup")
+            .expect("buffer");
+        assert_eq!(
+            buffer.content,
+            "Pool timeout is too low\nCache is never evicted\n"
+        );
+        let chunks = storage.get_chunks(buffer.id.expect("id")).expect("chunks");
+        assert_eq!(chunks.len(), 2);
+        // Sorted by relevance, so the high-relevance finding reports it first
+        assert_eq!(chunks[1].content, "Cache is never evicted");
+        assert_eq!(
+            chunks[1].custom_value("relevance"),
+            Some(serde_json::json!("high"))
+        );
+        assert_eq!(
+            chunks[1].custom_value("source_chunk_id"),
+            Some(serde_json::json!(3))
+        );
+        assert_eq!(
+            &buffer.content[chunks[1].byte_range.clone()],
+            "Cache is never evicted"
+        );
+    }
+
+    #[test]
+    fn test_cmd_history_and_revert_buffer() {
+        use rlm_rs::storage::{SqliteStorage, Storage};
+
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        execute(&make_cli(
+            db_path.clone(),
+            Commands::AddBuffer {
+                name: "draft".to_string(),
+                content: Some("first draft".to_string()),
+            },
+        ))
+        .expect("add");
+
+        let update = |content: &str, history: bool| {
+            execute(&make_cli_json(
+                db_path.clone(),
+                Commands::UpdateBuffer {
+                    buffer: "draft".to_string(),
+                    content: Some(content.to_string()),
+                    embed: false,
+                    no_embed_on_update: false,
+                    strategy: "semantic".to_string(),
+                    chunk_size: 1000,
+                    overlap: 0,
+                    history,
+                    keep_versions: 2,
+                },
+            ))
+            .expect("update")
+        };
+        let history = || {
+            let output = execute(&make_cli_json(
+                db_path.clone(),
+                Commands::History {
+                    buffer: "draft".to_string(),
+                },
+            ))
+            .expect("history");
+            serde_json::from_str::<serde_json::Value>(&output).expect("json")
+        };
+
+        // History is opt-in
+        let json: serde_json::Value =
+            serde_json::from_str(&update("second draft", false)).expect("json");
+        assert_eq!(json["saved_version"], serde_json::Value::Null);
+        assert_eq!(history().as_array().map(Vec::len), Some(0));
+
+        update("third draft", true);
+        update("fourth draft", true);
+        update("fifth draft", true);
+        let versions = history();
+        let numbers: Vec<_> = versions
+            .as_array()
+            .expect("array")
+            .iter()
+            .map(|v| v["version"].as_i64().expect("version"))
+            .collect();
+        assert_eq!(numbers, vec![3, 2]);
+        assert_eq!(versions[0]["size"], "fourth draft".len());
+
+        let output = execute(&make_cli_json(
+            db_path.clone(),
+            Commands::RevertBuffer {
+                buffer: "draft".to_string(),
+                version: 2,
+                embed: false,
+                keep_versions: DEFAULT_KEEP_VERSIONS,
+            },
+        ))
+        .expect("revert");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["saved_version"], 4);
+        let storage = SqliteStorage::open(&db_path).expect("open");
+        let buffer = storage
+            .get_buffer_by_name("draft")
+            .expect("lookup")
+            .expect("buffer");
+        assert_eq!(buffer.content, "third draft");
+        assert_eq!(
+            storage.get_chunks(buffer.id.expect("id")).expect("chunks")[0].content,
+            "third draft"
+        );
+
+        // Pruned versions can't be restored
+        let err = execute(&make_cli(
+            db_path,
+            Commands::RevertBuffer {
+                buffer: "draft".to_string(),
+                version: 1,
+                embed: false,
+                keep_versions: DEFAULT_KEEP_VERSIONS,
+            },
+        ))
+        .expect_err("pruned version");
+        assert!(err.to_string().contains("has no version 1"));
+    }
+
+    #[test]
+    fn test_cmd_chunk_reembed_missing() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        for name in ["gaps-a", "gaps-b"] {
+            let file_path = temp_dir.path().join(format!("{name}.txt"));
+            std::fs::write(&file_path, "Content with embedding gaps").expect("write file");
+            execute(&make_cli(
+                db_path.clone(),
+                Commands::Load {
+                    file: file_path,
+                    name: Some(name.to_string()),
+                    chunker: "fixed".to_string(),
+                    chunk_size: 1000,
+                    overlap: 0,
+                    chunk_unit: "chars".to_string(),
+                    encoding: None,
+                    normalize_whitespace: false,
+                },
+            ))
+            .expect("load");
+        }
+        let reembed = |buffer: Option<&str>| {
+            let output = execute(&make_cli_json(
+                db_path.clone(),
+                Commands::Chunk(ChunkCommands::ReembedMissing {
+                    buffer: buffer.map(ToString::to_string),
+                    force: false,
+                }),
+            ))
+            .expect("reembed-missing");
+            serde_json::from_str::<serde_json::Value>(&output).expect("json")
+        };
+
+        // Loading embeds everything, so there are no gaps yet
+        let json = reembed(None);
+        assert_eq!(json["missing_count"], 0);
+        assert_eq!(json["model"], serde_json::Value::Null);
+
+        let conn = rusqlite::Connection::open(&db_path).expect("open db");
+        conn.execute("DELETE FROM chunk_embeddings", [])
+            .expect("drop embeddings");
+
+        let json = reembed(Some("gaps-a"));
+        assert_eq!(json["missing_count"], 1);
+        assert_eq!(json["embedded_count"], 1);
+        let json = reembed(None);
+        assert_eq!(json["buffer_id"], serde_json::Value::Null);
+        assert_eq!(json["missing_count"], 1);
+        assert_eq!(json["embedded_count"], 1);
+        assert_eq!(reembed(None)["missing_count"], 0);
+
+        let output = execute(&make_cli(
+            db_path.clone(),
+            Commands::Chunk(ChunkCommands::ReembedMissing {
+                buffer: None,
+                force: false,
+            }),
+        ))
+        .expect("text");
+        assert!(output.contains("No chunks in any buffer are missing embeddings"));
+
+        // --force re-embeds everything, batched across both buffers
+        let output = execute(&make_cli(
+            db_path,
+            Commands::Chunk(ChunkCommands::ReembedMissing {
+                buffer: None,
+                force: true,
+            }),
+        ))
+        .expect("force");
+        assert!(output.contains("2/2 embedded"));
+        assert!(output.contains("Embedded 2 of 2 chunks in any buffer"));
+    }
+
+    #[test]
+    fn test_cmd_chunk_build_ivf_and_search_nprobe() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+
+        // Recall can't be estimated before the index exists
+        let recall = |nprobe: usize| {
+            execute(&make_cli_json(
+                db_path.clone(),
+                Commands::Chunk(ChunkCommands::IvfRecall {
+                    nprobe,
+                    top_k: 5,
+                    samples: 20,
+                    seed: 1,
+                }),
+            ))
+        };
+        let err = recall(1).expect_err("no index");
+        assert!(err.to_string().contains("chunk build-ivf"));
+
+        execute(&make_cli(
+            db_path.clone(),
+            Commands::GenCorpus {
+                buffers: 4,
+                chunks_per_buffer: 10,
+                seed: 3,
+                embed: true,
+            },
+        ))
+        .expect("gen-corpus");
+        let output = execute(&make_cli_json(
+            db_path.clone(),
+            Commands::Chunk(ChunkCommands::BuildIvf {
+                clusters: Some(4),
+                iterations: 10,
+                nprobe: 4,
+                samples: 20,
+                seed: 1,
             }),
+        ))
+        .expect("build-ivf");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["clusters"], 4);
+        assert_eq!(json["assigned"], 40);
+        assert_eq!(json["skipped"], 0);
+        // Probing every cluster is an exact scan
+        assert_eq!(json["recall"]["recall"], 1.0);
+        assert_eq!(json["recall"]["scanned"], 1.0);
+
+        let json: serde_json::Value =
+            serde_json::from_str(&recall(1).expect("ivf-recall")).expect("json");
+        assert_eq!(json["nprobe"], 1);
+        assert_eq!(json["samples"], 20);
+        assert!(json["scanned"].as_f64().expect("scanned") < 1.0);
+
+        let search = |nprobe: Option<usize>| {
+            let output = execute(&make_cli_json(
+                db_path.clone(),
+                Commands::Search {
+                    query: "lorem ipsum".to_string(),
+                    top_k: 40,
+                    offset: 0,
+                    threshold: -1.0,
+                    mode: "semantic".to_string(),
+                    raw_query: false,
+                    rrf_k: 60,
+                    semantic_weight: 1.0,
+                    bm25_weight: 1.0,
+                    buffer: None,
+                    preview: false,
+                    preview_len: 150,
+                    highlight: false,
+                    title_boost: 0.0,
+                    buffer_type: None,
+                    min_results: 0,
+                    diversity: None,
+                    tag: vec![],
+                    buffer_meta: vec![],
+                    auto_embed: false,
+                    ids_only: false,
+                    no_cache: false,
+                    nprobe,
+                    metric: "cosine".to_string(),
+                    timeout_ms: None,
+                },
+            ))
+            .expect("search");
+            let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+            json["count"].as_u64().expect("count")
+        };
+        assert!(search(Some(1)) <= search(None));
+        assert_eq!(search(Some(4)), search(None));
+    }
+
+    #[test]
+    fn test_cmd_diff_embeddings() {
+        use rlm_rs::core::{Buffer, Chunk};
+        use rlm_rs::storage::{SqliteStorage, Storage};
+
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        {
+            let mut storage = SqliteStorage::open(&db_path).expect("open");
+            let buffer_id = storage
+                .add_buffer(&Buffer::from_named("vecs".to_string(), String::new()))
+                .expect("add buffer");
+            let chunks: Vec<Chunk> = (0..4)
+                .map(|i| Chunk::new(buffer_id, format!("chunk {i}"), 0..7, i))
+                .collect();
+            storage.add_chunks(buffer_id, &chunks).expect("add chunks");
+            storage
+                .store_embedding(1, &[1.0, 0.0, 0.0], None)
+                .expect("embed 1");
+            storage
+                .store_embedding(2, &[0.6, 0.8, 0.0], None)
+                .expect("embed 2");
+            storage
+                .store_embedding(3, &[1.0, 0.0], None)
+                .expect("embed 3");
+        }
+        let diff = |a: i64, b: i64, top: usize| Commands::DiffEmbeddings { a, b, top };
+
+        let output = execute(&make_cli_json(db_path.clone(), diff(1, 2, 2))).expect("diff");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["dimensions"], 3);
+        let cosine = json["cosine_similarity"].as_f64().expect("cosine");
+        assert!((cosine - 0.6).abs() < 1e-5, "{cosine}");
+        let distance = json["euclidean_distance"].as_f64().expect("distance");
+        assert!((distance - 0.8_f64.sqrt()).abs() < 1e-5, "{distance}");
+        let top = json["top_dimensions"].as_array().expect("top");
+        let order: Vec<_> = top.iter().map(|d| d["dimension"].as_u64()).collect();
+        assert_eq!(order, [Some(1), Some(0)]);
+        let share = top[0]["share"].as_f64().expect("share");
+        assert!((share - 0.8).abs() < 1e-5, "{share}");
+
+        let output = execute(&make_cli(db_path.clone(), diff(1, 2, 10))).expect("text");
+        assert!(output.contains("Cosine similarity:  0.6"));
+        assert!(output.contains("Top 3 dimensions"));
+
+        let err = execute(&make_cli(db_path.clone(), diff(1, 3, 10))).expect_err("dims");
+        assert!(err.to_string().contains("dimensions differ"), "{err}");
+        let err = execute(&make_cli(db_path.clone(), diff(1, 4, 10))).expect_err("missing");
+        assert!(
+            err.to_string().contains("chunk 4 has no embedding"),
+            "{err}"
+        );
+        assert!(execute(&make_cli(db_path, diff(1, 99, 10))).is_err());
+    }
+
+    #[test]
+    fn test_cmd_completions() {
+        use clap_complete::Shell;
+
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::PowerShell] {
+            let script = execute(&make_cli_json(
+                db_path.clone(),
+                Commands::Completions { shell },
+            ))
+            .expect("completions");
+            assert!(script.contains("rlm-rs"), "{shell}");
+            // Nested subcommands complete too
+            for subcommand in ["get", "list", "embed", "reembed-missing", "status"] {
+                assert!(script.contains(subcommand), "{shell}: {subcommand}");
+            }
+        }
+        // Generating completions never touches the database
+        assert!(!db_path.exists());
+    }
+
+    #[test]
+    fn test_cmd_config_print() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let mut cli = make_cli_json(
+            db_path.clone(),
+            Commands::Config {
+                print: true,
+                action: None,
+            },
+        );
+        cli.embed_batch_size = 8;
+
+        let output = execute(&cli).expect("config");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["db_path"]["value"], db_path.display().to_string());
+        assert_eq!(json["db_path"]["source"], "flag");
+        assert_eq!(json["format"]["source"], "flag");
+        assert_eq!(json["journal"]["value"], "wal");
+        assert_eq!(json["journal"]["source"], "default");
+        assert_eq!(json["embed_batch_size"]["value"], 8);
+        assert_eq!(json["embed_batch_size"]["source"], "flag");
+        assert_eq!(json["cache_size_kib"]["value"], "auto");
+        assert!(json["model"]["value"].is_string());
+        // Printing the configuration never creates the database
+        assert!(!db_path.exists());
+
+        let output = execute(&make_cli(
+            db_path,
+            Commands::Config {
+                print: true,
+                action: None,
+            },
+        ))
+        .expect("text");
+        assert!(output.contains("journal"));
+        assert!(output.contains("default"));
+    }
+
+    #[test]
+    fn test_cmd_config_show_file_values() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let config_path = temp_dir.path().join(".rlm/config.toml");
+        std::fs::create_dir_all(temp_dir.path().join(".rlm")).expect("mkdir");
+        std::fs::write(
+            &config_path,
+            "db_path = \"project.db\"\nchunker = \"markdown\"\nformat = \"json\"\n",
+        )
+        .expect("write config");
+
+        let mut cli = make_cli_json(
+            PathBuf::new(),
+            Commands::Config {
+                print: false,
+                action: Some(ConfigCommands::Show),
+            },
+        );
+        cli.db_path = None;
+        cli.config = Config::discover(temp_dir.path()).expect("config");
+
+        let output = execute(&cli).expect("config show");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(
+            json["config_file"]["value"],
+            config_path.display().to_string()
+        );
+        assert_eq!(
+            json["db_path"]["value"],
+            temp_dir.path().join("project.db").display().to_string()
+        );
+        assert_eq!(json["db_path"]["source"], "config");
+        assert_eq!(json["format"]["source"], "config");
+        assert_eq!(json["chunker"]["value"], "markdown");
+        assert_eq!(json["chunker"]["source"], "config");
+        assert_eq!(json["chunk_size"]["source"], "default");
+        assert_eq!(json["search_mode"]["value"], "hybrid");
+    }
+
+    #[test]
+    fn test_cmd_refuses_newer_schema() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+
+        // Simulate a database written by a future release
+        let conn = rusqlite::Connection::open(&db_path).expect("open db");
+        conn.execute(
+            "UPDATE schema_info SET value = '999' WHERE key = 'version'",
+            [],
+        )
+        .expect("bump version");
+        drop(conn);
+
+        for command in [
+            Commands::Status { detailed: false },
+            Commands::Chunk(ChunkCommands::Status),
+        ] {
+            let err = execute(&make_cli(db_path.clone(), command)).expect_err("newer schema");
+            assert!(err.to_string().contains("created by a newer rlm-rs"));
+        }
+        // The stored version is left untouched
+        let conn = rusqlite::Connection::open(&db_path).expect("open db");
+        let version: String = conn
+            .query_row(
+                "SELECT value FROM schema_info WHERE key = 'version'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("version");
+        assert_eq!(version, "999");
+    }
+
+    #[test]
+    fn test_cmd_init_nested_directory() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        // Create a nested path that doesn't exist yet
+        let db_path = temp_dir.path().join("nested").join("dir").join("test.db");
+
+        let cli = make_cli(db_path.clone(), Commands::Init { force: false });
+        let result = execute(&cli);
+        assert!(result.is_ok());
+        assert!(db_path.exists());
+    }
+
+    #[test]
+    fn test_cmd_resolve_buffer_by_id() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let file_path = temp_dir.path().join("content.txt");
+        std::fs::write(&file_path, "Resolve by ID").expect("write file");
+
+        let cli = make_cli(db_path.clone(), Commands::Init { force: false });
+        execute(&cli).expect("init");
+
+        let cli = make_cli(
+            db_path.clone(),
+            Commands::Load {
+                file: file_path,
+                name: Some("resolvebuf".to_string()),
+                chunker: "fixed".to_string(),
+                chunk_size: 1000,
+                overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
+            },
+        );
+        execute(&cli).expect("load");
+
+        // Resolve by ID "1"
+        let cli = make_cli(
+            db_path,
+            Commands::ShowBuffer {
+                buffer: "1".to_string(), // ID instead of name
+                chunks: false,
+            },
+        );
+        let result = execute(&cli);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_cmd_search_with_buffer_filter() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let file_path = temp_dir.path().join("content.txt");
+        std::fs::write(&file_path, "Searchable content here").expect("write file");
+
+        let cli = make_cli(db_path.clone(), Commands::Init { force: false });
+        execute(&cli).expect("init");
+
+        let cli = make_cli(
+            db_path.clone(),
+            Commands::Load {
+                file: file_path,
+                name: Some("filterbuf".to_string()),
+                chunker: "fixed".to_string(),
+                chunk_size: 1000,
+                overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
+            },
+        );
+        execute(&cli).expect("load");
+
+        // Search with buffer filter
+        let cli = make_cli(
+            db_path,
+            Commands::Search {
+                query: "content".to_string(),
+                top_k: 5,
+                offset: 0,
+                threshold: 0.3,
+                mode: "bm25".to_string(),
+                raw_query: false,
+                rrf_k: 60,
+                semantic_weight: 1.0,
+                bm25_weight: 1.0,
+                buffer: Some("filterbuf".to_string()),
+                preview: false,
+                preview_len: 150,
+                highlight: false,
+                title_boost: 0.0,
+                buffer_type: None,
+                min_results: 0,
+                diversity: None,
+                ids_only: false,
+                tag: vec![],
+                buffer_meta: vec![],
+                auto_embed: false,
+                no_cache: false,
+                nprobe: None,
+                metric: "cosine".to_string(),
+                timeout_ms: None,
+            },
+        );
+        let result = execute(&cli);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_cmd_search_buffer_type() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+
+        for file in ["notes.txt", "guide.md"] {
+            let file_path = temp_dir.path().join(file);
+            std::fs::write(&file_path, "Searchable content here").expect("write file");
+            execute(&make_cli(
+                db_path.clone(),
+                Commands::Load {
+                    file: file_path,
+                    name: Some(file.to_string()),
+                    chunker: "fixed".to_string(),
+                    chunk_size: 1000,
+                    overlap: 0,
+                    chunk_unit: "chars".to_string(),
+                    encoding: None,
+                    normalize_whitespace: false,
+                },
+            ))
+            .expect("load");
+        }
+
+        let search = |buffer_type: &str| {
+            let output = execute(&make_cli_json(
+                db_path.clone(),
+                Commands::Search {
+                    query: "content".to_string(),
+                    top_k: 5,
+                    offset: 0,
+                    threshold: 0.3,
+                    mode: "bm25".to_string(),
+                    raw_query: false,
+                    rrf_k: 60,
+                    semantic_weight: 1.0,
+                    bm25_weight: 1.0,
+                    buffer: None,
+                    preview: false,
+                    preview_len: 150,
+                    highlight: false,
+                    title_boost: 0.0,
+                    buffer_type: Some(buffer_type.to_string()),
+                    min_results: 0,
+                    diversity: None,
+                    ids_only: false,
+                    tag: vec![],
+                    buffer_meta: vec![],
+                    auto_embed: false,
+                    no_cache: false,
+                    nprobe: None,
+                    metric: "cosine".to_string(),
+                    timeout_ms: None,
+                },
+            ))
+            .expect("search");
+            serde_json::from_str::<serde_json::Value>(&output).expect("json")
+        };
+
+        let json = search("md");
+        assert_eq!(json["count"], 1);
+        assert_eq!(json["results"][0]["buffer_id"], 2);
+
+        // Unknown type returns no results with a note
+        let json = search("py");
+        assert_eq!(json["count"], 0);
+        assert!(json["note"].as_str().expect("note").contains("md, txt"));
+    }
+
+    #[test]
+    fn test_cmd_search_auto_embed() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let file_path = temp_dir.path().join("notes.txt");
+        std::fs::write(&file_path, "Searchable content here").expect("write file");
+
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        execute(&make_cli(
+            db_path.clone(),
+            Commands::Load {
+                file: file_path,
+                name: Some("notes".to_string()),
+                chunker: "fixed".to_string(),
+                chunk_size: 1000,
+                overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
+            },
+        ))
+        .expect("load");
+        // Simulate chunks that were never embedded
+        rusqlite::Connection::open(&db_path)
+            .expect("open db")
+            .execute("DELETE FROM chunk_embeddings", [])
+            .expect("delete embeddings");
+
+        let search = |mode: &str, auto_embed| {
+            let output = execute(&make_cli_json(
+                db_path.clone(),
+                Commands::Search {
+                    query: "Searchable content here".to_string(),
+                    top_k: 10,
+                    offset: 0,
+                    threshold: 0.0,
+                    mode: mode.to_string(),
+                    raw_query: false,
+                    rrf_k: 60,
+                    semantic_weight: 1.0,
+                    bm25_weight: 1.0,
+                    buffer: None,
+                    preview: false,
+                    preview_len: 150,
+                    highlight: false,
+                    title_boost: 0.0,
+                    buffer_type: None,
+                    min_results: 0,
+                    diversity: None,
+                    tag: vec![],
+                    buffer_meta: vec![],
+                    auto_embed,
+                    ids_only: false,
+                    no_cache: false,
+                    nprobe: None,
+                    metric: "cosine".to_string(),
+                    timeout_ms: None,
+                },
+            ))
+            .expect("search");
+            serde_json::from_str::<serde_json::Value>(&output).expect("json")
+        };
+
+        let json = search("semantic", false);
+        assert_eq!(json["count"], 0);
+        assert!(json["auto_embedded"].is_null());
+
+        // BM25 needs no embeddings, so nothing is embedded
+        assert!(search("bm25", true)["auto_embedded"].is_null());
+
+        let json = search("semantic", true);
+        assert_eq!(json["auto_embedded"], 1);
+        assert_eq!(json["count"], 1);
+        assert_eq!(search("semantic", true)["auto_embedded"], 0);
+    }
+
+    #[test]
+    fn test_cmd_search_cache() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        let load = |name: &str| {
+            let file_path = temp_dir.path().join(format!("{name}.txt"));
+            std::fs::write(&file_path, "Cached search content").expect("write file");
+            execute(&make_cli(
+                db_path.clone(),
+                Commands::Load {
+                    file: file_path,
+                    name: Some(name.to_string()),
+                    chunker: "fixed".to_string(),
+                    chunk_size: 1000,
+                    overlap: 0,
+                    chunk_unit: "chars".to_string(),
+                    encoding: None,
+                    normalize_whitespace: false,
+                },
+            ))
+            .expect("load");
+        };
+        load("first");
+
+        let search = |no_cache| {
+            let output = execute(&make_cli_json(
+                db_path.clone(),
+                Commands::Search {
+                    query: "cached".to_string(),
+                    top_k: 10,
+                    offset: 0,
+                    threshold: 0.3,
+                    mode: "bm25".to_string(),
+                    raw_query: false,
+                    rrf_k: 60,
+                    semantic_weight: 1.0,
+                    bm25_weight: 1.0,
+                    buffer: None,
+                    preview: false,
+                    preview_len: 150,
+                    highlight: false,
+                    title_boost: 0.0,
+                    buffer_type: None,
+                    min_results: 0,
+                    diversity: None,
+                    tag: vec![],
+                    buffer_meta: vec![],
+                    auto_embed: false,
+                    ids_only: false,
+                    no_cache,
+                    nprobe: None,
+                    metric: "cosine".to_string(),
+                    timeout_ms: None,
+                },
+            ))
+            .expect("search");
+            serde_json::from_str::<serde_json::Value>(&output).expect("json")["count"].clone()
+        };
+        assert_eq!(search(false), 1);
+
+        // Tamper with the cached entry to observe hits
+        let conn = rusqlite::Connection::open(&db_path).expect("open db");
+        let updated = conn
+            .execute("UPDATE search_cache SET results = '[[], null]'", [])
+            .expect("update cache");
+        assert_eq!(updated, 1);
+        assert_eq!(search(false), 0);
+        assert_eq!(search(true), 1);
+
+        // Loading content changes the corpus version and invalidates the entry
+        load("second");
+        assert_eq!(search(false), 2);
+    }
+
+    #[test]
+    fn test_cmd_search_offset_pages() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        for name in ["a", "b"] {
+            let file_path = temp_dir.path().join(format!("{name}.txt"));
+            let content = (0..6)
+                .map(|i| format!("needle number {i} in haystack {name}."))
+                .collect::<Vec<_>>()
+                .join("\n");
+            std::fs::write(&file_path, content).expect("write file");
+            execute(&make_cli(
+                db_path.clone(),
+                Commands::Load {
+                    file: file_path,
+                    name: Some(name.to_string()),
+                    chunker: "fixed".to_string(),
+                    chunk_size: 40,
+                    overlap: 0,
+                    chunk_unit: "chars".to_string(),
+                    encoding: None,
+                    normalize_whitespace: false,
+                },
+            ))
+            .expect("load");
+        }
+
+        let search = |top_k: usize, offset: usize| {
+            let output = execute(&make_cli_json(
+                db_path.clone(),
+                Commands::Search {
+                    query: "needle".to_string(),
+                    top_k,
+                    offset,
+                    threshold: 0.3,
+                    mode: "bm25".to_string(),
+                    raw_query: false,
+                    rrf_k: 60,
+                    semantic_weight: 1.0,
+                    bm25_weight: 1.0,
+                    buffer: Some("a".to_string()),
+                    preview: false,
+                    preview_len: 150,
+                    highlight: false,
+                    title_boost: 0.0,
+                    buffer_type: None,
+                    min_results: 0,
+                    diversity: None,
+                    tag: vec![],
+                    buffer_meta: vec![],
+                    auto_embed: false,
+                    ids_only: false,
+                    no_cache: false,
+                    nprobe: None,
+                    metric: "cosine".to_string(),
+                    timeout_ms: None,
+                },
+            ))
+            .expect("search");
+            serde_json::from_str::<serde_json::Value>(&output).expect("json")
+        };
+
+        let all = search(100, 0);
+        assert_eq!(all["has_more"], false);
+        let expected: Vec<i64> = all["results"]
+            .as_array()
+            .expect("results")
+            .iter()
+            .map(|r| r["chunk_id"].as_i64().expect("id"))
+            .collect();
+        assert!(expected.len() > 2);
+        let buffer_a = all["results"][0]["buffer_id"].clone();
+
+        // Pages of 2 cover the filtered ranking exactly, in order
+        let mut paged = Vec::new();
+        for page in 0.. {
+            let json = search(2, page * 2);
+            assert_eq!(json["offset"], page * 2);
+            for result in json["results"].as_array().expect("results") {
+                assert_eq!(result["buffer_id"], buffer_a);
+                paged.push(result["chunk_id"].as_i64().expect("id"));
+            }
+            if json["has_more"] == false {
+                break;
+            }
+            assert_eq!(json["count"], 2);
+        }
+        assert_eq!(paged, expected);
+    }
+
+    #[test]
+    fn test_cmd_search_highlight() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let file_path = temp_dir.path().join("notes.txt");
+        std::fs::write(&file_path, "Tokens are refreshed before they expire").expect("write file");
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        execute(&make_cli(
+            db_path.clone(),
+            Commands::Load {
+                file: file_path,
+                name: Some("notes".to_string()),
+                chunker: "fixed".to_string(),
+                chunk_size: 1000,
+                overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
+            },
+        ))
+        .expect("load");
+
+        let search = |mode: &str, format_json: bool| {
+            let command = Commands::Search {
+                query: "expire".to_string(),
+                top_k: 10,
+                offset: 0,
+                threshold: 0.0,
+                mode: mode.to_string(),
+                raw_query: false,
+                rrf_k: 60,
+                semantic_weight: 1.0,
+                bm25_weight: 1.0,
+                buffer: None,
+                preview: false,
+                preview_len: 150,
+                highlight: true,
+                title_boost: 0.0,
+                buffer_type: None,
+                min_results: 0,
+                diversity: None,
+                tag: vec![],
+                buffer_meta: vec![],
+                auto_embed: false,
+                ids_only: false,
+                no_cache: false,
+                nprobe: None,
+                metric: "cosine".to_string(),
+                timeout_ms: None,
+            };
+            let cli = if format_json {
+                make_cli_json(db_path.clone(), command)
+            } else {
+                make_cli(db_path.clone(), command)
+            };
+            execute(&cli).expect("search")
+        };
+
+        let text = search("bm25", false);
+        assert!(
+            text.contains("Match: Tokens are refreshed before they [expire]"),
+            "{text}"
+        );
+        let json: serde_json::Value = serde_json::from_str(&search("bm25", true)).expect("json");
+        assert_eq!(
+            json["results"][0]["snippet"],
+            "Tokens are refreshed before they [expire]"
+        );
+
+        // Semantic matches have no lexical span
+        let json: serde_json::Value =
+            serde_json::from_str(&search("semantic", true)).expect("json");
+        assert!(json["results"][0].get("snippet").is_none());
+    }
+
+    #[test]
+    fn test_cmd_search_buffer_meta_filter() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+
+        for name in ["alpha", "beta", "gamma"] {
+            let file_path = temp_dir.path().join(format!("{name}.txt"));
+            std::fs::write(&file_path, "Searchable content here").expect("write file");
+            execute(&make_cli(
+                db_path.clone(),
+                Commands::Load {
+                    file: file_path,
+                    name: Some(name.to_string()),
+                    chunker: "fixed".to_string(),
+                    chunk_size: 1000,
+                    overlap: 0,
+                    chunk_unit: "chars".to_string(),
+                    encoding: None,
+                    normalize_whitespace: false,
+                },
+            ))
+            .expect("load");
+        }
+        for (buffer, key, value) in [
+            ("alpha", "project", "alpha"),
+            ("alpha", "version", "2"),
+            ("beta", "project", "alpha"),
+            ("gamma", "project", "beta"),
+        ] {
+            execute(&make_cli(
+                db_path.clone(),
+                Commands::BufferMeta {
+                    buffer: buffer.to_string(),
+                    key: Some(key.to_string()),
+                    value: Some(value.to_string()),
+                    delete: false,
+                },
+            ))
+            .expect("buffer-meta");
+        }
+
+        let search = |buffer: Option<&str>, tags: &[&str], filters: &[&str]| {
+            execute(&make_cli_json(
+                db_path.clone(),
+                Commands::Search {
+                    query: "content".to_string(),
+                    top_k: 10,
+                    offset: 0,
+                    threshold: 0.3,
+                    mode: "bm25".to_string(),
+                    raw_query: false,
+                    rrf_k: 60,
+                    semantic_weight: 1.0,
+                    bm25_weight: 1.0,
+                    buffer: buffer.map(String::from),
+                    preview: false,
+                    preview_len: 150,
+                    highlight: false,
+                    title_boost: 0.0,
+                    buffer_type: None,
+                    min_results: 0,
+                    diversity: None,
+                    ids_only: false,
+                    tag: tags.iter().map(ToString::to_string).collect(),
+                    buffer_meta: filters.iter().map(ToString::to_string).collect(),
+                    auto_embed: false,
+                    no_cache: false,
+                    nprobe: None,
+                    metric: "cosine".to_string(),
+                    timeout_ms: None,
+                },
+            ))
+            .map(|output| serde_json::from_str::<serde_json::Value>(&output).expect("json"))
+        };
+        let count = |json: serde_json::Value| json["count"].as_u64().expect("count");
+
+        assert_eq!(
+            count(search(None, &[], &["project=alpha"]).expect("search")),
+            2
+        );
+        // Values parse like buffer-meta values, so version=2 matches the number 2
+        let json = search(None, &[], &["project=alpha", "version=2"]).expect("search");
+        assert_eq!(json["count"], 1);
+        assert_eq!(json["results"][0]["buffer_id"], 1);
+        assert_eq!(
+            count(search(None, &[], &["project=gamma"]).expect("search")),
+            0
+        );
+
+        // Combines with --buffer and --tag
+        assert_eq!(
+            count(search(Some("beta"), &[], &["project=alpha"]).expect("search")),
+            1
+        );
+        assert_eq!(
+            count(search(Some("gamma"), &[], &["project=alpha"]).expect("search")),
+            0
+        );
+        assert_eq!(
+            count(search(None, &["none"], &["project=alpha"]).expect("search")),
+            0
         );
-        let result = execute(&cli);
-        assert!(result.is_ok());
+
+        assert!(search(None, &[], &["project"]).is_err());
+        assert!(search(None, &[], &["=alpha"]).is_err());
     }
 
-    #[test]
-    fn test_cmd_chunk_status() {
-        let temp_dir = TempDir::new().expect("temp dir");
+    /// Loads buffers `alpha`, `beta`, and `gamma` with the same content and
+    /// returns the database path.
+    fn setup_tag_buffers(temp_dir: &TempDir) -> PathBuf {
         let db_path = temp_dir.path().join("test.db");
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
 
-        let cli = make_cli(db_path.clone(), Commands::Init { force: false });
-        execute(&cli).expect("init");
+        for name in ["alpha", "beta", "gamma"] {
+            let file_path = temp_dir.path().join(format!("{name}.txt"));
+            std::fs::write(&file_path, "Searchable content here").expect("write file");
+            execute(&make_cli(
+                db_path.clone(),
+                Commands::Load {
+                    file: file_path,
+                    name: Some(name.to_string()),
+                    chunker: "fixed".to_string(),
+                    chunk_size: 1000,
+                    overlap: 0,
+                    chunk_unit: "chars".to_string(),
+                    encoding: None,
+                    normalize_whitespace: false,
+                },
+            ))
+            .expect("load");
+        }
+        db_path
+    }
 
-        let cli = make_cli(db_path, Commands::Chunk(ChunkCommands::Status));
-        let result = execute(&cli);
-        assert!(result.is_ok());
+    /// Adds and removes tags on a buffer, returning the JSON output.
+    fn tag_buffer(
+        db_path: &std::path::Path,
+        buffer: &str,
+        add: &[&str],
+        remove: &[&str],
+    ) -> String {
+        execute(&make_cli_json(
+            db_path.to_path_buf(),
+            Commands::Tag {
+                buffer: Some(buffer.to_string()),
+                add: add.iter().map(ToString::to_string).collect(),
+                remove: remove.iter().map(ToString::to_string).collect(),
+            },
+        ))
+        .expect("tag")
     }
 
     #[test]
-    fn test_cmd_search_json() {
+    fn test_cmd_tag() {
         let temp_dir = TempDir::new().expect("temp dir");
-        let db_path = temp_dir.path().join("test.db");
-        let file_path = temp_dir.path().join("content.txt");
-        std::fs::write(&file_path, "JSON search test content").expect("write file");
+        let db_path = setup_tag_buffers(&temp_dir);
 
-        let cli = make_cli(db_path.clone(), Commands::Init { force: false });
-        execute(&cli).expect("init");
+        tag_buffer(&db_path, "alpha", &["api", "v2"], &[]);
+        tag_buffer(&db_path, "beta", &["api", "old"], &[]);
+        let output = tag_buffer(&db_path, "beta", &[], &["old"]);
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["removed"], serde_json::json!(["old"]));
+        assert_eq!(json["tags"], serde_json::json!(["api"]));
 
-        let cli = make_cli(
+        // Listing all tags reports buffer counts
+        let output = execute(&make_cli_json(
             db_path.clone(),
-            Commands::Load {
-                file: file_path,
-                name: Some("jsonsearch".to_string()),
-                chunker: "fixed".to_string(),
-                chunk_size: 1000,
-                overlap: 0,
-            },
-        );
-        execute(&cli).expect("load");
-
-        let cli = make_cli_json(
-            db_path,
-            Commands::Search {
-                query: "test".to_string(),
-                top_k: 5,
-                threshold: 0.3,
-                mode: "bm25".to_string(),
-                rrf_k: 60,
+            Commands::Tag {
                 buffer: None,
-                preview: false,
-                preview_len: 150,
+                add: vec![],
+                remove: vec![],
             },
+        ))
+        .expect("tags");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json[0]["tag"], "api");
+        assert_eq!(json[0]["buffer_count"], 2);
+        assert_eq!(json[1]["tag"], "v2");
+
+        // Tag changes need a buffer, and empty tags are rejected
+        assert!(
+            execute(&make_cli(
+                db_path.clone(),
+                Commands::Tag {
+                    buffer: None,
+                    add: vec!["x".to_string()],
+                    remove: vec![],
+                },
+            ))
+            .is_err()
+        );
+        assert!(
+            execute(&make_cli(
+                db_path,
+                Commands::Tag {
+                    buffer: Some("alpha".to_string()),
+                    add: vec!["  ".to_string()],
+                    remove: vec![],
+                },
+            ))
+            .is_err()
         );
-        let result = execute(&cli);
-        assert!(result.is_ok());
-        let output = result.expect("json output");
-        assert!(output.contains('{') || output.contains('['));
     }
 
     #[test]
-    fn test_cmd_load_semantic_chunker() {
+    fn test_cmd_tag_filters_list() {
         let temp_dir = TempDir::new().expect("temp dir");
-        let db_path = temp_dir.path().join("test.db");
-        let file_path = temp_dir.path().join("content.txt");
-        std::fs::write(
-            &file_path,
-            "First paragraph here.\n\nSecond paragraph.\n\nThird paragraph.",
-        )
-        .expect("write file");
+        let db_path = setup_tag_buffers(&temp_dir);
+        tag_buffer(&db_path, "alpha", &["api", "v2"], &[]);
+        tag_buffer(&db_path, "beta", &["api"], &[]);
 
-        let cli = make_cli(db_path.clone(), Commands::Init { force: false });
-        execute(&cli).expect("init");
+        let list = |tags: &[&str]| {
+            let output = execute(&make_cli_json(
+                db_path.clone(),
+                Commands::ListBuffers {
+                    tag: tags.iter().map(ToString::to_string).collect(),
+                    sample: None,
+                    seed: None,
+                    fields: vec![],
+                },
+            ))
+            .expect("list");
+            let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+            json.as_array()
+                .expect("array")
+                .iter()
+                .map(|b| b["name"].as_str().expect("name").to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(list(&["api"]), vec!["alpha", "beta"]);
+        assert_eq!(list(&["api", "v2"]), vec!["alpha"]);
+        assert!(list(&["missing"]).is_empty());
+    }
 
-        let cli = make_cli(
-            db_path,
-            Commands::Load {
-                file: file_path,
-                name: Some("semantic".to_string()),
-                chunker: "semantic".to_string(),
-                chunk_size: 1000,
-                overlap: 100,
-            },
-        );
-        let result = execute(&cli);
-        assert!(result.is_ok());
+    #[test]
+    fn test_cmd_tag_filters_search() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = setup_tag_buffers(&temp_dir);
+        tag_buffer(&db_path, "alpha", &["api", "v2"], &[]);
+        tag_buffer(&db_path, "beta", &["api"], &[]);
+
+        let search = |tags: &[&str]| {
+            let output = execute(&make_cli_json(
+                db_path.clone(),
+                Commands::Search {
+                    query: "content".to_string(),
+                    top_k: 10,
+                    offset: 0,
+                    threshold: 0.3,
+                    mode: "bm25".to_string(),
+                    raw_query: false,
+                    rrf_k: 60,
+                    semantic_weight: 1.0,
+                    bm25_weight: 1.0,
+                    buffer: None,
+                    preview: false,
+                    preview_len: 150,
+                    highlight: false,
+                    title_boost: 0.0,
+                    buffer_type: None,
+                    min_results: 0,
+                    diversity: None,
+                    ids_only: false,
+                    tag: tags.iter().map(ToString::to_string).collect(),
+                    buffer_meta: vec![],
+                    auto_embed: false,
+                    no_cache: false,
+                    nprobe: None,
+                    metric: "cosine".to_string(),
+                    timeout_ms: None,
+                },
+            ))
+            .expect("search");
+            serde_json::from_str::<serde_json::Value>(&output).expect("json")
+        };
+        assert_eq!(search(&[])["count"], 3);
+        assert_eq!(search(&["api"])["count"], 2);
+        let json = search(&["api", "v2"]);
+        assert_eq!(json["count"], 1);
+        assert_eq!(json["results"][0]["buffer_id"], 1);
     }
 
     #[test]
-    fn test_cmd_load_parallel_chunker() {
+    fn test_cmd_search_ids_only() {
         let temp_dir = TempDir::new().expect("temp dir");
         let db_path = temp_dir.path().join("test.db");
         let file_path = temp_dir.path().join("content.txt");
-        std::fs::write(&file_path, "x".repeat(500)).expect("write file");
-
-        let cli = make_cli(db_path.clone(), Commands::Init { force: false });
-        execute(&cli).expect("init");
+        std::fs::write(&file_path, "Searchable content here").expect("write file");
 
-        let cli = make_cli(
-            db_path,
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        execute(&make_cli(
+            db_path.clone(),
             Commands::Load {
                 file: file_path,
-                name: Some("parallel".to_string()),
-                chunker: "parallel".to_string(),
-                chunk_size: 100,
-                overlap: 10,
+                name: Some("idsbuf".to_string()),
+                chunker: "fixed".to_string(),
+                chunk_size: 1000,
+                overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
-        );
-        let result = execute(&cli);
-        assert!(result.is_ok());
+        ))
+        .expect("load");
+
+        let search = |query: &str| Commands::Search {
+            query: query.to_string(),
+            top_k: 5,
+            offset: 0,
+            threshold: 0.3,
+            mode: "bm25".to_string(),
+            raw_query: false,
+            rrf_k: 60,
+            semantic_weight: 1.0,
+            bm25_weight: 1.0,
+            buffer: None,
+            preview: false,
+            preview_len: 150,
+            highlight: false,
+            title_boost: 0.0,
+            buffer_type: None,
+            min_results: 0,
+            diversity: None,
+            tag: vec![],
+            buffer_meta: vec![],
+            auto_embed: false,
+            no_cache: false,
+            nprobe: None,
+            metric: "cosine".to_string(),
+            timeout_ms: None,
+            ids_only: true,
+        };
+
+        let output = execute(&make_cli(db_path.clone(), search("content"))).expect("search");
+        assert_eq!(output, "1\n");
+
+        let output = execute(&make_cli_json(db_path.clone(), search("content"))).expect("search");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json, serde_json::json!([1]));
+
+        // No matches produce no output rather than a message
+        let output = execute(&make_cli(db_path, search("absent"))).expect("search");
+        assert!(output.is_empty());
     }
 
     #[test]
-    fn test_cmd_variable_not_found() {
+    fn test_cmd_search_metric() {
         let temp_dir = TempDir::new().expect("temp dir");
         let db_path = temp_dir.path().join("test.db");
+        let file_path = temp_dir.path().join("content.txt");
+        std::fs::write(&file_path, "metric test content about vectors").expect("write file");
 
-        let cli = make_cli(db_path.clone(), Commands::Init { force: false });
-        execute(&cli).expect("init");
-
-        // Get nonexistent variable
-        let cli = make_cli(
-            db_path,
-            Commands::Variable {
-                name: "nonexistent".to_string(),
-                value: None,
-                delete: false,
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        execute(&make_cli(
+            db_path.clone(),
+            Commands::Load {
+                file: file_path,
+                name: Some("metrics".to_string()),
+                chunker: "fixed".to_string(),
+                chunk_size: 1000,
+                overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
-        );
-        let result = execute(&cli);
-        // Should return a message about variable not found
-        assert!(result.is_ok() || result.is_err());
+        ))
+        .expect("load");
+
+        let search = |metric: &str, threshold: f32| {
+            execute(&make_cli_json(
+                db_path.clone(),
+                Commands::Search {
+                    query: "unrelated words".to_string(),
+                    top_k: 10,
+                    offset: 0,
+                    threshold,
+                    mode: "semantic".to_string(),
+                    raw_query: false,
+                    rrf_k: 60,
+                    semantic_weight: 1.0,
+                    bm25_weight: 1.0,
+                    buffer: None,
+                    preview: false,
+                    preview_len: 150,
+                    highlight: false,
+                    title_boost: 0.0,
+                    buffer_type: None,
+                    min_results: 0,
+                    diversity: None,
+                    tag: vec![],
+                    buffer_meta: vec![],
+                    auto_embed: false,
+                    ids_only: false,
+                    no_cache: false,
+                    nprobe: None,
+                    metric: metric.to_string(),
+                    timeout_ms: None,
+                },
+            ))
+            .map(|output| {
+                let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+                json["results"].as_array().expect("results").clone()
+            })
+        };
+
+        // Euclidean inverse never drops below 1/3 for normalized embeddings,
+        // so a 0.3 threshold keeps the chunk whatever its cosine score
+        let results = search("euclidean", 0.3).expect("euclidean");
+        assert_eq!(results.len(), 1);
+        let score = results[0]["semantic_score"].as_f64().expect("score");
+        assert!(score > 0.3 && score <= 1.0);
+
+        // Normalized embeddings score the same under dot product and cosine
+        let cosine = search("cosine", -1.0).expect("cosine");
+        let dot = search("dot", -1.0).expect("dot");
+        let cosine_score = cosine[0]["semantic_score"].as_f64().expect("score");
+        let dot_score = dot[0]["semantic_score"].as_f64().expect("score");
+        assert!((cosine_score - dot_score).abs() < 1e-5);
+
+        let err = search("manhattan", 0.3).expect_err("unknown metric");
+        assert!(err.to_string().contains("unknown metric 'manhattan'"));
     }
 
     #[test]
-    fn test_cmd_chunk_embed() {
+    fn test_cmd_search_timeout() {
         let temp_dir = TempDir::new().expect("temp dir");
         let db_path = temp_dir.path().join("test.db");
         let file_path = temp_dir.path().join("content.txt");
-        std::fs::write(&file_path, "Content for embedding test").expect("write file");
-
-        let cli = make_cli(db_path.clone(), Commands::Init { force: false });
-        execute(&cli).expect("init");
+        std::fs::write(&file_path, "deadline test content about timeouts").expect("write file");
 
-        let cli = make_cli(
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        execute(&make_cli(
             db_path.clone(),
             Commands::Load {
                 file: file_path,
-                name: Some("embedbuf".to_string()),
+                name: Some("timeouts".to_string()),
                 chunker: "fixed".to_string(),
                 chunk_size: 1000,
                 overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
-        );
-        execute(&cli).expect("load");
-
-        // Embed chunks
-        let cli = make_cli(
-            db_path.clone(),
-            Commands::Chunk(ChunkCommands::Embed {
-                buffer: "embedbuf".to_string(),
-                force: false,
-            }),
-        );
-        let result = execute(&cli);
-        assert!(result.is_ok());
-
-        // Embed with force
-        let cli = make_cli(
-            db_path,
-            Commands::Chunk(ChunkCommands::Embed {
-                buffer: "embedbuf".to_string(),
-                force: true,
-            }),
-        );
-        let result = execute(&cli);
-        assert!(result.is_ok());
-    }
-
-    #[test]
-    fn test_cmd_init_nested_directory() {
-        let temp_dir = TempDir::new().expect("temp dir");
-        // Create a nested path that doesn't exist yet
-        let db_path = temp_dir.path().join("nested").join("dir").join("test.db");
+        ))
+        .expect("load");
 
-        let cli = make_cli(db_path.clone(), Commands::Init { force: false });
-        let result = execute(&cli);
-        assert!(result.is_ok());
-        assert!(db_path.exists());
+        let search = |timeout_ms: Option<u64>| {
+            let output = execute(&make_cli_json(
+                db_path.clone(),
+                Commands::Search {
+                    query: "deadline".to_string(),
+                    top_k: 10,
+                    offset: 0,
+                    threshold: 0.0,
+                    mode: "hybrid".to_string(),
+                    raw_query: false,
+                    rrf_k: 60,
+                    semantic_weight: 1.0,
+                    bm25_weight: 1.0,
+                    buffer: None,
+                    preview: false,
+                    preview_len: 150,
+                    highlight: false,
+                    title_boost: 0.0,
+                    buffer_type: None,
+                    min_results: 0,
+                    diversity: None,
+                    tag: vec![],
+                    buffer_meta: vec![],
+                    auto_embed: false,
+                    ids_only: false,
+                    no_cache: false,
+                    nprobe: None,
+                    metric: "cosine".to_string(),
+                    timeout_ms,
+                },
+            ))
+            .expect("search");
+            serde_json::from_str::<serde_json::Value>(&output).expect("json")
+        };
+
+        // An immediate deadline stops both paths before scoring anything
+        let json = search(Some(0));
+        assert_eq!(json["partial"], true);
+        assert_eq!(json["count"], 0);
+
+        // Partial results are not cached, so the next search is complete
+        let json = search(None);
+        assert_eq!(json["partial"], false);
+        assert_eq!(json["count"], 1);
+        let json = search(Some(60_000));
+        assert_eq!(json["partial"], false);
+        assert_eq!(json["count"], 1);
     }
 
     #[test]
-    fn test_cmd_resolve_buffer_by_id() {
+    fn test_cmd_ndjson_streams_records() {
         let temp_dir = TempDir::new().expect("temp dir");
         let db_path = temp_dir.path().join("test.db");
         let file_path = temp_dir.path().join("content.txt");
-        std::fs::write(&file_path, "Resolve by ID").expect("write file");
-
-        let cli = make_cli(db_path.clone(), Commands::Init { force: false });
-        execute(&cli).expect("init");
+        std::fs::write(&file_path, "alpha stream one. alpha stream two.").expect("write file");
 
-        let cli = make_cli(
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        execute(&make_cli(
             db_path.clone(),
             Commands::Load {
                 file: file_path,
-                name: Some("resolvebuf".to_string()),
+                name: Some("streambuf".to_string()),
                 chunker: "fixed".to_string(),
-                chunk_size: 1000,
+                chunk_size: 18,
                 overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
-        );
-        execute(&cli).expect("load");
-
-        // Resolve by ID "1"
-        let cli = make_cli(
-            db_path,
-            Commands::ShowBuffer {
-                buffer: "1".to_string(), // ID instead of name
-                chunks: false,
-            },
-        );
-        let result = execute(&cli);
-        assert!(result.is_ok());
+        ))
+        .expect("load");
+
+        let ndjson = |command: Commands| -> Vec<serde_json::Value> {
+            let mut cli = make_cli(db_path.clone(), command);
+            cli.format = "ndjson".to_string();
+            execute(&cli)
+                .expect("execute")
+                .lines()
+                .map(|line| serde_json::from_str(line).expect("one JSON object per line"))
+                .collect()
+        };
+
+        // Search: a header line, then one line per result
+        let lines = ndjson(Commands::Search {
+            query: "alpha".to_string(),
+            top_k: 10,
+            offset: 0,
+            threshold: 0.3,
+            mode: "bm25".to_string(),
+            raw_query: false,
+            rrf_k: 60,
+            semantic_weight: 1.0,
+            bm25_weight: 1.0,
+            buffer: None,
+            preview: false,
+            preview_len: 150,
+            highlight: false,
+            title_boost: 0.0,
+            buffer_type: None,
+            min_results: 0,
+            diversity: None,
+            tag: vec![],
+            buffer_meta: vec![],
+            auto_embed: false,
+            ids_only: false,
+            no_cache: false,
+            nprobe: None,
+            metric: "cosine".to_string(),
+            timeout_ms: None,
+        });
+        assert_eq!(lines[0]["query"], "alpha");
+        assert_eq!(lines[0]["mode"], "bm25");
+        assert!(lines[0].get("results").is_none());
+        assert!(lines.len() > 1);
+        assert_eq!(lines[0]["count"], lines.len() - 1);
+        assert!(lines[1..].iter().all(|r| r["chunk_id"].is_i64()));
+
+        let lines = ndjson(Commands::Chunk(ChunkCommands::List {
+            buffer: Some("streambuf".to_string()),
+            all: false,
+            limit: None,
+            offset: 0,
+            preview: false,
+            preview_len: 100,
+            verify_hash: false,
+            sample: None,
+            seed: None,
+        }));
+        assert_eq!(lines[0]["chunk_count"], lines.len() - 1);
+        assert_eq!(lines[1]["index"], 0);
+        assert!(lines[0].get("chunks").is_none());
+
+        // Buffers have no header
+        let lines = ndjson(Commands::ListBuffers {
+            tag: vec![],
+            sample: None,
+            seed: None,
+            fields: vec![],
+        });
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["name"], "streambuf");
     }
 
     #[test]
-    fn test_cmd_search_with_buffer_filter() {
+    fn test_cmd_search_min_results_relaxes() {
         let temp_dir = TempDir::new().expect("temp dir");
         let db_path = temp_dir.path().join("test.db");
         let file_path = temp_dir.path().join("content.txt");
         std::fs::write(&file_path, "Searchable content here").expect("write file");
 
-        let cli = make_cli(db_path.clone(), Commands::Init { force: false });
-        execute(&cli).expect("init");
-
-        let cli = make_cli(
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        execute(&make_cli(
             db_path.clone(),
             Commands::Load {
                 file: file_path,
-                name: Some("filterbuf".to_string()),
+                name: Some("relaxbuf".to_string()),
                 chunker: "fixed".to_string(),
                 chunk_size: 1000,
                 overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
-        );
-        execute(&cli).expect("load");
+        ))
+        .expect("load");
 
-        // Search with buffer filter
-        let cli = make_cli(
+        // An impossible threshold in semantic mode finds nothing until relaxed
+        let output = execute(&make_cli_json(
             db_path,
             Commands::Search {
                 query: "content".to_string(),
                 top_k: 5,
-                threshold: 0.3,
-                mode: "bm25".to_string(),
+                offset: 0,
+                threshold: 1.1,
+                mode: "semantic".to_string(),
+                raw_query: false,
                 rrf_k: 60,
-                buffer: Some("filterbuf".to_string()),
+                semantic_weight: 1.0,
+                bm25_weight: 1.0,
+                buffer: None,
                 preview: false,
                 preview_len: 150,
+                highlight: false,
+                title_boost: 0.0,
+                buffer_type: None,
+                min_results: 1,
+                diversity: None,
+                ids_only: false,
+                tag: vec![],
+                buffer_meta: vec![],
+                auto_embed: false,
+                no_cache: false,
+                nprobe: None,
+                metric: "cosine".to_string(),
+                timeout_ms: None,
             },
-        );
-        let result = execute(&cli);
-        assert!(result.is_ok());
+        ))
+        .expect("search");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["count"], 1);
+        assert!(json["relaxation"].is_string());
+    }
+
+    #[test]
+    fn test_cmd_search_diversity() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let file_path = temp_dir.path().join("content.txt");
+        std::fs::write(&file_path, "Searchable content here").expect("write file");
+
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+        execute(&make_cli(
+            db_path.clone(),
+            Commands::Load {
+                file: file_path,
+                name: Some("mmrbuf".to_string()),
+                chunker: "fixed".to_string(),
+                chunk_size: 1000,
+                overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
+            },
+        ))
+        .expect("load");
+
+        let search = |diversity| Commands::Search {
+            query: "content".to_string(),
+            top_k: 5,
+            offset: 0,
+            threshold: 0.0,
+            mode: "hybrid".to_string(),
+            raw_query: false,
+            rrf_k: 60,
+            semantic_weight: 1.0,
+            bm25_weight: 1.0,
+            buffer: None,
+            preview: false,
+            preview_len: 150,
+            highlight: false,
+            title_boost: 0.0,
+            buffer_type: None,
+            min_results: 0,
+            diversity,
+            ids_only: false,
+            tag: vec![],
+            buffer_meta: vec![],
+            auto_embed: false,
+            no_cache: false,
+            nprobe: None,
+            metric: "cosine".to_string(),
+            timeout_ms: None,
+        };
+
+        let output = execute(&make_cli_json(db_path.clone(), search(Some(0.5)))).expect("search");
+        let json: serde_json::Value = serde_json::from_str(&output).expect("json");
+        assert_eq!(json["count"], 1);
+
+        assert!(execute(&make_cli(db_path, search(Some(1.5)))).is_err());
     }
 
     #[test]
@@ -1859,6 +5956,9 @@ mod cli_tests {
                 chunker: "fixed".to_string(),
                 chunk_size: 1000,
                 overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
         );
         execute(&cli).expect("load");
@@ -1869,6 +5969,7 @@ mod cli_tests {
             Commands::Chunk(ChunkCommands::Embed {
                 buffer: "semanticbuf".to_string(),
                 force: false,
+                older_than: None,
             }),
         );
         execute(&cli).expect("embed");
@@ -1879,12 +5980,29 @@ mod cli_tests {
             Commands::Search {
                 query: "semantic".to_string(),
                 top_k: 5,
+                offset: 0,
                 threshold: 0.1, // Low threshold for test
                 mode: "semantic".to_string(),
+                raw_query: false,
                 rrf_k: 60,
+                semantic_weight: 1.0,
+                bm25_weight: 1.0,
                 buffer: None,
                 preview: false,
                 preview_len: 150,
+                highlight: false,
+                title_boost: 0.0,
+                buffer_type: None,
+                min_results: 0,
+                diversity: None,
+                ids_only: false,
+                tag: vec![],
+                buffer_meta: vec![],
+                auto_embed: false,
+                no_cache: false,
+                nprobe: None,
+                metric: "cosine".to_string(),
+                timeout_ms: None,
             },
         );
         let result = execute(&cli);
@@ -1896,12 +6014,29 @@ mod cli_tests {
             Commands::Search {
                 query: "content".to_string(),
                 top_k: 5,
+                offset: 0,
                 threshold: 0.1,
                 mode: "hybrid".to_string(),
+                raw_query: false,
                 rrf_k: 60,
+                semantic_weight: 1.0,
+                bm25_weight: 1.0,
                 buffer: None,
                 preview: false,
                 preview_len: 150,
+                highlight: false,
+                title_boost: 0.0,
+                buffer_type: None,
+                min_results: 0,
+                diversity: None,
+                ids_only: false,
+                tag: vec![],
+                buffer_meta: vec![],
+                auto_embed: false,
+                no_cache: false,
+                nprobe: None,
+                metric: "cosine".to_string(),
+                timeout_ms: None,
             },
         );
         let result = execute(&cli);
@@ -1926,6 +6061,9 @@ mod cli_tests {
                 chunker: "fixed".to_string(),
                 chunk_size: 1000,
                 overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
         );
         execute(&cli).expect("load");
@@ -1964,6 +6102,9 @@ mod cli_tests {
                 chunker: "fixed".to_string(),
                 chunk_size: 1000,
                 overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
         );
         execute(&cli).expect("load");
@@ -1998,6 +6139,9 @@ mod cli_tests {
                 chunker: "fixed".to_string(),
                 chunk_size: 1000,
                 overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
         );
         execute(&cli).expect("load");
@@ -2005,9 +6149,15 @@ mod cli_tests {
         let cli = make_cli_json(
             db_path,
             Commands::Chunk(ChunkCommands::List {
-                buffer: "chunklistjson".to_string(),
+                buffer: Some("chunklistjson".to_string()),
+                all: false,
+                limit: None,
+                offset: 0,
                 preview: true,
                 preview_len: 50,
+                verify_hash: false,
+                sample: None,
+                seed: None,
             }),
         );
         let result = execute(&cli);
@@ -2032,6 +6182,9 @@ mod cli_tests {
                 chunker: "fixed".to_string(),
                 chunk_size: 1000,
                 overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
         );
         execute(&cli).expect("load");
@@ -2041,6 +6194,13 @@ mod cli_tests {
             Commands::Chunk(ChunkCommands::Get {
                 id: 1,
                 metadata: true,
+                next: false,
+                prev: false,
+                context: None,
+                resolve_refs: None,
+                stream: false,
+                verify_hash: false,
+                wrap: "none".to_string(),
             }),
         );
         let result = execute(&cli);
@@ -2067,6 +6227,9 @@ mod cli_tests {
                 chunker: "fixed".to_string(),
                 chunk_size: 1000,
                 overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
         );
         execute(&cli).expect("load");
@@ -2102,6 +6265,9 @@ mod cli_tests {
                 chunker: "fixed".to_string(),
                 chunk_size: 1000,
                 overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
         );
         execute(&cli).expect("load");
@@ -2138,6 +6304,9 @@ mod cli_tests {
                 chunker: "fixed".to_string(),
                 chunk_size: 1000,
                 overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
         );
         execute(&cli).expect("load");
@@ -2147,6 +6316,7 @@ mod cli_tests {
             Commands::DeleteBuffer {
                 buffer: "deletejson".to_string(),
                 yes: true,
+                soft: false,
             },
         );
         let result = execute(&cli);
@@ -2171,6 +6341,9 @@ mod cli_tests {
                 chunker: "fixed".to_string(),
                 chunk_size: 1000,
                 overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
         );
         execute(&cli).expect("load");
@@ -2188,6 +6361,7 @@ mod cli_tests {
             Commands::Chunk(ChunkCommands::Embed {
                 buffer: "statusbuf".to_string(),
                 force: false,
+                older_than: None,
             }),
         );
         execute(&cli).expect("embed");
@@ -2220,6 +6394,9 @@ mod cli_tests {
                 chunker: "fixed".to_string(),
                 chunk_size: 1000,
                 overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
         );
         execute(&cli).expect("load");
@@ -2253,12 +6430,23 @@ mod cli_tests {
                 chunker: "fixed".to_string(),
                 chunk_size: 1000,
                 overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
         );
         execute(&cli).expect("load");
 
         // List buffers should truncate the long name
-        let cli = make_cli(db_path.clone(), Commands::ListBuffers);
+        let cli = make_cli(
+            db_path.clone(),
+            Commands::ListBuffers {
+                tag: vec![],
+                sample: None,
+                seed: None,
+                fields: vec![],
+            },
+        );
         let result = execute(&cli);
         assert!(result.is_ok());
         let output = result.expect("list output");
@@ -2311,6 +6499,9 @@ mod cli_tests {
                 chunker: "fixed".to_string(),
                 chunk_size: 1000,
                 overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
         );
         execute(&cli).expect("load");
@@ -2321,6 +6512,7 @@ mod cli_tests {
             Commands::Chunk(ChunkCommands::Embed {
                 buffer: "alreadyembedded".to_string(),
                 force: false,
+                older_than: None,
             }),
         );
         execute(&cli).expect("first embed");
@@ -2331,6 +6523,7 @@ mod cli_tests {
             Commands::Chunk(ChunkCommands::Embed {
                 buffer: "alreadyembedded".to_string(),
                 force: false,
+                older_than: None,
             }),
         );
         let result = execute(&cli);
@@ -2357,6 +6550,9 @@ mod cli_tests {
                 chunker: "fixed".to_string(),
                 chunk_size: 1000,
                 overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
         );
         execute(&cli).expect("load");
@@ -2367,6 +6563,7 @@ mod cli_tests {
             Commands::Chunk(ChunkCommands::Embed {
                 buffer: "embedjson".to_string(),
                 force: false,
+                older_than: None,
             }),
         );
         let result = execute(&cli);
@@ -2389,6 +6586,7 @@ mod cli_tests {
             Commands::Variable {
                 name: "jsonvar".to_string(),
                 value: Some("jsonvalue".to_string()),
+                value_type: "string".to_string(),
                 delete: false,
             },
         );
@@ -2401,6 +6599,7 @@ mod cli_tests {
             Commands::Variable {
                 name: "jsonvar".to_string(),
                 value: None,
+                value_type: "string".to_string(),
                 delete: false,
             },
         );
@@ -2408,6 +6607,85 @@ mod cli_tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_cmd_variable_typed() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        execute(&make_cli(db_path.clone(), Commands::Init { force: false })).expect("init");
+
+        let set = |name: &str, value: &str, value_type: &str| {
+            execute(&make_cli(
+                db_path.clone(),
+                Commands::Variable {
+                    name: name.to_string(),
+                    value: Some(value.to_string()),
+                    value_type: value_type.to_string(),
+                    delete: false,
+                },
+            ))
+        };
+        let get = |name: &str| {
+            execute(&make_cli_json(
+                db_path.clone(),
+                Commands::Global {
+                    name: name.to_string(),
+                    value: None,
+                    value_type: "string".to_string(),
+                    delete: false,
+                },
+            ))
+        };
+
+        let output = set("count", " 7 ", "int").expect("integer");
+        assert!(output.contains("count = 7 (integer)"));
+        set("ratio", "0.25", "number").expect("float");
+        set("done", "yes", "bool").expect("boolean");
+        set("seen", r#"[1, "two", {"three": 3}]"#, "list").expect("list");
+
+        let get_var = |name: &str| {
+            execute(&make_cli(
+                db_path.clone(),
+                Commands::Variable {
+                    name: name.to_string(),
+                    value: None,
+                    value_type: "string".to_string(),
+                    delete: false,
+                },
+            ))
+            .expect("get")
+        };
+        assert_eq!(get_var("count"), "count (integer) = 7\n");
+        assert_eq!(get_var("ratio"), "ratio (float) = 0.25\n");
+        assert_eq!(get_var("done"), "done (boolean) = true\n");
+        let seen = get_var("seen");
+        assert!(seen.starts_with("seen (list) = [\n"));
+        assert!(seen.contains("\"three\": 3"));
+
+        // Stored tagged, as before
+        execute(&make_cli(
+            db_path.clone(),
+            Commands::Global {
+                name: "limits".to_string(),
+                value: Some(r#"{"max": 10}"#.to_string()),
+                value_type: "map".to_string(),
+                delete: false,
+            },
+        ))
+        .expect("map");
+        let json: serde_json::Value =
+            serde_json::from_str(&get("limits").expect("get map")).expect("json");
+        assert_eq!(json["type"], "Map");
+        assert_eq!(json["value"]["max"]["type"], "Integer");
+        assert_eq!(json["value"]["max"]["value"], 10);
+
+        let err = set("count", "seven", "integer").expect_err("not an integer");
+        assert!(err.to_string().contains("not a valid integer"));
+        let err = set("seen", "{}", "list").expect_err("not a list");
+        assert!(err.to_string().contains("JSON array"));
+        let err = set("x", "1", "decimal").expect_err("unknown type");
+        assert!(err.to_string().contains("unknown value type"));
+    }
+
     #[test]
     fn test_cmd_global_json() {
         let temp_dir = TempDir::new().expect("temp dir");
@@ -2422,6 +6700,7 @@ mod cli_tests {
             Commands::Global {
                 name: "jsonglobal".to_string(),
                 value: Some("jsonglobalvalue".to_string()),
+                value_type: "string".to_string(),
                 delete: false,
             },
         );
@@ -2434,6 +6713,7 @@ mod cli_tests {
             Commands::Global {
                 name: "jsonglobal".to_string(),
                 value: None,
+                value_type: "string".to_string(),
                 delete: false,
             },
         );
@@ -2466,6 +6746,9 @@ mod cli_tests {
                     chunker: "fixed".to_string(),
                     chunk_size: 1000,
                     overlap: 0,
+                    chunk_unit: "chars".to_string(),
+                    encoding: None,
+                    normalize_whitespace: false,
                 },
             );
             execute(&cli).expect("load");
@@ -2477,6 +6760,7 @@ mod cli_tests {
             Commands::Chunk(ChunkCommands::Embed {
                 buffer: "buf1".to_string(),
                 force: false,
+                older_than: None,
             }),
         );
         execute(&cli).expect("embed buf1");
@@ -2513,6 +6797,9 @@ mod cli_tests {
                 chunker: "fixed".to_string(),
                 chunk_size: 1000,
                 overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
         );
         execute(&cli).expect("load");
@@ -2549,12 +6836,23 @@ mod cli_tests {
                 chunker: "fixed".to_string(),
                 chunk_size: 1000,
                 overlap: 0,
+                chunk_unit: "chars".to_string(),
+                encoding: None,
+                normalize_whitespace: false,
             },
         );
         execute(&cli).expect("load");
 
         // List buffers with JSON output
-        let cli = make_cli_json(db_path, Commands::ListBuffers);
+        let cli = make_cli_json(
+            db_path,
+            Commands::ListBuffers {
+                tag: vec![],
+                sample: None,
+                seed: None,
+                fields: vec![],
+            },
+        );
         let result = execute(&cli);
         assert!(result.is_ok());
         let output = result.expect("json output");